@@ -184,6 +184,15 @@ impl NativeAgent {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            seed: None,
+            logprobs: None,
+            top_logprobs: None,
+            logit_bias: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            user: None,
+            stop: None,
+            parallel_tool_calls: None,
         };
 
         // 对于自定义 Provider，使用 provider 特定路由
@@ -384,6 +384,15 @@ impl Protocol for OpenAIProtocol {
                 None
             },
             reasoning_effort: None,
+            seed: None,
+            logprobs: None,
+            top_logprobs: None,
+            logit_bias: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            user: None,
+            stop: None,
+            parallel_tool_calls: None,
         };
 
         let url = format!("{}{}", base_url, self.endpoint());
@@ -460,6 +469,15 @@ impl Protocol for OpenAIProtocol {
                 None
             },
             reasoning_effort: None,
+            seed: None,
+            logprobs: None,
+            top_logprobs: None,
+            logit_bias: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            user: None,
+            stop: None,
+            parallel_tool_calls: None,
         };
 
         let url = format!("{}{}", base_url, self.endpoint());
@@ -161,7 +161,9 @@ pub struct AppStates {
 /// 初始化所有应用状态
 pub fn init_states(config: &Config) -> Result<AppStates, String> {
     // 核心状态
-    let state: AppState = Arc::new(RwLock::new(server::ServerState::new(config.clone())));
+    let mut server_state = server::ServerState::new(config.clone());
+    let injector = server_state.injector.clone();
+    let injection_enabled = server_state.injection_enabled.clone();
     let logs: LogState = Arc::new(RwLock::new(logger::LogStore::with_config(&config.logging)));
 
     // 数据库
@@ -173,6 +175,14 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     let skill_service_state = SkillServiceState(Arc::new(skill_service));
 
     let provider_pool_service = ProviderPoolService::new();
+    provider_pool_service.set_tier_rules(config.routing.tier_rules.clone());
+    provider_pool_service.set_health_scoring(config.health_scoring.clone());
+    provider_pool_service.set_selection_strategy(
+        config.routing.selection_strategy,
+        config.routing.latency_exploration_rate,
+    );
+    provider_pool_service.set_credential_fallback(&config.routing.credential_fallback);
+    let switch_log = provider_pool_service.switch_log();
     let provider_pool_service_state = ProviderPoolServiceState(Arc::new(provider_pool_service));
 
     let api_key_provider_service = ApiKeyProviderService::new();
@@ -188,7 +198,11 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
         .map_err(|e| format!("MachineIdService 初始化失败: {}", e))?;
     let machine_id_service_state: MachineIdState = Arc::new(RwLock::new(machine_id_service));
 
-    let resilience_config_state = ResilienceConfigState::default();
+    // 切换日志与凭证池共享，容错设置页可以看到凭证降级记录
+    let resilience_config_state = ResilienceConfigState {
+        switch_log,
+        ..Default::default()
+    };
 
     // 插件管理器
     let plugin_manager = plugin::PluginManager::with_defaults();
@@ -248,7 +262,12 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     // 初始化全局配置管理器
     let config_path = ConfigManager::default_config_path();
     let global_config_manager = GlobalConfigManager::new(config.clone(), config_path);
+    global_config_manager.register_injector_observer(injector, injection_enabled);
+    global_config_manager.register_feature_settings_observers();
+    global_config_manager.register_provider_pool_observer(provider_pool_service_state.0.clone());
     let global_config_manager_state = GlobalConfigManagerState::new(global_config_manager);
+    server_state.config_manager = Some(global_config_manager_state.0.clone());
+    let state: AppState = Arc::new(RwLock::new(server_state));
 
     // 初始化默认技能仓库
     {
@@ -456,6 +475,7 @@ fn init_flow_monitor(
             .map_err(|e| format!("SessionManager 初始化失败: {}", e))?,
     );
     let session_manager_state = SessionManagerState(session_manager.clone());
+    flow_monitor.set_session_manager(session_manager.clone());
 
     let quick_filter_manager = Arc::new(
         QuickFilterManager::new(db_path.clone())
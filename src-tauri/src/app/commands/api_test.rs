@@ -4,6 +4,7 @@
 
 use crate::app::types::{AppState, LogState, ProviderType};
 use crate::commands::model_registry_cmd::ModelRegistryState;
+use crate::router::{find_preset, recommended_presets, CanaryTarget, RoutingPreset};
 
 /// 测试结果
 #[derive(serde::Serialize)]
@@ -107,6 +108,8 @@ pub async fn check_api_compatibility(
         let test_name = format!("{model} ({test_type})");
 
         // 根据测试类型构建不同的请求
+        // 测试请求直接调用 Provider，不经过服务器管道，这里较小的 max_tokens
+        // 只用于缩短兼容性检查，不影响实际请求（实际请求按模型配置调整 max_tokens）
         let test_request = match test_type {
             "tool_call" => {
                 // 测试 Tool Calls - Claude Code 核心功能
@@ -142,6 +145,15 @@ pub async fn check_api_compatibility(
                     }]),
                     tool_choice: None,
                     reasoning_effort: None,
+                    seed: None,
+                    logprobs: None,
+                    top_logprobs: None,
+                    logit_bias: None,
+                    frequency_penalty: None,
+                    presence_penalty: None,
+                    user: None,
+                    stop: None,
+                    parallel_tool_calls: None,
                 }
             }
             _ => {
@@ -163,6 +175,15 @@ pub async fn check_api_compatibility(
                     tools: None,
                     tool_choice: None,
                     reasoning_effort: None,
+                    seed: None,
+                    logprobs: None,
+                    top_logprobs: None,
+                    logit_bias: None,
+                    frequency_penalty: None,
+                    presence_penalty: None,
+                    user: None,
+                    stop: None,
+                    parallel_tool_calls: None,
                 }
             }
         };
@@ -309,17 +330,33 @@ pub async fn test_api(
     body: Option<String>,
     auth: bool,
 ) -> Result<TestResult, String> {
+    let (base_url, api_key) = local_endpoint(&state).await;
+    let api_key = auth.then_some(api_key.as_str());
+    send_local_request(&base_url, api_key, &method, &path, body, &[]).await
+}
+
+/// 获取本地服务地址和当前生效的 API Key
+async fn local_endpoint(state: &AppState) -> (String, String) {
     let s = state.read().await;
     let base_url = format!("http://{}:{}", s.config.server.host, s.config.server.port);
     let api_key = s
         .running_api_key
-        .as_ref()
-        .unwrap_or(&s.config.server.api_key);
+        .clone()
+        .unwrap_or_else(|| s.config.server.api_key.clone());
+    (base_url, api_key)
+}
 
-    let client = reqwest::Client::builder()
-        .no_proxy()
-        .build()
-        .map_err(|e| e.to_string())?;
+/// 向本地服务发送请求
+async fn send_local_request(
+    base_url: &str,
+    api_key: Option<&str>,
+    method: &str,
+    path: &str,
+    body: Option<String>,
+    extra_headers: &[(&str, &str)],
+) -> Result<TestResult, String> {
+    // 测试本地服务，显式绕过出站代理
+    let client = crate::http_client::direct_client();
 
     let url = format!("{base_url}{path}");
 
@@ -327,7 +364,7 @@ pub async fn test_api(
 
     let start = std::time::Instant::now();
 
-    let mut req = match method.as_str() {
+    let mut req = match method {
         "GET" => client.get(&url),
         "POST" => client.post(&url),
         _ => return Err("Unsupported method".to_string()),
@@ -335,8 +372,12 @@ pub async fn test_api(
 
     req = req.header("Content-Type", "application/json");
 
-    if auth {
-        req = req.header("Authorization", format!("Bearer {api_key}"));
+    if let Some(key) = api_key {
+        req = req.header("Authorization", format!("Bearer {key}"));
+    }
+
+    for (name, value) in extra_headers {
+        req = req.header(*name, *value);
     }
 
     if let Some(b) = body {
@@ -368,3 +409,745 @@ pub async fn test_api(
         }
     }
 }
+
+/// 自检项状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelfTestStatus {
+    Pass,
+    Fail,
+    /// 前置检查失败，未执行
+    Skip,
+}
+
+/// 单个自检项结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SelfTestCheck {
+    /// 检查项标识
+    pub id: String,
+    /// 检查项名称
+    pub name: String,
+    pub status: SelfTestStatus,
+    /// 检查详情
+    pub detail: String,
+    /// 失败时的处理建议
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    pub time_ms: u64,
+}
+
+impl SelfTestCheck {
+    fn pass(id: &str, name: &str, detail: String, time_ms: u64) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            status: SelfTestStatus::Pass,
+            detail,
+            hint: None,
+            time_ms,
+        }
+    }
+
+    fn fail(id: &str, name: &str, detail: String, hint: &str, time_ms: u64) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            status: SelfTestStatus::Fail,
+            detail,
+            hint: Some(hint.to_string()),
+            time_ms,
+        }
+    }
+
+    fn skip(id: &str, name: &str, reason: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            status: SelfTestStatus::Skip,
+            detail: reason.to_string(),
+            hint: None,
+            time_ms: 0,
+        }
+    }
+}
+
+/// 自检报告
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SelfTestReport {
+    /// 所有检查项是否通过
+    pub passed: bool,
+    /// 用于对话测试的模型
+    pub model: String,
+    pub checked_at: String,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    fn new(model: String, checks: Vec<SelfTestCheck>) -> Self {
+        Self {
+            passed: checks.iter().all(|c| c.status == SelfTestStatus::Pass),
+            model,
+            checked_at: chrono::Utc::now().to_rfc3339(),
+            checks,
+        }
+    }
+}
+
+/// 截断响应体用于展示
+fn truncate_body(body: &str) -> String {
+    body.chars().take(200).collect()
+}
+
+/// 根据对话请求的响应状态码给出处理建议
+fn completion_hint(status: u16) -> &'static str {
+    match status {
+        401 | 403 => "上游凭证认证失败，请在凭证池中刷新 Token 或重新授权",
+        404 => "模型不可用，请检查模型名称或在路由设置中配置模型别名",
+        429 => "上游请求过于频繁或额度已用尽，请稍后重试或添加更多凭证",
+        503 => "没有可用凭证，请检查默认 Provider 的凭证是否健康",
+        500..=599 => "上游服务错误，请查看请求日志获取详细信息",
+        _ => "请查看请求日志获取详细信息",
+    }
+}
+
+/// 运行本地代理自检
+///
+/// 依次检查服务监听、API Key、健康凭证以及 `/v1/chat/completions` 和
+/// `/v1/messages` 的完整往返，前置检查失败时跳过后续依赖它的检查。
+#[tauri::command]
+pub async fn run_self_test(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    db: tauri::State<'_, crate::database::DbConnection>,
+    pool_service: tauri::State<'_, crate::commands::provider_pool_cmd::ProviderPoolServiceState>,
+    model: Option<String>,
+) -> Result<SelfTestReport, String> {
+    let (base_url, api_key) = local_endpoint(&state).await;
+    let default_provider = state.read().await.config.routing.default_provider.clone();
+    let model = model.filter(|m| !m.trim().is_empty()).unwrap_or_else(|| {
+        default_provider
+            .parse::<ProviderType>()
+            .map(|pt| crate::models::provider_pool_model::get_default_check_model(pt).to_string())
+            .unwrap_or_else(|_| "claude-sonnet-4-5".to_string())
+    });
+
+    logs.write().await.add(
+        "info",
+        &format!("[自检] 开始自检: {base_url}, model={model}"),
+    );
+
+    let mut checks = Vec::new();
+
+    // 1. 服务监听
+    let server_ok = match send_local_request(&base_url, None, "GET", "/health", None, &[]).await {
+        Ok(r) if r.success => {
+            checks.push(SelfTestCheck::pass(
+                "server",
+                "服务监听",
+                format!("{base_url} 正在监听"),
+                r.time_ms,
+            ));
+            true
+        }
+        Ok(r) => {
+            checks.push(SelfTestCheck::fail(
+                "server",
+                "服务监听",
+                format!("/health 返回 {}", r.status),
+                "端口可能被其他程序占用，请修改服务端口后重启服务",
+                r.time_ms,
+            ));
+            false
+        }
+        Err(e) => {
+            checks.push(SelfTestCheck::fail(
+                "server",
+                "服务监听",
+                format!("无法连接 {base_url}: {e}"),
+                "请先启动服务，并确认监听地址和端口配置正确",
+                0,
+            ));
+            false
+        }
+    };
+
+    // 2. API Key
+    let api_key_ok = if !server_ok {
+        checks.push(SelfTestCheck::skip("api_key", "API Key", "服务未运行"));
+        false
+    } else {
+        match send_local_request(&base_url, Some(&api_key), "GET", "/v1/models", None, &[]).await {
+            Ok(r) if r.success => {
+                checks.push(SelfTestCheck::pass(
+                    "api_key",
+                    "API Key",
+                    "API Key 验证通过".to_string(),
+                    r.time_ms,
+                ));
+                true
+            }
+            Ok(r) => {
+                checks.push(SelfTestCheck::fail(
+                    "api_key",
+                    "API Key",
+                    format!("/v1/models 返回 {}", r.status),
+                    "API Key 与运行中的服务不一致，请重启服务使配置生效",
+                    r.time_ms,
+                ));
+                false
+            }
+            Err(e) => {
+                checks.push(SelfTestCheck::fail(
+                    "api_key",
+                    "API Key",
+                    e,
+                    "请确认服务运行正常后重试",
+                    0,
+                ));
+                false
+            }
+        }
+    };
+
+    // 3. 健康凭证
+    let start = std::time::Instant::now();
+    match pool_service.0.get_overview(&db) {
+        Ok(overview) => {
+            let healthy: usize = overview.iter().map(|o| o.stats.healthy_count).sum();
+            let time_ms = start.elapsed().as_millis() as u64;
+            if healthy > 0 {
+                checks.push(SelfTestCheck::pass(
+                    "credentials",
+                    "健康凭证",
+                    format!("{healthy} 个健康凭证"),
+                    time_ms,
+                ));
+            } else {
+                checks.push(SelfTestCheck::fail(
+                    "credentials",
+                    "健康凭证",
+                    "凭证池中没有健康凭证".to_string(),
+                    "请在凭证池中添加凭证，或对现有凭证执行健康检查并重置状态",
+                    time_ms,
+                ));
+            }
+        }
+        Err(e) => checks.push(SelfTestCheck::fail(
+            "credentials",
+            "健康凭证",
+            format!("读取凭证池失败: {e}"),
+            "数据库可能损坏，请查看应用日志",
+            start.elapsed().as_millis() as u64,
+        )),
+    }
+
+    // 4. 对话往返
+    let round_trips = [
+        (
+            "chat_completions",
+            "/v1/chat/completions",
+            serde_json::json!({
+                "model": model,
+                "messages": [{"role": "user", "content": "Say 'OK' only."}],
+                "max_tokens": 10,
+                "stream": false
+            }),
+        ),
+        (
+            "messages",
+            "/v1/messages",
+            serde_json::json!({
+                "model": model,
+                "messages": [{"role": "user", "content": "Say 'OK' only."}],
+                "max_tokens": 10,
+                "stream": false
+            }),
+        ),
+    ];
+    for (id, path, body) in round_trips {
+        if !api_key_ok {
+            checks.push(SelfTestCheck::skip(id, path, "API Key 检查未通过"));
+            continue;
+        }
+        let check = match send_local_request(
+            &base_url,
+            Some(&api_key),
+            "POST",
+            path,
+            Some(body.to_string()),
+            &[],
+        )
+        .await
+        {
+            Ok(r) if r.success => {
+                SelfTestCheck::pass(id, path, "请求往返成功".to_string(), r.time_ms)
+            }
+            Ok(r) => SelfTestCheck::fail(
+                id,
+                path,
+                format!("返回 {}: {}", r.status, truncate_body(&r.body)),
+                completion_hint(r.status),
+                r.time_ms,
+            ),
+            Err(e) => SelfTestCheck::fail(id, path, e, "请求超时或连接中断，请查看请求日志", 0),
+        };
+        checks.push(check);
+    }
+
+    let report = SelfTestReport::new(model, checks);
+    logs.write().await.add(
+        "info",
+        &format!(
+            "[自检] 完成: {}",
+            if report.passed {
+                "全部通过"
+            } else {
+                "存在失败项"
+            }
+        ),
+    );
+    Ok(report)
+}
+
+/// 单次基准测试最多迭代次数
+const MAX_BENCHMARK_ITERATIONS: u32 = 50;
+
+/// 错误信息最多保留条数
+const MAX_BENCHMARK_ERRORS: usize = 5;
+
+/// 基准测试目标
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct BenchmarkTarget {
+    /// Provider ID（通过 X-Provider-Id 精确路由，不设置则按默认路由）
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// 模型名称
+    pub model: String,
+}
+
+/// 单个目标的基准测试结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BenchmarkResult {
+    pub provider: Option<String>,
+    pub model: String,
+    /// 实际执行的请求数（遇到限流或配额耗尽时提前停止）
+    pub requests: u32,
+    pub success_count: u32,
+    pub success_rate: f64,
+    pub p50_latency_ms: Option<u64>,
+    pub p90_latency_ms: Option<u64>,
+    pub p99_latency_ms: Option<u64>,
+    pub avg_latency_ms: Option<u64>,
+    /// 平均输出速度（completion tokens / 秒，响应未返回 usage 时为空）
+    pub avg_tokens_per_sec: Option<f64>,
+    /// 失败请求的错误信息（最多保留前几条）
+    pub errors: Vec<String>,
+}
+
+/// 基准测试报告
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BenchmarkReport {
+    pub prompt: String,
+    pub iterations: u32,
+    pub checked_at: String,
+    pub results: Vec<BenchmarkResult>,
+}
+
+/// 单次请求的测量结果
+struct BenchmarkSample {
+    success: bool,
+    latency_ms: u64,
+    completion_tokens: Option<u64>,
+    error: Option<String>,
+}
+
+impl BenchmarkSample {
+    fn from_response(r: TestResult) -> Self {
+        if !r.success {
+            return Self {
+                success: false,
+                latency_ms: r.time_ms,
+                completion_tokens: None,
+                error: Some(format!("{}: {}", r.status, truncate_body(&r.body))),
+            };
+        }
+        let completion_tokens = serde_json::from_str::<serde_json::Value>(&r.body)
+            .ok()
+            .and_then(|v| v.get("usage")?.get("completion_tokens")?.as_u64());
+        Self {
+            success: true,
+            latency_ms: r.time_ms,
+            completion_tokens,
+            error: None,
+        }
+    }
+}
+
+/// 汇总单个目标的测量结果
+fn summarize_benchmark(target: &BenchmarkTarget, samples: &[BenchmarkSample]) -> BenchmarkResult {
+    use crate::flow_monitor::enhanced_stats::percentile;
+
+    let successes: Vec<&BenchmarkSample> = samples.iter().filter(|s| s.success).collect();
+    let mut latencies: Vec<u64> = successes.iter().map(|s| s.latency_ms).collect();
+    latencies.sort_unstable();
+
+    let rates: Vec<f64> = successes
+        .iter()
+        .filter(|s| s.latency_ms > 0)
+        .filter_map(|s| Some(s.completion_tokens? as f64 * 1000.0 / s.latency_ms as f64))
+        .collect();
+
+    BenchmarkResult {
+        provider: target.provider.clone(),
+        model: target.model.clone(),
+        requests: samples.len() as u32,
+        success_count: successes.len() as u32,
+        success_rate: if samples.is_empty() {
+            0.0
+        } else {
+            successes.len() as f64 / samples.len() as f64
+        },
+        p50_latency_ms: percentile(&latencies, 50.0),
+        p90_latency_ms: percentile(&latencies, 90.0),
+        p99_latency_ms: percentile(&latencies, 99.0),
+        avg_latency_ms: (!latencies.is_empty())
+            .then(|| latencies.iter().sum::<u64>() / latencies.len() as u64),
+        avg_tokens_per_sec: (!rates.is_empty())
+            .then(|| rates.iter().sum::<f64>() / rates.len() as f64),
+        errors: samples
+            .iter()
+            .filter_map(|s| s.error.clone())
+            .take(MAX_BENCHMARK_ERRORS)
+            .collect(),
+    }
+}
+
+/// 对比多个 Provider / 模型的性能
+///
+/// 通过本地服务对每个目标顺序发送 `iterations` 次相同的非流式请求，统计延迟分位数、
+/// 输出速度和成功率。请求携带基准测试标记头，不计入请求统计和 Token 统计，也不使用
+/// 响应缓存；凭证选择和配额限制与普通请求相同，收到 429 时停止该目标的剩余迭代。
+#[tauri::command]
+pub async fn benchmark_providers(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    prompt: String,
+    models: Vec<BenchmarkTarget>,
+    iterations: u32,
+) -> Result<BenchmarkReport, String> {
+    if prompt.trim().is_empty() {
+        return Err("提示词不能为空".to_string());
+    }
+    if models.is_empty() {
+        return Err("至少需要选择一个模型".to_string());
+    }
+    let iterations = iterations.clamp(1, MAX_BENCHMARK_ITERATIONS);
+    let (base_url, api_key) = local_endpoint(&state).await;
+
+    logs.write().await.add(
+        "info",
+        &format!(
+            "[基准测试] 开始: {} 个目标, 每个 {} 次",
+            models.len(),
+            iterations
+        ),
+    );
+
+    let mut results = Vec::with_capacity(models.len());
+    for target in &models {
+        let body = serde_json::json!({
+            "model": target.model,
+            "messages": [{"role": "user", "content": prompt}],
+            "stream": false
+        })
+        .to_string();
+        let mut headers = vec![(
+            crate::server::BENCHMARK_HEADER,
+            crate::server::benchmark_token(),
+        )];
+        if let Some(provider) = target.provider.as_deref() {
+            headers.push(("X-Provider-Id", provider));
+        }
+
+        let mut samples = Vec::with_capacity(iterations as usize);
+        for _ in 0..iterations {
+            let (rate_limited, sample) = match send_local_request(
+                &base_url,
+                Some(&api_key),
+                "POST",
+                "/v1/chat/completions",
+                Some(body.clone()),
+                &headers,
+            )
+            .await
+            {
+                Ok(r) => (r.status == 429, BenchmarkSample::from_response(r)),
+                Err(e) => (
+                    false,
+                    BenchmarkSample {
+                        success: false,
+                        latency_ms: 0,
+                        completion_tokens: None,
+                        error: Some(e),
+                    },
+                ),
+            };
+            samples.push(sample);
+            // 限流或配额耗尽，继续请求只会放大影响
+            if rate_limited {
+                break;
+            }
+        }
+
+        let result = summarize_benchmark(target, &samples);
+        logs.write().await.add(
+            "info",
+            &format!(
+                "[基准测试] provider={} model={} 成功 {}/{} p50={:?}ms",
+                result.provider.as_deref().unwrap_or("default"),
+                result.model,
+                result.success_count,
+                result.requests,
+                result.p50_latency_ms
+            ),
+        );
+        results.push(result);
+    }
+
+    Ok(BenchmarkReport {
+        prompt,
+        iterations,
+        checked_at: chrono::Utc::now().to_rfc3339(),
+        results,
+    })
+}
+
+/// 金丝雀请求结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PresetCanaryResult {
+    /// 预设涉及的模型（别名）
+    pub models: Vec<String>,
+    /// 解析后的模型
+    pub resolved_model: String,
+    /// 请求经过的 Provider
+    pub provider: String,
+    pub passed: bool,
+    /// 本地服务返回的状态码（请求未完成时为空）
+    pub status: Option<u16>,
+    pub detail: String,
+    /// 失败时的处理建议
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    pub time_ms: u64,
+}
+
+impl PresetCanaryResult {
+    fn from_response(target: CanaryTarget, response: Result<TestResult, String>) -> Self {
+        let (passed, status, detail, hint, time_ms) = match response {
+            Ok(r) if r.success => (
+                true,
+                Some(r.status),
+                "请求往返成功".to_string(),
+                None,
+                r.time_ms,
+            ),
+            Ok(r) => (
+                false,
+                Some(r.status),
+                format!("返回 {}: {}", r.status, truncate_body(&r.body)),
+                Some(completion_hint(r.status).to_string()),
+                r.time_ms,
+            ),
+            Err(e) => (
+                false,
+                None,
+                e,
+                Some("请先启动服务，并确认监听地址和端口配置正确".to_string()),
+                0,
+            ),
+        };
+        Self {
+            models: target.models,
+            resolved_model: target.resolved_model,
+            provider: target.provider,
+            passed,
+            status,
+            detail,
+            hint,
+            time_ms,
+        }
+    }
+}
+
+/// 应用预设的结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PresetApplyReport {
+    pub preset_id: String,
+    /// 是否已写入配置
+    pub applied: bool,
+    /// 是否在金丝雀失败时强制应用
+    pub forced: bool,
+    pub checked_at: String,
+    pub canaries: Vec<PresetCanaryResult>,
+}
+
+/// 获取推荐路由预设
+#[tauri::command]
+pub async fn get_recommended_presets() -> Result<Vec<RoutingPreset>, String> {
+    Ok(recommended_presets())
+}
+
+/// 应用推荐路由预设
+///
+/// 先对预设涉及的每个模型通过 `/{provider}/v1/chat/completions` 发送一次极小的非流式请求
+/// （按预设的目标 Provider 和解析后的模型，不受当前别名影响）。全部通过时才替换默认 Provider
+/// 和模型别名；`force` 为 true 时即使有失败也应用。始终返回金丝雀结果。
+#[tauri::command]
+pub async fn apply_recommended_preset(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    config_manager: tauri::State<'_, crate::config::GlobalConfigManagerState>,
+    preset_id: String,
+    force: Option<bool>,
+) -> Result<PresetApplyReport, String> {
+    let preset = find_preset(&preset_id).ok_or_else(|| format!("未知的预设: {preset_id}"))?;
+    let force = force.unwrap_or(false);
+    let (base_url, api_key) = local_endpoint(&state).await;
+    let normalize = state.read().await.config.routing.normalize_model_names;
+
+    logs.write()
+        .await
+        .add("info", &format!("[预设] 开始金丝雀检查: {}", preset.id));
+
+    let mut canaries = Vec::new();
+    for target in preset.canary_targets(normalize) {
+        let body = serde_json::json!({
+            "model": target.resolved_model,
+            "messages": [{"role": "user", "content": "Say 'OK' only."}],
+            "max_tokens": 5,
+            "stream": false
+        })
+        .to_string();
+        let path = format!("/{}/v1/chat/completions", target.provider);
+        let response =
+            send_local_request(&base_url, Some(&api_key), "POST", &path, Some(body), &[]).await;
+        canaries.push(PresetCanaryResult::from_response(target, response));
+    }
+
+    let all_passed = canaries.iter().all(|c| c.passed);
+    let applied = all_passed || force;
+    if applied {
+        let routing = {
+            let mut s = state.write().await;
+            preset.apply_to(&mut s.config.routing);
+            s.config.default_provider = preset.default_provider.clone();
+            crate::config::save_config(&s.config).map_err(|e| e.to_string())?;
+            s.config.routing.clone()
+        };
+        // 路由器、模型别名和 default_provider_ref 由观察者同步
+        config_manager
+            .0
+            .update_routing(routing, crate::config::ConfigChangeSource::FrontendUI)
+            .await;
+    }
+
+    let failed = canaries.iter().filter(|c| !c.passed).count();
+    logs.write().await.add(
+        if all_passed { "info" } else { "warn" },
+        &format!(
+            "[预设] {}: 金丝雀 {}/{} 通过，{}",
+            preset.id,
+            canaries.len() - failed,
+            canaries.len(),
+            match (applied, all_passed) {
+                (true, true) => "已应用",
+                (true, false) => "已强制应用",
+                _ => "未应用",
+            }
+        ),
+    );
+
+    Ok(PresetApplyReport {
+        preset_id: preset.id,
+        applied,
+        forced: applied && !all_passed,
+        checked_at: chrono::Utc::now().to_rfc3339(),
+        canaries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(success: bool, latency_ms: u64, completion_tokens: Option<u64>) -> BenchmarkSample {
+        BenchmarkSample {
+            success,
+            latency_ms,
+            completion_tokens,
+            error: (!success).then(|| "500: error".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_preset_canary_result_from_response() {
+        let target = || CanaryTarget {
+            provider: "kiro".to_string(),
+            resolved_model: "claude-sonnet-4-5".to_string(),
+            models: vec!["gpt-4o".to_string()],
+        };
+        let ok = PresetCanaryResult::from_response(
+            target(),
+            Ok(TestResult {
+                success: true,
+                status: 200,
+                body: "{}".to_string(),
+                time_ms: 12,
+            }),
+        );
+        assert!(ok.passed && ok.hint.is_none());
+        assert_eq!(ok.status, Some(200));
+
+        let failed = PresetCanaryResult::from_response(
+            target(),
+            Ok(TestResult {
+                success: false,
+                status: 503,
+                body: "no credential".to_string(),
+                time_ms: 3,
+            }),
+        );
+        assert!(!failed.passed);
+        assert_eq!(failed.hint.as_deref(), Some(completion_hint(503)));
+        assert_eq!(failed.models, vec!["gpt-4o".to_string()]);
+
+        let unreachable =
+            PresetCanaryResult::from_response(target(), Err("connection refused".to_string()));
+        assert!(!unreachable.passed && unreachable.status.is_none());
+    }
+
+    #[test]
+    fn test_summarize_benchmark() {
+        let target = BenchmarkTarget {
+            provider: Some("openai".to_string()),
+            model: "gpt-4o".to_string(),
+        };
+        let samples = vec![
+            sample(true, 1000, Some(50)),
+            sample(true, 2000, Some(50)),
+            sample(true, 3000, None),
+            sample(false, 100, None),
+        ];
+
+        let result = summarize_benchmark(&target, &samples);
+        assert_eq!(result.requests, 4);
+        assert_eq!(result.success_count, 3);
+        assert_eq!(result.success_rate, 0.75);
+        // 失败请求不计入延迟
+        assert_eq!(result.p50_latency_ms, Some(2000));
+        assert_eq!(result.p99_latency_ms, Some(3000));
+        assert_eq!(result.avg_latency_ms, Some(2000));
+        assert_eq!(result.avg_tokens_per_sec, Some(37.5));
+        assert_eq!(result.errors, vec!["500: error".to_string()]);
+    }
+}
@@ -3,28 +3,77 @@
 //! 包含配置读取、保存、Provider 设置等命令。
 
 use crate::app::types::{AppState, LogState};
-use crate::app::utils::{is_non_local_bind, is_valid_bind_host};
+use crate::app::utils::{is_non_local_bind, is_valid_bind_host, mask_token};
+use crate::commands::config_cmd::ImportResult;
 use crate::config::{
     self,
     observer::{ConfigChangeEvent, RoutingChangeEvent},
-    ConfigChangeSource, GlobalConfigManagerState, DEFAULT_API_KEY,
+    ConfigChangeSource, ConfigManager, EffectiveConfig, ExportBundle, GlobalConfigManagerState,
+    ImportOptions, ImportService, PriceTableEntry, DEFAULT_API_KEY,
 };
+use crate::services::config_backup_service::ConfigBackupService;
+use crate::telemetry::pricing;
+use std::collections::HashMap;
 
 /// 获取配置
+///
+/// `server.api_key` 以掩码形式返回，完整密钥通过 [`reveal_api_key`] 获取。
 #[tauri::command]
 pub async fn get_config(state: tauri::State<'_, AppState>) -> Result<config::Config, String> {
     let s = state.read().await;
-    Ok(s.config.clone())
+    let mut config = s.config.clone();
+    config.server.api_key = mask_token(&config.server.api_key);
+    Ok(config)
+}
+
+/// 获取当前生效的完整服务器 API Key
+///
+/// 仅用于复制密钥、生成客户端配置等需要明文的场景。
+#[tauri::command]
+pub async fn reveal_api_key(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    Ok(state.read().await.server_api_key.current())
+}
+
+/// 获取运行中实际生效的配置
+///
+/// 服务器运行时取热重载管理器中的配置，并叠加运行时修改（轮换后的 API Key、默认 Provider、
+/// 参数注入开关），同时标注每个顶层字段来自配置文件、默认值还是运行时。
+#[tauri::command]
+pub async fn get_effective_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<EffectiveConfig, String> {
+    let s = state.read().await;
+    let mut config = match &s.config_reloader {
+        Some(reloader) => reloader.config(),
+        None => s.config.clone(),
+    };
+    config.server.api_key = mask_token(&s.server_api_key.current());
+    config.default_provider = s.default_provider_ref.read().await.clone();
+    config.injection.enabled = *s.injection_enabled.read().await;
+
+    Ok(EffectiveConfig::resolve(
+        config,
+        &ConfigManager::default_config_path(),
+        s.running,
+    ))
 }
 
 /// 保存配置
 #[tauri::command]
 pub async fn save_config(
     state: tauri::State<'_, AppState>,
-    config: config::Config,
+    mut config: config::Config,
 ) -> Result<(), String> {
     let host = config.server.host.to_lowercase();
 
+    // get_config 返回的是掩码，未修改时保留原密钥
+    {
+        let s = state.read().await;
+        if config.server.api_key == mask_token(&s.config.server.api_key) {
+            config.server.api_key = s.config.server.api_key.clone();
+        }
+    }
+
     // 验证绑定地址
     if !is_valid_bind_host(&host) {
         return Err(
@@ -46,7 +95,40 @@ pub async fn save_config(
 
     let mut s = state.write().await;
     s.config = config.clone();
-    config::save_config(&config).map_err(|e| e.to_string())
+    config::save_config(&config).map_err(|e| e.to_string())?;
+    let config_manager = s.config_manager.clone();
+    drop(s);
+
+    // 通知配置观察者，更新各功能模块的运行时设置
+    if let Some(manager) = config_manager {
+        manager
+            .update_config(config, ConfigChangeSource::ApiCall)
+            .await;
+    }
+    Ok(())
+}
+
+/// 立即从磁盘重新加载配置
+///
+/// 与文件监控触发的热重载流程相同（失败时回滚、更新处理器组件、同步凭证池），
+/// 用于文件监控漏掉变更（如网络文件系统）时手动触发。需要服务器正在运行。
+#[tauri::command]
+pub async fn reload_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<config::ReloadResult, String> {
+    // 只在取重载器时持有锁，重载期间不阻塞其他命令
+    let reloader = state
+        .read()
+        .await
+        .config_reloader
+        .clone()
+        .ok_or_else(|| "服务器未运行，无法重载配置".to_string())?;
+
+    let result = reloader.reload().await;
+    if let config::ReloadResult::Success { .. } = result {
+        state.write().await.config = reloader.config();
+    }
+    Ok(result)
 }
 
 /// 获取默认 Provider
@@ -119,7 +201,8 @@ pub async fn get_endpoint_providers(
         "codex": ep.codex.clone(),
         "windsurf": ep.windsurf.clone(),
         "kiro": ep.kiro.clone(),
-        "other": ep.other.clone()
+        "other": ep.other.clone(),
+        "force_non_streaming": ep.force_non_streaming.clone()
     }))
 }
 
@@ -153,18 +236,10 @@ pub async fn set_endpoint_provider(
     };
 
     // 通过 GlobalConfigManager 通知所有观察者
-    let event = ConfigChangeEvent::EndpointProvidersChanged(
-        config::observer::EndpointProvidersChangeEvent {
-            cursor: ep_config.cursor.clone(),
-            claude_code: ep_config.claude_code.clone(),
-            codex: ep_config.codex.clone(),
-            windsurf: ep_config.windsurf.clone(),
-            kiro: ep_config.kiro.clone(),
-            other: ep_config.other.clone(),
-            source: ConfigChangeSource::FrontendUI,
-        },
-    );
-    config_manager.0.subject().notify_event(event).await;
+    config_manager
+        .0
+        .update_endpoint_providers(ep_config, ConfigChangeSource::FrontendUI)
+        .await;
 
     let provider_display = provider.as_deref().unwrap_or("默认");
     logs.write().await.add(
@@ -182,3 +257,203 @@ pub async fn set_endpoint_provider(
     );
     Ok(provider_display.to_string())
 }
+
+/// 设置客户端是否强制非流式
+///
+/// 开启后该客户端的流式请求会在代理端缓冲上游完整响应，再以单个响应返回。
+#[tauri::command]
+pub async fn set_endpoint_force_non_streaming(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
+    endpoint: String,
+    enabled: bool,
+) -> Result<bool, String> {
+    let ep_config = {
+        let mut s = state.write().await;
+
+        if !s
+            .config
+            .endpoint_providers
+            .set_force_non_streaming(&endpoint, enabled)
+        {
+            return Err(format!("未知的客户端类型: {}", endpoint));
+        }
+
+        config::save_config(&s.config).map_err(|e| e.to_string())?;
+
+        s.config.endpoint_providers.clone()
+    };
+
+    config_manager
+        .0
+        .update_endpoint_providers(ep_config, ConfigChangeSource::FrontendUI)
+        .await;
+
+    let mode = if enabled {
+        "强制非流式"
+    } else {
+        "跟随请求"
+    };
+    logs.write().await.add(
+        "info",
+        &format!("客户端 {} 的流式模式已设置为: {}", endpoint, mode),
+    );
+
+    tracing::info!("[CONFIG] 端点流式模式已更新: {} -> {}", endpoint, mode);
+    Ok(enabled)
+}
+
+/// 价格表及路由中缺少价格的模型
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PriceTableStatus {
+    /// 配置中的价格表
+    pub table: HashMap<String, PriceTableEntry>,
+    /// 模型别名指向但查不到价格的模型（费用统计会按 0 计算）
+    pub missing_models: Vec<String>,
+}
+
+fn price_table_status(config: &config::Config) -> PriceTableStatus {
+    PriceTableStatus {
+        table: config.price_table.clone(),
+        missing_models: pricing::missing_prices(&pricing::routing_models(config)),
+    }
+}
+
+/// 获取价格表
+#[tauri::command]
+pub async fn get_price_table(
+    state: tauri::State<'_, AppState>,
+) -> Result<PriceTableStatus, String> {
+    let s = state.read().await;
+    Ok(price_table_status(&s.config))
+}
+
+/// 替换价格表
+///
+/// 立即更新费用计算使用的价格并保存到配置，无需重启服务器。
+#[tauri::command]
+pub async fn update_price_table(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    table: HashMap<String, PriceTableEntry>,
+) -> Result<PriceTableStatus, String> {
+    let mut normalized = HashMap::with_capacity(table.len());
+    for (model, entry) in table {
+        let model = model.trim().to_string();
+        if model.is_empty() {
+            return Err("模型 ID 不能为空".to_string());
+        }
+        if !entry.is_valid() {
+            return Err(format!("模型 {} 的价格必须是非负数", model));
+        }
+        normalized.insert(model, entry);
+    }
+
+    let mut s = state.write().await;
+    s.config.price_table = normalized;
+    config::save_config(&s.config).map_err(|e| e.to_string())?;
+    let status = price_table_status(&s.config);
+    let (config, config_manager) = (s.config.clone(), s.config_manager.clone());
+    drop(s);
+    if let Some(manager) = config_manager {
+        manager
+            .update_config(config, ConfigChangeSource::ApiCall)
+            .await;
+    }
+
+    logs.write().await.add(
+        "info",
+        &format!("价格表已更新: {} 个模型", status.table.len()),
+    );
+    if !status.missing_models.is_empty() {
+        logs.write().await.add(
+            "warn",
+            &format!(
+                "以下路由模型没有价格，费用统计将为 0: {}",
+                status.missing_models.join(", ")
+            ),
+        );
+    }
+    Ok(status)
+}
+
+/// 立即创建一份配置备份
+#[tauri::command]
+pub async fn create_config_backup(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let config = state.read().await.config.clone();
+    let service = ConfigBackupService::from_settings(&config.backup)?;
+    let path = service.create_backup(&config)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 列出配置备份文件
+#[tauri::command]
+pub async fn list_config_backups(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    let settings = state.read().await.config.backup.clone();
+    let service = ConfigBackupService::from_settings(&settings)?;
+    Ok(service
+        .list_backups()?
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+/// 从备份恢复配置和配置文件中的 OAuth 凭证
+///
+/// 先通过 `ImportService::validate` 校验备份内容，校验通过后以替换模式应用，
+/// 并通知配置观察者使恢复的配置立即生效。凭证池数据库不在备份范围内，保持不变。
+#[tauri::command]
+pub async fn restore_backup(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    path: String,
+) -> Result<ImportResult, String> {
+    let mut s = state.write().await;
+    let service = ConfigBackupService::from_settings(&s.config.backup)?;
+    let content = service.read_backup(&config::expand_tilde(&path))?;
+
+    let validation = ImportService::validate(&content);
+    if !validation.valid {
+        return Err(format!("备份校验失败: {}", validation.errors.join("; ")));
+    }
+
+    let bundle = ExportBundle::from_json(&content).map_err(|e| e.to_string())?;
+    let result = ImportService::import(
+        &bundle,
+        &s.config,
+        &ImportOptions::replace(),
+        &s.config.auth_dir,
+    )
+    .map_err(|e| e.to_string())?;
+
+    config::save_config(&result.config).map_err(|e| e.to_string())?;
+    s.config = result.config.clone();
+    let config_manager = s.config_manager.clone();
+    drop(s);
+
+    // 与 save_config 相同，通知配置观察者更新路由、别名等运行时设置
+    if let Some(manager) = config_manager {
+        manager
+            .update_config(result.config.clone(), ConfigChangeSource::ApiCall)
+            .await;
+    }
+
+    logs.write()
+        .await
+        .add("warn", &format!("[BACKUP] 已从备份恢复配置: {}", path));
+
+    let mut warnings = validation.warnings;
+    warnings.extend(result.warnings);
+    warnings.push(
+        "Config backups do not include the credential pool database; pooled credentials were left unchanged"
+            .to_string(),
+    );
+    let mut config = result.config;
+    config.server.api_key = mask_token(&config.server.api_key);
+    Ok(ImportResult {
+        success: result.success,
+        config,
+        warnings,
+    })
+}
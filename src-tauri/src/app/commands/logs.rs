@@ -1,10 +1,26 @@
 //! 日志命令
 //!
-//! 包含日志查询和清理命令。
+//! 包含日志查询、清理和实时订阅命令。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast::{self, error::RecvError};
 
 use crate::app::types::LogState;
 use crate::logger;
 
+/// 日志实时订阅状态
+///
+/// 新日志通过 `log-entry` 事件推送到前端，所有窗口共享同一个级别过滤。
+#[derive(Default)]
+pub struct LogTailState {
+    /// 当前过滤条件（None 表示未订阅）
+    filter: Arc<parking_lot::RwLock<Option<logger::LogFilter>>>,
+    started: AtomicBool,
+}
+
 /// 获取日志
 #[tauri::command]
 pub async fn get_logs(logs: tauri::State<'_, LogState>) -> Result<Vec<logger::LogEntry>, String> {
@@ -17,3 +33,61 @@ pub async fn clear_logs(logs: tauri::State<'_, LogState>) -> Result<(), String>
     logs.write().await.clear();
     Ok(())
 }
+
+/// 订阅新日志
+///
+/// 之后新增的日志通过 `log-entry` 事件推送，前端可通过 `listen("log-entry", ...)` 接收。
+/// 再次调用会更新级别过滤。
+///
+/// # Arguments
+/// * `level` - 最低日志级别（debug/info/warn/error，可选）
+#[tauri::command]
+pub async fn subscribe_logs(
+    app: AppHandle,
+    logs: tauri::State<'_, LogState>,
+    tail: tauri::State<'_, LogTailState>,
+    level: Option<String>,
+) -> Result<(), String> {
+    *tail.filter.write() = Some(logger::LogFilter { level });
+    if !tail.started.swap(true, Ordering::SeqCst) {
+        spawn_log_forwarder(app, logs.read().await.subscribe(), tail.filter.clone());
+    }
+    Ok(())
+}
+
+/// 取消订阅新日志
+#[tauri::command]
+pub async fn unsubscribe_logs(tail: tauri::State<'_, LogTailState>) -> Result<(), String> {
+    *tail.filter.write() = None;
+    Ok(())
+}
+
+/// 启动日志转发任务，只转发满足当前级别过滤的日志
+fn spawn_log_forwarder(
+    app: AppHandle,
+    mut receiver: broadcast::Receiver<logger::LogEntry>,
+    filter: Arc<parking_lot::RwLock<Option<logger::LogFilter>>>,
+) {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(entry) => {
+                    let matched = filter
+                        .read()
+                        .as_ref()
+                        .is_some_and(|filter| filter.matches(&entry));
+                    if !matched {
+                        continue;
+                    }
+                    if let Err(e) = app.emit("log-entry", &entry) {
+                        tracing::warn!("发送日志事件到前端失败: {}", e);
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    tracing::warn!("日志订阅落后 {} 条", n);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
@@ -2,12 +2,15 @@
 //!
 //! 包含服务器启动、停止、状态查询等命令。
 
-use crate::app::types::{AppState, LogState};
+use crate::app::types::{AppState, LogState, TrayManagerState};
+use crate::app::utils::{generate_api_key, mask_token};
 use crate::app::TokenCacheServiceState;
 use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
 use crate::commands::telemetry_cmd::TelemetryState;
+use crate::config;
 use crate::database;
 use crate::server;
+use crate::tray::TrayIconStatus;
 
 /// 启动服务器
 #[tauri::command]
@@ -66,3 +69,119 @@ pub async fn get_server_status(
 
     Ok(status)
 }
+
+/// 设置代理暂停状态（全局熔断开关）
+///
+/// 暂停后所有补全路由立即返回 503 + Retry-After，服务器和 UI 保持运行，
+/// `/health` 与管理端点不受影响。
+#[tauri::command]
+pub async fn set_proxy_paused(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    tray_state: tauri::State<'_, TrayManagerState<tauri::Wry>>,
+    paused: bool,
+) -> Result<bool, String> {
+    let (was_paused, server_running) = {
+        let s = state.read().await;
+        (s.set_proxy_paused(paused), s.running)
+    };
+
+    if was_paused != paused {
+        let message = if paused {
+            "[PROXY_PAUSE] 代理已暂停，所有上游请求将返回 503"
+        } else {
+            "[PROXY_PAUSE] 代理已恢复"
+        };
+        tracing::warn!("{}", message);
+        logs.write().await.add("warn", message);
+    }
+
+    // 在托盘中反映暂停状态
+    let tray_guard = tray_state.0.read().await;
+    if let Some(tray_manager) = tray_guard.as_ref() {
+        let mut snapshot = tray_manager.get_state().await;
+        snapshot.icon_status = if !server_running {
+            TrayIconStatus::Stopped
+        } else if paused {
+            TrayIconStatus::Warning
+        } else {
+            TrayIconStatus::Running
+        };
+        if let Err(e) = tray_manager.update_state(snapshot).await {
+            tracing::error!("[PROXY_PAUSE] 更新托盘状态失败: {}", e);
+        }
+        let tooltip = if paused {
+            "ProxyCast - 代理已暂停"
+        } else {
+            "ProxyCast - AI API 代理"
+        };
+        if let Err(e) = tray_manager.set_tooltip(tooltip) {
+            tracing::error!("[PROXY_PAUSE] 更新托盘提示失败: {}", e);
+        }
+    }
+
+    Ok(paused)
+}
+
+/// 获取代理暂停状态
+#[tauri::command]
+pub async fn get_proxy_paused(state: tauri::State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.read().await.is_proxy_paused())
+}
+
+/// API 密钥轮换结果
+#[derive(Debug, serde::Serialize)]
+pub struct RotateApiKeyResponse {
+    /// 新密钥（仅在本次响应中完整返回）
+    pub api_key: String,
+    /// 新密钥掩码
+    pub api_key_masked: String,
+    /// 旧密钥失效时间（RFC3339，无宽限期时为 None）
+    pub previous_key_expires_at: Option<String>,
+}
+
+/// 轮换服务器 API 密钥（无需重启服务器）
+///
+/// 生成新密钥并持久化到配置，同时立即应用到运行中的服务器。
+/// 旧密钥在宽限期内仍然有效，宽限期默认取 `server.api_key_grace_minutes`。
+#[tauri::command]
+pub async fn rotate_api_key(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    grace_minutes: Option<u32>,
+) -> Result<RotateApiKeyResponse, String> {
+    let mut s = state.write().await;
+    let grace_minutes = grace_minutes.unwrap_or(s.config.server.api_key_grace_minutes);
+    let new_key = generate_api_key();
+
+    let mut config = s.config.clone();
+    config.server.api_key = new_key.clone();
+    config::save_config(&config).map_err(|e| format!("保存配置失败: {}", e))?;
+    s.config = config;
+
+    s.server_api_key.rotate(
+        new_key.clone(),
+        std::time::Duration::from_secs(u64::from(grace_minutes) * 60),
+    );
+    if s.running {
+        s.running_api_key = Some(new_key.clone());
+    }
+
+    let api_key_masked = mask_token(&new_key);
+    let previous_key_expires_at = (grace_minutes > 0).then(|| {
+        (chrono::Utc::now() + chrono::Duration::minutes(i64::from(grace_minutes))).to_rfc3339()
+    });
+    logs.write().await.add(
+        "warn",
+        &format!(
+            "[API_KEY] API 密钥已轮换为 {}，旧密钥宽限期 {} 分钟",
+            api_key_masked, grace_minutes
+        ),
+    );
+
+    Ok(RotateApiKeyResponse {
+        api_key: new_key,
+        api_key_masked,
+        previous_key_expires_at,
+    })
+}
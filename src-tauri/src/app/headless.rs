@@ -0,0 +1,190 @@
+//! 无界面运行模式
+//!
+//! 以 `--headless` 参数（或设置环境变量 `PROXYCAST_HEADLESS=1`）启动时不创建窗口和托盘，
+//! 只加载凭证池、启动 HTTP 服务器和后台任务，日志输出到标准输出，适合部署在服务器上。
+//!
+//! 配置加载和安全检查与图形界面模式相同。收到 SIGINT/SIGTERM 后服务器停止接受新连接，
+//! 等待进行中的请求完成（最多 [`DRAIN_TIMEOUT`]）后退出。
+
+use std::time::Duration;
+
+use super::bootstrap::AppStates;
+use super::setup;
+use super::types::LogState;
+
+/// 命令行参数
+const HEADLESS_ARG: &str = "--headless";
+
+/// 环境变量
+const HEADLESS_ENV: &str = "PROXYCAST_HEADLESS";
+
+/// 退出时等待进行中请求完成的最长时间
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 是否以无界面模式启动
+pub fn requested() -> bool {
+    is_requested(
+        std::env::args().skip(1),
+        std::env::var(HEADLESS_ENV).ok().as_deref(),
+    )
+}
+
+fn is_requested(mut args: impl Iterator<Item = String>, env: Option<&str>) -> bool {
+    args.any(|arg| arg == HEADLESS_ARG)
+        || env.is_some_and(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes"
+            )
+        })
+}
+
+/// 初始化输出到标准输出的日志
+pub fn init_logging(level: &str) {
+    let level = level.parse().unwrap_or(tracing::Level::INFO);
+    let _ = tracing_subscriber::fmt()
+        .with_writer(std::io::stdout)
+        .with_max_level(level)
+        .try_init();
+}
+
+/// 运行无界面模式，阻塞直到收到退出信号，返回进程退出码
+pub fn run(states: AppStates) -> i32 {
+    tauri::async_runtime::block_on(serve(states))
+}
+
+async fn serve(states: AppStates) -> i32 {
+    let AppStates {
+        state,
+        logs,
+        db,
+        provider_pool_service,
+        token_cache_service,
+        shared_stats,
+        shared_tokens,
+        shared_logger,
+        flow_monitor_arc,
+        flow_interceptor_arc,
+        ..
+    } = states;
+
+    forward_logs(&logs).await;
+    tracing::info!("[HEADLESS] 以无界面模式启动");
+
+    setup::spawn_maintenance_tasks(state.clone(), db.clone());
+    tauri::async_runtime::spawn(setup::run_server_watchdog(
+        None,
+        state.clone(),
+        logs.clone(),
+    ));
+    tauri::async_runtime::spawn(setup::run_unhealthy_credential_pruner(
+        None,
+        provider_pool_service.0.clone(),
+        db.clone(),
+        logs.clone(),
+    ));
+
+    if setup::load_pool_and_start_server(
+        state.clone(),
+        logs.clone(),
+        db,
+        provider_pool_service.0,
+        token_cache_service.0,
+        shared_stats,
+        shared_tokens,
+        shared_logger,
+        flow_monitor_arc,
+        flow_interceptor_arc,
+    )
+    .await
+    .is_err()
+    {
+        return 1;
+    }
+
+    let signal = wait_for_shutdown_signal().await;
+    tracing::info!("[HEADLESS] 收到 {}，正在停止服务器...", signal);
+
+    let done = state.write().await.stop_graceful().await;
+    if let Some(done) = done {
+        match tokio::time::timeout(DRAIN_TIMEOUT, done).await {
+            Ok(_) => tracing::info!("[HEADLESS] 进行中的请求已处理完毕"),
+            Err(_) => tracing::warn!(
+                "[HEADLESS] 等待进行中的请求超时（{} 秒），强制退出",
+                DRAIN_TIMEOUT.as_secs()
+            ),
+        }
+    }
+    tracing::info!("[HEADLESS] 已退出");
+    0
+}
+
+/// 把应用日志（界面日志面板中的内容）转发到标准输出
+async fn forward_logs(logs: &LogState) {
+    let mut receiver = logs.read().await.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(entry) => match entry.level.as_str() {
+                    "error" => tracing::error!("{}", entry.message),
+                    "warn" => tracing::warn!("{}", entry.message),
+                    "debug" => tracing::debug!("{}", entry.message),
+                    _ => tracing::info!("{}", entry.message),
+                },
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("[HEADLESS] 日志输出过慢，跳过 {} 条", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// 等待 SIGINT（Ctrl+C）或 SIGTERM
+async fn wait_for_shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => "SIGINT",
+                    _ = terminate.recv() => "SIGTERM",
+                }
+            }
+            Err(e) => {
+                tracing::warn!("[HEADLESS] 无法监听 SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl+C"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> impl Iterator<Item = String> {
+        list.iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_is_requested() {
+        assert!(is_requested(args(&["--minimized", "--headless"]), None));
+        assert!(is_requested(args(&[]), Some("1")));
+        assert!(is_requested(args(&[]), Some(" TRUE ")));
+        assert!(!is_requested(args(&["--minimized"]), None));
+        assert!(!is_requested(args(&[]), Some("0")));
+    }
+}
@@ -10,9 +10,11 @@
 //! - `utils` - 辅助函数
 //! - `bootstrap` - 应用启动引导（配置验证、状态初始化）
 //! - `runner` - 应用运行器（Tauri Builder 配置和命令注册）
+//! - `headless` - 无界面运行模式（只启动服务器和后台任务）
 
 pub mod bootstrap;
 pub mod commands;
+mod headless;
 pub mod runner;
 mod setup;
 mod state;
@@ -13,6 +13,7 @@ use crate::tray::{TrayIconStatus, TrayManager, TrayStateSnapshot};
 
 use super::bootstrap::{self, AppStates};
 use super::commands as app_commands;
+use super::headless;
 use super::types::{AppState, TrayManagerState};
 
 /// 运行 Tauri 应用
@@ -23,6 +24,9 @@ use super::types::{AppState, TrayManagerState};
 /// 3. 配置 Tauri Builder（插件、状态管理、事件处理）
 /// 4. 注册所有 Tauri 命令
 /// 5. 启动应用
+///
+/// 带 `--headless` 参数（或设置 `PROXYCAST_HEADLESS=1`）时不创建窗口和托盘，
+/// 转入 [`headless::run`]。
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // 加载并验证配置
@@ -35,6 +39,11 @@ pub fn run() {
         }
     };
 
+    let headless = headless::requested();
+    if headless {
+        headless::init_logging(&config.logging.level);
+    }
+
     // 初始化所有应用状态
     let states = match bootstrap::init_states(&config) {
         Ok(s) => s,
@@ -45,6 +54,14 @@ pub fn run() {
         }
     };
 
+    if headless {
+        let code = headless::run(states);
+        if code != 0 {
+            std::process::exit(code);
+        }
+        return;
+    }
+
     // 解构状态以便使用
     let AppStates {
         state,
@@ -98,6 +115,7 @@ pub fn run() {
     let shared_logger_clone = shared_logger.clone();
     let flow_monitor_clone = flow_monitor.clone();
     let flow_interceptor_clone = flow_interceptor.clone();
+    let enhanced_stats_clone = enhanced_stats_service_state.0.clone();
     let update_check_service_clone = update_check_service_state.0.clone();
 
     let mut builder = tauri::Builder::default()
@@ -121,6 +139,24 @@ pub fn run() {
         .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
             tracing::info!("[单实例] 收到来自新实例的参数: {:?}", args);
 
+            // Linux 上本应用被注册为默认浏览器时，URL 会作为参数转发过来
+            #[cfg(target_os = "linux")]
+            {
+                let urls: Vec<&String> = args
+                    .iter()
+                    .skip(1)
+                    .filter(|arg| arg.starts_with("http://") || arg.starts_with("https://"))
+                    .collect();
+                if !urls.is_empty() {
+                    for url in urls {
+                        crate::browser_interceptor::platform::linux::handle_deep_link_url(
+                            url.clone(),
+                        );
+                    }
+                    return;
+                }
+            }
+
             // 将窗口带到前台
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.show();
@@ -162,6 +198,8 @@ pub fn run() {
         .manage(webview_manager_state)
         .manage(update_check_service_state)
         .manage(session_files_state)
+        .manage(commands::flow_monitor_cmd::FlowFollowState::default())
+        .manage(app_commands::LogTailState::default())
         .on_window_event(move |window, event| {
             // 处理窗口关闭事件
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
@@ -430,6 +468,24 @@ pub fn run() {
                 });
             }
 
+            // 启动定时维护任务（配置备份、数据库维护）
+            super::setup::spawn_maintenance_tasks(state_clone.clone(), db_clone.clone());
+
+            // 监控服务器任务
+            tauri::async_runtime::spawn(super::setup::run_server_watchdog(
+                Some(app.handle().clone()),
+                state_clone.clone(),
+                logs_clone.clone(),
+            ));
+
+            // 自动禁用长期不健康的凭证
+            tauri::async_runtime::spawn(super::setup::run_unhealthy_credential_pruner(
+                Some(app.handle().clone()),
+                pool_service_clone.clone(),
+                db_clone.clone(),
+                logs_clone.clone(),
+            ));
+
             // 自动启动服务器
             let state = state_clone.clone();
             let logs = logs_clone.clone();
@@ -583,6 +639,13 @@ pub fn run() {
             });
             tracing::info!("[启动] 后台更新检查任务已启动");
 
+            // 启动 Flow 异常告警评估任务
+            let alert_evaluator = Arc::new(crate::flow_monitor::AlertEvaluator::new(
+                flow_monitor_clone.clone(),
+                enhanced_stats_clone.clone(),
+            ));
+            tauri::async_runtime::spawn(alert_evaluator.run());
+
             // 启动会话文件清理任务（清理 30 天前的过期会话）
             tauri::async_runtime::spawn(async move {
                 // 延迟 10 秒执行，避免影响启动性能
@@ -626,13 +689,25 @@ pub fn run() {
             app_commands::start_server,
             app_commands::stop_server,
             app_commands::get_server_status,
+            app_commands::set_proxy_paused,
+            app_commands::get_proxy_paused,
+            app_commands::rotate_api_key,
             // Config commands (from app::commands)
             app_commands::get_config,
+            app_commands::reveal_api_key,
+            app_commands::get_effective_config,
             app_commands::save_config,
+            app_commands::reload_config,
+            app_commands::create_config_backup,
+            app_commands::list_config_backups,
+            app_commands::restore_backup,
             app_commands::get_default_provider,
             app_commands::set_default_provider,
             app_commands::get_endpoint_providers,
             app_commands::set_endpoint_provider,
+            app_commands::set_endpoint_force_non_streaming,
+            app_commands::get_price_table,
+            app_commands::update_price_table,
             // Unified OAuth commands (new)
             commands::oauth_cmd::get_oauth_credentials,
             commands::oauth_cmd::reload_oauth_credentials,
@@ -641,6 +716,7 @@ pub fn run() {
             commands::oauth_cmd::get_oauth_token_file_hash,
             commands::oauth_cmd::check_and_reload_oauth_credentials,
             commands::oauth_cmd::get_all_oauth_credentials,
+            commands::oauth_cmd::check_oauth_callback_port,
             // Legacy Kiro commands (from app::commands, deprecated)
             app_commands::refresh_kiro_token,
             app_commands::reload_credentials,
@@ -671,8 +747,14 @@ pub fn run() {
             // Log commands (from app::commands)
             app_commands::get_logs,
             app_commands::clear_logs,
+            app_commands::subscribe_logs,
+            app_commands::unsubscribe_logs,
             // API test commands (from app::commands)
             app_commands::test_api,
+            app_commands::run_self_test,
+            app_commands::benchmark_providers,
+            app_commands::get_recommended_presets,
+            app_commands::apply_recommended_preset,
             app_commands::get_available_models,
             app_commands::check_api_compatibility,
             // Switch commands
@@ -696,6 +778,7 @@ pub fn run() {
             // Config import/export commands
             commands::config_cmd::export_config,
             commands::config_cmd::validate_config_yaml,
+            commands::config_cmd::get_config_schema,
             commands::config_cmd::import_config,
             commands::config_cmd::get_config_paths,
             // Enhanced export/import commands (using ExportService/ImportService)
@@ -745,6 +828,17 @@ pub fn run() {
             commands::provider_pool_cmd::update_provider_pool_credential,
             commands::provider_pool_cmd::delete_provider_pool_credential,
             commands::provider_pool_cmd::toggle_provider_pool_credential,
+            commands::provider_pool_cmd::set_provider_pool_credential_tier,
+            commands::provider_pool_cmd::park_credential,
+            commands::provider_pool_cmd::unpark_credential,
+            commands::provider_pool_cmd::bulk_update_credentials,
+            commands::provider_pool_cmd::set_provider_pool_credential_upstream_headers,
+            commands::provider_pool_cmd::set_provider_pool_credential_project_id,
+            commands::provider_pool_cmd::export_pool_template,
+            commands::provider_pool_cmd::import_pool_template,
+            commands::provider_pool_cmd::fill_provider_pool_credential_secret,
+            commands::provider_pool_cmd::dedupe_pool_credentials,
+            commands::provider_pool_cmd::get_credential_account_info,
             commands::provider_pool_cmd::reset_provider_pool_credential,
             commands::provider_pool_cmd::reset_provider_pool_health,
             commands::provider_pool_cmd::check_provider_pool_credential_health,
@@ -762,6 +856,8 @@ pub fn run() {
             commands::provider_pool_cmd::add_iflow_oauth_credential,
             commands::provider_pool_cmd::add_iflow_cookie_credential,
             commands::provider_pool_cmd::refresh_pool_credential_token,
+            commands::provider_pool_cmd::get_token_cache_status,
+            commands::provider_pool_cmd::invalidate_token_cache,
             commands::provider_pool_cmd::get_pool_credential_oauth_status,
             commands::provider_pool_cmd::debug_kiro_credentials,
             commands::provider_pool_cmd::test_user_credentials,
@@ -824,6 +920,11 @@ pub fn run() {
             // Route commands
             commands::route_cmd::get_available_routes,
             commands::route_cmd::get_route_curl_examples,
+            commands::route_cmd::explain_model_routing,
+            commands::route_cmd::simulate_routing,
+            commands::route_cmd::lint_routing_rules,
+            commands::route_cmd::add_routing_rule,
+            commands::route_cmd::update_routing_rule,
             // Resilience config commands
             commands::resilience_cmd::get_retry_config,
             commands::resilience_cmd::update_retry_config,
@@ -831,6 +932,8 @@ pub fn run() {
             commands::resilience_cmd::update_failover_config,
             commands::resilience_cmd::get_switch_log,
             commands::resilience_cmd::clear_switch_log,
+            // Database maintenance commands
+            commands::database_cmd::compact_database,
             // Telemetry commands
             commands::telemetry_cmd::get_request_logs,
             commands::telemetry_cmd::get_request_log_detail,
@@ -838,6 +941,14 @@ pub fn run() {
             commands::telemetry_cmd::get_stats_summary,
             commands::telemetry_cmd::get_stats_by_provider,
             commands::telemetry_cmd::get_stats_by_model,
+            commands::telemetry_cmd::get_stats_by_user,
+            commands::telemetry_cmd::get_inflight_requests,
+            commands::telemetry_cmd::get_response_cache_stats,
+            commands::telemetry_cmd::clear_response_cache,
+            commands::telemetry_cmd::get_default_provider_split_stats,
+            commands::telemetry_cmd::reset_default_provider_split_stats,
+            commands::telemetry_cmd::get_request_trace,
+            commands::telemetry_cmd::list_request_traces,
             commands::telemetry_cmd::get_token_summary,
             commands::telemetry_cmd::get_token_stats_by_provider,
             commands::telemetry_cmd::get_token_stats_by_model,
@@ -890,6 +1001,7 @@ pub fn run() {
             commands::flow_monitor_cmd::search_flows,
             commands::flow_monitor_cmd::get_flow_stats,
             commands::flow_monitor_cmd::export_flows,
+            commands::flow_monitor_cmd::export_flows_ndjson,
             commands::flow_monitor_cmd::update_flow_annotations,
             commands::flow_monitor_cmd::toggle_flow_starred,
             commands::flow_monitor_cmd::add_flow_comment,
@@ -897,6 +1009,9 @@ pub fn run() {
             commands::flow_monitor_cmd::remove_flow_tag,
             commands::flow_monitor_cmd::set_flow_marker,
             commands::flow_monitor_cmd::cleanup_flows,
+            commands::flow_monitor_cmd::persist_flows_to_store,
+            commands::flow_monitor_cmd::prune_memory_flows,
+            commands::flow_monitor_cmd::prune_flow_files,
             commands::flow_monitor_cmd::get_recent_flows,
             commands::flow_monitor_cmd::get_flow_monitor_status,
             commands::flow_monitor_cmd::get_flow_monitor_debug_info,
@@ -904,6 +1019,7 @@ pub fn run() {
             commands::flow_monitor_cmd::enable_flow_monitor,
             commands::flow_monitor_cmd::disable_flow_monitor,
             commands::flow_monitor_cmd::subscribe_flow_events,
+            commands::flow_monitor_cmd::unfollow_flow_session,
             commands::flow_monitor_cmd::get_all_flow_tags,
             // Flow Monitor filter expression commands
             commands::flow_monitor_cmd::parse_filter,
@@ -929,9 +1045,16 @@ pub fn run() {
             commands::flow_monitor_cmd::update_threshold_config,
             commands::flow_monitor_cmd::get_request_rate,
             commands::flow_monitor_cmd::set_rate_window,
+            commands::flow_monitor_cmd::get_flow_capture_filter,
+            commands::flow_monitor_cmd::update_flow_capture_filter,
             // Flow Replayer commands
             commands::flow_monitor_cmd::replay_flow,
             commands::flow_monitor_cmd::replay_flows_batch,
+            commands::flow_monitor_cmd::fuzz_replay_flow,
+            commands::flow_monitor_cmd::get_dead_letters,
+            commands::flow_monitor_cmd::delete_dead_letter,
+            commands::flow_monitor_cmd::clear_dead_letters,
+            commands::flow_monitor_cmd::replay_dead_letter,
             // Flow Diff commands
             commands::flow_monitor_cmd::diff_flows,
             // Session Management commands
@@ -944,8 +1067,10 @@ pub fn run() {
             commands::flow_monitor_cmd::archive_session,
             commands::flow_monitor_cmd::unarchive_session,
             commands::flow_monitor_cmd::delete_session,
+            commands::flow_monitor_cmd::merge_sessions,
             commands::flow_monitor_cmd::export_session,
             commands::flow_monitor_cmd::get_session_flow_count,
+            commands::flow_monitor_cmd::get_session_cost_summary,
             commands::flow_monitor_cmd::is_flow_in_session,
             commands::flow_monitor_cmd::get_sessions_for_flow,
             commands::flow_monitor_cmd::get_auto_session_config,
@@ -962,6 +1087,9 @@ pub fn run() {
             commands::flow_monitor_cmd::export_quick_filters,
             commands::flow_monitor_cmd::import_quick_filters,
             commands::flow_monitor_cmd::find_quick_filter_by_name,
+            commands::flow_monitor_cmd::list_filter_variables,
+            commands::flow_monitor_cmd::save_filter_variable,
+            commands::flow_monitor_cmd::delete_filter_variable,
             // Code Export commands
             commands::flow_monitor_cmd::export_flow_as_code,
             commands::flow_monitor_cmd::export_flows_as_code,
@@ -985,6 +1113,7 @@ pub fn run() {
             commands::flow_monitor_cmd::get_request_trend,
             commands::flow_monitor_cmd::get_token_distribution,
             commands::flow_monitor_cmd::get_latency_histogram,
+            commands::flow_monitor_cmd::get_latency_percentiles,
             commands::flow_monitor_cmd::export_stats_report,
             // Batch Operations commands
             commands::flow_monitor_cmd::batch_star_flows,
@@ -1023,6 +1152,7 @@ pub fn run() {
             commands::browser_interceptor_cmd::get_browser_interceptor_statistics,
             commands::browser_interceptor_cmd::show_notification,
             commands::browser_interceptor_cmd::show_url_intercept_notification,
+            commands::browser_interceptor_cmd::get_recent_intercept_notifications,
             commands::browser_interceptor_cmd::show_status_notification,
             // Auto fix commands
             commands::auto_fix_cmd::auto_fix_configuration,
@@ -1032,6 +1162,9 @@ pub fn run() {
             commands::machine_id_cmd::generate_random_machine_id,
             commands::machine_id_cmd::validate_machine_id,
             commands::machine_id_cmd::check_admin_privileges,
+            commands::machine_id_cmd::audit_machine_id,
+            commands::machine_id_cmd::repair_machine_id,
+            commands::machine_id_cmd::undo_machine_id_repair,
             commands::machine_id_cmd::get_os_type,
             commands::machine_id_cmd::backup_machine_id_to_file,
             commands::machine_id_cmd::restore_machine_id_from_file,
@@ -1047,6 +1180,8 @@ pub fn run() {
             commands::kiro_local::switch_kiro_to_local,
             commands::kiro_local::get_kiro_fingerprint_info,
             commands::kiro_local::get_local_kiro_credential_uuid,
+            commands::kiro_local::discover_kiro_installations,
+            commands::kiro_local::import_kiro_from_installation,
             // Agent commands
             commands::agent_cmd::agent_start_process,
             commands::agent_cmd::agent_stop_process,
@@ -1165,6 +1300,8 @@ pub fn run() {
             commands::terminal_cmd::terminal_close,
             commands::terminal_cmd::terminal_list_sessions,
             commands::terminal_cmd::terminal_get_session,
+            commands::terminal_cmd::terminal_attach_session,
+            commands::terminal_cmd::terminal_get_session_scrollback,
             // Connection commands
             commands::connection_cmd::connection_list,
             commands::connection_cmd::connection_add,
@@ -10,10 +10,13 @@ use crate::agent::NativeAgentState;
 use crate::commands::oauth_plugin_cmd::OAuthPluginManagerState;
 use crate::database;
 use crate::flow_monitor::FlowInterceptor;
+use crate::services::config_backup_service::ConfigBackupService;
 use crate::services::provider_pool_service::ProviderPoolService;
 use crate::services::token_cache_service::TokenCacheService;
 use crate::telemetry;
-use crate::tray::{TrayIconStatus, TrayManager, TrayStateSnapshot};
+use crate::tray::{
+    calculate_icon_status, CredentialHealth, TrayIconStatus, TrayManager, TrayStateSnapshot,
+};
 
 use super::types::{AppState, LogState, TrayManagerState};
 
@@ -72,6 +75,13 @@ pub fn setup_app(
             .expect("Failed to initialize default skill repos");
     }
 
+    // 监控服务器任务
+    tauri::async_runtime::spawn(run_server_watchdog(
+        Some(app.handle().clone()),
+        state.clone(),
+        logs.clone(),
+    ));
+
     // 自动启动服务器
     let app_handle = app.handle().clone();
     tauri::async_runtime::spawn(async move {
@@ -94,8 +104,309 @@ pub fn setup_app(
     Ok(())
 }
 
-/// 异步启动服务器
-async fn start_server_async(
+/// 启动定时维护任务（配置备份、数据库维护）
+///
+/// 图形界面和 headless 模式共用
+pub(super) fn spawn_maintenance_tasks(state: AppState, db: database::DbConnection) {
+    tauri::async_runtime::spawn(run_config_backup_scheduler(state.clone()));
+    tauri::async_runtime::spawn(run_database_maintenance_scheduler(state, db));
+}
+
+/// 配置定时备份循环
+///
+/// 每次循环都重新读取备份设置，因此修改间隔或目录无需重启应用。
+/// 最近一份备份距今不足间隔时等待到期，避免每次启动都创建新备份。
+async fn run_config_backup_scheduler(state: AppState) {
+    loop {
+        let (settings, config) = {
+            let s = state.read().await;
+            (s.config.backup.clone(), s.config.clone())
+        };
+        let interval = std::time::Duration::from_secs(settings.interval_hours.max(1) * 3600);
+        let mut wait = interval;
+
+        if settings.enabled {
+            let result = tokio::task::spawn_blocking(move || -> Result<_, String> {
+                let service = ConfigBackupService::from_settings(&settings)?;
+                let remaining = service.time_until_next_backup(interval);
+                if !remaining.is_zero() {
+                    return Ok((None, remaining));
+                }
+                service
+                    .create_backup(&config)
+                    .map(|path| (Some(path), interval))
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+
+            match result {
+                Ok((Some(path), _)) => tracing::info!("[BACKUP] 配置已备份: {:?}", path),
+                Ok((None, remaining)) => {
+                    tracing::debug!("[BACKUP] 距上次备份不足间隔，{:?} 后再备份", remaining);
+                    wait = remaining;
+                }
+                Err(e) => tracing::error!("[BACKUP] 配置备份失败: {}", e),
+            }
+        }
+
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// 数据库维护检查间隔上限，保证修改维护时刻后最迟一小时内生效
+const DB_MAINTENANCE_MAX_SLEEP: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// 数据库定时维护循环
+///
+/// 每天在设置的时刻（本地时间）执行一次；每次醒来都重新读取设置，修改后无需重启应用
+async fn run_database_maintenance_scheduler(state: AppState, db: database::DbConnection) {
+    let mut last_run: Option<chrono::NaiveDate> = None;
+    loop {
+        let settings = state.read().await.config.database_maintenance.clone();
+        let now = chrono::Local::now().naive_local();
+
+        if settings.enabled
+            && database::maintenance::in_maintenance_window(now, settings.hour)
+            && last_run != Some(now.date())
+        {
+            last_run = Some(now.date());
+            let db = db.clone();
+            let result =
+                tokio::task::spawn_blocking(move || database::maintenance::run(&db, &settings))
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|r| r);
+            if let Err(e) = result {
+                tracing::error!("[DB_MAINTENANCE] 数据库维护失败: {}", e);
+            }
+            continue;
+        }
+
+        let delay = database::maintenance::delay_until_hour(now, settings.hour)
+            .min(DB_MAINTENANCE_MAX_SLEEP);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// 服务器稳定运行超过该时间后重新计算连续重启次数
+const WATCHDOG_STABLE_RUN: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// 服务器任务监控循环
+///
+/// 服务器任务意外退出（出错或 panic）时将 `ServerState` 标记为已停止、记录错误并把托盘
+/// 切换为停止状态，避免界面显示"运行中"而实际不再提供服务。开启
+/// `server.watchdog.auto_restart` 时按指数退避自动重启。
+pub(super) async fn run_server_watchdog(
+    app_handle: Option<tauri::AppHandle>,
+    state: AppState,
+    logs: LogState,
+) {
+    let Some(mut exits) = state.write().await.take_exit_receiver() else {
+        return;
+    };
+    let mut restarts = 0u32;
+    let mut last_restart: Option<std::time::Instant> = None;
+
+    while let Some(exit) = exits.recv().await {
+        let watchdog = {
+            let mut s = state.write().await;
+            if !s.is_current_run(&exit) {
+                continue;
+            }
+            s.stop().await;
+            s.config.server.watchdog.clone()
+        };
+
+        let reason = exit
+            .error
+            .unwrap_or_else(|| "服务器任务意外结束".to_string());
+        tracing::error!("[WATCHDOG] 服务器意外停止: {}", reason);
+        logs.write()
+            .await
+            .add("error", &format!("[WATCHDOG] 服务器意外停止: {}", reason));
+        update_tray_server_status(app_handle.as_ref(), false, String::new()).await;
+
+        if !watchdog.auto_restart {
+            continue;
+        }
+        if last_restart.is_some_and(|t| t.elapsed() >= WATCHDOG_STABLE_RUN) {
+            restarts = 0;
+        }
+        if restarts >= watchdog.max_restarts {
+            tracing::error!(
+                "[WATCHDOG] 已连续重启 {} 次，停止自动重启",
+                watchdog.max_restarts
+            );
+            logs.write()
+                .await
+                .add("error", "[WATCHDOG] 已达到最大重启次数，停止自动重启");
+            continue;
+        }
+        restarts += 1;
+        let delay = watchdog.backoff(restarts);
+        tracing::warn!(
+            "[WATCHDOG] {} 秒后第 {} 次自动重启服务器",
+            delay.as_secs(),
+            restarts
+        );
+        tokio::time::sleep(delay).await;
+
+        let mut s = state.write().await;
+        if s.running {
+            // 等待期间已被手动启动
+            continue;
+        }
+        match s.restart().await {
+            Ok(()) => {
+                last_restart = Some(std::time::Instant::now());
+                let address = format!("{}:{}", s.config.server.host, s.config.server.port);
+                drop(s);
+                logs.write()
+                    .await
+                    .add("info", &format!("[WATCHDOG] 服务器已重启: {}", address));
+                update_tray_server_status(app_handle.as_ref(), true, address).await;
+            }
+            Err(e) => {
+                drop(s);
+                tracing::error!("[WATCHDOG] 重启服务器失败: {}", e);
+                logs.write()
+                    .await
+                    .add("error", &format!("[WATCHDOG] 重启服务器失败: {}", e));
+            }
+        }
+    }
+}
+
+/// 不健康凭证自动禁用的检查间隔
+const UNHEALTHY_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// 不健康凭证自动禁用循环
+///
+/// 开启 `health_scoring.auto_disable_after_days` 时，每小时禁用一次连续不健康超过该天数的凭证
+/// （不会删除）。每个被禁用的凭证都写入日志（同时推送给 WebSocket 日志订阅者），并刷新托盘
+/// 中的凭证数量和图标。
+pub(super) async fn run_unhealthy_credential_pruner(
+    app_handle: Option<tauri::AppHandle>,
+    pool_service: Arc<ProviderPoolService>,
+    db: database::DbConnection,
+    logs: LogState,
+) {
+    loop {
+        tokio::time::sleep(UNHEALTHY_PRUNE_INTERVAL).await;
+
+        let (service, conn) = (pool_service.clone(), db.clone());
+        let result = tokio::task::spawn_blocking(move || {
+            service.auto_disable_unhealthy(&conn, chrono::Utc::now())
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+
+        let disabled = match result {
+            Ok(disabled) => disabled,
+            Err(e) => {
+                tracing::error!("[POOL] 自动禁用不健康凭证失败: {}", e);
+                continue;
+            }
+        };
+        if disabled.is_empty() {
+            continue;
+        }
+
+        for cred in &disabled {
+            let since = cred
+                .unhealthy_since
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default();
+            let message = format!(
+                "[POOL] 凭证 {} ({}) 自 {} 起持续不健康，已自动禁用: {}",
+                cred.name.as_deref().unwrap_or(&cred.uuid),
+                cred.provider_type,
+                since,
+                cred.last_error_message.as_deref().unwrap_or("-")
+            );
+            tracing::warn!("{}", message);
+            logs.write().await.add("warn", &message);
+        }
+        update_tray_credential_health(app_handle.as_ref(), &pool_service, &db).await;
+    }
+}
+
+/// 按凭证池当前状态刷新托盘中的凭证数量和图标（headless 模式下没有托盘）
+async fn update_tray_credential_health(
+    app_handle: Option<&tauri::AppHandle>,
+    pool_service: &ProviderPoolService,
+    db: &database::DbConnection,
+) {
+    let Some(tray_state) =
+        app_handle.and_then(|handle| handle.try_state::<TrayManagerState<tauri::Wry>>())
+    else {
+        return;
+    };
+    let credentials: Vec<CredentialHealth> = match pool_service.get_overview(db) {
+        Ok(overview) => overview
+            .iter()
+            .flat_map(|provider| &provider.credentials)
+            .map(|cred| {
+                if cred.is_healthy && !cred.is_disabled && !cred.is_parked {
+                    CredentialHealth::healthy()
+                } else {
+                    CredentialHealth::invalid()
+                }
+            })
+            .collect(),
+        Err(e) => {
+            tracing::error!("[POOL] 获取凭证池信息失败: {}", e);
+            return;
+        }
+    };
+
+    let tray_guard = tray_state.0.read().await;
+    let Some(tray_manager) = tray_guard.as_ref() else {
+        return;
+    };
+    let mut snapshot = tray_manager.get_state().await;
+    snapshot.available_credentials = credentials.iter().filter(|c| c.is_valid).count();
+    snapshot.total_credentials = credentials.len();
+    snapshot.icon_status = calculate_icon_status(snapshot.server_running, &credentials);
+    if let Err(e) = tray_manager.update_state(snapshot).await {
+        tracing::error!("[POOL] 更新托盘凭证状态失败: {}", e);
+    }
+}
+
+/// 更新托盘中的服务器运行状态（headless 模式下没有托盘）
+async fn update_tray_server_status(
+    app_handle: Option<&tauri::AppHandle>,
+    running: bool,
+    server_address: String,
+) {
+    let Some(tray_state) =
+        app_handle.and_then(|handle| handle.try_state::<TrayManagerState<tauri::Wry>>())
+    else {
+        return;
+    };
+    let tray_guard = tray_state.0.read().await;
+    let Some(tray_manager) = tray_guard.as_ref() else {
+        return;
+    };
+    let mut snapshot = tray_manager.get_state().await;
+    snapshot.server_running = running;
+    snapshot.server_address = server_address;
+    if !running {
+        snapshot.icon_status = TrayIconStatus::Stopped;
+    } else if snapshot.icon_status == TrayIconStatus::Stopped {
+        snapshot.icon_status = TrayIconStatus::Running;
+    }
+    if let Err(e) = tray_manager.update_state(snapshot).await {
+        tracing::error!("[WATCHDOG] 更新托盘状态失败: {}", e);
+    }
+}
+
+/// 加载凭证池并启动服务器，成功时返回监听地址
+///
+/// 图形界面和 headless 模式共用
+pub(super) async fn load_pool_and_start_server(
     state: AppState,
     logs: LogState,
     db: database::DbConnection,
@@ -106,8 +417,7 @@ async fn start_server_async(
     shared_logger: Arc<telemetry::RequestLogger>,
     shared_flow_monitor: Arc<crate::flow_monitor::FlowMonitor>,
     flow_interceptor: Arc<FlowInterceptor>,
-    app_handle: tauri::AppHandle,
-) {
+) -> Result<String, String> {
     // 先加载凭证池中的凭证
     {
         logs.write().await.add("info", "[启动] 正在加载凭证池...");
@@ -165,45 +475,68 @@ async fn start_server_async(
     }
 
     // 启动服务器
-    let server_started;
-    let server_address;
+    let mut s = state.write().await;
+    logs.write()
+        .await
+        .add("info", "[启动] 正在自动启动服务器...");
+    match s
+        .start_with_telemetry_and_flow_monitor(
+            logs.clone(),
+            pool_service,
+            token_cache,
+            Some(db),
+            Some(shared_stats),
+            Some(shared_tokens),
+            Some(shared_logger),
+            Some(shared_flow_monitor),
+            Some(flow_interceptor),
+        )
+        .await
     {
-        let mut s = state.write().await;
-        logs.write()
-            .await
-            .add("info", "[启动] 正在自动启动服务器...");
-        match s
-            .start_with_telemetry_and_flow_monitor(
-                logs.clone(),
-                pool_service,
-                token_cache,
-                Some(db),
-                Some(shared_stats),
-                Some(shared_tokens),
-                Some(shared_logger),
-                Some(shared_flow_monitor),
-                Some(flow_interceptor),
-            )
-            .await
-        {
-            Ok(_) => {
-                let host = s.config.server.host.clone();
-                let port = s.config.server.port;
-                logs.write()
-                    .await
-                    .add("info", &format!("[启动] 服务器已启动: {host}:{port}"));
-                server_started = true;
-                server_address = format!("{}:{}", host, port);
-            }
-            Err(e) => {
-                logs.write()
-                    .await
-                    .add("error", &format!("[启动] 服务器启动失败: {e}"));
-                server_started = false;
-                server_address = String::new();
-            }
+        Ok(_) => {
+            let address = format!("{}:{}", s.config.server.host, s.config.server.port);
+            logs.write()
+                .await
+                .add("info", &format!("[启动] 服务器已启动: {address}"));
+            Ok(address)
+        }
+        Err(e) => {
+            let message = format!("[启动] 服务器启动失败: {e}");
+            logs.write().await.add("error", &message);
+            Err(message)
         }
     }
+}
+
+/// 异步启动服务器
+async fn start_server_async(
+    state: AppState,
+    logs: LogState,
+    db: database::DbConnection,
+    pool_service: Arc<ProviderPoolService>,
+    token_cache: Arc<TokenCacheService>,
+    shared_stats: Arc<parking_lot::RwLock<telemetry::StatsAggregator>>,
+    shared_tokens: Arc<parking_lot::RwLock<telemetry::TokenTracker>>,
+    shared_logger: Arc<telemetry::RequestLogger>,
+    shared_flow_monitor: Arc<crate::flow_monitor::FlowMonitor>,
+    flow_interceptor: Arc<FlowInterceptor>,
+    app_handle: tauri::AppHandle,
+) {
+    let result = load_pool_and_start_server(
+        state,
+        logs,
+        db,
+        pool_service,
+        token_cache,
+        shared_stats,
+        shared_tokens,
+        shared_logger,
+        shared_flow_monitor,
+        flow_interceptor,
+    )
+    .await;
+    let server_started = result.is_ok();
+    let server_address = result.unwrap_or_default();
 
     // 更新托盘状态
     if let Some(tray_state) = app_handle.try_state::<TrayManagerState<tauri::Wry>>() {
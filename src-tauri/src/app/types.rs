@@ -62,6 +62,22 @@ impl std::fmt::Display for ProviderType {
     }
 }
 
+impl ProviderType {
+    /// 是否为 OAuth 凭证类型（其余为 API Key 类型）
+    pub fn is_oauth(&self) -> bool {
+        matches!(
+            self,
+            ProviderType::Kiro
+                | ProviderType::Gemini
+                | ProviderType::Qwen
+                | ProviderType::Antigravity
+                | ProviderType::Codex
+                | ProviderType::ClaudeOAuth
+                | ProviderType::IFlow
+        )
+    }
+}
+
 impl std::str::FromStr for ProviderType {
     type Err = String;
 
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 
 /// 拦截器状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +90,103 @@ impl Default for RecoveryConfig {
     }
 }
 
+/// URL 拦截通知的合并与限流配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationThrottleConfig {
+    /// 合并窗口（毫秒）：窗口内的后续拦截合并为一条分组通知，0 表示不合并
+    pub coalesce_window_ms: u64,
+    /// 每分钟最多发送的拦截通知数，0 表示不限制
+    pub max_per_minute: u32,
+}
+
+impl Default for NotificationThrottleConfig {
+    fn default() -> Self {
+        Self {
+            coalesce_window_ms: 3000,
+            max_per_minute: 10,
+        }
+    }
+}
+
+/// URL 规则动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlRuleAction {
+    /// 拦截并加入待处理列表
+    Intercept,
+    /// 放行，交给原默认浏览器打开
+    Ignore,
+    /// 直接在指纹浏览器中打开
+    OpenInFingerprintBrowser,
+}
+
+/// URL 模式类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlPatternKind {
+    /// 通配符（`*` 匹配任意字符，`?` 匹配单个字符）
+    #[default]
+    Glob,
+    /// 正则表达式
+    Regex,
+}
+
+/// URL 拦截规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub kind: UrlPatternKind,
+    pub action: UrlRuleAction,
+    /// 编译后的正则，首次匹配（或 `compile_rules`）时编译后复用
+    #[serde(skip)]
+    compiled: OnceLock<Option<regex::Regex>>,
+}
+
+impl UrlRule {
+    pub fn new(pattern: impl Into<String>, kind: UrlPatternKind, action: UrlRuleAction) -> Self {
+        Self {
+            pattern: pattern.into(),
+            kind,
+            action,
+            compiled: OnceLock::new(),
+        }
+    }
+
+    /// 编译为正则表达式（glob 会被转换并整体锚定）
+    pub fn compile(&self) -> std::result::Result<regex::Regex, String> {
+        let source = match self.kind {
+            UrlPatternKind::Regex => self.pattern.clone(),
+            UrlPatternKind::Glob => {
+                let mut source = String::from("^");
+                for ch in self.pattern.chars() {
+                    match ch {
+                        '*' => source.push_str(".*"),
+                        '?' => source.push('.'),
+                        other => source.push_str(&regex::escape(&other.to_string())),
+                    }
+                }
+                source.push('$');
+                source
+            }
+        };
+        regex::RegexBuilder::new(&source)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("无效的 URL 规则 \"{}\": {}", self.pattern, e))
+    }
+
+    /// 获取缓存的正则，未编译时编译一次（无效模式缓存为 None）
+    fn compiled(&self) -> Option<&regex::Regex> {
+        self.compiled.get_or_init(|| self.compile().ok()).as_ref()
+    }
+
+    /// 检查 URL 是否匹配（无效模式视为不匹配）
+    pub fn matches(&self, url: &str) -> bool {
+        self.compiled().is_some_and(|re| re.is_match(url))
+    }
+}
+
 /// 浏览器拦截器配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrowserInterceptorConfig {
@@ -103,6 +201,12 @@ pub struct BrowserInterceptorConfig {
     pub temporary_disable_timeout: Option<u64>, // 临时禁用超时（秒）
     pub fingerprint_browser: FingerprintBrowserConfig,
     pub recovery: RecoveryConfig,
+    /// URL 规则（按顺序匹配，首个命中的规则生效）
+    #[serde(default)]
+    pub rules: Vec<UrlRule>,
+    /// 拦截通知的合并与限流
+    #[serde(default)]
+    pub notification_throttle: NotificationThrottleConfig,
 }
 
 impl Default for BrowserInterceptorConfig {
@@ -145,6 +249,8 @@ impl Default for BrowserInterceptorConfig {
             temporary_disable_timeout: Some(300), // 5分钟
             fingerprint_browser: FingerprintBrowserConfig::default(),
             recovery: RecoveryConfig::default(),
+            rules: Vec::new(),
+            notification_throttle: NotificationThrottleConfig::default(),
         }
     }
 }
@@ -191,6 +297,27 @@ impl BrowserInterceptorConfig {
         })
     }
 
+    /// 预编译全部 URL 规则（加载配置时调用，匹配时直接复用）
+    pub fn compile_rules(&self) {
+        for rule in &self.rules {
+            rule.compiled();
+        }
+    }
+
+    /// 根据规则决定 URL 的处理方式
+    ///
+    /// 未配置规则时全部拦截；配置了规则但均未命中时放行。
+    pub fn url_action(&self, url: &str) -> UrlRuleAction {
+        if self.rules.is_empty() {
+            return UrlRuleAction::Intercept;
+        }
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(url))
+            .map(|rule| rule.action)
+            .unwrap_or(UrlRuleAction::Ignore)
+    }
+
     /// 验证配置的有效性
     pub fn validate(&self) -> std::result::Result<(), String> {
         if self.target_processes.is_empty() {
@@ -211,6 +338,21 @@ impl BrowserInterceptorConfig {
             }
         }
 
+        for rule in &self.rules {
+            if rule.pattern.trim().is_empty() {
+                return Err("URL 规则的模式不能为空".to_string());
+            }
+            rule.compile()?;
+            if rule.action == UrlRuleAction::OpenInFingerprintBrowser
+                && !self.fingerprint_browser.enabled
+            {
+                return Err(format!(
+                    "URL 规则 \"{}\" 使用指纹浏览器打开，但指纹浏览器未启用",
+                    rule.pattern
+                ));
+            }
+        }
+
         Ok(())
     }
 }
@@ -261,4 +403,145 @@ mod tests {
         config.fingerprint_browser.executable_path = String::new();
         assert!(config.validate().is_err());
     }
+
+    fn rule(pattern: &str, kind: UrlPatternKind, action: UrlRuleAction) -> UrlRule {
+        UrlRule::new(pattern, kind, action)
+    }
+
+    #[test]
+    fn test_compile_rules_caches_regex() {
+        let mut config = BrowserInterceptorConfig::default();
+        config.rules = vec![
+            rule(
+                "https://*.example.com/*",
+                UrlPatternKind::Glob,
+                UrlRuleAction::Ignore,
+            ),
+            rule("(", UrlPatternKind::Regex, UrlRuleAction::Intercept),
+        ];
+        config.compile_rules();
+
+        assert!(config.rules[0].compiled.get().unwrap().is_some());
+        // 无效模式也只编译一次，缓存为不匹配
+        assert!(config.rules[1].compiled.get().unwrap().is_none());
+        assert!(!config.rules[1].matches("("));
+
+        // 克隆后复用已编译的正则
+        let cloned = config.clone();
+        assert!(cloned.rules[0].compiled.get().is_some());
+        assert!(cloned.rules[0].matches("https://a.example.com/x"));
+    }
+
+    #[test]
+    fn test_url_action_without_rules_intercepts_everything() {
+        let config = BrowserInterceptorConfig::default();
+        assert_eq!(
+            config.url_action("https://example.com/anything"),
+            UrlRuleAction::Intercept
+        );
+    }
+
+    #[test]
+    fn test_url_action_first_matching_rule_wins() {
+        let mut config = BrowserInterceptorConfig::default();
+        config.rules = vec![
+            rule(
+                "*signin.aws.amazon.com*",
+                UrlPatternKind::Glob,
+                UrlRuleAction::Ignore,
+            ),
+            rule(
+                "*.amazonaws.com/oauth*",
+                UrlPatternKind::Glob,
+                UrlRuleAction::Intercept,
+            ),
+            rule(
+                r"^https://github\.com/login/.*",
+                UrlPatternKind::Regex,
+                UrlRuleAction::OpenInFingerprintBrowser,
+            ),
+        ];
+
+        assert_eq!(
+            config.url_action("https://oidc.us-east-1.amazonaws.com/oauth/authorize?x=1"),
+            UrlRuleAction::Intercept
+        );
+        assert_eq!(
+            config.url_action("https://github.com/login/oauth/authorize"),
+            UrlRuleAction::OpenInFingerprintBrowser
+        );
+        assert_eq!(
+            config.url_action("https://signin.aws.amazon.com/oauth"),
+            UrlRuleAction::Ignore
+        );
+        // 未命中任何规则时放行
+        assert_eq!(
+            config.url_action("https://example.com/normal-page"),
+            UrlRuleAction::Ignore
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_rules() {
+        let mut config = BrowserInterceptorConfig::default();
+        config.rules = vec![rule(
+            "(unclosed",
+            UrlPatternKind::Regex,
+            UrlRuleAction::Intercept,
+        )];
+        assert!(config.validate().is_err());
+
+        config.rules = vec![rule("  ", UrlPatternKind::Glob, UrlRuleAction::Intercept)];
+        assert!(config.validate().is_err());
+
+        config.rules = vec![rule(
+            "https://*",
+            UrlPatternKind::Glob,
+            UrlRuleAction::OpenInFingerprintBrowser,
+        )];
+        assert!(config.validate().is_err());
+
+        config.fingerprint_browser.enabled = true;
+        config.fingerprint_browser.executable_path = "/usr/bin/browser".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_url_rule_glob_semantics() {
+        let glob = |pattern: &str| rule(pattern, UrlPatternKind::Glob, UrlRuleAction::Intercept);
+
+        // glob 整体锚定，不做子串匹配
+        assert!(glob("https://example.com/*").matches("https://example.com/login"));
+        assert!(!glob("example.com/*").matches("https://example.com/login"));
+        // `?` 只匹配单个字符
+        assert!(glob("https://a?.example.com/").matches("https://a1.example.com/"));
+        assert!(!glob("https://a?.example.com/").matches("https://a12.example.com/"));
+        // `.` 等正则元字符按字面匹配
+        assert!(!glob("https://example.com/").matches("https://exampleXcom/"));
+        assert!(glob("https://example.com/a+b").matches("https://example.com/a+b"));
+        // 大小写不敏感
+        assert!(glob("https://EXAMPLE.com/*").matches("https://example.COM/Login"));
+    }
+
+    #[test]
+    fn test_url_rule_regex_and_invalid_patterns() {
+        let regex = |pattern: &str| rule(pattern, UrlPatternKind::Regex, UrlRuleAction::Intercept);
+
+        // 正则不自动锚定
+        assert!(regex(r"github\.com/login").matches("https://github.com/login/oauth"));
+        assert!(regex("GITHUB").matches("https://github.com/"));
+        // 无效模式视为不匹配
+        assert!(!regex("(unclosed").matches("(unclosed"));
+    }
+
+    #[test]
+    fn test_url_rule_kind_defaults_to_glob() {
+        let rule: UrlRule = serde_json::from_str(
+            r#"{"pattern":"https://*","action":"open_in_fingerprint_browser"}"#,
+        )
+        .unwrap();
+        assert_eq!(rule.kind, UrlPatternKind::Glob);
+        assert_eq!(rule.action, UrlRuleAction::OpenInFingerprintBrowser);
+        assert!(rule.matches("https://example.com/"));
+    }
 }
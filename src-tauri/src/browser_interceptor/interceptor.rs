@@ -1,10 +1,63 @@
 use crate::browser_interceptor::{
-    BrowserInterceptorConfig, BrowserInterceptorError, InterceptedUrl, NotificationService, Result,
-    StateManager, UrlManager,
+    BrowserInterceptorConfig, BrowserInterceptorError, InterceptedUrl, NotificationMessage,
+    NotificationService, Result, StateManager, UrlManager, UrlRuleAction,
 };
-use std::sync::Arc;
+use once_cell::sync::Lazy;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 
+/// deep-link 处理使用的拦截器配置（URL 规则、指纹浏览器）
+static DEEP_LINK_CONFIG: Lazy<Mutex<Option<BrowserInterceptorConfig>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// 更新 deep-link 处理使用的配置（拦截器启动和配置更新时调用）
+pub fn set_deep_link_config(config: BrowserInterceptorConfig) {
+    config.compile_rules();
+    if let Ok(mut current) = DEEP_LINK_CONFIG.lock() {
+        *current = Some(config);
+    }
+}
+
+/// 按 URL 规则分流 deep-link 收到的 URL
+///
+/// 放行的 URL 交给 `passthrough` 打开，指纹浏览器规则直接启动指纹浏览器。
+/// 返回 true 表示仍需拦截（包括指纹浏览器未启用或启动失败）。
+pub fn route_deep_link_url(url: &str, passthrough: impl FnOnce(&str)) -> bool {
+    // 在锁内直接用已编译的规则匹配，只在需要时克隆指纹浏览器配置
+    let (action, fingerprint_browser) = match DEEP_LINK_CONFIG.lock() {
+        Ok(guard) => match guard.as_ref() {
+            Some(config) => {
+                let action = config.url_action(url);
+                let fingerprint_browser = (action == UrlRuleAction::OpenInFingerprintBrowser)
+                    .then(|| config.fingerprint_browser.clone());
+                (action, fingerprint_browser)
+            }
+            None => (UrlRuleAction::Intercept, None),
+        },
+        Err(_) => (UrlRuleAction::Intercept, None),
+    };
+
+    match action {
+        UrlRuleAction::Intercept => true,
+        UrlRuleAction::Ignore => {
+            tracing::debug!("URL 未命中拦截规则，放行: {}", url);
+            passthrough(url);
+            false
+        }
+        UrlRuleAction::OpenInFingerprintBrowser => {
+            if let Some(fingerprint_browser) = fingerprint_browser.filter(|c| c.enabled) {
+                match BrowserInterceptor::launch_fingerprint_browser(&fingerprint_browser, url) {
+                    Ok(()) => return false,
+                    Err(e) => tracing::error!("指纹浏览器打开失败，改为拦截: {}", e),
+                }
+            } else {
+                tracing::warn!("指纹浏览器未启用，改为拦截: {}", url);
+            }
+            true
+        }
+    }
+}
+
 /// 浏览器拦截器主结构
 pub struct BrowserInterceptor {
     config: Arc<RwLock<BrowserInterceptorConfig>>,
@@ -22,11 +75,15 @@ pub struct BrowserInterceptor {
 impl BrowserInterceptor {
     /// 创建新的浏览器拦截器实例
     pub fn new(config: BrowserInterceptorConfig) -> Self {
+        let notification_service = NotificationService::new();
+        notification_service.set_throttle_config(config.notification_throttle.clone());
+        config.compile_rules();
+
         Self {
             config: Arc::new(RwLock::new(config)),
             state_manager: Arc::new(StateManager::new()),
             url_manager: Arc::new(UrlManager::new()),
-            notification_service: Arc::new(RwLock::new(NotificationService::new())),
+            notification_service: Arc::new(RwLock::new(notification_service)),
             #[cfg(target_os = "windows")]
             windows_interceptor: None,
             #[cfg(target_os = "macos")]
@@ -169,6 +226,23 @@ impl BrowserInterceptor {
         Ok(true) // 已拦截
     }
 
+    /// 发送 URL 拦截通知（受合并与限流配置约束）
+    pub async fn notify_url_intercepted(&self, url: String, source_process: String) -> Result<()> {
+        let intercepted_url = InterceptedUrl::new(url, source_process);
+        let notification_service = self.notification_service.read().await;
+        notification_service
+            .notify_url_intercepted(&intercepted_url)
+            .await
+    }
+
+    /// 获取最近发送的通知
+    pub async fn get_recent_notifications(&self) -> Vec<NotificationMessage> {
+        self.notification_service
+            .read()
+            .await
+            .recent_notifications()
+    }
+
     /// 获取当前状态
     pub async fn get_state(&self) -> Result<crate::browser_interceptor::InterceptorState> {
         self.state_manager.get_state()
@@ -205,8 +279,7 @@ impl BrowserInterceptor {
         }
 
         if let Some(intercepted_url) = self.url_manager.get_intercepted_url(url_id)? {
-            self.launch_fingerprint_browser(&config.fingerprint_browser, &intercepted_url.url)
-                .await?;
+            Self::launch_fingerprint_browser(&config.fingerprint_browser, &intercepted_url.url)?;
             self.url_manager.mark_as_opened(url_id)?;
             tracing::info!("URL {} 已在指纹浏览器中打开", url_id);
         }
@@ -228,6 +301,14 @@ impl BrowserInterceptor {
             .validate()
             .map_err(|e| BrowserInterceptorError::ConfigError(format!("配置验证失败: {}", e)))?;
 
+        new_config.compile_rules();
+        set_deep_link_config(new_config.clone());
+
+        self.notification_service
+            .read()
+            .await
+            .set_throttle_config(new_config.notification_throttle.clone());
+
         let mut config = self.config.write().await;
         *config = new_config;
 
@@ -252,6 +333,11 @@ impl BrowserInterceptor {
             return false;
         }
 
+        // 规则放行的 URL 不拦截
+        if config.url_action(url) == UrlRuleAction::Ignore {
+            return false;
+        }
+
         // 检查 URL 是否匹配模式
         config.matches_url_pattern(url)
     }
@@ -280,6 +366,8 @@ impl BrowserInterceptor {
                 });
             };
 
+            set_deep_link_config(self.config.read().await.clone());
+
             let mut interceptor =
                 crate::browser_interceptor::platform::windows::WindowsInterceptor::new(url_handler);
             interceptor.start().await?;
@@ -307,6 +395,8 @@ impl BrowserInterceptor {
                 });
             };
 
+            set_deep_link_config(self.config.read().await.clone());
+
             let mut interceptor =
                 crate::browser_interceptor::platform::macos::MacOSInterceptor::new(url_handler);
             interceptor.start().await?;
@@ -334,6 +424,8 @@ impl BrowserInterceptor {
                 });
             };
 
+            set_deep_link_config(self.config.read().await.clone());
+
             let mut interceptor =
                 crate::browser_interceptor::platform::linux::LinuxInterceptor::new(url_handler);
             interceptor.start().await?;
@@ -390,8 +482,7 @@ impl BrowserInterceptor {
     }
 
     /// 启动指纹浏览器
-    async fn launch_fingerprint_browser(
-        &self,
+    pub(crate) fn launch_fingerprint_browser(
         browser_config: &crate::browser_interceptor::config::FingerprintBrowserConfig,
         url: &str,
     ) -> Result<()> {
@@ -422,20 +513,18 @@ impl BrowserInterceptor {
         // 异步启动进程
         match command.spawn() {
             Ok(mut child) => {
-                // 在后台等待进程完成
-                tokio::spawn(async move {
-                    match child.wait() {
-                        Ok(status) => {
-                            if status.success() {
-                                tracing::info!("指纹浏览器启动成功");
-                            } else {
-                                tracing::error!("指纹浏览器退出异常: {}", status);
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("等待指纹浏览器进程失败: {}", e);
+                // 在后台线程等待进程完成（deep-link 回调中可能没有 tokio 运行时）
+                std::thread::spawn(move || match child.wait() {
+                    Ok(status) => {
+                        if status.success() {
+                            tracing::info!("指纹浏览器启动成功");
+                        } else {
+                            tracing::error!("指纹浏览器退出异常: {}", status);
                         }
                     }
+                    Err(e) => {
+                        tracing::error!("等待指纹浏览器进程失败: {}", e);
+                    }
                 });
 
                 tracing::info!(
@@ -458,3 +547,48 @@ impl Default for BrowserInterceptor {
         Self::new(BrowserInterceptorConfig::default())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_interceptor::{UrlPatternKind, UrlRule};
+    use std::cell::Cell;
+
+    #[test]
+    fn test_route_deep_link_url_follows_rules() {
+        let rule = |pattern: &str, action| UrlRule::new(pattern, UrlPatternKind::Glob, action);
+        let mut config = BrowserInterceptorConfig::default();
+        config.rules = vec![
+            rule("https://ignored.example.com/*", UrlRuleAction::Ignore),
+            rule(
+                "https://fingerprint.example.com/*",
+                UrlRuleAction::OpenInFingerprintBrowser,
+            ),
+            rule("https://*", UrlRuleAction::Intercept),
+        ];
+        set_deep_link_config(config);
+
+        let opened = Cell::new(None);
+        let passthrough = |url: &str| opened.set(Some(url.to_string()));
+
+        assert!(!route_deep_link_url(
+            "https://ignored.example.com/page",
+            passthrough
+        ));
+        assert_eq!(
+            opened.take().as_deref(),
+            Some("https://ignored.example.com/page")
+        );
+
+        assert!(route_deep_link_url(
+            "https://auth.example.com/login",
+            passthrough
+        ));
+        // 指纹浏览器未启用时回退为拦截
+        assert!(route_deep_link_url(
+            "https://fingerprint.example.com/login",
+            passthrough
+        ));
+        assert_eq!(opened.take(), None);
+    }
+}
@@ -20,9 +20,12 @@ pub mod platform {
 }
 
 // 重新导出主要类型和函数
-pub use config::{BrowserInterceptorConfig, InterceptedUrl, InterceptorState};
+pub use config::{
+    BrowserInterceptorConfig, InterceptedUrl, InterceptorState, UrlPatternKind, UrlRule,
+    UrlRuleAction,
+};
 pub use interceptor::BrowserInterceptor;
-pub use notification_service::NotificationService;
+pub use notification_service::{NotificationMessage, NotificationService};
 pub use state_manager::StateManager;
 pub use url_manager::{UrlManager, UrlStatistics};
 
@@ -1,6 +1,11 @@
-use crate::browser_interceptor::{InterceptedUrl, Result};
+use crate::browser_interceptor::{InterceptedUrl, NotificationThrottleConfig, Result};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 保留的最近通知数量
+const RECENT_NOTIFICATIONS_LIMIT: usize = 20;
 
 /// 通知类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +28,9 @@ pub struct NotificationMessage {
     pub source_process: Option<String>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub auto_dismiss_after: Option<Duration>,
+    /// 分组通知中合并的 URL（用于展开查看）
+    #[serde(default)]
+    pub grouped_urls: Vec<String>,
 }
 
 impl NotificationMessage {
@@ -36,6 +44,7 @@ impl NotificationMessage {
             source_process: None,
             timestamp: chrono::Utc::now(),
             auto_dismiss_after: Some(Duration::from_secs(30)),
+            grouped_urls: Vec::new(),
         }
     }
 
@@ -53,12 +62,99 @@ impl NotificationMessage {
         self.auto_dismiss_after = duration;
         self
     }
+
+    pub fn with_grouped_urls(mut self, urls: Vec<String>) -> Self {
+        self.grouped_urls = urls;
+        self
+    }
+}
+
+/// 限流判定结果
+#[derive(Debug, PartialEq)]
+enum ThrottleDecision {
+    /// 立即发送单条通知
+    SendNow,
+    /// 已并入当前合并窗口；需要时返回距离窗口结束的时间，用于安排分组通知
+    Deferred(Option<Duration>),
+    /// 超出每分钟上限，丢弃
+    Suppressed,
+}
+
+/// URL 拦截通知的合并与限流状态
+///
+/// 窗口内的首个拦截立即通知，其余拦截在窗口结束时合并为一条分组通知。
+#[derive(Debug, Default)]
+struct NotificationThrottle {
+    config: NotificationThrottleConfig,
+    window_start: Option<Instant>,
+    pending: Vec<InterceptedUrl>,
+    flush_scheduled: bool,
+    sent: VecDeque<Instant>,
+    suppressed: u64,
+}
+
+impl NotificationThrottle {
+    fn on_intercept(&mut self, url: &InterceptedUrl, now: Instant) -> ThrottleDecision {
+        let window = Duration::from_millis(self.config.coalesce_window_ms);
+        let elapsed = self.window_start.map(|start| now.duration_since(start));
+
+        if let Some(elapsed) = elapsed.filter(|e| !window.is_zero() && *e < window) {
+            self.pending.push(url.clone());
+            if self.flush_scheduled {
+                return ThrottleDecision::Deferred(None);
+            }
+            self.flush_scheduled = true;
+            return ThrottleDecision::Deferred(Some(window - elapsed));
+        }
+
+        self.window_start = Some(now);
+        if self.try_acquire(now) {
+            ThrottleDecision::SendNow
+        } else {
+            ThrottleDecision::Suppressed
+        }
+    }
+
+    /// 取出窗口内累积的 URL（超出上限时丢弃）
+    fn take_pending(&mut self, now: Instant) -> Option<Vec<InterceptedUrl>> {
+        self.flush_scheduled = false;
+        if self.pending.is_empty() {
+            return None;
+        }
+        let urls = std::mem::take(&mut self.pending);
+        if self.try_acquire(now) {
+            Some(urls)
+        } else {
+            self.suppressed += urls.len() as u64 - 1;
+            None
+        }
+    }
+
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        while let Some(&sent_at) = self.sent.front() {
+            if now.duration_since(sent_at) >= Duration::from_secs(60) {
+                self.sent.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let max = self.config.max_per_minute as usize;
+        if max > 0 && self.sent.len() >= max {
+            self.suppressed += 1;
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
 }
 
 /// 通知服务
 pub struct NotificationService {
     enabled: bool,
     show_url_preview: bool,
+    throttle: Arc<Mutex<NotificationThrottle>>,
+    recent: Arc<Mutex<VecDeque<NotificationMessage>>>,
 }
 
 impl NotificationService {
@@ -66,9 +162,26 @@ impl NotificationService {
         Self {
             enabled: true,
             show_url_preview: true,
+            throttle: Arc::new(Mutex::new(NotificationThrottle::default())),
+            recent: Arc::new(Mutex::new(VecDeque::new())),
         }
     }
 
+    /// 设置拦截通知的合并与限流配置
+    pub fn set_throttle_config(&self, config: NotificationThrottleConfig) {
+        if let Ok(mut throttle) = self.throttle.lock() {
+            throttle.config = config;
+        }
+    }
+
+    /// 获取最近发送的通知（包括分组通知的 URL 列表）
+    pub fn recent_notifications(&self) -> Vec<NotificationMessage> {
+        self.recent
+            .lock()
+            .map(|recent| recent.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
     /// 设置通知是否启用
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
@@ -85,6 +198,23 @@ impl NotificationService {
             return Ok(());
         }
 
+        let decision = match self.throttle.lock() {
+            Ok(mut throttle) => throttle.on_intercept(intercepted_url, Instant::now()),
+            Err(_) => ThrottleDecision::SendNow,
+        };
+        match decision {
+            ThrottleDecision::SendNow => {}
+            ThrottleDecision::Deferred(Some(delay)) => {
+                self.schedule_group_flush(delay);
+                return Ok(());
+            }
+            ThrottleDecision::Deferred(None) => return Ok(()),
+            ThrottleDecision::Suppressed => {
+                tracing::debug!("拦截通知超出每分钟上限，已丢弃: {}", intercepted_url.url);
+                return Ok(());
+            }
+        }
+
         let title = format!("已拦截来自 {} 的 URL", intercepted_url.source_process);
         let message = if self.show_url_preview {
             format!("URL: {}", self.truncate_url(&intercepted_url.url, 100))
@@ -100,6 +230,35 @@ impl NotificationService {
         self.send_notification(notification).await
     }
 
+    /// 合并窗口结束时发送分组通知
+    fn schedule_group_flush(&self, delay: Duration) {
+        let throttle = Arc::clone(&self.throttle);
+        let recent = Arc::clone(&self.recent);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            let urls = match throttle.lock() {
+                Ok(mut throttle) => throttle.take_pending(Instant::now()),
+                Err(_) => None,
+            };
+            let Some(urls) = urls else {
+                return;
+            };
+
+            let notification = NotificationMessage::new(
+                NotificationType::UrlIntercepted,
+                format!("已拦截 {} 个 URL", urls.len()),
+                "点击展开查看全部".to_string(),
+            )
+            .with_grouped_urls(urls.into_iter().map(|u| u.url).collect());
+
+            if let Err(e) = Self::dispatch(&recent, notification).await {
+                tracing::error!("发送分组通知失败: {}", e);
+            }
+        });
+    }
+
     /// 发送拦截器启用通知
     pub async fn notify_interceptor_enabled(&self) -> Result<()> {
         if !self.enabled {
@@ -163,6 +322,14 @@ impl NotificationService {
 
     /// 发送通知的具体实现
     async fn send_notification(&self, notification: NotificationMessage) -> Result<()> {
+        Self::dispatch(&self.recent, notification).await
+    }
+
+    /// 记录并发送通知
+    async fn dispatch(
+        recent: &Mutex<VecDeque<NotificationMessage>>,
+        notification: NotificationMessage,
+    ) -> Result<()> {
         // 记录日志
         tracing::info!(
             "发送通知: {} - {}",
@@ -171,26 +338,33 @@ impl NotificationService {
         );
 
         // 发送系统通知
-        self.send_system_notification(&notification).await?;
+        Self::send_system_notification(&notification).await?;
+
+        if let Ok(mut recent) = recent.lock() {
+            recent.push_back(notification);
+            while recent.len() > RECENT_NOTIFICATIONS_LIMIT {
+                recent.pop_front();
+            }
+        }
 
         Ok(())
     }
 
     /// 发送系统通知
-    async fn send_system_notification(&self, notification: &NotificationMessage) -> Result<()> {
+    async fn send_system_notification(notification: &NotificationMessage) -> Result<()> {
         #[cfg(target_os = "windows")]
         {
-            self.send_windows_notification(notification).await?;
+            Self::send_windows_notification(notification).await?;
         }
 
         #[cfg(target_os = "macos")]
         {
-            self.send_macos_notification(notification).await?;
+            Self::send_macos_notification(notification).await?;
         }
 
         #[cfg(target_os = "linux")]
         {
-            self.send_linux_notification(notification).await?;
+            Self::send_linux_notification(notification).await?;
         }
 
         Ok(())
@@ -198,21 +372,21 @@ impl NotificationService {
 
     /// Windows 系统通知
     #[cfg(target_os = "windows")]
-    async fn send_windows_notification(&self, notification: &NotificationMessage) -> Result<()> {
+    async fn send_windows_notification(notification: &NotificationMessage) -> Result<()> {
         tracing::debug!("Windows 通知: {}", notification.title);
         Ok(())
     }
 
     /// macOS 系统通知
     #[cfg(target_os = "macos")]
-    async fn send_macos_notification(&self, notification: &NotificationMessage) -> Result<()> {
+    async fn send_macos_notification(notification: &NotificationMessage) -> Result<()> {
         tracing::debug!("macOS 通知: {}", notification.title);
         Ok(())
     }
 
     /// Linux 系统通知
     #[cfg(target_os = "linux")]
-    async fn send_linux_notification(&self, notification: &NotificationMessage) -> Result<()> {
+    async fn send_linux_notification(notification: &NotificationMessage) -> Result<()> {
         tracing::debug!("Linux 通知: {}", notification.title);
         Ok(())
     }
@@ -232,3 +406,93 @@ impl Default for NotificationService {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle(coalesce_window_ms: u64, max_per_minute: u32) -> NotificationThrottle {
+        NotificationThrottle {
+            config: NotificationThrottleConfig {
+                coalesce_window_ms,
+                max_per_minute,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn url(n: usize) -> InterceptedUrl {
+        InterceptedUrl::new(
+            format!("https://auth.example.com/{}", n),
+            "Kiro".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_intercepts_within_window_are_grouped() {
+        let mut throttle = throttle(3000, 0);
+        let start = Instant::now();
+
+        assert_eq!(
+            throttle.on_intercept(&url(1), start),
+            ThrottleDecision::SendNow
+        );
+        assert_eq!(
+            throttle.on_intercept(&url(2), start + Duration::from_millis(500)),
+            ThrottleDecision::Deferred(Some(Duration::from_millis(2500)))
+        );
+        assert_eq!(
+            throttle.on_intercept(&url(3), start + Duration::from_millis(1000)),
+            ThrottleDecision::Deferred(None)
+        );
+
+        let grouped = throttle
+            .take_pending(start + Duration::from_millis(3000))
+            .unwrap();
+        assert_eq!(grouped.len(), 2);
+
+        // 窗口结束后的拦截重新开始计时
+        assert_eq!(
+            throttle.on_intercept(&url(4), start + Duration::from_millis(3500)),
+            ThrottleDecision::SendNow
+        );
+    }
+
+    #[test]
+    fn test_zero_window_disables_coalescing() {
+        let mut throttle = throttle(0, 0);
+        let now = Instant::now();
+
+        assert_eq!(
+            throttle.on_intercept(&url(1), now),
+            ThrottleDecision::SendNow
+        );
+        assert_eq!(
+            throttle.on_intercept(&url(2), now),
+            ThrottleDecision::SendNow
+        );
+    }
+
+    #[test]
+    fn test_per_minute_cap() {
+        let mut throttle = throttle(0, 2);
+        let start = Instant::now();
+
+        assert_eq!(
+            throttle.on_intercept(&url(1), start),
+            ThrottleDecision::SendNow
+        );
+        assert_eq!(
+            throttle.on_intercept(&url(2), start),
+            ThrottleDecision::SendNow
+        );
+        assert_eq!(
+            throttle.on_intercept(&url(3), start),
+            ThrottleDecision::Suppressed
+        );
+        assert_eq!(
+            throttle.on_intercept(&url(4), start + Duration::from_secs(61)),
+            ThrottleDecision::SendNow
+        );
+    }
+}
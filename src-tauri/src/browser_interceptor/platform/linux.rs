@@ -1,24 +1,81 @@
-use crate::browser_interceptor::{InterceptedUrl, Result};
+#![allow(dead_code)]
 
-/// Linux 平台的浏览器拦截器
+use crate::browser_interceptor::interceptor::route_deep_link_url;
+use crate::browser_interceptor::{BrowserInterceptorError, InterceptedUrl, Result};
+use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// 注册为默认浏览器时使用的 desktop 文件名
+const DESKTOP_FILE_NAME: &str = "proxycast-browser-interceptor.desktop";
+
+/// 全局 URL sender，用于从单实例回调接收 URL（使用 Mutex 保证线程安全）
+static GLOBAL_URL_SENDER: Lazy<Mutex<Option<mpsc::UnboundedSender<InterceptedUrl>>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// Linux 平台的浏览器拦截器（基于 xdg-settings 设置默认浏览器 + 单实例转发 URL）
 pub struct LinuxInterceptor {
     running: bool,
+    url_sender: Option<mpsc::UnboundedSender<InterceptedUrl>>,
+    original_default_browser: Option<String>,
+    url_handler: Option<Arc<dyn Fn(InterceptedUrl) + Send + Sync + 'static>>,
 }
 
 impl LinuxInterceptor {
-    pub fn new<F>(_url_handler: F) -> Self
+    pub fn new<F>(url_handler: F) -> Self
     where
         F: Fn(InterceptedUrl) + Send + Sync + 'static,
     {
-        Self { running: false }
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let handler = Arc::new(url_handler);
+        let handler_clone = handler.clone();
+
+        // 启动后台任务处理拦截的 URL
+        tokio::spawn(async move {
+            while let Some(intercepted_url) = rx.recv().await {
+                handler_clone(intercepted_url);
+            }
+        });
+
+        Self {
+            running: false,
+            url_sender: Some(tx),
+            original_default_browser: None,
+            url_handler: Some(handler),
+        }
     }
 
     /// 启动拦截
     pub async fn start(&mut self) -> Result<()> {
-        // TODO: 实现 Linux 平台的浏览器拦截
-        // 可以使用 xdg-open 拦截或 D-Bus 监听
+        if self.running {
+            tracing::info!("Linux 拦截器已在运行中，跳过启动");
+            return Ok(());
+        }
+
+        tracing::info!("正在启动 Linux 浏览器拦截器...");
+
+        // 1. 保存当前默认浏览器（同时持久化，崩溃后也能恢复）
+        self.original_default_browser = get_default_browser();
+        tracing::info!("当前默认浏览器: {:?}", self.original_default_browser);
+        if let Some(ref browser) = self.original_default_browser {
+            save_browser_backup(browser);
+        }
+
+        // 2. 设置全局 URL sender
+        if let Some(ref sender) = self.url_sender {
+            if let Ok(mut global_sender) = GLOBAL_URL_SENDER.lock() {
+                *global_sender = Some(sender.clone());
+            }
+        }
+
+        // 3. 注册 desktop 文件并设置为默认浏览器
+        install_desktop_file()?;
+        set_default_browser(DESKTOP_FILE_NAME)?;
+
         self.running = true;
-        tracing::info!("Linux 浏览器拦截器已启动 (占位符)");
+        tracing::info!("Linux 浏览器拦截器已启动");
         Ok(())
     }
 
@@ -28,11 +85,41 @@ impl LinuxInterceptor {
             return Ok(());
         }
 
+        tracing::info!("正在停止 Linux 浏览器拦截器...");
+
+        // 清除全局 URL sender
+        if let Ok(mut global_sender) = GLOBAL_URL_SENDER.lock() {
+            *global_sender = None;
+        }
+
+        self.restore_default_browser()?;
+
         self.running = false;
         tracing::info!("Linux 浏览器拦截器已停止");
         Ok(())
     }
 
+    /// 恢复原来的默认浏览器
+    fn restore_default_browser(&self) -> Result<()> {
+        let browser = self
+            .original_default_browser
+            .clone()
+            .filter(|b| b != DESKTOP_FILE_NAME)
+            .or_else(load_browser_backup);
+
+        match browser {
+            Some(browser) => {
+                tracing::info!("正在恢复默认浏览器为: {}", browser);
+                set_default_browser(&browser)?;
+            }
+            None => tracing::warn!("没有记录原始默认浏览器，跳过恢复"),
+        }
+
+        remove_desktop_file();
+        clear_browser_backup();
+        Ok(())
+    }
+
     /// 检查是否正在拦截
     pub fn is_running(&self) -> bool {
         self.running
@@ -40,18 +127,33 @@ impl LinuxInterceptor {
 
     /// 恢复系统默认设置
     pub async fn restore_system_defaults(&self) -> Result<()> {
+        self.restore_default_browser()?;
         tracing::info!("Linux 系统默认设置已恢复");
         Ok(())
     }
 
     /// 临时禁用拦截
     pub async fn temporarily_disable(&mut self) -> Result<()> {
+        if !self.running {
+            return Ok(());
+        }
+
+        if let Some(ref browser) = self.original_default_browser {
+            set_default_browser(browser)?;
+        }
         tracing::info!("Linux 拦截器已临时禁用");
         Ok(())
     }
 
     /// 重新启用拦截
     pub async fn re_enable(&mut self) -> Result<()> {
+        if !self.running {
+            return Err(BrowserInterceptorError::InterceptorError(
+                "拦截器未运行，无法重新启用".to_string(),
+            ));
+        }
+
+        set_default_browser(DESKTOP_FILE_NAME)?;
         tracing::info!("Linux 拦截器已重新启用");
         Ok(())
     }
@@ -60,12 +162,297 @@ impl LinuxInterceptor {
 impl Drop for LinuxInterceptor {
     fn drop(&mut self) {
         if self.running {
-            let _ = tokio::runtime::Handle::try_current().map(|handle| {
-                handle.block_on(async {
-                    let _ = self.stop().await;
-                    let _ = self.restore_system_defaults().await;
-                })
-            });
+            if let Ok(mut global_sender) = GLOBAL_URL_SENDER.lock() {
+                *global_sender = None;
+            }
+            let _ = self.restore_default_browser();
+            tracing::info!("Linux 浏览器拦截器资源已清理");
+        }
+    }
+}
+
+/// 处理由单实例插件转发的 URL（本应用作为默认浏览器被调用时）
+pub fn handle_deep_link_url(url: String) {
+    tracing::info!("收到 deep-link URL: {}", url);
+
+    // 检查是否是 http/https URL
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        tracing::debug!("忽略非 HTTP URL: {}", url);
+        return;
+    }
+
+    let sender = GLOBAL_URL_SENDER.lock().ok().and_then(|s| s.clone());
+    let Some(sender) = sender else {
+        // 拦截器未运行（例如异常退出后未恢复默认浏览器），直接放行
+        tracing::warn!("拦截器未运行，放行 URL");
+        open_in_passthrough_browser(&url);
+        return;
+    };
+
+    if !route_deep_link_url(&url, open_in_passthrough_browser) {
+        return;
+    }
+
+    let intercepted_url = InterceptedUrl::new(url, "Unknown App".to_string());
+    match sender.send(intercepted_url) {
+        Ok(_) => tracing::debug!("URL 已发送到处理器"),
+        Err(e) => tracing::error!("发送 URL 到处理器失败: {:?}", e),
+    }
+}
+
+/// 检查拦截器是否有活跃的 URL sender
+pub fn is_interceptor_active() -> bool {
+    GLOBAL_URL_SENDER
+        .lock()
+        .map(|sender| sender.is_some())
+        .unwrap_or(false)
+}
+
+/// 从持久化的备份恢复默认浏览器（拦截器未运行时也可调用，用于异常退出后的清理）
+pub fn restore_default_browser_from_backup() -> Result<bool> {
+    let current = get_default_browser();
+    let registered = current.as_deref() == Some(DESKTOP_FILE_NAME);
+
+    let restored = match load_browser_backup() {
+        Some(browser) if registered => {
+            set_default_browser(&browser)?;
+            tracing::info!("已从备份恢复默认浏览器为: {}", browser);
+            true
         }
+        _ => false,
+    };
+
+    remove_desktop_file();
+    clear_browser_backup();
+    Ok(restored)
+}
+
+/// 用备份记录的原默认浏览器（desktop 文件）打开放行的 URL
+fn open_in_passthrough_browser(url: &str) {
+    let Some(browser) = load_browser_backup().filter(|b| b != DESKTOP_FILE_NAME) else {
+        tracing::error!("没有记录原始默认浏览器，无法放行 URL: {}", url);
+        return;
+    };
+    let desktop_id = browser.trim_end_matches(".desktop");
+
+    // 优先使用 gtk-launch，回退到 gio launch
+    let launched = Command::new("gtk-launch")
+        .args([desktop_id, url])
+        .spawn()
+        .is_ok()
+        || find_desktop_file(&browser)
+            .and_then(|path| {
+                Command::new("gio")
+                    .arg("launch")
+                    .arg(path)
+                    .arg(url)
+                    .spawn()
+                    .ok()
+            })
+            .is_some();
+
+    if !launched {
+        tracing::error!("使用 {} 打开 URL 失败", browser);
+    }
+}
+
+/// 获取当前默认浏览器的 desktop 文件名
+fn get_default_browser() -> Option<String> {
+    let output = Command::new("xdg-settings")
+        .args(["get", "default-web-browser"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .or_else(|| {
+            Command::new("xdg-mime")
+                .args(["query", "default", "x-scheme-handler/https"])
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+        })?;
+
+    let browser = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!browser.is_empty()).then_some(browser)
+}
+
+/// 设置默认浏览器（xdg-settings 失败时回退到 xdg-mime）
+fn set_default_browser(desktop_file: &str) -> Result<()> {
+    let settings_ok = Command::new("xdg-settings")
+        .args(["set", "default-web-browser", desktop_file])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if settings_ok {
+        return Ok(());
+    }
+
+    tracing::warn!("xdg-settings 设置默认浏览器失败，回退到 xdg-mime");
+    let mime_ok = Command::new("xdg-mime")
+        .args([
+            "default",
+            desktop_file,
+            "x-scheme-handler/http",
+            "x-scheme-handler/https",
+        ])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+
+    if mime_ok {
+        Ok(())
+    } else {
+        Err(BrowserInterceptorError::PlatformError(format!(
+            "无法将 {} 设置为默认浏览器，请确认已安装 xdg-utils",
+            desktop_file
+        )))
+    }
+}
+
+fn applications_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("applications"))
+}
+
+/// 写入 desktop 文件，使本应用可以被注册为 http/https 处理程序
+fn install_desktop_file() -> Result<()> {
+    let apps_dir = applications_dir().ok_or_else(|| {
+        BrowserInterceptorError::PlatformError("无法获取 applications 目录".to_string())
+    })?;
+    std::fs::create_dir_all(&apps_dir)?;
+
+    let exe = std::env::current_exe()?;
+    std::fs::write(apps_dir.join(DESKTOP_FILE_NAME), desktop_entry(&exe))?;
+
+    // 刷新 MIME 缓存（失败不影响 xdg-settings）
+    let _ = Command::new("update-desktop-database")
+        .arg(&apps_dir)
+        .status();
+    Ok(())
+}
+
+/// 生成 desktop 文件内容（`%u` 由桌面环境替换为被打开的 URL）
+fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=ProxyCast Browser Interceptor\n\
+         Exec=\"{}\" %u\n\
+         NoDisplay=true\n\
+         Terminal=false\n\
+         MimeType=x-scheme-handler/http;x-scheme-handler/https;\n",
+        exe.display()
+    )
+}
+
+fn remove_desktop_file() {
+    if let Some(apps_dir) = applications_dir() {
+        let _ = std::fs::remove_file(apps_dir.join(DESKTOP_FILE_NAME));
+    }
+}
+
+/// 查找 desktop 文件的完整路径
+fn find_desktop_file(desktop_file: &str) -> Option<PathBuf> {
+    let data_dirs =
+        std::env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".into());
+    find_desktop_file_in(
+        applications_dir()
+            .into_iter()
+            .chain(xdg_applications_dirs(&data_dirs)),
+        desktop_file,
+    )
+}
+
+/// 将 `XDG_DATA_DIRS`（冒号分隔）转换为 applications 目录列表
+fn xdg_applications_dirs(data_dirs: &str) -> impl Iterator<Item = PathBuf> + '_ {
+    data_dirs
+        .split(':')
+        .filter(|d| !d.is_empty())
+        .map(|d| PathBuf::from(d).join("applications"))
+}
+
+/// 按顺序在目录中查找 desktop 文件，返回第一个存在的路径
+fn find_desktop_file_in(
+    dirs: impl IntoIterator<Item = PathBuf>,
+    desktop_file: &str,
+) -> Option<PathBuf> {
+    dirs.into_iter()
+        .map(|dir| dir.join(desktop_file))
+        .find(|path| path.exists())
+}
+
+fn browser_backup_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("proxycast").join("linux-default-browser"))
+}
+
+fn save_browser_backup(browser: &str) {
+    if browser == DESKTOP_FILE_NAME {
+        return;
+    }
+    if let Some(path) = browser_backup_path() {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Err(e) = std::fs::write(&path, browser) {
+            tracing::warn!("保存默认浏览器备份失败: {}", e);
+        }
+    }
+}
+
+fn load_browser_backup() -> Option<String> {
+    let content = std::fs::read_to_string(browser_backup_path()?).ok()?;
+    let browser = content.trim().to_string();
+    (!browser.is_empty()).then_some(browser)
+}
+
+fn clear_browser_backup() {
+    if let Some(path) = browser_backup_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_entry_registers_http_handlers() {
+        let entry = desktop_entry(Path::new("/opt/ProxyCast/proxycast"));
+
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains("Exec=\"/opt/ProxyCast/proxycast\" %u\n"));
+        assert!(entry.contains("MimeType=x-scheme-handler/http;x-scheme-handler/https;\n"));
+        // 续行不应带入缩进
+        assert!(entry.lines().all(|line| !line.starts_with(' ')));
+    }
+
+    #[test]
+    fn test_xdg_applications_dirs_skips_empty_entries() {
+        let dirs: Vec<PathBuf> = xdg_applications_dirs("/usr/local/share::/usr/share:").collect();
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("/usr/local/share/applications"),
+                PathBuf::from("/usr/share/applications"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_desktop_file_in_returns_first_existing() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        std::fs::write(second.path().join("firefox.desktop"), "").unwrap();
+        std::fs::write(first.path().join("chromium.desktop"), "").unwrap();
+        std::fs::write(second.path().join("chromium.desktop"), "").unwrap();
+
+        let dirs = || vec![first.path().to_path_buf(), second.path().to_path_buf()];
+        assert_eq!(
+            find_desktop_file_in(dirs(), "firefox.desktop"),
+            Some(second.path().join("firefox.desktop"))
+        );
+        assert_eq!(
+            find_desktop_file_in(dirs(), "chromium.desktop"),
+            Some(first.path().join("chromium.desktop"))
+        );
+        assert_eq!(find_desktop_file_in(dirs(), "missing.desktop"), None);
     }
 }
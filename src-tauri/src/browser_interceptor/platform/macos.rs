@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use crate::browser_interceptor::interceptor::route_deep_link_url;
 use crate::browser_interceptor::{BrowserInterceptorError, InterceptedUrl, Result};
 use once_cell::sync::Lazy;
 use std::process::Command;
@@ -10,6 +11,9 @@ use tokio::sync::mpsc;
 static GLOBAL_URL_SENDER: Lazy<Mutex<Option<mpsc::UnboundedSender<InterceptedUrl>>>> =
     Lazy::new(|| Mutex::new(None));
 
+/// 放行 URL 时使用的原默认浏览器 Bundle ID
+static PASSTHROUGH_BROWSER: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
 /// macOS 平台的浏览器拦截器（基于设置默认浏览器 + Deep Link）
 pub struct MacOSInterceptor {
     running: bool,
@@ -54,6 +58,9 @@ impl MacOSInterceptor {
         // 1. 保存当前默认浏览器
         self.original_default_browser = self.get_default_browser().await;
         tracing::info!("当前默认浏览器: {:?}", self.original_default_browser);
+        if let Ok(mut passthrough) = PASSTHROUGH_BROWSER.lock() {
+            *passthrough = self.original_default_browser.clone();
+        }
 
         // 2. 设置全局 URL sender
         if let Some(ref sender) = self.url_sender {
@@ -303,6 +310,10 @@ pub fn handle_deep_link_url(url: String) {
         return;
     }
 
+    if !route_deep_link_url(&url, open_in_passthrough_browser) {
+        return;
+    }
+
     // 尝试识别来源进程（macOS 上较难获取，使用默认值）
     let source_process = detect_source_process(&url);
 
@@ -323,6 +334,20 @@ pub fn handle_deep_link_url(url: String) {
     }
 }
 
+/// 在原默认浏览器中打开放行的 URL
+fn open_in_passthrough_browser(url: &str) {
+    let browser_id = PASSTHROUGH_BROWSER
+        .lock()
+        .ok()
+        .and_then(|b| b.clone())
+        .filter(|id| !id.contains("browser-interception"))
+        .unwrap_or_else(|| "com.apple.Safari".to_string());
+
+    if let Err(e) = Command::new("open").args(["-b", &browser_id, url]).spawn() {
+        tracing::error!("使用 {} 打开 URL 失败: {}", browser_id, e);
+    }
+}
+
 /// 尝试检测 URL 的来源进程
 fn detect_source_process(url: &str) -> String {
     // 基于 URL 特征推测来源
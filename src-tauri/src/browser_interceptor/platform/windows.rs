@@ -1,3 +1,4 @@
+use crate::browser_interceptor::interceptor::route_deep_link_url;
 use crate::browser_interceptor::{BrowserInterceptorError, InterceptedUrl, Result};
 use chrono::Utc;
 use std::sync::{Arc, Mutex};
@@ -286,19 +287,21 @@ pub fn is_target_process(process_name: &str) -> bool {
         .any(|&target| process_name.to_lowercase().contains(&target.to_lowercase()))
 }
 
-/// 检查 URL 是否匹配拦截模式
+/// 按共享的 URL 规则检查是否需要拦截（与 macOS/Linux 的 deep-link 分流一致）
+///
+/// 规则放行的 URL 交给系统默认处理程序打开。
 pub fn should_intercept_url(url: &str) -> bool {
-    let patterns = [
-        "https://auth.",
-        "https://accounts.google.com",
-        "https://github.com/login",
-        "https://login.microsoftonline.com",
-        "/oauth/",
-        "/auth/",
-        "localhost:8080/auth", // OAuth 回调地址
-    ];
+    route_deep_link_url(url, open_in_passthrough_browser)
+}
 
-    patterns.iter().any(|&pattern| url.contains(pattern))
+/// 使用系统默认处理程序打开放行的 URL
+fn open_in_passthrough_browser(url: &str) {
+    if let Err(e) = std::process::Command::new("rundll32")
+        .args(["url.dll,FileProtocolHandler", url])
+        .spawn()
+    {
+        tracing::error!("打开放行的 URL 失败: {}", e);
+    }
 }
 
 #[cfg(test)]
@@ -312,12 +315,4 @@ mod tests {
         assert!(is_target_process("code.exe"));
         assert!(!is_target_process("notepad.exe"));
     }
-
-    #[test]
-    fn test_should_intercept_url() {
-        assert!(should_intercept_url("https://accounts.google.com/oauth"));
-        assert!(should_intercept_url("https://github.com/login/oauth"));
-        assert!(should_intercept_url("localhost:8080/auth/callback"));
-        assert!(!should_intercept_url("https://example.com"));
-    }
 }
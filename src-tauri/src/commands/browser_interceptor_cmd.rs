@@ -1,5 +1,6 @@
 use crate::browser_interceptor::{
-    BrowserInterceptor, BrowserInterceptorConfig, InterceptedUrl, InterceptorState, UrlStatistics,
+    BrowserInterceptor, BrowserInterceptorConfig, InterceptedUrl, InterceptorState,
+    NotificationMessage, UrlStatistics,
 };
 use once_cell::sync::Lazy;
 use std::sync::Arc;
@@ -65,6 +66,16 @@ pub async fn restore_normal_browser_behavior() -> Result<String, String> {
         *interceptor_guard = None;
         Ok("已恢复正常浏览器行为".to_string())
     } else {
+        // 拦截器未运行时，仍尝试清理上次异常退出遗留的默认浏览器设置
+        #[cfg(target_os = "linux")]
+        {
+            let restored =
+                crate::browser_interceptor::platform::linux::restore_default_browser_from_backup()
+                    .map_err(|e| e.to_string())?;
+            if restored {
+                return Ok("已恢复正常浏览器行为".to_string());
+            }
+        }
         Ok("拦截器未运行".to_string())
     }
 }
@@ -233,9 +244,26 @@ pub async fn show_url_intercept_notification(
     source_process: String,
 ) -> Result<String, String> {
     tracing::info!("[URL 拦截] 来自 {}: {}", source_process, url);
+
+    let interceptor = INTERCEPTOR.read().await;
+    if let Some(ref int) = *interceptor {
+        int.notify_url_intercepted(url, source_process)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
     Ok("通知已显示".to_string())
 }
 
+/// 获取最近的拦截通知（分组通知包含合并的 URL 列表）
+#[tauri::command]
+pub async fn get_recent_intercept_notifications() -> Result<Vec<NotificationMessage>, String> {
+    let interceptor = INTERCEPTOR.read().await;
+    match *interceptor {
+        Some(ref int) => Ok(int.get_recent_notifications().await),
+        None => Ok(Vec::new()),
+    }
+}
+
 /// 显示状态通知
 #[tauri::command]
 pub async fn show_status_notification(
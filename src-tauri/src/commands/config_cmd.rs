@@ -1,6 +1,7 @@
 use crate::config::{
-    Config, ConfigManager, ExportBundle, ExportOptions as ExportServiceOptions, ExportService,
-    ImportOptions as ImportServiceOptions, ImportService, ValidationResult,
+    self, Config, ConfigManager, ConfigValidationReport, ExportBundle,
+    ExportOptions as ExportServiceOptions, ExportService, ImportOptions as ImportServiceOptions,
+    ImportService, ValidationResult,
 };
 use crate::models::AppType;
 use serde::{Deserialize, Serialize};
@@ -269,13 +270,22 @@ pub struct ImportResult {
     pub warnings: Vec<String>,
 }
 
-/// 验证配置 YAML 格式
+/// 验证配置 YAML
+///
+/// 返回结构化诊断（字段路径、提示信息、严重级别、行号），
+/// 解析成功时报告中包含解析后的配置。
 ///
 /// # Arguments
 /// * `yaml_content` - YAML 配置字符串
 #[tauri::command]
-pub fn validate_config_yaml(yaml_content: String) -> Result<Config, String> {
-    ConfigManager::parse_yaml(&yaml_content).map_err(|e| e.to_string())
+pub fn validate_config_yaml(yaml_content: String) -> ConfigValidationReport {
+    config::validate_yaml(&yaml_content)
+}
+
+/// 获取配置的 JSON Schema（含安全规则约束），供前端保存前内联校验
+#[tauri::command]
+pub fn get_config_schema() -> serde_json::Value {
+    config::config_schema()
 }
 
 /// 导入配置
@@ -605,7 +615,7 @@ pub async fn check_for_updates() -> Result<VersionCheckResult, String> {
     const GITHUB_API_URL: &str =
         "https://api.github.com/repos/aiclientproxy/proxycast/releases/latest";
 
-    let client = reqwest::Client::new();
+    let client = crate::http_client::shared_client();
 
     match client
         .get(GITHUB_API_URL)
@@ -788,7 +798,7 @@ pub async fn download_update(app_handle: AppHandle) -> Result<DownloadResult, St
     }
 
     // 下载文件
-    let client = reqwest::Client::new();
+    let client = crate::http_client::shared_client();
 
     match client
         .get(&download_url)
@@ -870,7 +880,7 @@ async fn get_platform_download_from_github(version: &str) -> Result<(String, Str
         version
     );
 
-    let client = reqwest::Client::new();
+    let client = crate::http_client::shared_client();
     let response = client
         .get(&api_url)
         .header("User-Agent", "ProxyCast")
@@ -0,0 +1,19 @@
+//! 数据库维护相关 Tauri 命令
+
+use crate::app::types::AppState;
+use crate::database::maintenance::{self, CompactionReport};
+use crate::database::DbConnection;
+use tauri::State;
+
+/// 立即执行一次数据库维护（删除过期记录、ANALYZE、VACUUM），返回回收的空间
+#[tauri::command]
+pub async fn compact_database(
+    state: State<'_, AppState>,
+    db: State<'_, DbConnection>,
+) -> Result<CompactionReport, String> {
+    let settings = state.read().await.config.database_maintenance.clone();
+    let db = db.inner().clone();
+    tokio::task::spawn_blocking(move || maintenance::run(&db, &settings))
+        .await
+        .map_err(|e| e.to_string())?
+}
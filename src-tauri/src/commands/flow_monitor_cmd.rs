@@ -5,14 +5,16 @@
 //! **Validates: Requirements 10.1-10.7**
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::Arc;
 use tauri::State;
 
 use crate::flow_monitor::{
-    get_filter_help, BatchOperation, BatchOperations, BatchResult, DiffConfig, ExportFormat,
-    ExportOptions, FilterExpr, FilterParser, FlowAnnotations, FlowDiff, FlowDiffResult,
-    FlowExporter, FlowFilter, FlowMonitor, FlowQueryResult, FlowQueryService, FlowSearchResult,
-    FlowSortBy, FlowStats, LLMFlow, FILTER_HELP,
+    get_filter_help, BatchOperation, BatchOperations, BatchResult, CaptureFilter, DiffConfig,
+    ExportFormat, ExportOptions, FilterExpr, FilterParser, FlowAnnotations, FlowDiff,
+    FlowDiffResult, FlowExporter, FlowFilter, FlowMonitor, FlowQueryResult, FlowQueryService,
+    FlowSearchResult, FlowSortBy, FlowStats, LLMFlow, NdjsonExportOptions, NdjsonExportSummary,
+    PruneResult, TimeRange, FILTER_HELP,
 };
 
 // ============================================================================
@@ -176,6 +178,20 @@ pub struct CleanupFlowsResponse {
     pub freed_bytes: u64,
 }
 
+/// 持久化 Flow 结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistFlowsResponse {
+    /// 成功持久化的 Flow 数量
+    pub persisted_count: usize,
+}
+
+/// 移除内存 Flow 结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneMemoryFlowsResponse {
+    /// 从内存中移除的 Flow 数量
+    pub pruned_count: usize,
+}
+
 // ============================================================================
 // Tauri 命令实现
 // ============================================================================
@@ -349,6 +365,43 @@ pub async fn export_flows(
     })
 }
 
+/// 按时间范围把 Flow 流式导出为 NDJSON 文件
+///
+/// 逐条读取并写入磁盘，不会把全部 Flow 加载到内存。`range` 覆盖 `filter` 中的时间范围。
+///
+/// # Arguments
+/// * `range` - 时间范围
+/// * `filter` - 其他过滤条件（可选）
+/// * `path` - 输出文件路径
+/// * `options` - 请求/响应体截断等导出选项（可选）
+/// * `query_service` - 查询服务状态
+///
+/// # Returns
+/// * `Ok(NdjsonExportSummary)` - 成功时返回写入的 Flow 数量和字节数
+/// * `Err(String)` - 失败时返回错误消息
+#[tauri::command]
+pub async fn export_flows_ndjson(
+    range: TimeRange,
+    filter: Option<FlowFilter>,
+    path: String,
+    options: Option<NdjsonExportOptions>,
+    query_service: State<'_, FlowQueryServiceState>,
+) -> Result<NdjsonExportSummary, String> {
+    let filter = FlowFilter {
+        time_range: Some(range),
+        ..filter.unwrap_or_default()
+    };
+    query_service
+        .0
+        .export_ndjson(
+            filter,
+            options.unwrap_or_default(),
+            std::path::Path::new(&path),
+        )
+        .await
+        .map_err(|e| format!("导出 NDJSON 失败: {}", e))
+}
+
 /// 更新 Flow 标注
 ///
 /// **Validates: Requirements 10.6**
@@ -614,6 +667,93 @@ pub async fn cleanup_flows(
     })
 }
 
+/// 将内存中的 Flow 持久化到文件存储
+///
+/// # Arguments
+/// * `flow_ids` - 要持久化的 Flow ID 列表
+/// * `monitor` - Flow 监控服务状态
+///
+/// # Returns
+/// * `Ok(PersistFlowsResponse)` - 成功时返回持久化数量
+/// * `Err(String)` - 未启用文件存储时返回错误消息
+#[tauri::command]
+pub async fn persist_flows_to_store(
+    flow_ids: Vec<String>,
+    monitor: State<'_, FlowMonitorState>,
+) -> Result<PersistFlowsResponse, String> {
+    let persisted_count = monitor.0.persist_flows(&flow_ids).await?;
+    Ok(PersistFlowsResponse { persisted_count })
+}
+
+/// 从内存中移除已持久化的 Flow
+///
+/// # Arguments
+/// * `monitor` - Flow 监控服务状态
+///
+/// # Returns
+/// * `Ok(PruneMemoryFlowsResponse)` - 成功时返回移除数量
+#[tauri::command]
+pub async fn prune_memory_flows(
+    monitor: State<'_, FlowMonitorState>,
+) -> Result<PruneMemoryFlowsResponse, String> {
+    let pruned_count = monitor.0.prune_persisted_flows().await;
+    Ok(PruneMemoryFlowsResponse { pruned_count })
+}
+
+/// 清理磁盘上没有索引引用的孤立 Flow 文件
+///
+/// 会话、书签和内存中的 Flow 所在的文件不会被删除。
+///
+/// # Arguments
+/// * `dry_run` - 只统计不删除（默认 true）
+/// * `min_age_hours` - 只清理修改时间超过该小时数的文件（默认按保留天数）
+/// * `monitor` - Flow 监控服务状态
+/// * `session_manager` - 会话管理器状态
+/// * `bookmark_manager` - 书签管理器状态
+///
+/// # Returns
+/// * `Ok(PruneResult)` - 清理结果（包含释放的字节数）
+/// * `Err(String)` - 未启用文件存储或清理失败时返回错误消息
+#[tauri::command]
+pub async fn prune_flow_files(
+    dry_run: Option<bool>,
+    min_age_hours: Option<u32>,
+    monitor: State<'_, FlowMonitorState>,
+    session_manager: State<'_, SessionManagerState>,
+    bookmark_manager: State<'_, BookmarkManagerState>,
+) -> Result<PruneResult, String> {
+    let file_store = monitor
+        .0
+        .file_store()
+        .ok_or_else(|| "文件存储未启用".to_string())?;
+
+    let mut protected_ids: HashSet<String> = session_manager
+        .0
+        .get_all_session_flow_ids()
+        .map_err(|e| format!("获取会话 Flow 失败: {}", e))?
+        .into_iter()
+        .collect();
+    protected_ids.extend(
+        bookmark_manager
+            .0
+            .list(None)
+            .map_err(|e| format!("获取书签失败: {}", e))?
+            .into_iter()
+            .map(|b| b.flow_id),
+    );
+    protected_ids.extend(monitor.0.memory_store().read().await.get_all_ids());
+
+    let min_age = match min_age_hours {
+        Some(hours) => chrono::Duration::hours(hours as i64),
+        None => chrono::Duration::days(file_store.rotation_config().retention_days as i64),
+    };
+    let older_than = chrono::Utc::now() - min_age;
+
+    file_store
+        .prune_orphan_files(older_than, &protected_ids, dry_run.unwrap_or(true))
+        .map_err(|e| format!("清理孤立文件失败: {}", e))
+}
+
 /// 获取最近的 Flow 列表
 ///
 /// **Validates: Requirements 10.1**
@@ -799,6 +939,7 @@ pub async fn create_test_flows(
                 ip: Some("127.0.0.1".to_string()),
                 user_agent: Some("test-agent".to_string()),
                 request_id: Some(format!("test-req-{}", i)),
+                session_id: None,
             },
             routing_info: RoutingInfo {
                 target_url: Some("https://api.openai.com".to_string()),
@@ -909,16 +1050,31 @@ mod tests {
 // 实时事件订阅命令
 // ============================================================================
 
+use crate::flow_monitor::FlowEventFilter;
 use tauri::{AppHandle, Emitter};
 
+/// Flow 跟随模式状态
+///
+/// 跟随模式的事件单独通过 `flow-follow-event` 推送，同一时间只跟随一个会话。
+#[derive(Default)]
+pub struct FlowFollowState {
+    filter: Arc<parking_lot::RwLock<Option<FlowEventFilter>>>,
+    started: std::sync::atomic::AtomicBool,
+}
+
 /// 订阅 Flow 实时事件
 ///
 /// 启动一个后台任务，将 Flow 事件通过 Tauri 事件系统推送到前端。
 /// 前端可以通过 `listen("flow-event", ...)` 来接收事件。
 ///
+/// 指定 `session_id` 时进入跟随模式：只把该会话的 Flow 事件推送到
+/// `flow-follow-event`，再次调用会切换跟随的会话。
+///
 /// # Arguments
 /// * `app` - Tauri AppHandle
 /// * `monitor` - Flow 监控服务状态
+/// * `follow` - 跟随模式状态
+/// * `session_id` - 跟随的客户端会话 ID（可选）
 ///
 /// # Returns
 /// * `Ok(())` - 成功启动订阅
@@ -927,7 +1083,24 @@ use tauri::{AppHandle, Emitter};
 pub async fn subscribe_flow_events(
     app: AppHandle,
     monitor: State<'_, FlowMonitorState>,
+    follow: State<'_, FlowFollowState>,
+    session_id: Option<String>,
 ) -> Result<(), String> {
+    if let Some(session_id) = session_id {
+        let session_id = session_id.trim();
+        if session_id.is_empty() {
+            return Err("会话 ID 不能为空".to_string());
+        }
+        *follow.filter.write() = Some(FlowEventFilter::session(session_id));
+        if !follow
+            .started
+            .swap(true, std::sync::atomic::Ordering::SeqCst)
+        {
+            spawn_follow_forwarder(app, monitor.0.subscribe(), follow.filter.clone());
+        }
+        return Ok(());
+    }
+
     let mut receiver = monitor.0.subscribe();
 
     // 启动后台任务来转发事件
@@ -954,6 +1127,46 @@ pub async fn subscribe_flow_events(
     Ok(())
 }
 
+/// 停止跟随会话
+#[tauri::command]
+pub async fn unfollow_flow_session(follow: State<'_, FlowFollowState>) -> Result<(), String> {
+    *follow.filter.write() = None;
+    Ok(())
+}
+
+/// 启动跟随模式的转发任务，只转发匹配当前跟随会话的事件
+fn spawn_follow_forwarder(
+    app: AppHandle,
+    mut receiver: tokio::sync::broadcast::Receiver<crate::flow_monitor::FlowEvent>,
+    filter: Arc<parking_lot::RwLock<Option<FlowEventFilter>>>,
+) {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let matched = filter
+                        .read()
+                        .as_ref()
+                        .is_some_and(|filter| filter.matches(&event));
+                    if !matched {
+                        continue;
+                    }
+                    if let Err(e) = app.emit("flow-follow-event", &event) {
+                        tracing::warn!("发送跟随 Flow 事件到前端失败: {}", e);
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("跟随 Flow 事件接收器落后 {} 条消息", n);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                    tracing::debug!("Flow 事件通道已关闭");
+                    break;
+                }
+            }
+        }
+    });
+}
+
 /// 获取所有可用的 Flow 标签
 ///
 /// # Arguments
@@ -1002,14 +1215,24 @@ pub struct FilterHelpItem {
 /// 验证并解析过滤表达式字符串，返回解析结果。
 /// 如果表达式有效，返回解析后的 AST；如果无效，返回错误信息。
 ///
+/// 表达式中的 `$name` 会先展开为已保存的过滤变量。
+///
 /// # Arguments
 /// * `expression` - 过滤表达式字符串
+/// * `quick_filter_manager` - 快速过滤器管理器状态（提供过滤变量）
 ///
 /// # Returns
 /// * `Ok(ParseFilterResult)` - 解析结果
 #[tauri::command]
-pub async fn parse_filter(expression: String) -> Result<ParseFilterResult, String> {
-    match FilterParser::parse(&expression) {
+pub async fn parse_filter(
+    expression: String,
+    quick_filter_manager: State<'_, QuickFilterManagerState>,
+) -> Result<ParseFilterResult, String> {
+    let variables = quick_filter_manager
+        .0
+        .variables_map()
+        .map_err(|e| format!("读取过滤变量失败: {}", e))?;
+    match FilterParser::parse_with_variables(&expression, &variables) {
         Ok(expr) => Ok(ParseFilterResult {
             valid: true,
             error: None,
@@ -1028,16 +1251,25 @@ pub async fn parse_filter(expression: String) -> Result<ParseFilterResult, Strin
 /// **Validates: Requirements 1.17**
 ///
 /// 仅验证过滤表达式语法是否正确，不返回解析后的 AST。
+/// 引用了不存在的变量或变量循环引用时视为无效。
 ///
 /// # Arguments
 /// * `expression` - 过滤表达式字符串
+/// * `quick_filter_manager` - 快速过滤器管理器状态（提供过滤变量）
 ///
 /// # Returns
 /// * `Ok(bool)` - 表达式是否有效
 /// * `Err(String)` - 验证过程中的错误
 #[tauri::command]
-pub async fn validate_filter(expression: String) -> Result<bool, String> {
-    Ok(FilterParser::validate(&expression).is_ok())
+pub async fn validate_filter(
+    expression: String,
+    quick_filter_manager: State<'_, QuickFilterManagerState>,
+) -> Result<bool, String> {
+    let variables = quick_filter_manager
+        .0
+        .variables_map()
+        .map_err(|e| format!("读取过滤变量失败: {}", e))?;
+    Ok(FilterParser::validate_with_variables(&expression, &variables).is_ok())
 }
 
 /// 获取过滤表达式帮助信息
@@ -1096,11 +1328,12 @@ pub struct QueryFlowsWithExpressionRequest {
 ///
 /// **Validates: Requirements 1.1-1.16**
 ///
-/// 使用类似 mitmproxy 的过滤表达式语法查询 Flow。
+/// 使用类似 mitmproxy 的过滤表达式语法查询 Flow，查询前展开过滤变量。
 ///
 /// # Arguments
 /// * `request` - 查询请求参数
 /// * `query_service` - 查询服务状态
+/// * `quick_filter_manager` - 快速过滤器管理器状态（提供过滤变量）
 ///
 /// # Returns
 /// * `Ok(FlowQueryResult)` - 成功时返回查询结果
@@ -1109,11 +1342,16 @@ pub struct QueryFlowsWithExpressionRequest {
 pub async fn query_flows_with_expression(
     request: QueryFlowsWithExpressionRequest,
     query_service: State<'_, FlowQueryServiceState>,
+    quick_filter_manager: State<'_, QuickFilterManagerState>,
 ) -> Result<FlowQueryResult, String> {
+    let filter_expr = quick_filter_manager
+        .0
+        .expand_expression(&request.filter_expr)
+        .map_err(|e| format!("查询 Flow 失败: {}", e))?;
     query_service
         .0
         .query_with_expression(
-            &request.filter_expr,
+            &filter_expr,
             request.sort_by,
             request.sort_desc,
             request.page,
@@ -1129,7 +1367,9 @@ pub async fn query_flows_with_expression(
 
 use crate::flow_monitor::{FlowInterceptor, InterceptConfig, InterceptedFlow, ModifiedData};
 
-use crate::flow_monitor::{BatchReplayResult, FlowReplayer, ReplayConfig, ReplayResult};
+use crate::flow_monitor::{
+    BatchReplayResult, FlowReplayer, FuzzMutation, FuzzResult, ReplayConfig, ReplayResult,
+};
 
 /// 拦截器状态封装
 pub struct FlowInterceptorState(pub Arc<FlowInterceptor>);
@@ -1471,6 +1711,107 @@ pub async fn replay_flows_batch(
         .await)
 }
 
+/// 模糊重放 Flow
+///
+/// 对 Flow 的请求体依次施加变异（删除字段、未知模型、非法 JSON、超大请求体）后
+/// 发往本地服务，返回每个变异的状态码和错误信息，用于回归验证错误处理。
+///
+/// # Arguments
+/// * `flow_id` - 作为基准的 Flow ID
+/// * `mutations` - 依次施加的变异
+#[tauri::command]
+pub async fn fuzz_replay_flow(
+    flow_id: String,
+    mutations: Vec<FuzzMutation>,
+    app_state: State<'_, crate::app::AppState>,
+    replayer: State<'_, FlowReplayerState>,
+) -> Result<Vec<FuzzResult>, String> {
+    let (base_url, api_key) = {
+        let s = app_state.read().await;
+        if !s.running {
+            return Err("服务器未运行，无法进行模糊重放".to_string());
+        }
+        (
+            format!("http://{}:{}", s.config.server.host, s.config.server.port),
+            s.server_api_key.current(),
+        )
+    };
+    replayer
+        .0
+        .fuzz_replay(&flow_id, &mutations, &base_url, &api_key)
+        .await
+        .map_err(|e| format!("模糊重放失败: {}", e))
+}
+
+// ============================================================================
+// 死信相关命令
+// ============================================================================
+
+use crate::database::dao::dead_letter::{DeadLetter, DeadLetterDao};
+use crate::database::DbConnection;
+
+/// 死信列表默认返回条数
+const DEFAULT_DEAD_LETTER_LIMIT: usize = 100;
+
+/// 查询死信记录（按时间倒序）
+///
+/// # Arguments
+/// * `provider` - 按 Provider 过滤（可选）
+/// * `limit` - 返回条数（默认 100）
+#[tauri::command]
+pub async fn get_dead_letters(
+    provider: Option<String>,
+    limit: Option<usize>,
+    db: State<'_, DbConnection>,
+) -> Result<Vec<DeadLetter>, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    DeadLetterDao::list(
+        &conn,
+        provider.as_deref(),
+        limit.unwrap_or(DEFAULT_DEAD_LETTER_LIMIT),
+    )
+    .map_err(|e| format!("查询死信记录失败: {}", e))
+}
+
+/// 删除单条死信记录
+#[tauri::command]
+pub async fn delete_dead_letter(id: String, db: State<'_, DbConnection>) -> Result<bool, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    DeadLetterDao::delete(&conn, &id).map_err(|e| format!("删除死信记录失败: {}", e))
+}
+
+/// 清空死信记录，返回删除的条数
+#[tauri::command]
+pub async fn clear_dead_letters(db: State<'_, DbConnection>) -> Result<usize, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    DeadLetterDao::clear(&conn).map_err(|e| format!("清空死信记录失败: {}", e))
+}
+
+/// 重放死信记录
+///
+/// # Arguments
+/// * `id` - 死信 ID
+/// * `config` - 重放配置
+#[tauri::command]
+pub async fn replay_dead_letter(
+    id: String,
+    config: Option<ReplayConfig>,
+    db: State<'_, DbConnection>,
+    replayer: State<'_, FlowReplayerState>,
+) -> Result<ReplayResult, String> {
+    let entry = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        DeadLetterDao::get(&conn, &id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("死信记录不存在: {}", id))?
+    };
+    replayer
+        .0
+        .replay_dead_letter(&entry, config.unwrap_or_default())
+        .await
+        .map_err(|e| format!("重放死信失败: {}", e))
+}
+
 // ============================================================================
 // 差异对比命令
 // ============================================================================
@@ -1628,7 +1969,9 @@ mod diff_tests {
 // 会话管理命令
 // ============================================================================
 
+use crate::commands::telemetry_cmd::TelemetryState;
 use crate::flow_monitor::{AutoSessionConfig, FlowSession, SessionExportResult, SessionManager};
+use crate::telemetry::SessionCostSummary;
 
 /// 会话管理器状态封装
 pub struct SessionManagerState(pub Arc<SessionManager>);
@@ -1870,6 +2213,30 @@ pub async fn delete_session(
         .map_err(|e| format!("删除会话失败: {}", e))
 }
 
+/// 合并会话
+///
+/// # Arguments
+/// * `source_id` - 源会话 ID（合并后删除或归档）
+/// * `target_id` - 目标会话 ID
+/// * `archive_source` - 是否归档源会话（默认删除）
+/// * `session_manager` - 会话管理器状态
+///
+/// # Returns
+/// * `Ok(usize)` - 合并后目标会话的 Flow 数量
+/// * `Err(String)` - 失败时返回错误消息
+#[tauri::command]
+pub async fn merge_sessions(
+    source_id: String,
+    target_id: String,
+    archive_source: Option<bool>,
+    session_manager: State<'_, SessionManagerState>,
+) -> Result<usize, String> {
+    session_manager
+        .0
+        .merge_sessions(&source_id, &target_id, archive_source.unwrap_or(false))
+        .map_err(|e| format!("合并会话失败: {}", e))
+}
+
 /// 导出会话
 ///
 /// **Validates: Requirements 5.6**
@@ -1929,6 +2296,55 @@ pub async fn get_session_flow_count(
         .map_err(|e| format!("获取会话 Flow 数量失败: {}", e))
 }
 
+/// 获取会话的 Token 用量和费用汇总
+///
+/// 会话中的 Flow 按请求 ID 关联 Token 记录，返回合计和按模型分组的统计。
+///
+/// # Arguments
+/// * `session_id` - 会话 ID
+/// * `session_manager` - 会话管理器状态
+/// * `query_service` - 查询服务状态
+/// * `telemetry` - 遥测服务状态
+///
+/// # Returns
+/// * `Ok(SessionCostSummary)` - 成功时返回费用汇总
+/// * `Err(String)` - 失败时返回错误消息
+#[tauri::command]
+pub async fn get_session_cost_summary(
+    session_id: String,
+    session_manager: State<'_, SessionManagerState>,
+    query_service: State<'_, FlowQueryServiceState>,
+    telemetry: State<'_, TelemetryState>,
+) -> Result<SessionCostSummary, String> {
+    if session_manager
+        .0
+        .get_session(&session_id)
+        .map_err(|e| format!("获取会话失败: {}", e))?
+        .is_none()
+    {
+        return Err(format!("会话不存在: {}", session_id));
+    }
+
+    let flow_ids = session_manager
+        .0
+        .get_session_flow_ids(&session_id)
+        .map_err(|e| format!("获取会话 Flow 列表失败: {}", e))?;
+
+    let mut request_ids = Vec::with_capacity(flow_ids.len());
+    for flow_id in &flow_ids {
+        let request_id = match query_service.0.get_flow(flow_id).await {
+            Ok(Some(flow)) => flow.metadata.client_info.request_id,
+            _ => None,
+        };
+        request_ids.push(request_id);
+    }
+
+    Ok(telemetry
+        .tokens
+        .read()
+        .session_cost_summary(&session_id, &request_ids))
+}
+
 /// 检查 Flow 是否在会话中
 ///
 /// # Arguments
@@ -2031,7 +2447,7 @@ pub async fn register_active_session(
 // 快速过滤器命令
 // ============================================================================
 
-use crate::flow_monitor::{QuickFilter, QuickFilterManager, QuickFilterUpdate};
+use crate::flow_monitor::{FilterVariable, QuickFilter, QuickFilterManager, QuickFilterUpdate};
 
 /// 快速过滤器管理器状态封装
 pub struct QuickFilterManagerState(pub Arc<QuickFilterManager>);
@@ -2308,6 +2724,80 @@ pub async fn find_quick_filter_by_name(
         .map_err(|e| format!("查找快速过滤器失败: {}", e))
 }
 
+/// 保存过滤变量请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveFilterVariableRequest {
+    /// 变量名（可带 `$` 前缀）
+    pub name: String,
+    /// 变量表达式
+    pub expression: String,
+    /// 描述（可选）
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// 列出所有过滤变量
+///
+/// # Arguments
+/// * `quick_filter_manager` - 快速过滤器管理器状态
+///
+/// # Returns
+/// * `Ok(Vec<FilterVariable>)` - 成功时返回过滤变量列表
+/// * `Err(String)` - 失败时返回错误消息
+#[tauri::command]
+pub async fn list_filter_variables(
+    quick_filter_manager: State<'_, QuickFilterManagerState>,
+) -> Result<Vec<FilterVariable>, String> {
+    quick_filter_manager
+        .0
+        .list_variables()
+        .map_err(|e| format!("列出过滤变量失败: {}", e))
+}
+
+/// 保存过滤变量（同名变量会被覆盖）
+///
+/// # Arguments
+/// * `request` - 保存过滤变量请求参数
+/// * `quick_filter_manager` - 快速过滤器管理器状态
+///
+/// # Returns
+/// * `Ok(FilterVariable)` - 成功时返回保存的过滤变量
+/// * `Err(String)` - 失败时返回错误消息
+#[tauri::command]
+pub async fn save_filter_variable(
+    request: SaveFilterVariableRequest,
+    quick_filter_manager: State<'_, QuickFilterManagerState>,
+) -> Result<FilterVariable, String> {
+    quick_filter_manager
+        .0
+        .save_variable(
+            &request.name,
+            &request.expression,
+            request.description.as_deref(),
+        )
+        .map_err(|e| format!("保存过滤变量失败: {}", e))
+}
+
+/// 删除过滤变量
+///
+/// # Arguments
+/// * `name` - 变量名
+/// * `quick_filter_manager` - 快速过滤器管理器状态
+///
+/// # Returns
+/// * `Ok(())` - 成功
+/// * `Err(String)` - 失败时返回错误消息（例如变量仍被引用）
+#[tauri::command]
+pub async fn delete_filter_variable(
+    name: String,
+    quick_filter_manager: State<'_, QuickFilterManagerState>,
+) -> Result<(), String> {
+    quick_filter_manager
+        .0
+        .delete_variable(&name)
+        .map_err(|e| format!("删除过滤变量失败: {}", e))
+}
+
 // ============================================================================
 // 代码导出命令
 // ============================================================================
@@ -2789,7 +3279,8 @@ pub async fn toggle_bookmark(
 // ============================================================================
 
 use crate::flow_monitor::{
-    Distribution, EnhancedStats, EnhancedStatsService, ReportFormat, StatsTimeRange, TrendData,
+    Distribution, EnhancedStats, EnhancedStatsService, LatencyBucket, LatencyPercentileSeries,
+    ReportFormat, StatsTimeRange, TrendData,
 };
 
 /// 增强统计服务状态封装
@@ -2842,6 +3333,20 @@ fn default_latency_buckets() -> Vec<u64> {
     vec![100, 500, 1000, 2000, 5000, 10000]
 }
 
+/// 获取延迟百分位请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetLatencyPercentilesRequest {
+    /// 过滤条件（可按 providers/models 限定）
+    #[serde(default)]
+    pub filter: FlowFilter,
+    /// 时间范围
+    #[serde(default)]
+    pub time_range: StatsTimeRange,
+    /// 时间桶大小（minute/hour/day）
+    #[serde(default)]
+    pub bucket: LatencyBucket,
+}
+
 /// 导出统计报告请求参数
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportStatsReportRequest {
@@ -2944,6 +3449,26 @@ pub async fn get_latency_histogram(
         .await)
 }
 
+/// 获取按时间桶统计的延迟百分位（p50/p90/p99）
+///
+/// # Arguments
+/// * `request` - 获取延迟百分位请求参数
+/// * `stats_service` - 增强统计服务状态
+///
+/// # Returns
+/// * `Ok(LatencyPercentileSeries)` - 成功时返回延迟百分位时间序列
+/// * `Err(String)` - 失败时返回错误消息
+#[tauri::command]
+pub async fn get_latency_percentiles(
+    request: GetLatencyPercentilesRequest,
+    stats_service: State<'_, EnhancedStatsServiceState>,
+) -> Result<LatencyPercentileSeries, String> {
+    Ok(stats_service
+        .0
+        .get_latency_percentiles(&request.filter, &request.time_range, request.bucket)
+        .await)
+}
+
 /// 导出统计报告
 ///
 /// **Validates: Requirements 9.7**
@@ -3211,7 +3736,7 @@ pub async fn batch_add_to_session(
 // 实时监控增强命令
 // ============================================================================
 
-use crate::flow_monitor::ThresholdConfig;
+use crate::flow_monitor::{AlertRule, ThresholdConfig};
 
 /// 阈值配置响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -3226,6 +3751,10 @@ pub struct ThresholdConfigResponse {
     pub input_token_threshold: Option<u32>,
     /// 输出 Token 阈值（可选）
     pub output_token_threshold: Option<u32>,
+    /// 异常告警规则
+    pub alert_rules: Vec<AlertRule>,
+    /// 同一规则两次告警之间的最小间隔（秒）
+    pub alert_cooldown_seconds: u64,
 }
 
 impl From<ThresholdConfig> for ThresholdConfigResponse {
@@ -3236,6 +3765,8 @@ impl From<ThresholdConfig> for ThresholdConfigResponse {
             token_threshold: config.token_threshold,
             input_token_threshold: config.input_token_threshold,
             output_token_threshold: config.output_token_threshold,
+            alert_rules: config.alert_rules,
+            alert_cooldown_seconds: config.alert_cooldown_seconds,
         }
     }
 }
@@ -3285,6 +3816,7 @@ pub async fn update_threshold_config(
     config: ThresholdConfig,
     monitor: State<'_, FlowMonitorState>,
 ) -> Result<(), String> {
+    config.validate_alert_rules()?;
     monitor.0.update_threshold_config(config).await;
     Ok(())
 }
@@ -3335,6 +3867,39 @@ pub async fn set_rate_window(
     monitor.0.set_rate_window(window_seconds).await;
     Ok(())
 }
+
+/// 获取捕获过滤器
+///
+/// # Arguments
+/// * `monitor` - Flow 监控服务状态
+///
+/// # Returns
+/// * `Ok(CaptureFilter)` - 当前的捕获排除规则
+#[tauri::command]
+pub async fn get_flow_capture_filter(
+    monitor: State<'_, FlowMonitorState>,
+) -> Result<CaptureFilter, String> {
+    Ok(monitor.0.capture_filter().await)
+}
+
+/// 更新捕获过滤器
+///
+/// 匹配排除规则的请求不会创建 Flow，修改立即对新请求生效。
+///
+/// # Arguments
+/// * `filter` - 新的捕获过滤器
+/// * `monitor` - Flow 监控服务状态
+///
+/// # Returns
+/// * `Ok(())` - 成功
+/// * `Err(String)` - 规则无效时返回错误消息
+#[tauri::command]
+pub async fn update_flow_capture_filter(
+    filter: CaptureFilter,
+    monitor: State<'_, FlowMonitorState>,
+) -> Result<(), String> {
+    monitor.0.update_capture_filter(filter).await
+}
 // ============================================================================
 // 通知配置命令
 // ============================================================================
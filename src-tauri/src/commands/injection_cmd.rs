@@ -1,6 +1,9 @@
 //! 参数注入相关命令
 
-use crate::config::{save_config, InjectionRuleConfig, InjectionSettings};
+use crate::config::{
+    save_config, ConfigChangeSource, GlobalConfigManagerState, InjectionRuleConfig,
+    InjectionSettings,
+};
 use crate::injection::{InjectionMode, InjectionRule};
 use crate::AppState;
 use serde::{Deserialize, Serialize};
@@ -77,11 +80,16 @@ pub async fn get_injection_config(
 #[tauri::command]
 pub async fn set_injection_enabled(
     state: tauri::State<'_, AppState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
     enabled: bool,
 ) -> Result<(), String> {
     let mut s = state.write().await;
     s.config.injection.enabled = enabled;
     save_config(&s.config).map_err(|e| e.to_string())?;
+    let injection = s.config.injection.clone();
+    drop(s);
+
+    apply_injection(&config_manager, injection).await;
     Ok(())
 }
 
@@ -103,6 +111,7 @@ pub async fn get_injection_rules(
 #[tauri::command]
 pub async fn add_injection_rule(
     state: tauri::State<'_, AppState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
     rule: InjectionRuleResponse,
 ) -> Result<(), String> {
     let mut s = state.write().await;
@@ -123,6 +132,10 @@ pub async fn add_injection_rule(
 
     s.config.injection.rules.push(config_rule);
     save_config(&s.config).map_err(|e| e.to_string())?;
+    let injection = s.config.injection.clone();
+    drop(s);
+
+    apply_injection(&config_manager, injection).await;
     Ok(())
 }
 
@@ -130,6 +143,7 @@ pub async fn add_injection_rule(
 #[tauri::command]
 pub async fn remove_injection_rule(
     state: tauri::State<'_, AppState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
     id: String,
 ) -> Result<(), String> {
     let mut s = state.write().await;
@@ -144,6 +158,10 @@ pub async fn remove_injection_rule(
 
     s.config.injection.rules.remove(pos);
     save_config(&s.config).map_err(|e| e.to_string())?;
+    let injection = s.config.injection.clone();
+    drop(s);
+
+    apply_injection(&config_manager, injection).await;
     Ok(())
 }
 
@@ -151,6 +169,7 @@ pub async fn remove_injection_rule(
 #[tauri::command]
 pub async fn update_injection_rule(
     state: tauri::State<'_, AppState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
     id: String,
     rule: InjectionRuleResponse,
 ) -> Result<(), String> {
@@ -174,5 +193,16 @@ pub async fn update_injection_rule(
     };
 
     save_config(&s.config).map_err(|e| e.to_string())?;
+    let injection = s.config.injection.clone();
+    drop(s);
+
+    apply_injection(&config_manager, injection).await;
     Ok(())
 }
+
+/// 通过配置观察者将注入配置同步到运行中的 RequestProcessor
+async fn apply_injection(config_manager: &GlobalConfigManagerState, injection: InjectionSettings) {
+    config_manager
+        .update_injection(injection, ConfigChangeSource::FrontendUI)
+        .await;
+}
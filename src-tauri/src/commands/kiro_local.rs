@@ -1,16 +1,22 @@
 //! Kiro 凭证本地切换命令
 //!
-//! 将 Kiro 凭证切换到本地 IDE，同时切换设备指纹。
+//! 将 Kiro 凭证切换到本地 IDE，同时切换设备指纹；
+//! 以及从本机已安装的 Kiro 客户端发现并导入凭证。
 
-use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
+use crate::commands::provider_pool_cmd::{
+    cleanup_credential_file, create_kiro_credential_from_json, ProviderPoolServiceState,
+};
+use crate::commands::usage_cmd::DEFAULT_KIRO_VERSION;
 use crate::database::DbConnection;
 use crate::models::kiro_fingerprint::{KiroFingerprintStore, SwitchToLocalResult};
-use crate::models::provider_pool_model::CredentialData;
+use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::providers::kiro::{generate_machine_id_from_credentials, KiroProvider};
 use crate::services::machine_id_service::MachineIdService;
+use crate::services::usage_service;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::State;
 
 /// Kiro auth token 文件格式
@@ -396,3 +402,516 @@ pub struct KiroFingerprintInfo {
     /// 最后切换时间
     pub last_switched_at: Option<String>,
 }
+
+// ============ 从本地 Kiro 安装导入凭证 ============
+
+/// Kiro 凭证来源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KiroCredentialSourceKind {
+    /// ~/.aws/sso/cache 下的 kiro-auth-token*.json
+    SsoCache,
+    /// Kiro 应用的 globalStorage/state.vscdb
+    AppState,
+}
+
+/// 本机发现的 Kiro 凭证来源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KiroCredentialSource {
+    /// 来源类型
+    pub kind: KiroCredentialSourceKind,
+    /// 文件路径
+    pub path: String,
+    /// state.vscdb 中的键（仅 app_state 来源）
+    pub key: Option<String>,
+    /// 是否由系统钥匙串加密（导入时需要访问钥匙串）
+    pub encrypted: bool,
+    /// 认证方式（未加密时可读取）
+    pub auth_method: Option<String>,
+    /// 过期时间（未加密时可读取）
+    pub expires_at: Option<String>,
+}
+
+/// Kiro 应用 state.vscdb 路径
+fn get_kiro_app_state_db_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| {
+        dir.join("Kiro")
+            .join("User")
+            .join("globalStorage")
+            .join("state.vscdb")
+    })
+}
+
+/// Kiro token 文件所在的 AWS SSO cache 目录（不创建）
+fn get_kiro_sso_cache_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".aws").join("sso").join("cache"))
+}
+
+/// 是否为 Kiro token 文件名（kiro-auth-token*.json）
+fn is_kiro_token_file_name(file_name: &str) -> bool {
+    file_name.starts_with("kiro-auth-token") && file_name.ends_with(".json")
+}
+
+/// 路径是否属于已知的 Kiro 凭证位置
+///
+/// 比较前会解析符号链接和 `..`，不存在的路径一律视为不允许。
+fn is_known_source_path(
+    kind: KiroCredentialSourceKind,
+    path: &Path,
+    sso_cache_dir: Option<&Path>,
+    app_state_db: Option<&Path>,
+) -> bool {
+    let Ok(path) = fs::canonicalize(path) else {
+        return false;
+    };
+    match kind {
+        KiroCredentialSourceKind::SsoCache => {
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            is_kiro_token_file_name(file_name)
+                && sso_cache_dir
+                    .and_then(|dir| fs::canonicalize(dir).ok())
+                    .is_some_and(|dir| path.parent() == Some(dir.as_path()))
+        }
+        KiroCredentialSourceKind::AppState => app_state_db
+            .and_then(|db| fs::canonicalize(db).ok())
+            .is_some_and(|db| db == path),
+    }
+}
+
+/// 解码 state.vscdb 中的值
+///
+/// Secret Storage 的值是序列化的 Buffer（`{"type":"Buffer","data":[...]}`），
+/// 以 `v10`/`v11` 开头表示已被系统钥匙串（Electron safeStorage）加密。
+/// 返回 (原始字节, 是否加密)。
+fn decode_state_value(raw: &str) -> (Vec<u8>, bool) {
+    let bytes = serde_json::from_str::<serde_json::Value>(raw)
+        .ok()
+        .filter(|v| v.get("type").and_then(|t| t.as_str()) == Some("Buffer"))
+        .and_then(|v| {
+            v.get("data")?
+                .as_array()?
+                .iter()
+                .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Option<Vec<u8>>>()
+        })
+        .unwrap_or_else(|| raw.as_bytes().to_vec());
+
+    let encrypted = bytes.starts_with(b"v10") || bytes.starts_with(b"v11");
+    (bytes, encrypted)
+}
+
+/// 将 Kiro 凭证 JSON 归一化为 camelCase 字段
+///
+/// 兼容 snake_case 字段名，并要求包含 refreshToken。
+fn normalize_kiro_credentials(content: &str) -> Result<serde_json::Value, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(content.trim()).map_err(|e| format!("凭证不是有效的 JSON: {}", e))?;
+    let object = value
+        .as_object()
+        .ok_or_else(|| "凭证 JSON 必须是对象".to_string())?;
+
+    let mut normalized = serde_json::Map::new();
+    for (key, value) in object {
+        let key = match key.as_str() {
+            "access_token" => "accessToken",
+            "refresh_token" => "refreshToken",
+            "expires_at" => "expiresAt",
+            "client_id_hash" => "clientIdHash",
+            "auth_method" => "authMethod",
+            "profile_arn" => "profileArn",
+            "client_id" => "clientId",
+            "client_secret" => "clientSecret",
+            other => other,
+        };
+        normalized.insert(key.to_string(), value.clone());
+    }
+
+    match normalized.get("refreshToken").and_then(|v| v.as_str()) {
+        Some(token) if !token.trim().is_empty() => Ok(serde_json::Value::Object(normalized)),
+        _ => Err("凭证缺少 refreshToken".to_string()),
+    }
+}
+
+fn source_summary(content: &str) -> (Option<String>, Option<String>) {
+    match normalize_kiro_credentials(content) {
+        Ok(creds) => (
+            creds
+                .get("authMethod")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            creds
+                .get("expiresAt")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+        ),
+        Err(_) => (None, None),
+    }
+}
+
+fn open_state_db(path: &std::path::Path) -> Result<rusqlite::Connection, String> {
+    rusqlite::Connection::open_with_flags(
+        path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| format!("打开 Kiro 状态数据库失败: {}", e))
+}
+
+/// 扫描 Kiro 应用状态数据库中的认证条目
+fn discover_app_state_sources(
+    db_path: &std::path::Path,
+) -> Result<Vec<KiroCredentialSource>, String> {
+    let conn = open_state_db(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT key, value FROM ItemTable \
+             WHERE key LIKE '%kiro%' AND (key LIKE '%token%' OR key LIKE '%auth%')",
+        )
+        .map_err(|e| format!("查询 Kiro 状态数据库失败: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("查询 Kiro 状态数据库失败: {}", e))?;
+
+    let mut sources = Vec::new();
+    for (key, raw) in rows.flatten() {
+        let (bytes, encrypted) = decode_state_value(&raw);
+        let (auth_method, expires_at) = if encrypted {
+            (None, None)
+        } else {
+            match String::from_utf8(bytes) {
+                Ok(content) if normalize_kiro_credentials(&content).is_ok() => {
+                    source_summary(&content)
+                }
+                _ => continue,
+            }
+        };
+        sources.push(KiroCredentialSource {
+            kind: KiroCredentialSourceKind::AppState,
+            path: db_path.to_string_lossy().to_string(),
+            key: Some(key),
+            encrypted,
+            auth_method,
+            expires_at,
+        });
+    }
+    Ok(sources)
+}
+
+/// 读取系统钥匙串中的 Kiro safeStorage 密码，返回 (密码, PBKDF2 迭代次数)
+#[cfg(target_os = "macos")]
+fn get_safe_storage_password(_version: &[u8]) -> Result<(String, usize), String> {
+    let output = std::process::Command::new("security")
+        .args(["find-generic-password", "-w", "-s", "Kiro Safe Storage"])
+        .output()
+        .map_err(|e| format!("访问钥匙串失败: {}", e))?;
+    if !output.status.success() {
+        return Err("无法从钥匙串读取 Kiro Safe Storage 密码（可能被拒绝访问）".to_string());
+    }
+    Ok((
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        1003,
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn get_safe_storage_password(version: &[u8]) -> Result<(String, usize), String> {
+    // v10 使用 Chromium 的固定密码，v11 存储在 libsecret 中
+    if version == b"v10" {
+        return Ok(("peanuts".to_string(), 1));
+    }
+    let output = std::process::Command::new("secret-tool")
+        .args(["lookup", "application", "kiro"])
+        .output()
+        .map_err(|e| format!("访问系统密钥环失败: {}", e))?;
+    let password = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || password.is_empty() {
+        return Err("无法从系统密钥环读取 Kiro safeStorage 密码".to_string());
+    }
+    Ok((password, 1))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn get_safe_storage_password(_version: &[u8]) -> Result<(String, usize), String> {
+    Err(
+        "当前平台暂不支持解密 Kiro 加密凭证，请在 Kiro 中重新登录以生成 kiro-auth-token.json"
+            .to_string(),
+    )
+}
+
+/// 解密 Electron safeStorage 加密的数据（AES-128-CBC）
+fn decrypt_safe_storage(data: &[u8]) -> Result<String, String> {
+    use openssl::hash::MessageDigest;
+    use openssl::pkcs5::pbkdf2_hmac;
+    use openssl::symm::{decrypt, Cipher};
+
+    if data.len() <= 3 {
+        return Err("加密凭证数据无效".to_string());
+    }
+    let (version, ciphertext) = data.split_at(3);
+    let (password, iterations) = get_safe_storage_password(version)?;
+
+    let mut key = [0u8; 16];
+    pbkdf2_hmac(
+        password.as_bytes(),
+        b"saltysalt",
+        iterations,
+        MessageDigest::sha1(),
+        &mut key,
+    )
+    .map_err(|e| format!("派生解密密钥失败: {}", e))?;
+
+    let plaintext = decrypt(Cipher::aes_128_cbc(), &key, Some(&[b' '; 16]), ciphertext)
+        .map_err(|_| "解密 Kiro 凭证失败：钥匙串密码不匹配".to_string())?;
+    String::from_utf8(plaintext).map_err(|e| format!("解密后的凭证不是有效的 UTF-8: {}", e))
+}
+
+/// 读取凭证来源的明文内容
+///
+/// 来源路径由前端传入，只允许读取已知的 Kiro 安装和 cache 位置。
+fn read_credential_source(source: &KiroCredentialSource) -> Result<String, String> {
+    if !is_known_source_path(
+        source.kind,
+        Path::new(&source.path),
+        get_kiro_sso_cache_dir().as_deref(),
+        get_kiro_app_state_db_path().as_deref(),
+    ) {
+        return Err(format!("不是已知的 Kiro 凭证位置: {}", source.path));
+    }
+
+    match source.kind {
+        KiroCredentialSourceKind::SsoCache => {
+            fs::read_to_string(&source.path).map_err(|e| format!("读取凭证文件失败: {}", e))
+        }
+        KiroCredentialSourceKind::AppState => {
+            let key = source
+                .key
+                .as_deref()
+                .ok_or_else(|| "缺少状态数据库键".to_string())?;
+            let conn = open_state_db(std::path::Path::new(&source.path))?;
+            let raw: String = conn
+                .query_row("SELECT value FROM ItemTable WHERE key = ?1", [key], |row| {
+                    row.get(0)
+                })
+                .map_err(|e| format!("读取 Kiro 状态数据库失败: {}", e))?;
+
+            let (bytes, encrypted) = decode_state_value(&raw);
+            if encrypted {
+                decrypt_safe_storage(&bytes)
+            } else {
+                String::from_utf8(bytes).map_err(|e| format!("凭证不是有效的 UTF-8: {}", e))
+            }
+        }
+    }
+}
+
+/// 发现本机已安装的 Kiro 客户端中的凭证来源
+///
+/// 包括 ~/.aws/sso/cache 下的 token 文件和 Kiro 应用状态数据库中的认证条目。
+/// 发现阶段不会解密，避免触发钥匙串授权弹窗。
+#[tauri::command]
+pub async fn discover_kiro_installations() -> Result<Vec<KiroCredentialSource>, String> {
+    let mut sources = Vec::new();
+
+    if let Some(cache_dir) = get_kiro_sso_cache_dir() {
+        if let Ok(entries) = fs::read_dir(&cache_dir) {
+            let mut paths: Vec<PathBuf> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    is_kiro_token_file_name(path.file_name().and_then(|n| n.to_str()).unwrap_or(""))
+                })
+                .collect();
+            paths.sort();
+
+            for path in paths {
+                let Ok(content) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                if normalize_kiro_credentials(&content).is_err() {
+                    continue;
+                }
+                let (auth_method, expires_at) = source_summary(&content);
+                sources.push(KiroCredentialSource {
+                    kind: KiroCredentialSourceKind::SsoCache,
+                    path: path.to_string_lossy().to_string(),
+                    key: None,
+                    encrypted: false,
+                    auth_method,
+                    expires_at,
+                });
+            }
+        }
+    }
+
+    if let Some(db_path) = get_kiro_app_state_db_path().filter(|p| p.exists()) {
+        match discover_app_state_sources(&db_path) {
+            Ok(found) => sources.extend(found),
+            Err(e) => tracing::warn!("[KIRO_IMPORT] 扫描 Kiro 状态数据库失败: {}", e),
+        }
+    }
+
+    tracing::info!("[KIRO_IMPORT] 发现 {} 个 Kiro 凭证来源", sources.len());
+    Ok(sources)
+}
+
+/// 不刷新 Token 验证凭证
+///
+/// 刷新会轮换 refresh token，导致 Kiro IDE 中的登录失效，因此只用现有的
+/// access token 调用用量接口验证。access token 已过期（或缺少 social
+/// 认证所需的 profileArn）时只做格式校验，首次使用时再由凭证池刷新。
+async fn validate_without_refresh(provider: &KiroProvider) -> Result<(), String> {
+    provider.validate_refresh_token()?;
+
+    let creds = &provider.credentials;
+    let auth_method = provider.detect_auth_method();
+    let access_token = match creds.access_token.as_deref() {
+        Some(token)
+            if !token.is_empty()
+                && !provider.is_token_expiring_soon()
+                && (auth_method != "social" || creds.profile_arn.is_some()) =>
+        {
+            token
+        }
+        _ => {
+            tracing::info!("[KIRO_IMPORT] access token 不可用，跳过在线验证");
+            return Ok(());
+        }
+    };
+
+    let machine_id = generate_machine_id_from_credentials(
+        creds.profile_arn.as_deref(),
+        creds.client_id.as_deref(),
+    );
+    usage_service::get_usage_limits(
+        access_token,
+        &auth_method,
+        creds.profile_arn.as_deref(),
+        &machine_id,
+        DEFAULT_KIRO_VERSION,
+    )
+    .await
+    .map(|_| ())
+    .map_err(|e| format!("凭证验证失败: {}", e))
+}
+
+/// 从本机 Kiro 安装导入凭证到凭证池
+///
+/// 读取（必要时解密）并归一化凭证的副本，不刷新 Token 验证有效后才保存，
+/// Kiro IDE 自身的登录状态保持不变。
+#[tauri::command]
+pub async fn import_kiro_from_installation(
+    source: KiroCredentialSource,
+    name: Option<String>,
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+) -> Result<ProviderCredential, String> {
+    tracing::info!(
+        "[KIRO_IMPORT] 从 {:?} 导入凭证: {}",
+        source.kind,
+        source.path
+    );
+
+    let content = read_credential_source(&source)?;
+    let creds = normalize_kiro_credentials(&content)?;
+    let json_content =
+        serde_json::to_string_pretty(&creds).map_err(|e| format!("序列化凭证失败: {}", e))?;
+    let stored_file_path = create_kiro_credential_from_json(&json_content)?;
+
+    // 保存前验证凭证：验证通过才加入凭证池
+    let mut provider = KiroProvider::new();
+    let validation = async {
+        provider
+            .load_credentials_from_path(&stored_file_path)
+            .await
+            .map_err(|e| format!("加载凭证失败: {}", e))?;
+        validate_without_refresh(&provider).await
+    }
+    .await;
+    if let Err(e) = validation {
+        tracing::error!("[KIRO_IMPORT] {}", e);
+        let _ = cleanup_credential_file(&stored_file_path);
+        return Err(e);
+    }
+
+    let credential = pool_service.0.add_credential(
+        &db,
+        "kiro",
+        CredentialData::KiroOAuth {
+            creds_file_path: stored_file_path,
+        },
+        name,
+        Some(true),
+        None,
+    )?;
+
+    tracing::info!("[KIRO_IMPORT] 凭证导入成功，UUID: {}", credential.uuid);
+    Ok(credential)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_snake_case_credentials() {
+        let creds = normalize_kiro_credentials(
+            r#"{"access_token":"a","refresh_token":"r","auth_method":"social","region":"us-east-1"}"#,
+        )
+        .unwrap();
+        assert_eq!(creds["accessToken"], "a");
+        assert_eq!(creds["refreshToken"], "r");
+        assert_eq!(creds["authMethod"], "social");
+        assert_eq!(creds["region"], "us-east-1");
+    }
+
+    #[test]
+    fn test_normalize_requires_refresh_token() {
+        assert!(normalize_kiro_credentials(r#"{"accessToken":"a"}"#).is_err());
+        assert!(normalize_kiro_credentials(r#"{"refreshToken":"  "}"#).is_err());
+        assert!(normalize_kiro_credentials("not json").is_err());
+    }
+
+    #[test]
+    fn test_known_source_paths() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache_dir = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache_dir).unwrap();
+        let token = cache_dir.join("kiro-auth-token.json");
+        let other = cache_dir.join("other.json");
+        let outside = temp_dir.path().join("kiro-auth-token.json");
+        let db = temp_dir.path().join("state.vscdb");
+        for path in [&token, &other, &outside, &db] {
+            fs::write(path, "{}").unwrap();
+        }
+
+        let sso = KiroCredentialSourceKind::SsoCache;
+        let app = KiroCredentialSourceKind::AppState;
+        let known =
+            |kind, path: &Path| is_known_source_path(kind, path, Some(&cache_dir), Some(&db));
+
+        assert!(known(sso, &token));
+        assert!(known(app, &db));
+        assert!(!known(sso, &other));
+        assert!(!known(sso, &outside));
+        assert!(!known(
+            sso,
+            &cache_dir.join("..").join("kiro-auth-token.json")
+        ));
+        assert!(!known(sso, &cache_dir.join("kiro-auth-token-missing.json")));
+        assert!(!known(app, &token));
+        assert!(!is_known_source_path(sso, &token, None, None));
+    }
+
+    #[test]
+    fn test_decode_state_value_detects_encrypted_buffer() {
+        let (bytes, encrypted) = decode_state_value(r#"{"type":"Buffer","data":[118,49,48,1,2]}"#);
+        assert!(encrypted);
+        assert_eq!(bytes, vec![118, 49, 48, 1, 2]);
+
+        let plain = r#"{"refreshToken":"r"}"#;
+        let (bytes, encrypted) = decode_state_value(plain);
+        assert!(!encrypted);
+        assert_eq!(bytes, plain.as_bytes());
+    }
+}
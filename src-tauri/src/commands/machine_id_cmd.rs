@@ -54,6 +54,35 @@ pub async fn check_admin_privileges(
     service.check_admin_privileges().await
 }
 
+/// 审计各系统位置的机器码是否一致
+#[tauri::command]
+pub async fn audit_machine_id(
+    service: State<'_, MachineIdState>,
+) -> Result<MachineIdAudit, String> {
+    let service = service.read().await;
+    service.audit_machine_id().await
+}
+
+/// 将规范机器码写入所有系统位置（写入前自动备份原值）
+#[tauri::command]
+pub async fn repair_machine_id(
+    canonical_id: String,
+    service: State<'_, MachineIdState>,
+) -> Result<MachineIdRepairResult, String> {
+    let service = service.read().await;
+    service.repair_machine_id(&canonical_id).await
+}
+
+/// 从修复备份恢复各系统位置的原值
+#[tauri::command]
+pub async fn undo_machine_id_repair(
+    backup_path: String,
+    service: State<'_, MachineIdState>,
+) -> Result<MachineIdRepairResult, String> {
+    let service = service.read().await;
+    service.undo_machine_id_repair(&backup_path).await
+}
+
 /// 获取操作系统类型
 #[tauri::command]
 pub async fn get_os_type() -> Result<String, String> {
@@ -5,6 +5,7 @@ pub mod browser_interceptor_cmd;
 pub mod config_cmd;
 pub mod connect_cmd;
 pub mod connection_cmd;
+pub mod database_cmd;
 pub mod flow_monitor_cmd;
 pub mod injection_cmd;
 pub mod kiro_local;
@@ -2,7 +2,9 @@
 //!
 //! 提供动态模型配置的 Tauri 命令
 
-use crate::config::{save_config, ModelInfo, ModelsConfig, ProviderModelsConfig};
+use crate::config::{
+    save_config, ConfigChangeSource, ModelInfo, ModelsConfig, ProviderModelsConfig,
+};
 use crate::AppState;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -25,6 +27,14 @@ pub async fn save_models_config(
     state.config.models = config;
     // 保存配置到文件
     save_config(&state.config).map_err(|e| e.to_string())?;
+    let (config, config_manager) = (state.config.clone(), state.config_manager.clone());
+    drop(state);
+    // 通知配置观察者，更新运行中服务器的 max_tokens 限制
+    if let Some(manager) = config_manager {
+        manager
+            .update_config(config, ConfigChangeSource::ApiCall)
+            .await;
+    }
     Ok(())
 }
 
@@ -106,6 +116,8 @@ pub async fn add_model_to_provider(
             id: model_id,
             name: model_name,
             enabled: true,
+            default_max_tokens: None,
+            max_output_tokens: None,
         });
     } else {
         return Err(format!("Provider {} 不存在", provider));
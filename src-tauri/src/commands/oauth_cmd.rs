@@ -491,3 +491,78 @@ pub async fn get_all_oauth_credentials(
 
     Ok(results)
 }
+
+/// Availability of a local OAuth callback port
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthCallbackPortStatus {
+    pub port: u16,
+    pub available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check whether a local OAuth callback port can be bound
+#[tauri::command]
+pub async fn check_oauth_callback_port(port: u16) -> Result<OAuthCallbackPortStatus, String> {
+    if port == 0 {
+        return Err("Invalid port: 0".to_string());
+    }
+    let status = match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(_) => OAuthCallbackPortStatus {
+            port,
+            available: true,
+            error: None,
+        },
+        Err(e) => OAuthCallbackPortStatus {
+            port,
+            available: false,
+            error: Some(e.to_string()),
+        },
+    };
+    Ok(status)
+}
+
+/// Bind the first free port in the configured callback port range
+pub async fn bind_oauth_callback_port(
+    settings: &crate::config::OAuthCallbackSettings,
+) -> Result<tokio::net::TcpListener, String> {
+    let mut last_error = None;
+    for port in settings.port_range_start.max(1)..=settings.port_range_end {
+        match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) => {
+                tracing::debug!("[OAuth] callback port {} unavailable: {}", port, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(format!(
+        "No free OAuth callback port in {}-{}{}",
+        settings.port_range_start,
+        settings.port_range_end,
+        last_error.map(|e| format!(": {}", e)).unwrap_or_default()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_oauth_callback_port_skips_taken_port() {
+        let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let settings = crate::config::OAuthCallbackSettings {
+            port_range_start: port,
+            port_range_end: port,
+        };
+
+        assert!(!check_oauth_callback_port(port).await.unwrap().available);
+        assert!(bind_oauth_callback_port(&settings).await.is_err());
+
+        drop(taken);
+        assert!(check_oauth_callback_port(port).await.unwrap().available);
+        let listener = bind_oauth_callback_port(&settings).await.unwrap();
+        assert_eq!(listener.local_addr().unwrap().port(), port);
+    }
+}
@@ -9,8 +9,10 @@ use crate::models::provider_pool_model::{
     AddCredentialRequest, CredentialData, CredentialDisplay, HealthCheckResult, OAuthStatus,
     PoolProviderType, ProviderCredential, ProviderPoolOverview, UpdateCredentialRequest,
 };
-use crate::services::provider_pool_service::ProviderPoolService;
-use chrono::Utc;
+use crate::services::provider_pool_service::{BulkCredentialUpdate, ProviderPoolService};
+use crate::services::token_cache_service::CredentialTokenCacheStatus;
+use crate::TokenCacheServiceState;
+use chrono::{TimeZone, Utc};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -225,7 +227,7 @@ fn copy_and_rename_credential_file(
 }
 
 /// 删除凭证文件（如果在应用存储目录中）
-fn cleanup_credential_file(file_path: &str) -> Result<(), String> {
+pub(crate) fn cleanup_credential_file(file_path: &str) -> Result<(), String> {
     let path = Path::new(file_path);
 
     // 只删除在应用凭证存储目录中的文件
@@ -316,6 +318,7 @@ pub fn update_provider_pool_credential(
         request.check_model_name,
         request.not_supported_models
     );
+    credential_account_service::invalidate(&uuid);
     // 如果需要重新上传文件，先处理文件上传
     let credential = if let Some(new_file_path) = request.new_creds_file_path {
         // 获取当前凭证以确定类型
@@ -527,6 +530,7 @@ pub fn delete_provider_pool_credential(
 ) -> Result<bool, String> {
     // 从数据库删除
     let result = pool_service.0.delete_credential(&db, &uuid)?;
+    credential_account_service::invalidate(&uuid);
 
     // 同步到 YAML 配置（如果同步服务可用且提供了 provider_type）
     if let Some(ref sync) = sync_service.0 {
@@ -556,6 +560,92 @@ pub fn toggle_provider_pool_credential(
         .update_credential(&db, &uuid, None, Some(is_disabled), None, None, None, None)
 }
 
+/// 设置凭证层级
+///
+/// 传入 None 或空字符串清除层级
+#[tauri::command]
+pub fn set_provider_pool_credential_tier(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    uuid: String,
+    tier: Option<String>,
+) -> Result<ProviderCredential, String> {
+    pool_service.0.set_credential_tier(&db, &uuid, tier)
+}
+
+/// 暂停凭证直到指定时间（Unix 秒）
+///
+/// 暂停期间选择时跳过该凭证，到期后自动恢复，不改变启用和健康状态
+#[tauri::command]
+pub fn park_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    uuid: String,
+    until_timestamp: i64,
+) -> Result<ProviderCredential, String> {
+    let until = Utc
+        .timestamp_opt(until_timestamp, 0)
+        .single()
+        .ok_or_else(|| format!("Invalid timestamp: {}", until_timestamp))?;
+    pool_service.0.park_credential(&db, &uuid, until)
+}
+
+/// 立即取消凭证暂停
+#[tauri::command]
+pub fn unpark_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    uuid: String,
+) -> Result<ProviderCredential, String> {
+    pool_service.0.unpark_credential(&db, &uuid)
+}
+
+/// 批量更新同一类型的凭证
+///
+/// 支持按模板重命名（`{n}` 序号、`{name}` 原名称）、批量启用/禁用和设置层级，
+/// 重命名后名称在该类型内必须唯一。返回实际变化的凭证 UUID
+#[tauri::command]
+pub fn bulk_update_credentials(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    provider_type: String,
+    updates: BulkCredentialUpdate,
+) -> Result<Vec<String>, String> {
+    pool_service
+        .0
+        .bulk_update_credentials(&db, &provider_type, &updates)
+}
+
+/// 设置凭证级上游请求头
+///
+/// 覆盖 `upstream_headers` 配置中的同名请求头（如特定账号需要的 User-Agent），传入空对象清除
+#[tauri::command]
+pub fn set_provider_pool_credential_upstream_headers(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    uuid: String,
+    headers: HashMap<String, String>,
+) -> Result<ProviderCredential, String> {
+    pool_service
+        .0
+        .set_credential_upstream_headers(&db, &uuid, headers)
+}
+
+/// 设置或清除 Antigravity 凭证缓存的项目 ID
+///
+/// 清除后下次请求会重新发现项目 ID 并写回凭证
+#[tauri::command]
+pub fn set_provider_pool_credential_project_id(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    uuid: String,
+    project_id: Option<String>,
+) -> Result<ProviderCredential, String> {
+    pool_service
+        .0
+        .set_antigravity_project_id(&db, &uuid, project_id)
+}
+
 /// 重置凭证计数器
 #[tauri::command]
 pub fn reset_provider_pool_credential(
@@ -652,7 +742,7 @@ pub fn add_kiro_oauth_credential(
 /// 从 JSON 内容创建 Kiro 凭证文件并添加到凭证池
 ///
 /// 直接粘贴 JSON 内容，无需选择文件
-fn create_kiro_credential_from_json(json_content: &str) -> Result<String, String> {
+pub(crate) fn create_kiro_credential_from_json(json_content: &str) -> Result<String, String> {
     // 验证 JSON 格式
     let creds: serde_json::Value =
         serde_json::from_str(json_content).map_err(|e| format!("JSON 格式无效: {}", e))?;
@@ -1094,6 +1184,27 @@ pub async fn refresh_pool_credential_token(
     result
 }
 
+/// 获取各凭证的 Token 缓存状态（缓存情况、过期时间、最后刷新时间、命中/未命中次数）
+#[tauri::command]
+pub fn get_token_cache_status(
+    db: State<'_, DbConnection>,
+    token_cache: State<'_, TokenCacheServiceState>,
+) -> Result<Vec<CredentialTokenCacheStatus>, String> {
+    token_cache.0.list_cache_status(&db)
+}
+
+/// 清除 Token 缓存，下次使用时重新获取
+///
+/// 未指定 `uuid` 时清除所有凭证的缓存，返回清除的凭证数
+#[tauri::command]
+pub fn invalidate_token_cache(
+    db: State<'_, DbConnection>,
+    token_cache: State<'_, TokenCacheServiceState>,
+    uuid: Option<String>,
+) -> Result<usize, String> {
+    token_cache.0.invalidate(&db, uuid.as_deref())
+}
+
 /// 获取凭证的 OAuth 状态
 #[tauri::command]
 pub fn get_pool_credential_oauth_status(
@@ -1319,6 +1430,35 @@ pub struct MigrationResultResponse {
     pub errors: Vec<String>,
 }
 
+/// 在 `oauth_callback` 端口范围内绑定 OAuth 登录回调端口
+async fn bind_login_callback_port(
+    app_state: &crate::AppState,
+) -> Result<tokio::net::TcpListener, String> {
+    let settings = app_state.read().await.config.oauth_callback.clone();
+    crate::commands::oauth_cmd::bind_oauth_callback_port(&settings)
+        .await
+        .map_err(|e| format!("无法启动回调服务器: {}", e))
+}
+
+/// 绑定 Codex 登录回调端口
+///
+/// OpenAI OAuth 只接受 client_id 注册的固定回调地址，因此不使用 `oauth_callback` 端口范围。
+async fn bind_codex_callback_port() -> Result<tokio::net::TcpListener, String> {
+    let port = crate::providers::codex::OPENAI_OAUTH_CALLBACK_PORT;
+    let settings = crate::config::OAuthCallbackSettings {
+        port_range_start: port,
+        port_range_end: port,
+    };
+    crate::commands::oauth_cmd::bind_oauth_callback_port(&settings)
+        .await
+        .map_err(|e| {
+            format!(
+                "无法启动回调服务器: {}。OpenAI OAuth 要求使用固定端口 {}，请关闭占用该端口的应用后重试",
+                e, port
+            )
+        })
+}
+
 /// 获取 Antigravity OAuth 授权 URL 并等待回调（不自动打开浏览器）
 ///
 /// 启动服务器后通过事件发送授权 URL，然后等待回调
@@ -1331,6 +1471,7 @@ pub struct AntigravityAuthUrlResponse {
 #[tauri::command]
 pub async fn get_antigravity_auth_url_and_wait(
     app: tauri::AppHandle,
+    app_state: State<'_, crate::AppState>,
     db: State<'_, DbConnection>,
     pool_service: State<'_, ProviderPoolServiceState>,
     name: Option<String>,
@@ -1341,10 +1482,13 @@ pub async fn get_antigravity_auth_url_and_wait(
     tracing::info!("[Antigravity OAuth] 启动服务器并获取授权 URL");
 
     // 启动服务器并获取授权 URL
-    let (auth_url, wait_future) =
-        antigravity::start_oauth_server_and_get_url(skip_project_id_fetch.unwrap_or(false))
-            .await
-            .map_err(|e| format!("启动 OAuth 服务器失败: {}", e))?;
+    let listener = bind_login_callback_port(&app_state).await?;
+    let (auth_url, wait_future) = antigravity::start_oauth_server_and_get_url(
+        listener,
+        skip_project_id_fetch.unwrap_or(false),
+    )
+    .await
+    .map_err(|e| format!("启动 OAuth 服务器失败: {}", e))?;
 
     tracing::info!("[Antigravity OAuth] 授权 URL: {}", auth_url);
 
@@ -1393,6 +1537,7 @@ pub async fn get_antigravity_auth_url_and_wait(
 /// 打开浏览器让用户登录 Google 账号，获取 Antigravity 凭证
 #[tauri::command]
 pub async fn start_antigravity_oauth_login(
+    app_state: State<'_, crate::AppState>,
     db: State<'_, DbConnection>,
     pool_service: State<'_, ProviderPoolServiceState>,
     name: Option<String>,
@@ -1403,7 +1548,8 @@ pub async fn start_antigravity_oauth_login(
     tracing::info!("[Antigravity OAuth] 开始 OAuth 登录流程");
 
     // 启动 OAuth 登录
-    let result = antigravity::start_oauth_login(skip_project_id_fetch.unwrap_or(false))
+    let listener = bind_login_callback_port(&app_state).await?;
+    let result = antigravity::start_oauth_login(listener, skip_project_id_fetch.unwrap_or(false))
         .await
         .map_err(|e| format!("Antigravity OAuth 登录失败: {}", e))?;
 
@@ -1458,7 +1604,8 @@ pub async fn get_codex_auth_url_and_wait(
     tracing::info!("[Codex OAuth] 启动服务器并获取授权 URL");
 
     // 启动服务器并获取授权 URL
-    let (auth_url, wait_future) = codex::start_codex_oauth_server_and_get_url()
+    let listener = bind_codex_callback_port().await?;
+    let (auth_url, wait_future) = codex::start_codex_oauth_server_and_get_url(listener)
         .await
         .map_err(|e| format!("启动 OAuth 服务器失败: {}", e))?;
 
@@ -1512,7 +1659,8 @@ pub async fn start_codex_oauth_login(
     tracing::info!("[Codex OAuth] 开始 OAuth 登录流程");
 
     // 启动 OAuth 登录
-    let result = codex::start_codex_oauth_login()
+    let listener = bind_codex_callback_port().await?;
+    let result = codex::start_codex_oauth_login(listener)
         .await
         .map_err(|e| format!("Codex OAuth 登录失败: {}", e))?;
 
@@ -1854,6 +2002,7 @@ pub struct IFlowAuthUrlResponse {
 #[tauri::command]
 pub async fn get_iflow_auth_url_and_wait(
     app: tauri::AppHandle,
+    app_state: State<'_, crate::AppState>,
     db: State<'_, DbConnection>,
     pool_service: State<'_, ProviderPoolServiceState>,
     name: Option<String>,
@@ -1863,7 +2012,8 @@ pub async fn get_iflow_auth_url_and_wait(
     tracing::info!("[iFlow OAuth] 启动服务器并获取授权 URL");
 
     // 启动服务器并获取授权 URL
-    let (auth_url, wait_future) = iflow::start_iflow_oauth_server_and_get_url()
+    let listener = bind_login_callback_port(&app_state).await?;
+    let (auth_url, wait_future) = iflow::start_iflow_oauth_server_and_get_url(listener)
         .await
         .map_err(|e| format!("启动 OAuth 服务器失败: {}", e))?;
 
@@ -1907,6 +2057,7 @@ pub async fn get_iflow_auth_url_and_wait(
 /// 打开浏览器让用户登录 iFlow 账号，获取凭证
 #[tauri::command]
 pub async fn start_iflow_oauth_login(
+    app_state: State<'_, crate::AppState>,
     db: State<'_, DbConnection>,
     pool_service: State<'_, ProviderPoolServiceState>,
     name: Option<String>,
@@ -1916,7 +2067,8 @@ pub async fn start_iflow_oauth_login(
     tracing::info!("[iFlow OAuth] 开始 OAuth 登录流程");
 
     // 启动 OAuth 登录
-    let result = iflow::start_iflow_oauth_login()
+    let listener = bind_login_callback_port(&app_state).await?;
+    let result = iflow::start_iflow_oauth_login(listener)
         .await
         .map_err(|e| format!("iFlow OAuth 登录失败: {}", e))?;
 
@@ -1963,7 +2115,7 @@ pub async fn get_kiro_credential_fingerprint(
     uuid: String,
 ) -> Result<KiroFingerprintInfo, String> {
     use crate::database::dao::provider_pool::ProviderPoolDao;
-    use crate::providers::kiro::{generate_machine_id_from_credentials, KiroProvider};
+    use crate::providers::kiro::{credential_fingerprint, KiroProvider};
 
     // 获取凭证文件路径（在锁释放前完成）
     let creds_file_path = {
@@ -1986,25 +2138,7 @@ pub async fn get_kiro_credential_fingerprint(
         .await
         .map_err(|e| format!("加载凭证失败: {}", e))?;
 
-    // 确定指纹来源
-    let (source, profile_arn, client_id) = if provider.credentials.profile_arn.is_some() {
-        (
-            "profileArn".to_string(),
-            provider.credentials.profile_arn.as_deref(),
-            None,
-        )
-    } else if provider.credentials.client_id.is_some() {
-        (
-            "clientId".to_string(),
-            None,
-            provider.credentials.client_id.as_deref(),
-        )
-    } else {
-        ("system".to_string(), None, None)
-    };
-
-    // 生成 Machine ID
-    let machine_id = generate_machine_id_from_credentials(profile_arn, client_id);
+    let (machine_id, source) = credential_fingerprint(&provider.credentials);
     // 安全地截取前 16 个字符（避免越界 panic）
     let machine_id_short: String = machine_id.chars().take(16).collect();
 
@@ -2018,7 +2152,7 @@ pub async fn get_kiro_credential_fingerprint(
     Ok(KiroFingerprintInfo {
         machine_id,
         machine_id_short,
-        source,
+        source: source.to_string(),
         auth_method,
     })
 }
@@ -2154,6 +2288,7 @@ pub async fn exchange_gemini_code(
 /// 打开浏览器让用户登录 Google 账号，获取 Gemini 凭证
 #[tauri::command]
 pub async fn start_gemini_oauth_login(
+    app_state: State<'_, crate::AppState>,
     db: State<'_, DbConnection>,
     pool_service: State<'_, ProviderPoolServiceState>,
     name: Option<String>,
@@ -2163,7 +2298,8 @@ pub async fn start_gemini_oauth_login(
     tracing::info!("[Gemini OAuth] 开始 OAuth 登录流程");
 
     // 启动 OAuth 登录
-    let result = gemini::start_gemini_oauth_login()
+    let listener = bind_login_callback_port(&app_state).await?;
+    let result = gemini::start_gemini_oauth_login(listener)
         .await
         .map_err(|e| format!("Gemini OAuth 登录失败: {}", e))?;
 
@@ -2272,7 +2408,7 @@ pub async fn start_kiro_builder_id_login(
 
     // Step 1: 注册 OIDC 客户端
     tracing::info!("[Kiro Builder ID] Step 1: 注册 OIDC 客户端...");
-    let client = reqwest::Client::new();
+    let client = crate::http_client::client_for("kiro");
 
     let reg_body = serde_json::json!({
         "clientName": "ProxyCast Kiro Manager",
@@ -2432,7 +2568,7 @@ pub async fn poll_kiro_builder_id_auth() -> Result<KiroBuilderIdPollResponse, St
     }
 
     let oidc_base = format!("https://oidc.{}.amazonaws.com", state.region);
-    let client = reqwest::Client::new();
+    let client = crate::http_client::client_for("kiro");
 
     let token_body = serde_json::json!({
         "clientId": state.client_id,
@@ -2652,6 +2788,8 @@ struct KiroSocialAuthLoginState {
     code_challenge: String,
     /// OAuth state
     oauth_state: String,
+    /// 回调地址（交换 Token 时需与登录时一致）
+    redirect_uri: String,
     /// 过期时间戳
     expires_at: i64,
 }
@@ -2660,6 +2798,39 @@ struct KiroSocialAuthLoginState {
 static KIRO_SOCIAL_AUTH_LOGIN_STATE: Lazy<RwLock<Option<KiroSocialAuthLoginState>>> =
     Lazy::new(|| RwLock::new(None));
 
+/// 运行中的 Social Auth 回调服务器
+struct KiroSocialAuthCallbackServer {
+    /// 监听端口
+    port: u16,
+    /// 停止信号
+    shutdown: tokio::sync::oneshot::Sender<()>,
+    /// 端口释放后触发
+    released: tokio::sync::oneshot::Receiver<()>,
+}
+
+static KIRO_SOCIAL_AUTH_CALLBACK_SERVER: Lazy<RwLock<Option<KiroSocialAuthCallbackServer>>> =
+    Lazy::new(|| RwLock::new(None));
+
+/// 回调服务器最长等待时间（与登录状态过期时间一致）
+const KIRO_SOCIAL_AUTH_CALLBACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+fn kiro_social_auth_redirect_uri(port: u16) -> String {
+    format!("http://127.0.0.1:{}/kiro-social-callback", port)
+}
+
+/// 停止回调服务器并等待端口释放
+async fn stop_kiro_social_auth_callback_server() {
+    let server = KIRO_SOCIAL_AUTH_CALLBACK_SERVER.write().await.take();
+    if let Some(server) = server {
+        let _ = server.shutdown.send(());
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(2), server.released).await;
+        tracing::info!(
+            "[Kiro Social Auth] 回调服务器已停止，端口 {} 已释放",
+            server.port
+        );
+    }
+}
+
 /// Kiro Social Auth 登录启动响应
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct KiroSocialAuthLoginResponse {
@@ -2744,14 +2915,20 @@ pub async fn start_kiro_social_auth_login(
     let oauth_state = generate_oauth_state();
 
     // 构建登录 URL
-    // 使用本地回调服务器接收授权码
-    let redirect_uri = "http://127.0.0.1:19823/kiro-social-callback";
+    // 使用本地回调服务器接收授权码（未启动时使用默认端口）
+    let callback_port = KIRO_SOCIAL_AUTH_CALLBACK_SERVER
+        .read()
+        .await
+        .as_ref()
+        .map(|server| server.port)
+        .unwrap_or_else(|| crate::config::OAuthCallbackSettings::default().port_range_start);
+    let redirect_uri = kiro_social_auth_redirect_uri(callback_port);
 
     let login_url = format!(
         "{}/login?idp={}&redirect_uri={}&code_challenge={}&code_challenge_method=S256&state={}",
         KIRO_AUTH_ENDPOINT,
         provider_normalized,
-        urlencoding::encode(redirect_uri),
+        urlencoding::encode(&redirect_uri),
         urlencoding::encode(&code_challenge),
         urlencoding::encode(&oauth_state)
     );
@@ -2767,6 +2944,7 @@ pub async fn start_kiro_social_auth_login(
             code_verifier,
             code_challenge,
             oauth_state: oauth_state.clone(),
+            redirect_uri,
             expires_at,
         });
     }
@@ -2829,14 +3007,12 @@ pub async fn exchange_kiro_social_auth_token(
         });
     }
 
-    let redirect_uri = "http://127.0.0.1:19823/kiro-social-callback";
-
     // 交换 Token
-    let client = reqwest::Client::new();
+    let client = crate::http_client::client_for("kiro");
     let token_body = serde_json::json!({
         "code": code,
         "code_verifier": login_state.code_verifier,
-        "redirect_uri": redirect_uri
+        "redirect_uri": login_state.redirect_uri
     });
 
     let token_res = client
@@ -2912,6 +3088,7 @@ pub async fn cancel_kiro_social_auth_login() -> Result<bool, String> {
         let mut state = KIRO_SOCIAL_AUTH_LOGIN_STATE.write().await;
         *state = None;
     }
+    stop_kiro_social_auth_callback_server().await;
     Ok(true)
 }
 
@@ -3757,7 +3934,7 @@ pub async fn start_kiro_playwright_login(
     tracing::info!("[Playwright Login] 获取到授权码，开始交换 Token");
 
     // 交换 Token
-    let client = reqwest::Client::new();
+    let client = crate::http_client::client_for("kiro");
     let token_body = serde_json::json!({
         "code": auth_code,
         "code_verifier": code_verifier,
@@ -3869,26 +4046,54 @@ pub async fn cancel_kiro_playwright_login() -> Result<bool, String> {
 
 /// 启动 Kiro Social Auth 回调服务器
 ///
-/// 启动一个本地 HTTP 服务器来接收 OAuth 回调
+/// 启动一个本地 HTTP 服务器来接收 OAuth 回调，端口取 `oauth_callback` 范围内第一个可用端口，
+/// 返回实际使用的端口。已有回调服务器时先停止并释放其端口
 #[tauri::command]
-pub async fn start_kiro_social_auth_callback_server(app: tauri::AppHandle) -> Result<bool, String> {
+pub async fn start_kiro_social_auth_callback_server(
+    app: tauri::AppHandle,
+    app_state: State<'_, crate::AppState>,
+) -> Result<u16, String> {
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
-    use tokio::net::TcpListener;
 
     tracing::info!("[Kiro Social Auth] 启动回调服务器...");
+    stop_kiro_social_auth_callback_server().await;
 
-    // 尝试绑定端口
-    let listener = TcpListener::bind("127.0.0.1:19823")
-        .await
-        .map_err(|e| format!("无法启动回调服务器: {}", e))?;
+    // 在端口范围内绑定可用端口
+    let listener = bind_login_callback_port(&app_state).await?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
 
-    tracing::info!("[Kiro Social Auth] 回调服务器已启动在 127.0.0.1:19823");
+    tracing::info!("[Kiro Social Auth] 回调服务器已启动在 127.0.0.1:{}", port);
+
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    let (released_tx, released_rx) = tokio::sync::oneshot::channel();
+    *KIRO_SOCIAL_AUTH_CALLBACK_SERVER.write().await = Some(KiroSocialAuthCallbackServer {
+        port,
+        shutdown: shutdown_tx,
+        released: released_rx,
+    });
 
     // 在后台处理连接
     let app_handle = app.clone();
     tokio::spawn(async move {
-        // 只处理一个连接
-        if let Ok((mut socket, _)) = listener.accept().await {
+        // 只处理一个连接，取消或超时后停止监听
+        let accepted = tokio::select! {
+            result = listener.accept() => result.ok(),
+            _ = shutdown_rx => None,
+            _ = tokio::time::sleep(KIRO_SOCIAL_AUTH_CALLBACK_TIMEOUT) => {
+                tracing::info!("[Kiro Social Auth] 回调服务器等待超时");
+                None
+            }
+        };
+        drop(listener);
+        {
+            let mut server = KIRO_SOCIAL_AUTH_CALLBACK_SERVER.write().await;
+            if server.as_ref().is_some_and(|s| s.port == port) {
+                *server = None;
+            }
+        }
+        let _ = released_tx.send(());
+
+        if let Some((mut socket, _)) = accepted {
             let mut buffer = [0u8; 4096];
             if let Ok(n) = socket.read(&mut buffer).await {
                 let request = String::from_utf8_lossy(&buffer[..n]);
@@ -3973,7 +4178,7 @@ pub async fn start_kiro_social_auth_callback_server(app: tauri::AppHandle) -> Re
         }
     });
 
-    Ok(true)
+    Ok(port)
 }
 
 // ============ Playwright 可用性检测测试 ============
@@ -4150,3 +4355,229 @@ pub async fn get_all_credential_health(
 ) -> Result<Vec<crate::services::provider_pool_service::CredentialHealthInfo>, String> {
     pool_service.0.get_all_credential_health(&db)
 }
+
+// ============ 凭证池模板 ============
+
+use crate::config::{ConfigChangeSource, GlobalConfigManagerState};
+use crate::services::pool_template_service::PoolTemplate;
+
+/// 凭证池模板导出结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PoolTemplateExportResult {
+    /// 模板 JSON
+    pub content: String,
+    /// 建议的文件名
+    pub suggested_filename: String,
+    /// 包含的凭证数量
+    pub credential_count: usize,
+}
+
+/// 凭证池模板导入结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PoolTemplateImportResult {
+    /// 创建的占位凭证（需要填写机密后才会启用）
+    pub credentials: Vec<CredentialDisplay>,
+    /// 是否应用了模板中的路由配置
+    pub routing_applied: bool,
+}
+
+/// 导出凭证池模板
+///
+/// 包含 Provider 类型、名称、层级、模型策略和路由配置，机密替换为占位符，可用于团队共享
+#[tauri::command]
+pub async fn export_pool_template(
+    app_state: State<'_, crate::AppState>,
+    db: State<'_, DbConnection>,
+) -> Result<PoolTemplateExportResult, String> {
+    let credentials = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+    };
+    let routing = app_state.read().await.config.routing.clone();
+
+    let template = PoolTemplate::export(&credentials, &routing, env!("CARGO_PKG_VERSION"));
+    let content = template.to_json()?;
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+
+    Ok(PoolTemplateExportResult {
+        content,
+        suggested_filename: format!("proxycast_pool_template_{}.json", timestamp),
+        credential_count: template.credentials.len(),
+    })
+}
+
+/// 导入凭证池模板
+///
+/// 为模板中的每个条目创建占位凭证，占位凭证不参与选择，通过
+/// `fill_provider_pool_credential_secret` 填写机密后启用；`apply_routing` 为 true 时
+/// 同时用模板中的路由配置替换当前配置
+#[tauri::command]
+pub async fn import_pool_template(
+    app_state: State<'_, crate::AppState>,
+    config_manager: State<'_, GlobalConfigManagerState>,
+    db: State<'_, DbConnection>,
+    content: String,
+    apply_routing: bool,
+) -> Result<PoolTemplateImportResult, String> {
+    let template = PoolTemplate::from_json(&content)?;
+    let slots = template.credential_slots();
+    {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        for slot in &slots {
+            ProviderPoolDao::insert(&conn, slot).map_err(|e| e.to_string())?;
+        }
+    }
+
+    if apply_routing {
+        let mut s = app_state.write().await;
+        s.config.routing = template.routing.clone();
+        crate::config::save_config(&s.config).map_err(|e| e.to_string())?;
+        drop(s);
+        config_manager
+            .0
+            .update_routing(template.routing, ConfigChangeSource::FrontendUI)
+            .await;
+    }
+
+    tracing::info!(
+        "[POOL_TEMPLATE] 导入 {} 个占位凭证，应用路由配置: {}",
+        slots.len(),
+        apply_routing
+    );
+    Ok(PoolTemplateImportResult {
+        credentials: slots.iter().map(CredentialDisplay::from).collect(),
+        routing_applied: apply_routing,
+    })
+}
+
+/// 为模板导入的占位凭证填写机密
+///
+/// API Key 凭证传入 API Key，OAuth 凭证传入凭证文件路径（会复制到应用凭证目录）。
+/// 填写后凭证参与选择，并同步到 YAML 配置
+#[tauri::command]
+pub fn fill_provider_pool_credential_secret(
+    db: State<'_, DbConnection>,
+    sync_service: State<'_, CredentialSyncServiceState>,
+    uuid: String,
+    secret: String,
+) -> Result<ProviderCredential, String> {
+    let secret = secret.trim();
+    if secret.is_empty() {
+        return Err("机密不能为空".to_string());
+    }
+
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    let mut credential = ProviderPoolDao::get_by_uuid(&conn, &uuid)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("凭证不存在: {}", uuid))?;
+    if !credential.credential.needs_secret() {
+        return Err("该凭证不是等待填写机密的模板凭证".to_string());
+    }
+
+    *credential.credential.secret_mut() = if credential.credential.is_file_based() {
+        copy_and_rename_credential_file(secret, &credential.provider_type.to_string())?
+    } else {
+        secret.to_string()
+    };
+    credential.updated_at = Utc::now();
+    ProviderPoolDao::update(&conn, &credential).map_err(|e| e.to_string())?;
+    drop(conn);
+
+    // 占位凭证导入时未同步到 YAML，填写后再添加
+    if let Some(ref sync) = sync_service.0 {
+        if let Err(e) = sync.add_credential(&credential) {
+            tracing::warn!("同步凭证到 YAML 失败: {}", e);
+        }
+    }
+
+    Ok(credential)
+}
+
+// ============ 凭证去重 ============
+
+use crate::services::pool_dedupe_service::{self, DuplicateGroup};
+
+/// 凭证去重结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PoolDedupeReport {
+    /// 是否为预览（未修改数据）
+    pub dry_run: bool,
+    /// 重复凭证组
+    pub groups: Vec<DuplicateGroup>,
+    /// 删除（或将删除）的凭证数量
+    pub removed_count: usize,
+}
+
+/// 合并凭证池中的重复凭证
+///
+/// 按内容指纹（API Key / OAuth refresh token）识别重复，每组保留创建最早的凭证并合并
+/// 使用统计与健康状态。`dry_run` 为 true 时只返回将要合并的内容，不修改数据
+#[tauri::command]
+pub fn dedupe_pool_credentials(
+    db: State<'_, DbConnection>,
+    sync_service: State<'_, CredentialSyncServiceState>,
+    dry_run: bool,
+) -> Result<PoolDedupeReport, String> {
+    let mut conn = db.lock().map_err(|e| e.to_string())?;
+    let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+    let groups = pool_dedupe_service::find_duplicates(&credentials);
+    let removed_count = groups.iter().map(|g| g.merged.len()).sum();
+
+    if !dry_run && !groups.is_empty() {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for group in &groups {
+            let mut kept = group.kept.clone();
+            kept.updated_at = Utc::now();
+            ProviderPoolDao::update(&tx, &kept).map_err(|e| e.to_string())?;
+            for merged in &group.merged {
+                ProviderPoolDao::delete(&tx, &merged.uuid).map_err(|e| e.to_string())?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        drop(conn);
+
+        if let Some(ref sync) = sync_service.0 {
+            for group in &groups {
+                for merged in &group.merged {
+                    if let Err(e) = sync.remove_credential(group.provider_type, &merged.uuid) {
+                        tracing::warn!("从 YAML 删除凭证失败: {}", e);
+                    }
+                }
+            }
+        }
+
+        tracing::info!(
+            "[POOL_DEDUPE] 合并 {} 组重复凭证，删除 {} 条",
+            groups.len(),
+            removed_count
+        );
+    }
+
+    Ok(PoolDedupeReport {
+        dry_run,
+        groups,
+        removed_count,
+    })
+}
+
+// ============ 凭证账号信息 ============
+
+use crate::services::credential_account_service::{self, CredentialAccountInfo};
+
+/// 查询凭证对应的上游账号信息
+///
+/// 按 Provider 类型调用身份接口或读取凭证文件，返回账号标识（邮箱、账号 ID 等），
+/// 用于区分同一 Provider 的多个账号。结果缓存 5 分钟
+#[tauri::command]
+pub async fn get_credential_account_info(
+    db: State<'_, DbConnection>,
+    uuid: String,
+) -> Result<CredentialAccountInfo, String> {
+    let credential = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_by_uuid(&conn, &uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("凭证不存在: {}", uuid))?
+    };
+    credential_account_service::get_account_info(&credential).await
+}
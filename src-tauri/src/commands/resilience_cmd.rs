@@ -1,43 +1,50 @@
 //! 容错配置相关 Tauri 命令
 
-use crate::resilience::{FailoverConfig, RetryConfig};
+use crate::config::{save_config, ConfigChangeSource, GlobalConfigManagerState};
+use crate::database::DbConnection;
+use crate::resilience::{FailoverConfig, RetryConfig, SwitchLog, SwitchLogPage, SwitchLogQuery};
+use crate::AppState;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tauri::State;
 use tokio::sync::RwLock;
 
+pub use crate::resilience::SwitchLogEntry;
+
 /// 容错配置状态
+///
+/// 重试策略保存在配置文件的 `retry` 中，由请求路径直接读取，不在此保存
 pub struct ResilienceConfigState {
-    pub retry_config: Arc<RwLock<RetryConfig>>,
     pub failover_config: Arc<RwLock<FailoverConfig>>,
-    pub switch_log: Arc<RwLock<Vec<SwitchLogEntry>>>,
+    /// 切换日志（与凭证池服务共享，记录凭证切换和降级）
+    pub switch_log: Arc<SwitchLog>,
 }
 
 impl Default for ResilienceConfigState {
     fn default() -> Self {
         Self {
-            retry_config: Arc::new(RwLock::new(RetryConfig::default())),
             failover_config: Arc::new(RwLock::new(FailoverConfig::default())),
-            switch_log: Arc::new(RwLock::new(Vec::new())),
+            switch_log: Arc::new(SwitchLog::new()),
         }
     }
 }
 
-/// 切换日志条目（用于前端显示）
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SwitchLogEntry {
-    pub from_provider: String,
-    pub to_provider: String,
-    pub failure_type: String,
-    pub timestamp: String,
-}
-
 /// 重试配置 DTO（用于前端）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetryConfigDto {
     pub max_retries: u32,
     pub base_delay_ms: u64,
     pub max_delay_ms: u64,
-    pub retryable_codes: Vec<u16>,
+    #[serde(alias = "retryable_codes")]
+    pub retryable_status_codes: Vec<u16>,
+    #[serde(default = "default_true")]
+    pub retry_on_connection_error: bool,
+    #[serde(default)]
+    pub retry_streaming_server_errors: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl From<RetryConfig> for RetryConfigDto {
@@ -46,7 +53,9 @@ impl From<RetryConfig> for RetryConfigDto {
             max_retries: config.max_retries,
             base_delay_ms: config.base_delay_ms,
             max_delay_ms: config.max_delay_ms,
-            retryable_codes: config.retryable_codes,
+            retryable_status_codes: config.retryable_status_codes,
+            retry_on_connection_error: config.retry_on_connection_error,
+            retry_streaming_server_errors: config.retry_streaming_server_errors,
         }
     }
 }
@@ -57,7 +66,9 @@ impl From<RetryConfigDto> for RetryConfig {
             max_retries: dto.max_retries,
             base_delay_ms: dto.base_delay_ms,
             max_delay_ms: dto.max_delay_ms,
-            retryable_codes: dto.retryable_codes,
+            retryable_status_codes: dto.retryable_status_codes,
+            retry_on_connection_error: dto.retry_on_connection_error,
+            retry_streaming_server_errors: dto.retry_streaming_server_errors,
         }
     }
 }
@@ -89,17 +100,16 @@ impl From<FailoverConfigDto> for FailoverConfig {
 
 /// 获取重试配置
 #[tauri::command]
-pub async fn get_retry_config(
-    state: tauri::State<'_, ResilienceConfigState>,
-) -> Result<RetryConfigDto, String> {
-    let config = state.retry_config.read().await;
-    Ok(RetryConfigDto::from(config.clone()))
+pub async fn get_retry_config(state: tauri::State<'_, AppState>) -> Result<RetryConfigDto, String> {
+    let s = state.read().await;
+    Ok(RetryConfigDto::from(RetryConfig::from(&s.config.retry)))
 }
 
-/// 更新重试配置
+/// 更新重试配置（保存到配置文件并立即生效）
 #[tauri::command]
 pub async fn update_retry_config(
-    state: tauri::State<'_, ResilienceConfigState>,
+    state: tauri::State<'_, AppState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
     config: RetryConfigDto,
 ) -> Result<(), String> {
     // 验证配置
@@ -116,8 +126,22 @@ pub async fn update_retry_config(
         return Err("最大延迟不能超过 120 秒".to_string());
     }
 
-    let mut retry_config = state.retry_config.write().await;
-    *retry_config = RetryConfig::from(config);
+    let mut s = state.write().await;
+    let retry = &mut s.config.retry;
+    retry.max_retries = config.max_retries;
+    retry.base_delay_ms = config.base_delay_ms;
+    retry.max_delay_ms = config.max_delay_ms;
+    retry.retryable_status_codes = config.retryable_status_codes;
+    retry.retry_on_connection_error = config.retry_on_connection_error;
+    retry.retry_streaming_server_errors = config.retry_streaming_server_errors;
+    save_config(&s.config).map_err(|e| e.to_string())?;
+    let updated = s.config.clone();
+    drop(s);
+
+    // 通知配置观察者，请求路径的重试策略立即生效
+    config_manager
+        .update_config(updated, ConfigChangeSource::FrontendUI)
+        .await;
     Ok(())
 }
 
@@ -141,42 +165,22 @@ pub async fn update_failover_config(
     Ok(())
 }
 
-/// 获取切换日志
+/// 查询切换日志（按时间倒序分页，可按原因、Provider、凭证、请求 ID 和时间范围过滤）
 #[tauri::command]
 pub async fn get_switch_log(
-    state: tauri::State<'_, ResilienceConfigState>,
-) -> Result<Vec<SwitchLogEntry>, String> {
-    let log = state.switch_log.read().await;
-    Ok(log.clone())
+    db: State<'_, DbConnection>,
+    query: Option<SwitchLogQuery>,
+) -> Result<SwitchLogPage, String> {
+    let mut query = query.unwrap_or_default();
+    query.limit = query.limit.clamp(1, 500);
+    SwitchLog::list(&db, &query)
 }
 
-/// 清除切换日志
+/// 清除切换日志，返回删除的条数
 #[tauri::command]
 pub async fn clear_switch_log(
-    state: tauri::State<'_, ResilienceConfigState>,
-) -> Result<(), String> {
-    let mut log = state.switch_log.write().await;
-    log.clear();
-    Ok(())
-}
-
-/// 添加切换日志条目（内部使用）
-#[allow(dead_code)]
-pub async fn add_switch_log_entry(
-    state: &ResilienceConfigState,
-    from_provider: &str,
-    to_provider: &str,
-    failure_type: &str,
-) {
-    let mut log = state.switch_log.write().await;
-    log.push(SwitchLogEntry {
-        from_provider: from_provider.to_string(),
-        to_provider: to_provider.to_string(),
-        failure_type: failure_type.to_string(),
-        timestamp: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-    });
-    // 保留最近 100 条记录
-    if log.len() > 100 {
-        log.remove(0);
-    }
+    state: State<'_, ResilienceConfigState>,
+    db: State<'_, DbConnection>,
+) -> Result<usize, String> {
+    state.switch_log.clear(&db)
 }
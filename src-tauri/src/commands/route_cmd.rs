@@ -1,15 +1,21 @@
 //! 路由相关 Tauri 命令
 
 use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
-use crate::config;
+use crate::config::{self, ConfigChangeSource, GlobalConfigManagerState, TierRule};
+use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
-use crate::models::route_model::{RouteInfo, RouteListResponse};
+use crate::models::provider_pool_model::pattern_matches;
+use crate::models::route_model::{RouteInfo, RouteListResponse, RouteQuery};
+use crate::router::{lint_tier_rules, ModelMapper, ModelResolution, Router, RoutingRuleWarning};
+use crate::ProviderType;
+use serde::Serialize;
 
 /// 获取所有可用的路由端点
 #[tauri::command]
 pub async fn get_available_routes(
     db: tauri::State<'_, DbConnection>,
     pool_service: tauri::State<'_, ProviderPoolServiceState>,
+    query: Option<RouteQuery>,
 ) -> Result<RouteListResponse, String> {
     // 获取配置中的服务器地址和默认 Provider
     let config = config::load_config().unwrap_or_default();
@@ -21,33 +27,13 @@ pub async fn get_available_routes(
         .get_available_routes(db.inner(), &base_url)
         .map_err(|e| e.to_string())?;
 
-    // 添加默认路由，使用配置中的默认 Provider
-    let mut all_routes = vec![RouteInfo {
-        selector: "default".to_string(),
-        provider_type: default_provider.clone(),
-        credential_count: 1,
-        endpoints: vec![
-            crate::models::route_model::RouteEndpoint {
-                path: "/v1/messages".to_string(),
-                protocol: "claude".to_string(),
-                url: format!("{}/v1/messages", base_url),
-            },
-            crate::models::route_model::RouteEndpoint {
-                path: "/v1/chat/completions".to_string(),
-                protocol: "openai".to_string(),
-                url: format!("{}/v1/chat/completions", base_url),
-            },
-        ],
-        tags: vec!["默认".to_string()],
-        enabled: true,
-    }];
-    all_routes.extend(routes);
-
-    Ok(RouteListResponse {
+    // 默认路由置顶，使用配置中的默认 Provider
+    Ok(RouteListResponse::build(
         base_url,
         default_provider,
-        routes: all_routes,
-    })
+        routes,
+        &query.unwrap_or_default(),
+    ))
 }
 
 /// 获取指定路由的 curl 示例
@@ -83,3 +69,296 @@ pub async fn get_route_curl_examples(
         }
     }
 }
+
+/// 模型路由诊断结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelRoutingExplanation {
+    /// 模型解析过程（含用于匹配的规范化模型名）
+    #[serde(flatten)]
+    pub resolution: ModelResolution,
+    /// 是否启用模型名规范化
+    pub normalization_enabled: bool,
+    /// 默认 Provider
+    pub default_provider: String,
+}
+
+/// 解释模型的路由过程：规范化、别名匹配与目标 Provider
+#[tauri::command]
+pub async fn explain_model_routing(
+    app_state: tauri::State<'_, crate::AppState>,
+    model: String,
+) -> Result<ModelRoutingExplanation, String> {
+    let s = app_state.read().await;
+    let routing = &s.config.routing;
+    let mapper = ModelMapper::from_aliases(routing.model_aliases.clone())
+        .with_normalization(routing.normalize_model_names);
+
+    Ok(ModelRoutingExplanation {
+        resolution: mapper.explain(&model),
+        normalization_enabled: routing.normalize_model_names,
+        default_provider: routing.default_provider.clone(),
+    })
+}
+
+/// 命中的层级规则
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedTierRule {
+    /// 规则在 tier_rules 中的位置
+    pub index: usize,
+    pub pattern: String,
+    pub tier: String,
+}
+
+/// 单个模型的路由模拟结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingSimulation {
+    /// 模型解析过程（命中的别名规则与解析后的模型名）
+    #[serde(flatten)]
+    pub resolution: ModelResolution,
+    /// 路由到的 Provider（默认 Provider 不是内置类型时为配置中的原始值）
+    pub provider: String,
+    /// 命中的层级规则
+    pub matched_tier_rule: Option<MatchedTierRule>,
+    /// 可用凭证数量（自定义 Provider 的凭证由 API Key Provider 管理，不检查，为 None）
+    pub healthy_credentials: Option<usize>,
+    /// 是否存在可用凭证
+    pub has_healthy_credential: bool,
+}
+
+/// 模拟单个模型的路由，`count_available` 返回 Provider 下可用于该模型的凭证数量
+fn simulate_model(
+    model: &str,
+    mapper: &ModelMapper,
+    router: &Router,
+    default_provider: &str,
+    tier_rules: &[TierRule],
+    count_available: impl Fn(ProviderType, &str) -> Result<usize, String>,
+) -> Result<RoutingSimulation, String> {
+    let resolution = mapper.explain(model);
+    let route = router.route(&resolution.resolved);
+    let matched_tier_rule = tier_rules
+        .iter()
+        .enumerate()
+        .find(|(_, rule)| pattern_matches(&rule.pattern, &resolution.resolved))
+        .map(|(index, rule)| MatchedTierRule {
+            index,
+            pattern: rule.pattern.clone(),
+            tier: rule.tier.clone(),
+        });
+    let healthy_credentials = route
+        .provider
+        .map(|provider| count_available(provider, &resolution.resolved))
+        .transpose()?;
+
+    Ok(RoutingSimulation {
+        provider: route
+            .provider
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| default_provider.to_string()),
+        matched_tier_rule,
+        has_healthy_credential: healthy_credentials.is_some_and(|n| n > 0),
+        healthy_credentials,
+        resolution,
+    })
+}
+
+/// 模拟一批模型的路由：解析后的模型、目标 Provider、命中的规则和是否有可用凭证
+///
+/// 服务器运行时使用运行中的路由器，凭证状态来自实时凭证池，不会实际选择凭证。
+#[tauri::command]
+pub async fn simulate_routing(
+    app_state: tauri::State<'_, crate::AppState>,
+    db: tauri::State<'_, DbConnection>,
+    pool_service: tauri::State<'_, ProviderPoolServiceState>,
+    models: Vec<String>,
+) -> Result<Vec<RoutingSimulation>, String> {
+    let s = app_state.read().await;
+    let routing = s.config.routing.clone();
+    let router = match &s.router_ref {
+        Some(router) => router.read().await.clone(),
+        None => routing
+            .default_provider
+            .parse::<ProviderType>()
+            .map(Router::new)
+            .unwrap_or_default(),
+    };
+    drop(s);
+
+    let mapper = ModelMapper::from_aliases(routing.model_aliases.clone())
+        .with_normalization(routing.normalize_model_names);
+    models
+        .iter()
+        .map(|model| model.trim())
+        .filter(|model| !model.is_empty())
+        .map(|model| {
+            simulate_model(
+                model,
+                &mapper,
+                &router,
+                &routing.default_provider,
+                &routing.tier_rules,
+                |provider, resolved| {
+                    pool_service
+                        .0
+                        .available_credential_count(db.inner(), provider, resolved)
+                },
+            )
+        })
+        .collect()
+}
+
+/// 路由规则变更结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingRulesUpdate {
+    /// 变更后的全部层级规则
+    pub rules: Vec<TierRule>,
+    /// 规则检查警告（不阻止保存）
+    pub warnings: Vec<RoutingRuleWarning>,
+}
+
+/// 结合凭证池当前状态检查层级规则
+fn lint_rules(db: &DbConnection, rules: &[TierRule]) -> Result<Vec<RoutingRuleWarning>, String> {
+    let credentials = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+    };
+    Ok(lint_tier_rules(rules, &credentials))
+}
+
+fn normalize_rule(rule: TierRule) -> Result<TierRule, String> {
+    let rule = TierRule {
+        pattern: rule.pattern.trim().to_string(),
+        tier: rule.tier.trim().to_string(),
+    };
+    if rule.pattern.is_empty() || rule.tier.is_empty() {
+        return Err("规则的模式和层级不能为空".to_string());
+    }
+    Ok(rule)
+}
+
+/// 修改并保存层级规则，同步到运行中的凭证池，并返回检查结果
+async fn update_tier_rules(
+    app_state: &tauri::State<'_, crate::AppState>,
+    config_manager: &tauri::State<'_, GlobalConfigManagerState>,
+    db: &DbConnection,
+    edit: impl FnOnce(&mut Vec<TierRule>) -> Result<(), String>,
+) -> Result<RoutingRulesUpdate, String> {
+    let mut s = app_state.write().await;
+    edit(&mut s.config.routing.tier_rules)?;
+    config::save_config(&s.config).map_err(|e| e.to_string())?;
+    let routing = s.config.routing.clone();
+    drop(s);
+
+    let rules = routing.tier_rules.clone();
+    config_manager
+        .0
+        .update_routing(routing, ConfigChangeSource::FrontendUI)
+        .await;
+
+    let warnings = lint_rules(db, &rules)?;
+    Ok(RoutingRulesUpdate { rules, warnings })
+}
+
+/// 检查层级规则：重复模式、被前面规则遮蔽的规则、没有健康凭证的层级
+#[tauri::command]
+pub async fn lint_routing_rules(
+    app_state: tauri::State<'_, crate::AppState>,
+    db: tauri::State<'_, DbConnection>,
+) -> Result<Vec<RoutingRuleWarning>, String> {
+    let rules = app_state.read().await.config.routing.tier_rules.clone();
+    lint_rules(db.inner(), &rules)
+}
+
+/// 添加层级规则（未指定位置时追加到末尾）
+#[tauri::command]
+pub async fn add_routing_rule(
+    app_state: tauri::State<'_, crate::AppState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
+    db: tauri::State<'_, DbConnection>,
+    rule: TierRule,
+    index: Option<usize>,
+) -> Result<RoutingRulesUpdate, String> {
+    let rule = normalize_rule(rule)?;
+    update_tier_rules(&app_state, &config_manager, db.inner(), |rules| {
+        let index = index.unwrap_or(rules.len()).min(rules.len());
+        rules.insert(index, rule);
+        Ok(())
+    })
+    .await
+}
+
+/// 更新指定位置的层级规则
+#[tauri::command]
+pub async fn update_routing_rule(
+    app_state: tauri::State<'_, crate::AppState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
+    db: tauri::State<'_, DbConnection>,
+    index: usize,
+    rule: TierRule,
+) -> Result<RoutingRulesUpdate, String> {
+    let rule = normalize_rule(rule)?;
+    update_tier_rules(&app_state, &config_manager, db.inner(), |rules| {
+        let slot = rules
+            .get_mut(index)
+            .ok_or_else(|| format!("规则位置 {} 不存在", index))?;
+        *slot = rule;
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_model_reports_alias_tier_rule_and_credentials() {
+        let mapper = ModelMapper::from_aliases(
+            [("fast".to_string(), "gemini-2.5-flash".to_string())].into(),
+        );
+        let rules = vec![
+            TierRule {
+                pattern: "claude-*".to_string(),
+                tier: "paid".to_string(),
+            },
+            TierRule {
+                pattern: "*-flash".to_string(),
+                tier: "free".to_string(),
+            },
+        ];
+        let router = Router::new(ProviderType::Gemini);
+
+        let result = simulate_model(
+            "fast",
+            &mapper,
+            &router,
+            "gemini",
+            &rules,
+            |provider, model| {
+                assert_eq!(provider, ProviderType::Gemini);
+                assert_eq!(model, "gemini-2.5-flash");
+                Ok(0)
+            },
+        )
+        .unwrap();
+        assert_eq!(result.resolution.matched_alias.as_deref(), Some("fast"));
+        assert_eq!(result.provider, "gemini");
+        assert_eq!(result.matched_tier_rule.unwrap().index, 1);
+        assert_eq!(result.healthy_credentials, Some(0));
+        assert!(!result.has_healthy_credential);
+
+        // 默认 Provider 不是内置类型时不检查凭证
+        let result = simulate_model(
+            "claude-sonnet-4-5",
+            &mapper,
+            &Router::new_empty(),
+            "deepseek",
+            &rules,
+            |_, _| unreachable!(),
+        )
+        .unwrap();
+        assert_eq!(result.provider, "deepseek");
+        assert_eq!(result.matched_tier_rule.unwrap().tier, "paid");
+        assert!(result.healthy_credentials.is_none());
+    }
+}
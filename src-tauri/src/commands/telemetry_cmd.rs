@@ -2,9 +2,12 @@
 //!
 //! 提供请求日志、统计数据和 Token 追踪的 Tauri 命令
 
+use crate::processor::{RequestTrace, ResponseCacheStats};
+use crate::resilience::InFlightSnapshot;
 use crate::telemetry::{
     ModelStats, ModelTokenStats, ProviderStats, ProviderTokenStats, RequestLog, RequestLogger,
     RequestStatus, StatsAggregator, StatsSummary, TimeRange, TokenStatsSummary, TokenTracker,
+    UserStats,
 };
 use crate::ProviderType;
 use chrono::{DateTime, Utc};
@@ -211,6 +214,78 @@ pub async fn get_stats_by_model(
     Ok(stats.by_model(range))
 }
 
+/// 按终端用户分组统计（请求携带的 `user` / `metadata.user_id`）
+#[tauri::command]
+pub async fn get_stats_by_user(
+    state: tauri::State<'_, TelemetryState>,
+    time_range: Option<TimeRangeParam>,
+) -> Result<HashMap<String, UserStats>, String> {
+    let range = time_range.map(|r| r.to_time_range()).transpose()?.flatten();
+    let stats = state.stats.read();
+    Ok(stats.by_user(range))
+}
+
+/// 获取各 Provider 当前进行中的上游请求数及并发上限
+#[tauri::command]
+pub async fn get_inflight_requests(
+    app_state: tauri::State<'_, crate::AppState>,
+) -> Result<Vec<InFlightSnapshot>, String> {
+    let s = app_state.read().await;
+    Ok(s.concurrency_limiter.snapshot())
+}
+
+/// 获取响应缓存统计（条目数、命中率）
+#[tauri::command]
+pub async fn get_response_cache_stats(
+    app_state: tauri::State<'_, crate::AppState>,
+) -> Result<ResponseCacheStats, String> {
+    let s = app_state.read().await;
+    Ok(s.response_cache.stats())
+}
+
+/// 清空响应缓存，返回清除的条目数
+#[tauri::command]
+pub async fn clear_response_cache(
+    app_state: tauri::State<'_, crate::AppState>,
+) -> Result<usize, String> {
+    let s = app_state.read().await;
+    Ok(s.response_cache.clear())
+}
+
+/// 获取默认 Provider 分流统计（配置权重对应的期望比例与实际比例）
+#[tauri::command]
+pub fn get_default_provider_split_stats() -> Vec<crate::server::default_split::SplitStat> {
+    crate::server::default_split::stats()
+}
+
+/// 清零默认 Provider 分流计数
+#[tauri::command]
+pub fn reset_default_provider_split_stats() {
+    crate::server::default_split::reset_stats();
+}
+
+// ========== 请求追踪命令 ==========
+
+/// 获取单个请求的管道追踪（按发生顺序排列的步骤）
+#[tauri::command]
+pub async fn get_request_trace(
+    app_state: tauri::State<'_, crate::AppState>,
+    request_id: String,
+) -> Result<Option<RequestTrace>, String> {
+    let s = app_state.read().await;
+    Ok(s.request_traces.get(&request_id))
+}
+
+/// 获取最近的请求追踪（最新的在前）
+#[tauri::command]
+pub async fn list_request_traces(
+    app_state: tauri::State<'_, crate::AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<RequestTrace>, String> {
+    let s = app_state.read().await;
+    Ok(s.request_traces.recent(limit.unwrap_or(50)))
+}
+
 // ========== Token 统计命令 ==========
 
 /// 获取 Token 统计摘要
@@ -11,6 +11,8 @@
 //! - `terminal_resize` - 调整终端大小
 //! - `terminal_close` - 关闭终端会话
 //! - `terminal_list_sessions` - 获取所有会话列表
+//! - `terminal_attach_session` - 重新连接到已有会话（回放输出历史）
+//! - `terminal_get_session_scrollback` - 获取会话最近的输出历史
 
 use std::sync::Arc;
 
@@ -18,7 +20,7 @@ use serde::Serialize;
 use tauri::State;
 use tokio::sync::RwLock;
 
-use crate::terminal::{SessionMetadata, TerminalSessionManager};
+use crate::terminal::{SessionMetadata, TerminalScrollback, TerminalSessionManager};
 
 /// 终端会话管理器状态包装
 pub struct TerminalManagerState(pub Arc<RwLock<Option<TerminalSessionManager>>>);
@@ -153,3 +155,49 @@ pub async fn terminal_get_session(
 
     Ok(manager.get_session(&session_id).await)
 }
+
+/// 重新连接到已有终端会话
+///
+/// 通过 `terminal:scrollback` 事件推送最近的输出历史后返回会话信息。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `max_bytes`: 最多回放的字节数（可选，默认回放整个回滚缓冲区）
+#[tauri::command]
+pub async fn terminal_attach_session(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    max_bytes: Option<usize>,
+) -> Result<SessionMetadata, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .attach_session(&session_id, max_bytes)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取终端会话最近的输出历史
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `max_bytes`: 最多返回的字节数（可选，默认返回整个回滚缓冲区）
+#[tauri::command]
+pub async fn terminal_get_session_scrollback(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    max_bytes: Option<usize>,
+) -> Result<TerminalScrollback, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .get_session_scrollback(&session_id, max_bytes)
+        .await
+        .map_err(|e| e.to_string())
+}
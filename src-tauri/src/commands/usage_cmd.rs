@@ -10,7 +10,7 @@ use crate::TokenCacheServiceState;
 use tauri::State;
 
 /// 默认 Kiro 版本号
-const DEFAULT_KIRO_VERSION: &str = "1.0.0";
+pub(crate) const DEFAULT_KIRO_VERSION: &str = "1.0.0";
 
 /// 获取 Kiro 用量信息
 ///
@@ -0,0 +1,142 @@
+//! 生效配置
+//!
+//! 运行中实际使用的配置可能与磁盘文件不一致：热重载尚未生效或失败回滚、
+//! 运行时轮换 API Key、切换默认 Provider 等。这里把生效配置的每个顶层字段与磁盘文件比较，
+//! 标注其来源，用于排查"修改为什么没有生效"。
+//!
+//! 应用不从环境变量读取配置覆盖，因此来源只有文件、默认值和运行时三种。
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::types::Config;
+
+/// 配置节的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    /// 与配置文件一致
+    File,
+    /// 配置文件未设置，使用默认值
+    Default,
+    /// 与配置文件不一致（运行时修改或尚未重载）
+    Runtime,
+}
+
+/// 生效配置及各顶层字段的来源
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    /// 运行中实际使用的配置
+    pub config: Config,
+    /// 顶层字段 -> 来源
+    pub sources: BTreeMap<String, ConfigSource>,
+    /// 比较所用的配置文件路径
+    pub config_path: String,
+    /// 配置文件读取或解析失败的原因（此时无法区分文件与运行时来源）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_error: Option<String>,
+    /// 服务器是否在运行（未运行时为命令修改后的内存配置）
+    pub server_running: bool,
+}
+
+impl EffectiveConfig {
+    /// 与 `config_path` 处的配置文件比较，生成各顶层字段的来源
+    pub fn resolve(config: Config, config_path: &Path, server_running: bool) -> Self {
+        let (file_config, file_keys, file_error) = match std::fs::read_to_string(config_path) {
+            Ok(content) => match parse_file(&content) {
+                Ok((file_config, keys)) => (Some(file_config), keys, None),
+                Err(e) => (None, HashSet::new(), Some(e)),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                (Some(Config::default()), HashSet::new(), None)
+            }
+            Err(e) => (None, HashSet::new(), Some(e.to_string())),
+        };
+
+        let sources = section_sources(&config, file_config.as_ref(), &file_keys);
+        Self {
+            config,
+            sources,
+            config_path: config_path.to_string_lossy().to_string(),
+            file_error,
+            server_running,
+        }
+    }
+}
+
+/// 解析配置文件，返回配置和文件中显式设置的顶层字段
+fn parse_file(content: &str) -> Result<(Config, HashSet<String>), String> {
+    let value: serde_yaml::Value =
+        serde_yaml::from_str(content).map_err(|e| format!("解析配置文件失败: {}", e))?;
+    let keys = value
+        .as_mapping()
+        .map(|mapping| {
+            mapping
+                .keys()
+                .filter_map(|k| k.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    let config = serde_yaml::from_value(value).map_err(|e| format!("解析配置文件失败: {}", e))?;
+    Ok((config, keys))
+}
+
+/// 比较生效配置与文件配置的顶层字段
+///
+/// 文件不可用时，与默认值一致的字段视为默认值，其余视为运行时。
+fn section_sources(
+    effective: &Config,
+    file: Option<&Config>,
+    file_keys: &HashSet<String>,
+) -> BTreeMap<String, ConfigSource> {
+    let effective = serde_json::to_value(effective).unwrap_or(Value::Null);
+    let baseline = serde_json::to_value(file.cloned().unwrap_or_default()).unwrap_or(Value::Null);
+
+    let Some(sections) = effective.as_object() else {
+        return BTreeMap::new();
+    };
+    sections
+        .iter()
+        .map(|(key, value)| {
+            let source = if baseline.get(key) != Some(value) {
+                ConfigSource::Runtime
+            } else if file.is_some() && file_keys.contains(key) {
+                ConfigSource::File
+            } else {
+                ConfigSource::Default
+            };
+            (key.clone(), source)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_sources() {
+        let yaml = "server:\n  host: 127.0.0.1\n  port: 9000\n  api_key: file-key\n";
+        let (file_config, keys) = parse_file(yaml).unwrap();
+
+        let mut effective = file_config.clone();
+        effective.server.api_key = "rotated-key".to_string();
+        effective.default_provider = "gemini".to_string();
+
+        let sources = section_sources(&effective, Some(&file_config), &keys);
+        assert_eq!(sources["server"], ConfigSource::Runtime);
+        assert_eq!(sources["default_provider"], ConfigSource::Runtime);
+        assert_eq!(sources["routing"], ConfigSource::Default);
+
+        let sources = section_sources(&file_config, Some(&file_config), &keys);
+        assert_eq!(sources["server"], ConfigSource::File);
+
+        // 文件不可用时，只有偏离默认值的字段标为运行时
+        let sources = section_sources(&effective, None, &HashSet::new());
+        assert_eq!(sources["server"], ConfigSource::Runtime);
+        assert_eq!(sources["routing"], ConfigSource::Default);
+    }
+}
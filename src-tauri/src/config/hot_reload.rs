@@ -48,12 +48,16 @@ impl std::fmt::Display for HotReloadError {
 impl std::error::Error for HotReloadError {}
 
 /// 热重载结果
-#[derive(Debug, Clone)]
+///
+/// 序列化为 `{ "status": "success" | "rolled_back" | "failed", ... }`（不含时间戳），供前端展示。
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
 #[allow(dead_code)]
 pub enum ReloadResult {
     /// 重载成功
     Success {
         /// 重载时间戳
+        #[serde(skip)]
         timestamp: Instant,
     },
     /// 重载失败，已回滚
@@ -61,6 +65,7 @@ pub enum ReloadResult {
         /// 错误信息
         error: String,
         /// 回滚时间戳
+        #[serde(skip)]
         timestamp: Instant,
     },
     /// 重载失败，回滚也失败
@@ -70,6 +75,7 @@ pub enum ReloadResult {
         /// 回滚错误
         rollback_error: Option<String>,
         /// 失败时间戳
+        #[serde(skip)]
         timestamp: Instant,
     },
 }
@@ -490,7 +496,7 @@ fn is_localhost_host(host: &str) -> bool {
 
 /// 检查是否为有效的绑定地址
 /// 允许回环地址、0.0.0.0 和私有网络地址
-fn is_valid_bind_host(host: &str) -> bool {
+pub(super) fn is_valid_bind_host(host: &str) -> bool {
     if is_localhost_host(host) {
         return true;
     }
@@ -514,7 +520,7 @@ fn is_valid_bind_host(host: &str) -> bool {
 }
 
 /// 检查是否为非本地绑定地址（需要强 API Key）
-fn is_non_local_bind(host: &str) -> bool {
+pub(super) fn is_non_local_bind(host: &str) -> bool {
     if host == "0.0.0.0" || host == "::" {
         return true;
     }
@@ -693,4 +699,23 @@ server:
         let err = HotReloadError::RollbackError("rollback error".to_string());
         assert!(err.to_string().contains("回滚错误"));
     }
+
+    #[test]
+    fn test_reload_result_serialization() {
+        let json = serde_json::to_value(ReloadResult::RolledBack {
+            error: "端口号无效".to_string(),
+            timestamp: Instant::now(),
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "status": "rolled_back", "error": "端口号无效" })
+        );
+
+        let json = serde_json::to_value(ReloadResult::Success {
+            timestamp: Instant::now(),
+        })
+        .unwrap();
+        assert_eq!(json, serde_json::json!({ "status": "success" }));
+    }
 }
@@ -5,14 +5,17 @@
 
 #![allow(unused_imports)]
 
+mod effective;
 mod export;
 mod hot_reload;
 mod import;
 pub mod observer;
 mod path_utils;
 mod types;
+mod validation;
 mod yaml;
 
+pub use effective::{ConfigSource, EffectiveConfig};
 pub use export::{ExportBundle, ExportOptions, ExportService, REDACTED_PLACEHOLDER};
 pub use hot_reload::{
     ConfigChangeEvent as FileChangeEvent, ConfigChangeKind, FileWatcher, HotReloadManager,
@@ -21,13 +24,23 @@ pub use hot_reload::{
 pub use import::{ImportOptions, ImportService, ValidationResult};
 pub use path_utils::{collapse_tilde, contains_tilde, expand_tilde};
 pub use types::{
-    generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, Config, CredentialEntry,
-    CredentialPoolConfig, CustomProviderConfig, EndpointProvidersConfig, ExperimentalFeatures,
-    GeminiApiKeyEntry, IFlowCredentialEntry, InjectionRuleConfig, InjectionSettings, LoggingConfig,
-    ModelInfo, ModelsConfig, NativeAgentConfig, ProviderConfig, ProviderModelsConfig,
-    ProvidersConfig, QuotaExceededConfig, RemoteManagementConfig, RetrySettings, RoutingConfig,
-    ScreenshotChatConfig, ServerConfig, TlsConfig, VertexApiKeyEntry, VertexModelAlias,
-    DEFAULT_API_KEY,
+    generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, BackupSettings,
+    ConcurrencySettings, Config, CredentialEntry, CredentialPoolConfig,
+    CredentialSelectionStrategy, CustomProviderConfig, DatabaseMaintenanceSettings,
+    DeadLetterSettings, EndpointProvidersConfig, ExperimentalFeatures, GeminiApiKeyEntry,
+    HealthScoringSettings, IFlowCredentialEntry, InjectionRuleConfig, InjectionSettings,
+    LoggingConfig, ModelInfo, ModelsConfig, NativeAgentConfig, OAuthCallbackSettings,
+    OutboundProxySettings, PriceTableEntry, ProviderConfig, ProviderModelsConfig, ProviderWeight,
+    ProvidersConfig, QuotaExceededConfig, RemoteManagementConfig, RequestLimitsSettings,
+    ResponseCacheSettings, RetrySettings, RoutingConfig, ScreenshotChatConfig, ServerConfig,
+    ServerWatchdogConfig, ShadowTestSettings, SpendGuardSettings, StreamResumeSettings,
+    StreamSettings, TelemetrySettings, TerminalSettings, TierRule, TlsConfig, ToolResultBudget,
+    ToolResultTruncationSettings, UpstreamHeadersSettings, UserLimitsSettings, VertexApiKeyEntry,
+    VertexModelAlias, WebSocketSettings, DEFAULT_API_KEY, DEFAULT_TERMINAL_SCROLLBACK_BYTES,
+};
+pub use validation::{
+    config_schema, validate_config, validate_yaml, ConfigDiagnostic, ConfigValidationReport,
+    DiagnosticSeverity,
 };
 pub use yaml::{load_config, save_config, ConfigError, ConfigManager, YamlService};
 
@@ -124,6 +124,9 @@ pub struct EndpointProvidersChangeEvent {
     pub windsurf: Option<String>,
     pub kiro: Option<String>,
     pub other: Option<String>,
+    /// 强制非流式的客户端类型
+    #[serde(default)]
+    pub force_non_streaming: Vec<String>,
     /// 变更来源
     pub source: ConfigChangeSource,
 }
@@ -2,15 +2,23 @@
 //!
 //! 整合配置主题、热重载和观察者管理
 
-use super::events::ConfigChangeSource;
+use super::events::{
+    ConfigChangeEvent, ConfigChangeSource, EndpointProvidersChangeEvent, InjectionChangeEvent,
+    RoutingChangeEvent,
+};
 use super::observers::{
     DefaultProviderRefObserver, EndpointObserver, InjectorObserver, LoggingObserver,
-    RouterObserver, TauriObserver,
+    ProviderPoolObserver, RouterObserver, TauriObserver, FEATURE_SETTINGS,
 };
 use super::subject::ConfigSubject;
-use super::traits::ConfigObserver;
-use crate::config::{Config, EndpointProvidersConfig, HotReloadManager, ReloadResult};
+use super::traits::{ConfigObserver, FnObserver};
+use crate::config::{
+    Config, EndpointProvidersConfig, HotReloadManager, InjectionSettings, ReloadResult,
+    RoutingConfig,
+};
+use crate::injection::Injector;
 use crate::processor::RequestProcessor;
+use crate::services::provider_pool_service::ProviderPoolService;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::AppHandle;
@@ -89,6 +97,45 @@ impl GlobalConfigManager {
         tracing::info!("[GlobalConfigManager] 已注册 RequestProcessor 观察者");
     }
 
+    /// 注册参数注入观察者
+    ///
+    /// 注入器和开关由 ServerState 持有并与运行中的 RequestProcessor 共享，
+    /// 注入配置变更后无需重启服务器即可生效。
+    pub fn register_injector_observer(
+        &self,
+        injector: Arc<RwLock<Injector>>,
+        enabled: Arc<RwLock<bool>>,
+    ) {
+        let observer = Arc::new(InjectorObserver::new(injector).with_enabled_flag(enabled));
+        self.subject.register(observer);
+    }
+
+    /// 注册功能设置观察者
+    ///
+    /// `FEATURE_SETTINGS` 中的每个模块注册一个观察者，注册时按当前配置应用一次
+    pub fn register_feature_settings_observers(&self) {
+        self.register_settings_observers(FEATURE_SETTINGS);
+    }
+
+    fn register_settings_observers(&self, settings: &[(&str, fn(&Config))]) {
+        let config = self.config();
+        for &(name, apply) in settings {
+            apply(&config);
+            let observer = FnObserver::new(name, move |_event, config| {
+                apply(config);
+                Ok(())
+            })
+            .with_priority(40);
+            self.subject.register(Arc::new(observer));
+        }
+    }
+
+    /// 注册凭证池观察者
+    pub fn register_provider_pool_observer(&self, pool_service: Arc<ProviderPoolService>) {
+        self.subject
+            .register(Arc::new(ProviderPoolObserver::new(pool_service)));
+    }
+
     /// 注册端点 Provider 观察者
     pub fn register_endpoint_observer(
         &self,
@@ -125,6 +172,76 @@ impl GlobalConfigManager {
         self.subject.update_config(new_config, source).await;
     }
 
+    /// 更新注入配置并通知观察者
+    ///
+    /// 只替换当前配置的 `injection` 部分，并发出 `InjectionChanged` 事件。
+    pub async fn update_injection(&self, injection: InjectionSettings, source: ConfigChangeSource) {
+        let event = ConfigChangeEvent::InjectionChanged(InjectionChangeEvent {
+            enabled: injection.enabled,
+            rules_count: injection.rules.len(),
+            source,
+        });
+
+        let mut config = self.subject.config();
+        config.injection = injection;
+        {
+            let hot_reload = self.hot_reload.read();
+            hot_reload.update_config(config.clone());
+        }
+        self.subject.set_config(config);
+        self.subject.notify_event(event).await;
+    }
+
+    /// 更新路由配置并通知观察者
+    ///
+    /// 只替换当前配置的 `routing` 部分，并发出 `RoutingChanged` 事件。
+    pub async fn update_routing(&self, routing: RoutingConfig, source: ConfigChangeSource) {
+        let event = ConfigChangeEvent::RoutingChanged(RoutingChangeEvent {
+            default_provider: None,
+            model_aliases_changed: false,
+            model_aliases: None,
+            source,
+        });
+
+        let mut config = self.subject.config();
+        config.routing = routing;
+        {
+            let hot_reload = self.hot_reload.read();
+            hot_reload.update_config(config.clone());
+        }
+        self.subject.set_config(config);
+        self.subject.notify_event(event).await;
+    }
+
+    /// 更新端点 Provider 配置并通知观察者
+    ///
+    /// 只替换当前配置的 `endpoint_providers` 部分，并发出 `EndpointProvidersChanged` 事件。
+    pub async fn update_endpoint_providers(
+        &self,
+        endpoint_providers: EndpointProvidersConfig,
+        source: ConfigChangeSource,
+    ) {
+        let event = ConfigChangeEvent::EndpointProvidersChanged(EndpointProvidersChangeEvent {
+            cursor: endpoint_providers.cursor.clone(),
+            claude_code: endpoint_providers.claude_code.clone(),
+            codex: endpoint_providers.codex.clone(),
+            windsurf: endpoint_providers.windsurf.clone(),
+            kiro: endpoint_providers.kiro.clone(),
+            other: endpoint_providers.other.clone(),
+            force_non_streaming: endpoint_providers.force_non_streaming.clone(),
+            source,
+        });
+
+        let mut config = self.subject.config();
+        config.endpoint_providers = endpoint_providers;
+        {
+            let hot_reload = self.hot_reload.read();
+            hot_reload.update_config(config.clone());
+        }
+        self.subject.set_config(config);
+        self.subject.notify_event(event).await;
+    }
+
     /// 执行热重载
     pub async fn reload(&self) -> ReloadResult {
         let result = {
@@ -233,4 +350,99 @@ mod tests {
             .observer_names()
             .contains(&"LoggingObserver".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_register_settings_observers_applies_on_register_and_update() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // 使用测试专用的设置表，避免改动真实功能模块的进程级设置
+        static FIRST: AtomicUsize = AtomicUsize::new(0);
+        static SECOND: AtomicUsize = AtomicUsize::new(0);
+        fn apply_first(_: &Config) {
+            FIRST.fetch_add(1, Ordering::SeqCst);
+        }
+        fn apply_second(_: &Config) {
+            SECOND.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let manager =
+            GlobalConfigManager::new(Config::default(), PathBuf::from("/tmp/test_config.yaml"));
+        manager.register_settings_observers(&[
+            ("FirstObserver", apply_first),
+            ("SecondObserver", apply_second),
+        ]);
+        assert_eq!(
+            manager.observer_names(),
+            vec!["FirstObserver".to_string(), "SecondObserver".to_string()]
+        );
+        assert_eq!(FIRST.load(Ordering::SeqCst), 1);
+        assert_eq!(SECOND.load(Ordering::SeqCst), 1);
+
+        manager
+            .update_config(Config::default(), ConfigChangeSource::FrontendUI)
+            .await;
+        assert_eq!(FIRST.load(Ordering::SeqCst), 2);
+        assert_eq!(SECOND.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_feature_settings_names_are_unique() {
+        let mut names: Vec<&str> = FEATURE_SETTINGS.iter().map(|(name, _)| *name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), FEATURE_SETTINGS.len());
+    }
+
+    #[tokio::test]
+    async fn test_injection_rule_edit_applies_without_restart() {
+        use crate::config::InjectionRuleConfig;
+        use crate::injection::InjectionMode;
+
+        let manager =
+            GlobalConfigManager::new(Config::default(), PathBuf::from("/tmp/test_config.yaml"));
+        // 模拟运行中的服务器持有的注入器与开关
+        let injector = Arc::new(RwLock::new(Injector::new()));
+        let enabled = Arc::new(RwLock::new(false));
+        manager.register_injector_observer(injector.clone(), enabled.clone());
+
+        let mut injection = InjectionSettings {
+            enabled: true,
+            rules: vec![InjectionRuleConfig {
+                id: "temp".to_string(),
+                pattern: "claude-*".to_string(),
+                parameters: serde_json::json!({"temperature": 0.3}),
+                mode: InjectionMode::Override,
+                priority: 100,
+                enabled: true,
+            }],
+        };
+        manager
+            .update_injection(injection.clone(), ConfigChangeSource::FrontendUI)
+            .await;
+
+        assert!(*enabled.read().await);
+        let mut payload = serde_json::json!({"model": "claude-sonnet-4-5"});
+        injector
+            .read()
+            .await
+            .inject("claude-sonnet-4-5", &mut payload);
+        assert_eq!(payload["temperature"], 0.3);
+
+        // 编辑规则后，下一次请求立即使用新参数
+        injection.rules[0].parameters = serde_json::json!({"temperature": 0.9});
+        manager
+            .update_injection(injection, ConfigChangeSource::FrontendUI)
+            .await;
+
+        let mut payload = serde_json::json!({"model": "claude-sonnet-4-5"});
+        injector
+            .read()
+            .await
+            .inject("claude-sonnet-4-5", &mut payload);
+        assert_eq!(payload["temperature"], 0.9);
+        assert_eq!(
+            manager.config().injection.rules[0].parameters["temperature"],
+            0.9
+        );
+    }
 }
@@ -16,7 +16,7 @@ pub use events::{
 pub use manager::{GlobalConfigManager, GlobalConfigManagerState};
 pub use observers::{
     DefaultProviderRefObserver, EndpointObserver, InjectorObserver, LoggingObserver,
-    RouterObserver, TauriObserver,
+    ProviderPoolObserver, RouterObserver, TauriObserver, WsConfigObserver, FEATURE_SETTINGS,
 };
 pub use subject::{ConfigSubject, CONFIG_CHANGED_EVENT, CONFIG_RELOAD_EVENT};
 pub use traits::{ConfigObserver, FnObserver, SyncConfigObserver, SyncObserverWrapper};
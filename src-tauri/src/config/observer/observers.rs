@@ -7,6 +7,8 @@ use super::traits::ConfigObserver;
 use crate::config::{Config, EndpointProvidersConfig};
 use crate::injection::Injector;
 use crate::router::{ModelMapper, Router};
+use crate::services::provider_pool_service::ProviderPoolService;
+use crate::websocket::WsConnectionManager;
 use async_trait::async_trait;
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter};
@@ -69,6 +71,7 @@ impl ConfigObserver for RouterObserver {
             for (alias, model) in &config.routing.model_aliases {
                 mapper.add_alias(alias, model);
             }
+            mapper.set_normalization(config.routing.normalize_model_names);
             tracing::debug!(
                 "[RouterObserver] 更新模型别名: {} 个",
                 config.routing.model_aliases.len()
@@ -81,14 +84,24 @@ impl ConfigObserver for RouterObserver {
 
 /// 注入器观察者
 ///
-/// 监听注入配置变更，更新 Injector
+/// 监听注入配置变更，更新 Injector 和注入开关
 pub struct InjectorObserver {
     injector: Arc<RwLock<Injector>>,
+    enabled: Option<Arc<RwLock<bool>>>,
 }
 
 impl InjectorObserver {
     pub fn new(injector: Arc<RwLock<Injector>>) -> Self {
-        Self { injector }
+        Self {
+            injector,
+            enabled: None,
+        }
+    }
+
+    /// 同时同步注入开关
+    pub fn with_enabled_flag(mut self, enabled: Arc<RwLock<bool>>) -> Self {
+        self.enabled = Some(enabled);
+        self
     }
 }
 
@@ -120,10 +133,16 @@ impl ConfigObserver for InjectorObserver {
         for rule in &config.injection.rules {
             injector.add_rule(rule.clone().into());
         }
+        drop(injector);
+
+        if let Some(enabled) = &self.enabled {
+            *enabled.write().await = config.injection.enabled;
+        }
 
         tracing::info!(
-            "[InjectorObserver] 更新注入规则: {} 条",
-            config.injection.rules.len()
+            "[InjectorObserver] 更新注入规则: {} 条 (enabled={})",
+            config.injection.rules.len(),
+            config.injection.enabled
         );
 
         Ok(())
@@ -211,6 +230,141 @@ impl ConfigObserver for LoggingObserver {
     }
 }
 
+/// 由配置驱动的功能模块：(观察者名称, 按配置更新该模块运行时设置的函数)
+///
+/// 每个模块注册一个观察者（见 `GlobalConfigManager::register_feature_settings_observers`），
+/// 注册时按当前配置应用一次，之后随配置变更通知更新。新增功能设置只需在此登记。
+pub const FEATURE_SETTINGS: &[(&str, fn(&Config))] = &[
+    ("OutboundProxyObserver", crate::http_client::configure),
+    ("SpendGuardObserver", crate::stream::cost_guard::configure),
+    ("PricingObserver", crate::telemetry::pricing::configure),
+    ("ShadowTestObserver", crate::server::shadow::configure),
+    ("TelemetryObserver", crate::telemetry::otlp::configure),
+    ("DeadLetterObserver", crate::server::dead_letter::configure),
+    (
+        "RetryPolicyObserver",
+        crate::server::retry_policy::configure,
+    ),
+    (
+        "StreamResumeObserver",
+        crate::server::stream_resume::configure,
+    ),
+    (
+        "RequestLimitsObserver",
+        crate::server::request_limits::configure,
+    ),
+    ("UserLimitsObserver", crate::server::user_limits::configure),
+    (
+        "StreamCoalesceObserver",
+        crate::server::stream_coalesce::configure,
+    ),
+    (
+        "DefaultSplitObserver",
+        crate::server::default_split::configure,
+    ),
+    (
+        "ModelFallbackObserver",
+        crate::server::model_fallback::configure,
+    ),
+    (
+        "ToolResultTruncationObserver",
+        crate::server::tool_result_truncation::configure,
+    ),
+    (
+        "TermScrollbackObserver",
+        crate::terminal::scrollback::configure,
+    ),
+];
+
+/// WebSocket 配置观察者
+///
+/// 配置变更后更新运行中服务器的 WebSocket 压缩开关
+pub struct WsConfigObserver {
+    manager: Arc<WsConnectionManager>,
+}
+
+impl WsConfigObserver {
+    pub const NAME: &'static str = "WsConfigObserver";
+
+    pub fn new(manager: Arc<WsConnectionManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl ConfigObserver for WsConfigObserver {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn priority(&self) -> i32 {
+        40
+    }
+
+    async fn on_config_changed(
+        &self,
+        _event: &ConfigChangeEvent,
+        config: &Config,
+    ) -> Result<(), String> {
+        self.manager.set_compression(config.websocket.compression);
+        Ok(())
+    }
+}
+
+/// 凭证池观察者
+///
+/// 配置变更后更新凭证池的层级偏好规则与健康评分配置
+pub struct ProviderPoolObserver {
+    pool_service: Arc<ProviderPoolService>,
+}
+
+impl ProviderPoolObserver {
+    pub fn new(pool_service: Arc<ProviderPoolService>) -> Self {
+        Self { pool_service }
+    }
+}
+
+#[async_trait]
+impl ConfigObserver for ProviderPoolObserver {
+    fn name(&self) -> &str {
+        "ProviderPoolObserver"
+    }
+
+    fn priority(&self) -> i32 {
+        15
+    }
+
+    fn is_interested_in(&self, event: &ConfigChangeEvent) -> bool {
+        matches!(
+            event,
+            ConfigChangeEvent::FullReload(_) | ConfigChangeEvent::RoutingChanged(_)
+        )
+    }
+
+    async fn on_config_changed(
+        &self,
+        _event: &ConfigChangeEvent,
+        config: &Config,
+    ) -> Result<(), String> {
+        self.pool_service
+            .set_tier_rules(config.routing.tier_rules.clone());
+        self.pool_service
+            .set_health_scoring(config.health_scoring.clone());
+        self.pool_service.set_selection_strategy(
+            config.routing.selection_strategy,
+            config.routing.latency_exploration_rate,
+        );
+        self.pool_service
+            .set_credential_fallback(&config.routing.credential_fallback);
+        tracing::debug!(
+            "[ProviderPoolObserver] 更新层级规则: {} 条，健康分下限: {}",
+            config.routing.tier_rules.len(),
+            config.health_scoring.disable_below
+        );
+        Ok(())
+    }
+}
+
 /// Tauri 前端通知观察者
 ///
 /// 将配置变更事件转发到前端
@@ -3,9 +3,10 @@
 //! 使用 proptest 进行属性测试
 
 use crate::config::{
-    collapse_tilde, contains_tilde, expand_tilde, Config, ConfigManager, CustomProviderConfig,
-    HotReloadManager, InjectionSettings, LoggingConfig, ProviderConfig, ProvidersConfig,
-    ReloadResult, RetrySettings, RoutingConfig, ServerConfig, YamlService,
+    collapse_tilde, contains_tilde, expand_tilde, Config, ConfigManager,
+    CredentialSelectionStrategy, CustomProviderConfig, HotReloadManager, InjectionSettings,
+    LoggingConfig, ProviderConfig, ProvidersConfig, ReloadResult, RetrySettings, RoutingConfig,
+    ServerConfig, YamlService,
 };
 use proptest::prelude::*;
 use std::io::Write;
@@ -38,6 +39,8 @@ fn arb_server_config() -> impl Strategy<Value = ServerConfig> {
         port,
         api_key,
         tls: crate::config::TlsConfig::default(),
+        api_key_grace_minutes: 10,
+        watchdog: Default::default(),
     })
 }
 
@@ -116,6 +119,13 @@ fn arb_routing_config() -> impl Strategy<Value = RoutingConfig> {
         .prop_map(|(default_provider, model_aliases)| RoutingConfig {
             default_provider,
             model_aliases,
+            normalize_model_names: true,
+            tier_rules: Vec::new(),
+            selection_strategy: CredentialSelectionStrategy::default(),
+            latency_exploration_rate: 0.05,
+            credential_fallback: Default::default(),
+            model_fallbacks: Default::default(),
+            default_provider_split: Vec::new(),
         })
 }
 
@@ -133,6 +143,7 @@ fn arb_retry_settings() -> impl Strategy<Value = RetrySettings> {
                 base_delay_ms,
                 max_delay_ms,
                 auto_switch_provider,
+                ..Default::default()
             },
         )
 }
@@ -189,6 +200,25 @@ fn arb_config() -> impl Strategy<Value = Config> {
             agent: crate::config::NativeAgentConfig::default(),
             language: "zh".to_string(),
             experimental: crate::config::ExperimentalFeatures::default(),
+            response_cache: crate::config::ResponseCacheSettings::default(),
+            outbound_proxy: crate::config::OutboundProxySettings::default(),
+            upstream_headers: crate::config::UpstreamHeadersSettings::default(),
+            health_scoring: crate::config::HealthScoringSettings::default(),
+            spend_guard: crate::config::SpendGuardSettings::default(),
+            shadow_test: crate::config::ShadowTestSettings::default(),
+            telemetry: crate::config::TelemetrySettings::default(),
+            dead_letter: crate::config::DeadLetterSettings::default(),
+            stream_resume: crate::config::StreamResumeSettings::default(),
+            limits: crate::config::RequestLimitsSettings::default(),
+            stream: crate::config::StreamSettings::default(),
+            oauth_callback: crate::config::OAuthCallbackSettings::default(),
+            price_table: std::collections::HashMap::new(),
+            terminal: crate::config::TerminalSettings::default(),
+            tool_result_truncation: crate::config::ToolResultTruncationSettings::default(),
+            websocket: crate::config::WebSocketSettings::default(),
+            backup: crate::config::BackupSettings::default(),
+            database_maintenance: crate::config::DatabaseMaintenanceSettings::default(),
+            concurrency: crate::config::ConcurrencySettings::default(),
         })
 }
 
@@ -354,6 +384,8 @@ fn arb_valid_server_config() -> impl Strategy<Value = ServerConfig> {
         port,
         api_key,
         tls: crate::config::TlsConfig::default(),
+        api_key_grace_minutes: 10,
+        watchdog: Default::default(),
     })
 }
 
@@ -371,6 +403,7 @@ fn arb_valid_retry_settings() -> impl Strategy<Value = RetrySettings> {
                 base_delay_ms,
                 max_delay_ms,
                 auto_switch_provider,
+                ..Default::default()
             },
         )
 }
@@ -427,6 +460,25 @@ fn arb_valid_config() -> impl Strategy<Value = Config> {
             agent: crate::config::NativeAgentConfig::default(),
             language: "zh".to_string(),
             experimental: crate::config::ExperimentalFeatures::default(),
+            response_cache: crate::config::ResponseCacheSettings::default(),
+            outbound_proxy: crate::config::OutboundProxySettings::default(),
+            upstream_headers: crate::config::UpstreamHeadersSettings::default(),
+            health_scoring: crate::config::HealthScoringSettings::default(),
+            spend_guard: crate::config::SpendGuardSettings::default(),
+            shadow_test: crate::config::ShadowTestSettings::default(),
+            telemetry: crate::config::TelemetrySettings::default(),
+            dead_letter: crate::config::DeadLetterSettings::default(),
+            stream_resume: crate::config::StreamResumeSettings::default(),
+            limits: crate::config::RequestLimitsSettings::default(),
+            stream: crate::config::StreamSettings::default(),
+            oauth_callback: crate::config::OAuthCallbackSettings::default(),
+            price_table: std::collections::HashMap::new(),
+            terminal: crate::config::TerminalSettings::default(),
+            tool_result_truncation: crate::config::ToolResultTruncationSettings::default(),
+            websocket: crate::config::WebSocketSettings::default(),
+            backup: crate::config::BackupSettings::default(),
+            database_maintenance: crate::config::DatabaseMaintenanceSettings::default(),
+            concurrency: crate::config::ConcurrencySettings::default(),
         })
 }
 
@@ -476,6 +528,25 @@ fn arb_invalid_config() -> impl Strategy<Value = Config> {
                     agent: crate::config::NativeAgentConfig::default(),
                     language: "zh".to_string(),
                     experimental: crate::config::ExperimentalFeatures::default(),
+                    response_cache: crate::config::ResponseCacheSettings::default(),
+                    outbound_proxy: crate::config::OutboundProxySettings::default(),
+                    upstream_headers: crate::config::UpstreamHeadersSettings::default(),
+                    health_scoring: crate::config::HealthScoringSettings::default(),
+                    spend_guard: crate::config::SpendGuardSettings::default(),
+                    shadow_test: crate::config::ShadowTestSettings::default(),
+                    telemetry: crate::config::TelemetrySettings::default(),
+                    dead_letter: crate::config::DeadLetterSettings::default(),
+                    stream_resume: crate::config::StreamResumeSettings::default(),
+                    limits: crate::config::RequestLimitsSettings::default(),
+                    stream: crate::config::StreamSettings::default(),
+                    oauth_callback: crate::config::OAuthCallbackSettings::default(),
+                    price_table: std::collections::HashMap::new(),
+                    terminal: crate::config::TerminalSettings::default(),
+                    tool_result_truncation: crate::config::ToolResultTruncationSettings::default(),
+                    websocket: crate::config::WebSocketSettings::default(),
+                    backup: crate::config::BackupSettings::default(),
+                    database_maintenance: crate::config::DatabaseMaintenanceSettings::default(),
+                    concurrency: crate::config::ConcurrencySettings::default(),
                 };
                 // 根据类型使配置无效
                 match invalid_type {
@@ -2458,6 +2529,7 @@ fn arb_endpoint_providers_config() -> impl Strategy<Value = EndpointProvidersCon
                 windsurf,
                 kiro,
                 other,
+                force_non_streaming: Vec::new(),
             }
         })
 }
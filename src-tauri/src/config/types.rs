@@ -196,6 +196,10 @@ pub struct EndpointProvidersConfig {
     /// 如果为空，则使用 default_provider
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub other: Option<String>,
+    /// 强制非流式的客户端类型（配置键名）
+    /// 即使客户端请求 `stream: true`，也缓冲上游完整响应后一次性返回
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub force_non_streaming: Vec<String>,
 }
 
 impl EndpointProvidersConfig {
@@ -256,6 +260,29 @@ impl EndpointProvidersConfig {
             _ => false,
         }
     }
+
+    /// 客户端类型是否强制非流式
+    pub fn is_force_non_streaming(&self, client_type: &str) -> bool {
+        self.force_non_streaming.iter().any(|c| c == client_type)
+    }
+
+    /// 设置客户端类型是否强制非流式
+    ///
+    /// # 返回
+    /// 如果客户端类型有效，返回 true；否则返回 false
+    pub fn set_force_non_streaming(&mut self, client_type: &str, enabled: bool) -> bool {
+        if !matches!(
+            client_type,
+            "cursor" | "claude_code" | "codex" | "windsurf" | "kiro" | "other"
+        ) {
+            return false;
+        }
+        self.force_non_streaming.retain(|c| c != client_type);
+        if enabled {
+            self.force_non_streaming.push(client_type.to_string());
+        }
+        true
+    }
 }
 
 /// 主配置结构
@@ -323,6 +350,63 @@ pub struct Config {
     /// 实验室功能配置
     #[serde(default)]
     pub experimental: ExperimentalFeatures,
+    /// 并发限制配置
+    #[serde(default)]
+    pub concurrency: ConcurrencySettings,
+    /// 配置自动备份
+    #[serde(default)]
+    pub backup: BackupSettings,
+    /// 数据库定时维护
+    #[serde(default)]
+    pub database_maintenance: DatabaseMaintenanceSettings,
+    /// 响应缓存
+    #[serde(default)]
+    pub response_cache: ResponseCacheSettings,
+    /// 出站代理（Provider 上游请求）
+    #[serde(default)]
+    pub outbound_proxy: OutboundProxySettings,
+    /// 上游请求头（User-Agent 与客户端标识）
+    #[serde(default)]
+    pub upstream_headers: UpstreamHeadersSettings,
+    /// 凭证健康评分配置
+    #[serde(default)]
+    pub health_scoring: HealthScoringSettings,
+    /// 单次请求费用上限
+    #[serde(default)]
+    pub spend_guard: SpendGuardSettings,
+    /// 凭证影子测试
+    #[serde(default)]
+    pub shadow_test: ShadowTestSettings,
+    /// 链路追踪导出
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    /// 失败请求死信记录
+    #[serde(default)]
+    pub dead_letter: DeadLetterSettings,
+    /// SSE 断线续传
+    #[serde(default)]
+    pub stream_resume: StreamResumeSettings,
+    /// 请求限制（工具数量与大小）
+    #[serde(default)]
+    pub limits: RequestLimitsSettings,
+    /// 流式响应转发
+    #[serde(default)]
+    pub stream: StreamSettings,
+    /// OAuth 登录回调端口
+    #[serde(default)]
+    pub oauth_callback: OAuthCallbackSettings,
+    /// 模型价格表（模型 ID -> 价格），优先于模型注册表中的价格
+    #[serde(default)]
+    pub price_table: HashMap<String, PriceTableEntry>,
+    /// 内置终端
+    #[serde(default)]
+    pub terminal: TerminalSettings,
+    /// 工具结果截断
+    #[serde(default)]
+    pub tool_result_truncation: ToolResultTruncationSettings,
+    /// WebSocket（`/v1/ws`）
+    #[serde(default)]
+    pub websocket: WebSocketSettings,
 }
 
 // ============ Native Agent 配置类型 ============
@@ -528,6 +612,66 @@ pub struct ServerConfig {
     /// TLS 配置
     #[serde(default)]
     pub tls: TlsConfig,
+    /// 轮换 API 密钥后旧密钥仍然有效的时间（分钟，0 表示立即失效）
+    #[serde(default = "default_api_key_grace_minutes")]
+    pub api_key_grace_minutes: u32,
+    /// 服务器任务监控
+    #[serde(default)]
+    pub watchdog: ServerWatchdogConfig,
+}
+
+/// 服务器任务监控配置
+///
+/// 服务器任务意外退出（出错或 panic）时总会标记为已停止并更新托盘；
+/// 开启 `auto_restart` 后按指数退避自动重启。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerWatchdogConfig {
+    /// 是否自动重启
+    #[serde(default)]
+    pub auto_restart: bool,
+    /// 连续重启的最大次数（服务器稳定运行一段时间后重新计数）
+    #[serde(default = "default_watchdog_max_restarts")]
+    pub max_restarts: u32,
+    /// 首次重启前的等待时间（秒），之后每次翻倍
+    #[serde(default = "default_watchdog_initial_backoff_secs")]
+    pub initial_backoff_secs: u64,
+    /// 重启等待时间上限（秒）
+    #[serde(default = "default_watchdog_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+}
+
+fn default_watchdog_max_restarts() -> u32 {
+    5
+}
+
+fn default_watchdog_initial_backoff_secs() -> u64 {
+    1
+}
+
+fn default_watchdog_max_backoff_secs() -> u64 {
+    60
+}
+
+impl Default for ServerWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            auto_restart: false,
+            max_restarts: default_watchdog_max_restarts(),
+            initial_backoff_secs: default_watchdog_initial_backoff_secs(),
+            max_backoff_secs: default_watchdog_max_backoff_secs(),
+        }
+    }
+}
+
+impl ServerWatchdogConfig {
+    /// 第 `attempt` 次重启（从 1 开始）前的等待时间
+    pub fn backoff(&self, attempt: u32) -> std::time::Duration {
+        let secs = self
+            .initial_backoff_secs
+            .max(1)
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(20));
+        std::time::Duration::from_secs(secs.min(self.max_backoff_secs.max(1)))
+    }
 }
 
 /// TLS 配置
@@ -639,6 +783,10 @@ fn default_api_key() -> String {
     DEFAULT_API_KEY.to_string()
 }
 
+fn default_api_key_grace_minutes() -> u32 {
+    10
+}
+
 /// 生成安全 API Key（32 字节随机）
 pub fn generate_secure_api_key() -> String {
     use rand::distributions::Alphanumeric;
@@ -664,6 +812,8 @@ impl Default for ServerConfig {
             port: default_port(),
             api_key: default_api_key(),
             tls: TlsConfig::default(),
+            api_key_grace_minutes: default_api_key_grace_minutes(),
+            watchdog: ServerWatchdogConfig::default(),
         }
     }
 }
@@ -763,17 +913,92 @@ pub struct RoutingConfig {
     /// 模型别名映射
     #[serde(default)]
     pub model_aliases: HashMap<String, String>,
+    /// 匹配别名前是否规范化模型名（去掉 `-20250929` 等日期后缀）
+    #[serde(default = "default_normalize_model_names")]
+    pub normalize_model_names: bool,
+    /// 模型到凭证层级的偏好规则（按顺序匹配，首条命中生效）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tier_rules: Vec<TierRule>,
+    /// 凭证选择策略
+    #[serde(default)]
+    pub selection_strategy: CredentialSelectionStrategy,
+    /// 延迟优先策略下随机选择其他凭证的探索概率（0~1）
+    #[serde(default = "default_latency_exploration_rate")]
+    pub latency_exploration_rate: f64,
+    /// 凭证降级链：Provider 类型 -> 其凭证全部不可用时依次尝试的 API Key Provider 类型
+    ///
+    /// 如 `claude_oauth: [anthropic]`。链上都没有可用凭证时仍按内置映射智能降级。
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub credential_fallback: HashMap<String, Vec<String>>,
+    /// 模型降级链：模型 -> 其凭证（含凭证降级）全部不可用时依次改用的模型
+    ///
+    /// 如 `claude-opus-4-5: [claude-sonnet-4-5, gemini-2.5-pro]`。按解析别名后的模型名匹配，
+    /// 替换后的模型通过 `x-proxycast-model-fallback` 响应头告知客户端。
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_fallbacks: HashMap<String, Vec<String>>,
+    /// 默认 Provider 按权重分流（非空时替代 `default_provider`）
+    ///
+    /// 未命中端点 Provider 配置的请求按权重随机选择 Provider，如 Kiro 70 / Antigravity 30。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_provider_split: Vec<ProviderWeight>,
+}
+
+/// 默认 Provider 分流权重
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderWeight {
+    /// Provider 名称（与 `default_provider` 取值相同）
+    pub provider: String,
+    /// 相对权重，0 表示不分配流量
+    pub weight: u32,
+}
+
+/// 凭证选择策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialSelectionStrategy {
+    /// 综合健康分、使用次数、错误率和冷却时间打分（默认）
+    #[default]
+    Weighted,
+    /// 优先选择历史上游延迟（EMA）最低的健康凭证
+    LatencyAware,
+}
+
+/// 凭证层级偏好规则
+///
+/// 模型匹配 `pattern` 时优先选择 `tier` 相同的凭证，
+/// 没有可用的该层级凭证时回退到任意健康凭证。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TierRule {
+    /// 模型通配符（如 `gemini-*-pro`、`*-flash`）
+    pub pattern: String,
+    /// 偏好的凭证层级（如 `paid`、`free`）
+    pub tier: String,
 }
 
 fn default_provider() -> String {
     "kiro".to_string()
 }
 
+fn default_normalize_model_names() -> bool {
+    true
+}
+
+fn default_latency_exploration_rate() -> f64 {
+    0.05
+}
+
 impl Default for RoutingConfig {
     fn default() -> Self {
         Self {
             default_provider: default_provider(),
             model_aliases: HashMap::new(),
+            normalize_model_names: default_normalize_model_names(),
+            tier_rules: Vec::new(),
+            selection_strategy: CredentialSelectionStrategy::default(),
+            latency_exploration_rate: default_latency_exploration_rate(),
+            credential_fallback: HashMap::new(),
+            model_fallbacks: HashMap::new(),
+            default_provider_split: Vec::new(),
         }
     }
 }
@@ -793,6 +1018,23 @@ pub struct RetrySettings {
     /// 是否自动切换 Provider
     #[serde(default = "default_auto_switch")]
     pub auto_switch_provider: bool,
+    /// 可重试的 HTTP 状态码（默认 429、500、502、503、504；408 默认不重试）
+    #[serde(default = "default_retryable_status_codes")]
+    pub retryable_status_codes: Vec<u16>,
+    /// 未收到任何响应字节的连接错误是否重试
+    #[serde(default = "default_retry_on_connection_error")]
+    pub retry_on_connection_error: bool,
+    /// 流式请求在输出开始前收到 5xx 时是否重试（默认不重试；输出开始后任何错误都不重试）
+    #[serde(default)]
+    pub retry_streaming_server_errors: bool,
+}
+
+fn default_retryable_status_codes() -> Vec<u16> {
+    vec![429, 500, 502, 503, 504]
+}
+
+fn default_retry_on_connection_error() -> bool {
+    true
 }
 
 fn default_max_retries() -> u32 {
@@ -818,10 +1060,663 @@ impl Default for RetrySettings {
             base_delay_ms: default_base_delay_ms(),
             max_delay_ms: default_max_delay_ms(),
             auto_switch_provider: default_auto_switch(),
+            retryable_status_codes: default_retryable_status_codes(),
+            retry_on_connection_error: default_retry_on_connection_error(),
+            retry_streaming_server_errors: false,
+        }
+    }
+}
+
+/// 并发限制配置
+///
+/// 按 Provider 类型限制同时进行的上游请求数，避免触发上游速率限制
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConcurrencySettings {
+    /// 每个 Provider 类型的最大并发数（如 `kiro: 4`），未配置或为 0 表示不限制
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub per_provider: HashMap<String, usize>,
+    /// 每个凭证的最大并发数，0 表示不限制
+    #[serde(default)]
+    pub per_credential: usize,
+    /// 并发已满时是否排队等待（false 则立即返回 429）
+    #[serde(default = "default_concurrency_queue")]
+    pub queue_when_saturated: bool,
+    /// 排队等待超时（毫秒）
+    #[serde(default = "default_concurrency_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
+    /// 批量请求排队超过该时长（毫秒）后不再让位于交互式请求，0 表示按到达顺序排队
+    ///
+    /// 请求优先级由 `x-priority` 请求头（interactive / batch）指定，
+    /// 未指定时已识别的客户端（Claude Code、Cursor 等）视为交互式，其余视为批量
+    #[serde(default = "default_concurrency_batch_max_wait_ms")]
+    pub batch_max_wait_ms: u64,
+}
+
+fn default_concurrency_queue() -> bool {
+    true
+}
+
+fn default_concurrency_queue_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_concurrency_batch_max_wait_ms() -> u64 {
+    5_000
+}
+
+impl Default for ConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            per_provider: HashMap::new(),
+            per_credential: 0,
+            queue_when_saturated: default_concurrency_queue(),
+            queue_timeout_ms: default_concurrency_queue_timeout_ms(),
+            batch_max_wait_ms: default_concurrency_batch_max_wait_ms(),
+        }
+    }
+}
+
+/// 配置自动备份设置
+///
+/// 定期将完整配置与 OAuth token 文件（不脱敏）加密写入备份目录，仅保留最近 N 份；
+/// 不包含凭证池数据库
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackupSettings {
+    /// 是否启用定时备份
+    #[serde(default)]
+    pub enabled: bool,
+    /// 备份间隔（小时）
+    #[serde(default = "default_backup_interval_hours")]
+    pub interval_hours: u64,
+    /// 备份目录
+    #[serde(default = "default_backup_dir")]
+    pub directory: String,
+    /// 保留的备份数量
+    #[serde(default = "default_backup_keep_last")]
+    pub keep_last: usize,
+}
+
+fn default_backup_interval_hours() -> u64 {
+    24
+}
+
+fn default_backup_dir() -> String {
+    "~/.proxycast/config_backups".to_string()
+}
+
+fn default_backup_keep_last() -> usize {
+    7
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: default_backup_interval_hours(),
+            directory: default_backup_dir(),
+            keep_last: default_backup_keep_last(),
+        }
+    }
+}
+
+/// 数据库维护设置
+///
+/// 每天在指定时刻（本地时间）删除超出保留期的使用统计、死信和切换日志，
+/// 然后执行 `ANALYZE` 和 `VACUUM` 回收空间。默认不删除记录，需要用户设置保留天数
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DatabaseMaintenanceSettings {
+    /// 是否启用定时维护
+    #[serde(default = "default_db_maintenance_enabled")]
+    pub enabled: bool,
+    /// 执行时刻（本地时间的小时，0-23）
+    #[serde(default = "default_db_maintenance_hour")]
+    pub hour: u32,
+    /// 记录保留天数，0 表示不删除
+    #[serde(default = "default_db_maintenance_retention_days")]
+    pub retention_days: u32,
+    /// 是否执行 VACUUM（会短暂阻塞其他数据库访问）
+    #[serde(default = "default_db_maintenance_vacuum")]
+    pub vacuum: bool,
+}
+
+fn default_db_maintenance_enabled() -> bool {
+    true
+}
+
+fn default_db_maintenance_hour() -> u32 {
+    4
+}
+
+fn default_db_maintenance_retention_days() -> u32 {
+    0
+}
+
+fn default_db_maintenance_vacuum() -> bool {
+    true
+}
+
+impl Default for DatabaseMaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_db_maintenance_enabled(),
+            hour: default_db_maintenance_hour(),
+            retention_days: default_db_maintenance_retention_days(),
+            vacuum: default_db_maintenance_vacuum(),
+        }
+    }
+}
+
+/// 响应缓存配置
+///
+/// 对 temperature 为 0 或未设置的非流式请求缓存上游响应，相同请求直接返回缓存
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseCacheSettings {
+    /// 是否启用响应缓存
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存有效期（秒）
+    #[serde(default = "default_response_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最大缓存条目数
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: usize,
+    /// 离线降级：上游连接失败时返回相同请求最近一次的缓存响应（即使已过期），
+    /// 响应头带 `x-proxycast-cache: stale-offline`
+    #[serde(default)]
+    pub offline_fallback: bool,
+}
+
+fn default_response_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_response_cache_max_entries() -> usize {
+    256
+}
+
+impl Default for ResponseCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_response_cache_ttl_secs(),
+            max_entries: default_response_cache_max_entries(),
+            offline_fallback: false,
+        }
+    }
+}
+
+/// 凭证健康评分配置
+///
+/// 每次请求结果按指数移动平均（EMA）更新凭证健康分（0~1），
+/// 选择凭证时优先高分凭证，分数低于 `disable_below` 时才标记为不健康。
+/// 设置 `auto_disable_after_days` 后，连续不健康超过该天数的凭证会被自动禁用（不会删除）。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthScoringSettings {
+    /// EMA 平滑系数（0~1，越大越看重最近的请求结果）
+    #[serde(default = "default_health_ema_alpha")]
+    pub ema_alpha: f64,
+    /// 健康分低于该值时标记为不健康
+    #[serde(default = "default_health_disable_below")]
+    pub disable_below: f64,
+    /// 连续不健康超过该天数后自动禁用凭证（为空或 0 时关闭）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_disable_after_days: Option<u32>,
+}
+
+fn default_health_ema_alpha() -> f64 {
+    0.3
+}
+
+fn default_health_disable_below() -> f64 {
+    0.3
+}
+
+impl Default for HealthScoringSettings {
+    fn default() -> Self {
+        Self {
+            ema_alpha: default_health_ema_alpha(),
+            disable_below: default_health_disable_below(),
+            auto_disable_after_days: None,
+        }
+    }
+}
+
+impl HealthScoringSettings {
+    /// 按本次请求结果更新健康分
+    pub fn next_score(&self, current: f64, success: bool) -> f64 {
+        let alpha = self.ema_alpha.clamp(0.0, 1.0);
+        let sample = if success { 1.0 } else { 0.0 };
+        (alpha * sample + (1.0 - alpha) * current).clamp(0.0, 1.0)
+    }
+
+    /// 自动禁用前允许的连续不健康时长（未开启时为 None）
+    pub fn auto_disable_after(&self) -> Option<chrono::Duration> {
+        self.auto_disable_after_days
+            .filter(|days| *days > 0)
+            .map(|days| chrono::Duration::days(i64::from(days)))
+    }
+}
+
+/// 单次请求费用上限配置
+///
+/// 流式响应按价格表和 token 数估算费用，超过上限时中止上游流并以 `length` 结束。
+/// 请求可通过 `x-proxycast-max-cost` 头覆盖（`0` / `off` 表示不限制）。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SpendGuardSettings {
+    /// 默认费用上限（美元），为空时不限制
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost_usd: Option<f64>,
+}
+
+/// 凭证影子测试配置
+///
+/// 按采样比例将请求复制一份发送到影子凭证，响应只用于和实际返回的响应对比，
+/// 结果记录在 Flow Monitor 中，不影响客户端。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShadowTestSettings {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 影子凭证 UUID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_uuid: Option<String>,
+    /// 采样比例（0~1）
+    #[serde(default = "default_shadow_sample_rate")]
+    pub sample_rate: f64,
+}
+
+fn default_shadow_sample_rate() -> f64 {
+    0.1
+}
+
+impl Default for ShadowTestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            credential_uuid: None,
+            sample_rate: default_shadow_sample_rate(),
+        }
+    }
+}
+
+/// 链路追踪导出配置
+///
+/// 配置 `otlp_endpoint` 后，每个请求导出为一条 OpenTelemetry 链路（OTLP/HTTP），
+/// 需要以 `otlp` 特性编译，否则忽略。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TelemetrySettings {
+    /// OTLP 接收地址（如 `http://localhost:4318`），为空时不导出
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otlp_endpoint: Option<String>,
+    /// 上报的服务名
+    #[serde(default = "default_otlp_service_name")]
+    pub service_name: String,
+}
+
+fn default_otlp_service_name() -> String {
+    "proxycast".to_string()
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: default_otlp_service_name(),
+        }
+    }
+}
+
+/// 死信记录配置
+///
+/// 启用后，Provider 调用失败的请求（脱敏后的请求体、Provider、错误）写入数据库，
+/// 可查询并重放。请求体可能包含敏感内容，默认关闭。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeadLetterSettings {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 最多保留的记录数，超出后删除最旧的记录
+    #[serde(default = "default_dead_letter_max_entries")]
+    pub max_entries: usize,
+    /// 单条记录请求体的最大字节数，超出后截断（截断的记录不可重放）
+    #[serde(default = "default_dead_letter_max_body_bytes")]
+    pub max_body_bytes: usize,
+}
+
+fn default_dead_letter_max_entries() -> usize {
+    500
+}
+
+fn default_dead_letter_max_body_bytes() -> usize {
+    256 * 1024
+}
+
+impl Default for DeadLetterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: default_dead_letter_max_entries(),
+            max_body_bytes: default_dead_letter_max_body_bytes(),
+        }
+    }
+}
+
+/// SSE 断线续传配置
+///
+/// 启用后流式响应的事件带有递增的 `id:`，客户端断线后可携带 `Last-Event-ID` 重连续传。
+/// 上游响应会在客户端断开后继续读取（以便续传），默认关闭。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamResumeSettings {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每个流最多缓冲的事件数，超出后丢弃最早的事件（之前的断点无法续传）
+    #[serde(default = "default_stream_resume_max_buffered_events")]
+    pub max_buffered_events: usize,
+    /// 流结束后保留缓冲区的秒数，供客户端最后一次重连
+    #[serde(default = "default_stream_resume_retention_secs")]
+    pub retention_secs: u64,
+}
+
+fn default_stream_resume_max_buffered_events() -> usize {
+    4096
+}
+
+fn default_stream_resume_retention_secs() -> u64 {
+    60
+}
+
+impl Default for StreamResumeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_buffered_events: default_stream_resume_max_buffered_events(),
+            retention_secs: default_stream_resume_retention_secs(),
+        }
+    }
+}
+
+/// 请求限制配置
+///
+/// 超出限制的请求在调用上游前直接返回 400，并列出超限的数量或工具，
+/// 避免上游返回含义不明的错误。未设置的限制不检查。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RequestLimitsSettings {
+    /// 单个请求最多携带的工具数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tools: Option<usize>,
+    /// 单个工具定义（序列化后）的最大字节数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tool_schema_bytes: Option<usize>,
+    /// 是否截断过长的工具描述（在检查大小之前执行）
+    #[serde(default)]
+    pub truncate_tool_descriptions: bool,
+    /// 工具描述的软上限（字节），超出时截断
+    #[serde(default = "default_max_tool_description_bytes")]
+    pub max_tool_description_bytes: usize,
+    /// 按终端用户（OpenAI `user` / Anthropic `metadata.user_id`）的限制
+    #[serde(default)]
+    pub per_user: UserLimitsSettings,
+}
+
+/// 按终端用户的限制
+///
+/// 用户标识来自请求体，未携带标识的请求不受限制。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UserLimitsSettings {
+    /// 每个用户每分钟最多请求数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// 每个用户每天（UTC）最多消耗的 Token 数（输入 + 输出）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_token_quota: Option<u64>,
+    /// 隐私模式：日志和统计中只保存用户标识的哈希
+    #[serde(default)]
+    pub hash_user_ids: bool,
+}
+
+fn default_max_tool_description_bytes() -> usize {
+    1024
+}
+
+/// OAuth 登录回调端口配置
+///
+/// 登录命令在范围内选择第一个可用端口启动本地回调服务器。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OAuthCallbackSettings {
+    /// 端口范围起点（含）
+    #[serde(default = "default_oauth_callback_port_start")]
+    pub port_range_start: u16,
+    /// 端口范围终点（含）
+    #[serde(default = "default_oauth_callback_port_end")]
+    pub port_range_end: u16,
+}
+
+fn default_oauth_callback_port_start() -> u16 {
+    19823
+}
+
+fn default_oauth_callback_port_end() -> u16 {
+    19833
+}
+
+impl Default for OAuthCallbackSettings {
+    fn default() -> Self {
+        Self {
+            port_range_start: default_oauth_callback_port_start(),
+            port_range_end: default_oauth_callback_port_end(),
+        }
+    }
+}
+
+/// 工具结果截断配置
+///
+/// 调用上游之前把超出预算的工具结果截断为开头 + 结尾两段，避免下一轮请求撑满上下文窗口。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolResultTruncationSettings {
+    /// 是否启用（可按 Provider 覆盖）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 单个工具结果的最大字节数
+    #[serde(default = "default_tool_result_max_bytes")]
+    pub max_bytes: usize,
+    /// 单个工具结果的最大 token 数（按约 4 字节 / token 估算，与 `max_bytes` 取较小值）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// 按 Provider 类型（如 `claude`、`kiro`、`openai`）覆盖
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub providers: HashMap<String, ToolResultBudget>,
+}
+
+fn default_tool_result_max_bytes() -> usize {
+    64 * 1024
+}
+
+impl Default for ToolResultTruncationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: default_tool_result_max_bytes(),
+            max_tokens: None,
+            providers: HashMap::new(),
+        }
+    }
+}
+
+/// 单个 Provider 的工具结果截断覆盖，未设置的项使用全局配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ToolResultBudget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+/// 终端回滚缓冲区默认容量（1MB）
+pub const DEFAULT_TERMINAL_SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+/// 内置终端配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TerminalSettings {
+    /// 每个会话保留的输出历史（字节），重新连接会话时回放
+    #[serde(default = "default_terminal_scrollback_bytes")]
+    pub scrollback_bytes: usize,
+}
+
+fn default_terminal_scrollback_bytes() -> usize {
+    DEFAULT_TERMINAL_SCROLLBACK_BYTES
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        Self {
+            scrollback_bytes: default_terminal_scrollback_bytes(),
+        }
+    }
+}
+
+/// WebSocket 配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct WebSocketSettings {
+    /// 启用 permessage-deflate 压缩（客户端在升级请求中提供该扩展时生效）
+    #[serde(default)]
+    pub compression: bool,
+}
+
+/// 模型价格（美元 / 百万 token）
+///
+/// 模型 ID 不区分大小写，未精确匹配时按最长前缀匹配（兼容带日期后缀的模型名）。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PriceTableEntry {
+    /// 输入价格
+    pub input_per_million: f64,
+    /// 输出价格
+    pub output_per_million: f64,
+    /// 缓存写入价格（未设置时按输入价格的 1.25 倍计算）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_per_million: Option<f64>,
+    /// 缓存读取价格（未设置时按输入价格的 0.1 倍计算）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_per_million: Option<f64>,
+}
+
+impl PriceTableEntry {
+    /// 所有价格都是非负有限数
+    pub fn is_valid(&self) -> bool {
+        [
+            Some(self.input_per_million),
+            Some(self.output_per_million),
+            self.cache_write_per_million,
+            self.cache_read_per_million,
+        ]
+        .into_iter()
+        .flatten()
+        .all(|p| p.is_finite() && p >= 0.0)
+    }
+}
+
+/// 流式响应转发配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct StreamSettings {
+    /// 内容增量的合并窗口（毫秒），0 表示逐个事件透传
+    ///
+    /// 窗口内的文本/思考增量合并为一次下游写入，工具调用和结束事件立即发送。
+    /// 能降低逐 token 上游的单事件开销和抖动，代价是首字和每段文本最多延迟一个窗口。
+    #[serde(default)]
+    pub coalesce_ms: u64,
+}
+
+impl Default for RequestLimitsSettings {
+    fn default() -> Self {
+        Self {
+            max_tools: None,
+            max_tool_schema_bytes: None,
+            truncate_tool_descriptions: false,
+            max_tool_description_bytes: default_max_tool_description_bytes(),
+            per_user: UserLimitsSettings::default(),
+        }
+    }
+}
+
+/// 出站代理配置
+///
+/// 所有 Provider 上游请求统一经由该代理发出，支持 http / https / socks5，
+/// 可按 Provider 覆盖代理地址（值为 `direct` 时该 Provider 直连）。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutboundProxySettings {
+    /// 代理 URL（为空时回退到全局 `proxy_url`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// 代理认证用户名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// 代理认证密码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// 按 Provider 覆盖的代理 URL（Provider 名称 -> 代理 URL 或 `direct`）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub overrides: HashMap<String, String>,
+    /// 连接超时（秒）
+    #[serde(default = "default_outbound_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// 读取超时（秒），两次读取之间的最长间隔，不限制流式响应总时长
+    #[serde(default = "default_outbound_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+}
+
+fn default_outbound_connect_timeout_secs() -> u64 {
+    30
+}
+
+fn default_outbound_read_timeout_secs() -> u64 {
+    300
+}
+
+impl Default for OutboundProxySettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            username: None,
+            password: None,
+            overrides: HashMap::new(),
+            connect_timeout_secs: default_outbound_connect_timeout_secs(),
+            read_timeout_secs: default_outbound_read_timeout_secs(),
         }
     }
 }
 
+/// 上游请求头配置
+///
+/// 作为共享客户端的默认请求头发送给所有 Provider，Provider 代码显式设置的同名请求头优先。
+/// 凭证记录上的 `upstream_headers` 可按账号覆盖。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UpstreamHeadersSettings {
+    /// 默认 User-Agent（为空时使用 `ProxyCast/<版本>`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// 所有上游请求附加的请求头
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// 按 Provider 附加的请求头（Provider 名称 -> 请求头），覆盖全局同名请求头
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub providers: HashMap<String, HashMap<String, String>>,
+    /// Kiro 客户端版本（用于 Kiro 指纹请求头，为空时自动检测）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kiro_version: Option<String>,
+    /// Anthropic 原生调用的 `anthropic-version`（为空时使用 `2023-06-01`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anthropic_version: Option<String>,
+    /// Anthropic 原生调用默认启用的 beta 功能（合并为 `anthropic-beta` 请求头）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anthropic_betas: Vec<String>,
+    /// 转发给客户端的上游响应头白名单（不区分大小写，`*` 结尾表示前缀匹配，
+    /// 如 `anthropic-ratelimit-*`、`x-request-id`）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forward_response_headers: Vec<String>,
+}
+
 /// 日志配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct LoggingConfig {
@@ -875,6 +1770,12 @@ pub struct ModelInfo {
     /// 是否启用
     #[serde(default = "default_model_enabled")]
     pub enabled: bool,
+    /// 客户端未指定 max_tokens 时填充的默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_max_tokens: Option<u32>,
+    /// 模型允许的最大输出 token 数，请求超出时截断到该值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
 }
 
 fn default_model_enabled() -> bool {
@@ -923,16 +1824,22 @@ impl Default for ModelsConfig {
                         id: "claude-opus-4-5-20251101".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "claude-sonnet-4-5-20250929".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "claude-sonnet-4-20250514".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                 ],
             },
@@ -948,16 +1855,22 @@ impl Default for ModelsConfig {
                         id: "claude-opus-4-5-20251101".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "claude-sonnet-4-5-20250929".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "claude-sonnet-4-20250514".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                 ],
             },
@@ -973,11 +1886,15 @@ impl Default for ModelsConfig {
                         id: "claude-sonnet-4-5-20250929".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "claude-sonnet-4-20250514".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                 ],
             },
@@ -993,36 +1910,50 @@ impl Default for ModelsConfig {
                         id: "gpt-4o".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gpt-4o-mini".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gpt-4-turbo".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "o1".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "o1-mini".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "o3".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "o3-mini".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                 ],
             },
@@ -1038,16 +1969,22 @@ impl Default for ModelsConfig {
                         id: "gemini-2.0-flash-exp".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gemini-1.5-pro".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gemini-1.5-flash".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                 ],
             },
@@ -1063,16 +2000,22 @@ impl Default for ModelsConfig {
                         id: "qwen-max".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "qwen-plus".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "qwen-turbo".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                 ],
             },
@@ -1087,6 +2030,8 @@ impl Default for ModelsConfig {
                     id: "codex-mini-latest".to_string(),
                     name: None,
                     enabled: true,
+                    default_max_tokens: None,
+                    max_output_tokens: None,
                 }],
             },
         );
@@ -1101,11 +2046,15 @@ impl Default for ModelsConfig {
                         id: "claude-sonnet-4-5-20250929".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "claude-3-5-sonnet-20241022".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                 ],
             },
@@ -1130,36 +2079,50 @@ impl Default for ModelsConfig {
                         id: "gemini-3-pro-preview".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gemini-3-pro-image-preview".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gemini-3-flash-preview".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gemini-2.5-computer-use-preview-10-2025".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gemini-claude-sonnet-4-5".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gemini-claude-sonnet-4-5-thinking".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                     ModelInfo {
                         id: "gemini-claude-opus-4-5-thinking".to_string(),
                         name: None,
                         enabled: true,
+                        default_max_tokens: None,
+                        max_output_tokens: None,
                     },
                 ],
             },
@@ -1270,6 +2233,25 @@ impl Default for Config {
             models: ModelsConfig::default(),
             agent: NativeAgentConfig::default(),
             experimental: ExperimentalFeatures::default(),
+            concurrency: ConcurrencySettings::default(),
+            backup: BackupSettings::default(),
+            database_maintenance: DatabaseMaintenanceSettings::default(),
+            response_cache: ResponseCacheSettings::default(),
+            outbound_proxy: OutboundProxySettings::default(),
+            upstream_headers: UpstreamHeadersSettings::default(),
+            health_scoring: HealthScoringSettings::default(),
+            spend_guard: SpendGuardSettings::default(),
+            shadow_test: ShadowTestSettings::default(),
+            telemetry: TelemetrySettings::default(),
+            dead_letter: DeadLetterSettings::default(),
+            stream_resume: StreamResumeSettings::default(),
+            limits: RequestLimitsSettings::default(),
+            stream: StreamSettings::default(),
+            oauth_callback: OAuthCallbackSettings::default(),
+            price_table: HashMap::new(),
+            terminal: TerminalSettings::default(),
+            tool_result_truncation: ToolResultTruncationSettings::default(),
+            websocket: WebSocketSettings::default(),
         }
     }
 }
@@ -1402,6 +2384,21 @@ mod unit_tests {
         assert_eq!(config.host, "127.0.0.1");
         assert_eq!(config.port, 8999);
         assert_eq!(config.api_key, "proxy_cast");
+        assert!(!config.watchdog.auto_restart);
+    }
+
+    #[test]
+    fn test_server_watchdog_backoff() {
+        let watchdog = ServerWatchdogConfig {
+            initial_backoff_secs: 2,
+            max_backoff_secs: 10,
+            ..Default::default()
+        };
+        assert_eq!(watchdog.backoff(1).as_secs(), 2);
+        assert_eq!(watchdog.backoff(2).as_secs(), 4);
+        assert_eq!(watchdog.backoff(3).as_secs(), 8);
+        assert_eq!(watchdog.backoff(4).as_secs(), 10);
+        assert_eq!(watchdog.backoff(100).as_secs(), 10);
     }
 
     #[test]
@@ -1427,6 +2424,23 @@ mod unit_tests {
         let config = RoutingConfig::default();
         assert_eq!(config.default_provider, "kiro");
         assert!(config.model_aliases.is_empty());
+        assert!(config.tier_rules.is_empty());
+    }
+
+    #[test]
+    fn test_routing_config_tier_rules_yaml() {
+        let yaml = r#"
+default_provider: gemini
+tier_rules:
+  - pattern: "gemini-*-pro"
+    tier: paid
+  - pattern: "*-flash"
+    tier: free
+"#;
+        let config: RoutingConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.tier_rules.len(), 2);
+        assert_eq!(config.tier_rules[0].pattern, "gemini-*-pro");
+        assert_eq!(config.tier_rules[1].tier, "free");
     }
 
     #[test]
@@ -1449,6 +2463,7 @@ mod unit_tests {
             windsurf: None,
             kiro: Some("gemini".to_string()),
             other: None,
+            force_non_streaming: Vec::new(),
         };
 
         assert_eq!(config.get_provider("cursor"), Some(&"qwen".to_string()));
@@ -1499,6 +2514,7 @@ mod unit_tests {
             windsurf: None,
             kiro: None,
             other: None,
+            force_non_streaming: Vec::new(),
         };
 
         // 使用 None 清除配置
@@ -1510,6 +2526,27 @@ mod unit_tests {
         assert_eq!(config.claude_code, None);
     }
 
+    #[test]
+    fn test_endpoint_providers_config_force_non_streaming() {
+        let mut config = EndpointProvidersConfig::default();
+        assert!(!config.is_force_non_streaming("cursor"));
+
+        assert!(config.set_force_non_streaming("cursor", true));
+        assert!(config.set_force_non_streaming("cursor", true));
+        assert!(config.is_force_non_streaming("cursor"));
+        assert_eq!(config.force_non_streaming, vec!["cursor".to_string()]);
+
+        assert!(config.set_force_non_streaming("cursor", false));
+        assert!(!config.is_force_non_streaming("cursor"));
+
+        assert!(!config.set_force_non_streaming("invalid", true));
+        assert!(config.force_non_streaming.is_empty());
+
+        // 空列表不写入 YAML
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(!yaml.contains("force_non_streaming"));
+    }
+
     #[test]
     fn test_endpoint_providers_config_serialization() {
         let config = EndpointProvidersConfig {
@@ -1519,6 +2556,7 @@ mod unit_tests {
             windsurf: None,
             kiro: None,
             other: None,
+            force_non_streaming: Vec::new(),
         };
 
         let yaml = serde_yaml::to_string(&config).unwrap();
@@ -1543,6 +2581,7 @@ mod unit_tests {
             windsurf: None,
             kiro: None,
             other: Some("openai".to_string()),
+            force_non_streaming: Vec::new(),
         };
 
         let json = serde_json::to_string(&config).unwrap();
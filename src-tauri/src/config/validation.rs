@@ -0,0 +1,882 @@
+//! 配置校验与 JSON Schema 导出
+//!
+//! `validate_yaml` 返回带字段路径、行号和严重级别的结构化诊断，
+//! `config_schema` 导出 `Config` 的 JSON Schema 供前端在保存前内联校验。
+//! 安全规则（监听地址、默认 API Key、远程管理需要 TLS）在两处使用相同的提示文案。
+
+use super::hot_reload::{is_non_local_bind, is_valid_bind_host};
+use super::types::{is_default_api_key, Config, DEFAULT_API_KEY};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+const MSG_INVALID_HOST: &str =
+    "无效的监听地址。允许的地址：127.0.0.1、localhost、::1、0.0.0.0、::、私有网络地址";
+const MSG_DEFAULT_KEY_NON_LOCAL: &str =
+    "监听所有网络接口 (0.0.0.0 或 ::) 时，必须设置非默认的 API Key";
+const MSG_NON_LOCAL_BIND: &str = "当前监听地址会将代理暴露到局域网，请确认防火墙设置";
+const MSG_TLS_UNSUPPORTED: &str = "当前版本尚未支持 TLS，请关闭 TLS 配置";
+const MSG_REMOTE_REQUIRES_TLS: &str = "远程管理需要 TLS 支持，当前版本未启用";
+const MSG_PORT_ZERO: &str = "端口号不能为 0";
+const MSG_API_KEY_EMPTY: &str = "API Key 不能为空";
+const MSG_MAX_RETRIES: &str = "最大重试次数不能超过 100";
+const MSG_BASE_DELAY_ZERO: &str = "基础延迟不能为 0";
+const MSG_RETENTION_ZERO: &str = "日志保留天数不能为 0";
+const MSG_HEALTH_EMA_ALPHA: &str = "健康评分平滑系数必须大于 0 且不超过 1";
+const MSG_HEALTH_DISABLE_BELOW: &str = "健康分下限必须在 0 到 1 之间";
+const MSG_MAX_COST: &str = "单次请求费用上限必须大于 0";
+const MSG_SHADOW_SAMPLE_RATE: &str = "影子测试采样比例必须在 0 到 1 之间";
+const MSG_SHADOW_CREDENTIAL: &str = "启用影子测试时必须指定影子凭证";
+const MSG_OTLP_ENDPOINT: &str = "OTLP 地址必须以 http:// 或 https:// 开头";
+const MSG_PROXY_URL: &str = "代理 URL 无效（例如 http://host:port 或 socks5://host:port）";
+const MSG_UPSTREAM_HEADER: &str = "上游请求头名称或值无效";
+const MSG_ANTHROPIC_VERSION: &str = "anthropic_version 不是合法的请求头值";
+const MSG_ANTHROPIC_BETA: &str = "beta 名称不能为空，且不能包含逗号或空白";
+const MSG_DEAD_LETTER_MAX_ENTRIES: &str = "死信最大记录数不能为 0";
+const MSG_DB_MAINTENANCE_HOUR: &str = "数据库维护时刻必须在 0 到 23 之间";
+const MSG_STREAM_RESUME_MAX_EVENTS: &str = "续传缓冲事件数不能为 0";
+const MSG_LIMITS_MAX_TOOLS: &str = "最大工具数不能为 0";
+const MSG_LIMITS_MAX_TOOL_SCHEMA_BYTES: &str = "工具定义大小上限不能为 0";
+const MSG_LIMITS_MAX_TOOL_DESCRIPTION_BYTES: &str = "工具描述软上限不能为 0";
+const MSG_STREAM_COALESCE_MS: &str = "合并窗口不能超过 1000 毫秒";
+const MSG_OAUTH_CALLBACK_PORT_RANGE: &str = "回调端口范围无效（起点不能为 0 且不能大于终点）";
+const MSG_MODEL_MAX_TOKENS: &str = "模型 max_tokens 限制不能为 0";
+const MSG_LATENCY_EXPLORATION_RATE: &str = "延迟探索概率必须在 0 到 1 之间";
+const MSG_UNKNOWN_PROVIDER_TYPE: &str = "未知的 Provider 类型";
+const MSG_DEFAULT_PROVIDER_SPLIT: &str = "默认 Provider 分流的权重总和必须大于 0";
+const MSG_PRICE_TABLE_PRICE: &str = "模型价格必须是非负数";
+const MSG_FALLBACK_NOT_API_KEY: &str =
+    "降级目标必须是 API Key 类型的 Provider（如 anthropic、openai）";
+const MSG_MODEL_FALLBACK_TARGET: &str = "降级模型不能为空，也不能是模型本身";
+const MSG_TOOL_RESULT_BUDGET: &str = "工具结果截断预算不能为 0";
+const MSG_TERMINAL_SCROLLBACK_BYTES: &str = "终端回滚缓冲区大小必须在 1 字节到 64MB 之间";
+
+/// 终端回滚缓冲区容量上限
+const MAX_TERMINAL_SCROLLBACK_BYTES: usize = 64 * 1024 * 1024;
+
+/// 允许的监听地址（回环、所有接口、私有网络），与 `is_valid_bind_host` 保持一致
+const BIND_HOST_PATTERN: &str = r"^(localhost|127\.\d{1,3}\.\d{1,3}\.\d{1,3}|::1|0\.0\.0\.0|::|10\.\d{1,3}\.\d{1,3}\.\d{1,3}|172\.(1[6-9]|2\d|3[01])\.\d{1,3}\.\d{1,3}|192\.168\.\d{1,3}\.\d{1,3})$";
+
+/// 诊断严重级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    /// 错误，配置不能保存
+    Error,
+    /// 警告，可以保存但需要确认
+    Warning,
+}
+
+/// 单条配置诊断
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigDiagnostic {
+    /// 字段路径（如 `server.host`），无法定位到字段时为空
+    pub path: String,
+    /// 提示信息
+    pub message: String,
+    /// 严重级别
+    pub severity: DiagnosticSeverity,
+    /// 所在行（从 1 开始）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 所在列（从 1 开始）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl ConfigDiagnostic {
+    fn error(path: &str, message: &str) -> Self {
+        Self::new(path, message, DiagnosticSeverity::Error)
+    }
+
+    fn warning(path: &str, message: &str) -> Self {
+        Self::new(path, message, DiagnosticSeverity::Warning)
+    }
+
+    fn new(path: &str, message: &str, severity: DiagnosticSeverity) -> Self {
+        Self {
+            path: path.to_string(),
+            message: message.to_string(),
+            severity,
+            line: None,
+            column: None,
+        }
+    }
+}
+
+/// 配置校验报告
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigValidationReport {
+    /// 没有 error 级别的诊断时为 true
+    pub valid: bool,
+    /// 解析成功时的配置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<Config>,
+    /// 诊断列表
+    pub diagnostics: Vec<ConfigDiagnostic>,
+}
+
+/// 校验已解析的配置（语义和安全规则）
+pub fn validate_config(config: &Config) -> Vec<ConfigDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let host = config.server.host.to_lowercase();
+
+    if config.server.port == 0 {
+        diagnostics.push(ConfigDiagnostic::error("server.port", MSG_PORT_ZERO));
+    }
+    if !is_valid_bind_host(&host) {
+        diagnostics.push(ConfigDiagnostic::error("server.host", MSG_INVALID_HOST));
+    } else if is_non_local_bind(&host) {
+        if is_default_api_key(&config.server.api_key) {
+            diagnostics.push(ConfigDiagnostic::error(
+                "server.api_key",
+                MSG_DEFAULT_KEY_NON_LOCAL,
+            ));
+        } else {
+            diagnostics.push(ConfigDiagnostic::warning("server.host", MSG_NON_LOCAL_BIND));
+        }
+    }
+    if config.server.api_key.trim().is_empty() {
+        diagnostics.push(ConfigDiagnostic::error("server.api_key", MSG_API_KEY_EMPTY));
+    }
+    if config.server.tls.enable {
+        diagnostics.push(ConfigDiagnostic::error(
+            "server.tls.enable",
+            MSG_TLS_UNSUPPORTED,
+        ));
+    }
+    if config.remote_management.allow_remote && !config.server.tls.enable {
+        diagnostics.push(ConfigDiagnostic::error(
+            "remote_management.allow_remote",
+            MSG_REMOTE_REQUIRES_TLS,
+        ));
+    }
+    if config.retry.max_retries > 100 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "retry.max_retries",
+            MSG_MAX_RETRIES,
+        ));
+    }
+    if config.retry.base_delay_ms == 0 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "retry.base_delay_ms",
+            MSG_BASE_DELAY_ZERO,
+        ));
+    }
+    if config.logging.retention_days == 0 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "logging.retention_days",
+            MSG_RETENTION_ZERO,
+        ));
+    }
+    let alpha = config.health_scoring.ema_alpha;
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "health_scoring.ema_alpha",
+            MSG_HEALTH_EMA_ALPHA,
+        ));
+    }
+    if !(0.0..1.0).contains(&config.health_scoring.disable_below) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "health_scoring.disable_below",
+            MSG_HEALTH_DISABLE_BELOW,
+        ));
+    }
+    if config
+        .spend_guard
+        .max_cost_usd
+        .is_some_and(|max| max.is_nan() || max <= 0.0)
+    {
+        diagnostics.push(ConfigDiagnostic::error(
+            "spend_guard.max_cost_usd",
+            MSG_MAX_COST,
+        ));
+    }
+    if !(0.0..=1.0).contains(&config.routing.latency_exploration_rate) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "routing.latency_exploration_rate",
+            MSG_LATENCY_EXPLORATION_RATE,
+        ));
+    }
+    if !(0.0..=1.0).contains(&config.shadow_test.sample_rate) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "shadow_test.sample_rate",
+            MSG_SHADOW_SAMPLE_RATE,
+        ));
+    }
+    if config.shadow_test.enabled
+        && config
+            .shadow_test
+            .credential_uuid
+            .as_deref()
+            .is_none_or(|uuid| uuid.trim().is_empty())
+    {
+        diagnostics.push(ConfigDiagnostic::error(
+            "shadow_test.credential_uuid",
+            MSG_SHADOW_CREDENTIAL,
+        ));
+    }
+    if config
+        .telemetry
+        .otlp_endpoint
+        .as_deref()
+        .is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+    {
+        diagnostics.push(ConfigDiagnostic::error(
+            "telemetry.otlp_endpoint",
+            MSG_OTLP_ENDPOINT,
+        ));
+    }
+    if config.dead_letter.max_entries == 0 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "dead_letter.max_entries",
+            MSG_DEAD_LETTER_MAX_ENTRIES,
+        ));
+    }
+    if config.database_maintenance.hour > 23 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "database_maintenance.hour",
+            MSG_DB_MAINTENANCE_HOUR,
+        ));
+    }
+    if config.stream_resume.max_buffered_events == 0 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "stream_resume.max_buffered_events",
+            MSG_STREAM_RESUME_MAX_EVENTS,
+        ));
+    }
+    let limits = &config.limits;
+    if limits.max_tools == Some(0) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "limits.max_tools",
+            MSG_LIMITS_MAX_TOOLS,
+        ));
+    }
+    if limits.max_tool_schema_bytes == Some(0) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "limits.max_tool_schema_bytes",
+            MSG_LIMITS_MAX_TOOL_SCHEMA_BYTES,
+        ));
+    }
+    if limits.max_tool_description_bytes == 0 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "limits.max_tool_description_bytes",
+            MSG_LIMITS_MAX_TOOL_DESCRIPTION_BYTES,
+        ));
+    }
+    if config.stream.coalesce_ms > 1000 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "stream.coalesce_ms",
+            MSG_STREAM_COALESCE_MS,
+        ));
+    }
+    let oauth_callback = &config.oauth_callback;
+    if oauth_callback.port_range_start == 0
+        || oauth_callback.port_range_start > oauth_callback.port_range_end
+    {
+        diagnostics.push(ConfigDiagnostic::error(
+            "oauth_callback.port_range_start",
+            MSG_OAUTH_CALLBACK_PORT_RANGE,
+        ));
+    }
+    let truncation = &config.tool_result_truncation;
+    if truncation.max_bytes == 0 {
+        diagnostics.push(ConfigDiagnostic::error(
+            "tool_result_truncation.max_bytes",
+            MSG_TOOL_RESULT_BUDGET,
+        ));
+    }
+    if truncation.max_tokens == Some(0) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "tool_result_truncation.max_tokens",
+            MSG_TOOL_RESULT_BUDGET,
+        ));
+    }
+    for (provider, budget) in &truncation.providers {
+        for (field, is_zero) in [
+            ("max_bytes", budget.max_bytes == Some(0)),
+            ("max_tokens", budget.max_tokens == Some(0)),
+        ] {
+            if is_zero {
+                diagnostics.push(ConfigDiagnostic::error(
+                    &format!("tool_result_truncation.providers.{}.{}", provider, field),
+                    MSG_TOOL_RESULT_BUDGET,
+                ));
+            }
+        }
+    }
+    if !(1..=MAX_TERMINAL_SCROLLBACK_BYTES).contains(&config.terminal.scrollback_bytes) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "terminal.scrollback_bytes",
+            MSG_TERMINAL_SCROLLBACK_BYTES,
+        ));
+    }
+    for (provider, models) in &config.models.providers {
+        for (i, model) in models.models.iter().enumerate() {
+            for (field, value) in [
+                ("default_max_tokens", model.default_max_tokens),
+                ("max_output_tokens", model.max_output_tokens),
+            ] {
+                if value == Some(0) {
+                    diagnostics.push(ConfigDiagnostic::error(
+                        &format!("models.providers.{}.models.{}.{}", provider, i, field),
+                        MSG_MODEL_MAX_TOKENS,
+                    ));
+                }
+            }
+        }
+    }
+    for (model, price) in &config.price_table {
+        if !price.is_valid() {
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("price_table.{}", model),
+                MSG_PRICE_TABLE_PRICE,
+            ));
+        }
+    }
+    let split = &config.routing.default_provider_split;
+    if !split.is_empty() && split.iter().all(|w| w.weight == 0) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "routing.default_provider_split",
+            MSG_DEFAULT_PROVIDER_SPLIT,
+        ));
+    }
+    for (i, w) in split.iter().enumerate() {
+        if w.provider.trim().parse::<crate::ProviderType>().is_err() {
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("routing.default_provider_split.{}.provider", i),
+                MSG_UNKNOWN_PROVIDER_TYPE,
+            ));
+        }
+    }
+    for (provider, chain) in &config.routing.credential_fallback {
+        if provider.parse::<crate::ProviderType>().is_err() {
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("routing.credential_fallback.{}", provider),
+                MSG_UNKNOWN_PROVIDER_TYPE,
+            ));
+        }
+        for (i, target) in chain.iter().enumerate() {
+            let message = match target.parse::<crate::ProviderType>() {
+                Ok(pt) if pt.is_oauth() => MSG_FALLBACK_NOT_API_KEY,
+                Ok(_) => continue,
+                Err(_) => MSG_UNKNOWN_PROVIDER_TYPE,
+            };
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("routing.credential_fallback.{}.{}", provider, i),
+                message,
+            ));
+        }
+    }
+    for (model, chain) in &config.routing.model_fallbacks {
+        for (i, target) in chain.iter().enumerate() {
+            let target = target.trim();
+            if target.is_empty() || target == model.trim() {
+                diagnostics.push(ConfigDiagnostic::error(
+                    &format!("routing.model_fallbacks.{}.{}", model, i),
+                    MSG_MODEL_FALLBACK_TARGET,
+                ));
+            }
+        }
+    }
+    let invalid_proxy = |url: &str| {
+        let url = url.trim();
+        !url.is_empty() && !crate::http_client::is_valid_proxy_url(url)
+    };
+    if config.proxy_url.as_deref().is_some_and(invalid_proxy) {
+        diagnostics.push(ConfigDiagnostic::error("proxy_url", MSG_PROXY_URL));
+    }
+    if config
+        .outbound_proxy
+        .url
+        .as_deref()
+        .is_some_and(invalid_proxy)
+    {
+        diagnostics.push(ConfigDiagnostic::error("outbound_proxy.url", MSG_PROXY_URL));
+    }
+    for (provider, url) in &config.outbound_proxy.overrides {
+        if !url.trim().eq_ignore_ascii_case(crate::http_client::DIRECT) && invalid_proxy(url) {
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("outbound_proxy.overrides.{}", provider),
+                MSG_PROXY_URL,
+            ));
+        }
+    }
+    let upstream = &config.upstream_headers;
+    if crate::http_client::normalize_headers(&upstream.headers).is_err() {
+        diagnostics.push(ConfigDiagnostic::error(
+            "upstream_headers.headers",
+            MSG_UPSTREAM_HEADER,
+        ));
+    }
+    for (provider, headers) in &upstream.providers {
+        if crate::http_client::normalize_headers(headers).is_err() {
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("upstream_headers.providers.{}", provider),
+                MSG_UPSTREAM_HEADER,
+            ));
+        }
+    }
+    if upstream
+        .anthropic_version
+        .as_deref()
+        .is_some_and(|v| reqwest::header::HeaderValue::from_str(v.trim()).is_err())
+    {
+        diagnostics.push(ConfigDiagnostic::error(
+            "upstream_headers.anthropic_version",
+            MSG_ANTHROPIC_VERSION,
+        ));
+    }
+    if !upstream
+        .anthropic_betas
+        .iter()
+        .all(|beta| crate::http_client::is_valid_anthropic_beta(beta))
+    {
+        diagnostics.push(ConfigDiagnostic::error(
+            "upstream_headers.anthropic_betas",
+            MSG_ANTHROPIC_BETA,
+        ));
+    }
+
+    diagnostics
+}
+
+/// 解析并校验 YAML 配置，诊断尽量附带行号
+pub fn validate_yaml(yaml: &str) -> ConfigValidationReport {
+    let config: Config = match serde_yaml::from_str(yaml) {
+        Ok(config) => config,
+        Err(e) => {
+            return ConfigValidationReport {
+                valid: false,
+                config: None,
+                diagnostics: vec![parse_error_diagnostic(&e)],
+            }
+        }
+    };
+
+    let diagnostics: Vec<ConfigDiagnostic> = validate_config(&config)
+        .into_iter()
+        .map(|mut d| {
+            d.line = locate_line(yaml, &d.path);
+            d
+        })
+        .collect();
+
+    ConfigValidationReport {
+        valid: !diagnostics
+            .iter()
+            .any(|d| d.severity == DiagnosticSeverity::Error),
+        config: Some(config),
+        diagnostics,
+    }
+}
+
+/// 将 YAML 解析错误转换为诊断
+///
+/// serde_yaml 的错误信息格式为 `<path>: <message> at line X column Y`，
+/// 这里拆出字段路径和位置。
+fn parse_error_diagnostic(error: &serde_yaml::Error) -> ConfigDiagnostic {
+    let mut message = error.to_string();
+    let location = error.location();
+    if location.is_some() {
+        if let Some(pos) = message.rfind(" at line ") {
+            message.truncate(pos);
+        }
+    }
+
+    let (path, message) = match message.split_once(": ") {
+        Some((path, rest)) if !path.is_empty() && !path.contains(' ') => {
+            (path.to_string(), rest.to_string())
+        }
+        _ => (String::new(), message),
+    };
+
+    ConfigDiagnostic {
+        path,
+        message: format!("配置解析失败: {}", message),
+        severity: DiagnosticSeverity::Error,
+        line: location.as_ref().map(|l| l.line()),
+        column: location.as_ref().map(|l| l.column()),
+    }
+}
+
+/// 按缩进在 YAML 文本中查找字段所在行（从 1 开始）
+fn locate_line(yaml: &str, path: &str) -> Option<usize> {
+    if path.is_empty() {
+        return None;
+    }
+    let lines: Vec<&str> = yaml.lines().collect();
+    let mut parent_indent: Option<usize> = None;
+    let mut start = 0;
+
+    for segment in path.split('.') {
+        let mut found = None;
+        for (i, line) in lines.iter().enumerate().skip(start) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+            match parent_indent {
+                // 回到父级缩进说明已离开父节点
+                Some(parent) if indent <= parent => break,
+                None if indent != 0 => continue,
+                _ => {}
+            }
+            if trimmed
+                .strip_prefix(segment)
+                .is_some_and(|rest| rest.starts_with(':'))
+            {
+                found = Some((i, indent));
+                break;
+            }
+        }
+        let (i, indent) = found?;
+        parent_indent = Some(indent);
+        start = i + 1;
+    }
+
+    // start 指向最后一个字段的下一行
+    Some(start)
+}
+
+/// 导出 `Config` 的 JSON Schema
+///
+/// 字段结构和类型从默认配置推导，数值范围和安全规则以 Schema 约束表达，
+/// `errorMessage` 与后端诊断文案一致（兼容 ajv-errors）。
+pub fn config_schema() -> Value {
+    let defaults = serde_json::to_value(Config::default()).unwrap_or(Value::Null);
+    let mut schema = infer_schema(&defaults);
+    if let Some(obj) = schema.as_object_mut() {
+        obj.insert(
+            "$schema".to_string(),
+            json!("http://json-schema.org/draft-07/schema#"),
+        );
+        obj.insert("title".to_string(), json!("ProxyCast Config"));
+    }
+
+    constrain(
+        &mut schema,
+        "server.port",
+        json!({ "minimum": 1, "maximum": 65535, "errorMessage": MSG_PORT_ZERO }),
+    );
+    constrain(
+        &mut schema,
+        "server.host",
+        json!({ "pattern": BIND_HOST_PATTERN, "errorMessage": MSG_INVALID_HOST }),
+    );
+    constrain(
+        &mut schema,
+        "server.api_key",
+        json!({ "minLength": 1, "pattern": r"\S", "errorMessage": MSG_API_KEY_EMPTY }),
+    );
+    constrain(
+        &mut schema,
+        "server.tls.enable",
+        json!({ "const": false, "errorMessage": MSG_TLS_UNSUPPORTED }),
+    );
+    constrain(
+        &mut schema,
+        "retry.max_retries",
+        json!({ "maximum": 100, "errorMessage": MSG_MAX_RETRIES }),
+    );
+    constrain(
+        &mut schema,
+        "retry.base_delay_ms",
+        json!({ "minimum": 1, "errorMessage": MSG_BASE_DELAY_ZERO }),
+    );
+    constrain(
+        &mut schema,
+        "logging.retention_days",
+        json!({ "minimum": 1, "errorMessage": MSG_RETENTION_ZERO }),
+    );
+    constrain(
+        &mut schema,
+        "shadow_test.sample_rate",
+        json!({ "minimum": 0, "maximum": 1, "errorMessage": MSG_SHADOW_SAMPLE_RATE }),
+    );
+    constrain(
+        &mut schema,
+        "routing.latency_exploration_rate",
+        json!({ "minimum": 0, "maximum": 1, "errorMessage": MSG_LATENCY_EXPLORATION_RATE }),
+    );
+    constrain(
+        &mut schema,
+        "dead_letter.max_entries",
+        json!({ "minimum": 1, "errorMessage": MSG_DEAD_LETTER_MAX_ENTRIES }),
+    );
+    constrain(
+        &mut schema,
+        "database_maintenance.hour",
+        json!({ "maximum": 23, "errorMessage": MSG_DB_MAINTENANCE_HOUR }),
+    );
+    constrain(
+        &mut schema,
+        "stream_resume.max_buffered_events",
+        json!({ "minimum": 1, "errorMessage": MSG_STREAM_RESUME_MAX_EVENTS }),
+    );
+    constrain(
+        &mut schema,
+        "limits.max_tools",
+        json!({ "minimum": 1, "errorMessage": MSG_LIMITS_MAX_TOOLS }),
+    );
+    constrain(
+        &mut schema,
+        "limits.max_tool_schema_bytes",
+        json!({ "minimum": 1, "errorMessage": MSG_LIMITS_MAX_TOOL_SCHEMA_BYTES }),
+    );
+    constrain(
+        &mut schema,
+        "limits.max_tool_description_bytes",
+        json!({ "minimum": 1, "errorMessage": MSG_LIMITS_MAX_TOOL_DESCRIPTION_BYTES }),
+    );
+    constrain(
+        &mut schema,
+        "stream.coalesce_ms",
+        json!({ "maximum": 1000, "errorMessage": MSG_STREAM_COALESCE_MS }),
+    );
+    constrain(
+        &mut schema,
+        "tool_result_truncation.max_bytes",
+        json!({ "minimum": 1, "errorMessage": MSG_TOOL_RESULT_BUDGET }),
+    );
+    constrain(
+        &mut schema,
+        "terminal.scrollback_bytes",
+        json!({
+            "minimum": 1,
+            "maximum": MAX_TERMINAL_SCROLLBACK_BYTES,
+            "errorMessage": MSG_TERMINAL_SCROLLBACK_BYTES
+        }),
+    );
+
+    // 跨字段的安全规则
+    if let Some(obj) = schema.as_object_mut() {
+        obj.insert(
+            "allOf".to_string(),
+            json!([
+                {
+                    "if": {
+                        "properties": { "server": { "properties": {
+                            "host": { "enum": ["0.0.0.0", "::"] }
+                        } } }
+                    },
+                    "then": {
+                        "properties": { "server": { "properties": {
+                            "api_key": {
+                                "not": { "const": DEFAULT_API_KEY },
+                                "errorMessage": MSG_DEFAULT_KEY_NON_LOCAL
+                            }
+                        } } }
+                    }
+                },
+                {
+                    "if": {
+                        "properties": { "remote_management": { "properties": {
+                            "allow_remote": { "const": true }
+                        } } },
+                        "required": ["remote_management"]
+                    },
+                    "then": {
+                        "properties": { "server": {
+                            "properties": { "tls": {
+                                "properties": { "enable": { "const": true } },
+                                "required": ["enable"]
+                            } },
+                            "required": ["tls"]
+                        } },
+                        "required": ["server"],
+                        "errorMessage": MSG_REMOTE_REQUIRES_TLS
+                    }
+                }
+            ]),
+        );
+    }
+
+    schema
+}
+
+/// 根据默认值推导 Schema（Option 为 None 的字段不约束类型）
+fn infer_schema(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let properties: serde_json::Map<String, Value> = map
+                .iter()
+                .map(|(k, v)| (k.clone(), infer_schema(v)))
+                .collect();
+            json!({ "type": "object", "properties": properties })
+        }
+        Value::Array(items) => match items.first() {
+            Some(first) => json!({ "type": "array", "items": infer_schema(first) }),
+            None => json!({ "type": "array" }),
+        },
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(n) if n.is_u64() => json!({ "type": "integer", "minimum": 0 }),
+        Value::Number(n) if n.is_i64() => json!({ "type": "integer" }),
+        Value::Number(_) => json!({ "type": "number" }),
+        Value::Null => json!({}),
+    }
+}
+
+/// 向指定字段的 Schema 合并约束
+fn constrain(schema: &mut Value, path: &str, constraints: Value) {
+    let mut node = schema;
+    for segment in path.split('.') {
+        node = match node
+            .get_mut("properties")
+            .and_then(|props| props.get_mut(segment))
+        {
+            Some(next) => next,
+            None => return,
+        };
+    }
+    if let (Some(target), Value::Object(extra)) = (node.as_object_mut(), constraints) {
+        target.extend(extra);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderWeight;
+
+    #[test]
+    fn test_security_rules_reported_with_path_and_line() {
+        let yaml = "server:\n  host: 0.0.0.0\n  port: 8999\n  api_key: proxy_cast\nremote_management:\n  allow_remote: true\n";
+        let report = validate_yaml(yaml);
+
+        assert!(!report.valid);
+        let api_key = report
+            .diagnostics
+            .iter()
+            .find(|d| d.path == "server.api_key")
+            .unwrap();
+        assert_eq!(api_key.message, MSG_DEFAULT_KEY_NON_LOCAL);
+        assert_eq!(api_key.line, Some(4));
+
+        let remote = report
+            .diagnostics
+            .iter()
+            .find(|d| d.path == "remote_management.allow_remote")
+            .unwrap();
+        assert_eq!(remote.severity, DiagnosticSeverity::Error);
+        assert_eq!(remote.line, Some(6));
+    }
+
+    #[test]
+    fn test_credential_fallback_targets_must_be_api_key_types() {
+        let mut config = Config::default();
+        config.routing.credential_fallback.insert(
+            "claude_oauth".to_string(),
+            vec![
+                "anthropic".to_string(),
+                "kiro".to_string(),
+                "nope".to_string(),
+            ],
+        );
+        let paths: Vec<_> = validate_config(&config)
+            .into_iter()
+            .filter(|d| d.path.starts_with("routing.credential_fallback"))
+            .map(|d| (d.path, d.message))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                (
+                    "routing.credential_fallback.claude_oauth.1".to_string(),
+                    MSG_FALLBACK_NOT_API_KEY.to_string()
+                ),
+                (
+                    "routing.credential_fallback.claude_oauth.2".to_string(),
+                    MSG_UNKNOWN_PROVIDER_TYPE.to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_provider_split_targets_must_be_known() {
+        let mut config = Config::default();
+        config.routing.default_provider_split = vec![
+            ProviderWeight {
+                provider: "kiro".to_string(),
+                weight: 70,
+            },
+            ProviderWeight {
+                provider: "kiro-typo".to_string(),
+                weight: 30,
+            },
+        ];
+        let paths: Vec<_> = validate_config(&config)
+            .into_iter()
+            .filter(|d| d.path.starts_with("routing.default_provider_split"))
+            .map(|d| (d.path, d.message))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![(
+                "routing.default_provider_split.1.provider".to_string(),
+                MSG_UNKNOWN_PROVIDER_TYPE.to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_invalid_proxy_urls_are_errors() {
+        let mut config = Config::default();
+        config.proxy_url = Some("http://[::1".to_string());
+        config.outbound_proxy.url = Some("socks5://127.0.0.1:1080".to_string());
+        config
+            .outbound_proxy
+            .overrides
+            .insert("kiro".to_string(), "DIRECT".to_string());
+        config
+            .outbound_proxy
+            .overrides
+            .insert("qwen".to_string(), "http://proxy:bad-port".to_string());
+
+        let mut paths: Vec<_> = validate_config(&config)
+            .into_iter()
+            .filter(|d| d.message == MSG_PROXY_URL)
+            .map(|d| d.path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["outbound_proxy.overrides.qwen", "proxy_url"]);
+    }
+
+    #[test]
+    fn test_non_local_bind_with_strong_key_is_warning() {
+        let yaml = "server:\n  host: 0.0.0.0\n  api_key: pc_strong_key\n";
+        let report = validate_yaml(yaml);
+
+        assert!(report.valid);
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].severity, DiagnosticSeverity::Warning);
+        assert_eq!(report.diagnostics[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_parse_error_has_field_path_and_location() {
+        let report = validate_yaml("server:\n  port: not-a-number\n");
+
+        assert!(!report.valid);
+        assert!(report.config.is_none());
+        let diagnostic = &report.diagnostics[0];
+        assert_eq!(diagnostic.path, "server.port");
+        assert_eq!(diagnostic.line, Some(2));
+    }
+
+    #[test]
+    fn test_schema_contains_security_constraints() {
+        let schema = config_schema();
+        let server = &schema["properties"]["server"]["properties"];
+
+        assert_eq!(server["port"]["type"], "integer");
+        assert_eq!(server["tls"]["properties"]["enable"]["const"], false);
+        assert_eq!(server["host"]["errorMessage"], MSG_INVALID_HOST);
+        let rules = schema["allOf"].as_array().unwrap();
+        assert!(rules
+            .iter()
+            .any(|r| r["then"]["errorMessage"] == MSG_REMOTE_REQUIRES_TLS));
+    }
+}
@@ -106,12 +106,8 @@ impl ConfigManager {
             std::fs::create_dir_all(parent).map_err(|e| ConfigError::WriteError(e.to_string()))?;
         }
 
-        if path.exists() {
-            let backup_path = path.with_extension("yaml.backup");
-            let _ = std::fs::copy(path, backup_path);
-        }
         let yaml = Self::to_yaml(&self.config)?;
-        std::fs::write(path, yaml).map_err(|e| ConfigError::WriteError(e.to_string()))
+        write_atomic(path, &yaml).map_err(|e| ConfigError::WriteError(e.to_string()))
     }
 
     /// 重新加载配置
@@ -297,7 +293,7 @@ impl YamlService {
         }
 
         // 写入文件
-        std::fs::write(path, final_content).map_err(|e| ConfigError::WriteError(e.to_string()))
+        write_atomic(path, &final_content).map_err(|e| ConfigError::WriteError(e.to_string()))
     }
 
     /// 合并原文件的注释到新 YAML 内容中
@@ -635,7 +631,7 @@ impl YamlService {
         }
 
         // 写入文件
-        std::fs::write(path, result_lines.join("\n"))
+        write_atomic(path, &result_lines.join("\n"))
             .map_err(|e| ConfigError::WriteError(e.to_string()))
     }
 }
@@ -662,8 +658,7 @@ pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
 
     // 优先尝试 YAML 配置
     if yaml_path.exists() {
-        let content = std::fs::read_to_string(&yaml_path)?;
-        let mut config: Config = serde_yaml::from_str(&content)?;
+        let mut config = load_yaml_with_recovery(&yaml_path)?;
         // 如果配置中使用默认 API Key，生成强随机 Key 并保存
         if is_default_api_key(&config.server.api_key) {
             let new_key = generate_secure_api_key();
@@ -717,29 +712,149 @@ pub fn save_config(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
         std::fs::create_dir_all(parent)?;
     }
     let content = serde_json::to_string_pretty(config)?;
-    std::fs::write(&path, content)?;
+    write_atomic(&path, &content)?;
     Ok(())
 }
 
 /// 保存配置为 YAML 格式
 pub fn save_config_yaml(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     let path = ConfigManager::default_config_path();
+    let content = serde_yaml::to_string(config)?;
+    write_atomic(&path, &content)?;
+    Ok(())
+}
+
+/// 配置文件的备份路径（`config.yaml` → `config.yaml.bak`）
+pub fn backup_path(path: &Path) -> PathBuf {
+    append_extension(path, "bak")
+}
+
+fn append_extension(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(ext);
+    path.with_file_name(name)
+}
+
+/// 原子写入配置文件
+///
+/// 先写入同目录下的临时文件并 fsync，再重命名覆盖原文件，
+/// 写入过程中崩溃不会留下半个配置文件。原文件会先复制为 `.bak`。
+pub fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    if path.exists() {
-        let backup_path = path.with_extension("yaml.backup");
-        let _ = std::fs::copy(&path, &backup_path);
+
+    let tmp_path = append_extension(path, "tmp");
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+
+        if path.exists() {
+            std::fs::copy(path, backup_path(path))?;
+        }
+        std::fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return result;
+    }
+
+    // 同步目录项，确保重命名持久化（仅 Unix 支持打开目录）
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = std::fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
     }
-    let content = serde_yaml::to_string(config)?;
-    std::fs::write(&path, content)?;
     Ok(())
 }
 
+/// 加载 YAML 配置，主配置解析失败时尝试从 `.bak` 恢复
+///
+/// 恢复成功后损坏的主配置另存为 `.corrupt` 供排查，并用备份内容重写主配置。
+fn load_yaml_with_recovery(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)?;
+    let parse_error = match serde_yaml::from_str::<Config>(&content) {
+        Ok(config) => return Ok(config),
+        Err(e) => e,
+    };
+
+    let bak = backup_path(path);
+    let recovered = std::fs::read_to_string(&bak).ok().and_then(|backup| {
+        serde_yaml::from_str::<Config>(&backup)
+            .ok()
+            .map(|c| (backup, c))
+    });
+    let Some((backup_content, config)) = recovered else {
+        return Err(Box::new(parse_error));
+    };
+
+    tracing::warn!(
+        "[CONFIG] 主配置解析失败（{}），已从备份 {:?} 恢复",
+        parse_error,
+        bak
+    );
+    // 先移走损坏的主配置，避免重写时覆盖掉唯一可用的备份
+    let corrupt_path = append_extension(path, "corrupt");
+    if let Err(e) = std::fs::rename(path, &corrupt_path) {
+        tracing::error!("[CONFIG] 保留损坏的配置文件失败: {}", e);
+    }
+    if let Err(e) = write_atomic(path, &backup_content) {
+        tracing::error!("[CONFIG] 恢复后重写主配置失败: {}", e);
+    }
+    Ok(config)
+}
+
 #[cfg(test)]
 mod unit_tests {
     use super::*;
 
+    #[test]
+    fn test_write_atomic_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        write_atomic(&path, "first").unwrap();
+        assert!(!backup_path(&path).exists());
+
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(
+            std::fs::read_to_string(backup_path(&path)).unwrap(),
+            "first"
+        );
+        assert!(!append_extension(&path, "tmp").exists());
+    }
+
+    #[test]
+    fn test_load_recovers_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(backup_path(&path), "server:\n  port: 9100\n").unwrap();
+        std::fs::write(&path, "server:\n  port: [broken").unwrap();
+
+        let config = load_yaml_with_recovery(&path).unwrap();
+        assert_eq!(config.server.port, 9100);
+        assert!(append_extension(&path, "corrupt").exists());
+        // 主配置已用备份重写，备份保持不变
+        assert!(ConfigManager::parse_yaml(&std::fs::read_to_string(&path).unwrap()).is_ok());
+        assert!(backup_path(&path).exists());
+    }
+
+    #[test]
+    fn test_load_without_backup_returns_parse_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(&path, "server:\n  port: [broken").unwrap();
+
+        assert!(load_yaml_with_recovery(&path).is_err());
+    }
+
     #[test]
     fn test_parse_yaml_minimal() {
         let yaml = r#"
@@ -48,11 +48,41 @@ pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCo
         top_p: None,
         stream: request.stream,
         tools,
-        tool_choice: request.tool_choice.clone(),
+        tool_choice: request.tool_choice.as_ref().and_then(convert_tool_choice),
         reasoning_effort: None,
+        seed: None,
+        logprobs: None,
+        top_logprobs: None,
+        logit_bias: None,
+        frequency_penalty: None,
+        presence_penalty: None,
+        user: request.user_id().map(str::to_string),
+        stop: request
+            .stop_sequences
+            .clone()
+            .filter(|seqs| !seqs.is_empty())
+            .map(StopSequences::Multiple),
+        parallel_tool_calls: request.parallel_tool_use_disabled().then_some(false),
     }
 }
 
+/// Anthropic tool_choice -> OpenAI tool_choice
+///
+/// 无法识别的值原样透传。
+fn convert_tool_choice(choice: &serde_json::Value) -> Option<serde_json::Value> {
+    let converted = match choice.get("type").and_then(|t| t.as_str()) {
+        Some("auto") => serde_json::json!("auto"),
+        Some("none") => serde_json::json!("none"),
+        Some("any") => serde_json::json!("required"),
+        Some("tool") => {
+            let name = choice.get("name")?.as_str()?;
+            serde_json::json!({"type": "function", "function": {"name": name}})
+        }
+        _ => choice.clone(),
+    };
+    Some(converted)
+}
+
 fn extract_system_text(system: &serde_json::Value) -> String {
     match system {
         serde_json::Value::String(s) => s.clone(),
@@ -85,28 +115,39 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                 tool_call_id: None,
             });
         }
-        serde_json::Value::Array(parts) => {
-            let mut text_parts: Vec<String> = Vec::new();
+        serde_json::Value::Array(blocks) => {
+            // 按原始顺序保留 text / image 内容块
+            let mut content_parts: Vec<ContentPart> = Vec::new();
             let mut tool_calls: Vec<ToolCall> = Vec::new();
-            let mut tool_results: Vec<(String, String)> = Vec::new(); // (tool_use_id, content)
+            // (tool_use_id, content)
+            let mut tool_results: Vec<(String, String)> = Vec::new();
+            // tool_result 中的图片（OpenAI tool 消息不支持图片，追加到随后的 user 消息）
+            let mut tool_result_images: Vec<ContentPart> = Vec::new();
 
-            for part in parts {
-                let part_type = part.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            for block in blocks {
+                let block_type = block.get("type").and_then(|t| t.as_str()).unwrap_or("");
 
-                match part_type {
+                match block_type {
                     "text" => {
-                        if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
-                            text_parts.push(text.to_string());
+                        if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                            content_parts.push(ContentPart::Text {
+                                text: text.to_string(),
+                            });
+                        }
+                    }
+                    "image" => {
+                        if let Some(part) = convert_image_block(block) {
+                            content_parts.push(part);
                         }
                     }
                     "tool_use" => {
                         let default_id = format!("call_{}", &Uuid::new_v4().to_string()[..8]);
-                        let id = part
+                        let id = block
                             .get("id")
                             .and_then(|i| i.as_str())
                             .unwrap_or(&default_id);
-                        let name = part.get("name").and_then(|n| n.as_str()).unwrap_or("");
-                        let input = part.get("input").cloned().unwrap_or(serde_json::json!({}));
+                        let name = block.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                        let input = block.get("input").cloned().unwrap_or(serde_json::json!({}));
 
                         tool_calls.push(ToolCall {
                             id: id.to_string(),
@@ -118,12 +159,13 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                         });
                     }
                     "tool_result" => {
-                        let tool_use_id = part
+                        let tool_use_id = block
                             .get("tool_use_id")
                             .and_then(|i| i.as_str())
                             .unwrap_or("");
-                        let content = extract_tool_result_content(part.get("content"));
+                        let content = extract_tool_result_content(block.get("content"));
                         tool_results.push((tool_use_id.to_string(), content));
+                        tool_result_images.extend(extract_tool_result_images(block.get("content")));
                     }
                     _ => {}
                 }
@@ -131,11 +173,6 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
 
             // 处理 assistant 消息
             if msg.role == "assistant" {
-                let content = if text_parts.is_empty() {
-                    None
-                } else {
-                    Some(MessageContent::Text(text_parts.join("")))
-                };
                 let tc = if tool_calls.is_empty() {
                     None
                 } else {
@@ -144,14 +181,14 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
 
                 result.push(ChatMessage {
                     role: "assistant".to_string(),
-                    content,
+                    content: build_message_content(content_parts),
                     tool_calls: tc,
                     tool_call_id: None,
                 });
             }
             // 处理 user 消息
             else if msg.role == "user" {
-                // 先添加 tool results 作为 tool 角色消息
+                // 先添加 tool results 作为 tool 角色消息（须紧跟 assistant 的 tool_calls）
                 for (tool_use_id, content) in tool_results {
                     result.push(ChatMessage {
                         role: "tool".to_string(),
@@ -161,11 +198,12 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                     });
                 }
 
-                // 添加文本内容
-                if !text_parts.is_empty() {
+                // 添加 tool_result 图片和用户自身的文本/图片内容
+                tool_result_images.extend(content_parts);
+                if let Some(content) = build_message_content(tool_result_images) {
                     result.push(ChatMessage {
                         role: "user".to_string(),
-                        content: Some(MessageContent::Text(text_parts.join(""))),
+                        content: Some(content),
                         tool_calls: None,
                         tool_call_id: None,
                     });
@@ -178,6 +216,41 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
     result
 }
 
+/// 根据内容块构建消息内容
+///
+/// 只有单个文本块时保持字符串形式，多个块或包含图片时使用 content 数组，
+/// 避免多个文本块被直接拼接在一起。
+fn build_message_content(parts: Vec<ContentPart>) -> Option<MessageContent> {
+    match parts.as_slice() {
+        [] => None,
+        [ContentPart::Text { text }] => Some(MessageContent::Text(text.clone())),
+        _ => Some(MessageContent::Parts(parts)),
+    }
+}
+
+/// 将 Anthropic image 块转换为 OpenAI image_url 内容块
+///
+/// 支持 `base64`（转换为 data URL）和 `url` 两种 source 类型。
+fn convert_image_block(block: &serde_json::Value) -> Option<ContentPart> {
+    let source = block.get("source")?;
+    let url = match source.get("type").and_then(|t| t.as_str()) {
+        Some("base64") => {
+            let media_type = source
+                .get("media_type")
+                .and_then(|m| m.as_str())
+                .unwrap_or("image/jpeg");
+            let data = source.get("data").and_then(|d| d.as_str())?;
+            format!("data:{};base64,{}", media_type, data)
+        }
+        Some("url") => source.get("url").and_then(|u| u.as_str())?.to_string(),
+        _ => return None,
+    };
+
+    Some(ContentPart::ImageUrl {
+        image_url: ImageUrl { url, detail: None },
+    })
+}
+
 fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
     match content {
         Some(serde_json::Value::String(s)) => s.clone(),
@@ -197,3 +270,254 @@ fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
         _ => String::new(),
     }
 }
+
+fn extract_tool_result_images(content: Option<&serde_json::Value>) -> Vec<ContentPart> {
+    match content {
+        Some(serde_json::Value::Array(arr)) => arr
+            .iter()
+            .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("image"))
+            .filter_map(convert_image_block)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn convert_messages(messages: serde_json::Value) -> serde_json::Value {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": messages
+        }))
+        .unwrap();
+        serde_json::to_value(convert_anthropic_to_openai(&request).messages).unwrap()
+    }
+
+    #[test]
+    fn test_convert_messages_table() {
+        let cases = vec![
+            (
+                "纯文本字符串",
+                json!([{"role": "user", "content": "hello"}]),
+                json!([{"role": "user", "content": "hello"}]),
+            ),
+            (
+                "单个文本块保持字符串",
+                json!([{"role": "user", "content": [{"type": "text", "text": "hello"}]}]),
+                json!([{"role": "user", "content": "hello"}]),
+            ),
+            (
+                "多个文本块保留为 content 数组",
+                json!([{"role": "user", "content": [
+                    {"type": "text", "text": "first"},
+                    {"type": "text", "text": "second"}
+                ]}]),
+                json!([{"role": "user", "content": [
+                    {"type": "text", "text": "first"},
+                    {"type": "text", "text": "second"}
+                ]}]),
+            ),
+            (
+                "base64 图片转换为 data URL",
+                json!([{"role": "user", "content": [
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0"}},
+                    {"type": "text", "text": "describe"}
+                ]}]),
+                json!([{"role": "user", "content": [
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0"}},
+                    {"type": "text", "text": "describe"}
+                ]}]),
+            ),
+            (
+                "url 图片保持原地址",
+                json!([{"role": "user", "content": [
+                    {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}}
+                ]}]),
+                json!([{"role": "user", "content": [
+                    {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}
+                ]}]),
+            ),
+            (
+                "多轮工具调用",
+                json!([
+                    {"role": "user", "content": "weather?"},
+                    {"role": "assistant", "content": [
+                        {"type": "text", "text": "Checking."},
+                        {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}},
+                        {"type": "tool_use", "id": "toolu_2", "name": "get_time", "input": {}}
+                    ]},
+                    {"role": "user", "content": [
+                        {"type": "tool_result", "tool_use_id": "toolu_1", "content": "sunny"},
+                        {"type": "tool_result", "tool_use_id": "toolu_2", "content": [{"type": "text", "text": "12:00"}]}
+                    ]},
+                    {"role": "assistant", "content": "It is sunny at noon."}
+                ]),
+                json!([
+                    {"role": "user", "content": "weather?"},
+                    {"role": "assistant", "content": "Checking.", "tool_calls": [
+                        {"id": "toolu_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}},
+                        {"id": "toolu_2", "type": "function", "function": {"name": "get_time", "arguments": "{}"}}
+                    ]},
+                    {"role": "tool", "content": "sunny", "tool_call_id": "toolu_1"},
+                    {"role": "tool", "content": "12:00", "tool_call_id": "toolu_2"},
+                    {"role": "assistant", "content": "It is sunny at noon."}
+                ]),
+            ),
+            (
+                "混合内容：tool_result + 文本 + 图片",
+                json!([{"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [
+                        {"type": "text", "text": "screenshot taken"},
+                        {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "/9j/"}}
+                    ]},
+                    {"type": "text", "text": "what do you see?"}
+                ]}]),
+                json!([
+                    {"role": "tool", "content": "screenshot taken", "tool_call_id": "toolu_1"},
+                    {"role": "user", "content": [
+                        {"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,/9j/"}},
+                        {"type": "text", "text": "what do you see?"}
+                    ]}
+                ]),
+            ),
+            (
+                "仅含工具调用的 assistant 消息无 content",
+                json!([{"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "toolu_9", "name": "noop", "input": {}}
+                ]}]),
+                json!([{"role": "assistant", "tool_calls": [
+                    {"id": "toolu_9", "type": "function", "function": {"name": "noop", "arguments": "{}"}}
+                ]}]),
+            ),
+        ];
+
+        for (name, input, expected) in cases {
+            assert_eq!(convert_messages(input), expected, "case: {}", name);
+        }
+    }
+
+    #[test]
+    fn test_convert_image_edge_cases() {
+        let cases = vec![
+            (
+                "不支持的 source 类型被忽略",
+                json!([{"role": "user", "content": [
+                    {"type": "image", "source": {"type": "file", "file_id": "file_1"}},
+                    {"type": "text", "text": "hi"}
+                ]}]),
+                json!([{"role": "user", "content": "hi"}]),
+            ),
+            (
+                "缺少 media_type 时按 image/jpeg 处理",
+                json!([{"role": "user", "content": [
+                    {"type": "image", "source": {"type": "base64", "data": "AAAA"}}
+                ]}]),
+                json!([{"role": "user", "content": [
+                    {"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,AAAA"}}
+                ]}]),
+            ),
+            (
+                "缺少 data 的图片被忽略，空消息不输出",
+                json!([{"role": "user", "content": [
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png"}}
+                ]}]),
+                json!([]),
+            ),
+            (
+                "assistant 的多个文本块不拼接",
+                json!([{"role": "assistant", "content": [
+                    {"type": "text", "text": "first"},
+                    {"type": "text", "text": "second"}
+                ]}]),
+                json!([{"role": "assistant", "content": [
+                    {"type": "text", "text": "first"},
+                    {"type": "text", "text": "second"}
+                ]}]),
+            ),
+            (
+                "只有图片的 tool_result",
+                json!([{"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [
+                        {"type": "image", "source": {"type": "url", "url": "https://example.com/s.png"}}
+                    ]}
+                ]}]),
+                json!([
+                    {"role": "tool", "content": "", "tool_call_id": "toolu_1"},
+                    {"role": "user", "content": [
+                        {"type": "image_url", "image_url": {"url": "https://example.com/s.png"}}
+                    ]}
+                ]),
+            ),
+        ];
+
+        for (name, input, expected) in cases {
+            assert_eq!(convert_messages(input), expected, "case: {}", name);
+        }
+    }
+
+    #[test]
+    fn test_system_prompt_blocks_joined() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "system": [{"type": "text", "text": "a"}, {"type": "text", "text": "b"}],
+            "messages": [{"role": "user", "content": "hi"}]
+        }))
+        .unwrap();
+
+        let result = convert_anthropic_to_openai(&request);
+        assert_eq!(result.messages[0].role, "system");
+        assert_eq!(result.messages[0].get_content_text(), "a\nb");
+    }
+
+    #[test]
+    fn test_stop_sequences_mapped_to_stop() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "stop_sequences": ["\n\nHuman:", "END"],
+            "messages": [{"role": "user", "content": "hi"}]
+        }))
+        .unwrap();
+
+        let result = convert_anthropic_to_openai(&request);
+        assert_eq!(result.stop_sequences(), vec!["\n\nHuman:", "END"]);
+    }
+
+    #[test]
+    fn test_openai_style_functions_on_messages_endpoint() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": "hi"}],
+            "functions": [{"name": "search", "parameters": {"type": "object"}}],
+            "function_call": {"name": "search"}
+        }))
+        .unwrap();
+
+        let result = convert_anthropic_to_openai(&request);
+        let tools = serde_json::to_value(result.tools.unwrap()).unwrap();
+        assert_eq!(tools[0]["function"]["name"], "search");
+        assert_eq!(
+            result.tool_choice,
+            Some(json!({"type": "function", "function": {"name": "search"}}))
+        );
+    }
+
+    #[test]
+    fn test_disable_parallel_tool_use_mapped() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": "hi"}],
+            "tools": [{"name": "search", "input_schema": {"type": "object"}}],
+            "tool_choice": {"type": "any", "disable_parallel_tool_use": true}
+        }))
+        .unwrap();
+
+        let result = convert_anthropic_to_openai(&request);
+        assert_eq!(result.parallel_tool_calls, Some(false));
+        assert_eq!(result.tool_choice, Some(json!("required")));
+        let body = serde_json::to_value(&result).unwrap();
+        assert_eq!(body["parallel_tool_calls"], false);
+    }
+}
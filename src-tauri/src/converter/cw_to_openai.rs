@@ -30,10 +30,12 @@ pub fn convert_cw_event_to_openai_chunk(
                     delta: StreamDelta {
                         role: Some("assistant".to_string()),
                         content: Some(content.clone()),
+                        reasoning_content: None,
                         tool_calls: None,
                     },
                     finish_reason: None,
                 }],
+                system_fingerprint: None,
             });
         }
 
@@ -49,6 +51,7 @@ pub fn convert_cw_event_to_openai_chunk(
                     delta: StreamDelta {
                         role: Some("assistant".to_string()),
                         content: None,
+                        reasoning_content: None,
                         tool_calls: Some(vec![ToolCall {
                             id: tool_use.tool_use_id.clone(),
                             call_type: "function".to_string(),
@@ -61,6 +64,7 @@ pub fn convert_cw_event_to_openai_chunk(
                     },
                     finish_reason: None,
                 }],
+                system_fingerprint: None,
             });
         }
     }
@@ -101,6 +105,7 @@ pub fn create_openai_response(
                 } else {
                     Some(content.to_string())
                 },
+                reasoning_content: None,
                 tool_calls,
             },
             finish_reason: finish_reason.to_string(),
@@ -110,6 +115,7 @@ pub fn create_openai_response(
             completion_tokens,
             total_tokens: prompt_tokens + completion_tokens,
         },
+        system_fingerprint: None,
     }
 }
 
@@ -130,9 +136,11 @@ pub fn create_stream_end_chunk(model: &str, response_id: &str) -> ChatCompletion
             delta: StreamDelta {
                 role: None,
                 content: None,
+                reasoning_content: None,
                 tool_calls: None,
             },
             finish_reason: Some("stop".to_string()),
         }],
+        system_fingerprint: None,
     }
 }
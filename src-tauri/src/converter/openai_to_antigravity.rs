@@ -132,6 +132,15 @@ pub struct GeminiGenerationConfig {
     /// 响应模态（TEXT, IMAGE）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_modalities: Option<Vec<String>>,
+    /// 采样随机种子
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// 是否返回 token 对数概率
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_logprobs: Option<bool>,
+    /// 每个位置返回的候选 token 数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -530,10 +539,13 @@ pub fn convert_openai_to_antigravity_with_context(
         max_output_tokens: request.max_tokens.map(|t| t as i32),
         top_p: request.top_p,
         top_k: None,
-        stop_sequences: None,
+        stop_sequences: Some(request.stop_sequences()).filter(|seqs| !seqs.is_empty()),
         candidate_count: None,
         thinking_config: None,
         response_modalities: None,
+        seed: request.seed,
+        response_logprobs: request.logprobs,
+        logprobs: request.top_logprobs,
     };
 
     // 为图片生成模型设置 response_modalities
@@ -556,28 +568,17 @@ pub fn convert_openai_to_antigravity_with_context(
 
     // 处理 reasoning_effort（思维链配置）
     if supports_thinking {
-        if let Some(ref effort) = request.reasoning_effort {
-            let effort_lower = effort.to_lowercase();
-            if effort_lower != "none" {
-                if model_uses_thinking_levels(actual_model) {
-                    // Gemini 3 使用离散级别
-                    generation_config.thinking_config = Some(ThinkingConfig {
-                        include_thoughts: Some(true),
-                        thinking_budget: None,
-                    });
-                } else {
-                    // Gemini 2.5 使用数值预算
-                    let budget = match effort_lower.as_str() {
-                        "low" => 1024,
-                        "medium" => 8192,
-                        "high" => 24576,
-                        _ => 8192,
-                    };
-                    generation_config.thinking_config = Some(ThinkingConfig {
-                        include_thoughts: Some(true),
-                        thinking_budget: Some(budget),
-                    });
-                }
+        if request.reasoning_effort.is_some() {
+            if let Some(budget) = request.thinking_budget() {
+                generation_config.thinking_config = Some(ThinkingConfig {
+                    include_thoughts: Some(true),
+                    // Gemini 3 使用离散级别，Gemini 2.5 使用数值预算
+                    thinking_budget: if model_uses_thinking_levels(actual_model) {
+                        None
+                    } else {
+                        Some(budget as i32)
+                    },
+                });
             }
         } else if is_enable_thinking(&request.model) {
             // 默认启用思维链
@@ -588,6 +589,13 @@ pub fn convert_openai_to_antigravity_with_context(
         }
     }
 
+    // Claude 模型启用思维链时上游要求 temperature 为 1、top_p 不低于 0.95，且不支持 top_k
+    if generation_config.thinking_config.is_some() && is_claude_model(actual_model) {
+        generation_config.temperature = Some(1.0);
+        generation_config.top_p = generation_config.top_p.filter(|p| *p >= 0.95);
+        generation_config.top_k = None;
+    }
+
     // 转换工具定义
     // 注意：Antigravity API 统一使用 functionDeclarations 格式
     // Claude 和 Gemini 模型都使用相同的结构，但字段名可能不同
@@ -1323,6 +1331,73 @@ mod image_tests {
     }
 }
 
+#[cfg(test)]
+mod generation_config_tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_and_logprobs_forwarded_to_generation_config() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gemini-2.5-flash",
+            "messages": [{"role": "user", "content": "hi"}],
+            "seed": 42,
+            "logprobs": true,
+            "top_logprobs": 3
+        }))
+        .unwrap();
+
+        let result = convert_openai_to_antigravity(&request);
+        let config = &result["request"]["generationConfig"];
+        assert_eq!(config["seed"], 42);
+        assert_eq!(config["responseLogprobs"], true);
+        assert_eq!(config["logprobs"], 3);
+    }
+
+    #[test]
+    fn test_claude_thinking_forces_temperature_one() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-opus-4-5",
+            "messages": [{"role": "user", "content": "hi"}],
+            "reasoning_effort": "high",
+            "temperature": 0.2,
+            "top_p": 0.5
+        }))
+        .unwrap();
+        let result = convert_openai_to_antigravity(&request);
+        let config = &result["request"]["generationConfig"];
+        assert_eq!(config["thinkingConfig"]["thinkingBudget"], 24576);
+        assert_eq!(config["temperature"], 1.0);
+        assert!(config.get("topP").is_none());
+
+        // Gemini 模型保留原始采样参数
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gemini-2.5-flash",
+            "messages": [{"role": "user", "content": "hi"}],
+            "reasoning_effort": "low",
+            "temperature": 0.25
+        }))
+        .unwrap();
+        let result = convert_openai_to_antigravity(&request);
+        assert_eq!(result["request"]["generationConfig"]["temperature"], 0.25);
+    }
+
+    #[test]
+    fn test_stop_forwarded_as_stop_sequences() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gemini-2.5-flash",
+            "messages": [{"role": "user", "content": "hi"}],
+            "stop": "END"
+        }))
+        .unwrap();
+
+        let result = convert_openai_to_antigravity(&request);
+        assert_eq!(
+            result["request"]["generationConfig"]["stopSequences"],
+            serde_json::json!(["END"])
+        );
+    }
+}
+
 // ============================================================================
 // 图像生成 API 属性测试
 // ============================================================================
@@ -0,0 +1,190 @@
+//! 死信数据访问对象
+//!
+//! 记录 Provider 调用失败的请求，超出容量时删除最旧的记录。
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// 死信记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeadLetter {
+    /// 记录 ID
+    pub id: String,
+    /// 记录时间
+    pub created_at: DateTime<Utc>,
+    /// Provider 类型
+    pub provider: String,
+    /// 使用的凭证 UUID
+    pub credential_uuid: Option<String>,
+    /// 请求的模型
+    pub model: String,
+    /// 请求路径（如 `/v1/chat/completions`）
+    pub path: String,
+    /// 返回给客户端的状态码
+    pub status_code: u16,
+    /// 错误类型
+    pub error_type: String,
+    /// 错误信息
+    pub error_message: String,
+    /// 脱敏后的请求体（JSON）
+    pub request_body: String,
+    /// 请求体是否被截断（截断的记录不可重放）
+    pub truncated: bool,
+}
+
+const SELECT_COLUMNS: &str = "id, created_at, provider, credential_uuid, model, path, status_code,
+     error_type, error_message, request_body, truncated";
+
+fn from_row(row: &Row) -> Result<DeadLetter, rusqlite::Error> {
+    let created_at: String = row.get(1)?;
+    Ok(DeadLetter {
+        id: row.get(0)?,
+        created_at: DateTime::parse_from_rfc3339(&created_at)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+        provider: row.get(2)?,
+        credential_uuid: row.get(3)?,
+        model: row.get(4)?,
+        path: row.get(5)?,
+        status_code: row.get(6)?,
+        error_type: row.get(7)?,
+        error_message: row.get(8)?,
+        request_body: row.get(9)?,
+        truncated: row.get::<_, i32>(10)? != 0,
+    })
+}
+
+pub struct DeadLetterDao;
+
+impl DeadLetterDao {
+    /// 写入一条记录，并只保留最新的 `max_entries` 条
+    pub fn insert(
+        conn: &Connection,
+        entry: &DeadLetter,
+        max_entries: usize,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO dead_letters (id, created_at, provider, credential_uuid, model, path,
+                status_code, error_type, error_message, request_body, truncated)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                entry.id,
+                // 固定精度，保证按字符串排序即按时间排序
+                entry
+                    .created_at
+                    .to_rfc3339_opts(SecondsFormat::Micros, true),
+                entry.provider,
+                entry.credential_uuid,
+                entry.model,
+                entry.path,
+                entry.status_code,
+                entry.error_type,
+                entry.error_message,
+                entry.request_body,
+                entry.truncated as i32,
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM dead_letters WHERE id NOT IN (
+                SELECT id FROM dead_letters ORDER BY created_at DESC LIMIT ?1
+            )",
+            params![max_entries as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 按时间倒序列出记录，可按 Provider 过滤
+    pub fn list(
+        conn: &Connection,
+        provider: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<DeadLetter>, rusqlite::Error> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM dead_letters
+             WHERE ?1 IS NULL OR provider = ?1
+             ORDER BY created_at DESC LIMIT ?2"
+        ))?;
+        let entries = stmt.query_map(params![provider, limit as i64], from_row)?;
+        entries.collect()
+    }
+
+    /// 获取单条记录
+    pub fn get(conn: &Connection, id: &str) -> Result<Option<DeadLetter>, rusqlite::Error> {
+        conn.query_row(
+            &format!("SELECT {SELECT_COLUMNS} FROM dead_letters WHERE id = ?1"),
+            [id],
+            from_row,
+        )
+        .optional()
+    }
+
+    /// 删除单条记录
+    pub fn delete(conn: &Connection, id: &str) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute("DELETE FROM dead_letters WHERE id = ?1", [id])?;
+        Ok(affected > 0)
+    }
+
+    /// 清空所有记录，返回删除的条数
+    pub fn clear(conn: &Connection) -> Result<usize, rusqlite::Error> {
+        conn.execute("DELETE FROM dead_letters", [])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn create_test_entry(id: &str, provider: &str, seconds_ago: i64) -> DeadLetter {
+        DeadLetter {
+            id: id.to_string(),
+            created_at: Utc::now() - chrono::Duration::seconds(seconds_ago),
+            provider: provider.to_string(),
+            credential_uuid: Some("cred-1".to_string()),
+            model: "claude-sonnet-4".to_string(),
+            path: "/v1/messages".to_string(),
+            status_code: 502,
+            error_type: "upstream_error".to_string(),
+            error_message: "bad gateway".to_string(),
+            request_body: r#"{"model":"claude-sonnet-4"}"#.to_string(),
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn test_insert_trims_oldest_entries() {
+        let conn = create_test_connection();
+        for (i, seconds_ago) in [30, 20, 10].into_iter().enumerate() {
+            let entry = create_test_entry(&format!("dl-{i}"), "claude", seconds_ago);
+            DeadLetterDao::insert(&conn, &entry, 2).unwrap();
+        }
+
+        let entries = DeadLetterDao::list(&conn, None, 10).unwrap();
+        let ids: Vec<_> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["dl-2", "dl-1"]);
+        assert!(DeadLetterDao::get(&conn, "dl-0").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_list_filter_and_delete() {
+        let conn = create_test_connection();
+        DeadLetterDao::insert(&conn, &create_test_entry("a", "claude", 2), 10).unwrap();
+        DeadLetterDao::insert(&conn, &create_test_entry("b", "kiro", 1), 10).unwrap();
+
+        let kiro = DeadLetterDao::list(&conn, Some("kiro"), 10).unwrap();
+        assert_eq!(kiro.len(), 1);
+        assert_eq!(kiro[0].id, "b");
+        assert_eq!(kiro[0].status_code, 502);
+        assert_eq!(kiro[0].credential_uuid.as_deref(), Some("cred-1"));
+
+        assert!(DeadLetterDao::delete(&conn, "a").unwrap());
+        assert!(!DeadLetterDao::delete(&conn, "a").unwrap());
+        assert_eq!(DeadLetterDao::clear(&conn).unwrap(), 1);
+    }
+}
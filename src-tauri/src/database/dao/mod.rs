@@ -1,5 +1,6 @@
 pub mod agent;
 pub mod api_key_provider;
+pub mod dead_letter;
 pub mod installed_plugins;
 pub mod mcp;
 pub mod orchestrator;
@@ -8,3 +9,4 @@ pub mod prompts;
 pub mod provider_pool;
 pub mod providers;
 pub mod skills;
+pub mod switch_log;
@@ -8,9 +8,18 @@ use crate::models::provider_pool_model::{
 };
 use chrono::{DateTime, TimeZone, Utc};
 use rusqlite::{params, Connection};
+use std::collections::HashMap;
 
 pub struct ProviderPoolDao;
 
+/// 凭证级上游请求头序列化为 JSON（为空时存 NULL）
+fn upstream_headers_json(cred: &ProviderCredential) -> Option<String> {
+    if cred.upstream_headers.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&cred.upstream_headers).ok()
+}
+
 impl ProviderPoolDao {
     /// 获取所有凭证
     pub fn get_all(conn: &Connection) -> Result<Vec<ProviderCredential>, rusqlite::Error> {
@@ -18,7 +27,8 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+                    health_score, upstream_headers, parked_until, unhealthy_since
              FROM provider_pool_credentials
              ORDER BY provider_type, created_at ASC",
         )?;
@@ -41,7 +51,8 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+                    health_score, upstream_headers, parked_until, unhealthy_since
              FROM provider_pool_credentials
              WHERE provider_type = ?1
              ORDER BY created_at ASC",
@@ -67,7 +78,8 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+                    health_score, upstream_headers, parked_until, unhealthy_since
              FROM provider_pool_credentials
              WHERE uuid = ?1",
         )?;
@@ -89,7 +101,8 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+                    health_score, upstream_headers, parked_until, unhealthy_since
              FROM provider_pool_credentials
              WHERE name = ?1",
         )?;
@@ -131,8 +144,9 @@ impl ProviderPoolDao {
              (uuid, provider_type, credential_data, name, is_healthy, is_disabled,
               check_health, check_model_name, not_supported_models, usage_count, error_count,
               last_used, last_error_time, last_error_message, last_health_check_time,
-              last_health_check_model, created_at, updated_at, source, proxy_url)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
+              last_health_check_model, created_at, updated_at, source, proxy_url, tier,
+              health_score, upstream_headers, parked_until, unhealthy_since)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25)",
             params![
                 cred.uuid,
                 cred.provider_type.to_string(),
@@ -154,6 +168,11 @@ impl ProviderPoolDao {
                 cred.updated_at.timestamp(),
                 source_str,
                 cred.proxy_url,
+                cred.tier,
+                cred.health_score,
+                upstream_headers_json(cred),
+                cred.parked_until.map(|t| t.timestamp()),
+                cred.unhealthy_since.map(|t| t.timestamp()),
             ],
         )?;
         Ok(())
@@ -172,7 +191,9 @@ impl ProviderPoolDao {
              is_disabled = ?6, check_health = ?7, check_model_name = ?8,
              not_supported_models = ?9, usage_count = ?10, error_count = ?11,
              last_used = ?12, last_error_time = ?13, last_error_message = ?14,
-             last_health_check_time = ?15, last_health_check_model = ?16, updated_at = ?17, proxy_url = ?18
+             last_health_check_time = ?15, last_health_check_model = ?16, updated_at = ?17, proxy_url = ?18,
+             tier = ?19, health_score = ?20, upstream_headers = ?21, parked_until = ?22,
+             unhealthy_since = CASE WHEN ?5 THEN NULL ELSE COALESCE(?23, unhealthy_since, ?17) END
              WHERE uuid = ?1",
             params![
                 cred.uuid,
@@ -193,6 +214,11 @@ impl ProviderPoolDao {
                 cred.last_health_check_model,
                 cred.updated_at.timestamp(),
                 cred.proxy_url,
+                cred.tier,
+                cred.health_score,
+                upstream_headers_json(cred),
+                cred.parked_until.map(|t| t.timestamp()),
+                cred.unhealthy_since.map(|t| t.timestamp()),
             ],
         )?;
         Ok(())
@@ -208,10 +234,13 @@ impl ProviderPoolDao {
     }
 
     /// 更新健康状态
+    ///
+    /// 变为不健康时记录连续不健康的开始时间，恢复健康时清除。
     pub fn update_health_status(
         conn: &Connection,
         uuid: &str,
         is_healthy: bool,
+        health_score: f64,
         error_count: u32,
         last_error_time: Option<DateTime<Utc>>,
         last_error_message: Option<&str>,
@@ -222,7 +251,8 @@ impl ProviderPoolDao {
             "UPDATE provider_pool_credentials SET
              is_healthy = ?2, error_count = ?3, last_error_time = ?4,
              last_error_message = ?5, last_health_check_time = ?6,
-             last_health_check_model = ?7, updated_at = ?8
+             last_health_check_model = ?7, updated_at = ?8, health_score = ?9,
+             unhealthy_since = CASE WHEN ?2 THEN NULL ELSE COALESCE(unhealthy_since, ?8) END
              WHERE uuid = ?1",
             params![
                 uuid,
@@ -233,6 +263,7 @@ impl ProviderPoolDao {
                 last_health_check_time.map(|t| t.timestamp()),
                 last_health_check_model,
                 Utc::now().timestamp(),
+                health_score,
             ],
         )?;
         Ok(())
@@ -263,8 +294,9 @@ impl ProviderPoolDao {
     pub fn reset_counters(conn: &Connection, uuid: &str) -> Result<(), rusqlite::Error> {
         conn.execute(
             "UPDATE provider_pool_credentials SET
-             usage_count = 0, error_count = 0, is_healthy = 1,
-             last_error_time = NULL, last_error_message = NULL, updated_at = ?2
+             usage_count = 0, error_count = 0, is_healthy = 1, health_score = 1.0,
+             unhealthy_since = NULL, last_error_time = NULL, last_error_message = NULL,
+             updated_at = ?2
              WHERE uuid = ?1",
             params![uuid, Utc::now().timestamp()],
         )?;
@@ -278,8 +310,8 @@ impl ProviderPoolDao {
     ) -> Result<usize, rusqlite::Error> {
         let affected = conn.execute(
             "UPDATE provider_pool_credentials SET
-             is_healthy = 1, error_count = 0, last_error_time = NULL,
-             last_error_message = NULL, updated_at = ?2
+             is_healthy = 1, health_score = 1.0, unhealthy_since = NULL, error_count = 0,
+             last_error_time = NULL, last_error_message = NULL, updated_at = ?2
              WHERE provider_type = ?1",
             params![provider_type.to_string(), Utc::now().timestamp()],
         )?;
@@ -308,6 +340,11 @@ impl ProviderPoolDao {
         let updated_at_ts: i64 = row.get(17)?;
         let source_str: Option<String> = row.get(18).ok();
         let proxy_url: Option<String> = row.get(19).ok();
+        let tier: Option<String> = row.get(20).ok();
+        let health_score: f64 = row.get::<_, Option<f64>>(21).ok().flatten().unwrap_or(1.0);
+        let upstream_headers_json: Option<String> = row.get(22).ok().flatten();
+        let parked_until_ts: Option<i64> = row.get(23).ok().flatten();
+        let unhealthy_since_ts: Option<i64> = row.get(24).ok().flatten();
 
         let provider_type: PoolProviderType =
             provider_type_str.parse().unwrap_or(PoolProviderType::Kiro);
@@ -320,6 +357,10 @@ impl ProviderPoolDao {
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default();
 
+        let upstream_headers: HashMap<String, String> = upstream_headers_json
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
         let source = match source_str.as_deref() {
             Some("imported") => CredentialSource::Imported,
             Some("private") => CredentialSource::Private,
@@ -355,6 +396,11 @@ impl ProviderPoolDao {
             cached_token: None, // 从 get_token_cache 单独获取
             source,
             proxy_url,
+            tier,
+            health_score,
+            upstream_headers,
+            parked_until: parked_until_ts.and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+            unhealthy_since: unhealthy_since_ts.and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
         })
     }
 
@@ -0,0 +1,380 @@
+//! 切换日志数据访问对象
+//!
+//! 记录流量离开某个凭证的事件（限流、上游 5xx、健康检查失败、手动禁用、凭证降级），
+//! 超出容量时删除最旧的记录。
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+
+/// 切换原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchTrigger {
+    /// 上游返回 429
+    RateLimit,
+    /// 上游返回 5xx
+    ServerError,
+    /// 健康检查失败
+    Health,
+    /// 手动禁用凭证
+    Manual,
+    /// Provider 无可用凭证，降级到其他 Provider
+    CredentialsUnavailable,
+}
+
+impl SwitchTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RateLimit => "rate_limit",
+            Self::ServerError => "server_error",
+            Self::Health => "health",
+            Self::Manual => "manual",
+            Self::CredentialsUnavailable => "credentials_unavailable",
+        }
+    }
+
+    /// 根据上游响应状态码判断切换原因（不会导致切换的状态码返回 None）
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            429 => Some(Self::RateLimit),
+            500..=599 => Some(Self::ServerError),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for SwitchTrigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rate_limit" => Ok(Self::RateLimit),
+            "server_error" => Ok(Self::ServerError),
+            "health" => Ok(Self::Health),
+            "manual" => Ok(Self::Manual),
+            "credentials_unavailable" => Ok(Self::CredentialsUnavailable),
+            _ => Err(format!("Unknown switch trigger: {}", s)),
+        }
+    }
+}
+
+/// 切换日志条目
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SwitchLogEntry {
+    /// 记录 ID（写入时忽略）
+    #[serde(default)]
+    pub id: i64,
+    /// 切换时间
+    pub timestamp: DateTime<Utc>,
+    /// 原 Provider 类型
+    pub from_provider: String,
+    /// 新 Provider 类型
+    pub to_provider: String,
+    /// 原凭证 UUID
+    pub from_credential: Option<String>,
+    /// 新凭证 UUID
+    pub to_credential: Option<String>,
+    /// 切换原因
+    pub trigger: SwitchTrigger,
+    /// 触发切换的请求 ID
+    pub request_id: Option<String>,
+    /// 触发切换的请求模型
+    pub model: Option<String>,
+    /// 附加说明（如错误信息）
+    pub detail: Option<String>,
+}
+
+/// 切换日志查询条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SwitchLogQuery {
+    /// 按切换原因过滤
+    pub trigger: Option<SwitchTrigger>,
+    /// 按 Provider 过滤（匹配原或新 Provider）
+    pub provider: Option<String>,
+    /// 按凭证 UUID 过滤（匹配原或新凭证）
+    pub credential_uuid: Option<String>,
+    /// 按请求 ID 过滤
+    pub request_id: Option<String>,
+    /// 起始时间（含）
+    pub since: Option<DateTime<Utc>>,
+    /// 结束时间（不含）
+    pub until: Option<DateTime<Utc>>,
+    /// 跳过的条数
+    pub offset: usize,
+    /// 每页条数
+    pub limit: usize,
+}
+
+impl Default for SwitchLogQuery {
+    fn default() -> Self {
+        Self {
+            trigger: None,
+            provider: None,
+            credential_uuid: None,
+            request_id: None,
+            since: None,
+            until: None,
+            offset: 0,
+            limit: 50,
+        }
+    }
+}
+
+/// 分页查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchLogPage {
+    /// 当前页记录（按时间倒序）
+    pub entries: Vec<SwitchLogEntry>,
+    /// 满足条件的记录总数
+    pub total: usize,
+}
+
+const SELECT_COLUMNS: &str = "id, created_at, from_provider, to_provider, from_credential,
+     to_credential, trigger, request_id, model, detail";
+
+const WHERE_CLAUSE: &str = "WHERE (?1 IS NULL OR trigger = ?1)
+       AND (?2 IS NULL OR from_provider = ?2 OR to_provider = ?2)
+       AND (?3 IS NULL OR from_credential = ?3 OR to_credential = ?3)
+       AND (?4 IS NULL OR request_id = ?4)
+       AND (?5 IS NULL OR created_at >= ?5)
+       AND (?6 IS NULL OR created_at < ?6)";
+
+/// 固定精度，保证按字符串排序即按时间排序
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn from_row(row: &Row) -> Result<SwitchLogEntry, rusqlite::Error> {
+    let created_at: String = row.get(1)?;
+    let trigger: String = row.get(6)?;
+    Ok(SwitchLogEntry {
+        id: row.get(0)?,
+        timestamp: DateTime::parse_from_rfc3339(&created_at)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+        from_provider: row.get(2)?,
+        to_provider: row.get(3)?,
+        from_credential: row.get(4)?,
+        to_credential: row.get(5)?,
+        trigger: trigger.parse().map_err(|e: String| {
+            rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, e.into())
+        })?,
+        request_id: row.get(7)?,
+        model: row.get(8)?,
+        detail: row.get(9)?,
+    })
+}
+
+pub struct SwitchLogDao;
+
+impl SwitchLogDao {
+    /// 写入一条记录，并只保留最新的 `max_entries` 条
+    pub fn insert(
+        conn: &Connection,
+        entry: &SwitchLogEntry,
+        max_entries: usize,
+    ) -> Result<i64, rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO switch_events (created_at, from_provider, to_provider, from_credential,
+                to_credential, trigger, request_id, model, detail)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                format_time(&entry.timestamp),
+                entry.from_provider,
+                entry.to_provider,
+                entry.from_credential,
+                entry.to_credential,
+                entry.trigger.as_str(),
+                entry.request_id,
+                entry.model,
+                entry.detail,
+            ],
+        )?;
+        let id = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM switch_events WHERE id NOT IN (
+                SELECT id FROM switch_events ORDER BY created_at DESC, id DESC LIMIT ?1
+            )",
+            params![max_entries as i64],
+        )?;
+        Ok(id)
+    }
+
+    /// 按条件分页查询，按时间倒序
+    pub fn list(
+        conn: &Connection,
+        query: &SwitchLogQuery,
+    ) -> Result<SwitchLogPage, rusqlite::Error> {
+        let trigger = query.trigger.map(|t| t.as_str());
+        let since = query.since.as_ref().map(format_time);
+        let until = query.until.as_ref().map(format_time);
+        let filters = params![
+            trigger,
+            query.provider,
+            query.credential_uuid,
+            query.request_id,
+            since,
+            until,
+        ];
+
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM switch_events {WHERE_CLAUSE}"),
+            filters,
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM switch_events {WHERE_CLAUSE}
+             ORDER BY created_at DESC, id DESC LIMIT ?7 OFFSET ?8"
+        ))?;
+        let entries = stmt
+            .query_map(
+                params![
+                    trigger,
+                    query.provider,
+                    query.credential_uuid,
+                    query.request_id,
+                    since,
+                    until,
+                    query.limit as i64,
+                    query.offset as i64,
+                ],
+                from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SwitchLogPage {
+            entries,
+            total: total as usize,
+        })
+    }
+
+    /// 清空所有记录，返回删除的条数
+    pub fn clear(conn: &Connection) -> Result<usize, rusqlite::Error> {
+        conn.execute("DELETE FROM switch_events", [])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn create_test_entry(trigger: SwitchTrigger, from: &str, seconds_ago: i64) -> SwitchLogEntry {
+        SwitchLogEntry {
+            id: 0,
+            timestamp: Utc::now() - chrono::Duration::seconds(seconds_ago),
+            from_provider: "kiro".to_string(),
+            to_provider: "kiro".to_string(),
+            from_credential: Some(from.to_string()),
+            to_credential: Some("cred-next".to_string()),
+            trigger,
+            request_id: Some(format!("req-{from}")),
+            model: Some("claude-sonnet-4".to_string()),
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn test_insert_trims_oldest_entries() {
+        let conn = create_test_connection();
+        for (i, seconds_ago) in [30, 20, 10].into_iter().enumerate() {
+            let entry = create_test_entry(SwitchTrigger::RateLimit, &format!("c{i}"), seconds_ago);
+            SwitchLogDao::insert(&conn, &entry, 2).unwrap();
+        }
+
+        let page = SwitchLogDao::list(&conn, &SwitchLogQuery::default()).unwrap();
+        assert_eq!(page.total, 2);
+        let from: Vec<_> = page
+            .entries
+            .iter()
+            .map(|e| e.from_credential.as_deref().unwrap())
+            .collect();
+        assert_eq!(from, vec!["c2", "c1"]);
+    }
+
+    #[test]
+    fn test_list_filters_and_pagination() {
+        let conn = create_test_connection();
+        SwitchLogDao::insert(
+            &conn,
+            &create_test_entry(SwitchTrigger::RateLimit, "a", 40),
+            100,
+        )
+        .unwrap();
+        SwitchLogDao::insert(
+            &conn,
+            &create_test_entry(SwitchTrigger::ServerError, "b", 30),
+            100,
+        )
+        .unwrap();
+        SwitchLogDao::insert(
+            &conn,
+            &create_test_entry(SwitchTrigger::RateLimit, "c", 20),
+            100,
+        )
+        .unwrap();
+
+        let rate_limited = SwitchLogDao::list(
+            &conn,
+            &SwitchLogQuery {
+                trigger: Some(SwitchTrigger::RateLimit),
+                limit: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(rate_limited.total, 2);
+        assert_eq!(rate_limited.entries.len(), 1);
+        assert_eq!(
+            rate_limited.entries[0].from_credential.as_deref(),
+            Some("c")
+        );
+
+        let second_page = SwitchLogDao::list(
+            &conn,
+            &SwitchLogQuery {
+                trigger: Some(SwitchTrigger::RateLimit),
+                offset: 1,
+                limit: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(second_page.entries[0].from_credential.as_deref(), Some("a"));
+
+        let by_credential = SwitchLogDao::list(
+            &conn,
+            &SwitchLogQuery {
+                credential_uuid: Some("b".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(by_credential.total, 1);
+        assert_eq!(by_credential.entries[0].trigger, SwitchTrigger::ServerError);
+        assert_eq!(
+            by_credential.entries[0].request_id.as_deref(),
+            Some("req-b")
+        );
+
+        let recent = SwitchLogDao::list(
+            &conn,
+            &SwitchLogQuery {
+                since: Some(Utc::now() - chrono::Duration::seconds(25)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(recent.total, 1);
+
+        assert_eq!(SwitchLogDao::clear(&conn).unwrap(), 3);
+    }
+}
@@ -0,0 +1,344 @@
+//! 数据库维护
+//!
+//! 删除超出保留期的记录（使用统计、死信、切换日志），然后执行 `ANALYZE` 和 `VACUUM`
+//! 回收空间。由定时任务在空闲时段执行，也可以通过 `compact_database` 命令手动触发。
+//!
+//! `VACUUM` 在独立连接上执行，不占用共享连接的锁。其他连接持有锁时 `VACUUM`
+//! 会在 `busy_timeout` 后失败，此时跳过本次 `VACUUM` 并在报告中说明，已删除的记录不受影响。
+
+use crate::config::DatabaseMaintenanceSettings;
+use crate::database::DbConnection;
+use chrono::{Duration as ChronoDuration, NaiveDateTime, SecondsFormat, Timelike, Utc};
+use rusqlite::{Connection, ErrorCode};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// 需要按保留期清理的表：(表名, 时间列, 时间列是否只有日期)
+const PRUNE_TABLES: &[(&str, &str, bool)] = &[
+    ("model_usage_stats", "date", true),
+    ("dead_letters", "created_at", false),
+    ("switch_events", "created_at", false),
+];
+
+/// 单张表的清理结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PrunedTable {
+    pub table: String,
+    pub rows: usize,
+}
+
+/// 数据库维护结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactionReport {
+    /// 各表删除的记录数
+    pub pruned: Vec<PrunedTable>,
+    /// 维护前的数据库大小（字节）
+    pub size_before: u64,
+    /// 维护后的数据库大小（字节）
+    pub size_after: u64,
+    /// 回收的空间（字节）
+    pub reclaimed_bytes: u64,
+    /// 是否执行了 VACUUM
+    pub vacuumed: bool,
+    /// 跳过 VACUUM 的原因（如数据库繁忙）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vacuum_skipped: Option<String>,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+}
+
+impl CompactionReport {
+    /// 删除的记录总数
+    pub fn pruned_rows(&self) -> usize {
+        self.pruned.iter().map(|p| p.rows).sum()
+    }
+}
+
+/// 数据库占用的字节数（页数 × 页大小）
+fn database_size(conn: &Connection) -> Result<u64, rusqlite::Error> {
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok((page_count * page_size).max(0) as u64)
+}
+
+/// 是否为其他连接持有锁导致的失败
+fn is_busy(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// 删除超出保留期的记录
+fn prune(conn: &Connection, retention_days: u32) -> Result<Vec<PrunedTable>, rusqlite::Error> {
+    let cutoff = Utc::now() - ChronoDuration::days(retention_days as i64);
+    let cutoff_date = cutoff.format("%Y-%m-%d").to_string();
+    let cutoff_time = cutoff.to_rfc3339_opts(SecondsFormat::Micros, true);
+
+    let tx = conn.unchecked_transaction()?;
+    let mut pruned = Vec::with_capacity(PRUNE_TABLES.len());
+    for (table, column, date_only) in PRUNE_TABLES {
+        let cutoff = if *date_only {
+            &cutoff_date
+        } else {
+            &cutoff_time
+        };
+        let rows = tx.execute(
+            &format!("DELETE FROM {table} WHERE {column} < ?1"),
+            [cutoff],
+        )?;
+        pruned.push(PrunedTable {
+            table: table.to_string(),
+            rows,
+        });
+    }
+    tx.commit()?;
+    Ok(pruned)
+}
+
+/// 删除过期记录并执行 `ANALYZE`
+fn prune_and_analyze(conn: &Connection, retention_days: u32) -> Result<Vec<PrunedTable>, String> {
+    let pruned = if retention_days > 0 {
+        prune(conn, retention_days).map_err(|e| {
+            if is_busy(&e) {
+                "数据库繁忙，请稍后重试".to_string()
+            } else {
+                format!("清理过期记录失败: {}", e)
+            }
+        })?
+    } else {
+        Vec::new()
+    };
+
+    if let Err(e) = conn.execute_batch("ANALYZE") {
+        tracing::warn!("[DB_MAINTENANCE] ANALYZE 失败: {}", e);
+    }
+    Ok(pruned)
+}
+
+/// 执行 `VACUUM`，数据库繁忙时返回跳过原因
+fn vacuum(conn: &Connection) -> Result<Option<String>, String> {
+    match conn.execute_batch("VACUUM") {
+        Ok(()) => Ok(None),
+        Err(e) if is_busy(&e) => {
+            tracing::warn!("[DB_MAINTENANCE] 数据库繁忙，跳过 VACUUM: {}", e);
+            Ok(Some("database is busy".to_string()))
+        }
+        Err(e) => Err(format!("VACUUM 失败: {}", e)),
+    }
+}
+
+/// 执行一次数据库维护
+///
+/// `conn` 用于统计大小和执行 `VACUUM`，`prune` 负责删除过期记录和 `ANALYZE`。
+fn compact_with(
+    conn: &Connection,
+    prune: impl FnOnce() -> Result<Vec<PrunedTable>, String>,
+    vacuum_enabled: bool,
+) -> Result<CompactionReport, String> {
+    let started = std::time::Instant::now();
+    let size_before = database_size(conn).map_err(|e| e.to_string())?;
+    let pruned = prune()?;
+    let vacuum_skipped = if vacuum_enabled { vacuum(conn)? } else { None };
+    let size_after = database_size(conn).map_err(|e| e.to_string())?;
+    Ok(CompactionReport {
+        pruned,
+        size_before,
+        size_after,
+        reclaimed_bytes: size_before.saturating_sub(size_after),
+        vacuumed: vacuum_enabled && vacuum_skipped.is_none(),
+        vacuum_skipped,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// 在单个连接上执行一次数据库维护
+///
+/// `retention_days` 为 0 时不删除记录；`vacuum` 为 false 时只执行 `ANALYZE`。
+pub fn compact(
+    conn: &Connection,
+    retention_days: u32,
+    vacuum: bool,
+) -> Result<CompactionReport, String> {
+    compact_with(conn, || prune_and_analyze(conn, retention_days), vacuum)
+}
+
+/// 打开维护专用的数据库连接
+fn open_maintenance_connection(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| format!("打开数据库失败: {}", e))?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(|e| format!("设置 busy_timeout 失败: {}", e))?;
+    Ok(conn)
+}
+
+/// 按维护设置执行一次维护（阻塞调用，应在 `spawn_blocking` 中执行）
+///
+/// 只在删除过期记录时持有共享连接的锁；`VACUUM` 可能持续数秒，在独立连接上执行，
+/// 期间请求路径仍可获取共享连接。
+pub fn run(
+    db: &DbConnection,
+    settings: &DatabaseMaintenanceSettings,
+) -> Result<CompactionReport, String> {
+    run_at(db, &crate::database::get_db_path()?, settings)
+}
+
+fn run_at(
+    db: &DbConnection,
+    path: &Path,
+    settings: &DatabaseMaintenanceSettings,
+) -> Result<CompactionReport, String> {
+    let conn = open_maintenance_connection(path)?;
+    let report = compact_with(
+        &conn,
+        || {
+            let shared = db.lock().map_err(|e| e.to_string())?;
+            prune_and_analyze(&shared, settings.retention_days)
+        },
+        settings.vacuum,
+    )?;
+    tracing::info!(
+        "[DB_MAINTENANCE] 删除 {} 条过期记录，回收 {} 字节，耗时 {}ms",
+        report.pruned_rows(),
+        report.reclaimed_bytes,
+        report.duration_ms
+    );
+    Ok(report)
+}
+
+/// 距离下一次到达指定整点（本地时间）的时长
+pub fn delay_until_hour(now: NaiveDateTime, hour: u32) -> Duration {
+    let today = now.date().and_hms_opt(hour.min(23), 0, 0).unwrap_or(now);
+    let next = if today > now {
+        today
+    } else {
+        today + ChronoDuration::days(1)
+    };
+    (next - now).to_std().unwrap_or_default()
+}
+
+/// 当前是否处于维护时段（指定整点的一小时内）
+pub fn in_maintenance_window(now: NaiveDateTime, hour: u32) -> bool {
+    now.hour() == hour.min(23)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use rusqlite::params;
+
+    fn create_test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn insert_switch_event(conn: &Connection, days_ago: i64) {
+        let created_at = (Utc::now() - ChronoDuration::days(days_ago))
+            .to_rfc3339_opts(SecondsFormat::Micros, true);
+        conn.execute(
+            "INSERT INTO switch_events (created_at, from_provider, to_provider, trigger, detail)
+             VALUES (?1, 'kiro', 'kiro', 'rate_limit', ?2)",
+            params![created_at, "x".repeat(4096)],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_compact_prunes_expired_rows() {
+        let conn = create_test_connection();
+        for days_ago in [90, 60, 1] {
+            insert_switch_event(&conn, days_ago);
+        }
+        let old_date = (Utc::now() - ChronoDuration::days(45))
+            .format("%Y-%m-%d")
+            .to_string();
+        conn.execute(
+            "INSERT INTO model_usage_stats (model_id, credential_id, date) VALUES ('m', 'c', ?1)",
+            [old_date],
+        )
+        .unwrap();
+
+        let report = compact(&conn, 30, true).unwrap();
+        let rows_of = |table: &str| {
+            report
+                .pruned
+                .iter()
+                .find(|p| p.table == table)
+                .map(|p| p.rows)
+        };
+        assert_eq!(rows_of("switch_events"), Some(2));
+        assert_eq!(rows_of("model_usage_stats"), Some(1));
+        assert_eq!(rows_of("dead_letters"), Some(0));
+        assert_eq!(report.pruned_rows(), 3);
+        assert!(report.vacuumed);
+        assert!(report.size_after <= report.size_before);
+
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM switch_events", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 1);
+    }
+
+    #[test]
+    fn test_compact_zero_retention_keeps_rows() {
+        let conn = create_test_connection();
+        insert_switch_event(&conn, 400);
+
+        let report = compact(&conn, 0, false).unwrap();
+        assert!(report.pruned.is_empty());
+        assert!(!report.vacuumed);
+        assert!(report.vacuum_skipped.is_none());
+    }
+
+    #[test]
+    fn test_run_vacuums_without_holding_shared_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("proxycast.db");
+        let conn = Connection::open(&path).unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        for days_ago in [90, 1] {
+            insert_switch_event(&conn, days_ago);
+        }
+        let db: DbConnection = std::sync::Arc::new(std::sync::Mutex::new(conn));
+
+        let settings = DatabaseMaintenanceSettings {
+            enabled: true,
+            hour: 4,
+            retention_days: 30,
+            vacuum: true,
+        };
+        let report = run_at(&db, &path, &settings).unwrap();
+        assert_eq!(report.pruned_rows(), 1);
+        assert!(report.vacuumed);
+        // 共享连接在 VACUUM 后仍可正常使用
+        let remaining: i64 = db
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM switch_events", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 1);
+    }
+
+    #[test]
+    fn test_delay_until_hour() {
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2025, 3, 1)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+        assert_eq!(
+            delay_until_hour(at(1, 30), 4),
+            Duration::from_secs(2 * 3600 + 1800)
+        );
+        // 已过当天的维护时间，等到第二天
+        assert_eq!(
+            delay_until_hour(at(4, 0), 4),
+            Duration::from_secs(24 * 3600)
+        );
+        assert!(in_maintenance_window(at(4, 59), 4));
+        assert!(!in_maintenance_window(at(5, 0), 4));
+    }
+}
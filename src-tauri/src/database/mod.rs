@@ -1,4 +1,5 @@
 pub mod dao;
+pub mod maintenance;
 pub mod migration;
 pub mod schema;
 pub mod system_providers;
@@ -222,6 +222,46 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
     // Migration: 添加代理URL字段 - 使用重建表结构的方式
     migrate_add_proxy_url_column(conn)?;
 
+    // Migration: 添加凭证层级字段
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN tier TEXT",
+        [],
+    );
+
+    // Migration: 添加凭证健康分字段
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN health_score REAL DEFAULT 1.0",
+        [],
+    );
+
+    // Migration: 添加凭证级上游请求头字段（JSON 对象）
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN upstream_headers TEXT",
+        [],
+    );
+
+    // Migration: 添加凭证暂停到期时间字段（Unix 秒）
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN parked_until INTEGER",
+        [],
+    );
+
+    // Migration: 添加凭证连续不健康开始时间字段（Unix 秒）
+    // 已处于不健康状态的凭证从迁移时开始计时，避免升级后立即被自动禁用
+    if conn
+        .execute(
+            "ALTER TABLE provider_pool_credentials ADD COLUMN unhealthy_since INTEGER",
+            [],
+        )
+        .is_ok()
+    {
+        let _ = conn.execute(
+            "UPDATE provider_pool_credentials SET unhealthy_since = CAST(strftime('%s', 'now') AS INTEGER)
+             WHERE is_healthy = 0",
+            [],
+        );
+    }
+
     // 已安装插件表
     // _需求: 1.2, 1.3_
     conn.execute(
@@ -576,6 +616,53 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
+    // 死信表
+    // 存储 Provider 调用失败的请求（脱敏后），用于事后排查和重放
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS dead_letters (
+            id TEXT PRIMARY KEY,
+            created_at TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            credential_uuid TEXT,
+            model TEXT NOT NULL,
+            path TEXT NOT NULL,
+            status_code INTEGER NOT NULL,
+            error_type TEXT NOT NULL,
+            error_message TEXT NOT NULL,
+            request_body TEXT NOT NULL,
+            truncated INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_dead_letters_created_at ON dead_letters(created_at)",
+        [],
+    )?;
+
+    // 切换日志表
+    // 记录流量离开某个凭证的原因，用于事后审计
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS switch_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created_at TEXT NOT NULL,
+            from_provider TEXT NOT NULL,
+            to_provider TEXT NOT NULL,
+            from_credential TEXT,
+            to_credential TEXT,
+            trigger TEXT NOT NULL,
+            request_id TEXT,
+            model TEXT,
+            detail TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_switch_events_created_at ON switch_events(created_at)",
+        [],
+    )?;
+
     Ok(())
 }
 
@@ -0,0 +1,195 @@
+//! 异常告警评估器
+//!
+//! 定时按 `ThresholdConfig.alert_rules` 从增强统计服务获取窗口指标，
+//! 指标越过阈值时通过 Flow 事件通道（WebSocket / 前端）发送告警，
+//! 同一规则在冷却时间内只告警一次。
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::enhanced_stats::{EnhancedStatsService, StatsTimeRange, WindowMetrics};
+use super::memory_store::FlowFilter;
+use super::monitor::{AlertEvent, AlertMetric, FlowMonitor};
+
+/// 告警评估间隔（秒）
+const ALERT_EVAL_INTERVAL_SECS: u64 = 15;
+
+/// 异常告警评估器
+pub struct AlertEvaluator {
+    monitor: Arc<FlowMonitor>,
+    stats: Arc<EnhancedStatsService>,
+    /// 规则 ID -> 上次告警时间
+    last_fired: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl AlertEvaluator {
+    pub fn new(monitor: Arc<FlowMonitor>, stats: Arc<EnhancedStatsService>) -> Self {
+        Self {
+            monitor,
+            stats,
+            last_fired: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 定时评估循环（在后台任务中运行）
+    pub async fn run(self: Arc<Self>) {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(ALERT_EVAL_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let fired = self.evaluate(Utc::now()).await;
+            if fired > 0 {
+                tracing::debug!("[AlertEvaluator] 本轮触发 {} 条告警", fired);
+            }
+        }
+    }
+
+    /// 评估所有启用的规则，返回本次发出的告警数量
+    pub async fn evaluate(&self, now: DateTime<Utc>) -> usize {
+        let config = self.monitor.threshold_config().await;
+        if !config.enabled || !self.monitor.is_enabled().await {
+            return 0;
+        }
+        let cooldown = Duration::seconds(config.alert_cooldown_seconds as i64);
+
+        // 相同窗口的规则共用一次统计
+        let mut metrics_by_window: HashMap<u64, WindowMetrics> = HashMap::new();
+        let mut fired = 0;
+
+        for rule in config.alert_rules.iter().filter(|r| r.enabled) {
+            let metrics = match metrics_by_window.get(&rule.window_seconds) {
+                Some(metrics) => metrics.clone(),
+                None => {
+                    let time_range = StatsTimeRange {
+                        start: now - Duration::seconds(rule.window_seconds.max(1) as i64),
+                        end: now,
+                    };
+                    let metrics = self
+                        .stats
+                        .get_window_metrics(&FlowFilter::default(), &time_range)
+                        .await;
+                    metrics_by_window.insert(rule.window_seconds, metrics.clone());
+                    metrics
+                }
+            };
+
+            let Some(value) = metric_value(rule.metric, &metrics) else {
+                continue;
+            };
+            if !rule.is_breached(value) {
+                continue;
+            }
+
+            let mut last_fired = self.last_fired.lock().await;
+            if !cooldown_elapsed(last_fired.get(&rule.id).copied(), now, cooldown) {
+                continue;
+            }
+            last_fired.insert(rule.id.clone(), now);
+            drop(last_fired);
+
+            self.monitor.emit_alert(AlertEvent::new(rule, value));
+            fired += 1;
+        }
+
+        // 清理已删除规则的去抖状态
+        self.last_fired
+            .lock()
+            .await
+            .retain(|id, _| config.alert_rules.iter().any(|r| &r.id == id));
+
+        fired
+    }
+}
+
+/// 取出规则对应的指标值，无样本时返回 None（不参与评估）
+fn metric_value(metric: AlertMetric, metrics: &WindowMetrics) -> Option<f64> {
+    match metric {
+        AlertMetric::RequestRate => Some(metrics.request_rate),
+        AlertMetric::ErrorRate => metrics.error_rate,
+        AlertMetric::P99Latency => metrics.p99_latency_ms.map(|v| v as f64),
+    }
+}
+
+/// 距上次告警是否已超过冷却时间
+fn cooldown_elapsed(last: Option<DateTime<Utc>>, now: DateTime<Utc>, cooldown: Duration) -> bool {
+    match last {
+        Some(last) => now - last >= cooldown,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_monitor::models::{
+        FlowError, FlowErrorType, FlowMetadata, FlowState, FlowType, LLMFlow, LLMRequest,
+    };
+    use crate::flow_monitor::monitor::{
+        AlertComparator, AlertRule, FlowEvent, FlowMonitorConfig, ThresholdConfig,
+    };
+
+    fn failed_flow(id: &str) -> LLMFlow {
+        let mut flow = LLMFlow::new(
+            id.to_string(),
+            FlowType::ChatCompletions,
+            LLMRequest::default(),
+            FlowMetadata::default(),
+        );
+        flow.state = FlowState::Failed;
+        flow.error = Some(FlowError::new(FlowErrorType::ServerError, "boom"));
+        flow
+    }
+
+    #[test]
+    fn test_cooldown_elapsed() {
+        let now = Utc::now();
+        let cooldown = Duration::seconds(300);
+        assert!(cooldown_elapsed(None, now, cooldown));
+        assert!(!cooldown_elapsed(
+            Some(now - Duration::seconds(10)),
+            now,
+            cooldown
+        ));
+        assert!(cooldown_elapsed(
+            Some(now - Duration::seconds(300)),
+            now,
+            cooldown
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_error_rate_alert_is_debounced() {
+        let monitor = Arc::new(FlowMonitor::new(FlowMonitorConfig::default(), None));
+        let stats = Arc::new(EnhancedStatsService::new(monitor.memory_store()));
+        monitor
+            .update_threshold_config(ThresholdConfig {
+                alert_rules: vec![AlertRule {
+                    id: "errors".to_string(),
+                    enabled: true,
+                    metric: AlertMetric::ErrorRate,
+                    comparator: AlertComparator::Gte,
+                    threshold: 0.5,
+                    window_seconds: 60,
+                    desktop_notification: false,
+                }],
+                ..Default::default()
+            })
+            .await;
+        monitor.memory_store().write().await.add(failed_flow("f1"));
+
+        let evaluator = AlertEvaluator::new(monitor.clone(), stats);
+        let mut receiver = monitor.subscribe();
+        let now = Utc::now();
+
+        assert_eq!(evaluator.evaluate(now).await, 1);
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(FlowEvent::AlertTriggered { alert }) if alert.rule_id == "errors"
+        ));
+
+        // 冷却时间内不再重复告警
+        assert_eq!(evaluator.evaluate(now + Duration::seconds(15)).await, 0);
+    }
+}
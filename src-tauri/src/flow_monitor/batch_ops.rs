@@ -42,13 +42,26 @@ pub enum BatchOperation {
     AddToSession { session_id: String },
 }
 
+/// 单个 Flow 的操作结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchItemResult {
+    pub flow_id: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// 批量操作结果
+///
+/// 单个 Flow 失败不会中断批量操作，`items` 按请求顺序记录每个 Flow 的结果。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct BatchResult {
     pub total: usize,
     pub success: usize,
     pub failed: usize,
     pub errors: Vec<(String, String)>,
+    #[serde(default)]
+    pub items: Vec<BatchItemResult>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub export_data: Option<String>,
 }
@@ -60,15 +73,36 @@ impl BatchResult {
             success: 0,
             failed: 0,
             errors: Vec::new(),
+            items: Vec::with_capacity(total),
             export_data: None,
         }
     }
-    pub fn record_success(&mut self) {
+    pub fn record_success(&mut self, flow_id: impl Into<String>) {
         self.success += 1;
+        self.items.push(BatchItemResult {
+            flow_id: flow_id.into(),
+            success: true,
+            error: None,
+        });
     }
     pub fn record_failure(&mut self, flow_id: impl Into<String>, error: impl Into<String>) {
+        let flow_id = flow_id.into();
+        let error = error.into();
         self.failed += 1;
-        self.errors.push((flow_id.into(), error.into()));
+        self.errors.push((flow_id.clone(), error.clone()));
+        self.items.push(BatchItemResult {
+            flow_id,
+            success: false,
+            error: Some(error),
+        });
+    }
+    /// 失败的 Flow ID（按请求顺序）
+    pub fn failed_ids(&self) -> Vec<&str> {
+        self.items
+            .iter()
+            .filter(|item| !item.success)
+            .map(|item| item.flow_id.as_str())
+            .collect()
     }
     pub fn is_all_success(&self) -> bool {
         self.failed == 0
@@ -164,13 +198,13 @@ impl BatchOperations {
             match current_starred {
                 Some(current) if current != starred => {
                     if self.flow_monitor.toggle_starred(flow_id).await {
-                        result.record_success();
+                        result.record_success(flow_id);
                     } else {
                         result.record_failure(flow_id, "更新收藏状态失败");
                     }
                 }
                 Some(_) => {
-                    result.record_success();
+                    result.record_success(flow_id);
                 }
                 None => {
                     result.record_failure(flow_id, "Flow 不存在");
@@ -199,17 +233,16 @@ impl BatchOperations {
                 result.record_failure(flow_id, "Flow 不存在");
                 continue;
             }
-            let mut all_success = true;
+            let mut failed_tag = None;
             for tag in tags {
                 if !self.flow_monitor.add_tag(flow_id, tag.clone()).await {
-                    all_success = false;
+                    failed_tag = Some(tag);
                     break;
                 }
             }
-            if all_success {
-                result.record_success();
-            } else {
-                result.record_failure(flow_id, "添加标签失败");
+            match failed_tag {
+                None => result.record_success(flow_id),
+                Some(tag) => result.record_failure(flow_id, format!("添加标签失败: {}", tag)),
             }
         }
     }
@@ -237,7 +270,7 @@ impl BatchOperations {
             for tag in tags {
                 let _ = self.flow_monitor.remove_tag(flow_id, tag).await;
             }
-            result.record_success();
+            result.record_success(flow_id);
         }
     }
 
@@ -259,7 +292,7 @@ impl BatchOperations {
             if let Some(flow_lock) = store.get(flow_id) {
                 if let Ok(flow) = flow_lock.read() {
                     flows.push(flow.clone());
-                    result.record_success();
+                    result.record_success(flow_id);
                 } else {
                     result.record_failure(flow_id, "无法读取 Flow");
                 }
@@ -288,7 +321,7 @@ impl BatchOperations {
             let memory_store = self.flow_monitor.memory_store();
             let mut store = memory_store.write().await;
             if store.remove(flow_id) {
-                result.record_success();
+                result.record_success(flow_id);
             } else {
                 result.record_failure(flow_id, "Flow 不存在或删除失败");
             }
@@ -341,7 +374,7 @@ impl BatchOperations {
             }
             match session_manager.add_flow(session_id, flow_id) {
                 Ok(_) => {
-                    result.record_success();
+                    result.record_success(flow_id);
                 }
                 Err(e) => {
                     result.record_failure(flow_id, format!("添加到会话失败: {}", e));
@@ -485,6 +518,14 @@ mod property_tests {
                     prop_assert!(found_error, "无效的 Flow ID {} 应该在错误列表中", invalid_id);
                 }
 
+                // 每个 Flow 都有按请求顺序的单项结果
+                prop_assert_eq!(result.items.len(), all_flow_ids.len());
+                for (item, flow_id) in result.items.iter().zip(&all_flow_ids) {
+                    prop_assert_eq!(&item.flow_id, flow_id);
+                    prop_assert_eq!(item.success, valid_flow_ids.contains(flow_id));
+                }
+                prop_assert_eq!(result.failed_ids(), invalid_flow_ids.iter().map(String::as_str).collect::<Vec<_>>());
+
                 // 验证部分成功状态
                 prop_assert!(result.is_partial_success(), "应该是部分成功状态");
                 prop_assert!(!result.is_all_success(), "不应该是全部成功");
@@ -466,6 +466,7 @@ mod tests {
             timestamps: FlowTimestamps::default(),
             state: FlowState::Pending,
             annotations: FlowAnnotations::default(),
+            shadow: None,
         }
     }
 
@@ -742,6 +743,7 @@ mod property_tests {
                 timestamps: FlowTimestamps::default(),
                 state: FlowState::Pending,
                 annotations: FlowAnnotations::default(),
+                shadow: None,
             }
         })
     }
@@ -9,11 +9,13 @@
 //! - 对比消息列表的差异
 //! - 计算 Token 使用量差异
 //! - 支持忽略动态字段（时间戳、ID 等）
+//! - 对比影子凭证的响应与实际返回的响应
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::models::{LLMFlow, Message, MessageContent, TokenUsage};
+use super::models::{LLMFlow, LLMResponse, Message, MessageContent, TokenUsage};
 
 // ============================================================================
 // 差异类型
@@ -512,7 +514,7 @@ impl FlowDiff {
     }
 
     /// 对比响应
-    fn diff_responses(
+    pub fn diff_responses(
         left: Option<&super::models::LLMResponse>,
         right: Option<&super::models::LLMResponse>,
         config: &DiffConfig,
@@ -918,6 +920,86 @@ impl FlowDiff {
     }
 }
 
+// ============================================================================
+// 影子测试对比
+// ============================================================================
+
+/// 影子请求结果（等待与实际响应对比）
+#[derive(Debug, Clone)]
+pub struct ShadowResponse {
+    /// 影子凭证 ID
+    pub credential_id: String,
+    /// 影子凭证名称
+    pub credential_name: Option<String>,
+    /// 影子响应（请求失败时为空）
+    pub response: Option<LLMResponse>,
+    /// 错误信息
+    pub error: Option<String>,
+    /// 影子请求耗时（毫秒）
+    pub latency_ms: u64,
+}
+
+/// 影子测试对比结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowComparison {
+    /// 影子凭证 ID
+    pub credential_id: String,
+    /// 影子凭证名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_name: Option<String>,
+    /// 影子响应
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<LLMResponse>,
+    /// 错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 影子请求耗时（毫秒）
+    pub latency_ms: u64,
+    /// 响应内容是否完全一致
+    pub content_identical: bool,
+    /// 响应差异（实际响应为左侧，影子响应为右侧）
+    pub response_diffs: Vec<DiffItem>,
+    /// Token 差异
+    pub token_diff: TokenDiff,
+    /// 对比时间
+    pub compared_at: DateTime<Utc>,
+}
+
+impl ShadowComparison {
+    /// 对比实际返回的响应和影子响应
+    ///
+    /// 响应体格式取决于是否流式，不参与对比；实际响应没有停止原因时也不对比停止原因。
+    pub fn compare(served: Option<&LLMResponse>, shadow: ShadowResponse) -> Self {
+        let (response_diffs, token_diff, content_identical) = match (served, &shadow.response) {
+            (Some(served), Some(response)) => {
+                let mut ignore_fields = vec!["response.body".to_string()];
+                if served.stop_reason.is_none() {
+                    ignore_fields.push("response.stop_reason".to_string());
+                }
+                let config = DiffConfig::default().with_ignore_fields(ignore_fields);
+                (
+                    FlowDiff::diff_responses(Some(served), Some(response), &config),
+                    TokenDiff::from_usage(&served.usage, &response.usage),
+                    served.content == response.content,
+                )
+            }
+            _ => (Vec::new(), TokenDiff::default(), false),
+        };
+
+        Self {
+            credential_id: shadow.credential_id,
+            credential_name: shadow.credential_name,
+            response: shadow.response,
+            error: shadow.error,
+            latency_ms: shadow.latency_ms,
+            content_identical,
+            response_diffs,
+            token_diff,
+            compared_at: Utc::now(),
+        }
+    }
+}
+
 // ============================================================================
 // 单元测试
 // ============================================================================
@@ -927,6 +1009,7 @@ mod tests {
     use super::*;
     use crate::flow_monitor::models::{
         FlowMetadata, FlowType, LLMRequest, LLMResponse, Message, MessageRole, RequestParameters,
+        StopReason,
     };
     use crate::ProviderType;
 
@@ -980,6 +1063,37 @@ mod tests {
             .all(|d| d.diff_type == DiffType::Unchanged));
     }
 
+    #[test]
+    fn test_shadow_comparison_ignores_body() {
+        let served = create_test_flow("id1", "gpt-4", "Hello").response.unwrap();
+        let mut response = served.clone();
+        response.body = serde_json::json!({"id": "chatcmpl-shadow"});
+        response.content = "Shadow content".to_string();
+        response.usage.output_tokens = 80;
+        response.usage.total_tokens = 180;
+        response.stop_reason = Some(StopReason::Stop);
+
+        let comparison = ShadowComparison::compare(
+            Some(&served),
+            ShadowResponse {
+                credential_id: "shadow".to_string(),
+                credential_name: None,
+                response: Some(response),
+                error: None,
+                latency_ms: 120,
+            },
+        );
+
+        assert!(!comparison.content_identical);
+        assert_eq!(comparison.token_diff.output_diff, 30);
+        let paths: Vec<&str> = comparison
+            .response_diffs
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["response.content"]);
+    }
+
     #[test]
     fn test_diff_different_models() {
         let flow1 = create_test_flow("id1", "gpt-4", "Hello");
@@ -9,8 +9,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use super::html_report::{HtmlReport, ModelUsage};
 use super::memory_store::{FlowFilter, FlowMemoryStore, TimeRange};
 use super::models::{FlowState, LLMFlow};
+use crate::telemetry::pricing;
 use tokio::sync::RwLock;
 
 // ============================================================================
@@ -124,6 +126,8 @@ pub enum ReportFormat {
     Markdown,
     /// CSV 格式
     Csv,
+    /// 自包含的 HTML 报告（内联 SVG 图表）
+    Html,
 }
 
 impl Default for ReportFormat {
@@ -132,6 +136,66 @@ impl Default for ReportFormat {
     }
 }
 
+/// 延迟百分位的时间桶大小
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyBucket {
+    /// 按分钟
+    Minute,
+    /// 按小时
+    #[default]
+    Hour,
+    /// 按天
+    Day,
+}
+
+impl LatencyBucket {
+    /// 桶时长
+    pub fn duration(&self) -> Duration {
+        match self {
+            LatencyBucket::Minute => Duration::minutes(1),
+            LatencyBucket::Hour => Duration::hours(1),
+            LatencyBucket::Day => Duration::days(1),
+        }
+    }
+}
+
+/// 单个时间桶的延迟百分位（毫秒）
+///
+/// 桶内没有已结束的请求时百分位为 None。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LatencyPercentilePoint {
+    /// 桶起始时间
+    pub timestamp: DateTime<Utc>,
+    /// 样本数
+    pub count: u64,
+    pub p50: Option<u64>,
+    pub p90: Option<u64>,
+    pub p99: Option<u64>,
+}
+
+/// 延迟百分位时间序列
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyPercentileSeries {
+    /// 时间桶大小
+    pub bucket: LatencyBucket,
+    /// 数据点列表
+    pub points: Vec<LatencyPercentilePoint>,
+}
+
+/// 时间窗口内的聚合指标（用于异常告警）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WindowMetrics {
+    /// 请求数
+    pub request_count: u64,
+    /// 请求速率（每秒）
+    pub request_rate: f64,
+    /// 错误率（0.0-1.0），窗口内无已结束请求时为 None
+    pub error_rate: Option<f64>,
+    /// p99 延迟（毫秒），窗口内无已结束请求时为 None
+    pub p99_latency_ms: Option<u64>,
+}
+
 // ============================================================================
 // 增强统计服务
 // ============================================================================
@@ -256,6 +320,38 @@ impl EnhancedStatsService {
         self.calculate_latency_histogram(&flows, buckets)
     }
 
+    /// 获取按时间桶统计的延迟百分位（p50/p90/p99）
+    ///
+    /// 只统计已结束的请求（排除 pending/streaming），可通过 `filter`
+    /// 的 providers/models 限定范围。
+    ///
+    /// # Arguments
+    /// * `filter` - 过滤条件
+    /// * `time_range` - 时间范围
+    /// * `bucket` - 时间桶大小
+    ///
+    /// # Returns
+    /// 延迟百分位时间序列
+    pub async fn get_latency_percentiles(
+        &self,
+        filter: &FlowFilter,
+        time_range: &StatsTimeRange,
+        bucket: LatencyBucket,
+    ) -> LatencyPercentileSeries {
+        let flows = self.get_flows_in_range(filter, time_range).await;
+        self.calculate_latency_percentiles(&flows, bucket)
+    }
+
+    /// 获取时间窗口内的聚合指标（请求速率、错误率、p99 延迟）
+    pub async fn get_window_metrics(
+        &self,
+        filter: &FlowFilter,
+        time_range: &StatsTimeRange,
+    ) -> WindowMetrics {
+        let flows = self.get_flows_in_range(filter, time_range).await;
+        self.calculate_window_metrics(&flows, time_range)
+    }
+
     /// 导出统计报告
     ///
     /// **Validates: Requirements 9.7**
@@ -273,12 +369,16 @@ impl EnhancedStatsService {
         time_range: &StatsTimeRange,
         format: &ReportFormat,
     ) -> String {
-        let stats = self.get_stats(filter, time_range).await;
-
         match format {
-            ReportFormat::Json => self.export_json(&stats),
-            ReportFormat::Markdown => self.export_markdown(&stats),
-            ReportFormat::Csv => self.export_csv(&stats),
+            ReportFormat::Json => self.export_json(&self.get_stats(filter, time_range).await),
+            ReportFormat::Markdown => {
+                self.export_markdown(&self.get_stats(filter, time_range).await)
+            }
+            ReportFormat::Csv => self.export_csv(&self.get_stats(filter, time_range).await),
+            ReportFormat::Html => {
+                let flows = self.get_flows_in_range(filter, time_range).await;
+                self.export_html(&flows, time_range)
+            }
         }
     }
 
@@ -447,6 +547,134 @@ impl EnhancedStatsService {
         }
     }
 
+    /// 计算延迟百分位时间序列
+    fn calculate_latency_percentiles(
+        &self,
+        flows: &[LLMFlow],
+        bucket: LatencyBucket,
+    ) -> LatencyPercentileSeries {
+        let bucket_secs = bucket.duration().num_seconds();
+
+        // 按时间桶收集延迟样本
+        let mut samples: HashMap<i64, Vec<u64>> = HashMap::new();
+        for flow in flows {
+            if matches!(flow.state, FlowState::Pending | FlowState::Streaming) {
+                continue;
+            }
+            let key = flow.timestamps.created.timestamp().div_euclid(bucket_secs) * bucket_secs;
+            samples
+                .entry(key)
+                .or_default()
+                .push(flow.timestamps.duration_ms);
+        }
+
+        let (Some(&first), Some(&last)) = (samples.keys().min(), samples.keys().max()) else {
+            return LatencyPercentileSeries {
+                bucket,
+                points: Vec::new(),
+            };
+        };
+
+        // 生成连续的时间序列（空桶的百分位为 None）
+        let mut points = Vec::new();
+        let mut current = first;
+        while current <= last {
+            let mut latencies = samples.remove(&current).unwrap_or_default();
+            latencies.sort_unstable();
+            if let Some(timestamp) = DateTime::from_timestamp(current, 0) {
+                points.push(LatencyPercentilePoint {
+                    timestamp,
+                    count: latencies.len() as u64,
+                    p50: percentile(&latencies, 50.0),
+                    p90: percentile(&latencies, 90.0),
+                    p99: percentile(&latencies, 99.0),
+                });
+            }
+            current += bucket_secs;
+        }
+
+        LatencyPercentileSeries { bucket, points }
+    }
+
+    /// 计算窗口聚合指标
+    fn calculate_window_metrics(
+        &self,
+        flows: &[LLMFlow],
+        time_range: &StatsTimeRange,
+    ) -> WindowMetrics {
+        let mut latencies: Vec<u64> = Vec::new();
+        let mut failed = 0usize;
+        for flow in flows {
+            if matches!(flow.state, FlowState::Pending | FlowState::Streaming) {
+                continue;
+            }
+            if flow.state == FlowState::Failed || flow.error.is_some() {
+                failed += 1;
+            }
+            latencies.push(flow.timestamps.duration_ms);
+        }
+        latencies.sort_unstable();
+
+        let error_rate = if latencies.is_empty() {
+            None
+        } else {
+            Some(failed as f64 / latencies.len() as f64)
+        };
+
+        WindowMetrics {
+            request_count: flows.len() as u64,
+            request_rate: self.calculate_request_rate(flows, time_range),
+            error_rate,
+            p99_latency_ms: percentile(&latencies, 99.0),
+        }
+    }
+
+    /// 计算按模型的 Token 用量和估算费用（按 Token 数降序）
+    fn calculate_model_usage(&self, flows: &[LLMFlow]) -> Vec<ModelUsage> {
+        let mut usage: HashMap<String, (ModelUsage, Option<pricing::ModelPrice>)> = HashMap::new();
+
+        for flow in flows {
+            let model = &flow.request.model;
+            let (entry, price) = usage.entry(model.clone()).or_insert_with(|| {
+                let price = pricing::price_for(model);
+                let entry = ModelUsage {
+                    model: model.clone(),
+                    cost_usd: price.map(|_| 0.0),
+                    ..Default::default()
+                };
+                (entry, price)
+            });
+            entry.requests += 1;
+
+            let Some(ref response) = flow.response else {
+                continue;
+            };
+            let tokens = &response.usage;
+            let cache_read = tokens.cache_read_tokens.unwrap_or(0);
+            let cache_write = tokens.cache_write_tokens.unwrap_or(0);
+            entry.input_tokens += tokens.input_tokens as u64;
+            entry.output_tokens += tokens.output_tokens as u64;
+            entry.cache_read_tokens += cache_read as u64;
+            entry.cache_write_tokens += cache_write as u64;
+            if let (Some(cost), Some(price)) = (entry.cost_usd.as_mut(), price) {
+                *cost += price.cost_usd_with_cache(
+                    tokens.input_tokens,
+                    tokens.output_tokens,
+                    cache_write,
+                    cache_read,
+                );
+            }
+        }
+
+        let mut result: Vec<ModelUsage> = usage.into_values().map(|(entry, _)| entry).collect();
+        result.sort_by(|a, b| {
+            b.total_tokens()
+                .cmp(&a.total_tokens())
+                .then_with(|| a.model.cmp(&b.model))
+        });
+        result
+    }
+
     /// 计算错误分布
     fn calculate_error_distribution(&self, flows: &[LLMFlow]) -> Distribution {
         let mut error_counts: HashMap<String, u64> = HashMap::new();
@@ -486,6 +714,39 @@ impl EnhancedStatsService {
         serde_json::to_string_pretty(stats).unwrap_or_else(|_| "{}".to_string())
     }
 
+    /// 导出为自包含的 HTML 报告
+    fn export_html(&self, flows: &[LLMFlow], time_range: &StatsTimeRange) -> String {
+        let (interval, bucket) = report_granularity(time_range);
+
+        let mut latencies: Vec<u64> = flows
+            .iter()
+            .filter(|f| !matches!(f.state, FlowState::Pending | FlowState::Streaming))
+            .map(|f| f.timestamps.duration_ms)
+            .collect();
+        latencies.sort_unstable();
+
+        HtmlReport {
+            time_range: time_range.clone(),
+            generated_at: Utc::now(),
+            total_requests: flows.len() as u64,
+            completed_requests: flows
+                .iter()
+                .filter(|f| f.state == FlowState::Completed)
+                .count() as u64,
+            request_rate: self.calculate_request_rate(flows, time_range),
+            latency_p50: percentile(&latencies, 50.0),
+            latency_p90: percentile(&latencies, 90.0),
+            latency_p99: percentile(&latencies, 99.0),
+            request_trend: self.calculate_request_trend(flows, interval),
+            latency_percentiles: self.calculate_latency_percentiles(flows, bucket),
+            model_usage: self.calculate_model_usage(flows),
+            success_by_provider: self.calculate_success_by_provider(flows),
+            latency_histogram: self.calculate_latency_histogram(flows, &default_latency_buckets()),
+            error_distribution: self.calculate_error_distribution(flows),
+        }
+        .render()
+    }
+
     /// 导出为 Markdown 格式
     fn export_markdown(&self, stats: &EnhancedStats) -> String {
         let mut md = String::new();
@@ -619,6 +880,27 @@ fn default_latency_buckets() -> Vec<u64> {
     vec![100, 500, 1000, 2000, 5000, 10000]
 }
 
+/// HTML 报告图表的时间粒度（趋势间隔、延迟百分位时间桶），随时间范围放大
+fn report_granularity(time_range: &StatsTimeRange) -> (&'static str, LatencyBucket) {
+    let span = time_range.end - time_range.start;
+    if span <= Duration::hours(2) {
+        ("1m", LatencyBucket::Minute)
+    } else if span <= Duration::days(3) {
+        ("1h", LatencyBucket::Hour)
+    } else {
+        ("1d", LatencyBucket::Day)
+    }
+}
+
+/// 计算已排序样本的百分位（最近秩法）
+pub(crate) fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
 // ============================================================================
 // 测试模块
 // ============================================================================
@@ -671,6 +953,31 @@ mod tests {
         assert_eq!(format, ReportFormat::Json);
     }
 
+    #[test]
+    fn test_report_granularity() {
+        let now = Utc::now();
+        let range = |hours| StatsTimeRange {
+            start: now - Duration::hours(hours),
+            end: now,
+        };
+        assert_eq!(report_granularity(&range(1)), ("1m", LatencyBucket::Minute));
+        assert_eq!(report_granularity(&range(24)), ("1h", LatencyBucket::Hour));
+        assert_eq!(
+            report_granularity(&range(24 * 30)),
+            ("1d", LatencyBucket::Day)
+        );
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let samples: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&samples, 50.0), Some(50));
+        assert_eq!(percentile(&samples, 90.0), Some(90));
+        assert_eq!(percentile(&samples, 99.0), Some(99));
+        assert_eq!(percentile(&[42], 99.0), Some(42));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
     #[test]
     fn test_stats_time_range_default() {
         let range = StatsTimeRange::default();
@@ -908,6 +1215,29 @@ mod property_tests {
             );
         }
 
+        /// *对于任意* Flow 集合，延迟百分位序列的样本数应等于已结束的 Flow 数，
+        /// 且每个桶内 p50 <= p90 <= p99。
+        #[test]
+        fn prop_latency_percentiles_correctness(flows in arb_flow_list()) {
+            let service = EnhancedStatsService::new(
+                Arc::new(RwLock::new(FlowMemoryStore::new(1000)))
+            );
+
+            let series = service.calculate_latency_percentiles(&flows, LatencyBucket::Minute);
+
+            let finished = flows
+                .iter()
+                .filter(|f| !matches!(f.state, FlowState::Pending | FlowState::Streaming))
+                .count();
+            let total: u64 = series.points.iter().map(|p| p.count).sum();
+            prop_assert_eq!(total as usize, finished);
+
+            for point in &series.points {
+                prop_assert!(point.p50 <= point.p90 && point.p90 <= point.p99);
+                prop_assert_eq!(point.count == 0, point.p50.is_none());
+            }
+        }
+
         /// **Feature: flow-monitor-enhancement, Property 17c: 请求速率计算正确性**
         /// **Validates: Requirements 9.1**
         ///
@@ -1019,6 +1349,22 @@ mod property_tests {
                 csv_report.contains("Model,Tokens"),
                 "CSV 报告应该包含 Token 分布表头"
             );
+
+            // 测试 HTML 导出
+            let html_report = service.export_html(&flows, &time_range);
+            prop_assert!(
+                html_report.starts_with("<!DOCTYPE html>"),
+                "HTML 报告应该是完整的 HTML 文档"
+            );
+            prop_assert!(
+                !html_report.contains("<script"),
+                "HTML 报告不应该依赖外部脚本"
+            );
+
+            // 验证: 按模型的请求数之和应该等于 Flow 数量
+            let usage = service.calculate_model_usage(&flows);
+            let usage_requests: u64 = usage.iter().map(|u| u.requests).sum();
+            prop_assert_eq!(usage_requests, flows.len() as u64);
         }
     }
 }
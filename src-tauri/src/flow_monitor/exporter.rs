@@ -5,6 +5,7 @@
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 
 use super::models::{
     FlowAnnotations, FlowError, LLMFlow, LLMRequest, LLMResponse, Message, MessageContent,
@@ -1175,6 +1176,129 @@ impl ExportResult {
     }
 }
 
+// ============================================================================
+// NDJSON 流式导出
+// ============================================================================
+
+/// NDJSON 流式导出选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NdjsonExportOptions {
+    /// 是否包含原始请求/响应体
+    #[serde(default = "default_true")]
+    pub include_raw: bool,
+    /// 请求/响应体序列化后的最大字节数，超出时截断为字符串（None 不截断）
+    #[serde(default)]
+    pub max_body_bytes: Option<usize>,
+    /// 是否包含流式 chunks
+    #[serde(default)]
+    pub include_stream_chunks: bool,
+    /// 是否脱敏敏感数据
+    #[serde(default)]
+    pub redact_sensitive: bool,
+}
+
+impl Default for NdjsonExportOptions {
+    fn default() -> Self {
+        Self {
+            include_raw: true,
+            max_body_bytes: None,
+            include_stream_chunks: false,
+            redact_sensitive: false,
+        }
+    }
+}
+
+/// NDJSON 导出结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NdjsonExportSummary {
+    /// 写入的 Flow 数量
+    pub count: usize,
+    /// 写入的总字节数
+    pub bytes_written: u64,
+}
+
+/// 逐条写入 Flow 的 NDJSON 写入器
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+    options: NdjsonExportOptions,
+    redactor: Option<Redactor>,
+    summary: NdjsonExportSummary,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    /// 创建新的写入器
+    pub fn new(writer: W, options: NdjsonExportOptions) -> Self {
+        let redactor = options
+            .redact_sensitive
+            .then(|| Redactor::new(&default_redaction_rules()));
+        Self {
+            writer,
+            options,
+            redactor,
+            summary: NdjsonExportSummary::default(),
+        }
+    }
+
+    /// 按选项处理后写入一行
+    pub fn write_flow(&mut self, flow: &LLMFlow) -> std::io::Result<()> {
+        let mut flow = match self.redactor {
+            Some(ref redactor) => redactor.redact_flow(flow),
+            None => flow.clone(),
+        };
+        self.apply_body_options(&mut flow);
+
+        let mut line = serde_json::to_vec(&flow)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.summary.count += 1;
+        self.summary.bytes_written += line.len() as u64;
+        Ok(())
+    }
+
+    /// 刷新缓冲区并返回统计
+    pub fn finish(mut self) -> std::io::Result<NdjsonExportSummary> {
+        self.writer.flush()?;
+        Ok(self.summary)
+    }
+
+    fn apply_body_options(&self, flow: &mut LLMFlow) {
+        if !self.options.include_stream_chunks {
+            if let Some(info) = flow.response.as_mut().and_then(|r| r.stream_info.as_mut()) {
+                info.raw_chunks = None;
+            }
+        }
+
+        let mut bodies = vec![&mut flow.request.body];
+        if let Some(response) = flow.response.as_mut() {
+            bodies.push(&mut response.body);
+        }
+        for body in bodies {
+            if !self.options.include_raw {
+                *body = serde_json::Value::Null;
+            } else if let Some(max_bytes) = self.options.max_body_bytes {
+                truncate_body(body, max_bytes);
+            }
+        }
+    }
+}
+
+/// 序列化后超过上限的请求/响应体替换为截断后的字符串
+fn truncate_body(body: &mut serde_json::Value, max_bytes: usize) {
+    let text = body.to_string();
+    if text.len() <= max_bytes {
+        return;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    *body = serde_json::Value::String(format!(
+        "{}...[已截断，原始 {} 字节]",
+        &text[..end],
+        text.len()
+    ));
+}
+
 // ============================================================================
 // 测试模块
 // ============================================================================
@@ -1469,6 +1593,48 @@ mod tests {
         assert_eq!(tokens.output, 5);
         assert_eq!(tokens.total, 15);
     }
+
+    #[test]
+    fn test_ndjson_writer_truncates_bodies() {
+        let flow = create_test_flow();
+        let options = NdjsonExportOptions {
+            max_body_bytes: Some(16),
+            ..Default::default()
+        };
+        let mut writer = NdjsonWriter::new(Vec::new(), options);
+        writer.write_flow(&flow).unwrap();
+        writer.write_flow(&flow).unwrap();
+        let summary = writer.summary.clone();
+        let output = writer.writer;
+
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.bytes_written, output.len() as u64);
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let parsed: LLMFlow = serde_json::from_str(lines[0]).unwrap();
+        let body = parsed.request.body.as_str().unwrap();
+        assert!(body.starts_with('{'));
+        assert!(body.contains("已截断"));
+
+        let mut body = serde_json::json!({"a": 1});
+        truncate_body(&mut body, 100);
+        assert_eq!(body, serde_json::json!({"a": 1}));
+    }
+
+    #[test]
+    fn test_ndjson_writer_without_raw_bodies() {
+        let options = NdjsonExportOptions {
+            include_raw: false,
+            ..Default::default()
+        };
+        let mut writer = NdjsonWriter::new(Vec::new(), options);
+        writer.write_flow(&create_test_flow()).unwrap();
+
+        let parsed: LLMFlow = serde_json::from_slice(&writer.writer).unwrap();
+        assert!(parsed.request.body.is_null());
+        assert!(parsed.response.unwrap().body.is_null());
+    }
 }
 
 // ============================================================================
@@ -6,6 +6,7 @@
 use chrono::{DateTime, NaiveDate, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
@@ -43,6 +44,9 @@ pub enum FileStoreError {
 
 pub type Result<T> = std::result::Result<T, FileStoreError>;
 
+/// 逐条遍历时每批读取的索引条数
+const SCAN_BATCH_SIZE: usize = 500;
+
 // ============================================================================
 // 配置结构
 // ============================================================================
@@ -82,6 +86,21 @@ pub struct CleanupResult {
     pub bytes_freed: u64,
 }
 
+/// 孤立文件清理结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PruneResult {
+    /// 是否为试运行（只统计，不删除）
+    pub dry_run: bool,
+    /// 扫描的文件数
+    pub files_scanned: usize,
+    /// 删除（或试运行时将删除）的文件
+    pub pruned_files: Vec<String>,
+    /// 因包含受保护 Flow（会话、书签、内存中的 Flow）而跳过的文件数
+    pub files_protected: usize,
+    /// 释放（或试运行时可释放）的空间（字节）
+    pub bytes_reclaimed: u64,
+}
+
 // ============================================================================
 // 索引记录
 // ============================================================================
@@ -585,7 +604,7 @@ impl FlowFileStore {
     /// 查询 Flow（从索引）
     pub fn query(&self, filter: &FlowFilter, limit: usize, offset: usize) -> Result<Vec<LLMFlow>> {
         // 先获取所有文件位置信息
-        let file_locations = self.query_index(filter, limit, offset)?;
+        let file_locations = self.query_index(filter, limit, offset, false)?;
 
         // 读取 Flow
         let mut flows = Vec::new();
@@ -601,12 +620,41 @@ impl FlowFileStore {
         Ok(flows)
     }
 
+    /// 按创建时间升序逐条遍历匹配的 Flow，返回匹配数量
+    ///
+    /// 分批读取索引，不会一次性把所有 Flow 加载到内存。遍历期间有新 Flow 写入时
+    /// 分批偏移会错位，调用方应在过滤条件中固定结束时间。
+    pub fn for_each_matching(
+        &self,
+        filter: &FlowFilter,
+        mut visit: impl FnMut(LLMFlow) -> Result<()>,
+    ) -> Result<usize> {
+        let mut offset = 0;
+        let mut matched = 0;
+        loop {
+            let file_locations = self.query_index(filter, SCAN_BATCH_SIZE, offset, true)?;
+            for (file_path, file_offset) in &file_locations {
+                if let Some(flow) = self.read_flow_from_file(file_path, *file_offset)? {
+                    if filter.matches(&flow) {
+                        matched += 1;
+                        visit(flow)?;
+                    }
+                }
+            }
+            if file_locations.len() < SCAN_BATCH_SIZE {
+                return Ok(matched);
+            }
+            offset += file_locations.len();
+        }
+    }
+
     /// 从索引查询文件位置
     fn query_index(
         &self,
         filter: &FlowFilter,
         limit: usize,
         offset: usize,
+        ascending: bool,
     ) -> Result<Vec<(String, i64)>> {
         let conn = self.index_db.lock().unwrap();
 
@@ -674,8 +722,9 @@ impl FlowFileStore {
         };
 
         let sql = format!(
-            "SELECT file_path, file_offset FROM flow_index {} ORDER BY created_at DESC LIMIT ? OFFSET ?",
-            where_clause
+            "SELECT file_path, file_offset FROM flow_index {} ORDER BY created_at {} LIMIT ? OFFSET ?",
+            where_clause,
+            if ascending { "ASC" } else { "DESC" }
         );
 
         params_vec.push(Box::new(limit as i64));
@@ -900,6 +949,124 @@ impl FlowFileStore {
         Ok(())
     }
 
+    /// 清理磁盘上的孤立 Flow 文件
+    ///
+    /// 孤立文件指没有任何索引记录引用的 JSONL 文件（通常是轮转后索引已被清理的旧文件）。
+    /// 只删除修改时间早于 `older_than` 的文件；当前写入的文件、
+    /// 以及包含 `protected_ids` 中任一 Flow 的文件不会被删除。
+    ///
+    /// # 参数
+    /// - `older_than`: 只清理此时间之前修改的文件
+    /// - `protected_ids`: 受保护的 Flow ID（会话、书签、内存中的 Flow）
+    /// - `dry_run`: 为 true 时只统计，不删除
+    pub fn prune_orphan_files(
+        &self,
+        older_than: DateTime<Utc>,
+        protected_ids: &HashSet<String>,
+        dry_run: bool,
+    ) -> Result<PruneResult> {
+        let mut result = PruneResult {
+            dry_run,
+            ..Default::default()
+        };
+
+        let referenced: HashSet<PathBuf> = {
+            let conn = self.index_db.lock().unwrap();
+            let mut stmt = conn.prepare("SELECT DISTINCT file_path FROM flow_index")?;
+            let paths = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .filter_map(|r| r.ok())
+                .map(PathBuf::from)
+                .collect();
+            paths
+        };
+        let current_file = self
+            .current_writer
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|w| w.path().to_path_buf());
+
+        for date_dir in fs::read_dir(&self.base_dir)?.flatten() {
+            let date_dir = date_dir.path();
+            if !date_dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&date_dir)?.flatten() {
+                let path = entry.path();
+                if path.extension().map_or(true, |ext| ext != "jsonl") {
+                    continue;
+                }
+                result.files_scanned += 1;
+
+                if referenced.contains(&path) || current_file.as_ref() == Some(&path) {
+                    continue;
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                let modified: DateTime<Utc> = match metadata.modified() {
+                    Ok(time) => time.into(),
+                    Err(_) => continue,
+                };
+                if modified >= older_than {
+                    continue;
+                }
+                if Self::file_contains_any(&path, protected_ids)? {
+                    result.files_protected += 1;
+                    continue;
+                }
+
+                if !dry_run {
+                    if let Err(e) = fs::remove_file(&path) {
+                        tracing::warn!("[FLOW_STORE] 删除孤立文件失败 {:?}: {}", path, e);
+                        continue;
+                    }
+                }
+                result.bytes_reclaimed += metadata.len();
+                result.pruned_files.push(path.to_string_lossy().to_string());
+            }
+        }
+
+        if !dry_run && !result.pruned_files.is_empty() {
+            self.cleanup_empty_dirs()?;
+        }
+
+        tracing::info!(
+            "[FLOW_STORE] 孤立文件清理{}: 扫描 {} 个文件，清理 {} 个，跳过受保护 {} 个，释放 {} 字节",
+            if dry_run { "（试运行）" } else { "" },
+            result.files_scanned,
+            result.pruned_files.len(),
+            result.files_protected,
+            result.bytes_reclaimed
+        );
+
+        Ok(result)
+    }
+
+    /// 检查文件中是否包含指定的 Flow
+    fn file_contains_any(path: &Path, ids: &HashSet<String>) -> Result<bool> {
+        if ids.is_empty() {
+            return Ok(false);
+        }
+
+        #[derive(Deserialize)]
+        struct FlowId {
+            id: String,
+        }
+
+        let reader = BufReader::new(File::open(path)?);
+        for line in reader.lines() {
+            let line = line?;
+            if let Ok(flow) = serde_json::from_str::<FlowId>(&line) {
+                if ids.contains(&flow.id) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
     /// 根据保留天数清理
     pub fn cleanup_by_retention(&self) -> Result<CleanupResult> {
         let retention_days = self.rotation_config.retention_days;
@@ -1029,6 +1196,38 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn test_file_store_for_each_matching() {
+        let temp_dir = TempDir::new().unwrap();
+        let store =
+            FlowFileStore::new(temp_dir.path().to_path_buf(), RotationConfig::default()).unwrap();
+        for i in 0..5 {
+            let provider = if i % 2 == 0 {
+                ProviderType::OpenAI
+            } else {
+                ProviderType::Claude
+            };
+            store
+                .write(&create_test_flow(&format!("flow-{}", i), "gpt-4", provider))
+                .unwrap();
+        }
+
+        let filter = FlowFilter {
+            providers: Some(vec![ProviderType::OpenAI]),
+            ..Default::default()
+        };
+        let mut ids = Vec::new();
+        let matched = store
+            .for_each_matching(&filter, |flow| {
+                ids.push(flow.id);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(matched, 3);
+        ids.sort();
+        assert_eq!(ids, vec!["flow-0", "flow-2", "flow-4"]);
+    }
+
     #[test]
     fn test_file_store_rotation() {
         let temp_dir = TempDir::new().unwrap();
@@ -1073,6 +1272,60 @@ mod tests {
         assert_eq!(store.count().unwrap(), 0);
     }
 
+    #[test]
+    fn test_prune_orphan_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let store =
+            FlowFileStore::new(temp_dir.path().to_path_buf(), RotationConfig::default()).unwrap();
+
+        let flow = create_test_flow("indexed", "gpt-4", ProviderType::OpenAI);
+        store.write(&flow).unwrap();
+
+        // 构造两个没有索引引用的旧文件，其中一个包含受保护的 Flow
+        let old_dir = temp_dir.path().join("2020-01-01");
+        fs::create_dir_all(&old_dir).unwrap();
+        let orphan = old_dir.join("flows_001.jsonl");
+        let protected = old_dir.join("flows_002.jsonl");
+        let write_flow = |path: &Path, id: &str| {
+            let flow = create_test_flow(id, "gpt-4", ProviderType::OpenAI);
+            fs::write(path, format!("{}\n", serde_json::to_string(&flow).unwrap())).unwrap();
+        };
+        write_flow(&orphan, "orphan");
+        write_flow(&protected, "bookmarked");
+        let orphan_size = fs::metadata(&orphan).unwrap().len();
+
+        let protected_ids: HashSet<String> = ["bookmarked".to_string()].into_iter().collect();
+        let future = Utc::now() + chrono::Duration::hours(1);
+
+        // 试运行不删除文件
+        let dry = store
+            .prune_orphan_files(future, &protected_ids, true)
+            .unwrap();
+        assert_eq!(dry.files_scanned, 3);
+        assert_eq!(dry.pruned_files.len(), 1);
+        assert_eq!(dry.files_protected, 1);
+        assert_eq!(dry.bytes_reclaimed, orphan_size);
+        assert!(orphan.exists());
+
+        let result = store
+            .prune_orphan_files(future, &protected_ids, false)
+            .unwrap();
+        assert_eq!(result.pruned_files.len(), 1);
+        assert!(!orphan.exists());
+        assert!(protected.exists());
+        assert!(store.get("indexed").unwrap().is_some());
+
+        // 未超过保留时间的孤立文件不会被删除
+        let recent = store
+            .prune_orphan_files(
+                Utc::now() - chrono::Duration::hours(1),
+                &HashSet::new(),
+                true,
+            )
+            .unwrap();
+        assert!(recent.pruned_files.is_empty());
+    }
+
     #[test]
     fn test_index_record_from_flow() {
         let flow = create_test_flow("test-1", "gpt-4", ProviderType::OpenAI);
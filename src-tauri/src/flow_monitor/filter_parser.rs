@@ -23,9 +23,11 @@
 //! - `|`: OR 逻辑
 //! - `!`: NOT 逻辑
 //! - `()`: 分组
+//! - `$name`: 引用已保存的过滤变量
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 use thiserror::Error;
 
@@ -81,6 +83,18 @@ pub enum FilterParseError {
     /// 空表达式
     #[error("空表达式")]
     EmptyExpression,
+
+    /// 无效的变量名
+    #[error("无效的变量名 '{0}'，只能包含字母、数字和下划线，且不能以数字开头")]
+    InvalidVariableName(String),
+
+    /// 未定义的变量
+    #[error("未定义的变量 '${0}'")]
+    UndefinedVariable(String),
+
+    /// 变量循环引用
+    #[error("变量 '${0}' 存在循环引用")]
+    RecursiveVariable(String),
 }
 
 // ============================================================================
@@ -690,6 +704,58 @@ impl FilterParser {
         Ok(())
     }
 
+    /// 展开变量后解析过滤表达式
+    pub fn parse_with_variables(
+        input: &str,
+        variables: &HashMap<String, String>,
+    ) -> Result<FilterExpr, FilterParseError> {
+        let expanded = Self::expand_variables(input, variables)?;
+        Self::parse(&expanded)
+    }
+
+    /// 展开变量后验证表达式语法（同时检查引用的变量是否存在）
+    pub fn validate_with_variables(
+        input: &str,
+        variables: &HashMap<String, String>,
+    ) -> Result<(), FilterParseError> {
+        Self::parse_with_variables(input, variables)?;
+        Ok(())
+    }
+
+    /// 展开表达式中的 `$name` 变量引用
+    ///
+    /// 每个引用替换为加括号的变量表达式，变量之间可以互相引用；
+    /// 引号内的 `$` 不视为变量引用。
+    pub fn expand_variables(
+        input: &str,
+        variables: &HashMap<String, String>,
+    ) -> Result<String, FilterParseError> {
+        expand_variables_inner(input, variables, &mut Vec::new())
+    }
+
+    /// 获取表达式直接引用的变量名（去重，保持出现顺序）
+    pub fn referenced_variables(input: &str) -> Result<Vec<String>, FilterParseError> {
+        let mut names: Vec<String> = Vec::new();
+        replace_variable_refs(input, |name| {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+            Ok(String::new())
+        })?;
+        Ok(names)
+    }
+
+    /// 检查变量名是否有效
+    pub fn is_valid_variable_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            }
+            _ => false,
+        }
+    }
+
     /// 将 FilterExpr 编译为可执行的过滤函数
     pub fn compile(expr: &FilterExpr) -> Box<dyn Fn(&LLMFlow) -> bool + Send + Sync> {
         let expr = expr.clone();
@@ -864,6 +930,84 @@ impl FilterParser {
     }
 }
 
+// ============================================================================
+// 变量展开
+// ============================================================================
+
+/// 递归展开变量，`stack` 记录当前展开链用于检测循环引用
+fn expand_variables_inner(
+    input: &str,
+    variables: &HashMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Result<String, FilterParseError> {
+    replace_variable_refs(input, |name| {
+        if stack.iter().any(|n| n == name) {
+            return Err(FilterParseError::RecursiveVariable(name.to_string()));
+        }
+        let body = variables
+            .get(name)
+            .ok_or_else(|| FilterParseError::UndefinedVariable(name.to_string()))?;
+
+        stack.push(name.to_string());
+        let expanded = expand_variables_inner(body, variables, stack)?;
+        stack.pop();
+
+        Ok(format!("({})", expanded.trim()))
+    })
+}
+
+/// 扫描表达式中的 `$name` 引用并用 `replace` 的返回值替换
+fn replace_variable_refs<F>(input: &str, mut replace: F) -> Result<String, FilterParseError>
+where
+    F: FnMut(&str) -> Result<String, FilterParseError>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.char_indices().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some((pos, c)) = chars.next() {
+        if let Some(q) = quote {
+            output.push(c);
+            if c == '\\' {
+                if let Some((_, escaped)) = chars.next() {
+                    output.push(escaped);
+                }
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                output.push(c);
+            }
+            '$' => {
+                let mut name = String::new();
+                while let Some(&(_, n)) = chars.peek() {
+                    if n.is_ascii_alphanumeric() || n == '_' {
+                        name.push(n);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if name.is_empty() {
+                    return Err(FilterParseError::UnexpectedChar('$', pos));
+                }
+                if !FilterParser::is_valid_variable_name(&name) {
+                    return Err(FilterParseError::InvalidVariableName(name));
+                }
+                output.push_str(&replace(&name)?);
+            }
+            _ => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
 // ============================================================================
 // 帮助信息
 // ============================================================================
@@ -890,6 +1034,7 @@ pub const FILTER_HELP: &[(&str, &str)] = &[
     ("|", "OR 逻辑"),
     ("!", "NOT 逻辑"),
     ("()", "分组"),
+    ("$name", "引用已保存的过滤变量"),
 ];
 
 /// 获取帮助文本
@@ -904,6 +1049,7 @@ pub fn get_filter_help() -> String {
     help.push_str("  ~e | ~latency >5s      有错误或延迟超过 5 秒\n");
     help.push_str("  !~e                    没有错误\n");
     help.push_str("  (~p kiro | ~p gemini) & ~tokens >1000\n");
+    help.push_str("  $myhosts & ~e          引用变量 myhosts 且有错误\n");
     help
 }
 
@@ -1250,6 +1396,63 @@ mod tests {
         let reparsed = FilterParser::parse(&display).unwrap();
         assert_eq!(format!("{}", expr), format!("{}", reparsed));
     }
+
+    fn test_variables() -> HashMap<String, String> {
+        let mut variables = HashMap::new();
+        variables.insert("myhosts".to_string(), "~p kiro | ~p gemini".to_string());
+        variables.insert(
+            "slow_hosts".to_string(),
+            "$myhosts & ~latency >5s".to_string(),
+        );
+        variables
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        let variables = test_variables();
+
+        let expanded = FilterParser::expand_variables("$slow_hosts & ~e", &variables).unwrap();
+        assert_eq!(expanded, "((~p kiro | ~p gemini) & ~latency >5s) & ~e");
+
+        // 引号内的 $ 不展开
+        let quoted = FilterParser::expand_variables("~b \"$myhosts\"", &variables).unwrap();
+        assert_eq!(quoted, "~b \"$myhosts\"");
+
+        let flow = create_test_flow("claude-3", ProviderType::Gemini);
+        let expr = FilterParser::parse_with_variables("$myhosts & !~e", &variables).unwrap();
+        assert!(FilterParser::compile(&expr)(&flow));
+    }
+
+    #[test]
+    fn test_expand_variables_errors() {
+        let mut variables = test_variables();
+
+        assert_eq!(
+            FilterParser::validate_with_variables("$missing", &variables),
+            Err(FilterParseError::UndefinedVariable("missing".to_string()))
+        );
+        assert!(matches!(
+            FilterParser::parse("$myhosts"),
+            Err(FilterParseError::UnexpectedChar('$', 0))
+        ));
+
+        variables.insert("a".to_string(), "$b".to_string());
+        variables.insert("b".to_string(), "~e | $a".to_string());
+        assert!(matches!(
+            FilterParser::validate_with_variables("$a", &variables),
+            Err(FilterParseError::RecursiveVariable(_))
+        ));
+    }
+
+    #[test]
+    fn test_referenced_variables() {
+        let names = FilterParser::referenced_variables("$a & ($b | $a) & ~b '$c'").unwrap();
+        assert_eq!(names, vec!["a".to_string(), "b".to_string()]);
+
+        assert!(FilterParser::is_valid_variable_name("my_hosts2"));
+        assert!(!FilterParser::is_valid_variable_name("2hosts"));
+        assert!(!FilterParser::is_valid_variable_name("my-hosts"));
+    }
 }
 
 // ============================================================================
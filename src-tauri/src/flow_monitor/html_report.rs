@@ -0,0 +1,613 @@
+//! HTML 统计报告
+//!
+//! 把增强统计渲染为单个自包含的 HTML 文件：样式内联，图表预先渲染为 SVG，
+//! 不引用任何外部脚本、字体或图片，可以直接在浏览器中打开，也可以作为邮件附件发送。
+//!
+//! **Validates: Requirements 9.7**
+
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+use super::enhanced_stats::{
+    Distribution, LatencyPercentilePoint, LatencyPercentileSeries, StatsTimeRange, TrendData,
+};
+
+/// 图表宽度（SVG 坐标）
+const CHART_WIDTH: f64 = 720.0;
+/// 图表高度（SVG 坐标）
+const CHART_HEIGHT: f64 = 220.0;
+/// 左侧坐标轴留白
+const AXIS_LEFT: f64 = 56.0;
+/// 底部标签留白
+const AXIS_BOTTOM: f64 = 28.0;
+/// 顶部留白
+const AXIS_TOP: f64 = 12.0;
+/// 横轴最多显示的标签数
+const MAX_X_LABELS: usize = 8;
+
+const COLOR_PRIMARY: &str = "#2563eb";
+const COLOR_P50: &str = "#16a34a";
+const COLOR_P90: &str = "#f59e0b";
+const COLOR_P99: &str = "#dc2626";
+
+/// 按模型汇总的用量
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelUsage {
+    /// 模型名称
+    pub model: String,
+    /// 请求数
+    pub requests: u64,
+    /// 输入 Token 数
+    pub input_tokens: u64,
+    /// 输出 Token 数
+    pub output_tokens: u64,
+    /// 缓存读取 Token 数
+    pub cache_read_tokens: u64,
+    /// 缓存写入 Token 数
+    pub cache_write_tokens: u64,
+    /// 估算费用（美元），模型没有价格时为 None
+    pub cost_usd: Option<f64>,
+}
+
+impl ModelUsage {
+    /// 总 Token 数
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_read_tokens + self.cache_write_tokens
+    }
+}
+
+/// HTML 报告数据
+#[derive(Debug, Clone)]
+pub struct HtmlReport {
+    /// 时间范围
+    pub time_range: StatsTimeRange,
+    /// 生成时间
+    pub generated_at: DateTime<Utc>,
+    /// 请求数
+    pub total_requests: u64,
+    /// 成功完成的请求数
+    pub completed_requests: u64,
+    /// 请求速率（每秒）
+    pub request_rate: f64,
+    /// 全部已结束请求的 p50 延迟（毫秒）
+    pub latency_p50: Option<u64>,
+    /// 全部已结束请求的 p90 延迟（毫秒）
+    pub latency_p90: Option<u64>,
+    /// 全部已结束请求的 p99 延迟（毫秒）
+    pub latency_p99: Option<u64>,
+    /// 请求趋势
+    pub request_trend: TrendData,
+    /// 延迟百分位时间序列
+    pub latency_percentiles: LatencyPercentileSeries,
+    /// 按模型的用量和费用（按 Token 数降序）
+    pub model_usage: Vec<ModelUsage>,
+    /// 按提供商的成功率
+    pub success_by_provider: Vec<(String, f64)>,
+    /// 延迟直方图
+    pub latency_histogram: Distribution,
+    /// 错误分布
+    pub error_distribution: Distribution,
+}
+
+impl HtmlReport {
+    /// 渲染为完整的 HTML 文档
+    pub fn render(&self) -> String {
+        let mut html = String::with_capacity(32 * 1024);
+        let range = format!(
+            "{} - {} (UTC)",
+            self.time_range.start.format("%Y-%m-%d %H:%M"),
+            self.time_range.end.format("%Y-%m-%d %H:%M")
+        );
+
+        html.push_str("<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        let _ = writeln!(html, "<title>ProxyCast 统计报告 {}</title>", escape(&range));
+        let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+
+        let _ = writeln!(
+            html,
+            "<header><h1>ProxyCast 统计报告</h1><p class=\"muted\">时间范围：{}<br>生成时间：{}</p></header>",
+            escape(&range),
+            self.generated_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+
+        self.render_summary(&mut html);
+
+        html.push_str("<section><h2>请求趋势</h2>");
+        let trend: Vec<(String, f64)> = self
+            .request_trend
+            .points
+            .iter()
+            .map(|p| (p.timestamp.format("%m-%d %H:%M").to_string(), p.value))
+            .collect();
+        html.push_str(&bar_chart(&trend, COLOR_PRIMARY));
+        html.push_str("</section>\n");
+
+        html.push_str("<section><h2>延迟百分位（毫秒）</h2>");
+        let labels: Vec<String> = self
+            .latency_percentiles
+            .points
+            .iter()
+            .map(|p| p.timestamp.format("%m-%d %H:%M").to_string())
+            .collect();
+        let series = |f: fn(&LatencyPercentilePoint) -> Option<u64>| {
+            self.latency_percentiles
+                .points
+                .iter()
+                .map(|p| f(p).map(|v| v as f64))
+                .collect::<Vec<_>>()
+        };
+        html.push_str(&line_chart(
+            &labels,
+            &[
+                ("p50", COLOR_P50, series(|p| p.p50)),
+                ("p90", COLOR_P90, series(|p| p.p90)),
+                ("p99", COLOR_P99, series(|p| p.p99)),
+            ],
+        ));
+        html.push_str("</section>\n");
+
+        self.render_usage(&mut html);
+
+        html.push_str("<section><h2>延迟分布</h2>");
+        let histogram: Vec<(String, f64)> = self
+            .latency_histogram
+            .buckets
+            .iter()
+            .map(|(label, count)| (label.clone(), *count as f64))
+            .collect();
+        html.push_str(&bar_chart(&histogram, COLOR_PRIMARY));
+        html.push_str("</section>\n");
+
+        html.push_str("<section><h2>成功率（按提供商）</h2>");
+        if self.success_by_provider.is_empty() {
+            html.push_str(EMPTY);
+        } else {
+            html.push_str("<table><thead><tr><th>提供商</th><th class=\"num\">成功率</th></tr></thead><tbody>");
+            for (provider, rate) in &self.success_by_provider {
+                let _ = write!(
+                    html,
+                    "<tr><td>{}</td><td class=\"num\">{:.1}%</td></tr>",
+                    escape(provider),
+                    rate * 100.0
+                );
+            }
+            html.push_str("</tbody></table>");
+        }
+        html.push_str("</section>\n");
+
+        if !self.error_distribution.buckets.is_empty() {
+            html.push_str("<section><h2>错误分布</h2><table><thead><tr><th>错误类型</th><th class=\"num\">数量</th></tr></thead><tbody>");
+            for (error_type, count) in &self.error_distribution.buckets {
+                let _ = write!(
+                    html,
+                    "<tr><td>{}</td><td class=\"num\">{}</td></tr>",
+                    escape(error_type),
+                    count
+                );
+            }
+            html.push_str("</tbody></table></section>\n");
+        }
+
+        html.push_str("<footer class=\"muted\">费用按价格表估算，仅供参考；“-” 表示模型没有价格。</footer>\n</body>\n</html>\n");
+        html
+    }
+
+    /// 概览卡片
+    fn render_summary(&self, html: &mut String) {
+        let success_rate = if self.total_requests > 0 {
+            format!(
+                "{:.1}%",
+                self.completed_requests as f64 / self.total_requests as f64 * 100.0
+            )
+        } else {
+            "-".to_string()
+        };
+        let total_tokens: u64 = self.model_usage.iter().map(ModelUsage::total_tokens).sum();
+        let cards = [
+            ("请求数", self.total_requests.to_string()),
+            ("成功率", success_rate),
+            ("请求速率", format!("{:.2}/s", self.request_rate)),
+            ("p50 延迟", format_latency(self.latency_p50)),
+            ("p90 延迟", format_latency(self.latency_p90)),
+            ("p99 延迟", format_latency(self.latency_p99)),
+            ("总 Token", format_count(total_tokens as f64)),
+            ("估算费用", format_cost(self.total_cost_usd())),
+        ];
+
+        html.push_str("<section class=\"cards\">");
+        for (label, value) in cards {
+            let _ = write!(
+                html,
+                "<div class=\"card\"><div class=\"muted\">{}</div><div class=\"value\">{}</div></div>",
+                label,
+                escape(&value)
+            );
+        }
+        html.push_str("</section>\n");
+    }
+
+    /// Token 用量和费用
+    fn render_usage(&self, html: &mut String) {
+        html.push_str("<section><h2>Token 用量与费用（按模型）</h2>");
+        if self.model_usage.is_empty() {
+            html.push_str(EMPTY);
+            html.push_str("</section>\n");
+            return;
+        }
+
+        let bars: Vec<(String, f64)> = self
+            .model_usage
+            .iter()
+            .map(|u| (u.model.clone(), u.total_tokens() as f64))
+            .collect();
+        html.push_str(&horizontal_bar_chart(&bars, COLOR_PRIMARY));
+
+        html.push_str(
+            "<table><thead><tr><th>模型</th><th class=\"num\">请求数</th><th class=\"num\">输入</th>\
+             <th class=\"num\">输出</th><th class=\"num\">缓存读取</th><th class=\"num\">缓存写入</th>\
+             <th class=\"num\">费用 (USD)</th></tr></thead><tbody>",
+        );
+        for usage in &self.model_usage {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                 <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape(&usage.model),
+                usage.requests,
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.cache_read_tokens,
+                usage.cache_write_tokens,
+                format_cost(usage.cost_usd)
+            );
+        }
+        let _ = write!(
+            html,
+            "<tr class=\"total\"><td>总计</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            self.model_usage.iter().map(|u| u.requests).sum::<u64>(),
+            self.model_usage.iter().map(|u| u.input_tokens).sum::<u64>(),
+            self.model_usage.iter().map(|u| u.output_tokens).sum::<u64>(),
+            self.model_usage.iter().map(|u| u.cache_read_tokens).sum::<u64>(),
+            self.model_usage.iter().map(|u| u.cache_write_tokens).sum::<u64>(),
+            format_cost(self.total_cost_usd())
+        );
+        html.push_str("</tbody></table></section>\n");
+    }
+
+    /// 有价格的模型的费用之和，所有模型都没有价格时为 None
+    fn total_cost_usd(&self) -> Option<f64> {
+        self.model_usage
+            .iter()
+            .filter_map(|u| u.cost_usd)
+            .fold(None, |total, cost| Some(total.unwrap_or(0.0) + cost))
+    }
+}
+
+const EMPTY: &str = "<p class=\"muted\">暂无数据</p>";
+
+const STYLE: &str = "\
+body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Roboto,'PingFang SC','Microsoft YaHei',sans-serif;\
+color:#111827;background:#f9fafb;max-width:800px;margin:0 auto;padding:24px;}\
+h1{font-size:24px;margin:0 0 4px;}h2{font-size:16px;margin:0 0 12px;}\
+section{background:#fff;border:1px solid #e5e7eb;border-radius:8px;padding:16px;margin:16px 0;}\
+.muted{color:#6b7280;font-size:13px;}\
+.cards{display:grid;grid-template-columns:repeat(4,1fr);gap:8px;background:none;border:none;padding:0;}\
+.card{background:#fff;border:1px solid #e5e7eb;border-radius:8px;padding:12px;}\
+.value{font-size:20px;font-weight:600;margin-top:4px;}\
+table{width:100%;border-collapse:collapse;font-size:13px;margin-top:8px;}\
+th,td{padding:6px 8px;border-bottom:1px solid #e5e7eb;text-align:left;}\
+.num{text-align:right;font-variant-numeric:tabular-nums;}\
+tr.total td{font-weight:600;}\
+svg{width:100%;height:auto;font-size:10px;}\
+footer{margin-top:16px;}";
+
+/// 转义 HTML 文本和属性值
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_latency(latency: Option<u64>) -> String {
+    latency.map_or_else(|| "-".to_string(), |ms| format!("{} ms", ms))
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map_or_else(|| "-".to_string(), |cost| format!("${:.4}", cost))
+}
+
+/// 紧凑的数字格式（用于坐标轴和卡片）
+fn format_count(value: f64) -> String {
+    if value >= 1_000_000.0 {
+        format!("{:.1}M", value / 1_000_000.0)
+    } else if value >= 1_000.0 {
+        format!("{:.1}K", value / 1_000.0)
+    } else {
+        format!("{}", value.round())
+    }
+}
+
+/// 纵轴最大值和横轴标签间隔
+fn chart_scale(max: f64, count: usize) -> (f64, usize) {
+    let max = if max > 0.0 { max } else { 1.0 };
+    (max, count.div_ceil(MAX_X_LABELS).max(1))
+}
+
+/// 图表外框、纵轴刻度
+fn chart_frame(svg: &mut String, max: f64) {
+    let _ = write!(
+        svg,
+        "<svg viewBox=\"0 0 {w} {h}\" xmlns=\"http://www.w3.org/2000/svg\" role=\"img\">\
+         <line x1=\"{l}\" y1=\"{b}\" x2=\"{w}\" y2=\"{b}\" stroke=\"#9ca3af\"/>\
+         <line x1=\"{l}\" y1=\"{t}\" x2=\"{w}\" y2=\"{t}\" stroke=\"#e5e7eb\"/>\
+         <text x=\"{lx}\" y=\"{ty}\" text-anchor=\"end\" fill=\"#6b7280\">{max}</text>\
+         <text x=\"{lx}\" y=\"{b}\" text-anchor=\"end\" fill=\"#6b7280\">0</text>",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        l = AXIS_LEFT,
+        t = AXIS_TOP,
+        b = CHART_HEIGHT - AXIS_BOTTOM,
+        lx = AXIS_LEFT - 6.0,
+        ty = AXIS_TOP + 4.0,
+        max = format_count(max),
+    );
+}
+
+fn x_label(svg: &mut String, x: f64, label: &str) {
+    let _ = write!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"#6b7280\">{}</text>",
+        x,
+        CHART_HEIGHT - AXIS_BOTTOM + 16.0,
+        escape(label)
+    );
+}
+
+/// 纵向柱状图
+fn bar_chart(bars: &[(String, f64)], color: &str) -> String {
+    if bars.is_empty() {
+        return EMPTY.to_string();
+    }
+    let (max, label_step) =
+        chart_scale(bars.iter().map(|(_, v)| *v).fold(0.0, f64::max), bars.len());
+    let plot_height = CHART_HEIGHT - AXIS_BOTTOM - AXIS_TOP;
+    let slot = (CHART_WIDTH - AXIS_LEFT) / bars.len() as f64;
+    let width = (slot * 0.8).max(1.0);
+
+    let mut svg = String::new();
+    chart_frame(&mut svg, max);
+    for (i, (label, value)) in bars.iter().enumerate() {
+        let height = value / max * plot_height;
+        let x = AXIS_LEFT + slot * i as f64 + (slot - width) / 2.0;
+        let _ = write!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{}: {}</title></rect>",
+            x,
+            CHART_HEIGHT - AXIS_BOTTOM - height,
+            width,
+            height,
+            color,
+            escape(label),
+            value
+        );
+        if i % label_step == 0 {
+            x_label(&mut svg, x + width / 2.0, label);
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// 横向柱状图（标签较长时使用）
+fn horizontal_bar_chart(bars: &[(String, f64)], color: &str) -> String {
+    const ROW_HEIGHT: f64 = 22.0;
+    const LABEL_WIDTH: f64 = 220.0;
+    const VALUE_WIDTH: f64 = 60.0;
+
+    let max = bars.iter().map(|(_, v)| *v).fold(0.0, f64::max).max(1.0);
+    let plot_width = CHART_WIDTH - LABEL_WIDTH - VALUE_WIDTH;
+    let height = ROW_HEIGHT * bars.len() as f64;
+
+    let mut svg = format!(
+        "<svg viewBox=\"0 0 {} {}\" xmlns=\"http://www.w3.org/2000/svg\" role=\"img\">",
+        CHART_WIDTH, height
+    );
+    for (i, (label, value)) in bars.iter().enumerate() {
+        let y = ROW_HEIGHT * i as f64;
+        let width = value / max * plot_width;
+        let _ = write!(
+            svg,
+            "<text x=\"{lx:.1}\" y=\"{ty:.1}\" text-anchor=\"end\" fill=\"#374151\">{label}</text>\
+             <rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{width:.1}\" height=\"{h:.1}\" fill=\"{color}\"/>\
+             <text x=\"{vx:.1}\" y=\"{ty:.1}\" fill=\"#6b7280\">{value}</text>",
+            lx = LABEL_WIDTH - 8.0,
+            ty = y + ROW_HEIGHT * 0.65,
+            label = escape(label),
+            x = LABEL_WIDTH,
+            y = y + 3.0,
+            width = width,
+            h = ROW_HEIGHT - 6.0,
+            color = color,
+            vx = LABEL_WIDTH + width + 6.0,
+            value = format_count(*value),
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// 折线图，值为 None 的点断开折线
+fn line_chart(labels: &[String], series: &[(&str, &str, Vec<Option<f64>>)]) -> String {
+    if labels.is_empty() {
+        return EMPTY.to_string();
+    }
+    let max = series
+        .iter()
+        .flat_map(|(_, _, values)| values.iter().flatten().copied())
+        .fold(0.0, f64::max);
+    let (max, label_step) = chart_scale(max, labels.len());
+    let plot_height = CHART_HEIGHT - AXIS_BOTTOM - AXIS_TOP;
+    let slot = (CHART_WIDTH - AXIS_LEFT) / labels.len() as f64;
+    let x_of = |i: usize| AXIS_LEFT + slot * (i as f64 + 0.5);
+
+    let mut svg = String::new();
+    chart_frame(&mut svg, max);
+    for (i, label) in labels.iter().enumerate() {
+        if i % label_step == 0 {
+            x_label(&mut svg, x_of(i), label);
+        }
+    }
+
+    for (index, (name, color, values)) in series.iter().enumerate() {
+        let mut segment: Vec<String> = Vec::new();
+        let mut segments = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            match value {
+                Some(value) => segment.push(format!(
+                    "{:.1},{:.1}",
+                    x_of(i),
+                    CHART_HEIGHT - AXIS_BOTTOM - value / max * plot_height
+                )),
+                None if !segment.is_empty() => segments.push(std::mem::take(&mut segment)),
+                None => {}
+            }
+        }
+        if !segment.is_empty() {
+            segments.push(segment);
+        }
+        for points in segments {
+            if points.len() == 1 {
+                let (x, y) = points[0].split_once(',').unwrap_or(("0", "0"));
+                let _ = write!(
+                    svg,
+                    "<circle cx=\"{x}\" cy=\"{y}\" r=\"2\" fill=\"{color}\"/>"
+                );
+            } else {
+                let _ = write!(
+                    svg,
+                    "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/>",
+                    points.join(" "),
+                    color
+                );
+            }
+        }
+
+        // 图例
+        let legend_x = AXIS_LEFT + 12.0 + index as f64 * 56.0;
+        let _ = write!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"10\" height=\"3\" fill=\"{color}\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\" fill=\"#374151\">{name}</text>",
+            legend_x,
+            AXIS_TOP + 4.0,
+            legend_x + 14.0,
+            AXIS_TOP + 8.0,
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_monitor::enhanced_stats::LatencyBucket;
+
+    fn report() -> HtmlReport {
+        let time_range = StatsTimeRange::default();
+        HtmlReport {
+            generated_at: time_range.end,
+            total_requests: 3,
+            completed_requests: 2,
+            request_rate: 0.0,
+            latency_p50: Some(120),
+            latency_p90: Some(800),
+            latency_p99: Some(800),
+            request_trend: TrendData::default(),
+            latency_percentiles: LatencyPercentileSeries {
+                bucket: LatencyBucket::Hour,
+                points: vec![
+                    LatencyPercentilePoint {
+                        timestamp: time_range.start,
+                        count: 2,
+                        p50: Some(120),
+                        p90: Some(800),
+                        p99: Some(800),
+                    },
+                    LatencyPercentilePoint {
+                        timestamp: time_range.end,
+                        count: 0,
+                        p50: None,
+                        p90: None,
+                        p99: None,
+                    },
+                ],
+            },
+            model_usage: vec![
+                ModelUsage {
+                    model: "claude-<script>".to_string(),
+                    requests: 2,
+                    input_tokens: 1000,
+                    output_tokens: 500,
+                    cost_usd: Some(0.25),
+                    ..Default::default()
+                },
+                ModelUsage {
+                    model: "unknown".to_string(),
+                    requests: 1,
+                    input_tokens: 10,
+                    ..Default::default()
+                },
+            ],
+            success_by_provider: vec![("Claude".to_string(), 0.5)],
+            latency_histogram: Distribution::default(),
+            error_distribution: Distribution::default(),
+            time_range,
+        }
+    }
+
+    #[test]
+    fn test_render_is_self_contained() {
+        let html = report().render();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("src=\""));
+        assert!(html.contains("claude-&lt;script&gt;"));
+        // 空桶断开折线，孤立的点画成圆点
+        assert!(html.contains("<circle"));
+        assert!(!html.contains("<polyline"));
+        // 没有价格的模型不计入总费用
+        assert!(html.contains("$0.2500</td></tr></tbody>"));
+        assert!(html.contains("66.7%"));
+    }
+
+    #[test]
+    fn test_empty_report_renders_placeholders() {
+        let html = HtmlReport {
+            total_requests: 0,
+            completed_requests: 0,
+            latency_percentiles: LatencyPercentileSeries {
+                bucket: LatencyBucket::Hour,
+                points: Vec::new(),
+            },
+            model_usage: Vec::new(),
+            success_by_provider: Vec::new(),
+            ..report()
+        }
+        .render();
+        assert!(html.contains(EMPTY));
+        assert!(!html.contains("<svg"));
+    }
+}
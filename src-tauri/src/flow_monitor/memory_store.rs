@@ -5,7 +5,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, RwLock};
 
 use super::models::{FlowState, FlowType, LLMFlow};
@@ -358,6 +358,8 @@ pub struct FlowMemoryStore {
     ordered_ids: VecDeque<String>,
     /// 最大缓存大小
     max_size: usize,
+    /// 已持久化到文件存储的 Flow ID
+    persisted_ids: HashSet<String>,
 }
 
 impl FlowMemoryStore {
@@ -370,6 +372,7 @@ impl FlowMemoryStore {
             flows: HashMap::with_capacity(max_size),
             ordered_ids: VecDeque::with_capacity(max_size),
             max_size,
+            persisted_ids: HashSet::new(),
         }
     }
 
@@ -402,6 +405,7 @@ impl FlowMemoryStore {
         // 如果已存在，先移除旧的
         if self.flows.contains_key(&id) {
             self.ordered_ids.retain(|i| i != &id);
+            self.persisted_ids.remove(&id);
             eprintln!("[MEMORY_STORE] 移除旧的 Flow: id={}", id);
         }
 
@@ -503,6 +507,7 @@ impl FlowMemoryStore {
     pub fn remove(&mut self, id: &str) -> bool {
         if self.flows.remove(id).is_some() {
             self.ordered_ids.retain(|i| i != id);
+            self.persisted_ids.remove(id);
             true
         } else {
             false
@@ -513,6 +518,7 @@ impl FlowMemoryStore {
     pub fn clear(&mut self) {
         self.flows.clear();
         self.ordered_ids.clear();
+        self.persisted_ids.clear();
     }
 
     /// 按时间清理 Flow
@@ -541,6 +547,7 @@ impl FlowMemoryStore {
         for id in to_remove {
             self.flows.remove(&id);
             self.ordered_ids.retain(|i| i != &id);
+            self.persisted_ids.remove(&id);
         }
 
         count
@@ -550,6 +557,7 @@ impl FlowMemoryStore {
     fn evict_oldest(&mut self) {
         if let Some(oldest_id) = self.ordered_ids.pop_front() {
             self.flows.remove(&oldest_id);
+            self.persisted_ids.remove(&oldest_id);
         }
     }
 
@@ -562,6 +570,37 @@ impl FlowMemoryStore {
     pub fn contains(&self, id: &str) -> bool {
         self.flows.contains_key(id)
     }
+
+    /// 标记 Flow 已持久化到文件存储
+    ///
+    /// # 返回
+    /// - `true`: 标记成功
+    /// - `false`: Flow 不在内存中
+    pub fn mark_persisted(&mut self, id: &str) -> bool {
+        if self.flows.contains_key(id) {
+            self.persisted_ids.insert(id.to_string());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 检查 Flow 是否已持久化
+    pub fn is_persisted(&self, id: &str) -> bool {
+        self.persisted_ids.contains(id)
+    }
+
+    /// 移除所有已持久化的 Flow
+    ///
+    /// # 返回
+    /// 移除的 Flow 数量
+    pub fn prune_persisted(&mut self) -> usize {
+        let before = self.flows.len();
+        let persisted = std::mem::take(&mut self.persisted_ids);
+        self.flows.retain(|id, _| !persisted.contains(id));
+        self.ordered_ids.retain(|id| !persisted.contains(id));
+        before - self.flows.len()
+    }
 }
 
 // ============================================================================
@@ -842,6 +881,25 @@ mod tests {
         assert!(!range.contains(50));
         assert!(!range.contains(1500));
     }
+
+    #[test]
+    fn test_prune_persisted() {
+        let mut store = FlowMemoryStore::new(10);
+        store.add(create_test_flow("flow-1", "gpt-4", ProviderType::OpenAI));
+        store.add(create_test_flow("flow-2", "gpt-4", ProviderType::OpenAI));
+        store.add(create_test_flow("flow-3", "gpt-4", ProviderType::OpenAI));
+
+        assert!(store.mark_persisted("flow-1"));
+        assert!(store.mark_persisted("flow-3"));
+        assert!(!store.mark_persisted("missing"));
+        assert!(store.is_persisted("flow-1"));
+
+        assert_eq!(store.prune_persisted(), 2);
+        assert_eq!(store.len(), 1);
+        assert!(store.contains("flow-2"));
+        assert_eq!(store.get_all_ids(), vec!["flow-2".to_string()]);
+        assert!(!store.is_persisted("flow-1"));
+    }
 }
 
 // ============================================================================
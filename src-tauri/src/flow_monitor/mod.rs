@@ -13,7 +13,10 @@
 //! - `exporter`: 导出服务，支持 HAR、JSON、JSONL、Markdown、CSV 格式
 //! - `monitor`: 核心监控服务
 //! - `filter_parser`: 高级过滤表达式解析器，支持类似 mitmproxy 的语法
+//! - `alerts`: 基于窗口指标的异常告警评估器
+//! - `html_report`: 自包含的 HTML 统计报告渲染
 
+pub mod alerts;
 pub mod batch_ops;
 pub mod bookmark;
 pub mod code_exporter;
@@ -22,6 +25,7 @@ pub mod enhanced_stats;
 pub mod exporter;
 pub mod file_store;
 pub mod filter_parser;
+pub mod html_report;
 pub mod interceptor;
 pub mod memory_store;
 pub mod models;
@@ -65,6 +69,7 @@ pub use models::{
     ToolCallDelta,
     ToolDefinition,
     ToolResult,
+    SESSION_ID_HEADERS,
 };
 
 // 重新导出流重建器
@@ -75,7 +80,8 @@ pub use memory_store::{FlowFilter, FlowMemoryStore, LatencyRange, TimeRange, Tok
 
 // 重新导出文件存储
 pub use file_store::{
-    CleanupResult, FileStoreError, FlowFileStore, FlowIndexRecord, FtsSearchResult, RotationConfig,
+    CleanupResult, FileStoreError, FlowFileStore, FlowIndexRecord, FtsSearchResult, PruneResult,
+    RotationConfig,
 };
 
 // 重新导出查询服务
@@ -87,15 +93,20 @@ pub use query_service::{
 // 重新导出导出服务
 pub use exporter::{
     default_redaction_rules, ExportFormat, ExportOptions, ExportResult, FlowExporter, HarArchive,
-    HarEntry, HarLlmExtension, HarLog, RedactionRule, Redactor,
+    HarEntry, HarLlmExtension, HarLog, NdjsonExportOptions, NdjsonExportSummary, NdjsonWriter,
+    RedactionRule, Redactor,
 };
 
 // 重新导出监控服务
 pub use monitor::{
-    FlowEvent, FlowMonitor, FlowMonitorConfig, FlowSummary, FlowUpdate, RequestRateTracker,
-    ThresholdCheckResult, ThresholdConfig,
+    AlertComparator, AlertEvent, AlertMetric, AlertRule, CaptureExclusionRule, CaptureFilter,
+    FlowEvent, FlowEventFilter, FlowMonitor, FlowMonitorConfig, FlowSummary, FlowUpdate,
+    RequestRateTracker, ThresholdCheckResult, ThresholdConfig,
 };
 
+// 重新导出异常告警评估器
+pub use alerts::AlertEvaluator;
+
 // 重新导出过滤表达式解析器
 pub use filter_parser::{
     get_filter_help, Comparison, ComparisonOp, FilterExpr, FilterParseError, FilterParser,
@@ -110,12 +121,14 @@ pub use interceptor::{
 
 // 重新导出重放器
 pub use replayer::{
-    BatchReplayResult, FlowReplayer, ReplayConfig, ReplayResult, ReplayerError, RequestModification,
+    BatchReplayResult, FlowReplayer, FuzzMutation, FuzzResult, ReplayConfig, ReplayResult,
+    ReplayerError, RequestModification,
 };
 
 // 重新导出差异对比器
 pub use diff::{
-    DiffConfig, DiffItem, DiffType, FlowDiff, FlowDiffResult, MessageDiffItem, TokenDiff,
+    DiffConfig, DiffItem, DiffType, FlowDiff, FlowDiffResult, MessageDiffItem, ShadowComparison,
+    ShadowResponse, TokenDiff,
 };
 
 // 重新导出会话管理器
@@ -125,8 +138,8 @@ pub use session::{
 
 // 重新导出快速过滤器管理器
 pub use quick_filter::{
-    QuickFilter, QuickFilterError, QuickFilterExport, QuickFilterManager, QuickFilterUpdate,
-    PRESET_FILTERS,
+    FilterVariable, QuickFilter, QuickFilterError, QuickFilterExport, QuickFilterManager,
+    QuickFilterUpdate, PRESET_FILTERS,
 };
 
 // 重新导出代码导出器
@@ -137,12 +150,14 @@ pub use bookmark::{BookmarkError, BookmarkExport, BookmarkManager, FlowBookmark}
 
 // 重新导出增强统计服务
 pub use enhanced_stats::{
-    Distribution, EnhancedStats, EnhancedStatsService, ReportFormat, StatsTimeRange,
-    TimeSeriesPoint, TrendData,
+    Distribution, EnhancedStats, EnhancedStatsService, LatencyBucket, LatencyPercentilePoint,
+    LatencyPercentileSeries, ReportFormat, StatsTimeRange, TimeSeriesPoint, TrendData,
+    WindowMetrics,
 };
+pub use html_report::{HtmlReport, ModelUsage};
 
 // 重新导出批量操作服务
-pub use batch_ops::{BatchOperation, BatchOperations, BatchOpsError, BatchResult};
+pub use batch_ops::{BatchItemResult, BatchOperation, BatchOperations, BatchOpsError, BatchResult};
 
 // 重新导出 ProviderType（从 lib.rs）
 pub use crate::ProviderType;
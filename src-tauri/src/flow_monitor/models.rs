@@ -6,6 +6,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::diff::ShadowComparison;
 use crate::ProviderType;
 
 // ============================================================================
@@ -36,6 +37,9 @@ pub struct LLMFlow {
     pub state: FlowState,
     /// 用户标记和注释
     pub annotations: FlowAnnotations,
+    /// 影子测试对比结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<ShadowComparison>,
 }
 
 impl LLMFlow {
@@ -65,6 +69,7 @@ impl LLMFlow {
             },
             state: FlowState::Pending,
             annotations: FlowAnnotations::default(),
+            shadow: None,
         }
     }
 }
@@ -591,8 +596,14 @@ pub struct ClientInfo {
     /// 请求 ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub request_id: Option<String>,
+    /// 客户端会话 ID（来自会话关联请求头）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
 }
 
+/// 用于关联客户端会话的请求头，按优先级排列
+pub const SESSION_ID_HEADERS: [&str; 3] = ["x-session-id", "x-conversation-id", "x-correlation-id"];
+
 /// 路由信息
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RoutingInfo {
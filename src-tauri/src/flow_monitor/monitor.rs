@@ -17,12 +17,14 @@ use std::sync::Arc;
 use tokio::sync::{broadcast, RwLock};
 use uuid::Uuid;
 
+use super::diff::{ShadowComparison, ShadowResponse};
 use super::file_store::FlowFileStore;
 use super::memory_store::FlowMemoryStore;
 use super::models::{
     FlowAnnotations, FlowError, FlowMetadata, FlowState, FlowType, LLMFlow, LLMRequest,
     LLMResponse, TokenUsage,
 };
+use super::session::SessionManager;
 use super::stream_rebuilder::{StreamFormat, StreamRebuilder};
 
 // ============================================================================
@@ -70,6 +72,9 @@ pub struct FlowMonitorConfig {
     /// 排除的路径列表（支持通配符）
     #[serde(default)]
     pub excluded_paths: Vec<String>,
+    /// 捕获过滤器（在创建 Flow 前排除健康检查等噪声请求）
+    #[serde(default)]
+    pub capture_filter: CaptureFilter,
 }
 
 fn default_enabled() -> bool {
@@ -119,6 +124,7 @@ impl Default for FlowMonitorConfig {
             sampling_rate: default_sampling_rate(),
             excluded_models: Vec::new(),
             excluded_paths: Vec::new(),
+            capture_filter: CaptureFilter::default(),
         }
     }
 }
@@ -156,7 +162,7 @@ impl FlowMonitorConfig {
     }
 
     /// 模式匹配（支持 * 通配符）
-    fn match_pattern(pattern: &str, text: &str) -> bool {
+    pub(crate) fn match_pattern(pattern: &str, text: &str) -> bool {
         if pattern == "*" {
             return true;
         }
@@ -193,6 +199,113 @@ impl FlowMonitorConfig {
     }
 }
 
+// ============================================================================
+// 捕获过滤器
+// ============================================================================
+
+/// 捕获排除规则
+///
+/// 已设置的条件全部满足时排除该请求；未设置任何条件的规则无效。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptureExclusionRule {
+    /// 请求路径（支持 * 通配符）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// HTTP 方法（不区分大小写）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// 请求头名称（不区分大小写）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    /// 请求头值（支持 * 通配符，未设置时只要求请求头存在）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header_value: Option<String>,
+}
+
+impl CaptureExclusionRule {
+    /// 规则是否至少包含一个条件
+    pub fn has_conditions(&self) -> bool {
+        self.path.is_some() || self.method.is_some() || self.header.is_some()
+    }
+
+    /// 检查请求是否匹配该规则
+    pub fn matches(&self, request: &LLMRequest) -> bool {
+        if !self.has_conditions() {
+            return false;
+        }
+        if let Some(path) = &self.path {
+            if !FlowMonitorConfig::match_pattern(path, &request.path) {
+                return false;
+            }
+        }
+        if let Some(method) = &self.method {
+            if !method.eq_ignore_ascii_case(&request.method) {
+                return false;
+            }
+        }
+        if let Some(header) = &self.header {
+            let value = request
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(header))
+                .map(|(_, value)| value);
+            match (value, &self.header_value) {
+                (None, _) => return false,
+                (Some(value), Some(pattern)) => {
+                    if !FlowMonitorConfig::match_pattern(pattern, value) {
+                        return false;
+                    }
+                }
+                (Some(_), None) => {}
+            }
+        }
+        true
+    }
+}
+
+/// 捕获过滤器配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptureFilter {
+    /// 排除规则（任一规则匹配即排除）
+    #[serde(default)]
+    pub exclusions: Vec<CaptureExclusionRule>,
+    /// 被排除的请求是否仍计入请求速率
+    #[serde(default)]
+    pub count_excluded_in_rate: bool,
+}
+
+impl CaptureFilter {
+    /// 检查请求是否被排除
+    pub fn is_excluded(&self, request: &LLMRequest) -> bool {
+        self.exclusions.iter().any(|rule| rule.matches(request))
+    }
+
+    /// 验证规则
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, rule) in self.exclusions.iter().enumerate() {
+            if !rule.has_conditions() {
+                return Err(format!(
+                    "排除规则 #{} 至少需要设置路径、方法或请求头之一",
+                    i + 1
+                ));
+            }
+            if rule.header_value.is_some() && rule.header.is_none() {
+                return Err(format!(
+                    "排除规则 #{} 设置了请求头值但未设置请求头名称",
+                    i + 1
+                ));
+            }
+            let empty = [&rule.path, &rule.method, &rule.header]
+                .iter()
+                .any(|v| v.as_deref().is_some_and(|v| v.trim().is_empty()));
+            if empty {
+                return Err(format!("排除规则 #{} 包含空条件", i + 1));
+            }
+        }
+        Ok(())
+    }
+}
+
 // ============================================================================
 // 阈值配置
 // ============================================================================
@@ -219,6 +332,16 @@ pub struct ThresholdConfig {
     /// 输出 Token 阈值（可选）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output_token_threshold: Option<u32>,
+    /// 异常告警规则（按时间窗口聚合的指标）
+    #[serde(default)]
+    pub alert_rules: Vec<AlertRule>,
+    /// 同一规则两次告警之间的最小间隔（秒）
+    #[serde(default = "default_alert_cooldown")]
+    pub alert_cooldown_seconds: u64,
+}
+
+fn default_alert_cooldown() -> u64 {
+    300
 }
 
 fn default_threshold_enabled() -> bool {
@@ -241,6 +364,157 @@ impl Default for ThresholdConfig {
             token_threshold: default_token_threshold(),
             input_token_threshold: None,
             output_token_threshold: None,
+            alert_rules: Vec::new(),
+            alert_cooldown_seconds: default_alert_cooldown(),
+        }
+    }
+}
+
+impl ThresholdConfig {
+    /// 校验异常告警规则
+    pub fn validate_alert_rules(&self) -> Result<(), String> {
+        let mut ids = std::collections::HashSet::new();
+        for rule in &self.alert_rules {
+            if rule.id.trim().is_empty() {
+                return Err("告警规则 ID 不能为空".to_string());
+            }
+            if !ids.insert(rule.id.as_str()) {
+                return Err(format!("告警规则 ID 重复: {}", rule.id));
+            }
+            if !rule.threshold.is_finite() {
+                return Err(format!("告警规则 {} 的阈值无效", rule.id));
+            }
+            if rule.window_seconds == 0 {
+                return Err(format!("告警规则 {} 的统计窗口必须大于 0", rule.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 告警指标
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// 请求速率（每秒）
+    RequestRate,
+    /// 错误率（0.0-1.0，失败数 / 已结束请求数）
+    ErrorRate,
+    /// p99 延迟（毫秒）
+    P99Latency,
+}
+
+/// 告警比较运算符
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertComparator {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl AlertComparator {
+    fn compare(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            AlertComparator::Gt => value > threshold,
+            AlertComparator::Gte => value >= threshold,
+            AlertComparator::Lt => value < threshold,
+            AlertComparator::Lte => value <= threshold,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            AlertComparator::Gt => ">",
+            AlertComparator::Gte => ">=",
+            AlertComparator::Lt => "<",
+            AlertComparator::Lte => "<=",
+        }
+    }
+}
+
+/// 异常告警规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertRule {
+    /// 规则 ID（用于去抖）
+    pub id: String,
+    /// 是否启用
+    #[serde(default = "default_threshold_enabled")]
+    pub enabled: bool,
+    /// 指标
+    pub metric: AlertMetric,
+    /// 比较运算符
+    pub comparator: AlertComparator,
+    /// 阈值
+    pub threshold: f64,
+    /// 统计窗口（秒）
+    #[serde(default = "default_alert_window")]
+    pub window_seconds: u64,
+    /// 是否同时发送桌面通知
+    #[serde(default)]
+    pub desktop_notification: bool,
+}
+
+fn default_alert_window() -> u64 {
+    60
+}
+
+impl AlertRule {
+    /// 指标值是否触发规则
+    pub fn is_breached(&self, value: f64) -> bool {
+        self.comparator.compare(value, self.threshold)
+    }
+}
+
+/// 告警事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertEvent {
+    /// 触发的规则 ID
+    pub rule_id: String,
+    /// 指标
+    pub metric: AlertMetric,
+    /// 比较运算符
+    pub comparator: AlertComparator,
+    /// 阈值
+    pub threshold: f64,
+    /// 实际值
+    pub value: f64,
+    /// 统计窗口（秒）
+    pub window_seconds: u64,
+    /// 告警描述
+    pub message: String,
+    /// 是否需要桌面通知
+    pub desktop: bool,
+    /// 触发时间
+    pub timestamp: DateTime<Utc>,
+}
+
+impl AlertEvent {
+    /// 根据规则和实际值创建告警事件
+    pub fn new(rule: &AlertRule, value: f64) -> Self {
+        let metric_name = match rule.metric {
+            AlertMetric::RequestRate => "请求速率",
+            AlertMetric::ErrorRate => "错误率",
+            AlertMetric::P99Latency => "p99 延迟",
+        };
+        Self {
+            rule_id: rule.id.clone(),
+            metric: rule.metric,
+            comparator: rule.comparator,
+            threshold: rule.threshold,
+            value,
+            window_seconds: rule.window_seconds,
+            message: format!(
+                "最近 {} 秒{} {:.2} {} {}",
+                rule.window_seconds,
+                metric_name,
+                value,
+                rule.comparator.symbol(),
+                rule.threshold
+            ),
+            desktop: rule.desktop_notification,
+            timestamp: Utc::now(),
         }
     }
 }
@@ -652,6 +926,9 @@ pub struct FlowSummary {
     pub has_tool_calls: bool,
     /// 是否有思维链
     pub has_thinking: bool,
+    /// 客户端会话 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
 }
 
 impl From<&LLMFlow> for FlowSummary {
@@ -678,6 +955,7 @@ impl From<&LLMFlow> for FlowSummary {
                 .response
                 .as_ref()
                 .map_or(false, |r| r.thinking.is_some()),
+            session_id: flow.metadata.client_info.session_id.clone(),
         }
     }
 }
@@ -702,17 +980,29 @@ pub enum FlowEvent {
     /// Flow 开始
     FlowStarted { flow: FlowSummary },
     /// Flow 更新
-    FlowUpdated { id: String, update: FlowUpdate },
+    FlowUpdated {
+        id: String,
+        update: FlowUpdate,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+    },
     /// Flow 完成
     FlowCompleted { id: String, summary: FlowSummary },
     /// Flow 失败
-    FlowFailed { id: String, error: FlowError },
+    FlowFailed {
+        id: String,
+        error: FlowError,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+    },
     /// 阈值警告
     ///
     /// **Validates: Requirements 10.3, 10.4**
     ThresholdWarning {
         id: String,
         result: ThresholdCheckResult,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
     },
     /// 通知事件
     ///
@@ -722,6 +1012,53 @@ pub enum FlowEvent {
     ///
     /// **Validates: Requirements 10.7**
     RequestRateUpdate { rate: f64, count: usize },
+    /// 异常告警
+    AlertTriggered { alert: AlertEvent },
+}
+
+impl FlowEvent {
+    /// 事件关联的客户端会话 ID
+    ///
+    /// 与单个 Flow 无关的事件（通知、速率、告警）返回 None。
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            FlowEvent::FlowStarted { flow } => flow.session_id.as_deref(),
+            FlowEvent::FlowCompleted { summary, .. } => summary.session_id.as_deref(),
+            FlowEvent::FlowUpdated { session_id, .. }
+            | FlowEvent::FlowFailed { session_id, .. }
+            | FlowEvent::ThresholdWarning { session_id, .. } => session_id.as_deref(),
+            FlowEvent::Notification { .. }
+            | FlowEvent::RequestRateUpdate { .. }
+            | FlowEvent::AlertTriggered { .. } => None,
+        }
+    }
+}
+
+/// Flow 事件订阅过滤器
+///
+/// 指定 `session_id` 时进入跟随模式，只推送属于该会话的 Flow 事件。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowEventFilter {
+    /// 跟随的客户端会话 ID
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+impl FlowEventFilter {
+    /// 跟随指定会话
+    pub fn session(session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: Some(session_id.into()),
+        }
+    }
+
+    /// 事件是否应推送给该订阅
+    pub fn matches(&self, event: &FlowEvent) -> bool {
+        match self.session_id.as_deref() {
+            Some(session_id) => event.session_id() == Some(session_id),
+            None => true,
+        }
+    }
 }
 
 // ============================================================================
@@ -738,6 +1075,20 @@ struct ActiveFlow {
     stream_rebuilder: Option<StreamRebuilder>,
     /// 请求开始时间
     request_start: DateTime<Utc>,
+    /// 先于 Flow 结束返回的影子请求结果
+    shadow: Option<ShadowResponse>,
+}
+
+impl ActiveFlow {
+    /// Flow 结束时与暂存的影子请求结果对比
+    fn attach_shadow(&mut self) {
+        if let Some(shadow) = self.shadow.take() {
+            self.flow.shadow = Some(ShadowComparison::compare(
+                self.flow.response.as_ref(),
+                shadow,
+            ));
+        }
+    }
 }
 
 // ============================================================================
@@ -764,6 +1115,8 @@ pub struct FlowMonitor {
     rate_tracker: RwLock<RequestRateTracker>,
     /// 通知配置
     notification_config: RwLock<NotificationConfig>,
+    /// 会话管理器（用于自动会话分组）
+    session_manager: parking_lot::RwLock<Option<Arc<SessionManager>>>,
 }
 
 impl FlowMonitor {
@@ -785,6 +1138,7 @@ impl FlowMonitor {
             threshold_config: RwLock::new(ThresholdConfig::default()),
             rate_tracker: RwLock::new(RequestRateTracker::default()),
             notification_config: RwLock::new(NotificationConfig::default()),
+            session_manager: parking_lot::RwLock::new(None),
         }
     }
 
@@ -813,6 +1167,7 @@ impl FlowMonitor {
             threshold_config: RwLock::new(threshold_config),
             rate_tracker: RwLock::new(RequestRateTracker::default()),
             notification_config: RwLock::new(notification_config),
+            session_manager: parking_lot::RwLock::new(None),
         }
     }
 
@@ -841,6 +1196,7 @@ impl FlowMonitor {
             threshold_config: RwLock::new(threshold_config),
             rate_tracker: RwLock::new(RequestRateTracker::default()),
             notification_config: RwLock::new(notification_config),
+            session_manager: parking_lot::RwLock::new(None),
         }
     }
 
@@ -874,6 +1230,23 @@ impl FlowMonitor {
         *current = config;
     }
 
+    /// 设置会话管理器，新捕获的 Flow 会按自动会话配置归入会话
+    pub fn set_session_manager(&self, session_manager: Arc<SessionManager>) {
+        *self.session_manager.write() = Some(session_manager);
+    }
+
+    /// 获取捕获过滤器
+    pub async fn capture_filter(&self) -> CaptureFilter {
+        self.config.read().await.capture_filter.clone()
+    }
+
+    /// 更新捕获过滤器（立即对新请求生效）
+    pub async fn update_capture_filter(&self, filter: CaptureFilter) -> Result<(), String> {
+        filter.validate()?;
+        self.config.write().await.capture_filter = filter;
+        Ok(())
+    }
+
     /// 获取阈值配置
     ///
     /// **Validates: Requirements 10.3, 10.4**
@@ -1019,6 +1392,16 @@ impl FlowMonitor {
         }
     }
 
+    /// 发送异常告警事件
+    pub fn emit_alert(&self, alert: AlertEvent) {
+        tracing::warn!(
+            "[FlowMonitor] 异常告警 {}: {}",
+            alert.rule_id,
+            alert.message
+        );
+        let _ = self.event_sender.send(FlowEvent::AlertTriggered { alert });
+    }
+
     /// 发送请求速率更新事件
     ///
     /// **Validates: Requirements 10.7**
@@ -1050,6 +1433,21 @@ impl FlowMonitor {
     pub async fn start_flow(&self, request: LLMRequest, metadata: FlowMetadata) -> Option<String> {
         let config = self.config.read().await;
 
+        // 捕获过滤器在记录 Flow 之前生效，被排除的请求不会进入存储
+        if config.enabled && config.capture_filter.is_excluded(&request) {
+            tracing::debug!(
+                "[FLOW_MONITOR] 捕获过滤器排除请求: {} {}",
+                request.method,
+                request.path
+            );
+            if config.capture_filter.count_excluded_in_rate {
+                drop(config);
+                self.rate_tracker.write().await.record_request();
+                self.send_rate_update().await;
+            }
+            return None;
+        }
+
         // 检查是否应该监控
         if !config.should_monitor(&request.model, &request.path) {
             eprintln!(
@@ -1079,11 +1477,20 @@ impl FlowMonitor {
         // 创建 Flow
         let flow = LLMFlow::new(flow_id.clone(), flow_type, request.clone(), metadata);
 
+        // 自动会话分组
+        let session_manager = self.session_manager.read().clone();
+        if let Some(session_manager) = session_manager {
+            if let Err(e) = session_manager.auto_assign(&flow) {
+                tracing::warn!("[FLOW_MONITOR] 自动会话分组失败: {}", e);
+            }
+        }
+
         // 创建活跃 Flow 状态
         let active_flow = ActiveFlow {
             flow: flow.clone(),
             stream_rebuilder: None,
             request_start: Utc::now(),
+            shadow: None,
         };
 
         // 添加到活跃 Flow
@@ -1150,6 +1557,7 @@ impl FlowMonitor {
                     content_length: None,
                     chunk_count: None,
                 },
+                session_id: active_flow.flow.metadata.client_info.session_id.clone(),
             });
         }
     }
@@ -1206,6 +1614,7 @@ impl FlowMonitor {
             active_flow.flow.timestamps.response_end = Some(now);
             active_flow.flow.timestamps.calculate_duration();
             active_flow.flow.timestamps.calculate_ttfb();
+            active_flow.attach_shadow();
 
             eprintln!(
                 "[FLOW_MONITOR] Flow 状态更新: id={}, state={:?}, duration_ms={}",
@@ -1232,6 +1641,7 @@ impl FlowMonitor {
                     tracing::error!("保存 Flow 到文件失败: {}", e);
                     eprintln!("[FLOW_MONITOR] 保存到文件失败: id={}, error={}", flow_id, e);
                 } else {
+                    self.memory_store.write().await.mark_persisted(flow_id);
                     eprintln!("[FLOW_MONITOR] 已保存到文件存储: id={}", flow_id);
                 }
             } else {
@@ -1250,6 +1660,7 @@ impl FlowMonitor {
                 let _ = self.event_sender.send(FlowEvent::ThresholdWarning {
                     id: flow_id.to_string(),
                     result: threshold_result.clone(),
+                    session_id: active_flow.flow.metadata.client_info.session_id.clone(),
                 });
 
                 // 检查并触发阈值通知
@@ -1279,6 +1690,7 @@ impl FlowMonitor {
             active_flow.flow.state = FlowState::Failed;
             active_flow.flow.timestamps.response_end = Some(now);
             active_flow.flow.timestamps.calculate_duration();
+            active_flow.attach_shadow();
 
             // 保存到内存存储
             {
@@ -1290,6 +1702,8 @@ impl FlowMonitor {
             if let Some(ref file_store) = self.file_store {
                 if let Err(e) = file_store.write(&active_flow.flow) {
                     tracing::error!("保存 Flow 到文件失败: {}", e);
+                } else {
+                    self.memory_store.write().await.mark_persisted(flow_id);
                 }
             }
 
@@ -1297,6 +1711,7 @@ impl FlowMonitor {
             let _ = self.event_sender.send(FlowEvent::FlowFailed {
                 id: flow_id.to_string(),
                 error: error.clone(),
+                session_id: active_flow.flow.metadata.client_info.session_id.clone(),
             });
 
             // 检查错误 Flow 通知
@@ -1319,6 +1734,7 @@ impl FlowMonitor {
             active_flow.flow.state = FlowState::Cancelled;
             active_flow.flow.timestamps.response_end = Some(now);
             active_flow.flow.timestamps.calculate_duration();
+            active_flow.attach_shadow();
 
             // 保存到内存存储
             {
@@ -1330,11 +1746,51 @@ impl FlowMonitor {
             if let Some(ref file_store) = self.file_store {
                 if let Err(e) = file_store.write(&active_flow.flow) {
                     tracing::error!("保存 Flow 到文件失败: {}", e);
+                } else {
+                    self.memory_store.write().await.mark_persisted(flow_id);
                 }
             }
         }
     }
 
+    /// 记录影子请求结果
+    ///
+    /// Flow 仍在进行中时暂存结果，结束时再与实际响应对比；
+    /// 已结束时直接对比并重新写入文件存储（索引指向最新记录）。
+    ///
+    /// # 返回
+    /// - `true`: 已记录
+    /// - `false`: Flow 不存在
+    pub async fn record_shadow_response(&self, flow_id: &str, shadow: ShadowResponse) -> bool {
+        {
+            let mut active = self.active_flows.write().await;
+            if let Some(active_flow) = active.get_mut(flow_id) {
+                active_flow.shadow = Some(shadow);
+                return true;
+            }
+        }
+
+        let flow = {
+            let store = self.memory_store.read().await;
+            let updated = store.update(flow_id, |flow| {
+                flow.shadow = Some(ShadowComparison::compare(flow.response.as_ref(), shadow));
+            });
+            if !updated {
+                return false;
+            }
+            store
+                .get(flow_id)
+                .and_then(|flow| flow.read().ok().map(|flow| flow.clone()))
+        };
+
+        if let (Some(flow), Some(file_store)) = (flow, &self.file_store) {
+            if let Err(e) = file_store.write(&flow) {
+                tracing::error!("保存影子测试结果到文件失败: {}", e);
+            }
+        }
+        true
+    }
+
     /// 更新 Flow 标注
     ///
     /// # 参数
@@ -1417,6 +1873,82 @@ impl FlowMonitor {
         self.memory_store.read().await.len()
     }
 
+    /// 将内存中的指定 Flow 持久化到文件存储
+    ///
+    /// Flow 结束时已写入文件存储的会直接计为已持久化，不会重复写入；
+    /// 只有此前写入失败的 Flow 会被重新写入。写入在释放内存存储锁之后进行。
+    /// 写入成功的 Flow 会被标记为已持久化，之后可通过
+    /// `prune_persisted_flows` 从内存中移除。
+    ///
+    /// # 参数
+    /// - `flow_ids`: 要持久化的 Flow ID 列表
+    ///
+    /// # 返回
+    /// 已持久化的 Flow 数量；未启用文件存储时返回错误
+    pub async fn persist_flows(&self, flow_ids: &[String]) -> Result<usize, String> {
+        let file_store = self
+            .file_store
+            .as_ref()
+            .ok_or_else(|| "文件存储未启用".to_string())?;
+
+        // 在读锁内只做快照，文件 I/O 放到锁外
+        let mut already_persisted = 0;
+        let pending: Vec<LLMFlow> = {
+            let store = self.memory_store.read().await;
+            flow_ids
+                .iter()
+                .filter(|id| {
+                    if store.is_persisted(id) {
+                        already_persisted += 1;
+                        false
+                    } else {
+                        true
+                    }
+                })
+                .filter_map(|id| store.get(id))
+                .filter_map(|flow_lock| flow_lock.read().ok().map(|flow| flow.clone()))
+                .collect()
+        };
+
+        let mut written = Vec::with_capacity(pending.len());
+        for flow in &pending {
+            if let Err(e) = file_store.write(flow) {
+                tracing::error!("持久化 Flow 失败: id={}, error={}", flow.id, e);
+                continue;
+            }
+            if let Err(e) = file_store.update_annotations(&flow.id, &flow.annotations) {
+                tracing::warn!("持久化 Flow 标注失败: id={}, error={}", flow.id, e);
+            }
+            written.push(flow.id.as_str());
+        }
+
+        if !written.is_empty() {
+            let mut store = self.memory_store.write().await;
+            for id in &written {
+                store.mark_persisted(id);
+            }
+        }
+
+        let persisted = already_persisted + written.len();
+        tracing::info!(
+            "已持久化 {}/{} 个 Flow（本次写入 {} 个）",
+            persisted,
+            flow_ids.len(),
+            written.len()
+        );
+        Ok(persisted)
+    }
+
+    /// 从内存中移除已持久化的 Flow
+    ///
+    /// # 返回
+    /// 移除的 Flow 数量
+    pub async fn prune_persisted_flows(&self) -> usize {
+        let pruned = self.memory_store.write().await.prune_persisted();
+        tracing::info!("已从内存中移除 {} 个已持久化的 Flow", pruned);
+        pruned
+    }
+
     /// 检查监控是否启用
     pub async fn is_enabled(&self) -> bool {
         self.config.read().await.enabled
@@ -1657,6 +2189,107 @@ mod tests {
         assert_eq!(monitor.memory_flow_count().await, 1);
     }
 
+    #[tokio::test]
+    async fn test_record_shadow_response_before_and_after_completion() {
+        let monitor = FlowMonitor::new(FlowMonitorConfig::default(), None);
+        let shadow = |content: &str| ShadowResponse {
+            credential_id: "shadow-cred".to_string(),
+            credential_name: None,
+            response: Some(LLMResponse {
+                content: content.to_string(),
+                ..Default::default()
+            }),
+            error: None,
+            latency_ms: 10,
+        };
+        let served = || LLMResponse {
+            content: "served".to_string(),
+            ..Default::default()
+        };
+
+        // 影子请求先返回：Flow 结束时对比
+        let early = monitor
+            .start_flow(
+                create_test_request("gpt-4", "/v1/chat/completions"),
+                create_test_metadata(ProviderType::OpenAI),
+            )
+            .await
+            .unwrap();
+        assert!(
+            monitor
+                .record_shadow_response(&early, shadow("served"))
+                .await
+        );
+        monitor.complete_flow(&early, Some(served())).await;
+
+        // 影子请求后返回：直接更新已结束的 Flow
+        let late = monitor
+            .start_flow(
+                create_test_request("gpt-4", "/v1/chat/completions"),
+                create_test_metadata(ProviderType::OpenAI),
+            )
+            .await
+            .unwrap();
+        monitor.complete_flow(&late, Some(served())).await;
+        assert!(monitor.record_shadow_response(&late, shadow("other")).await);
+
+        let store = monitor.memory_store();
+        let store = store.read().await;
+        let comparison = |id: &str| {
+            let flow = store.get(id).unwrap();
+            let flow = flow.read().unwrap();
+            flow.shadow.clone().unwrap()
+        };
+        assert!(comparison(&early).content_identical);
+        assert!(!comparison(&late).content_identical);
+        assert!(!monitor.record_shadow_response("missing", shadow("x")).await);
+    }
+
+    #[tokio::test]
+    async fn test_follow_filter_matches_session_events() {
+        let monitor = FlowMonitor::new(FlowMonitorConfig::default(), None);
+        let mut receiver = monitor.subscribe();
+        let filter = FlowEventFilter::session("conv-1");
+
+        let mut metadata = create_test_metadata(ProviderType::OpenAI);
+        metadata.client_info.session_id = Some("conv-1".to_string());
+        let followed = monitor
+            .start_flow(
+                create_test_request("gpt-4", "/v1/chat/completions"),
+                metadata,
+            )
+            .await
+            .unwrap();
+        let other = monitor
+            .start_flow(
+                create_test_request("gpt-4", "/v1/chat/completions"),
+                create_test_metadata(ProviderType::OpenAI),
+            )
+            .await
+            .unwrap();
+        monitor
+            .fail_flow(
+                &followed,
+                FlowError::new(crate::flow_monitor::models::FlowErrorType::Network, "boom"),
+            )
+            .await;
+
+        let mut matched = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            assert!(FlowEventFilter::default().matches(&event));
+            if filter.matches(&event) {
+                matched.push(event);
+            }
+        }
+
+        assert_eq!(matched.len(), 2);
+        assert!(matches!(&matched[0], FlowEvent::FlowStarted { flow } if flow.id == followed));
+        assert!(
+            matches!(&matched[1], FlowEvent::FlowFailed { id, session_id, .. } if *id == followed && session_id.as_deref() == Some("conv-1"))
+        );
+        assert_ne!(followed, other);
+    }
+
     #[tokio::test]
     async fn test_config_should_monitor() {
         let config = FlowMonitorConfig {
@@ -1677,6 +2310,89 @@ mod tests {
         assert!(!config.should_monitor("gpt-4", "/health"));
     }
 
+    #[tokio::test]
+    async fn test_capture_filter_excludes_noise() {
+        let filter = CaptureFilter {
+            exclusions: vec![
+                CaptureExclusionRule {
+                    path: Some("/health*".to_string()),
+                    ..Default::default()
+                },
+                CaptureExclusionRule {
+                    path: Some("/v1/models".to_string()),
+                    method: Some("get".to_string()),
+                    ..Default::default()
+                },
+                CaptureExclusionRule {
+                    header: Some("User-Agent".to_string()),
+                    header_value: Some("uptime-*".to_string()),
+                    ..Default::default()
+                },
+            ],
+            count_excluded_in_rate: false,
+        };
+        let monitor = FlowMonitor::new(FlowMonitorConfig::default(), None);
+        monitor.update_capture_filter(filter).await.unwrap();
+
+        let metadata = create_test_metadata(ProviderType::OpenAI);
+        let health = create_test_request("gpt-4", "/health/ready");
+        assert!(monitor.start_flow(health, metadata.clone()).await.is_none());
+
+        let mut models = create_test_request("gpt-4", "/v1/models");
+        models.method = "GET".to_string();
+        assert!(monitor.start_flow(models, metadata.clone()).await.is_none());
+
+        let mut probe = create_test_request("gpt-4", "/v1/chat/completions");
+        probe
+            .headers
+            .insert("user-agent".to_string(), "uptime-kuma/1.0".to_string());
+        assert!(monitor.start_flow(probe, metadata.clone()).await.is_none());
+
+        // 方法不匹配时不排除
+        let models_post = create_test_request("gpt-4", "/v1/models");
+        assert!(monitor.start_flow(models_post, metadata).await.is_some());
+        assert_eq!(monitor.active_flow_count().await, 1);
+        assert_eq!(monitor.get_request_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_capture_filter_counts_excluded_in_rate() {
+        let monitor = FlowMonitor::new(FlowMonitorConfig::default(), None);
+        let filter = CaptureFilter {
+            exclusions: vec![CaptureExclusionRule {
+                path: Some("/health".to_string()),
+                ..Default::default()
+            }],
+            count_excluded_in_rate: true,
+        };
+        monitor.update_capture_filter(filter).await.unwrap();
+
+        let request = create_test_request("gpt-4", "/health");
+        let metadata = create_test_metadata(ProviderType::OpenAI);
+        assert!(monitor.start_flow(request, metadata).await.is_none());
+        assert_eq!(monitor.active_flow_count().await, 0);
+        assert_eq!(monitor.get_request_count().await, 1);
+    }
+
+    #[test]
+    fn test_capture_filter_validate() {
+        let invalid = CaptureFilter {
+            exclusions: vec![CaptureExclusionRule::default()],
+            count_excluded_in_rate: false,
+        };
+        assert!(invalid.validate().is_err());
+
+        let value_without_header = CaptureFilter {
+            exclusions: vec![CaptureExclusionRule {
+                header_value: Some("x".to_string()),
+                path: Some("/health".to_string()),
+                ..Default::default()
+            }],
+            count_excluded_in_rate: false,
+        };
+        assert!(value_without_header.validate().is_err());
+    }
+
     #[tokio::test]
     async fn test_disabled_monitor() {
         let config = FlowMonitorConfig {
@@ -1763,6 +2479,48 @@ mod tests {
         // 测试设置标记
         assert!(monitor.set_marker(&flow_id, Some("⭐".to_string())).await);
     }
+
+    #[tokio::test]
+    async fn test_persist_flows_skips_flows_written_on_completion() {
+        use crate::flow_monitor::file_store::RotationConfig;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_store = Arc::new(
+            FlowFileStore::new(temp_dir.path().to_path_buf(), RotationConfig::default()).unwrap(),
+        );
+        let monitor = FlowMonitor::new(FlowMonitorConfig::default(), Some(file_store));
+
+        let request = create_test_request("gpt-4", "/v1/chat/completions");
+        let metadata = create_test_metadata(ProviderType::OpenAI);
+        let flow_id = monitor.start_flow(request, metadata).await.unwrap();
+        monitor.complete_flow(&flow_id, None).await;
+
+        let persisted = monitor
+            .persist_flows(&[flow_id.clone(), "missing".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(persisted, 1);
+
+        // 完成时已写入一次，persist_flows 不应再追加记录
+        let mut lines = 0;
+        for day in std::fs::read_dir(temp_dir.path()).unwrap().flatten() {
+            if !day.path().is_dir() {
+                continue;
+            }
+            for file in std::fs::read_dir(day.path()).unwrap().flatten() {
+                if file.path().extension().is_some_and(|ext| ext == "jsonl") {
+                    lines += std::fs::read_to_string(file.path())
+                        .unwrap()
+                        .lines()
+                        .count();
+                }
+            }
+        }
+        assert_eq!(lines, 1);
+
+        assert_eq!(monitor.prune_persisted_flows().await, 1);
+        assert_eq!(monitor.memory_flow_count().await, 0);
+    }
 }
 
 // ============================================================================
@@ -2004,7 +2762,11 @@ mod property_tests {
                 for _ in 0..3 {  // 最多尝试 3 次
                     let event = receiver.try_recv();
                     if event.is_ok() {
-                        if let FlowEvent::FlowFailed { id, error: evt_error } = event.unwrap() {
+                        if let FlowEvent::FlowFailed {
+                            id,
+                            error: evt_error,
+                            ..
+                        } = event.unwrap() {
                             prop_assert_eq!(id, flow_id, "事件中的 Flow ID 应该正确");
                             prop_assert_eq!(
                                 evt_error.error_type,
@@ -2223,6 +2985,7 @@ mod property_tests {
                 token_threshold,
                 input_token_threshold,
                 output_token_threshold,
+                ..Default::default()
             };
 
             // 创建测试 Flow
@@ -2349,6 +3112,7 @@ mod property_tests {
                 token_threshold: 100,       // 很低的阈值
                 input_token_threshold: Some(100),
                 output_token_threshold: Some(100),
+                ..Default::default()
             };
 
             // 创建测试 Flow
@@ -2455,6 +3219,7 @@ mod property_tests {
                     token_threshold: 100,
                     input_token_threshold: None,
                     output_token_threshold: None,
+                    ..Default::default()
                 };
 
                 let config = FlowMonitorConfig::default();
@@ -6,13 +6,17 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::RwLock;
 
+use super::exporter::{NdjsonExportOptions, NdjsonExportSummary, NdjsonWriter};
 use super::file_store::{FileStoreError, FlowFileStore};
 use super::filter_parser::{FilterParseError, FilterParser};
-use super::memory_store::{FlowFilter, FlowMemoryStore};
+use super::memory_store::{FlowFilter, FlowMemoryStore, TimeRange};
 use super::models::{FlowState, LLMFlow};
 
 // ============================================================================
@@ -778,6 +782,48 @@ impl FlowQueryService {
         let store = self.memory_store.read().await;
         store.get_recent(limit)
     }
+
+    /// 把匹配的 Flow 逐条写入 NDJSON 文件
+    ///
+    /// 先按创建时间升序写入文件存储中的 Flow，再写入内存中的 Flow（内存中的版本
+    /// 标注更新，文件中的同 ID 记录会被跳过）。未指定结束时间时以导出开始时刻为准。
+    /// 读取文件存储和写入导出文件都是阻塞 I/O，在 `spawn_blocking` 中执行。
+    pub async fn export_ndjson(
+        &self,
+        mut filter: FlowFilter,
+        options: NdjsonExportOptions,
+        path: &Path,
+    ) -> Result<NdjsonExportSummary, FileStoreError> {
+        filter
+            .time_range
+            .get_or_insert_with(|| TimeRange::new(None, None))
+            .end
+            .get_or_insert_with(Utc::now);
+
+        let mut memory_flows = self.memory_store.read().await.query(&filter);
+        memory_flows.reverse();
+
+        let file_store = self.file_store.clone();
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || -> Result<NdjsonExportSummary, FileStoreError> {
+            let memory_ids: std::collections::HashSet<&str> =
+                memory_flows.iter().map(|f| f.id.as_str()).collect();
+
+            let mut writer = NdjsonWriter::new(BufWriter::new(File::create(&path)?), options);
+            file_store.for_each_matching(&filter, |flow| {
+                if memory_ids.contains(flow.id.as_str()) {
+                    return Ok(());
+                }
+                Ok(writer.write_flow(&flow)?)
+            })?;
+            for flow in &memory_flows {
+                writer.write_flow(flow)?;
+            }
+            Ok(writer.finish()?)
+        })
+        .await
+        .map_err(|e| FileStoreError::Io(std::io::Error::other(e)))?
+    }
 }
 
 // ============================================================================
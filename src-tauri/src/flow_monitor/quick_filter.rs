@@ -8,12 +8,13 @@
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Mutex;
 use thiserror::Error;
 use uuid::Uuid;
 
-use super::filter_parser::FilterParser;
+use super::filter_parser::{FilterParseError, FilterParser};
 
 // ============================================================================
 // 错误类型
@@ -42,6 +43,12 @@ pub enum QuickFilterError {
 
     #[error("过滤器名称已存在: {0}")]
     DuplicateName(String),
+
+    #[error("过滤变量不存在: {0}")]
+    VariableNotFound(String),
+
+    #[error("过滤变量 '{0}' 仍被 '{1}' 引用")]
+    VariableInUse(String, String),
 }
 
 pub type Result<T> = std::result::Result<T, QuickFilterError>;
@@ -135,6 +142,22 @@ pub struct QuickFilterUpdate {
     pub order: Option<i32>,
 }
 
+/// 过滤变量
+///
+/// 在过滤表达式中以 `$name` 引用，解析前展开为对应的表达式。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FilterVariable {
+    /// 变量名（不含 `$`）
+    pub name: String,
+    /// 变量表达式
+    pub expression: String,
+    /// 描述
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 更新时间
+    pub updated_at: DateTime<Utc>,
+}
+
 /// 快速过滤器导出数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuickFilterExport {
@@ -144,6 +167,9 @@ pub struct QuickFilterExport {
     pub exported_at: DateTime<Utc>,
     /// 过滤器列表
     pub filters: Vec<QuickFilter>,
+    /// 过滤变量列表
+    #[serde(default)]
+    pub variables: Vec<FilterVariable>,
 }
 
 impl QuickFilterExport {
@@ -152,8 +178,15 @@ impl QuickFilterExport {
             version: "1.0".to_string(),
             exported_at: Utc::now(),
             filters,
+            variables: Vec::new(),
         }
     }
+
+    /// 附带过滤变量
+    pub fn with_variables(mut self, variables: Vec<FilterVariable>) -> Self {
+        self.variables = variables;
+        self
+    }
 }
 
 // ============================================================================
@@ -238,6 +271,14 @@ impl QuickFilterManager {
             CREATE INDEX IF NOT EXISTS idx_quick_filters_name ON quick_filters(name);
             CREATE INDEX IF NOT EXISTS idx_quick_filters_group ON quick_filters(group_name);
             CREATE INDEX IF NOT EXISTS idx_quick_filters_order ON quick_filters(sort_order);
+
+            -- 过滤变量表
+            CREATE TABLE IF NOT EXISTS filter_variables (
+                name TEXT PRIMARY KEY,
+                expression TEXT NOT NULL,
+                description TEXT,
+                updated_at TEXT NOT NULL
+            );
             "#,
         )?;
 
@@ -308,8 +349,7 @@ impl QuickFilterManager {
         let filter_expr = filter_expr.into();
 
         // 验证过滤表达式
-        FilterParser::validate(&filter_expr)
-            .map_err(|e| QuickFilterError::InvalidFilterExpr(e.to_string()))?;
+        self.validate_expr(&filter_expr)?;
 
         let filter = QuickFilter::new(
             name,
@@ -405,8 +445,7 @@ impl QuickFilterManager {
     pub fn update(&self, id: &str, updates: QuickFilterUpdate) -> Result<QuickFilter> {
         // 验证新的过滤表达式（如果有）
         if let Some(ref expr) = updates.filter_expr {
-            FilterParser::validate(expr)
-                .map_err(|e| QuickFilterError::InvalidFilterExpr(e.to_string()))?;
+            self.validate_expr(expr)?;
         }
 
         let conn = self.db.lock().unwrap();
@@ -648,7 +687,7 @@ impl QuickFilterManager {
             self.list()?.into_iter().filter(|f| !f.is_preset).collect()
         };
 
-        let export_data = QuickFilterExport::new(filters);
+        let export_data = QuickFilterExport::new(filters).with_variables(self.list_variables()?);
         let json = serde_json::to_string_pretty(&export_data)?;
 
         Ok(json)
@@ -667,6 +706,10 @@ impl QuickFilterManager {
     pub fn import(&self, data: &str, overwrite: bool) -> Result<Vec<QuickFilter>> {
         let export_data: QuickFilterExport = serde_json::from_str(data)?;
 
+        // 先导入过滤变量，过滤器可能引用它们
+        self.import_variables(export_data.variables, overwrite)?;
+        let variables = self.variables_map()?;
+
         let mut imported = Vec::new();
         let conn = self.db.lock().unwrap();
 
@@ -677,7 +720,7 @@ impl QuickFilterManager {
             }
 
             // 验证过滤表达式
-            if FilterParser::validate(&filter.filter_expr).is_err() {
+            if FilterParser::validate_with_variables(&filter.filter_expr, &variables).is_err() {
                 continue;
             }
 
@@ -806,6 +849,178 @@ impl QuickFilterManager {
         }
     }
 
+    /// 验证过滤表达式（展开已保存的变量）
+    fn validate_expr(&self, expr: &str) -> Result<()> {
+        let variables = self.variables_map()?;
+        FilterParser::validate_with_variables(expr, &variables)
+            .map_err(|e| QuickFilterError::InvalidFilterExpr(e.to_string()))
+    }
+
+    /// 保存过滤变量（同名变量会被覆盖）
+    ///
+    /// # Arguments
+    /// * `name` - 变量名（可带 `$` 前缀）
+    /// * `expression` - 变量表达式，可引用其他变量
+    /// * `description` - 描述（可选）
+    pub fn save_variable(
+        &self,
+        name: &str,
+        expression: &str,
+        description: Option<&str>,
+    ) -> Result<FilterVariable> {
+        let name = name.trim().trim_start_matches('$').to_string();
+        if !FilterParser::is_valid_variable_name(&name) {
+            return Err(QuickFilterError::InvalidFilterExpr(
+                FilterParseError::InvalidVariableName(name).to_string(),
+            ));
+        }
+
+        // 带上新定义后展开一次，检查语法、未定义变量和循环引用
+        let mut variables = self.variables_map()?;
+        variables.insert(name.clone(), expression.to_string());
+        FilterParser::validate_with_variables(&format!("${}", name), &variables)
+            .map_err(|e| QuickFilterError::InvalidFilterExpr(e.to_string()))?;
+
+        let variable = FilterVariable {
+            name,
+            expression: expression.to_string(),
+            description: description.map(String::from),
+            updated_at: Utc::now(),
+        };
+
+        let conn = self.db.lock().unwrap();
+        Self::upsert_variable(&conn, &variable)?;
+
+        Ok(variable)
+    }
+
+    /// 删除过滤变量
+    ///
+    /// 仍被其他变量或快速过滤器引用时拒绝删除。
+    pub fn delete_variable(&self, name: &str) -> Result<()> {
+        let name = name.trim().trim_start_matches('$');
+        let variables = self.list_variables()?;
+        if !variables.iter().any(|v| v.name == name) {
+            return Err(QuickFilterError::VariableNotFound(name.to_string()));
+        }
+
+        let references = |expr: &str| {
+            FilterParser::referenced_variables(expr)
+                .map(|names| names.iter().any(|n| n == name))
+                .unwrap_or(false)
+        };
+        if let Some(user) = variables
+            .iter()
+            .find(|v| v.name != name && references(&v.expression))
+        {
+            return Err(QuickFilterError::VariableInUse(
+                name.to_string(),
+                format!("${}", user.name),
+            ));
+        }
+        if let Some(user) = self.list()?.iter().find(|f| references(&f.filter_expr)) {
+            return Err(QuickFilterError::VariableInUse(
+                name.to_string(),
+                user.name.clone(),
+            ));
+        }
+
+        let conn = self.db.lock().unwrap();
+        conn.execute(
+            "DELETE FROM filter_variables WHERE name = ?1",
+            params![name],
+        )?;
+
+        Ok(())
+    }
+
+    /// 列出所有过滤变量（按名称排序）
+    pub fn list_variables(&self) -> Result<Vec<FilterVariable>> {
+        let conn = self.db.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT name, expression, description, updated_at FROM filter_variables ORDER BY name ASC",
+        )?;
+
+        let variables = stmt
+            .query_map([], |row| {
+                let updated_at: String = row.get(3)?;
+                Ok(FilterVariable {
+                    name: row.get(0)?,
+                    expression: row.get(1)?,
+                    description: row.get(2)?,
+                    updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(variables)
+    }
+
+    /// 获取变量名到表达式的映射，用于展开过滤表达式
+    pub fn variables_map(&self) -> Result<HashMap<String, String>> {
+        Ok(self
+            .list_variables()?
+            .into_iter()
+            .map(|v| (v.name, v.expression))
+            .collect())
+    }
+
+    /// 展开过滤表达式中引用的变量
+    pub fn expand_expression(&self, expr: &str) -> Result<String> {
+        let variables = self.variables_map()?;
+        FilterParser::expand_variables(expr, &variables)
+            .map_err(|e| QuickFilterError::InvalidFilterExpr(e.to_string()))
+    }
+
+    /// 导入过滤变量，展开失败（语法错误、缺失引用或循环引用）的变量会被跳过
+    fn import_variables(&self, incoming: Vec<FilterVariable>, overwrite: bool) -> Result<()> {
+        let mut candidates = self.variables_map()?;
+        let incoming: Vec<FilterVariable> = incoming
+            .into_iter()
+            .filter(|v| FilterParser::is_valid_variable_name(&v.name))
+            .filter(|v| overwrite || !candidates.contains_key(&v.name))
+            .collect();
+        for variable in &incoming {
+            candidates.insert(variable.name.clone(), variable.expression.clone());
+        }
+
+        let conn = self.db.lock().unwrap();
+        for mut variable in incoming {
+            if FilterParser::validate_with_variables(&format!("${}", variable.name), &candidates)
+                .is_err()
+            {
+                continue;
+            }
+            variable.updated_at = Utc::now();
+            Self::upsert_variable(&conn, &variable)?;
+        }
+
+        Ok(())
+    }
+
+    fn upsert_variable(conn: &Connection, variable: &FilterVariable) -> Result<()> {
+        conn.execute(
+            r#"
+            INSERT INTO filter_variables (name, expression, description, updated_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(name) DO UPDATE SET
+                expression = excluded.expression,
+                description = excluded.description,
+                updated_at = excluded.updated_at
+            "#,
+            params![
+                variable.name,
+                variable.expression,
+                variable.description,
+                variable.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
     /// 清除所有非预设过滤器（用于测试）
     #[cfg(test)]
     pub fn clear_custom(&self) -> Result<()> {
@@ -1094,6 +1309,73 @@ mod tests {
         assert_eq!(manager.count().unwrap(), initial_count + 2);
         assert_eq!(manager.count_custom().unwrap(), 2);
     }
+
+    #[test]
+    fn test_filter_variables() {
+        let manager = create_test_manager();
+
+        // 未定义变量时无法保存引用它的过滤器
+        assert!(matches!(
+            manager.save("Hosts", "$myhosts & ~e", None, None),
+            Err(QuickFilterError::InvalidFilterExpr(_))
+        ));
+
+        manager
+            .save_variable("$myhosts", "~p kiro | ~p gemini", Some("常用提供商"))
+            .unwrap();
+        manager.save("Hosts", "$myhosts & ~e", None, None).unwrap();
+        manager
+            .save("Slow hosts", "$myhosts & ~latency >5s", None, None)
+            .unwrap();
+
+        assert_eq!(
+            manager.expand_expression("$myhosts & ~e").unwrap(),
+            "(~p kiro | ~p gemini) & ~e"
+        );
+
+        // 更新变量后所有引用它的过滤器自动生效
+        manager.save_variable("myhosts", "~p kiro", None).unwrap();
+        let variables = manager.list_variables().unwrap();
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables[0].expression, "~p kiro");
+
+        // 被引用的变量不能删除
+        assert!(matches!(
+            manager.delete_variable("myhosts"),
+            Err(QuickFilterError::VariableInUse(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_filter_variable_cycle_rejected() {
+        let manager = create_test_manager();
+
+        manager.save_variable("a", "~e", None).unwrap();
+        manager.save_variable("b", "$a | ~t", None).unwrap();
+        assert!(manager.save_variable("a", "$b", None).is_err());
+        assert!(manager.save_variable("1a", "~e", None).is_err());
+
+        manager.delete_variable("b").unwrap();
+        manager.delete_variable("a").unwrap();
+        assert!(matches!(
+            manager.delete_variable("a"),
+            Err(QuickFilterError::VariableNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_import_with_variables() {
+        let manager = create_test_manager();
+        manager.save_variable("myhosts", "~p kiro", None).unwrap();
+        manager.save("Hosts", "$myhosts & ~e", None, None).unwrap();
+
+        let exported = manager.export(false).unwrap();
+
+        let other = create_test_manager();
+        let imported = other.import(&exported, false).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(other.list_variables().unwrap().len(), 1);
+    }
 }
 
 // ============================================================================
@@ -9,6 +9,8 @@
 //! - 支持修改请求参数后重放
 //! - 支持选择不同的凭证
 //! - 重放的 Flow 会被标记为 "replay"
+//! - 重放死信记录（失败请求）
+//! - 模糊重放：对请求施加变异后发往本地服务，检查错误处理
 
 use chrono::{DateTime, Utc};
 use reqwest::Client;
@@ -20,10 +22,11 @@ use tokio::time::sleep;
 use uuid::Uuid;
 
 use super::models::{
-    FlowAnnotations, FlowMetadata, FlowState, FlowTimestamps, LLMFlow, LLMRequest, LLMResponse,
-    Message, RequestParameters, TokenUsage,
+    FlowAnnotations, FlowMetadata, FlowState, FlowTimestamps, FlowType, LLMFlow, LLMRequest,
+    LLMResponse, Message, RequestParameters, TokenUsage,
 };
 use super::monitor::FlowMonitor;
+use crate::database::dao::dead_letter::DeadLetter;
 use crate::database::DbConnection;
 use crate::ProviderPoolService;
 use crate::ProviderType;
@@ -164,6 +167,129 @@ pub struct BatchReplayResult {
     pub total_duration_ms: u64,
 }
 
+// ============================================================================
+// 模糊重放
+// ============================================================================
+
+/// 超大请求体的默认大小，略大于服务器 100MB 的请求体上限
+const DEFAULT_OVERSIZED_BYTES: usize = 101 * 1024 * 1024;
+
+/// 未知模型变异使用的模型名
+const FUZZ_UNKNOWN_MODEL: &str = "proxycast-fuzz-unknown-model";
+
+/// 错误信息最多保留的字符数
+const MAX_FUZZ_ERROR_CHARS: usize = 500;
+
+fn default_oversized_bytes() -> usize {
+    DEFAULT_OVERSIZED_BYTES
+}
+
+/// 模糊重放的请求变异
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FuzzMutation {
+    /// 删除请求体中的字段（JSON Pointer，如 `/messages`、`/messages/0/content`）
+    DropField { path: String },
+    /// 把模型替换为不存在的模型
+    UnknownModel,
+    /// 发送无法解析的 JSON（去掉请求体结尾）
+    MalformedJson,
+    /// 填充请求体到指定字节数
+    OversizedBody {
+        #[serde(default = "default_oversized_bytes")]
+        bytes: usize,
+    },
+}
+
+/// 单个变异的模糊重放结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzResult {
+    /// 施加的变异
+    pub mutation: FuzzMutation,
+    /// HTTP 状态码（变异无法施加或请求未能发出时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// 错误信息（响应中的错误消息，或变异/发送失败的原因）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 实际发送的请求体大小
+    pub request_bytes: usize,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+}
+
+/// 按 JSON Pointer 删除字段，返回是否删除成功
+fn remove_pointer(body: &mut serde_json::Value, pointer: &str) -> bool {
+    let Some((parent, last)) = pointer.rsplit_once('/') else {
+        return false;
+    };
+    let key = last.replace("~1", "/").replace("~0", "~");
+    match body.pointer_mut(parent) {
+        Some(serde_json::Value::Object(map)) => map.remove(&key).is_some(),
+        Some(serde_json::Value::Array(items)) => match key.parse::<usize>() {
+            Ok(index) if index < items.len() => {
+                items.remove(index);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// 对请求体施加变异，返回要发送的原始字节
+fn mutate_body(body: &serde_json::Value, mutation: &FuzzMutation) -> Result<Vec<u8>, String> {
+    let mut body = body.clone();
+    match mutation {
+        FuzzMutation::DropField { path } => {
+            if !remove_pointer(&mut body, path) {
+                return Err(format!("请求体中不存在字段 '{}'", path));
+            }
+        }
+        FuzzMutation::UnknownModel => {
+            let Some(map) = body.as_object_mut() else {
+                return Err("请求体不是 JSON 对象".to_string());
+            };
+            map.insert(
+                "model".to_string(),
+                serde_json::Value::String(FUZZ_UNKNOWN_MODEL.to_string()),
+            );
+        }
+        FuzzMutation::MalformedJson => {
+            let mut bytes = serde_json::to_vec(&body).map_err(|e| e.to_string())?;
+            bytes.pop();
+            return Ok(bytes);
+        }
+        FuzzMutation::OversizedBody { bytes } => {
+            let current = serde_json::to_vec(&body).map_err(|e| e.to_string())?.len();
+            let Some(map) = body.as_object_mut() else {
+                return Err("请求体不是 JSON 对象".to_string());
+            };
+            // `,"_fuzz_padding":""` 另占 19 字节
+            let padding = bytes.saturating_sub(current + 19);
+            map.insert(
+                "_fuzz_padding".to_string(),
+                serde_json::Value::String("x".repeat(padding)),
+            );
+        }
+    }
+    serde_json::to_vec(&body).map_err(|e| e.to_string())
+}
+
+/// 从错误响应中提取错误消息
+fn extract_fuzz_error(body: &[u8]) -> String {
+    let message = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| {
+            json.pointer("/error/message")
+                .or_else(|| json.get("error"))
+                .or_else(|| json.get("message"))
+                .and_then(|v| v.as_str().map(str::to_string))
+        })
+        .unwrap_or_else(|| String::from_utf8_lossy(body).to_string());
+    message.chars().take(MAX_FUZZ_ERROR_CHARS).collect()
+}
+
 // ============================================================================
 // 重放器错误
 // ============================================================================
@@ -239,20 +365,71 @@ impl FlowReplayer {
         flow_id: &str,
         config: ReplayConfig,
     ) -> Result<ReplayResult, ReplayerError> {
-        let started_at = Utc::now();
-
         // 获取原始 Flow
         let original_flow = self.get_flow(flow_id).await?;
+        self.replay_flow(&original_flow, config).await
+    }
+
+    /// 重放死信记录
+    ///
+    /// 以死信中的请求体构建原始 Flow 后重放，请求体被截断的记录不可重放。
+    ///
+    /// # Arguments
+    /// * `entry` - 死信记录
+    /// * `config` - 重放配置
+    pub async fn replay_dead_letter(
+        &self,
+        entry: &DeadLetter,
+        config: ReplayConfig,
+    ) -> Result<ReplayResult, ReplayerError> {
+        if entry.truncated {
+            return Err(ReplayerError::Internal(format!(
+                "死信 '{}' 的请求体已被截断，无法重放",
+                entry.id
+            )));
+        }
+        let body: serde_json::Value = serde_json::from_str(&entry.request_body)
+            .map_err(|e| ReplayerError::Internal(format!("死信请求体解析失败: {}", e)))?;
+
+        let flow_type = if entry.path.ends_with("/messages") {
+            FlowType::AnthropicMessages
+        } else {
+            FlowType::ChatCompletions
+        };
+        let request = LLMRequest {
+            path: entry.path.clone(),
+            body,
+            model: entry.model.clone(),
+            size_bytes: entry.request_body.len(),
+            ..Default::default()
+        };
+        let metadata = FlowMetadata {
+            provider: entry.provider.parse().unwrap_or(ProviderType::OpenAI),
+            credential_id: entry.credential_uuid.clone(),
+            ..Default::default()
+        };
+        let original_flow = LLMFlow::new(entry.id.clone(), flow_type, request, metadata);
+        self.replay_flow(&original_flow, config).await
+    }
+
+    /// 重放给定的原始 Flow
+    async fn replay_flow(
+        &self,
+        original_flow: &LLMFlow,
+        config: ReplayConfig,
+    ) -> Result<ReplayResult, ReplayerError> {
+        let started_at = Utc::now();
+        let flow_id = original_flow.id.as_str();
 
         // 应用请求修改
         let request = self.apply_modifications(&original_flow.request, &config.modify_request);
 
         // 确定使用的凭证
-        let credential_id = self.resolve_credential(&original_flow, &config).await?;
+        let credential_id = self.resolve_credential(original_flow, &config).await?;
 
         // 创建重放 Flow
         let replay_flow_id = self
-            .create_replay_flow(&original_flow, &request, &credential_id)
+            .create_replay_flow(original_flow, &request, &credential_id)
             .await;
 
         // 执行重放请求
@@ -343,6 +520,88 @@ impl FlowReplayer {
         }
     }
 
+    /// 模糊重放：对 Flow 的请求体依次施加变异后发往本地服务
+    ///
+    /// 用于验证各种异常请求都能得到合理的错误响应。请求直接发送原始字节，
+    /// 不经过重放 Flow 记录，每个变异独立返回状态码和错误信息。
+    ///
+    /// # Arguments
+    /// * `flow_id` - 作为基准的 Flow ID
+    /// * `mutations` - 依次施加的变异
+    /// * `base_url` - 本地服务地址
+    /// * `api_key` - 本地服务 API Key
+    pub async fn fuzz_replay(
+        &self,
+        flow_id: &str,
+        mutations: &[FuzzMutation],
+        base_url: &str,
+        api_key: &str,
+    ) -> Result<Vec<FuzzResult>, ReplayerError> {
+        let flow = self.get_flow(flow_id).await?;
+        let path = if flow.request.path.is_empty() {
+            match flow.flow_type {
+                FlowType::AnthropicMessages => "/v1/messages",
+                _ => "/v1/chat/completions",
+            }
+        } else {
+            flow.request.path.as_str()
+        };
+        let url = format!("{}{}", base_url, path);
+        // 发往本地服务，显式绕过出站代理
+        let client = crate::http_client::direct_client();
+
+        let mut results = Vec::with_capacity(mutations.len());
+        for mutation in mutations {
+            let started_at = Utc::now();
+            let body = match mutate_body(&flow.request.body, mutation) {
+                Ok(body) => body,
+                Err(e) => {
+                    results.push(FuzzResult {
+                        mutation: mutation.clone(),
+                        status: None,
+                        error: Some(e),
+                        request_bytes: 0,
+                        duration_ms: 0,
+                    });
+                    continue;
+                }
+            };
+            let request_bytes = body.len();
+
+            let (status, error) = match client
+                .post(&url)
+                .bearer_auth(api_key)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send()
+                .await
+            {
+                Ok(response) => {
+                    let status = response.status();
+                    let bytes = response.bytes().await.unwrap_or_default();
+                    let error = (!status.is_success()).then(|| extract_fuzz_error(&bytes));
+                    (Some(status.as_u16()), error)
+                }
+                Err(e) => (None, Some(e.to_string())),
+            };
+
+            tracing::debug!(
+                "[FUZZ] Flow {} 变异 {:?}: 状态 {:?}",
+                flow_id,
+                mutation,
+                status
+            );
+            results.push(FuzzResult {
+                mutation: mutation.clone(),
+                status,
+                error,
+                request_bytes,
+                duration_ms: (Utc::now() - started_at).num_milliseconds().max(0) as u64,
+            });
+        }
+        Ok(results)
+    }
+
     /// 获取 Flow
     async fn get_flow(&self, flow_id: &str) -> Result<LLMFlow, ReplayerError> {
         // 先从内存存储获取
@@ -456,6 +715,7 @@ impl FlowReplayer {
                 tags: vec!["replay".to_string()],
                 starred: false,
             },
+            shadow: None,
         };
 
         // 保存到内存存储
@@ -807,6 +1067,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mutate_body() {
+        let body = serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "hi"}],
+        });
+
+        let dropped = mutate_body(
+            &body,
+            &FuzzMutation::DropField {
+                path: "/messages/0/content".to_string(),
+            },
+        )
+        .unwrap();
+        let dropped: serde_json::Value = serde_json::from_slice(&dropped).unwrap();
+        assert!(dropped["messages"][0].get("content").is_none());
+        assert!(mutate_body(
+            &body,
+            &FuzzMutation::DropField {
+                path: "/missing".to_string()
+            }
+        )
+        .is_err());
+
+        let unknown = mutate_body(&body, &FuzzMutation::UnknownModel).unwrap();
+        let unknown: serde_json::Value = serde_json::from_slice(&unknown).unwrap();
+        assert_eq!(unknown["model"], FUZZ_UNKNOWN_MODEL);
+
+        let malformed = mutate_body(&body, &FuzzMutation::MalformedJson).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&malformed).is_err());
+
+        let oversized = mutate_body(&body, &FuzzMutation::OversizedBody { bytes: 4096 }).unwrap();
+        assert_eq!(oversized.len(), 4096);
+    }
+
+    #[test]
+    fn test_fuzz_mutation_deserialization() {
+        let mutation: FuzzMutation = serde_json::from_str(r#"{"type":"oversized_body"}"#).unwrap();
+        assert_eq!(
+            mutation,
+            FuzzMutation::OversizedBody {
+                bytes: DEFAULT_OVERSIZED_BYTES
+            }
+        );
+        assert_eq!(
+            extract_fuzz_error(br#"{"error":{"message":"bad","type":"invalid_request_error"}}"#),
+            "bad"
+        );
+    }
+
     #[test]
     fn test_request_modification_serialization() {
         let modification = RequestModification {
@@ -951,6 +1261,7 @@ mod property_tests {
                     tags: vec!["replay".to_string()],
                     starred: false,
                 },
+                shadow: None,
             };
 
             // 验证 1: 重放 Flow 应该有 "replay" 标签
@@ -15,7 +15,7 @@ use thiserror::Error;
 use uuid::Uuid;
 
 use super::exporter::{ExportFormat, ExportOptions, FlowExporter};
-use super::models::LLMFlow;
+use super::models::{LLMFlow, MessageRole};
 
 // ============================================================================
 // 错误类型
@@ -33,6 +33,9 @@ pub enum SessionError {
     #[error("Flow 不存在: {0}")]
     FlowNotFound(String),
 
+    #[error("无效操作: {0}")]
+    InvalidOperation(String),
+
     #[error("JSON 序列化错误: {0}")]
     Json(#[from] serde_json::Error),
 
@@ -95,6 +98,16 @@ pub struct AutoSessionConfig {
     pub time_window_ms: u64,
     /// 是否按客户端分组
     pub group_by_client: bool,
+    /// 是否按对话关联分组（对话 ID 请求头，或系统提示词 + 首条用户消息的指纹）
+    #[serde(default)]
+    pub group_by_conversation: bool,
+    /// 对话空闲超时（毫秒）- 超时后同一对话的新请求会进入新会话
+    #[serde(default = "default_idle_timeout_ms")]
+    pub idle_timeout_ms: u64,
+}
+
+fn default_idle_timeout_ms() -> u64 {
+    30 * 60 * 1000 // 30 分钟
 }
 
 impl Default for AutoSessionConfig {
@@ -103,6 +116,8 @@ impl Default for AutoSessionConfig {
             enabled: false,
             time_window_ms: 30_000, // 30 秒
             group_by_client: true,
+            group_by_conversation: false,
+            idle_timeout_ms: default_idle_timeout_ms(),
         }
     }
 }
@@ -135,6 +150,9 @@ pub struct SessionManager {
     /// 最近活跃会话缓存（用于自动检测）
     /// key: client_id 或 "default", value: (session_id, last_activity_time)
     active_sessions: Mutex<HashMap<String, (String, DateTime<Utc>)>>,
+    /// 对话关联缓存（用于按对话自动分组）
+    /// key: 对话指纹, value: (session_id, last_activity_time)
+    conversation_sessions: Mutex<HashMap<String, (String, DateTime<Utc>)>>,
 }
 
 impl SessionManager {
@@ -155,6 +173,7 @@ impl SessionManager {
             db: Mutex::new(conn),
             auto_config: Mutex::new(AutoSessionConfig::default()),
             active_sessions: Mutex::new(HashMap::new()),
+            conversation_sessions: Mutex::new(HashMap::new()),
         })
     }
 
@@ -166,6 +185,7 @@ impl SessionManager {
             db: Mutex::new(conn),
             auto_config: Mutex::new(AutoSessionConfig::default()),
             active_sessions: Mutex::new(HashMap::new()),
+            conversation_sessions: Mutex::new(HashMap::new()),
         })
     }
 
@@ -546,6 +566,103 @@ impl SessionManager {
         Ok(())
     }
 
+    /// 合并会话
+    ///
+    /// 将源会话的 Flow 关联移动到目标会话（两者都包含的 Flow 只保留一份），
+    /// 源会话的描述追加到目标会话描述之后。合并后源会话被删除，
+    /// 或在 `archive_source` 为 true 时保留为空的归档会话。
+    ///
+    /// # Arguments
+    /// * `source_id` - 源会话 ID
+    /// * `target_id` - 目标会话 ID
+    /// * `archive_source` - 是否归档而不是删除源会话
+    ///
+    /// # Returns
+    /// 合并后目标会话的 Flow 数量
+    pub fn merge_sessions(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        archive_source: bool,
+    ) -> Result<usize> {
+        if source_id == target_id {
+            return Err(SessionError::InvalidOperation(
+                "不能将会话合并到自身".to_string(),
+            ));
+        }
+
+        let mut conn = self.db.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let load_description = |id: &str| -> Result<Option<String>> {
+            tx.query_row(
+                "SELECT description FROM flow_sessions WHERE id = ?1",
+                params![id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?
+            .ok_or_else(|| SessionError::SessionNotFound(id.to_string()))
+        };
+        let source_description = load_description(source_id)?;
+        let target_description = load_description(target_id)?;
+
+        // 移动关联，保留原始添加时间；目标会话已有的 Flow 被忽略
+        tx.execute(
+            r#"
+            INSERT OR IGNORE INTO session_flows (session_id, flow_id, added_at)
+            SELECT ?1, flow_id, added_at FROM session_flows WHERE session_id = ?2
+            "#,
+            params![target_id, source_id],
+        )?;
+        tx.execute(
+            "DELETE FROM session_flows WHERE session_id = ?1",
+            params![source_id],
+        )?;
+
+        let description = match (target_description, source_description) {
+            (Some(target), Some(source)) if !source.is_empty() && source != target => {
+                Some(format!("{}\n\n{}", target, source))
+            }
+            (None, source) => source,
+            (target, _) => target,
+        };
+        let now = Utc::now().to_rfc3339();
+        tx.execute(
+            "UPDATE flow_sessions SET description = ?1, updated_at = ?2 WHERE id = ?3",
+            params![description, now, target_id],
+        )?;
+
+        if archive_source {
+            tx.execute(
+                "UPDATE flow_sessions SET archived = 1, updated_at = ?1 WHERE id = ?2",
+                params![now, source_id],
+            )?;
+        } else {
+            tx.execute(
+                "DELETE FROM flow_sessions WHERE id = ?1",
+                params![source_id],
+            )?;
+        }
+
+        let count: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM session_flows WHERE session_id = ?1",
+            params![target_id],
+            |row| row.get(0),
+        )?;
+        tx.commit()?;
+
+        // 自动检测中指向源会话的客户端和对话改为归入目标会话
+        for cache in [&self.active_sessions, &self.conversation_sessions] {
+            for (session_id, _) in cache.lock().unwrap().values_mut() {
+                if session_id == source_id {
+                    *session_id = target_id.to_string();
+                }
+            }
+        }
+
+        Ok(count as usize)
+    }
+
     /// 获取会话中的 Flow ID 列表
     ///
     /// # Arguments
@@ -700,6 +817,118 @@ impl SessionManager {
     pub fn clear_active_sessions(&self) {
         let mut active_sessions = self.active_sessions.lock().unwrap();
         active_sessions.clear();
+        self.conversation_sessions.lock().unwrap().clear();
+    }
+
+    /// 计算 Flow 的对话指纹
+    ///
+    /// 优先使用客户端会话 ID 请求头（`x-session-id`、`x-conversation-id` 等），
+    /// 否则对系统提示词和首条用户消息取哈希。多轮对话中这两者保持不变，
+    /// 同一次 Agent 运行的请求会得到相同的指纹。
+    pub fn conversation_fingerprint(flow: &LLMFlow) -> Option<String> {
+        use sha2::{Digest, Sha256};
+
+        if let Some(session_id) = &flow.metadata.client_info.session_id {
+            return Some(format!("id:{}", session_id));
+        }
+
+        let first_user = flow
+            .request
+            .messages
+            .iter()
+            .find(|m| matches!(m.role, MessageRole::User))?
+            .content
+            .get_all_text();
+
+        let mut hasher = Sha256::new();
+        hasher.update(flow.request.system_prompt.as_deref().unwrap_or_default());
+        hasher.update([0u8]);
+        hasher.update(first_user.as_bytes());
+        Some(format!("fp:{}", hex::encode(&hasher.finalize()[..16])))
+    }
+
+    /// 将新捕获的 Flow 自动归入会话
+    ///
+    /// 按对话分组时，同一对话指纹在空闲超时内的请求归入同一会话，
+    /// 首次出现或超时后创建新会话；否则按时间窗口归入已注册的活跃会话。
+    ///
+    /// # Returns
+    /// Flow 归入的会话 ID（未启用或没有匹配会话时为 None）
+    pub fn auto_assign(&self, flow: &LLMFlow) -> Result<Option<String>> {
+        let config = self.get_auto_config();
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        if !config.group_by_conversation {
+            let Some(session_id) = self.detect_session(flow) else {
+                return Ok(None);
+            };
+            self.add_flow(&session_id, &flow.id)?;
+            return Ok(Some(session_id));
+        }
+
+        let Some(fingerprint) = Self::conversation_fingerprint(flow) else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        self.close_idle_conversations_at(now);
+
+        let existing = self
+            .conversation_sessions
+            .lock()
+            .unwrap()
+            .get(&fingerprint)
+            .map(|(session_id, _)| session_id.clone());
+
+        let session_id = match existing {
+            Some(session_id) => match self.add_flow(&session_id, &flow.id) {
+                Ok(()) => session_id,
+                // 会话已被手动删除，重新创建
+                Err(SessionError::SessionNotFound(_)) => self.create_conversation_session(flow)?,
+                Err(e) => return Err(e),
+            },
+            None => self.create_conversation_session(flow)?,
+        };
+
+        self.conversation_sessions
+            .lock()
+            .unwrap()
+            .insert(fingerprint, (session_id.clone(), now));
+        Ok(Some(session_id))
+    }
+
+    /// 为对话创建新会话并加入 Flow
+    fn create_conversation_session(&self, flow: &LLMFlow) -> Result<String> {
+        let name = format!(
+            "{} · {}",
+            flow.request.model,
+            flow.timestamps.created.format("%m-%d %H:%M")
+        );
+        let description = match &flow.metadata.client_info.session_id {
+            Some(id) => format!("按对话自动分组（会话 ID: {}）", id),
+            None => "按对话自动分组".to_string(),
+        };
+        let session = self.create_session(&name, Some(&description))?;
+        self.add_flow(&session.id, &flow.id)?;
+        tracing::info!("[SESSION] 自动创建对话会话: {} ({})", session.id, name);
+        Ok(session.id)
+    }
+
+    /// 关闭空闲超时的对话关联，返回关闭的数量
+    ///
+    /// 已关闭的会话保留在列表中，同一对话的后续请求会进入新会话。
+    pub fn close_idle_conversations(&self) -> usize {
+        self.close_idle_conversations_at(Utc::now())
+    }
+
+    fn close_idle_conversations_at(&self, now: DateTime<Utc>) -> usize {
+        let idle_timeout =
+            chrono::Duration::milliseconds(self.get_auto_config().idle_timeout_ms as i64);
+        let mut conversations = self.conversation_sessions.lock().unwrap();
+        let before = conversations.len();
+        conversations.retain(|_, (_, last_activity)| now - *last_activity < idle_timeout);
+        before - conversations.len()
     }
 
     // ========================================================================
@@ -799,6 +1028,17 @@ impl SessionManager {
             .collect();
         Ok(ids)
     }
+
+    /// 获取所有会话（包括已归档）关联的 Flow ID
+    pub fn get_all_session_flow_ids(&self) -> Result<Vec<String>> {
+        let conn = self.db.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT DISTINCT flow_id FROM session_flows")?;
+        let ids: Vec<String> = stmt
+            .query_map([], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(ids)
+    }
 }
 
 // ============================================================================
@@ -910,6 +1150,160 @@ mod tests {
         assert!(retrieved.is_none());
     }
 
+    #[test]
+    fn test_merge_sessions_dedupes_flows() {
+        let manager = create_test_manager();
+
+        let source = manager
+            .create_session("Source", Some("second half"))
+            .unwrap();
+        let target = manager
+            .create_session("Target", Some("first half"))
+            .unwrap();
+        manager.add_flow(&source.id, "flow-1").unwrap();
+        manager.add_flow(&source.id, "flow-2").unwrap();
+        manager.add_flow(&target.id, "flow-2").unwrap();
+        manager.add_flow(&target.id, "flow-3").unwrap();
+
+        let count = manager
+            .merge_sessions(&source.id, &target.id, false)
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let merged = manager.get_session(&target.id).unwrap().unwrap();
+        assert_eq!(merged.flow_ids.len(), 3);
+        assert_eq!(
+            merged.description.as_deref(),
+            Some("first half\n\nsecond half")
+        );
+        assert!(manager.get_session(&source.id).unwrap().is_none());
+        assert_eq!(
+            manager.get_sessions_for_flow("flow-1").unwrap(),
+            vec![target.id]
+        );
+    }
+
+    #[test]
+    fn test_merge_sessions_archive_source() {
+        let manager = create_test_manager();
+
+        let source = manager.create_session("Source", None).unwrap();
+        let target = manager.create_session("Target", None).unwrap();
+        manager.add_flow(&source.id, "flow-1").unwrap();
+
+        let count = manager
+            .merge_sessions(&source.id, &target.id, true)
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let archived = manager.get_session(&source.id).unwrap().unwrap();
+        assert!(archived.archived);
+        assert!(archived.flow_ids.is_empty());
+
+        assert!(matches!(
+            manager.merge_sessions(&target.id, &target.id, false),
+            Err(SessionError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            manager.merge_sessions("non-existent", &target.id, false),
+            Err(SessionError::SessionNotFound(_))
+        ));
+    }
+
+    fn create_conversation_flow(id: &str, system: &str, first_user: &str) -> LLMFlow {
+        use crate::flow_monitor::models::{
+            FlowMetadata, FlowType, LLMRequest, Message, MessageContent,
+        };
+
+        let message = |role, text: &str| Message {
+            role,
+            content: MessageContent::Text(text.to_string()),
+            tool_calls: None,
+            tool_result: None,
+            name: None,
+        };
+        let request = LLMRequest {
+            model: "claude-sonnet-4".to_string(),
+            system_prompt: Some(system.to_string()),
+            messages: vec![
+                message(MessageRole::User, first_user),
+                message(MessageRole::Assistant, "ok"),
+                message(MessageRole::User, id),
+            ],
+            ..Default::default()
+        };
+        LLMFlow::new(
+            id.to_string(),
+            FlowType::ChatCompletions,
+            request,
+            FlowMetadata::default(),
+        )
+    }
+
+    fn enable_conversation_grouping(manager: &SessionManager, idle_timeout_ms: u64) {
+        manager.set_auto_config(AutoSessionConfig {
+            enabled: true,
+            group_by_conversation: true,
+            idle_timeout_ms,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_auto_assign_groups_by_conversation() {
+        let manager = create_test_manager();
+        enable_conversation_grouping(&manager, 60_000);
+
+        let first = create_conversation_flow("flow-1", "You are an agent", "fix the bug");
+        let second = create_conversation_flow("flow-2", "You are an agent", "fix the bug");
+        let other = create_conversation_flow("flow-3", "You are an agent", "write docs");
+
+        let session = manager.auto_assign(&first).unwrap().unwrap();
+        assert_eq!(manager.auto_assign(&second).unwrap(), Some(session.clone()));
+        let other_session = manager.auto_assign(&other).unwrap().unwrap();
+        assert_ne!(other_session, session);
+
+        assert_eq!(manager.get_session_flow_count(&session).unwrap(), 2);
+        assert_eq!(manager.get_session_flow_count(&other_session).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_auto_assign_prefers_conversation_id_header() {
+        let manager = create_test_manager();
+        enable_conversation_grouping(&manager, 60_000);
+
+        let mut first = create_conversation_flow("flow-1", "sys", "a");
+        first.metadata.client_info.session_id = Some("conv-1".to_string());
+        let mut second = create_conversation_flow("flow-2", "sys", "b");
+        second.metadata.client_info.session_id = Some("conv-1".to_string());
+
+        let session = manager.auto_assign(&first).unwrap();
+        assert!(session.is_some());
+        assert_eq!(manager.auto_assign(&second).unwrap(), session);
+    }
+
+    #[test]
+    fn test_idle_timeout_starts_new_session() {
+        let manager = create_test_manager();
+        enable_conversation_grouping(&manager, 0);
+
+        let first = create_conversation_flow("flow-1", "sys", "task");
+        let second = create_conversation_flow("flow-2", "sys", "task");
+
+        let session = manager.auto_assign(&first).unwrap();
+        assert_ne!(manager.auto_assign(&second).unwrap(), session);
+        assert_eq!(manager.close_idle_conversations(), 1);
+        assert_eq!(manager.session_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_auto_assign_disabled() {
+        let manager = create_test_manager();
+        let flow = create_conversation_flow("flow-1", "sys", "task");
+        assert!(manager.auto_assign(&flow).unwrap().is_none());
+        assert_eq!(manager.session_count().unwrap(), 0);
+    }
+
     #[test]
     fn test_session_not_found() {
         let manager = create_test_manager();
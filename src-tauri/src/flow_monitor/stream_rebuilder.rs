@@ -217,6 +217,9 @@ impl StreamRebuilder {
                         self.format = StreamFormat::OpenAI;
                         self.process_openai_chunk(data, &mut chunk)
                     }
+                } else if data.contains("\"candidates\"") {
+                    self.format = StreamFormat::Gemini;
+                    self.process_gemini_chunk(data, &mut chunk)
                 } else {
                     // 尝试 OpenAI 格式
                     self.format = StreamFormat::OpenAI;
@@ -345,7 +348,7 @@ impl StreamRebuilder {
     }
 
     /// 解析 OpenAI 停止原因
-    fn parse_openai_stop_reason(reason: &str) -> StopReason {
+    pub(crate) fn parse_openai_stop_reason(reason: &str) -> StopReason {
         match reason {
             "stop" => StopReason::Stop,
             "length" => StopReason::Length,
@@ -608,7 +611,7 @@ impl StreamRebuilder {
     }
 
     /// 解析 Anthropic 停止原因
-    fn parse_anthropic_stop_reason(reason: &str) -> StopReason {
+    pub(crate) fn parse_anthropic_stop_reason(reason: &str) -> StopReason {
         match reason {
             "end_turn" => StopReason::EndTurn,
             "stop_sequence" => StopReason::Stop,
@@ -625,37 +628,85 @@ impl StreamRebuilder {
     /// data: {"candidates":[{"content":{"parts":[{"text":"Hello"}],"role":"model"},
     ///        "finishReason":"STOP","index":0}],"usageMetadata":{"promptTokenCount":10,"candidatesTokenCount":5}}
     /// ```
+    ///
+    /// 同时兼容：
+    /// - Antigravity / Code Assist 的 `{"response": {...}}` 包装
+    /// - 非 SSE 的 JSON 数组流（chunk 带有前导 `[` / `,` 或结尾 `]`）
+    /// - `thought: true` 的思维链 part（写入思维链而不是正文）
+    ///
+    /// 只合并 index 为 0 的候选，避免多候选时内容交错。
     fn process_gemini_chunk(
         &mut self,
         data: &str,
         chunk: &mut StreamChunk,
     ) -> Result<(), StreamRebuilderError> {
-        let data = data.trim();
-
-        // 空数据跳过
-        if data.is_empty() {
+        let data = data
+            .trim()
+            .trim_start_matches(['[', ','])
+            .trim_end_matches([']', ','])
+            .trim();
+
+        // 空数据和结束标记跳过
+        if data.is_empty() || data == "[DONE]" {
             return Ok(());
         }
 
         // 解析 JSON
         let json: serde_json::Value = serde_json::from_str(data)?;
+        let json = json.get("response").unwrap_or(&json);
+
+        if self.response_id.is_none() {
+            self.response_id = json
+                .get("responseId")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+        }
+        if self.model.is_none() {
+            self.model = json
+                .get("modelVersion")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+        }
 
         // 处理 candidates
         if let Some(candidates) = json.get("candidates").and_then(|v| v.as_array()) {
             for candidate in candidates {
+                if candidate.get("index").and_then(|v| v.as_u64()).unwrap_or(0) != 0 {
+                    continue;
+                }
+
                 // 处理内容
-                if let Some(content) = candidate.get("content") {
-                    if let Some(parts) = content.get("parts").and_then(|v| v.as_array()) {
-                        for part in parts {
-                            if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
+                if let Some(parts) = candidate
+                    .get("content")
+                    .and_then(|c| c.get("parts"))
+                    .and_then(|v| v.as_array())
+                {
+                    for part in parts {
+                        if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
+                            let is_thought = part
+                                .get("thought")
+                                .and_then(|v| v.as_bool())
+                                .unwrap_or(false);
+                            if is_thought {
+                                self.thinking_buffer
+                                    .get_or_insert_with(String::new)
+                                    .push_str(text);
+                                chunk
+                                    .thinking_delta
+                                    .get_or_insert_with(String::new)
+                                    .push_str(text);
+                            } else {
                                 self.content_buffer.push_str(text);
-                                chunk.content_delta = Some(text.to_string());
+                                chunk
+                                    .content_delta
+                                    .get_or_insert_with(String::new)
+                                    .push_str(text);
                             }
+                        }
 
-                            // 处理函数调用
-                            if let Some(function_call) = part.get("functionCall") {
-                                self.process_gemini_function_call(function_call, chunk)?;
-                            }
+                        // 处理函数调用
+                        if let Some(function_call) = part.get("functionCall") {
+                            self.process_gemini_function_call(function_call, chunk)?;
                         }
                     }
                 }
@@ -733,6 +784,9 @@ impl StreamRebuilder {
         if let Some(total_tokens) = usage.get("totalTokenCount").and_then(|v| v.as_u64()) {
             self.usage.total_tokens = total_tokens as u32;
         }
+        if let Some(thoughts_tokens) = usage.get("thoughtsTokenCount").and_then(|v| v.as_u64()) {
+            self.usage.thinking_tokens = Some(thoughts_tokens as u32);
+        }
     }
 
     /// 完成流重建，返回完整的 LLM 响应
@@ -938,6 +992,14 @@ impl StreamRebuilder {
     fn build_gemini_response_body(&self, tool_calls: &[ToolCall]) -> serde_json::Value {
         let mut parts: Vec<serde_json::Value> = Vec::new();
 
+        // 添加思维链内容
+        if let Some(ref thinking) = self.thinking_buffer {
+            parts.push(serde_json::json!({
+                "text": thinking,
+                "thought": true,
+            }));
+        }
+
         // 添加文本内容
         if !self.content_buffer.is_empty() {
             parts.push(serde_json::json!({
@@ -1213,6 +1275,47 @@ mod tests {
         assert_eq!(response.usage.output_tokens, 5);
     }
 
+    #[test]
+    fn test_gemini_wrapped_stream_with_thoughts() {
+        let mut rebuilder = StreamRebuilder::new(StreamFormat::Gemini);
+
+        // Antigravity 包装 + JSON 数组流 + 思维链 part
+        let chunks = vec![
+            r#"[{"response":{"candidates":[{"content":{"parts":[{"text":"Thinking...","thought":true}],"role":"model"}}],"modelVersion":"gemini-2.5-pro","responseId":"resp-1"}}"#,
+            r#",{"response":{"candidates":[{"content":{"parts":[{"text":"Hello"},{"text":" there"}],"role":"model"}},{"index":1,"content":{"parts":[{"text":"other"}]}}]}}"#,
+            r#",{"response":{"candidates":[{"content":{"parts":[{"functionCall":{"name":"get_weather","args":{"city":"Paris"}}}],"role":"model"},"finishReason":"STOP"}],"usageMetadata":{"promptTokenCount":8,"candidatesTokenCount":4,"thoughtsTokenCount":3}}}]"#,
+        ];
+        for chunk in chunks {
+            rebuilder.process_event(None, chunk).unwrap();
+        }
+
+        let response = rebuilder.finish();
+        assert_eq!(response.content, "Hello there");
+        assert_eq!(response.thinking.as_ref().unwrap().text, "Thinking...");
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].function.name, "get_weather");
+        assert_eq!(response.usage.thinking_tokens, Some(3));
+        assert_eq!(
+            response.body["candidates"][0]["content"]["parts"][0]["thought"],
+            true
+        );
+        assert_eq!(
+            response.body["candidates"][0]["content"]["parts"][1]["text"],
+            "Hello there"
+        );
+
+        // 未指定格式时根据 candidates 自动识别
+        let mut rebuilder = StreamRebuilder::new(StreamFormat::Unknown);
+        rebuilder
+            .process_event(
+                None,
+                r#"{"candidates":[{"content":{"parts":[{"text":"Hi"}],"role":"model"}}]}"#,
+            )
+            .unwrap();
+        assert_eq!(rebuilder.format(), StreamFormat::Gemini);
+        assert_eq!(rebuilder.content(), "Hi");
+    }
+
     #[test]
     fn test_done_signal() {
         let mut rebuilder = StreamRebuilder::new(StreamFormat::OpenAI);
@@ -41,6 +41,7 @@ mod converter;
 mod data;
 #[cfg(debug_assertions)]
 mod dev_bridge;
+mod http_client;
 mod logger;
 mod models;
 mod providers;
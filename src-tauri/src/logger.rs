@@ -9,7 +9,10 @@ use std::fs::{self, OpenOptions};
 use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+
+/// 日志实时订阅通道容量，订阅方落后超过该条数时丢弃最旧的日志
+const LOG_BROADCAST_CAPACITY: usize = 256;
 
 #[derive(Debug, Clone)]
 pub struct LogStoreConfig {
@@ -42,6 +45,8 @@ pub struct LogStore {
     max_logs: usize,
     config: LogStoreConfig,
     log_file_path: Option<PathBuf>,
+    /// 新日志广播（实时订阅）
+    sender: broadcast::Sender<LogEntry>,
 }
 
 impl Default for LogStore {
@@ -64,6 +69,7 @@ impl Default for LogStore {
             max_logs: config.max_logs,
             config,
             log_file_path: Some(log_file),
+            sender: broadcast::channel(LOG_BROADCAST_CAPACITY).0,
         }
     }
 }
@@ -91,6 +97,8 @@ impl LogStore {
         };
 
         self.logs.push_back(entry.clone());
+        // 没有订阅方时发送失败，忽略即可
+        let _ = self.sender.send(entry);
 
         // 写入日志文件
         if self.config.enable_file_logging {
@@ -134,6 +142,11 @@ impl LogStore {
         self.logs.iter().cloned().collect()
     }
 
+    /// 订阅之后新增的日志
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.sender.subscribe()
+    }
+
     pub fn clear(&mut self) {
         self.logs.clear();
     }
@@ -260,6 +273,33 @@ impl LogStore {
     }
 }
 
+/// 日志级别的严重程度，未知级别按 info 处理
+fn level_rank(level: &str) -> u8 {
+    match level.to_ascii_lowercase().as_str() {
+        "trace" => 0,
+        "debug" => 1,
+        "warn" | "warning" => 3,
+        "error" => 4,
+        _ => 2,
+    }
+}
+
+/// 日志订阅过滤
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogFilter {
+    /// 最低日志级别（debug/info/warn/error），为空时不过滤
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.level
+            .as_deref()
+            .is_none_or(|min_level| level_rank(&entry.level) >= level_rank(min_level))
+    }
+}
+
 #[allow(dead_code)]
 pub type SharedLogStore = Arc<RwLock<LogStore>>;
 
@@ -315,7 +355,42 @@ pub fn sanitize_log_message(message: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::sanitize_log_message;
+    use super::{sanitize_log_message, LogEntry, LogFilter, LogStore};
+
+    #[test]
+    fn test_log_filter_matches_min_level() {
+        let entry = |level: &str| LogEntry {
+            timestamp: String::new(),
+            level: level.to_string(),
+            message: String::new(),
+        };
+        let warn = LogFilter {
+            level: Some("warn".to_string()),
+        };
+        assert!(warn.matches(&entry("error")));
+        assert!(warn.matches(&entry("WARN")));
+        assert!(!warn.matches(&entry("info")));
+        assert!(!warn.matches(&entry("debug")));
+        assert!(LogFilter::default().matches(&entry("debug")));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_new_entries() {
+        let mut store = LogStore::new();
+        store.config.enable_file_logging = false;
+        store.add("info", "before subscribe");
+
+        let mut first = store.subscribe();
+        let mut second = store.subscribe();
+        store.add("warn", "token=abc123");
+
+        for receiver in [&mut first, &mut second] {
+            let entry = receiver.recv().await.unwrap();
+            assert_eq!(entry.level, "warn");
+            assert_eq!(entry.message, "token: ***");
+            assert!(receiver.try_recv().is_err());
+        }
+    }
 
     #[test]
     fn test_sanitize_bearer_token() {
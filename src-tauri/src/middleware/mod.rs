@@ -3,8 +3,14 @@
 //! 提供 HTTP 请求处理的中间件组件
 
 pub mod management_auth;
+pub mod proxy_pause;
+pub mod request_priority;
+pub mod user_limits;
 
 #[cfg(test)]
 mod tests;
 
 pub use management_auth::{ManagementAuthLayer, ManagementAuthService};
+pub use proxy_pause::{ProxyPauseLayer, ProxyPauseService};
+pub use request_priority::{RequestPriorityLayer, RequestPriorityService};
+pub use user_limits::{UserLimitsLayer, UserLimitsService};
@@ -0,0 +1,156 @@
+//! 代理暂停（全局熔断开关）中间件
+//!
+//! 当代理被暂停时，所有补全类路由直接返回 503 Service Unavailable，
+//! 并附带 Retry-After 头，不再向上游发送任何请求。
+//!
+//! `/health` 与管理端点不挂载此中间件，因此暂停期间仍可正常访问。
+
+use axum::{
+    body::Body,
+    http::{header, Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// 暂停期间建议客户端等待的秒数
+const PAUSED_RETRY_AFTER_SECS: u64 = 30;
+
+/// 代理暂停层
+///
+/// 与 `ServerState::proxy_paused` 共享同一个 `AtomicBool`
+#[derive(Clone)]
+pub struct ProxyPauseLayer {
+    paused: Arc<AtomicBool>,
+}
+
+impl ProxyPauseLayer {
+    /// 创建新的暂停层
+    pub fn new(paused: Arc<AtomicBool>) -> Self {
+        Self { paused }
+    }
+}
+
+impl<S> Layer<S> for ProxyPauseLayer {
+    type Service = ProxyPauseService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ProxyPauseService {
+            inner,
+            paused: self.paused.clone(),
+        }
+    }
+}
+
+/// 代理暂停服务
+#[derive(Clone)]
+pub struct ProxyPauseService<S> {
+    inner: S,
+    paused: Arc<AtomicBool>,
+}
+
+impl<S> Service<Request<Body>> for ProxyPauseService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        if self.paused.load(Ordering::SeqCst) {
+            tracing::debug!("[PROXY_PAUSE] 代理已暂停，拒绝请求: {}", req.uri().path());
+            return Box::pin(async move { Ok(create_paused_response()) });
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(async move { inner.call(req).await })
+    }
+}
+
+/// 创建代理暂停时的 503 响应
+pub fn create_paused_response() -> Response<Body> {
+    let body = serde_json::json!({
+        "error": {
+            "code": StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+            "type": "proxy_paused",
+            "message": "Proxy is paused by administrator",
+            "retry_after_seconds": PAUSED_RETRY_AFTER_SECS
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header("content-type", "application/json")
+        .header(header::RETRY_AFTER, PAUSED_RETRY_AFTER_SECS.to_string())
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    #[derive(Clone)]
+    struct OkService;
+
+    impl Service<Request<Body>> for OkService {
+        type Response = Response<Body>;
+        type Error = Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            Box::pin(async { Ok(Response::new(Body::empty())) })
+        }
+    }
+
+    fn request() -> Request<Body> {
+        Request::builder()
+            .uri("/v1/chat/completions")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_passes_through_when_not_paused() {
+        let paused = Arc::new(AtomicBool::new(false));
+        let mut service = ProxyPauseLayer::new(paused).layer(OkService);
+
+        let response = service.call(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_returns_503_with_retry_after_when_paused() {
+        let paused = Arc::new(AtomicBool::new(false));
+        let mut service = ProxyPauseLayer::new(paused.clone()).layer(OkService);
+
+        paused.store(true, Ordering::SeqCst);
+        let response = service.call(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.headers().get(header::RETRY_AFTER).unwrap(),
+            &PAUSED_RETRY_AFTER_SECS.to_string()
+        );
+
+        paused.store(false, Ordering::SeqCst);
+        let response = service.call(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
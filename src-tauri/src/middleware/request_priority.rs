@@ -0,0 +1,127 @@
+//! 请求优先级中间件
+//!
+//! 为补全类路由的每个请求确定优先级，并在该优先级下执行后续处理，
+//! 并发已满时交互式请求优先获得上游槽位。
+//!
+//! 优先级判定顺序：
+//! 1. `x-priority` 请求头（`interactive` / `high`、`batch` / `low` / `background`）
+//! 2. 已识别的客户端（Claude Code、Cursor 等，按 User-Agent）视为交互式
+//! 3. 其余请求（脚本、回放等）视为批量
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, Request, Response},
+};
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::resilience::priority::{self, RequestPriority};
+use crate::server::client_detector::ClientType;
+
+/// 请求优先级请求头
+pub const PRIORITY_HEADER: &str = "x-priority";
+
+/// 按请求头判定请求优先级
+pub fn classify(headers: &HeaderMap) -> RequestPriority {
+    if let Some(priority) = headers
+        .get(PRIORITY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(RequestPriority::parse)
+    {
+        return priority;
+    }
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    match ClientType::from_user_agent(user_agent) {
+        ClientType::Other => RequestPriority::Batch,
+        _ => RequestPriority::Interactive,
+    }
+}
+
+/// 请求优先级层
+#[derive(Clone, Default)]
+pub struct RequestPriorityLayer;
+
+impl RequestPriorityLayer {
+    /// 创建新的优先级层
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for RequestPriorityLayer {
+    type Service = RequestPriorityService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestPriorityService { inner }
+    }
+}
+
+/// 请求优先级服务
+#[derive(Clone)]
+pub struct RequestPriorityService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for RequestPriorityService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let request_priority = classify(req.headers());
+        let mut inner = self.inner.clone();
+        Box::pin(priority::with_priority(request_priority, async move {
+            inner.call(req).await
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(&headers(&[("user-agent", "claude-code/2.0.1")])),
+            RequestPriority::Interactive
+        );
+        assert_eq!(
+            classify(&headers(&[("user-agent", "python-requests/2.31")])),
+            RequestPriority::Batch
+        );
+        // 请求头优先于客户端识别
+        assert_eq!(
+            classify(&headers(&[
+                ("user-agent", "claude-code/2.0.1"),
+                ("x-priority", "batch")
+            ])),
+            RequestPriority::Batch
+        );
+        assert_eq!(
+            classify(&headers(&[("x-priority", "interactive")])),
+            RequestPriority::Interactive
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! 按终端用户限流中间件
+//!
+//! 为补全类路由统一执行 `limits.per_user` 限制：从 JSON 请求体中读取用户标识
+//! （OpenAI `user`、Anthropic `metadata.user_id`，Message Batches 按每条请求读取），
+//! 超出频率或配额时直接返回 429，不再进入处理器。
+//!
+//! 未配置按用户限制时不读取请求体。WebSocket 请求的标识在消息内，由 WebSocket
+//! 处理器单独检查。
+
+use axum::{
+    body::Body,
+    http::{Method, Request, Response},
+};
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::processor::{ErrorFormat, ProcessError};
+use crate::server::user_limits;
+
+/// 请求体中携带的用户标识（原始值，未规范化）
+///
+/// 依次读取 OpenAI `user`、Anthropic `metadata.user_id`；批量请求读取 `requests[].params`。
+pub fn request_user_ids(body: &serde_json::Value) -> Vec<&str> {
+    fn user_of(params: &serde_json::Value) -> Option<&str> {
+        params
+            .get("user")
+            .and_then(|v| v.as_str())
+            .or_else(|| params.get("metadata")?.get("user_id")?.as_str())
+    }
+
+    match body.get("requests").and_then(|v| v.as_array()) {
+        Some(requests) => requests
+            .iter()
+            .filter_map(|r| r.get("params").and_then(user_of))
+            .collect(),
+        None => user_of(body).into_iter().collect(),
+    }
+}
+
+/// 按路由确定错误响应格式
+fn error_format(path: &str) -> ErrorFormat {
+    if path.ends_with("/messages") || path.contains("/messages/batches") {
+        ErrorFormat::Anthropic
+    } else {
+        ErrorFormat::OpenAI
+    }
+}
+
+/// 检查请求体中的所有用户标识，任一超限时返回错误
+fn check_body(bytes: &[u8]) -> Result<(), ProcessError> {
+    let Ok(body) = serde_json::from_slice::<serde_json::Value>(bytes) else {
+        // 请求体格式错误由处理器返回 400
+        return Ok(());
+    };
+    for user in request_user_ids(&body)
+        .into_iter()
+        .filter_map(user_limits::user_key)
+    {
+        if let Err(e) = user_limits::check_and_record(&user) {
+            tracing::warn!("[USER_LIMITS] user={} {}", user, e);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// 按用户限流层
+#[derive(Clone)]
+pub struct UserLimitsLayer {
+    body_limit: usize,
+}
+
+impl UserLimitsLayer {
+    /// 创建新的按用户限流层，`body_limit` 与路由的请求体大小上限一致
+    pub fn new(body_limit: usize) -> Self {
+        Self { body_limit }
+    }
+}
+
+impl<S> Layer<S> for UserLimitsLayer {
+    type Service = UserLimitsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        UserLimitsService {
+            inner,
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+/// 按用户限流服务
+#[derive(Clone)]
+pub struct UserLimitsService<S> {
+    inner: S,
+    body_limit: usize,
+}
+
+impl<S> Service<Request<Body>> for UserLimitsService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        if req.method() != Method::POST || !user_limits::is_enabled() {
+            return Box::pin(async move { inner.call(req).await });
+        }
+
+        let body_limit = self.body_limit;
+        Box::pin(async move {
+            let format = error_format(req.uri().path());
+            let (parts, body) = req.into_parts();
+            let bytes = match axum::body::to_bytes(body, body_limit).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    return Ok(ProcessError::InvalidRequest(format!(
+                        "Failed to read request body: {}",
+                        e
+                    ))
+                    .into_response_for(format));
+                }
+            };
+            if let Err(e) = check_body(&bytes) {
+                return Ok(e.into_response_for(format));
+            }
+            inner
+                .call(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_user_ids() {
+        assert_eq!(
+            request_user_ids(&json!({"model": "gpt-4o", "user": "alice"})),
+            vec!["alice"]
+        );
+        assert_eq!(
+            request_user_ids(&json!({"metadata": {"user_id": "bob"}})),
+            vec!["bob"]
+        );
+        assert!(request_user_ids(&json!({"model": "gpt-4o"})).is_empty());
+        assert_eq!(
+            request_user_ids(&json!({"requests": [
+                {"custom_id": "1", "params": {"metadata": {"user_id": "carol"}}},
+                {"custom_id": "2", "params": {}},
+                {"custom_id": "3", "params": {"metadata": {"user_id": "dave"}}}
+            ]})),
+            vec!["carol", "dave"]
+        );
+    }
+
+    #[test]
+    fn test_error_format_by_path() {
+        assert_eq!(error_format("/v1/messages"), ErrorFormat::Anthropic);
+        assert_eq!(error_format("/kiro/v1/messages"), ErrorFormat::Anthropic);
+        assert_eq!(error_format("/v1/messages/batches"), ErrorFormat::Anthropic);
+        assert_eq!(
+            error_format("/api/provider/openai/v1/chat/completions"),
+            ErrorFormat::OpenAI
+        );
+    }
+}
@@ -1,5 +1,6 @@
 //! Anthropic/Claude API 数据模型
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -51,7 +52,14 @@ pub struct AnthropicTool {
     pub input_schema: Option<serde_json::Value>,
 }
 
+/// Anthropic Messages 请求
+///
+/// 反序列化时会先把 OpenAI 风格的工具字段（`functions` / `function_call`、
+/// `{"type": "function", "function": {...}}` 形式的 `tools` 以及字符串形式的
+/// `tool_choice`）转换为 Anthropic 的 `tools` / `tool_choice`，
+/// 参见 [`normalize_openai_tool_fields`]。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct AnthropicMessagesRequest {
     pub model: String,
     pub messages: Vec<AnthropicMessage>,
@@ -67,6 +75,179 @@ pub struct AnthropicMessagesRequest {
     pub tools: Option<Vec<AnthropicTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<serde_json::Value>,
+    /// 停止序列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    /// 请求元数据（`user_id` 用于滥用追踪和按用户统计）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<AnthropicRequestMetadata>,
+}
+
+/// Anthropic 请求元数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnthropicRequestMetadata {
+    /// 终端用户标识
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+}
+
+impl Serialize for AnthropicMessagesRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        AnthropicMessagesRequest::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AnthropicMessagesRequest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        normalize_openai_tool_fields(&mut value);
+        AnthropicMessagesRequest::deserialize(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 将请求体中 OpenAI 风格的工具字段转换为 Anthropic 格式
+///
+/// - `functions` 追加到 `tools`，`parameters` 改名为 `input_schema`
+/// - `tools` 中 `{"type": "function", "function": {...}}` 形式的条目同样展开
+/// - `function_call` 在未设置 `tool_choice` 时转换为 `tool_choice`
+/// - `tool_choice` 的 `"auto"` / `"none"` / `"required"` 以及
+///   `{"type": "function", "function": {"name": ...}}` 转换为对应的 Anthropic 对象
+/// - `parallel_tool_calls: false` 转换为 `tool_choice.disable_parallel_tool_use`
+///
+/// 已经是 Anthropic 格式的字段保持不变。
+pub fn normalize_openai_tool_fields(body: &mut Value) {
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+
+    let functions = obj.remove("functions");
+    let function_call = obj.remove("function_call");
+    let parallel_tool_calls = obj.remove("parallel_tool_calls");
+
+    if let Some(Value::Array(tools)) = obj.get_mut("tools") {
+        for tool in tools.iter_mut() {
+            if tool.get("type").and_then(|t| t.as_str()) == Some("function") {
+                if let Some(function) = tool.get("function") {
+                    *tool = openai_function_to_tool(function);
+                }
+            }
+        }
+    }
+
+    if let Some(Value::Array(functions)) = functions {
+        let converted = functions.iter().map(openai_function_to_tool);
+        match obj.get_mut("tools") {
+            Some(Value::Array(tools)) => tools.extend(converted),
+            _ => {
+                obj.insert("tools".to_string(), Value::Array(converted.collect()));
+            }
+        }
+    }
+
+    let tool_choice = match obj.remove("tool_choice") {
+        Some(Value::Null) | None => function_call,
+        choice => choice,
+    };
+    if let Some(choice) = tool_choice.and_then(|c| openai_tool_choice_to_anthropic(&c)) {
+        obj.insert("tool_choice".to_string(), choice);
+    }
+
+    if parallel_tool_calls == Some(Value::Bool(false)) {
+        let choice = obj
+            .entry("tool_choice")
+            .or_insert_with(|| serde_json::json!({"type": "auto"}));
+        // `none` 不允许设置 disable_parallel_tool_use
+        if let Some(choice) = choice
+            .as_object_mut()
+            .filter(|c| c.get("type").and_then(|t| t.as_str()) != Some("none"))
+        {
+            choice.insert("disable_parallel_tool_use".to_string(), Value::Bool(true));
+        }
+    }
+}
+
+/// OpenAI 函数定义 -> Anthropic 工具定义
+fn openai_function_to_tool(function: &Value) -> Value {
+    let mut tool = serde_json::Map::new();
+    for (from, to) in [
+        ("name", "name"),
+        ("description", "description"),
+        ("parameters", "input_schema"),
+    ] {
+        if let Some(v) = function.get(from).filter(|v| !v.is_null()) {
+            tool.insert(to.to_string(), v.clone());
+        }
+    }
+    Value::Object(tool)
+}
+
+/// OpenAI `tool_choice` / `function_call` -> Anthropic `tool_choice`
+///
+/// Anthropic 格式的对象原样返回；无法识别的字符串返回 None（不设置）。
+fn openai_tool_choice_to_anthropic(choice: &Value) -> Option<Value> {
+    match choice {
+        Value::String(s) => match s.as_str() {
+            "auto" => Some(serde_json::json!({"type": "auto"})),
+            "none" => Some(serde_json::json!({"type": "none"})),
+            "required" | "any" => Some(serde_json::json!({"type": "any"})),
+            _ => None,
+        },
+        Value::Object(obj) => {
+            // {"type": "function", "function": {"name": ...}}
+            if let Some(name) = obj
+                .get("function")
+                .and_then(|f| f.get("name"))
+                .and_then(|n| n.as_str())
+            {
+                return Some(serde_json::json!({"type": "tool", "name": name}));
+            }
+            // function_call: {"name": ...}
+            if obj.get("type").is_none() {
+                if let Some(name) = obj.get("name").and_then(|n| n.as_str()) {
+                    return Some(serde_json::json!({"type": "tool", "name": name}));
+                }
+            }
+            Some(choice.clone())
+        }
+        _ => None,
+    }
+}
+
+impl AnthropicMessagesRequest {
+    /// 是否通过 `tool_choice.disable_parallel_tool_use` 禁用了并行工具调用
+    pub fn parallel_tool_use_disabled(&self) -> bool {
+        self.tool_choice
+            .as_ref()
+            .and_then(|c| c.get("disable_parallel_tool_use"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// 请求携带的终端用户标识
+    pub fn user_id(&self) -> Option<&str> {
+        self.metadata.as_ref()?.user_id.as_deref()
+    }
+
+    /// 第一个 `url` 类型图片块的地址（包括 tool_result 中的图片）
+    pub fn first_remote_image_url(&self) -> Option<&str> {
+        fn find(blocks: &serde_json::Value) -> Option<&str> {
+            blocks.as_array()?.iter().find_map(|block| {
+                match block.get("type").and_then(|t| t.as_str()) {
+                    Some("image") => {
+                        let source = block.get("source")?;
+                        if source.get("type")?.as_str()? == "url" {
+                            source.get("url")?.as_str()
+                        } else {
+                            None
+                        }
+                    }
+                    Some("tool_result") => find(block.get("content")?),
+                    _ => None,
+                }
+            })
+        }
+        self.messages.iter().find_map(|msg| find(&msg.content))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,3 +321,92 @@ pub enum AnthropicDelta {
 pub struct AnthropicMessageDelta {
     pub stop_reason: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_functions_normalized_to_anthropic_tools() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4",
+            "messages": [{"role": "user", "content": "weather?"}],
+            "functions": [{
+                "name": "get_weather",
+                "description": "Get weather",
+                "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }],
+            "function_call": {"name": "get_weather"}
+        }))
+        .unwrap();
+
+        let tools = request.tools.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "get_weather");
+        assert_eq!(tools[0].description.as_deref(), Some("Get weather"));
+        assert_eq!(tools[0].input_schema.as_ref().unwrap()["type"], "object");
+        assert_eq!(
+            request.tool_choice,
+            Some(serde_json::json!({"type": "tool", "name": "get_weather"}))
+        );
+    }
+
+    #[test]
+    fn test_openai_and_anthropic_tools_parse_to_same_request() {
+        let openai: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4",
+            "messages": [{"role": "user", "content": "hi"}],
+            "tools": [{
+                "type": "function",
+                "function": {"name": "search", "parameters": {"type": "object"}}
+            }],
+            "tool_choice": "required"
+        }))
+        .unwrap();
+        let anthropic: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4",
+            "messages": [{"role": "user", "content": "hi"}],
+            "tools": [{"name": "search", "input_schema": {"type": "object"}}],
+            "tool_choice": {"type": "any"}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&openai).unwrap(),
+            serde_json::to_value(&anthropic).unwrap()
+        );
+        assert!(serde_json::to_value(&openai)
+            .unwrap()
+            .get("functions")
+            .is_none());
+    }
+
+    #[test]
+    fn test_parallel_tool_calls_false_disables_parallel_tool_use() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4",
+            "messages": [{"role": "user", "content": "hi"}],
+            "tools": [{"name": "search", "input_schema": {"type": "object"}}],
+            "parallel_tool_calls": false
+        }))
+        .unwrap();
+        assert!(request.parallel_tool_use_disabled());
+        assert_eq!(
+            request.tool_choice,
+            Some(serde_json::json!({"type": "auto", "disable_parallel_tool_use": true}))
+        );
+
+        let request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4",
+            "messages": [{"role": "user", "content": "hi"}],
+            "tool_choice": "none",
+            "parallel_tool_calls": false
+        }))
+        .unwrap();
+        assert!(!request.parallel_tool_use_disabled());
+        assert_eq!(
+            request.tool_choice,
+            Some(serde_json::json!({"type": "none"}))
+        );
+    }
+}
@@ -122,6 +122,58 @@ pub struct MachineIdValidation {
     pub formatted_id: Option<String>,
 }
 
+/// 单个机器码存储位置的读取结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MachineIdLocation {
+    /// 位置标识（注册表路径或文件路径）
+    pub location: String,
+    /// 读取到的原始值（读取失败或不存在时为 None）
+    pub value: Option<String>,
+    /// 是否可写入
+    pub writable: bool,
+    /// 读取错误信息
+    pub error: Option<String>,
+}
+
+/// 机器码一致性审计结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MachineIdAudit {
+    /// 操作系统平台
+    pub platform: String,
+    /// 所有已知位置的读取结果
+    pub locations: Vec<MachineIdLocation>,
+    /// 所有可读位置的值是否一致（忽略格式差异）
+    pub consistent: bool,
+    /// 归一化后的不同取值（32 位小写十六进制）
+    pub distinct_values: Vec<String>,
+}
+
+/// 修复前的机器码快照，用于回滚
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MachineIdRepairBackup {
+    /// 备份时间戳
+    pub timestamp: i64,
+    /// 操作系统平台
+    pub platform: String,
+    /// 修复前各位置的值
+    pub locations: Vec<MachineIdLocation>,
+}
+
+/// 机器码修复结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MachineIdRepairResult {
+    /// 操作是否成功
+    pub success: bool,
+    /// 结果消息
+    pub message: String,
+    /// 是否需要管理员权限
+    pub requires_admin: bool,
+    /// 修复前快照的备份文件路径
+    pub backup_path: Option<String>,
+    /// 已写入的位置
+    pub updated_locations: Vec<String>,
+}
+
 impl MachineIdFormat {
     /// 从字符串检测机器码格式
     pub fn detect(machine_id: &str) -> Self {
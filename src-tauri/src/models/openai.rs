@@ -11,6 +11,7 @@
 //!
 //! - 2025-12-27: 添加 web_search 工具支持，修复 Issue #49
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageUrl {
@@ -163,6 +164,130 @@ pub struct ChatCompletionRequest {
     /// 思维链强度：none, low, medium, high
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<String>,
+    /// 采样随机种子（用于可复现的输出）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// 是否返回 token 对数概率
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    /// 每个位置返回的候选 token 数量（需要 logprobs 为 true）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u32>,
+    /// token ID -> 偏置值（-100 到 100）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<HashMap<String, f32>>,
+    /// 频率惩罚（-2.0 到 2.0）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    /// 存在惩罚（-2.0 到 2.0）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    /// 终端用户标识（用于滥用追踪和按用户统计）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// 停止序列（字符串或字符串数组）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<StopSequences>,
+    /// 是否允许一次返回多个工具调用（false 时最多返回一个）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+}
+
+/// OpenAI `stop` 参数，可以是单个字符串或字符串数组
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StopSequences {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl StopSequences {
+    /// 展开为非空的停止序列列表
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            StopSequences::Single(s) => vec![s.clone()],
+            StopSequences::Multiple(v) => v.clone(),
+        }
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect()
+    }
+}
+
+impl ChatCompletionRequest {
+    /// 是否携带确定性采样参数（seed / logprobs / top_logprobs）
+    pub fn has_sampling_controls(&self) -> bool {
+        self.seed.is_some() || self.logprobs.is_some() || self.top_logprobs.is_some()
+    }
+
+    /// 请求中设置的 logit_bias / 惩罚参数名
+    pub fn penalty_params(&self) -> Vec<&'static str> {
+        [
+            ("logit_bias", self.logit_bias.is_some()),
+            ("frequency_penalty", self.frequency_penalty.is_some()),
+            ("presence_penalty", self.presence_penalty.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
+
+    /// 清除 logit_bias / 惩罚参数
+    pub fn clear_penalty_params(&mut self) {
+        self.logit_bias = None;
+        self.frequency_penalty = None;
+        self.presence_penalty = None;
+    }
+
+    /// 请求中的停止序列（未设置时为空）
+    pub fn stop_sequences(&self) -> Vec<String> {
+        self.stop
+            .as_ref()
+            .map(StopSequences::to_vec)
+            .unwrap_or_default()
+    }
+
+    /// 是否禁用了并行工具调用
+    pub fn parallel_tool_calls_disabled(&self) -> bool {
+        self.parallel_tool_calls == Some(false)
+    }
+
+    /// 将 `reasoning_effort` 映射为思维链 token 预算
+    ///
+    /// low/medium/high 分别对应 1024/8192/24576，未设置或为 `none` 时返回 None，
+    /// 未知取值按 medium 处理。
+    pub fn thinking_budget(&self) -> Option<u32> {
+        let effort = self.reasoning_effort.as_deref()?.to_lowercase();
+        match effort.as_str() {
+            "none" => None,
+            "low" | "minimal" => Some(1024),
+            "high" => Some(24576),
+            _ => Some(8192),
+        }
+    }
+
+    /// 第一个不是 base64 data URL 的图片地址
+    ///
+    /// 只接受内联图片的 Provider（Kiro、Antigravity）用它拒绝远程图片，
+    /// 避免图片在转换时被静默丢弃。
+    pub fn first_remote_image_url(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .filter_map(|msg| match &msg.content {
+                Some(MessageContent::Parts(parts)) => Some(parts),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|part| match part {
+                ContentPart::ImageUrl { image_url }
+                    if !(image_url.url.starts_with("data:")
+                        && image_url.url.contains(";base64,")) =>
+                {
+                    Some(image_url.url.as_str())
+                }
+                _ => None,
+            })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -177,6 +302,9 @@ pub struct ResponseMessage {
     pub role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
+    /// 思维链内容（与正文分开返回）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
 }
@@ -196,6 +324,9 @@ pub struct ChatCompletionResponse {
     pub model: String,
     pub choices: Vec<Choice>,
     pub usage: Usage,
+    /// 上游后端配置指纹（上游返回时透传）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -204,6 +335,9 @@ pub struct StreamDelta {
     pub role: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
+    /// 思维链内容增量
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
 }
@@ -223,6 +357,9 @@ pub struct ChatCompletionChunk {
     pub created: u64,
     pub model: String,
     pub choices: Vec<StreamChoice>,
+    /// 上游后端配置指纹（上游返回时透传）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
 }
 
 // ============================================================================
@@ -303,3 +440,52 @@ pub struct ImageData {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub revised_prompt: Option<String>,
 }
+
+// ============================================================================
+// 旧版文本补全 API 数据模型
+// ============================================================================
+
+/// OpenAI 旧版文本补全请求（`/v1/completions`）
+///
+/// `prompt` 可以是字符串或数组，其他参数与对话补全同名参数含义相同。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionRequest {
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<StopSequences>,
+    /// 每个 prompt 生成的候选数（仅支持 1）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    /// 服务端生成后择优的候选数（仅支持 1）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_of: Option<u32>,
+    /// 是否在结果中回显 prompt（不支持）
+    #[serde(default)]
+    pub echo: bool,
+    /// 插入模式的后缀文本（不支持）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// 返回的对数概率数量（不支持）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<HashMap<String, f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
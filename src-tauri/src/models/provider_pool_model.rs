@@ -145,6 +145,90 @@ impl CredentialData {
         }
     }
 
+    /// 凭证的机密部分：API Key 或 OAuth 凭证文件路径
+    pub fn secret(&self) -> &str {
+        match self {
+            CredentialData::KiroOAuth { creds_file_path }
+            | CredentialData::GeminiOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::QwenOAuth { creds_file_path }
+            | CredentialData::AntigravityOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::CodexOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::ClaudeOAuth { creds_file_path }
+            | CredentialData::IFlowOAuth { creds_file_path }
+            | CredentialData::IFlowCookie { creds_file_path } => creds_file_path,
+            CredentialData::OpenAIKey { api_key, .. }
+            | CredentialData::ClaudeKey { api_key, .. }
+            | CredentialData::VertexKey { api_key, .. }
+            | CredentialData::GeminiApiKey { api_key, .. }
+            | CredentialData::AnthropicKey { api_key, .. } => api_key,
+        }
+    }
+
+    /// 凭证的机密部分（可修改）
+    pub fn secret_mut(&mut self) -> &mut String {
+        match self {
+            CredentialData::KiroOAuth { creds_file_path }
+            | CredentialData::GeminiOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::QwenOAuth { creds_file_path }
+            | CredentialData::AntigravityOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::CodexOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::ClaudeOAuth { creds_file_path }
+            | CredentialData::IFlowOAuth { creds_file_path }
+            | CredentialData::IFlowCookie { creds_file_path } => creds_file_path,
+            CredentialData::OpenAIKey { api_key, .. }
+            | CredentialData::ClaudeKey { api_key, .. }
+            | CredentialData::VertexKey { api_key, .. }
+            | CredentialData::GeminiApiKey { api_key, .. }
+            | CredentialData::AnthropicKey { api_key, .. } => api_key,
+        }
+    }
+
+    /// 是否使用 OAuth 凭证文件（否则为 API Key）
+    pub fn is_file_based(&self) -> bool {
+        !matches!(
+            self,
+            CredentialData::OpenAIKey { .. }
+                | CredentialData::ClaudeKey { .. }
+                | CredentialData::VertexKey { .. }
+                | CredentialData::GeminiApiKey { .. }
+                | CredentialData::AnthropicKey { .. }
+        )
+    }
+
+    /// 机密部分替换为占位符的副本（用于导出模板）
+    pub fn redacted(&self) -> Self {
+        let mut data = self.clone();
+        *data.secret_mut() = crate::config::REDACTED_PLACEHOLDER.to_string();
+        data
+    }
+
+    /// 是否为尚未填写机密的占位凭证（从模板导入）
+    pub fn needs_secret(&self) -> bool {
+        self.secret() == crate::config::REDACTED_PLACEHOLDER
+    }
+
+    /// 是否直接调用 Anthropic 原生 API（需要 `anthropic-version` 等请求头）
+    pub fn is_anthropic_native(&self) -> bool {
+        matches!(
+            self,
+            CredentialData::ClaudeKey { .. }
+                | CredentialData::ClaudeOAuth { .. }
+                | CredentialData::AnthropicKey { .. }
+        )
+    }
+
     /// 获取 Provider 类型
     pub fn provider_type(&self) -> PoolProviderType {
         match self {
@@ -241,12 +325,31 @@ pub struct ProviderCredential {
     pub source: CredentialSource,
     /// 代理 URL（可覆盖全局代理设置）
     pub proxy_url: Option<String>,
+    /// 凭证层级（如 `paid`、`free`），配合 `routing.tier_rules` 按模型偏好选择
+    #[serde(default)]
+    pub tier: Option<String>,
+    /// 健康分（0~1，按请求结果的指数移动平均计算）
+    #[serde(default = "default_health_score")]
+    pub health_score: f64,
+    /// 凭证级上游请求头（覆盖 `upstream_headers` 配置中的同名请求头，如特定账号需要的 User-Agent）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub upstream_headers: HashMap<String, String>,
+    /// 暂停选择直到该时间（手动设置，如已知上游限额的重置时间），到期后自动恢复
+    #[serde(default)]
+    pub parked_until: Option<DateTime<Utc>>,
+    /// 本次连续不健康的开始时间（恢复健康时清除），用于按天数自动禁用
+    #[serde(default)]
+    pub unhealthy_since: Option<DateTime<Utc>>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_health_score() -> f64 {
+    1.0
+}
+
 impl ProviderCredential {
     /// 创建新凭证
     pub fn new(provider_type: PoolProviderType, credential: CredentialData) -> Self {
@@ -273,6 +376,11 @@ impl ProviderCredential {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         }
     }
 
@@ -287,9 +395,17 @@ impl ProviderCredential {
         cred
     }
 
-    /// 是否可用（健康且未禁用）
+    /// 是否可用（健康、未禁用、未暂停且已填写机密）
     pub fn is_available(&self) -> bool {
-        self.is_healthy && !self.is_disabled
+        self.is_healthy
+            && !self.is_disabled
+            && !self.is_parked(Utc::now())
+            && !self.credential.needs_secret()
+    }
+
+    /// 在指定时间是否处于暂停状态
+    pub fn is_parked(&self, now: DateTime<Utc>) -> bool {
+        self.parked_until.is_some_and(|until| until > now)
     }
 
     /// 是否支持指定模型
@@ -329,6 +445,7 @@ impl ProviderCredential {
     /// 标记为健康
     pub fn mark_healthy(&mut self, check_model: Option<String>) {
         self.is_healthy = true;
+        self.unhealthy_since = None;
         self.error_count = 0;
         self.last_health_check_time = Some(Utc::now());
         self.last_health_check_model = check_model;
@@ -344,7 +461,18 @@ impl ProviderCredential {
         // 错误次数达到阈值则标记为不健康
         if self.error_count >= 3 {
             self.is_healthy = false;
+            self.unhealthy_since.get_or_insert(Utc::now());
+        }
+    }
+
+    /// 手动启用或禁用
+    ///
+    /// 重新启用仍不健康的凭证时重新开始不健康计时，避免随即又被自动禁用。
+    pub fn set_disabled(&mut self, disabled: bool) {
+        if self.is_disabled && !disabled && !self.is_healthy {
+            self.unhealthy_since = Some(Utc::now());
         }
+        self.is_disabled = disabled;
     }
 
     /// 记录使用
@@ -359,6 +487,7 @@ impl ProviderCredential {
         self.usage_count = 0;
         self.error_count = 0;
         self.is_healthy = true;
+        self.unhealthy_since = None;
         self.last_error_time = None;
         self.last_error_message = None;
         self.updated_at = Utc::now();
@@ -374,23 +503,43 @@ pub struct PoolStats {
     pub healthy_count: usize,
     /// 禁用凭证数
     pub disabled_count: usize,
+    /// 暂停中的凭证数
+    #[serde(default)]
+    pub parked_count: usize,
     /// 总使用次数
     pub total_usage: u64,
     /// 总错误次数
     pub total_errors: u64,
+    /// 平均健康分（不含禁用凭证，无凭证时为 1.0）
+    #[serde(default = "default_health_score")]
+    pub avg_health_score: f64,
     /// 最后更新时间
     pub last_update: DateTime<Utc>,
 }
 
 impl PoolStats {
     pub fn from_credentials(credentials: &[ProviderCredential]) -> Self {
+        let now = Utc::now();
+        let enabled: Vec<f64> = credentials
+            .iter()
+            .filter(|c| !c.is_disabled)
+            .map(|c| c.health_score)
+            .collect();
+        let avg_health_score = if enabled.is_empty() {
+            1.0
+        } else {
+            enabled.iter().sum::<f64>() / enabled.len() as f64
+        };
+
         Self {
             total_count: credentials.len(),
             healthy_count: credentials.iter().filter(|c| c.is_healthy).count(),
             disabled_count: credentials.iter().filter(|c| c.is_disabled).count(),
+            parked_count: credentials.iter().filter(|c| c.is_parked(now)).count(),
             total_usage: credentials.iter().map(|c| c.usage_count).sum(),
             total_errors: credentials.iter().map(|c| c.error_count as u64).sum(),
-            last_update: Utc::now(),
+            avg_health_score,
+            last_update: now,
         }
     }
 }
@@ -439,6 +588,20 @@ pub struct TokenCacheStatus {
     pub last_refresh_error: Option<String>,
 }
 
+impl From<&CachedTokenInfo> for TokenCacheStatus {
+    fn from(cache: &CachedTokenInfo) -> Self {
+        Self {
+            has_cached_token: cache.access_token.is_some(),
+            is_valid: cache.is_valid(),
+            is_expiring_soon: cache.is_expiring_soon(),
+            expiry_time: cache.expiry_time.map(|t| t.to_rfc3339()),
+            last_refresh: cache.last_refresh.map(|t| t.to_rfc3339()),
+            refresh_error_count: cache.refresh_error_count,
+            last_refresh_error: cache.last_refresh_error.clone(),
+        }
+    }
+}
+
 /// Token 缓存信息
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CachedTokenInfo {
@@ -553,6 +716,18 @@ pub struct CredentialDisplay {
     pub api_key: Option<String>,
     /// 凭证级代理 URL（可覆盖全局代理设置）
     pub proxy_url: Option<String>,
+    /// 凭证层级
+    pub tier: Option<String>,
+    /// 健康分（0~1）
+    pub health_score: f64,
+    /// 凭证级上游请求头
+    pub upstream_headers: HashMap<String, String>,
+    /// 是否为等待填写机密的模板凭证
+    pub needs_secret: bool,
+    /// 是否暂停中（与禁用、不健康分开显示）
+    pub is_parked: bool,
+    /// 暂停到期时间（仅暂停中时有值）
+    pub parked_until: Option<String>,
 }
 
 /// 获取凭证类型字符串
@@ -618,15 +793,8 @@ fn get_api_key(cred: &CredentialData) -> Option<String> {
 impl From<&ProviderCredential> for CredentialDisplay {
     fn from(cred: &ProviderCredential) -> Self {
         // 构建 token 缓存状态
-        let token_cache_status = cred.cached_token.as_ref().map(|cache| TokenCacheStatus {
-            has_cached_token: cache.access_token.is_some(),
-            is_valid: cache.is_valid(),
-            is_expiring_soon: cache.is_expiring_soon(),
-            expiry_time: cache.expiry_time.map(|t| t.to_rfc3339()),
-            last_refresh: cache.last_refresh.map(|t| t.to_rfc3339()),
-            refresh_error_count: cache.refresh_error_count,
-            last_refresh_error: cache.last_refresh_error.clone(),
-        });
+        let token_cache_status = cred.cached_token.as_ref().map(TokenCacheStatus::from);
+        let is_parked = cred.is_parked(Utc::now());
 
         Self {
             uuid: cred.uuid.clone(),
@@ -654,6 +822,15 @@ impl From<&ProviderCredential> for CredentialDisplay {
             base_url: get_base_url(&cred.credential),
             api_key: get_api_key(&cred.credential),
             proxy_url: cred.proxy_url.clone(),
+            tier: cred.tier.clone(),
+            health_score: cred.health_score,
+            upstream_headers: cred.upstream_headers.clone(),
+            needs_secret: cred.credential.needs_secret(),
+            is_parked,
+            parked_until: cred
+                .parked_until
+                .filter(|_| is_parked)
+                .map(|t| t.to_rfc3339()),
         }
     }
 }
@@ -781,6 +958,11 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         };
 
         assert!(!cred.supports_model("claude-opus"));
@@ -815,6 +997,11 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         };
 
         // Exact match exclusion
@@ -851,6 +1038,11 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         };
 
         // Prefix wildcard exclusion
@@ -891,6 +1083,11 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         };
 
         // Contains wildcard exclusion
@@ -928,6 +1125,11 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         };
 
         // Excluded by not_supported_models (exact match)
@@ -966,6 +1168,11 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         };
 
         // All models should be supported since not_supported_models is empty
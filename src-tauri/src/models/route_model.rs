@@ -13,6 +13,9 @@ pub struct RouteInfo {
     pub provider_type: String,
     /// 关联的凭证数量
     pub credential_count: usize,
+    /// 当前健康（健康且未禁用）的凭证数量
+    #[serde(default)]
+    pub healthy_credential_count: usize,
     /// 可用的端点列表
     pub endpoints: Vec<RouteEndpoint>,
     /// 标签 (如 "突破限制", "官方API/三方")
@@ -41,6 +44,79 @@ pub struct RouteListResponse {
     pub default_provider: String,
     /// 所有可用路由
     pub routes: Vec<RouteInfo>,
+    /// 过滤后（分页前）的路由总数，不含默认路由
+    #[serde(default)]
+    pub total: usize,
+}
+
+/// 路由列表查询参数
+///
+/// 过滤与分页只作用于默认路由之外的路由，默认路由始终排在第一位。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteQuery {
+    /// 按 Provider 类型过滤
+    #[serde(default)]
+    pub provider_type: Option<String>,
+    /// 按标签过滤
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// 每页数量
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// 偏移量
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+impl RouteQuery {
+    /// 是否匹配过滤条件
+    pub fn matches(&self, route: &RouteInfo) -> bool {
+        let provider_ok = self
+            .provider_type
+            .as_deref()
+            .map_or(true, |p| route.provider_type.eq_ignore_ascii_case(p));
+        let tag_ok = self
+            .tag
+            .as_deref()
+            .map_or(true, |t| route.tags.iter().any(|tag| tag == t));
+        provider_ok && tag_ok
+    }
+}
+
+impl RouteListResponse {
+    /// 组装路由列表：默认路由置顶，其余路由按查询参数过滤并分页
+    pub fn build(
+        base_url: String,
+        default_provider: String,
+        routes: Vec<RouteInfo>,
+        query: &RouteQuery,
+    ) -> Self {
+        // 默认路由的健康凭证数取默认 Provider 的轮询路由
+        let default_healthy = routes
+            .iter()
+            .find(|r| r.selector == default_provider)
+            .map_or(0, |r| r.healthy_credential_count);
+        let mut default_route = RouteInfo::default_route(&base_url, &default_provider);
+        default_route.healthy_credential_count = default_healthy;
+
+        let filtered: Vec<RouteInfo> = routes.into_iter().filter(|r| query.matches(r)).collect();
+        let total = filtered.len();
+
+        let mut all_routes = vec![default_route];
+        all_routes.extend(
+            filtered
+                .into_iter()
+                .skip(query.offset.unwrap_or(0))
+                .take(query.limit.unwrap_or(usize::MAX)),
+        );
+
+        Self {
+            base_url,
+            default_provider,
+            routes: all_routes,
+            total,
+        }
+    }
 }
 
 /// curl 示例
@@ -59,12 +135,37 @@ impl RouteInfo {
             selector,
             provider_type,
             credential_count: 0,
+            healthy_credential_count: 0,
             endpoints: Vec::new(),
             tags: Vec::new(),
             enabled: true,
         }
     }
 
+    /// 默认路由（`/v1/messages`、`/v1/chat/completions`，使用默认 Provider）
+    pub fn default_route(base_url: &str, default_provider: &str) -> Self {
+        Self {
+            selector: "default".to_string(),
+            provider_type: default_provider.to_string(),
+            credential_count: 1,
+            healthy_credential_count: 0,
+            endpoints: vec![
+                RouteEndpoint {
+                    path: "/v1/messages".to_string(),
+                    protocol: "claude".to_string(),
+                    url: format!("{}/v1/messages", base_url),
+                },
+                RouteEndpoint {
+                    path: "/v1/chat/completions".to_string(),
+                    protocol: "openai".to_string(),
+                    url: format!("{}/v1/chat/completions", base_url),
+                },
+            ],
+            tags: vec!["默认".to_string()],
+            enabled: true,
+        }
+    }
+
     /// 添加端点
     pub fn add_endpoint(&mut self, base_url: &str, protocol: &str) {
         let path = match protocol {
@@ -145,3 +246,57 @@ impl RouteInfo {
         examples
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(selector: &str, provider_type: &str, tag: &str, healthy: usize) -> RouteInfo {
+        let mut route = RouteInfo::new(selector.to_string(), provider_type.to_string());
+        route.credential_count = healthy;
+        route.healthy_credential_count = healthy;
+        route.tags.push(tag.to_string());
+        route
+    }
+
+    #[test]
+    fn test_build_keeps_default_route_first_and_paginates() {
+        let routes = vec![
+            route("kiro", "kiro", "轮询", 2),
+            route("gemini", "gemini", "轮询", 1),
+            route("my-kiro", "kiro", "指定凭证", 1),
+        ];
+        let query = RouteQuery {
+            provider_type: Some("kiro".to_string()),
+            limit: Some(1),
+            offset: Some(1),
+            ..Default::default()
+        };
+
+        let response = RouteListResponse::build(
+            "http://127.0.0.1:8999".to_string(),
+            "kiro".to_string(),
+            routes,
+            &query,
+        );
+
+        assert_eq!(response.total, 2);
+        let selectors: Vec<_> = response
+            .routes
+            .iter()
+            .map(|r| r.selector.as_str())
+            .collect();
+        assert_eq!(selectors, vec!["default", "my-kiro"]);
+        assert_eq!(response.routes[0].healthy_credential_count, 2);
+    }
+
+    #[test]
+    fn test_query_filters_by_tag() {
+        let query = RouteQuery {
+            tag: Some("指定凭证".to_string()),
+            ..Default::default()
+        };
+        assert!(query.matches(&route("my-kiro", "kiro", "指定凭证", 1)));
+        assert!(!query.matches(&route("kiro", "kiro", "轮询", 1)));
+    }
+}
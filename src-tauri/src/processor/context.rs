@@ -2,9 +2,11 @@
 //!
 //! 定义请求处理过程中的上下文信息
 
+use super::request_trace::{CapturedStep, RequestTraceStore, TraceStep, TraceStepKind};
 use crate::plugin::PluginContext;
 use crate::ProviderType;
 use chrono::{DateTime, Utc};
+use std::sync::Arc;
 use std::time::Instant;
 
 /// 请求上下文
@@ -26,14 +28,28 @@ pub struct RequestContext {
     pub provider: Option<ProviderType>,
     /// 使用的凭证 ID
     pub credential_id: Option<String>,
+    /// 终端用户标识（隐私模式下为哈希）
+    pub user_id: Option<String>,
     /// 重试次数
     pub retry_count: u32,
     /// 是否为流式请求
     pub is_stream: bool,
+    /// 是否为基准测试请求（不计入统计，不使用响应缓存）
+    pub benchmark: bool,
+    /// 请求体大小（字节）
+    pub request_bytes: Option<u64>,
+    /// 响应体大小（字节，记录统计时已知的大小）
+    pub response_bytes: Option<u64>,
+    /// 上游 Provider 调用耗时（毫秒，流式请求为收到响应头的时间，不含等待并发槽位的时间）
+    pub upstream_elapsed_ms: Option<u64>,
     /// 插件上下文
     pub plugin_ctx: Option<PluginContext>,
     /// 元数据
     pub metadata: std::collections::HashMap<String, serde_json::Value>,
+    /// 管道追踪步骤
+    pub trace: Vec<TraceStep>,
+    /// 追踪存储（设置后步骤会同步写入，便于按请求 ID 查询）
+    trace_store: Option<Arc<RequestTraceStore>>,
 }
 
 impl RequestContext {
@@ -48,19 +64,42 @@ impl RequestContext {
             resolved_model: model,
             provider: None,
             credential_id: None,
+            user_id: None,
             retry_count: 0,
             is_stream: false,
+            benchmark: false,
+            request_bytes: None,
+            response_bytes: None,
+            upstream_elapsed_ms: None,
             plugin_ctx: None,
             metadata: std::collections::HashMap::new(),
+            trace: Vec::new(),
+            trace_store: None,
         }
     }
 
+    /// 将追踪步骤同步写入追踪存储
+    pub fn with_trace_store(mut self, store: Arc<RequestTraceStore>) -> Self {
+        store.begin(&self.request_id, &self.original_model, self.timestamp);
+        for step in &self.trace {
+            store.push(&self.request_id, step.clone());
+        }
+        self.trace_store = Some(store);
+        self
+    }
+
     /// 设置流式请求标志
     pub fn with_stream(mut self, is_stream: bool) -> Self {
         self.is_stream = is_stream;
         self
     }
 
+    /// 设置请求体大小
+    pub fn with_request_bytes(mut self, bytes: u64) -> Self {
+        self.request_bytes = Some(bytes);
+        self
+    }
+
     /// 设置 Provider
     pub fn set_provider(&mut self, provider: ProviderType) {
         self.provider = Some(provider);
@@ -71,11 +110,46 @@ impl RequestContext {
         self.credential_id = Some(credential_id);
     }
 
-    /// 设置解析后的模型名称
+    /// 设置解析后的模型名称（模型发生变化时记录别名解析步骤）
     pub fn set_resolved_model(&mut self, model: String) {
+        if model != self.resolved_model {
+            self.record_step(TraceStepKind::AliasResolved {
+                from: self.resolved_model.clone(),
+                to: model.clone(),
+            });
+        }
         self.resolved_model = model;
     }
 
+    /// 记录管道追踪步骤
+    pub fn record_step(&mut self, kind: TraceStepKind) {
+        let step = TraceStep {
+            elapsed_ms: self.elapsed_ms(),
+            timestamp: Utc::now(),
+            kind,
+        };
+        self.push_step(step);
+    }
+
+    /// 写入 Provider 调用期间捕获的步骤，耗时按步骤发生的时刻计算
+    pub fn record_captured_steps(&mut self, steps: Vec<CapturedStep>) {
+        for captured in steps {
+            let step = TraceStep {
+                elapsed_ms: captured.at.duration_since(self.start_time).as_millis() as u64,
+                timestamp: captured.timestamp,
+                kind: captured.kind,
+            };
+            self.push_step(step);
+        }
+    }
+
+    fn push_step(&mut self, step: TraceStep) {
+        if let Some(store) = &self.trace_store {
+            store.push(&self.request_id, step.clone());
+        }
+        self.trace.push(step);
+    }
+
     /// 增加重试计数
     pub fn increment_retry(&mut self) {
         self.retry_count += 1;
@@ -157,6 +231,49 @@ mod tests {
         assert_eq!(ctx.retry_count, 2);
     }
 
+    #[test]
+    fn test_request_context_trace_is_mirrored_to_store() {
+        let store = Arc::new(RequestTraceStore::with_defaults());
+        let mut ctx = RequestContext::new("gpt-4".to_string()).with_trace_store(store.clone());
+
+        ctx.set_resolved_model("gpt-4".to_string());
+        ctx.set_resolved_model("claude-sonnet-4-5".to_string());
+        ctx.record_step(TraceStepKind::UpstreamResponse { status_code: 200 });
+
+        let trace = store.get(&ctx.request_id).unwrap();
+        assert_eq!(trace.model, "gpt-4");
+        assert_eq!(trace.steps.len(), 2);
+        assert_eq!(
+            trace.steps[0].kind,
+            TraceStepKind::AliasResolved {
+                from: "gpt-4".to_string(),
+                to: "claude-sonnet-4-5".to_string(),
+            }
+        );
+        assert_eq!(ctx.trace.len(), 2);
+    }
+
+    #[test]
+    fn test_request_context_records_captured_steps() {
+        let store = Arc::new(RequestTraceStore::with_defaults());
+        let mut ctx = RequestContext::new("gpt-4".to_string()).with_trace_store(store.clone());
+
+        ctx.record_captured_steps(vec![CapturedStep {
+            at: ctx.start_time + std::time::Duration::from_millis(25),
+            timestamp: Utc::now(),
+            kind: TraceStepKind::Retry {
+                attempt: 1,
+                status_code: Some(403),
+                error: "token refresh".to_string(),
+            },
+        }]);
+
+        let trace = store.get(&ctx.request_id).unwrap();
+        assert_eq!(trace.steps.len(), 1);
+        assert_eq!(trace.steps[0].elapsed_ms, 25);
+        assert_eq!(ctx.trace[0].kind, trace.steps[0].kind);
+    }
+
     #[test]
     fn test_request_context_metadata() {
         let mut ctx = RequestContext::new("model".to_string());
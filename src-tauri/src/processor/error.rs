@@ -1,9 +1,24 @@
 //! 处理错误类型
 //!
-//! 定义请求处理过程中可能发生的错误
+//! 定义请求处理过程中可能发生的错误，并按路由格式（OpenAI / Anthropic）转换为 HTTP 响应
 
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
 use thiserror::Error;
 
+use crate::resilience::ConcurrencyError;
+
+/// 错误响应体格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// `{"error": {"message", "type", "code"}}`
+    #[default]
+    OpenAI,
+    /// `{"type": "error", "error": {"type", "message"}}`
+    Anthropic,
+}
+
 /// 处理错误
 #[derive(Error, Debug, Clone)]
 pub enum ProcessError {
@@ -11,6 +26,33 @@ pub enum ProcessError {
     #[error("认证失败: {0}")]
     AuthError(String),
 
+    /// 请求频率超限（上游 429 或本地并发已满）
+    #[error("请求频率超限: {message}")]
+    RateLimited {
+        message: String,
+        retry_after_secs: Option<u64>,
+    },
+
+    /// 上游返回错误状态码
+    #[error("上游错误 ({status}): {message}")]
+    Upstream { status: u16, message: String },
+
+    /// 没有可用凭证
+    #[error("没有可用凭证: {0}")]
+    NoCredential(String),
+
+    /// 请求或响应格式转换失败
+    #[error("格式转换失败: {0}")]
+    Conversion(String),
+
+    /// 凭证类型不支持该请求
+    #[error("不支持的请求: {0}")]
+    Unsupported(String),
+
+    /// 请求超出本地限制（工具数量、大小等）
+    #[error("请求无效: {0}")]
+    InvalidRequest(String),
+
     /// 路由失败
     #[error("路由失败: 无可用 Provider 处理模型 {model}")]
     RoutingError { model: String },
@@ -19,6 +61,10 @@ pub enum ProcessError {
     #[error("Provider 调用失败: {0}")]
     ProviderError(String),
 
+    /// 上游连接失败（连接被拒绝、DNS 解析失败、请求超时等，未收到响应）
+    #[error("上游连接失败: {0}")]
+    Connection(String),
+
     /// 重试耗尽
     #[error("重试耗尽: 尝试 {attempts} 次后失败")]
     RetriesExhausted { attempts: u32 },
@@ -64,8 +110,16 @@ impl ProcessError {
     pub fn status_code(&self) -> u16 {
         match self {
             ProcessError::AuthError(_) => 401,
+            ProcessError::RateLimited { .. } => 429,
+            ProcessError::Upstream { status, .. } if (400..=599).contains(status) => *status,
+            ProcessError::Upstream { .. } => 502,
+            ProcessError::NoCredential(_) => 503,
+            ProcessError::Conversion(_) => 500,
+            ProcessError::Unsupported(_) => 400,
+            ProcessError::InvalidRequest(_) => 400,
             ProcessError::RoutingError { .. } => 404,
             ProcessError::ProviderError(_) => 502,
+            ProcessError::Connection(_) => 502,
             ProcessError::RetriesExhausted { .. } => 503,
             ProcessError::Timeout { .. } => 408,
             ProcessError::StreamIdleTimeout { .. } => 408,
@@ -83,8 +137,18 @@ impl ProcessError {
         matches!(
             self,
             ProcessError::ProviderError(_)
+                | ProcessError::Connection(_)
+                | ProcessError::RateLimited { .. }
                 | ProcessError::Timeout { .. }
                 | ProcessError::StreamIdleTimeout { .. }
+        ) || matches!(self, ProcessError::Upstream { status, .. } if *status >= 500)
+    }
+
+    /// 检查是否为上游连接失败（未收到响应）
+    pub fn is_connection_failure(&self) -> bool {
+        matches!(
+            self,
+            ProcessError::Connection(_) | ProcessError::Timeout { .. }
         )
     }
 
@@ -93,9 +157,57 @@ impl ProcessError {
         matches!(
             self,
             ProcessError::ProviderError(_)
+                | ProcessError::Connection(_)
+                | ProcessError::RateLimited { .. }
                 | ProcessError::RetriesExhausted { .. }
                 | ProcessError::CredentialPoolError(_)
-        )
+                | ProcessError::NoCredential(_)
+        ) || matches!(self, ProcessError::Upstream { status, .. } if *status >= 500)
+    }
+
+    /// 根据上游响应状态码构建错误（429 视为限流）
+    pub fn upstream(status: u16, message: impl Into<String>) -> Self {
+        let message = message.into();
+        if status == 429 {
+            ProcessError::RateLimited {
+                message,
+                retry_after_secs: None,
+            }
+        } else {
+            ProcessError::Upstream { status, message }
+        }
+    }
+
+    /// 根据 Provider 调用返回的错误构建
+    ///
+    /// 只有传输层错误（连接失败、请求超时）归为 [`ProcessError::Connection`]，
+    /// Token 刷新、读取响应体等其他失败归为 [`ProcessError::ProviderError`]。
+    pub fn provider_call(
+        error: &(dyn std::error::Error + 'static),
+        message: impl Into<String>,
+    ) -> Self {
+        if is_transport_error(error) {
+            ProcessError::Connection(message.into())
+        } else {
+            ProcessError::ProviderError(message.into())
+        }
+    }
+
+    /// 为上游限流错误补上上游建议的等待时间（向上取整到秒，已有时不覆盖）
+    pub fn with_retry_after(self, delay: Option<std::time::Duration>) -> Self {
+        match (self, delay) {
+            (
+                ProcessError::RateLimited {
+                    message,
+                    retry_after_secs: None,
+                },
+                Some(delay),
+            ) => ProcessError::RateLimited {
+                message,
+                retry_after_secs: Some(delay.as_millis().div_ceil(1000) as u64),
+            },
+            (error, _) => error,
+        }
     }
 
     /// 转换为 JSON 错误响应
@@ -109,12 +221,65 @@ impl ProcessError {
         })
     }
 
+    /// 转换为 Anthropic 格式的 JSON 错误响应
+    pub fn to_anthropic_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": self.anthropic_error_type(),
+                "message": self.to_string()
+            }
+        })
+    }
+
+    /// 获取 Anthropic 错误类型（按状态码归类）
+    pub fn anthropic_error_type(&self) -> &'static str {
+        match self.status_code() {
+            400 => "invalid_request_error",
+            401 => "authentication_error",
+            403 => "permission_error",
+            404 => "not_found_error",
+            413 => "request_too_large",
+            429 => "rate_limit_error",
+            503 | 529 => "overloaded_error",
+            _ => "api_error",
+        }
+    }
+
+    /// 按路由格式转换为 HTTP 响应
+    pub fn into_response_for(self, format: ErrorFormat) -> Response {
+        let status =
+            StatusCode::from_u16(self.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = match format {
+            ErrorFormat::OpenAI => self.to_json(),
+            ErrorFormat::Anthropic => self.to_anthropic_json(),
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let ProcessError::RateLimited {
+            retry_after_secs: Some(secs),
+            ..
+        } = self
+        {
+            if let Ok(value) = HeaderValue::from_str(&secs.to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+        }
+        response
+    }
+
     /// 获取错误类型字符串
     pub fn error_type(&self) -> &'static str {
         match self {
             ProcessError::AuthError(_) => "authentication_error",
+            ProcessError::RateLimited { .. } => "rate_limit_error",
+            ProcessError::Upstream { .. } => "upstream_error",
+            ProcessError::NoCredential(_) => "no_credential",
+            ProcessError::Conversion(_) => "conversion_error",
+            ProcessError::Unsupported(_) => "unsupported_error",
+            ProcessError::InvalidRequest(_) => "invalid_request_error",
             ProcessError::RoutingError { .. } => "routing_error",
             ProcessError::ProviderError(_) => "provider_error",
+            ProcessError::Connection(_) => "connection_error",
             ProcessError::RetriesExhausted { .. } => "retries_exhausted",
             ProcessError::Timeout { .. } => "timeout_error",
             ProcessError::StreamIdleTimeout { .. } => "stream_idle_timeout",
@@ -140,6 +305,53 @@ impl ProcessError {
     }
 }
 
+/// 沿错误链查找传输层错误
+fn is_transport_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+            if e.is_connect() || e.is_timeout() {
+                return true;
+            }
+        }
+        if let Some(crate::providers::ProviderError::NetworkError(_)) =
+            error.downcast_ref::<crate::providers::ProviderError>()
+        {
+            return true;
+        }
+        if let Some(e) = error.downcast_ref::<std::io::Error>() {
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::TimedOut
+            ) {
+                return true;
+            }
+        }
+        current = error.source();
+    }
+    false
+}
+
+/// 默认使用 OpenAI 格式，Anthropic 路由使用 [`ProcessError::into_response_for`]
+impl IntoResponse for ProcessError {
+    fn into_response(self) -> Response {
+        self.into_response_for(ErrorFormat::OpenAI)
+    }
+}
+
+/// 并发已满或排队超时视为限流
+impl From<ConcurrencyError> for ProcessError {
+    fn from(e: ConcurrencyError) -> Self {
+        ProcessError::RateLimited {
+            message: e.to_string(),
+            retry_after_secs: Some(1),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,6 +403,44 @@ mod tests {
         assert!(!ProcessError::Timeout { timeout_ms: 5000 }.should_failover());
     }
 
+    #[test]
+    fn test_provider_call_classifies_transport_errors() {
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let error = ProcessError::provider_call(&refused, "connection refused");
+        assert!(matches!(error, ProcessError::Connection(_)));
+        assert!(error.is_connection_failure());
+        assert_eq!(error.status_code(), 502);
+        assert_eq!(error.error_type(), "connection_error");
+
+        let network = crate::providers::ProviderError::NetworkError("请求超时".to_string());
+        assert!(ProcessError::provider_call(&network, network.to_string()).is_connection_failure());
+
+        // 包装在其他错误中的传输错误同样识别
+        #[derive(Debug)]
+        struct Wrapped(std::io::Error);
+        impl std::fmt::Display for Wrapped {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "request failed")
+            }
+        }
+        impl std::error::Error for Wrapped {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+        let wrapped = Wrapped(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert!(ProcessError::provider_call(&wrapped, "wrapped").is_connection_failure());
+
+        // Token 刷新失败、读取响应体失败等不算连接失败
+        let auth = crate::providers::ProviderError::AuthenticationError("expired".to_string());
+        let error = ProcessError::provider_call(&auth, auth.to_string());
+        assert!(matches!(error, ProcessError::ProviderError(_)));
+        assert!(!error.is_connection_failure());
+        let body = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+        assert!(!ProcessError::provider_call(&body, "body").is_connection_failure());
+        assert!(!ProcessError::ProviderError("refresh failed".to_string()).is_connection_failure());
+    }
+
     #[test]
     fn test_process_error_to_json() {
         let error = ProcessError::AuthError("Invalid API key".to_string());
@@ -203,4 +453,59 @@ mod tests {
         assert_eq!(json["error"]["type"], "authentication_error");
         assert_eq!(json["error"]["code"], 401);
     }
+
+    #[test]
+    fn test_upstream_status_mapping() {
+        assert_eq!(ProcessError::upstream(403, "forbidden").status_code(), 403);
+        assert!(matches!(
+            ProcessError::upstream(429, "slow down"),
+            ProcessError::RateLimited { .. }
+        ));
+        // 非错误状态码按 502 处理
+        assert_eq!(ProcessError::upstream(200, "odd").status_code(), 502);
+        assert!(ProcessError::upstream(503, "busy").should_failover());
+        assert!(!ProcessError::upstream(400, "bad").is_retryable());
+    }
+
+    #[test]
+    fn test_with_retry_after() {
+        let delay = Some(std::time::Duration::from_millis(1500));
+        assert!(matches!(
+            ProcessError::upstream(429, "slow down").with_retry_after(delay),
+            ProcessError::RateLimited {
+                retry_after_secs: Some(2),
+                ..
+            }
+        ));
+        assert!(matches!(
+            ProcessError::upstream(503, "busy").with_retry_after(delay),
+            ProcessError::Upstream { status: 503, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_into_response_for_route_format() {
+        let error = ProcessError::RateLimited {
+            message: "too many requests".to_string(),
+            retry_after_secs: Some(3),
+        };
+
+        let response = error.clone().into_response_for(ErrorFormat::Anthropic);
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["type"], "error");
+        assert_eq!(json["error"]["type"], "rate_limit_error");
+
+        let response = error.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["type"], "rate_limit_error");
+        assert_eq!(json["error"]["code"], 429);
+    }
 }
@@ -15,10 +15,17 @@
 
 mod context;
 mod error;
+mod request_trace;
+mod response_cache;
 mod steps;
 
 pub use context::RequestContext;
-pub use error::ProcessError;
+pub use error::{ErrorFormat, ProcessError};
+pub use request_trace::{
+    record_captured_step, with_step_capture, CapturedStep, RequestTrace, RequestTraceStore,
+    TraceStep, TraceStepKind,
+};
+pub use response_cache::{CachedResponse, ResponseCache, ResponseCacheConfig, ResponseCacheStats};
 pub use steps::{
     AuthStep, InjectionStep, PipelineStep, PluginPostStep, PluginPreStep, ProviderStep,
     RoutingStep, TelemetryStep,
@@ -26,7 +33,7 @@ pub use steps::{
 
 use crate::injection::Injector;
 use crate::plugin::PluginManager;
-use crate::resilience::{Failover, Retrier, TimeoutController};
+use crate::resilience::{ConcurrencyLimiter, Failover, Retrier, TimeoutController};
 use crate::router::{ModelMapper, Router};
 use crate::services::provider_pool_service::ProviderPoolService;
 use crate::telemetry::{StatsAggregator, TokenTracker};
@@ -50,6 +57,12 @@ pub struct RequestProcessor {
     pub failover: Arc<Failover>,
     /// 超时控制器
     pub timeout: Arc<TimeoutController>,
+    /// 并发限制器（按 Provider 类型限制上游并发请求数）
+    pub concurrency: Arc<ConcurrencyLimiter>,
+    /// 响应缓存（确定性非流式请求）
+    pub response_cache: Arc<ResponseCache>,
+    /// 请求管道追踪
+    pub traces: Arc<RequestTraceStore>,
     /// 插件管理器
     pub plugins: Arc<PluginManager>,
     /// 统计聚合器（使用 parking_lot::RwLock 以支持与 TelemetryState 共享）
@@ -83,6 +96,9 @@ impl RequestProcessor {
             retrier,
             failover,
             timeout,
+            concurrency: Arc::new(ConcurrencyLimiter::with_defaults()),
+            response_cache: Arc::new(ResponseCache::with_defaults()),
+            traces: Arc::new(RequestTraceStore::with_defaults()),
             plugins,
             stats,
             tokens,
@@ -100,6 +116,9 @@ impl RequestProcessor {
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
             timeout: Arc::new(TimeoutController::with_defaults()),
+            concurrency: Arc::new(ConcurrencyLimiter::with_defaults()),
+            response_cache: Arc::new(ResponseCache::with_defaults()),
+            traces: Arc::new(RequestTraceStore::with_defaults()),
             plugins: Arc::new(PluginManager::with_defaults()),
             stats: Arc::new(ParkingLotRwLock::new(StatsAggregator::with_defaults())),
             tokens: Arc::new(ParkingLotRwLock::new(TokenTracker::with_defaults())),
@@ -138,6 +157,9 @@ impl RequestProcessor {
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
             timeout: Arc::new(TimeoutController::with_defaults()),
+            concurrency: Arc::new(ConcurrencyLimiter::with_defaults()),
+            response_cache: Arc::new(ResponseCache::with_defaults()),
+            traces: Arc::new(RequestTraceStore::with_defaults()),
             plugins: Arc::new(PluginManager::with_defaults()),
             stats,
             tokens,
@@ -146,6 +168,38 @@ impl RequestProcessor {
         }
     }
 
+    /// 使用共享的并发限制器
+    ///
+    /// 并发限制器由 ServerState 持有，服务器重启后仍保留进行中计数
+    pub fn with_concurrency_limiter(mut self, concurrency: Arc<ConcurrencyLimiter>) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// 使用共享的响应缓存
+    ///
+    /// 响应缓存由 ServerState 持有，服务器重启后仍保留缓存和命中统计
+    pub fn with_response_cache(mut self, response_cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = response_cache;
+        self
+    }
+
+    /// 使用共享的参数注入器
+    ///
+    /// 注入器由 ServerState 持有，注入规则变更通过配置观察者实时生效
+    pub fn with_injector(mut self, injector: Arc<RwLock<Injector>>) -> Self {
+        self.injector = injector;
+        self
+    }
+
+    /// 使用共享的请求追踪存储
+    ///
+    /// 追踪存储由 ServerState 持有，供 `get_request_trace` 命令查询
+    pub fn with_request_traces(mut self, traces: Arc<RequestTraceStore>) -> Self {
+        self.traces = traces;
+        self
+    }
+
     /// 解析模型别名
     ///
     /// 使用 ModelMapper 将模型别名解析为实际模型名称
@@ -208,6 +262,14 @@ impl RequestProcessor {
 
         if let Some(p) = provider {
             ctx.set_provider(p);
+            ctx.record_step(TraceStepKind::ProviderSelected {
+                provider: p.to_string(),
+                reason: if is_default {
+                    "default_provider".to_string()
+                } else {
+                    "routing_rule".to_string()
+                },
+            });
             tracing::info!(
                 "[ROUTE] request_id={} model={} provider={} is_default={}",
                 ctx.request_id,
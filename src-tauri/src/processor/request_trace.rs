@@ -0,0 +1,273 @@
+//! 请求管道追踪
+//!
+//! 按顺序记录单个请求在处理管道中的决策（别名解析、参数注入、Provider 选择、
+//! 重试、故障转移等）及耗时，调试路由问题时可按请求 ID 查询完整链路。
+//!
+//! Provider 调用内部拿不到 `RequestContext`，在 [`with_step_capture`] 范围内通过
+//! [`record_captured_step`] 记录，调用返回后由处理器写入上下文。
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// 默认保留的请求追踪数量
+const DEFAULT_TRACE_CAPACITY: usize = 500;
+
+/// 追踪步骤内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceStepKind {
+    /// 模型别名解析
+    AliasResolved { from: String, to: String },
+    /// 参数注入
+    InjectionApplied {
+        applied_rules: Vec<String>,
+        injected_params: Vec<String>,
+    },
+    /// 选择 Provider
+    ProviderSelected { provider: String, reason: String },
+    /// 命中响应缓存
+    CacheHit,
+    /// 选择凭证
+    CredentialSelected {
+        provider: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        credential_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        credential_name: Option<String>,
+        /// 凭证来源（pool / api_key_provider）
+        source: String,
+    },
+    /// Anthropic 原生调用生效的版本请求头
+    AnthropicHeaders {
+        version: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        beta: Option<String>,
+    },
+    /// 重试
+    Retry {
+        attempt: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        status_code: Option<u16>,
+        error: String,
+    },
+    /// 故障转移
+    Failover {
+        from: String,
+        to: String,
+        reason: String,
+    },
+    /// 目标 Provider 不支持、已丢弃的请求参数
+    ParamsDropped {
+        provider: String,
+        params: Vec<String>,
+    },
+    /// 截断了超出预算的工具结果
+    ToolResultsTruncated {
+        provider: String,
+        count: usize,
+        removed_bytes: usize,
+    },
+    /// 上游响应
+    UpstreamResponse { status_code: u16 },
+    /// 请求失败
+    Failed { error: String },
+}
+
+/// 追踪步骤
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceStep {
+    /// 距请求开始的耗时（毫秒）
+    pub elapsed_ms: u64,
+    /// 记录时间
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: TraceStepKind,
+}
+
+/// Provider 调用期间捕获的步骤
+#[derive(Debug, Clone)]
+pub struct CapturedStep {
+    /// 发生时刻，写入上下文时据此计算耗时
+    pub at: Instant,
+    pub timestamp: DateTime<Utc>,
+    pub kind: TraceStepKind,
+}
+
+tokio::task_local! {
+    /// 当前 Provider 调用捕获到的步骤
+    static CAPTURED_STEPS: Arc<Mutex<Vec<CapturedStep>>>;
+}
+
+/// 在 `fut` 内捕获追踪步骤，返回 `fut` 的结果和按发生顺序排列的步骤
+pub async fn with_step_capture<F: Future>(fut: F) -> (F::Output, Vec<CapturedStep>) {
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let output = CAPTURED_STEPS.scope(captured.clone(), fut).await;
+    let steps = std::mem::take(&mut *captured.lock());
+    (output, steps)
+}
+
+/// 记录一个步骤，仅在 [`with_step_capture`] 内生效
+pub fn record_captured_step(kind: TraceStepKind) {
+    let _ = CAPTURED_STEPS.try_with(|captured| {
+        captured.lock().push(CapturedStep {
+            at: Instant::now(),
+            timestamp: Utc::now(),
+            kind,
+        });
+    });
+}
+
+/// 单个请求的完整追踪
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestTrace {
+    pub request_id: String,
+    /// 请求中的原始模型
+    pub model: String,
+    pub started_at: DateTime<Utc>,
+    /// 按发生顺序排列的步骤
+    pub steps: Vec<TraceStep>,
+}
+
+/// 请求追踪存储
+///
+/// 只保留最近的请求，超出容量时淘汰最早开始的请求。
+#[derive(Debug)]
+pub struct RequestTraceStore {
+    capacity: usize,
+    traces: Mutex<VecDeque<RequestTrace>>,
+}
+
+impl RequestTraceStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            traces: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn with_defaults() -> Self {
+        Self::new(DEFAULT_TRACE_CAPACITY)
+    }
+
+    /// 开始追踪一个请求
+    pub fn begin(&self, request_id: &str, model: &str, started_at: DateTime<Utc>) {
+        let mut traces = self.traces.lock();
+        while traces.len() >= self.capacity {
+            traces.pop_front();
+        }
+        traces.push_back(RequestTrace {
+            request_id: request_id.to_string(),
+            model: model.to_string(),
+            started_at,
+            steps: Vec::new(),
+        });
+    }
+
+    /// 追加步骤（请求已被淘汰时忽略）
+    pub fn push(&self, request_id: &str, step: TraceStep) {
+        let mut traces = self.traces.lock();
+        if let Some(trace) = traces.iter_mut().rev().find(|t| t.request_id == request_id) {
+            trace.steps.push(step);
+        }
+    }
+
+    /// 按请求 ID 获取追踪
+    pub fn get(&self, request_id: &str) -> Option<RequestTrace> {
+        self.traces
+            .lock()
+            .iter()
+            .rev()
+            .find(|t| t.request_id == request_id)
+            .cloned()
+    }
+
+    /// 获取最近的请求追踪（最新的在前）
+    pub fn recent(&self, limit: usize) -> Vec<RequestTrace> {
+        self.traces
+            .lock()
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// 清空所有追踪，返回清除的数量
+    pub fn clear(&self) -> usize {
+        let mut traces = self.traces.lock();
+        let count = traces.len();
+        traces.clear();
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(kind: TraceStepKind) -> TraceStep {
+        TraceStep {
+            elapsed_ms: 0,
+            timestamp: Utc::now(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_store_evicts_oldest_trace() {
+        let store = RequestTraceStore::new(2);
+        store.begin("a", "m", Utc::now());
+        store.begin("b", "m", Utc::now());
+        store.begin("c", "m", Utc::now());
+
+        assert!(store.get("a").is_none());
+        let ids: Vec<_> = store.recent(10).into_iter().map(|t| t.request_id).collect();
+        assert_eq!(ids, vec!["c", "b"]);
+
+        // 已淘汰的请求不再接受步骤
+        store.push("a", step(TraceStepKind::CacheHit));
+        assert!(store.get("a").is_none());
+    }
+
+    #[test]
+    fn test_step_serializes_with_kind_tag() {
+        let value = serde_json::to_value(step(TraceStepKind::AliasResolved {
+            from: "gpt-4".to_string(),
+            to: "claude-sonnet-4-5".to_string(),
+        }))
+        .unwrap();
+
+        assert_eq!(value["kind"], "alias_resolved");
+        assert_eq!(value["to"], "claude-sonnet-4-5");
+        assert!(value.get("elapsed_ms").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_step_capture_only_within_scope() {
+        record_captured_step(TraceStepKind::CacheHit);
+
+        let ((), steps) = with_step_capture(async {
+            record_captured_step(TraceStepKind::Retry {
+                attempt: 1,
+                status_code: Some(401),
+                error: "token expired".to_string(),
+            });
+            tokio::task::yield_now().await;
+            record_captured_step(TraceStepKind::UpstreamResponse { status_code: 200 });
+        })
+        .await;
+
+        let kinds: Vec<_> = steps.into_iter().map(|s| s.kind).collect();
+        assert_eq!(kinds.len(), 2);
+        assert!(matches!(kinds[0], TraceStepKind::Retry { attempt: 1, .. }));
+        assert_eq!(
+            kinds[1],
+            TraceStepKind::UpstreamResponse { status_code: 200 }
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! 响应缓存
+//!
+//! 对确定性（temperature 为 0 或未设置）的非流式请求缓存上游响应，
+//! 相同 Provider + 相同请求体在有效期内直接返回缓存，不再调用上游。
+//!
+//! 开启 `offline_fallback` 后过期条目不会被主动清理（仍受容量限制），
+//! 上游连接失败时可通过 [`ResponseCache::get_stale`] 返回最近一次的缓存响应。
+
+use crate::models::openai::ChatCompletionRequest;
+use bytes::Bytes;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// 响应缓存配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseCacheConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 缓存有效期
+    pub ttl: Duration,
+    /// 最大缓存条目数
+    pub max_entries: usize,
+    /// 上游连接失败时是否返回过期的缓存响应
+    pub offline_fallback: bool,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl: Duration::from_secs(300),
+            max_entries: 256,
+            offline_fallback: false,
+        }
+    }
+}
+
+impl From<&crate::config::ResponseCacheSettings> for ResponseCacheConfig {
+    fn from(settings: &crate::config::ResponseCacheSettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            ttl: Duration::from_secs(settings.ttl_secs),
+            max_entries: settings.max_entries,
+            offline_fallback: settings.offline_fallback,
+        }
+    }
+}
+
+/// 缓存的响应
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    /// 响应体
+    pub body: Bytes,
+    /// Content-Type
+    pub content_type: Option<String>,
+    inserted_at: Instant,
+}
+
+/// 响应缓存统计
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseCacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    pub max_entries: usize,
+    pub ttl_secs: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// 命中率（0.0 - 1.0），无查询时为 0
+    pub hit_rate: f64,
+    /// 离线降级返回过期缓存的次数
+    pub stale_hits: u64,
+}
+
+/// 响应缓存
+pub struct ResponseCache {
+    config: RwLock<ResponseCacheConfig>,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale_hits: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stale_hits: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_defaults() -> Self {
+        Self::new(ResponseCacheConfig::default())
+    }
+
+    /// 更新配置（禁用时清空缓存）
+    pub fn update_config(&self, config: ResponseCacheConfig) {
+        if !config.enabled {
+            self.entries.lock().clear();
+        }
+        *self.config.write() = config;
+    }
+
+    /// 计算请求的缓存键
+    ///
+    /// 仅在启用缓存、非流式且 temperature 为 0 或未设置时返回 Some，
+    /// 避免缓存采样输出。
+    pub fn cache_key(&self, provider: &str, request: &ChatCompletionRequest) -> Option<String> {
+        if !self.config.read().enabled || request.stream {
+            return None;
+        }
+        if request.temperature.is_some_and(|t| t != 0.0) {
+            return None;
+        }
+
+        let body = serde_json::to_vec(request).ok()?;
+        let mut hasher = Sha256::new();
+        hasher.update(provider.to_lowercase().as_bytes());
+        hasher.update(b"\n");
+        hasher.update(&body);
+        Some(hex::encode(hasher.finalize()))
+    }
+
+    /// 查询缓存（过期条目会被移除，开启离线降级时保留）
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        let config = self.config.read().clone();
+        let mut entries = self.entries.lock();
+        let hit = match entries.get(key) {
+            Some(entry) if entry.inserted_at.elapsed() < config.ttl => Some(entry.clone()),
+            Some(_) => {
+                if !config.offline_fallback {
+                    entries.remove(key);
+                }
+                None
+            }
+            None => None,
+        };
+
+        if hit.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        hit
+    }
+
+    /// 查询缓存，忽略有效期（用于上游连接失败时的离线降级）
+    ///
+    /// 仅在启用缓存且开启 `offline_fallback` 时返回。
+    pub fn get_stale(&self, key: &str) -> Option<CachedResponse> {
+        {
+            let config = self.config.read();
+            if !config.enabled || !config.offline_fallback {
+                return None;
+            }
+        }
+        let hit = self.entries.lock().get(key).cloned();
+        if hit.is_some() {
+            self.stale_hits.fetch_add(1, Ordering::Relaxed);
+        }
+        hit
+    }
+
+    /// 写入缓存，超出容量时淘汰最早写入的条目
+    pub fn insert(&self, key: String, body: Bytes, content_type: Option<String>) {
+        let config = self.config.read().clone();
+        if !config.enabled || config.max_entries == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock();
+        if !config.offline_fallback {
+            entries.retain(|_, entry| entry.inserted_at.elapsed() < config.ttl);
+        }
+        while entries.len() >= config.max_entries && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.inserted_at)
+                .map(|(k, _)| k.clone());
+            match oldest {
+                Some(k) => {
+                    entries.remove(&k);
+                }
+                None => break,
+            }
+        }
+        entries.insert(
+            key,
+            CachedResponse {
+                body,
+                content_type,
+                inserted_at: Instant::now(),
+            },
+        );
+    }
+
+    /// 清空缓存和命中统计，返回清除的条目数
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.lock();
+        let count = entries.len();
+        entries.clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.stale_hits.store(0, Ordering::Relaxed);
+        count
+    }
+
+    pub fn stats(&self) -> ResponseCacheStats {
+        let config = self.config.read().clone();
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let total = hits + misses;
+        ResponseCacheStats {
+            enabled: config.enabled,
+            entries: self.entries.lock().len(),
+            max_entries: config.max_entries,
+            ttl_secs: config.ttl.as_secs(),
+            hits,
+            misses,
+            hit_rate: if total == 0 {
+                0.0
+            } else {
+                hits as f64 / total as f64
+            },
+            stale_hits: self.stale_hits.load(Ordering::Relaxed),
+        }
+    }
+}
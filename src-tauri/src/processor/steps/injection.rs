@@ -4,7 +4,7 @@
 
 use super::traits::{PipelineStep, StepError};
 use crate::injection::Injector;
-use crate::processor::RequestContext;
+use crate::processor::{RequestContext, TraceStepKind};
 use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -69,6 +69,10 @@ impl PipelineStep for InjectionStep {
                     "injected_params": result.injected_params
                 }),
             );
+            ctx.record_step(TraceStepKind::InjectionApplied {
+                applied_rules: result.applied_rules.clone(),
+                injected_params: result.injected_params.clone(),
+            });
         }
 
         Ok(())
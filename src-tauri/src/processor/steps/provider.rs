@@ -3,16 +3,17 @@
 //! 集成重试、故障转移和超时控制
 
 use super::traits::{PipelineStep, StepError};
-use crate::processor::RequestContext;
+use crate::processor::{RequestContext, TraceStepKind};
 use crate::resilience::{
-    Failover, FailoverConfig, FailoverManager, Retrier, RetryConfig, TimeoutConfig,
-    TimeoutController, TimeoutError,
+    ConcurrencyLimiter, Failover, FailoverConfig, FailoverManager, Retrier, RetryConfig,
+    TimeoutConfig, TimeoutController, TimeoutError,
 };
 use crate::services::provider_pool_service::ProviderPoolService;
 use crate::ProviderType;
 use async_trait::async_trait;
 use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Provider 调用结果
 #[derive(Debug, Clone)]
@@ -38,6 +39,10 @@ pub struct ProviderCallError {
     pub retryable: bool,
     /// 是否应触发故障转移
     pub should_failover: bool,
+    /// 失败前是否已经开始输出（此后不再重试）
+    pub output_started: bool,
+    /// 上游建议的重试等待时间（来自 `retry-after` 等限流头）
+    pub retry_after: Option<Duration>,
 }
 
 impl ProviderCallError {
@@ -48,6 +53,8 @@ impl ProviderCallError {
             status_code,
             retryable: true,
             should_failover: false,
+            output_started: false,
+            retry_after: None,
         }
     }
 
@@ -58,6 +65,8 @@ impl ProviderCallError {
             status_code,
             retryable: false,
             should_failover: true,
+            output_started: false,
+            retry_after: None,
         }
     }
 
@@ -68,9 +77,17 @@ impl ProviderCallError {
             status_code,
             retryable: false,
             should_failover: false,
+            output_started: false,
+            retry_after: None,
         }
     }
 
+    /// 附带上游建议的重试等待时间
+    pub fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
     /// 检查是否为配额超限错误
     pub fn is_quota_exceeded(&self) -> bool {
         Failover::is_quota_exceeded(self.status_code, &self.message)
@@ -87,6 +104,8 @@ pub struct ProviderStep {
     failover: Arc<Failover>,
     /// 超时控制器
     timeout: Arc<TimeoutController>,
+    /// 并发限制器
+    concurrency: Arc<ConcurrencyLimiter>,
     /// 凭证池服务
     pool_service: Arc<ProviderPoolService>,
 }
@@ -103,6 +122,7 @@ impl ProviderStep {
             retrier,
             failover,
             timeout,
+            concurrency: Arc::new(ConcurrencyLimiter::with_defaults()),
             pool_service,
         }
     }
@@ -113,6 +133,7 @@ impl ProviderStep {
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::new(FailoverConfig::default())),
             timeout: Arc::new(TimeoutController::with_defaults()),
+            concurrency: Arc::new(ConcurrencyLimiter::with_defaults()),
             pool_service,
         }
     }
@@ -128,10 +149,17 @@ impl ProviderStep {
             retrier: Arc::new(Retrier::new(retry_config)),
             failover: Arc::new(Failover::new(failover_config)),
             timeout: Arc::new(TimeoutController::new(timeout_config)),
+            concurrency: Arc::new(ConcurrencyLimiter::with_defaults()),
             pool_service,
         }
     }
 
+    /// 使用共享的并发限制器
+    pub fn with_concurrency_limiter(mut self, concurrency: Arc<ConcurrencyLimiter>) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
     /// 获取重试器
     pub fn retrier(&self) -> &Retrier {
         &self.retrier
@@ -147,6 +175,11 @@ impl ProviderStep {
         &self.timeout
     }
 
+    /// 获取并发限制器
+    pub fn concurrency(&self) -> &ConcurrencyLimiter {
+        &self.concurrency
+    }
+
     /// 获取凭证池服务
     pub fn pool_service(&self) -> &ProviderPoolService {
         &self.pool_service
@@ -182,6 +215,11 @@ impl ProviderStep {
                 Err(err) => {
                     // 增加重试计数
                     ctx.increment_retry();
+                    ctx.record_step(TraceStepKind::Retry {
+                        attempt: attempts,
+                        status_code: err.status_code,
+                        error: err.message.clone(),
+                    });
 
                     tracing::warn!(
                         "[RETRY] request_id={} attempt={}/{} error={} status={:?} retryable={}",
@@ -199,9 +237,11 @@ impl ProviderStep {
                     }
 
                     // 检查状态码是否可重试
-                    let should_retry = err
-                        .status_code
-                        .is_none_or(|code| self.retrier.config().is_retryable(code));
+                    let should_retry = self.retrier.config().should_retry(
+                        err.status_code,
+                        ctx.is_stream,
+                        err.output_started,
+                    );
 
                     let should_failover = err.should_failover || err.is_quota_exceeded();
 
@@ -211,11 +251,24 @@ impl ProviderStep {
                             status_code: err.status_code,
                             retryable: false,
                             should_failover,
+                            output_started: err.output_started,
+                            retry_after: err.retry_after,
                         });
                     }
 
-                    // 等待退避时间
-                    let delay = self.retrier.backoff_delay(attempts - 1);
+                    // 等待退避时间（优先使用上游建议的等待时间，超过最大退避时间时改为故障转移）
+                    let Some(delay) = self.retrier.delay_for(attempts - 1, err.retry_after) else {
+                        tracing::warn!(
+                            "[RETRY] request_id={} 上游要求等待 {:?}，超过最大退避时间，改为故障转移",
+                            ctx.request_id,
+                            err.retry_after
+                        );
+                        return Err(ProviderCallError {
+                            retryable: false,
+                            should_failover: true,
+                            ..err
+                        });
+                    };
                     tokio::time::sleep(delay).await;
                 }
             }
@@ -258,11 +311,16 @@ impl ProviderStep {
                     timeout_ms
                 );
 
+                // 流空闲超时发生时已经开始输出，不能再重试
+                let output_started = matches!(timeout_err, TimeoutError::StreamIdleTimeout { .. });
+
                 Err(ProviderCallError {
                     message: timeout_err.to_string(),
                     status_code: Some(408),
                     retryable: true,
                     should_failover: false,
+                    output_started,
+                    retry_after: None,
                 })
             }
         }
@@ -354,6 +412,21 @@ impl ProviderStep {
                 failover_attempts
             );
 
+            // 在调用上游前获取并发许可，许可在本次 Provider 尝试结束后释放
+            let _permit = self
+                .concurrency
+                .acquire(&current_provider.to_string(), ctx.credential_id.as_deref())
+                .await
+                .map_err(|e| {
+                    tracing::warn!(
+                        "[CONCURRENCY] request_id={} provider={} error={}",
+                        ctx.request_id,
+                        current_provider,
+                        e
+                    );
+                    StepError::ConcurrencyLimited(e.to_string())
+                })?;
+
             // 重试循环
             let mut retry_attempts = 0u32;
             let result: Result<ProviderCallResult, ProviderCallError> = loop {
@@ -368,6 +441,11 @@ impl ProviderStep {
                     Ok(result) => break Ok(result),
                     Err(err) => {
                         ctx.increment_retry();
+                        ctx.record_step(TraceStepKind::Retry {
+                            attempt: retry_attempts,
+                            status_code: err.status_code,
+                            error: err.message.clone(),
+                        });
 
                         tracing::warn!(
                             "[RETRY] request_id={} attempt={}/{} error={} status={:?} retryable={}",
@@ -385,9 +463,11 @@ impl ProviderStep {
                         }
 
                         // 检查状态码是否可重试
-                        let should_retry = err
-                            .status_code
-                            .is_none_or(|code| self.retrier.config().is_retryable(code));
+                        let should_retry = self.retrier.config().should_retry(
+                            err.status_code,
+                            ctx.is_stream,
+                            err.output_started,
+                        );
 
                         let should_failover = err.should_failover || err.is_quota_exceeded();
 
@@ -397,11 +477,26 @@ impl ProviderStep {
                                 status_code: err.status_code,
                                 retryable: false,
                                 should_failover,
+                                output_started: err.output_started,
+                                retry_after: err.retry_after,
                             });
                         }
 
-                        // 等待退避时间
-                        let delay = self.retrier.backoff_delay(retry_attempts - 1);
+                        // 等待退避时间（优先使用上游建议的等待时间，超过最大退避时间时改为故障转移）
+                        let Some(delay) =
+                            self.retrier.delay_for(retry_attempts - 1, err.retry_after)
+                        else {
+                            tracing::warn!(
+                                "[RETRY] request_id={} 上游要求等待 {:?}，超过最大退避时间，改为故障转移",
+                                ctx.request_id,
+                                err.retry_after
+                            );
+                            break Err(ProviderCallError {
+                                retryable: false,
+                                should_failover: true,
+                                ..err
+                            });
+                        };
                         tokio::time::sleep(delay).await;
                     }
                 }
@@ -444,6 +539,11 @@ impl ProviderStep {
                                 new_provider,
                                 failover_result.failure_type
                             );
+                            ctx.record_step(TraceStepKind::Failover {
+                                from: current_provider.to_string(),
+                                to: new_provider.to_string(),
+                                reason: format!("{:?}", failover_result.failure_type),
+                            });
                             current_provider = new_provider;
                             continue 'failover;
                         }
@@ -585,7 +685,6 @@ mod tests {
         let step = ProviderStep::with_defaults(pool_service);
 
         // 可重试状态码
-        assert!(step.is_retryable_status(408));
         assert!(step.is_retryable_status(429));
         assert!(step.is_retryable_status(500));
         assert!(step.is_retryable_status(502));
@@ -595,6 +694,7 @@ mod tests {
         // 不可重试状态码
         assert!(!step.is_retryable_status(200));
         assert!(!step.is_retryable_status(400));
+        assert!(!step.is_retryable_status(408));
         assert!(!step.is_retryable_status(401));
         assert!(!step.is_retryable_status(403));
         assert!(!step.is_retryable_status(404));
@@ -640,6 +740,32 @@ mod tests {
         assert!(!err.retryable);
     }
 
+    #[tokio::test]
+    async fn test_execute_with_retry_long_retry_after_fails_over() {
+        let pool_service = Arc::new(ProviderPoolService::new());
+        let step = ProviderStep::with_defaults(pool_service);
+        let mut ctx = RequestContext::new("test-model".to_string());
+        let calls = std::sync::atomic::AtomicU32::new(0);
+
+        let result = step
+            .execute_with_retry(&mut ctx, || {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                async {
+                    Err(
+                        ProviderCallError::retryable("Rate limit exceeded", Some(429))
+                            .with_retry_after(Some(Duration::from_secs(120))),
+                    )
+                }
+            })
+            .await;
+
+        // 上游要求等待的时间超过最大退避时间，不再重试而是故障转移
+        let err = result.unwrap_err();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(err.should_failover);
+        assert_eq!(err.retry_after, Some(Duration::from_secs(120)));
+    }
+
     #[tokio::test]
     async fn test_handle_failover() {
         let pool_service = Arc::new(ProviderPoolService::new());
@@ -5,7 +5,7 @@
 #![allow(dead_code)]
 
 use super::traits::{PipelineStep, StepError};
-use crate::processor::RequestContext;
+use crate::processor::{RequestContext, TraceStepKind};
 use crate::router::{ModelMapper, Router};
 use crate::ProviderType;
 use async_trait::async_trait;
@@ -77,6 +77,10 @@ impl PipelineStep for RoutingStep {
         // 选择 Provider
         let provider = self.select_provider(&ctx.resolved_model).await?;
         ctx.set_provider(provider);
+        ctx.record_step(TraceStepKind::ProviderSelected {
+            provider: provider.to_string(),
+            reason: "routing_rule".to_string(),
+        });
 
         tracing::info!(
             "[ROUTE] request_id={} original_model={} resolved_model={} provider={}",
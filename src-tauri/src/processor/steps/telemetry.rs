@@ -5,7 +5,8 @@
 use super::traits::{PipelineStep, StepError};
 use crate::processor::RequestContext;
 use crate::telemetry::{
-    RequestLog, RequestStatus, StatsAggregator, TokenSource, TokenTracker, TokenUsageRecord,
+    AnthropicUsage, RequestLog, RequestStatus, StatsAggregator, TokenSource, TokenTracker,
+    TokenUsageRecord,
 };
 use crate::ProviderType;
 use async_trait::async_trait;
@@ -66,6 +67,8 @@ impl TelemetryStep {
 
         // 设置重试次数
         log.retry_count = ctx.retry_count;
+        log.request_bytes = ctx.request_bytes;
+        log.response_bytes = ctx.response_bytes;
 
         // 使用 parking_lot::RwLock 的同步写锁
         let stats = self.stats.write();
@@ -124,20 +127,16 @@ impl TelemetryStep {
             }
         }
 
-        // 尝试从 Anthropic 格式响应中提取 Token
-        if let Some(usage) = response.get("usage") {
-            let input_tokens = usage
-                .get("input_tokens")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as u32);
-            let output_tokens = usage
-                .get("output_tokens")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as u32);
-
-            if input_tokens.is_some() || output_tokens.is_some() {
-                self.record_tokens(ctx, input_tokens, output_tokens, TokenSource::Actual);
-            }
+        // 尝试从 Anthropic 格式响应中提取 Token（含提示缓存 Token）
+        if let Some(usage) = AnthropicUsage::from_response(response) {
+            let provider = ctx.provider.unwrap_or(ProviderType::Kiro);
+            let tokens = self.tokens.write();
+            tokens.record_anthropic_usage(
+                ctx.request_id.clone(),
+                provider,
+                ctx.resolved_model.clone(),
+                &usage,
+            );
         }
     }
 }
@@ -221,6 +220,31 @@ mod tests {
         assert_eq!(tokens_guard.len(), 1);
     }
 
+    #[test]
+    fn test_telemetry_step_records_anthropic_cache_tokens() {
+        let stats = Arc::new(RwLock::new(StatsAggregator::with_defaults()));
+        let tokens = Arc::new(RwLock::new(TokenTracker::with_defaults()));
+        let step = TelemetryStep::new(stats, tokens.clone());
+
+        let ctx = RequestContext::new("claude-sonnet-4-5".to_string());
+        let response = serde_json::json!({
+            "usage": {
+                "input_tokens": 10,
+                "output_tokens": 5,
+                "cache_creation_input_tokens": 200,
+                "cache_read_input_tokens": 1000
+            }
+        });
+
+        step.record_tokens_from_response(&ctx, &response);
+
+        let records = tokens.read().get_all();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].input_tokens, 10);
+        assert_eq!(records[0].cache_creation_input_tokens, 200);
+        assert_eq!(records[0].cache_read_input_tokens, 1000);
+    }
+
     #[tokio::test]
     async fn test_telemetry_step_execute() {
         let stats = Arc::new(RwLock::new(StatsAggregator::with_defaults()));
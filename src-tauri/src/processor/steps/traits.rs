@@ -40,6 +40,10 @@ pub enum StepError {
     #[error("超时: {timeout_ms}ms")]
     Timeout { timeout_ms: u64 },
 
+    /// 并发限制错误
+    #[error("并发限制: {0}")]
+    ConcurrencyLimited(String),
+
     /// 内部错误
     #[error("内部错误: {0}")]
     Internal(String),
@@ -56,6 +60,7 @@ impl StepError {
             StepError::Plugin { .. } => 500,
             StepError::Telemetry(_) => 500,
             StepError::Timeout { .. } => 408,
+            StepError::ConcurrencyLimited(_) => 429,
             StepError::Internal(_) => 500,
         }
     }
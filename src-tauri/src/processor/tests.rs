@@ -295,3 +295,110 @@ proptest! {
         }
     }
 }
+
+fn cache_request(
+    temperature: Option<f32>,
+    stream: bool,
+) -> crate::models::openai::ChatCompletionRequest {
+    serde_json::from_value(serde_json::json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "hello"}],
+        "temperature": temperature,
+        "stream": stream
+    }))
+    .unwrap()
+}
+
+fn enabled_cache(max_entries: usize) -> ResponseCache {
+    ResponseCache::new(ResponseCacheConfig {
+        enabled: true,
+        ttl: std::time::Duration::from_secs(60),
+        max_entries,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_response_cache_key_only_for_deterministic_requests() {
+    let cache = enabled_cache(8);
+
+    assert!(cache
+        .cache_key("openai", &cache_request(None, false))
+        .is_some());
+    assert!(cache
+        .cache_key("openai", &cache_request(Some(0.0), false))
+        .is_some());
+    assert!(cache
+        .cache_key("openai", &cache_request(Some(0.7), false))
+        .is_none());
+    assert!(cache
+        .cache_key("openai", &cache_request(None, true))
+        .is_none());
+
+    // 不同 Provider 使用不同的缓存键
+    assert_ne!(
+        cache.cache_key("openai", &cache_request(None, false)),
+        cache.cache_key("kiro", &cache_request(None, false))
+    );
+
+    let disabled = ResponseCache::with_defaults();
+    assert!(disabled
+        .cache_key("openai", &cache_request(None, false))
+        .is_none());
+}
+
+#[test]
+fn test_response_cache_hit_rate_and_eviction() {
+    let cache = enabled_cache(2);
+
+    assert!(cache.get("a").is_none());
+    cache.insert("a".to_string(), bytes::Bytes::from_static(b"1"), None);
+    cache.insert("b".to_string(), bytes::Bytes::from_static(b"2"), None);
+    cache.insert("c".to_string(), bytes::Bytes::from_static(b"3"), None);
+
+    // 超出容量时淘汰最早写入的条目
+    assert!(cache.get("a").is_none());
+    assert_eq!(
+        cache.get("c").unwrap().body,
+        bytes::Bytes::from_static(b"3")
+    );
+
+    let stats = cache.stats();
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 2);
+    assert!((stats.hit_rate - 1.0 / 3.0).abs() < f64::EPSILON);
+
+    assert_eq!(cache.clear(), 2);
+    assert_eq!(cache.stats().hits, 0);
+}
+
+#[test]
+fn test_response_cache_offline_fallback_keeps_expired_entries() {
+    let config = ResponseCacheConfig {
+        enabled: true,
+        ttl: std::time::Duration::ZERO,
+        max_entries: 8,
+        offline_fallback: false,
+    };
+    let cache = ResponseCache::new(config.clone());
+    cache.insert("a".to_string(), bytes::Bytes::from_static(b"1"), None);
+    assert!(cache.get("a").is_none());
+    assert!(cache.get_stale("a").is_none());
+
+    let cache = ResponseCache::new(ResponseCacheConfig {
+        offline_fallback: true,
+        ..config
+    });
+    cache.insert("a".to_string(), bytes::Bytes::from_static(b"1"), None);
+    cache.insert("b".to_string(), bytes::Bytes::from_static(b"2"), None);
+
+    // 过期条目不会作为普通命中返回，但保留给离线降级使用
+    assert!(cache.get("a").is_none());
+    assert_eq!(
+        cache.get_stale("a").unwrap().body,
+        bytes::Bytes::from_static(b"1")
+    );
+    assert!(cache.get_stale("missing").is_none());
+    assert_eq!(cache.stats().stale_hits, 1);
+}
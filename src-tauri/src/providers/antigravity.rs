@@ -41,6 +41,9 @@ const REFRESH_SKEW: i64 = 3000;
 // Token 即将过期的阈值（秒）- 10 分钟
 const TOKEN_EXPIRING_SOON_THRESHOLD: i64 = 600;
 
+// 项目 ID 发现的最大重试次数
+const DISCOVER_PROJECT_MAX_RETRIES: u32 = 3;
+
 /// Token 验证结果
 /// Requirements: 1.1, 1.2, 1.3, 1.4
 #[derive(Debug, Clone, PartialEq)]
@@ -283,10 +286,7 @@ impl Default for AntigravityProvider {
         Self {
             credentials: AntigravityCredentials::default(),
             project_id: None,
-            client: Client::builder()
-                .timeout(std::time::Duration::from_secs(120))
-                .build()
-                .unwrap_or_else(|_| Client::new()),
+            client: crate::http_client::client_for("antigravity"),
             base_urls: vec![
                 ANTIGRAVITY_BASE_URL_DAILY.to_string(),
                 ANTIGRAVITY_BASE_URL_AUTOPUSH.to_string(),
@@ -801,11 +801,53 @@ impl AntigravityProvider {
     }
 
     /// 发现项目 ID
+    ///
+    /// 失败时按指数退避重试（500ms, 1s, 2s），全部失败后返回最后一次的错误，
+    /// 由调用方决定是否使用 [`Self::fallback_project_id`]。
     pub async fn discover_project(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
         if let Some(ref project_id) = self.project_id {
             return Ok(project_id.clone());
         }
 
+        let mut retry_count = 0;
+        loop {
+            match self.discover_project_once().await {
+                Ok(project_id) => {
+                    self.project_id = Some(project_id.clone());
+                    return Ok(project_id);
+                }
+                Err(e) if retry_count < DISCOVER_PROJECT_MAX_RETRIES => {
+                    let delay_ms = 500 * (1 << retry_count);
+                    retry_count += 1;
+                    tracing::warn!(
+                        "[Antigravity] 获取项目 ID 失败: {}，{}ms 后重试 {}/{}",
+                        e,
+                        delay_ms,
+                        retry_count,
+                        DISCOVER_PROJECT_MAX_RETRIES
+                    );
+                    tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 使用随机生成的项目 ID（最后手段）
+    ///
+    /// 随机 ID 不属于当前账号，上游很可能返回 403，只在项目 ID 发现失败时使用。
+    pub fn fallback_project_id(&mut self) -> String {
+        let fallback = generate_project_id();
+        tracing::error!(
+            "[Antigravity] ⚠️ 无法获取项目 ID，使用随机生成的项目 ID {}，请求可能被上游拒绝（403）。\
+             请检查账号状态，或通过凭证设置手动指定项目 ID",
+            fallback
+        );
+        self.project_id = Some(fallback.clone());
+        fallback
+    }
+
+    async fn discover_project_once(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
         let body = serde_json::json!({
             "cloudaicompanionProject": "",
             "metadata": {
@@ -820,7 +862,6 @@ impl AntigravityProvider {
 
         if let Some(project) = resp["cloudaicompanionProject"].as_str() {
             if !project.is_empty() {
-                self.project_id = Some(project.to_string());
                 return Ok(project.to_string());
             }
         }
@@ -854,13 +895,8 @@ impl AntigravityProvider {
             .to_string();
 
         if project_id.is_empty() {
-            // 生成一个随机项目 ID 作为后备
-            let fallback = generate_project_id();
-            self.project_id = Some(fallback.clone());
-            return Ok(fallback);
+            return Err("onboardUser 未返回项目 ID".into());
         }
-
-        self.project_id = Some(project_id.clone());
         Ok(project_id)
     }
 
@@ -1209,7 +1245,9 @@ pub struct OAuthAuthUrlResult {
 
 /// 启动 OAuth 服务器并返回授权 URL（不打开浏览器）
 /// 服务器会在后台等待回调，成功后返回凭证
+/// 回调服务器在调用方绑定的 `listener` 上监听（见 `oauth_cmd::bind_oauth_callback_port`）
 pub async fn start_oauth_server_and_get_url(
+    listener: tokio::net::TcpListener,
     skip_project_id_fetch: bool,
 ) -> Result<
     (
@@ -1220,9 +1258,8 @@ pub async fn start_oauth_server_and_get_url(
 > {
     use axum::{extract::Query, response::Html, routing::get, Router};
     use std::collections::HashMap;
-    use tokio::net::TcpListener;
 
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("antigravity")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -1234,8 +1271,6 @@ pub async fn start_oauth_server_and_get_url(
     let (tx, rx) = oneshot::channel::<Result<AntigravityOAuthResult, String>>();
     let tx = Arc::new(tokio::sync::Mutex::new(Some(tx)));
 
-    // 绑定到随机端口
-    let listener = TcpListener::bind("127.0.0.1:0").await?;
     let port = listener.local_addr()?.port();
 
     let redirect_uri = format!("http://localhost:{}/oauth-callback", port);
@@ -1455,7 +1490,7 @@ pub async fn start_oauth_login_with_port(
     use std::collections::HashMap;
     use tokio::net::TcpListener;
 
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("antigravity")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -1663,13 +1698,13 @@ pub async fn start_oauth_login_with_port(
 /// 启动 OAuth 登录流程
 /// 返回 (auth_url, credentials_file_path)
 pub async fn start_oauth_login(
+    listener: tokio::net::TcpListener,
     skip_project_id_fetch: bool,
 ) -> Result<AntigravityOAuthResult, Box<dyn Error + Send + Sync>> {
     use axum::{extract::Query, response::Html, routing::get, Router};
     use std::collections::HashMap;
-    use tokio::net::TcpListener;
 
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("antigravity")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -1681,8 +1716,6 @@ pub async fn start_oauth_login(
     let (tx, rx) = oneshot::channel::<Result<AntigravityOAuthResult, String>>();
     let tx = Arc::new(tokio::sync::Mutex::new(Some(tx)));
 
-    // 绑定到随机端口
-    let listener = TcpListener::bind("127.0.0.1:0").await?;
     let port = listener.local_addr()?.port();
 
     let redirect_uri = format!("http://localhost:{}/oauth-callback", port);
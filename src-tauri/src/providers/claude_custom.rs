@@ -1,10 +1,9 @@
 //! Claude Custom Provider (自定义 Claude API)
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::{ChatCompletionRequest, ContentPart, MessageContent};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ClaudeCustomConfig {
@@ -13,24 +12,24 @@ pub struct ClaudeCustomConfig {
     pub enabled: bool,
 }
 
+/// Claude OAuth access token 调用 Messages API 时需要的 beta
+pub const CLAUDE_OAUTH_BETA: &str = "oauth-2025-04-20";
+
 pub struct ClaudeCustomProvider {
     pub config: ClaudeCustomConfig,
     pub client: Client,
+    /// `api_key` 为 Claude OAuth access token，使用 Bearer 认证
+    pub oauth: bool,
 }
 
-/// 创建配置好的 HTTP 客户端
+/// 获取共享 HTTP 客户端
 ///
 /// 配置说明：
-/// - connect_timeout: 连接超时 30 秒
-/// - timeout: 总超时 10 分钟（流式响应可能很长）
-/// - 不设置 pool_idle_timeout 以保持连接活跃
+/// - 代理、连接超时与读取超时：来自 outbound_proxy 配置
+/// - 不设置总超时，长时间流式响应由读取超时兜底
+/// - 连接池与 TCP keepalive 在请求间共享，保持连接活跃
 fn create_http_client() -> Client {
-    Client::builder()
-        .connect_timeout(Duration::from_secs(30))
-        .timeout(Duration::from_secs(600)) // 10 分钟总超时，支持长时间流式响应
-        .tcp_keepalive(Duration::from_secs(60)) // TCP keepalive 保持连接活跃
-        .build()
-        .unwrap_or_else(|_| Client::new())
+    crate::http_client::client_for("claude")
 }
 
 impl Default for ClaudeCustomProvider {
@@ -38,6 +37,7 @@ impl Default for ClaudeCustomProvider {
         Self {
             config: ClaudeCustomConfig::default(),
             client: create_http_client(),
+            oauth: false,
         }
     }
 }
@@ -56,9 +56,28 @@ impl ClaudeCustomProvider {
                 enabled: true,
             },
             client: create_http_client(),
+            oauth: false,
+        }
+    }
+
+    /// 使用 Claude OAuth access token 创建 Provider（官方 API，Bearer 认证）
+    pub fn with_oauth_token(access_token: String) -> Self {
+        Self {
+            oauth: true,
+            ..Self::with_config(access_token, None)
         }
     }
 
+    /// 构建带认证头和 Anthropic 版本请求头的 POST 请求
+    fn post(&self, url: &str, api_key: &str) -> RequestBuilder {
+        let mut headers = crate::http_client::anthropic_headers();
+        if !self.oauth {
+            return headers.apply(self.client.post(url).header("x-api-key", api_key));
+        }
+        headers.add_beta(CLAUDE_OAUTH_BETA);
+        headers.apply(self.client.post(url).bearer_auth(api_key))
+    }
+
     pub fn get_base_url(&self) -> String {
         self.config
             .base_url
@@ -85,6 +104,35 @@ impl ClaudeCustomProvider {
         }
     }
 
+    /// 将 `reasoning_effort` 映射为 Anthropic extended thinking 配置
+    ///
+    /// Anthropic 要求 `max_tokens` 大于 `budget_tokens`（不足时自动上调），
+    /// 且启用 thinking 时 temperature 必须为 1、top_p 不低于 0.95、不支持 top_k。
+    fn apply_thinking_config(body: &mut serde_json::Value, request: &ChatCompletionRequest) {
+        let Some(budget) = request.thinking_budget() else {
+            return;
+        };
+        body["thinking"] = serde_json::json!({
+            "type": "enabled",
+            "budget_tokens": budget
+        });
+        body["temperature"] = serde_json::json!(1);
+        if let Some(obj) = body.as_object_mut() {
+            obj.remove("top_k");
+            if obj
+                .get("top_p")
+                .and_then(|p| p.as_f64())
+                .is_some_and(|p| p < 0.95)
+            {
+                obj.remove("top_p");
+            }
+        }
+        let max_tokens = body["max_tokens"].as_u64().unwrap_or(0);
+        if max_tokens <= u64::from(budget) {
+            body["max_tokens"] = serde_json::json!(budget + 4096);
+        }
+    }
+
     /// 将 OpenAI 图片 URL 格式转换为 Claude 图片格式
     ///
     /// 支持两种格式：
@@ -150,10 +198,7 @@ impl ClaudeCustomProvider {
         );
 
         let resp = self
-            .client
-            .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
+            .post(&url, api_key)
             .header("Content-Type", "application/json")
             .json(request)
             .send()
@@ -241,6 +286,11 @@ impl ClaudeCustomProvider {
         if let Some(sys) = system_content {
             anthropic_body["system"] = serde_json::json!(sys);
         }
+        let stop_sequences = request.stop_sequences();
+        if !stop_sequences.is_empty() {
+            anthropic_body["stop_sequences"] = serde_json::json!(stop_sequences);
+        }
+        Self::apply_thinking_config(&mut anthropic_body, request);
 
         let api_key = self
             .config
@@ -259,10 +309,7 @@ impl ClaudeCustomProvider {
         );
 
         let resp = self
-            .client
-            .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
+            .post(&url, api_key)
             .header("Content-Type", "application/json")
             .json(&anthropic_body)
             .send()
@@ -283,12 +330,25 @@ impl ClaudeCustomProvider {
 
         let anthropic_resp: serde_json::Value = resp.json().await?;
 
-        // 转换回 OpenAI 格式
-        let content = anthropic_resp["content"]
-            .as_array()
-            .and_then(|arr| arr.first())
-            .and_then(|block| block["text"].as_str())
-            .unwrap_or("");
+        // 转换回 OpenAI 格式（thinking 块单独放入 reasoning_content）
+        let mut content = String::new();
+        let mut reasoning_content = String::new();
+        for block in anthropic_resp["content"].as_array().into_iter().flatten() {
+            match block["type"].as_str() {
+                Some("text") => content.push_str(block["text"].as_str().unwrap_or("")),
+                Some("thinking") => {
+                    reasoning_content.push_str(block["thinking"].as_str().unwrap_or(""))
+                }
+                _ => {}
+            }
+        }
+        let mut message = serde_json::json!({
+            "role": "assistant",
+            "content": content
+        });
+        if !reasoning_content.is_empty() {
+            message["reasoning_content"] = serde_json::json!(reasoning_content);
+        }
 
         Ok(serde_json::json!({
             "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
@@ -300,10 +360,7 @@ impl ClaudeCustomProvider {
             "model": request.model,
             "choices": [{
                 "index": 0,
-                "message": {
-                    "role": "assistant",
-                    "content": content
-                },
+                "message": message,
                 "finish_reason": "stop"
             }],
             "usage": {
@@ -343,10 +400,7 @@ impl ClaudeCustomProvider {
         );
 
         let resp = self
-            .client
-            .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
+            .post(&url, api_key)
             .header("Content-Type", "application/json")
             .json(request)
             .send()
@@ -375,10 +429,7 @@ impl ClaudeCustomProvider {
         let url = self.build_url("messages/count_tokens");
 
         let resp = self
-            .client
-            .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
+            .post(&url, api_key)
             .header("Content-Type", "application/json")
             .json(request)
             .send()
@@ -539,6 +590,11 @@ impl StreamingProvider for ClaudeCustomProvider {
         if let Some(sys) = system_content {
             anthropic_body["system"] = serde_json::json!(sys);
         }
+        let stop_sequences = request.stop_sequences();
+        if !stop_sequences.is_empty() {
+            anthropic_body["stop_sequences"] = serde_json::json!(stop_sequences);
+        }
+        Self::apply_thinking_config(&mut anthropic_body, request);
 
         // 转换 tools: OpenAI 格式 -> Anthropic 格式
         if let Some(ref tools) = request.tools {
@@ -610,6 +666,17 @@ impl StreamingProvider for ClaudeCustomProvider {
             }
         }
 
+        // parallel_tool_calls: false -> disable_parallel_tool_use（tool_choice 为 none 时不支持）
+        if request.parallel_tool_calls_disabled() && anthropic_body.get("tools").is_some() {
+            if anthropic_body.get("tool_choice").is_none() {
+                anthropic_body["tool_choice"] = serde_json::json!({"type": "auto"});
+            }
+            if anthropic_body["tool_choice"]["type"] != "none" {
+                anthropic_body["tool_choice"]["disable_parallel_tool_use"] =
+                    serde_json::json!(true);
+            }
+        }
+
         let url = self.build_url("messages");
 
         tracing::info!(
@@ -619,10 +686,7 @@ impl StreamingProvider for ClaudeCustomProvider {
         );
 
         let resp = self
-            .client
-            .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
+            .post(&url, api_key)
             .header("Content-Type", "application/json")
             .header("Accept", "text/event-stream")
             .json(&anthropic_body)
@@ -656,3 +720,79 @@ impl StreamingProvider for ClaudeCustomProvider {
         StreamFormat::AnthropicSse
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oauth_request_uses_bearer_and_oauth_beta() {
+        let claude = ClaudeCustomProvider::with_oauth_token("tok".to_string());
+        let url = claude.build_url("messages");
+        let req = claude.post(&url, "tok").build().unwrap();
+        let headers = req.headers();
+        assert_eq!(headers.get("authorization").unwrap(), "Bearer tok");
+        assert!(headers.get("x-api-key").is_none());
+        let beta = headers.get("anthropic-beta").unwrap().to_str().unwrap();
+        assert!(beta.split(',').any(|b| b.trim() == CLAUDE_OAUTH_BETA));
+        assert!(headers.contains_key("anthropic-version"));
+    }
+
+    #[test]
+    fn test_thinking_config_forces_temperature_one() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": "hi"}],
+            "reasoning_effort": "medium"
+        }))
+        .unwrap();
+        let mut body = serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 1024,
+            "temperature": 0.3,
+            "top_k": 40
+        });
+        ClaudeCustomProvider::apply_thinking_config(&mut body, &request);
+        assert_eq!(body["thinking"]["budget_tokens"], 8192);
+        assert_eq!(body["temperature"], 1);
+        assert!(body.get("top_k").is_none());
+        assert!(body["max_tokens"].as_u64().unwrap() > 8192);
+    }
+
+    #[test]
+    fn test_thinking_config_drops_low_top_p() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": "hi"}],
+            "reasoning_effort": "low"
+        }))
+        .unwrap();
+
+        let mut body = serde_json::json!({"max_tokens": 1024, "top_p": 0.5});
+        ClaudeCustomProvider::apply_thinking_config(&mut body, &request);
+        assert!(body.get("top_p").is_none());
+
+        let mut body = serde_json::json!({"max_tokens": 1024, "top_p": 0.97});
+        ClaudeCustomProvider::apply_thinking_config(&mut body, &request);
+        assert_eq!(body["top_p"], 0.97);
+
+        // 未启用 thinking 时保持原样
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": "hi"}]
+        }))
+        .unwrap();
+        let mut body = serde_json::json!({"max_tokens": 1024, "top_p": 0.5});
+        ClaudeCustomProvider::apply_thinking_config(&mut body, &request);
+        assert_eq!(body["top_p"], 0.5);
+    }
+
+    #[test]
+    fn test_api_key_request_uses_x_api_key() {
+        let claude = ClaudeCustomProvider::with_config("sk-ant".to_string(), None);
+        let url = claude.build_url("messages");
+        let req = claude.post(&url, "sk-ant").build().unwrap();
+        assert_eq!(req.headers().get("x-api-key").unwrap(), "sk-ant");
+        assert!(req.headers().get("authorization").is_none());
+    }
+}
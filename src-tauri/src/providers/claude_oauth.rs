@@ -125,7 +125,7 @@ impl Default for ClaudeOAuthProvider {
     fn default() -> Self {
         Self {
             credentials: ClaudeOAuthCredentials::default(),
-            client: Client::new(),
+            client: crate::http_client::client_for("claude_oauth"),
             creds_path: None,
         }
     }
@@ -570,7 +570,7 @@ pub async fn exchange_claude_authorization_code(
     code_verifier: &str,
     state: &str,
 ) -> Result<ClaudeOAuthResult, Box<dyn Error + Send + Sync>> {
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("claude_oauth")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -862,7 +862,7 @@ pub async fn oauth_with_cookie(
     session_key: &str,
     is_setup_token: bool,
 ) -> Result<CookieOAuthResult, Box<dyn Error + Send + Sync>> {
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("claude_oauth")
         .timeout(std::time::Duration::from_secs(30))
         .redirect(reqwest::redirect::Policy::none()) // 禁止自动重定向
         .build()?;
@@ -394,7 +394,7 @@ impl Default for CodexProvider {
     fn default() -> Self {
         Self {
             credentials: CodexCredentials::default(),
-            client: Client::new(),
+            client: crate::http_client::client_for("codex"),
             creds_path: None,
             callback_port: DEFAULT_CALLBACK_PORT,
         }
@@ -1166,7 +1166,7 @@ impl CodexProvider {
 /// Extracts user information from the JWT ID token returned by OpenAI OAuth.
 /// The account_id is extracted from the `chatgpt_account_id` field in the
 /// `https://api.openai.com/auth` claim, which is required for Codex API calls.
-fn parse_jwt_claims(token: &str) -> (Option<String>, Option<String>) {
+pub(crate) fn parse_jwt_claims(token: &str) -> (Option<String>, Option<String>) {
     use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 
     let parts: Vec<&str> = token.split('.').collect();
@@ -1758,7 +1758,7 @@ pub struct CodexOAuthResult {
 }
 
 /// OpenAI OAuth 固定回调端口（必须与 client_id 注册的回调地址一致）
-const OPENAI_OAUTH_CALLBACK_PORT: u16 = 1455;
+pub const OPENAI_OAUTH_CALLBACK_PORT: u16 = 1455;
 
 /// OpenAI OAuth 固定回调路径（必须与 client_id 注册的回调地址一致）
 const OPENAI_OAUTH_CALLBACK_PATH: &str = "/auth/callback";
@@ -1877,9 +1877,13 @@ const CODEX_OAUTH_ERROR_HTML: &str = r#"<!DOCTYPE html>
 
 /// 启动 OAuth 服务器并返回授权 URL（不打开浏览器）
 /// 服务器会在后台等待回调，成功后返回凭证
+/// 回调服务器在调用方绑定的 `listener` 上监听（见 `oauth_cmd::bind_oauth_callback_port`）
 ///
-/// 注意：OpenAI OAuth 要求使用固定的回调地址 http://localhost:1455/auth/callback
-pub async fn start_codex_oauth_server_and_get_url() -> Result<
+/// 注意：OpenAI OAuth 要求使用固定的回调地址 http://localhost:1455/auth/callback，
+/// `listener` 必须绑定在 [`OPENAI_OAUTH_CALLBACK_PORT`] 上
+pub async fn start_codex_oauth_server_and_get_url(
+    listener: tokio::net::TcpListener,
+) -> Result<
     (
         String,
         impl std::future::Future<Output = Result<CodexOAuthResult, Box<dyn Error + Send + Sync>>>,
@@ -1888,9 +1892,8 @@ pub async fn start_codex_oauth_server_and_get_url() -> Result<
 > {
     use axum::{extract::Query, response::Html, routing::get, Router};
     use std::collections::HashMap;
-    use tokio::net::TcpListener;
 
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("codex")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -1907,18 +1910,7 @@ pub async fn start_codex_oauth_server_and_get_url() -> Result<
     let (tx, rx) = oneshot::channel::<Result<CodexOAuthResult, String>>();
     let tx = Arc::new(tokio::sync::Mutex::new(Some(tx)));
 
-    // 使用固定端口 1455（OpenAI OAuth 要求）
-    let port = OPENAI_OAUTH_CALLBACK_PORT;
-    let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).await.map_err(|e| {
-        if e.kind() == std::io::ErrorKind::AddrInUse {
-            format!(
-                "端口 {} 已被占用。OpenAI OAuth 要求使用固定端口 1455，请关闭占用该端口的应用后重试。",
-                port
-            )
-        } else {
-            format!("绑定端口 {} 失败: {}", port, e)
-        }
-    })?;
+    let port = listener.local_addr()?.port();
 
     let redirect_uri = format!(
         "http://localhost:{}{}",
@@ -2128,8 +2120,10 @@ pub async fn start_codex_oauth_server_and_get_url() -> Result<
 }
 
 /// 启动 Codex OAuth 登录流程（自动打开浏览器）
-pub async fn start_codex_oauth_login() -> Result<CodexOAuthResult, Box<dyn Error + Send + Sync>> {
-    let (auth_url, wait_future) = start_codex_oauth_server_and_get_url().await?;
+pub async fn start_codex_oauth_login(
+    listener: tokio::net::TcpListener,
+) -> Result<CodexOAuthResult, Box<dyn Error + Send + Sync>> {
+    let (auth_url, wait_future) = start_codex_oauth_server_and_get_url(listener).await?;
 
     tracing::info!("[Codex OAuth] 打开浏览器进行授权: {}", auth_url);
 
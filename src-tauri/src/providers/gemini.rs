@@ -184,7 +184,7 @@ impl Default for GeminiProvider {
         Self {
             credentials: GeminiCredentials::default(),
             project_id: None,
-            client: Client::new(),
+            client: crate::http_client::client_for("gemini"),
         }
     }
 }
@@ -577,7 +577,7 @@ impl GeminiApiKeyProvider {
     /// Create a new Gemini API Key provider
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http_client::client_for("gemini"),
         }
     }
 
@@ -864,7 +864,7 @@ pub async fn exchange_gemini_code_and_create_credentials(
     code: &str,
     code_verifier: &str,
 ) -> Result<GeminiOAuthResult, Box<dyn std::error::Error + Send + Sync>> {
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("gemini")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -1100,7 +1100,10 @@ async fn save_gemini_credentials_to_file(
 
 /// 启动 OAuth 服务器并返回授权 URL（不打开浏览器）
 /// 服务器会在后台等待回调，成功后返回凭证
-pub async fn start_gemini_oauth_server_and_get_url() -> Result<
+/// 回调服务器在调用方绑定的 `listener` 上监听（见 `oauth_cmd::bind_oauth_callback_port`）
+pub async fn start_gemini_oauth_server_and_get_url(
+    listener: tokio::net::TcpListener,
+) -> Result<
     (
         String,
         impl std::future::Future<
@@ -1111,9 +1114,8 @@ pub async fn start_gemini_oauth_server_and_get_url() -> Result<
 > {
     use axum::{extract::Query, response::Html, routing::get, Router};
     use std::collections::HashMap;
-    use tokio::net::TcpListener;
 
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("gemini")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -1129,27 +1131,7 @@ pub async fn start_gemini_oauth_server_and_get_url() -> Result<
     let (tx, rx) = oneshot::channel::<Result<GeminiOAuthResult, String>>();
     let tx = Arc::new(tokio::sync::Mutex::new(Some(tx)));
 
-    // 尝试绑定到多个端口
-    let ports_to_try = [11451, 11452, 11453, 11454, 11455, 0];
-    let mut listener = None;
-    let mut bound_port = 0;
-
-    for port in ports_to_try {
-        match TcpListener::bind(format!("127.0.0.1:{}", port)).await {
-            Ok(l) => {
-                bound_port = l.local_addr()?.port();
-                listener = Some(l);
-                tracing::info!("[Gemini OAuth] 成功绑定到端口 {}", bound_port);
-                break;
-            }
-            Err(e) => {
-                tracing::warn!("[Gemini OAuth] 端口 {} 绑定失败: {}", port, e);
-                continue;
-            }
-        }
-    }
-
-    let listener = listener.ok_or("无法绑定到任何可用端口")?;
+    let bound_port = listener.local_addr()?.port();
 
     // 生成授权 URL
     let auth_url = generate_gemini_auth_url(&state, &code_challenge);
@@ -1345,8 +1327,9 @@ pub async fn start_gemini_oauth_server_and_get_url() -> Result<
 
 /// 启动 Gemini OAuth 登录流程（自动打开浏览器）
 pub async fn start_gemini_oauth_login(
+    listener: tokio::net::TcpListener,
 ) -> Result<GeminiOAuthResult, Box<dyn std::error::Error + Send + Sync>> {
-    let (auth_url, wait_future) = start_gemini_oauth_server_and_get_url().await?;
+    let (auth_url, wait_future) = start_gemini_oauth_server_and_get_url(listener).await?;
 
     // 打开浏览器
     tracing::info!("[Gemini OAuth] 正在打开浏览器...");
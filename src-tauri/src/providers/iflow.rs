@@ -470,7 +470,7 @@ impl Default for IFlowProvider {
     fn default() -> Self {
         Self {
             credentials: IFlowCredentials::default(),
-            client: Client::new(),
+            client: crate::http_client::client_for("iflow"),
             creds_path: None,
             callback_port: DEFAULT_CALLBACK_PORT,
         }
@@ -1264,14 +1264,6 @@ impl IFlowProvider {
         Ok(resp)
     }
 
-    /// Call the iFlow API with streaming response
-    pub async fn call_api_stream(
-        &self,
-        request: &serde_json::Value,
-    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
-        self.call_api(request).await
-    }
-
     /// Check if this provider supports the given model
     pub fn supports_model(model: &str) -> bool {
         let model_lower = model.to_lowercase();
@@ -1279,6 +1271,62 @@ impl IFlowProvider {
     }
 }
 
+// ============================================================================
+// StreamingProvider Trait 实现
+// ============================================================================
+
+use crate::models::openai::ChatCompletionRequest;
+use crate::streaming::traits::{
+    reqwest_stream_to_stream_response, StreamFormat, StreamResponse, StreamingProvider,
+};
+use async_trait::async_trait;
+
+#[async_trait]
+impl StreamingProvider for IFlowProvider {
+    /// 发起流式 API 调用
+    ///
+    /// iFlow 接口兼容 OpenAI，直接返回上游 OpenAI SSE 字节流。
+    async fn call_api_stream(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<StreamResponse, ProviderError> {
+        let mut stream_request = request.clone();
+        stream_request.stream = true;
+        let request_json = serde_json::to_value(&stream_request)
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+
+        tracing::info!("[IFLOW_STREAM] 发起流式请求: model={}", request.model);
+
+        let resp = self.call_api(&request_json).await.map_err(|e| {
+            match e.downcast_ref::<reqwest::Error>() {
+                Some(err) => ProviderError::from_reqwest_error(err),
+                None => ProviderError::AuthenticationError(e.to_string()),
+            }
+        })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            tracing::error!("[IFLOW_STREAM] 请求失败: {} - {}", status, body);
+            return Err(ProviderError::from_http_status(status.as_u16(), &body));
+        }
+
+        Ok(reqwest_stream_to_stream_response(resp))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.is_valid()
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "IFlowProvider"
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::OpenAiSse
+    }
+}
+
 /// Parse cookie string to extract expiration time
 ///
 /// Looks for Expires or Max-Age attributes in the cookie string.
@@ -1763,7 +1811,10 @@ pub async fn exchange_iflow_code_for_token(
 }
 
 /// 启动 OAuth 服务器并返回授权 URL（不打开浏览器）
-pub async fn start_iflow_oauth_server_and_get_url() -> Result<
+/// 回调服务器在调用方绑定的 `listener` 上监听（见 `oauth_cmd::bind_oauth_callback_port`）
+pub async fn start_iflow_oauth_server_and_get_url(
+    listener: tokio::net::TcpListener,
+) -> Result<
     (
         String,
         impl std::future::Future<Output = Result<IFlowOAuthResult, Box<dyn Error + Send + Sync>>>,
@@ -1772,9 +1823,8 @@ pub async fn start_iflow_oauth_server_and_get_url() -> Result<
 > {
     use axum::{extract::Query, response::Html, routing::get, Router};
     use std::collections::HashMap;
-    use tokio::net::TcpListener;
 
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("iflow")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -1786,32 +1836,6 @@ pub async fn start_iflow_oauth_server_and_get_url() -> Result<
     let (tx, rx) = oneshot::channel::<Result<IFlowOAuthResult, String>>();
     let tx = Arc::new(tokio::sync::Mutex::new(Some(tx)));
 
-    // 尝试绑定到端口，如果默认端口被占用则尝试其他端口
-    let ports_to_try = [DEFAULT_CALLBACK_PORT, 11452, 11453, 11454, 11455, 0]; // 0 表示让系统分配
-    let mut listener = None;
-    let mut last_error = None;
-
-    for port in ports_to_try {
-        match TcpListener::bind(format!("127.0.0.1:{}", port)).await {
-            Ok(l) => {
-                listener = Some(l);
-                break;
-            }
-            Err(e) => {
-                last_error = Some(e);
-                continue;
-            }
-        }
-    }
-
-    let listener = listener.ok_or_else(|| {
-        format!(
-            "启动 OAuth 服务器失败: {}",
-            last_error
-                .map(|e| e.to_string())
-                .unwrap_or_else(|| "无法绑定端口".to_string())
-        )
-    })?;
     let port = listener.local_addr()?.port();
 
     let redirect_uri = format!("http://localhost:{}/oauth2callback", port);
@@ -1991,8 +2015,10 @@ pub async fn start_iflow_oauth_server_and_get_url() -> Result<
 }
 
 /// 启动 iFlow OAuth 登录流程（自动打开浏览器）
-pub async fn start_iflow_oauth_login() -> Result<IFlowOAuthResult, Box<dyn Error + Send + Sync>> {
-    let (auth_url, wait_future) = start_iflow_oauth_server_and_get_url().await?;
+pub async fn start_iflow_oauth_login(
+    listener: tokio::net::TcpListener,
+) -> Result<IFlowOAuthResult, Box<dyn Error + Send + Sync>> {
+    let (auth_url, wait_future) = start_iflow_oauth_server_and_get_url(listener).await?;
 
     tracing::info!("[iFlow OAuth] 打开浏览器进行授权: {}", auth_url);
 
@@ -52,6 +52,25 @@ pub fn generate_machine_id_from_credentials_with_uuid(
     format!("{:x}", result)
 }
 
+/// 计算凭证的设备指纹，返回 (Machine ID, 指纹来源)
+///
+/// 来源为 profileArn / clientId / system，优先级与 [`generate_machine_id_from_credentials`] 一致
+pub fn credential_fingerprint(creds: &KiroCredentials) -> (String, &'static str) {
+    let profile_arn = creds.profile_arn.as_deref().filter(|s| !s.is_empty());
+    let client_id = creds.client_id.as_deref().filter(|s| !s.is_empty());
+    let source = if profile_arn.is_some() {
+        "profileArn"
+    } else if client_id.is_some() {
+        "clientId"
+    } else {
+        "system"
+    };
+    (
+        generate_machine_id_from_credentials(profile_arn, client_id),
+        source,
+    )
+}
+
 /// 获取系统运行时信息
 ///
 /// 返回真实的操作系统名称和版本，用于构建更真实的 User-Agent
@@ -161,10 +180,15 @@ fn get_raw_machine_id() -> Option<String> {
 
 /// 获取 Kiro IDE 版本号
 ///
-/// 尝试从 Kiro.app 的 Info.plist 读取实际版本，失败时使用默认值
+/// 优先使用 `upstream_headers.kiro_version` 配置，其次尝试从 Kiro.app 的 Info.plist
+/// 读取实际版本，失败时使用默认值
 fn get_kiro_version() -> String {
     use std::process::Command;
 
+    if let Some(version) = crate::http_client::kiro_version() {
+        return version;
+    }
+
     if cfg!(target_os = "macos") {
         // 尝试从 Kiro.app 读取版本
         let kiro_paths = [
@@ -246,24 +270,14 @@ impl Default for KiroCredentials {
 
 pub struct KiroProvider {
     pub credentials: KiroCredentials,
-    pub client: Client,
     /// 当前加载的凭证文件路径
     pub creds_path: Option<PathBuf>,
 }
 
 impl Default for KiroProvider {
     fn default() -> Self {
-        // 创建带超时配置的 HTTP 客户端
-        // 参考 AIClient-2-API: AXIOS_TIMEOUT: 300000 (5分钟)
-        let client = Client::builder()
-            .connect_timeout(std::time::Duration::from_secs(30)) // 连接超时 30 秒
-            .timeout(std::time::Duration::from_secs(300)) // 总超时 5 分钟
-            .build()
-            .unwrap_or_else(|_| Client::new());
-
         Self {
             credentials: KiroCredentials::default(),
-            client,
             creds_path: None,
         }
     }
@@ -274,6 +288,11 @@ impl KiroProvider {
         Self::default()
     }
 
+    /// 共享 HTTP 客户端，每次调用时按当前 outbound_proxy 配置获取，代理变更立即生效
+    fn client(&self) -> Client {
+        crate::http_client::client_for("kiro")
+    }
+
     pub fn default_creds_path() -> PathBuf {
         dirs::home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -758,7 +777,7 @@ impl KiroProvider {
             tracing::debug!("[KIRO] IdC 刷新请求体已构建");
 
             // IdC 认证的 Headers（参考 Kir-Manager）
-            self.client
+            self.client()
                 .post(&refresh_url)
                 .header("Content-Type", "application/json")
                 .header("Host", "oidc.us-east-1.amazonaws.com")
@@ -777,7 +796,7 @@ impl KiroProvider {
             let body = serde_json::json!({ "refreshToken": &refresh_token });
 
             // Social 认证的 Headers（参考 Kir-Manager）
-            self.client
+            self.client()
                 .post(&refresh_url)
                 .header(
                     "User-Agent",
@@ -1018,7 +1037,7 @@ impl KiroProvider {
         );
 
         let resp = self
-            .client
+            .client()
             .post(&url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
@@ -1037,6 +1056,8 @@ impl KiroProvider {
                     "aws-sdk-js/1.0.0 ua/2.1 os/{os_name} lang/js md/nodejs#{node_version} api/codewhispererruntime#1.0.0 m/E KiroIDE-{kiro_version}-{machine_id}"
                 ),
             )
+            // 凭证级请求头覆盖上面的默认指纹头
+            .headers(crate::http_client::credential_headers())
             // 添加 Connection: close 避免连接复用被检测
             .header("Connection", "close")
             .json(&cw_request)
@@ -1172,7 +1193,7 @@ impl StreamingProvider for KiroProvider {
         );
 
         let resp = self
-            .client
+            .client()
             .post(&url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
@@ -1190,6 +1211,8 @@ impl StreamingProvider for KiroProvider {
                     "aws-sdk-js/1.0.0 ua/2.1 os/{os_name} lang/js md/nodejs#{node_version} api/codewhispererruntime#1.0.0 m/E KiroIDE-{kiro_version}-{machine_id}"
                 ),
             )
+            // 凭证级请求头覆盖上面的默认指纹头
+            .headers(crate::http_client::credential_headers())
             // 注意：不要设置 Connection: close，否则会导致流式响应无法工作
             .json(&cw_request)
             .send()
@@ -1272,7 +1295,7 @@ impl KiroProvider {
         );
 
         let resp = self
-            .client
+            .client()
             .post(&url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
@@ -1290,6 +1313,8 @@ impl KiroProvider {
                     "aws-sdk-js/1.0.0 ua/2.1 os/{os_name} lang/js md/nodejs#{node_version} api/codewhispererruntime#1.0.0 m/E KiroIDE-{kiro_version}-{machine_id}"
                 ),
             )
+            // 凭证级请求头覆盖上面的默认指纹头
+            .headers(crate::http_client::credential_headers())
             .json(&cw_request)
             .send()
             .await
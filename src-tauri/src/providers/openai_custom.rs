@@ -20,7 +20,7 @@ impl Default for OpenAICustomProvider {
     fn default() -> Self {
         Self {
             config: OpenAICustomConfig::default(),
-            client: Client::new(),
+            client: crate::http_client::client_for("openai"),
         }
     }
 }
@@ -38,7 +38,7 @@ impl OpenAICustomProvider {
                 base_url,
                 enabled: true,
             },
-            client: Client::new(),
+            client: crate::http_client::client_for("openai"),
         }
     }
 
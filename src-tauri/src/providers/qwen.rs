@@ -79,7 +79,7 @@ impl Default for QwenProvider {
     fn default() -> Self {
         Self {
             credentials: QwenCredentials::default(),
-            client: Client::new(),
+            client: crate::http_client::client_for("qwen"),
         }
     }
 }
@@ -299,26 +299,100 @@ impl QwenProvider {
 
         let base_url = self.get_base_url();
         let url = format!("{base_url}/chat/completions");
+        let req_body = Self::normalize_model(request.clone());
 
-        // Ensure model is valid
-        let mut req_body = request.clone();
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", "application/json")
+            .header("X-DashScope-AuthType", "qwen-oauth")
+            .json(&req_body)
+            .send()
+            .await?;
+
+        Ok(resp)
+    }
+
+    /// 不支持的模型替换为默认模型
+    fn normalize_model(mut req_body: serde_json::Value) -> serde_json::Value {
         if let Some(model) = req_body.get("model").and_then(|m| m.as_str()) {
             if !QWEN_MODELS.contains(&model) {
                 req_body["model"] = serde_json::json!(QWEN_MODELS[0]);
             }
         }
+        req_body
+    }
+}
+
+// ============================================================================
+// StreamingProvider Trait 实现
+// ============================================================================
+
+use crate::models::openai::ChatCompletionRequest;
+use crate::streaming::traits::{
+    reqwest_stream_to_stream_response, StreamFormat, StreamResponse, StreamingProvider,
+};
+
+#[async_trait]
+impl StreamingProvider for QwenProvider {
+    /// 发起流式 API 调用
+    ///
+    /// Qwen 接口兼容 OpenAI，直接返回上游 OpenAI SSE 字节流。
+    async fn call_api_stream(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<StreamResponse, ProviderError> {
+        let token = self.credentials.access_token.as_ref().ok_or_else(|| {
+            ProviderError::AuthenticationError("No access token available".to_string())
+        })?;
+
+        let mut stream_request = request.clone();
+        stream_request.stream = true;
+        let req_body = Self::normalize_model(
+            serde_json::to_value(&stream_request)
+                .map_err(|e| ProviderError::ParseError(e.to_string()))?,
+        );
+
+        let url = format!("{}/chat/completions", self.get_base_url());
+        tracing::info!(
+            "[QWEN_STREAM] 发起流式请求: url={} model={}",
+            url,
+            request.model
+        );
 
         let resp = self
             .client
             .post(&url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
+            .header("Accept", "text/event-stream")
             .header("X-DashScope-AuthType", "qwen-oauth")
             .json(&req_body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::from_reqwest_error(&e))?;
 
-        Ok(resp)
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            tracing::error!("[QWEN_STREAM] 请求失败: {} - {}", status, body);
+            return Err(ProviderError::from_http_status(status.as_u16(), &body));
+        }
+
+        Ok(reqwest_stream_to_stream_response(resp))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.credentials.access_token.is_some()
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "QwenProvider"
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::OpenAiSse
     }
 }
 
@@ -547,7 +621,7 @@ pub async fn poll_for_token(
 /// 启动 Qwen Device Code Flow 登录
 pub async fn start_qwen_device_code_login() -> Result<QwenOAuthResult, Box<dyn Error + Send + Sync>>
 {
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("qwen")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -612,7 +686,7 @@ pub async fn start_qwen_device_code_and_get_info() -> Result<
     ),
     Box<dyn Error + Send + Sync>,
 > {
-    let client = Client::builder()
+    let client = crate::http_client::builder_for("qwen")
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
@@ -53,7 +53,7 @@ impl Default for VertexProvider {
     fn default() -> Self {
         Self {
             config: VertexConfig::default(),
-            client: Client::new(),
+            client: crate::http_client::client_for("vertex"),
         }
     }
 }
@@ -74,7 +74,7 @@ impl VertexProvider {
                 model_aliases: HashMap::new(),
                 proxy_url: None,
             },
-            client: Client::new(),
+            client: crate::http_client::client_for("vertex"),
         }
     }
 
@@ -93,7 +93,7 @@ impl VertexProvider {
                 model_aliases,
                 proxy_url: entry.proxy_url.clone(),
             },
-            client: Client::new(),
+            client: crate::http_client::client_for("vertex"),
         }
     }
 
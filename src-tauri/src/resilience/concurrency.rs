@@ -0,0 +1,531 @@
+//! 并发限制实现
+//!
+//! 按 Provider 类型（以及可选的单个凭证）限制同时进行的上游请求数，
+//! 避免触发上游的速率限制。
+//!
+//! 当并发已满时，根据配置选择：
+//! - 排队等待空闲槽位（带超时，交互式请求优先，见 [`super::priority`]）
+//! - 直接返回 429
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::priority::{self, GatePermit, PriorityGate};
+
+/// 并发限制配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConcurrencyConfig {
+    /// 每个 Provider 类型的最大并发数（key 为 Provider 类型，如 "kiro"）
+    pub per_provider: HashMap<String, usize>,
+    /// 每个凭证的最大并发数，0 表示不限制
+    pub per_credential: usize,
+    /// 并发已满时是否排队等待（false 则立即返回 429）
+    pub queue_when_saturated: bool,
+    /// 排队等待超时（毫秒）
+    pub queue_timeout_ms: u64,
+    /// 批量请求排队超过该时长（毫秒）后不再让位于交互式请求，0 表示按到达顺序排队
+    pub batch_max_wait_ms: u64,
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            per_provider: HashMap::new(),
+            per_credential: 0,
+            queue_when_saturated: true,
+            queue_timeout_ms: 30_000,
+            batch_max_wait_ms: 5_000,
+        }
+    }
+}
+
+impl From<&crate::config::ConcurrencySettings> for ConcurrencyConfig {
+    fn from(settings: &crate::config::ConcurrencySettings) -> Self {
+        Self {
+            per_provider: settings
+                .per_provider
+                .iter()
+                .map(|(provider, limit)| (provider.to_lowercase(), *limit))
+                .collect(),
+            per_credential: settings.per_credential,
+            queue_when_saturated: settings.queue_when_saturated,
+            queue_timeout_ms: settings.queue_timeout_ms,
+            batch_max_wait_ms: settings.batch_max_wait_ms,
+        }
+    }
+}
+
+impl ConcurrencyConfig {
+    /// 获取指定 Provider 的并发上限（0 或未配置表示不限制）
+    pub fn limit_for(&self, provider: &str) -> Option<usize> {
+        self.per_provider
+            .get(&provider.to_lowercase())
+            .copied()
+            .filter(|limit| *limit > 0)
+    }
+
+    /// 获取排队超时 Duration
+    pub fn queue_timeout(&self) -> Duration {
+        Duration::from_millis(self.queue_timeout_ms)
+    }
+
+    /// 获取批量请求最长让位时间 Duration
+    pub fn batch_max_wait(&self) -> Duration {
+        Duration::from_millis(self.batch_max_wait_ms)
+    }
+}
+
+/// 并发限制错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConcurrencyError {
+    /// 并发已满（快速失败模式）
+    Saturated { key: String, limit: usize },
+    /// 排队等待超时
+    QueueTimeout { key: String, timeout_ms: u64 },
+}
+
+impl std::fmt::Display for ConcurrencyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConcurrencyError::Saturated { key, limit } => {
+                write!(f, "并发请求已达上限: {} (limit={})", key, limit)
+            }
+            ConcurrencyError::QueueTimeout { key, timeout_ms } => {
+                write!(f, "等待并发槽位超时: {} ({}ms)", key, timeout_ms)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConcurrencyError {}
+
+/// 实现 IntoResponse 以便在 axum 处理器中直接返回 429 响应
+impl axum::response::IntoResponse for ConcurrencyError {
+    fn into_response(self) -> axum::response::Response {
+        use axum::http::{header, StatusCode};
+        use axum::Json;
+
+        let json_body = serde_json::json!({
+            "error": {
+                "message": self.to_string(),
+                "type": "concurrency_limit_exceeded",
+                "code": 429
+            }
+        });
+
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(json_body)).into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, header::HeaderValue::from_static("1"));
+        response
+    }
+}
+
+/// 单个 Provider 的并发快照
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InFlightSnapshot {
+    /// Provider 类型
+    pub provider: String,
+    /// 当前进行中的请求数
+    pub in_flight: usize,
+    /// 并发上限（None 表示不限制）
+    pub limit: Option<usize>,
+    /// 排队中的交互式请求数
+    pub queued_interactive: usize,
+    /// 排队中的批量请求数
+    pub queued_batch: usize,
+}
+
+/// 并发许可
+///
+/// 持有期间占用并发槽位，Drop 时自动释放并更新进行中计数
+pub struct ConcurrencyPermit {
+    _provider_permit: Option<GatePermit>,
+    _credential_permit: Option<GatePermit>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 并发限制器
+///
+/// 为每个 Provider 类型（以及可选的每个凭证）维护一个带优先级的槽位队列
+pub struct ConcurrencyLimiter {
+    config: RwLock<ConcurrencyConfig>,
+    /// 槽位队列表（key 为 Provider 类型或 `credential:<uuid>`）
+    gates: DashMap<String, Arc<PriorityGate>>,
+    /// 每个 Provider 类型的进行中请求数
+    in_flight: DashMap<String, Arc<AtomicUsize>>,
+}
+
+impl ConcurrencyLimiter {
+    /// 创建新的并发限制器
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            gates: DashMap::new(),
+            in_flight: DashMap::new(),
+        }
+    }
+
+    /// 使用默认配置创建（不限制任何 Provider）
+    pub fn with_defaults() -> Self {
+        Self::new(ConcurrencyConfig::default())
+    }
+
+    /// 获取当前配置
+    pub fn config(&self) -> ConcurrencyConfig {
+        self.config.read().clone()
+    }
+
+    /// 更新配置
+    ///
+    /// 已有队列按新上限原地调整（见 `PriorityGate::resize`），已发放的许可仍计入新上限；
+    /// 不再限制的队列放行全部排队请求后移除。
+    pub fn update_config(&self, config: ConcurrencyConfig) {
+        *self.config.write() = config.clone();
+        self.gates.retain(|key, gate| {
+            let limit = match key.strip_prefix("credential:") {
+                Some(_) => Some(config.per_credential).filter(|limit| *limit > 0),
+                None => config.limit_for(key),
+            };
+            PriorityGate::resize(gate, limit.unwrap_or(usize::MAX), config.batch_max_wait());
+            limit.is_some()
+        });
+    }
+
+    fn gate(&self, key: &str, limit: usize, config: &ConcurrencyConfig) -> Arc<PriorityGate> {
+        self.gates
+            .entry(key.to_string())
+            .or_insert_with(|| PriorityGate::new(limit, config.batch_max_wait()))
+            .clone()
+    }
+
+    async fn acquire_one(
+        &self,
+        key: &str,
+        limit: usize,
+        config: &ConcurrencyConfig,
+    ) -> Result<GatePermit, ConcurrencyError> {
+        let gate = self.gate(key, limit, config);
+
+        if !config.queue_when_saturated {
+            return PriorityGate::try_acquire(&gate).ok_or_else(|| ConcurrencyError::Saturated {
+                key: key.to_string(),
+                limit,
+            });
+        }
+
+        let start = Instant::now();
+        let permit =
+            PriorityGate::acquire(&gate, priority::current_priority(), config.queue_timeout())
+                .await;
+        priority::record_queue_wait(start.elapsed());
+        permit.ok_or_else(|| ConcurrencyError::QueueTimeout {
+            key: key.to_string(),
+            timeout_ms: config.queue_timeout_ms,
+        })
+    }
+
+    /// 获取并发许可
+    ///
+    /// # Arguments
+    /// * `provider` - Provider 类型
+    /// * `credential_id` - 凭证 ID（配置了 per_credential 时生效）
+    pub async fn acquire(
+        &self,
+        provider: &str,
+        credential_id: Option<&str>,
+    ) -> Result<ConcurrencyPermit, ConcurrencyError> {
+        let provider = provider.to_lowercase();
+        let config = self.config();
+
+        let provider_permit = match config.limit_for(&provider) {
+            Some(limit) => Some(self.acquire_one(&provider, limit, &config).await?),
+            None => None,
+        };
+
+        let credential_permit = match credential_id {
+            Some(id) if config.per_credential > 0 => Some(
+                self.acquire_one(
+                    &format!("credential:{}", id),
+                    config.per_credential,
+                    &config,
+                )
+                .await?,
+            ),
+            _ => None,
+        };
+
+        Ok(self.permit(provider, provider_permit, credential_permit))
+    }
+
+    /// 立即获取并发许可，没有空闲槽位时返回 None（不排队，不受 `queue_when_saturated` 影响）
+    ///
+    /// 用于可以放弃的后台请求（如影子测试），避免占用实际请求排队的槽位。
+    pub fn try_acquire(
+        &self,
+        provider: &str,
+        credential_id: Option<&str>,
+    ) -> Option<ConcurrencyPermit> {
+        let provider = provider.to_lowercase();
+        let config = self.config();
+
+        let provider_permit = match config.limit_for(&provider) {
+            Some(limit) => Some(PriorityGate::try_acquire(
+                &self.gate(&provider, limit, &config),
+            )?),
+            None => None,
+        };
+
+        let credential_permit = match credential_id {
+            Some(id) if config.per_credential > 0 => Some(PriorityGate::try_acquire(&self.gate(
+                &format!("credential:{}", id),
+                config.per_credential,
+                &config,
+            ))?),
+            _ => None,
+        };
+
+        Some(self.permit(provider, provider_permit, credential_permit))
+    }
+
+    fn permit(
+        &self,
+        provider: String,
+        provider_permit: Option<GatePermit>,
+        credential_permit: Option<GatePermit>,
+    ) -> ConcurrencyPermit {
+        let in_flight = self
+            .in_flight
+            .entry(provider)
+            .or_insert_with(|| Arc::new(AtomicUsize::new(0)))
+            .clone();
+        in_flight.fetch_add(1, Ordering::SeqCst);
+
+        ConcurrencyPermit {
+            _provider_permit: provider_permit,
+            _credential_permit: credential_permit,
+            in_flight,
+        }
+    }
+
+    /// 获取指定 Provider 当前进行中的请求数
+    pub fn in_flight(&self, provider: &str) -> usize {
+        self.in_flight
+            .get(&provider.to_lowercase())
+            .map(|count| count.load(Ordering::SeqCst))
+            .unwrap_or(0)
+    }
+
+    /// 获取所有 Provider 的并发快照
+    pub fn snapshot(&self) -> Vec<InFlightSnapshot> {
+        let config = self.config();
+        let mut snapshots: Vec<InFlightSnapshot> = self
+            .in_flight
+            .iter()
+            .map(|entry| {
+                let (queued_interactive, queued_batch) = self
+                    .gates
+                    .get(entry.key())
+                    .map(|gate| gate.queued())
+                    .unwrap_or_default();
+                InFlightSnapshot {
+                    provider: entry.key().clone(),
+                    in_flight: entry.value().load(Ordering::SeqCst),
+                    limit: config.limit_for(entry.key()),
+                    queued_interactive,
+                    queued_batch,
+                }
+            })
+            .collect();
+        snapshots.sort_by(|a, b| a.provider.cmp(&b.provider));
+        snapshots
+    }
+}
+
+impl Default for ConcurrencyLimiter {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_limit(provider: &str, limit: usize, queue: bool) -> ConcurrencyConfig {
+        let mut per_provider = HashMap::new();
+        per_provider.insert(provider.to_string(), limit);
+        ConcurrencyConfig {
+            per_provider,
+            per_credential: 0,
+            queue_when_saturated: queue,
+            queue_timeout_ms: 50,
+            batch_max_wait_ms: 5_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_provider_tracks_in_flight() {
+        let limiter = ConcurrencyLimiter::with_defaults();
+        let p1 = limiter.acquire("kiro", None).await.unwrap();
+        let p2 = limiter.acquire("KIRO", None).await.unwrap();
+        assert_eq!(limiter.in_flight("kiro"), 2);
+
+        drop(p1);
+        drop(p2);
+        assert_eq!(limiter.in_flight("kiro"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_fast_fail_when_saturated() {
+        let limiter = ConcurrencyLimiter::new(config_with_limit("kiro", 1, false));
+        let _permit = limiter.acquire("kiro", None).await.unwrap();
+
+        let err = limiter.acquire("kiro", None).await.err().unwrap();
+        assert!(matches!(err, ConcurrencyError::Saturated { limit: 1, .. }));
+
+        // 其他 Provider 不受影响
+        assert!(limiter.acquire("gemini", None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_try_acquire_never_queues() {
+        // 排队模式下也立即返回
+        let limiter = ConcurrencyLimiter::new(config_with_limit("kiro", 1, true));
+        let permit = limiter.try_acquire("kiro", None).unwrap();
+        assert_eq!(limiter.in_flight("kiro"), 1);
+        assert!(limiter.try_acquire("kiro", None).is_none());
+        assert_eq!(limiter.snapshot()[0].queued_batch, 0);
+
+        drop(permit);
+        assert!(limiter.try_acquire("kiro", None).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_queue_times_out() {
+        let limiter = ConcurrencyLimiter::new(config_with_limit("kiro", 1, true));
+        let _permit = limiter.acquire("kiro", None).await.unwrap();
+
+        let err = limiter.acquire("kiro", None).await.err().unwrap();
+        assert!(matches!(
+            err,
+            ConcurrencyError::QueueTimeout { timeout_ms: 50, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_queue_acquires_after_release() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(config_with_limit("kiro", 1, true)));
+        let permit = limiter.acquire("kiro", None).await.unwrap();
+
+        let limiter_clone = limiter.clone();
+        let waiter = tokio::spawn(async move { limiter_clone.acquire("kiro", None).await });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(permit);
+
+        assert!(waiter.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_update_config_shrinks_in_place() {
+        let limiter = ConcurrencyLimiter::new(config_with_limit("kiro", 2, false));
+        let first = limiter.acquire("kiro", None).await.unwrap();
+        let second = limiter.acquire("kiro", None).await.unwrap();
+
+        // 已发放的许可计入新上限，释放到上限以下之前不放行新请求
+        limiter.update_config(config_with_limit("kiro", 1, false));
+        assert!(limiter.acquire("kiro", None).await.is_err());
+        drop(first);
+        assert!(limiter.acquire("kiro", None).await.is_err());
+        drop(second);
+        let _third = limiter.acquire("kiro", None).await.unwrap();
+        assert!(limiter.acquire("kiro", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_config_grows_and_wakes_waiters() {
+        let queued = |limit| ConcurrencyConfig {
+            queue_timeout_ms: 5_000,
+            ..config_with_limit("kiro", limit, true)
+        };
+        let limiter = Arc::new(ConcurrencyLimiter::new(queued(1)));
+        let _held = limiter.acquire("kiro", None).await.unwrap();
+
+        let limiter_clone = limiter.clone();
+        let waiter = tokio::spawn(async move { limiter_clone.acquire("kiro", None).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        limiter.update_config(queued(2));
+        let acquired = tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("扩大上限后排队请求应立即获得槽位");
+        let _second = acquired.unwrap().unwrap();
+        assert_eq!(limiter.in_flight("kiro"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_per_credential_limit() {
+        let limiter = ConcurrencyLimiter::new(ConcurrencyConfig {
+            per_credential: 1,
+            queue_when_saturated: false,
+            ..ConcurrencyConfig::default()
+        });
+        let _permit = limiter.acquire("kiro", Some("cred-a")).await.unwrap();
+
+        assert!(limiter.acquire("kiro", Some("cred-a")).await.is_err());
+        assert!(limiter.acquire("kiro", Some("cred-b")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_reports_limits() {
+        let limiter = ConcurrencyLimiter::new(config_with_limit("kiro", 4, true));
+        let _permit = limiter.acquire("kiro", None).await.unwrap();
+
+        let snapshot = limiter.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].provider, "kiro");
+        assert_eq!(snapshot[0].in_flight, 1);
+        assert_eq!(snapshot[0].limit, Some(4));
+        assert_eq!(snapshot[0].queued_interactive, 0);
+    }
+
+    #[tokio::test]
+    async fn test_queued_requests_reported_by_priority() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(ConcurrencyConfig {
+            queue_timeout_ms: 5_000,
+            ..config_with_limit("kiro", 1, true)
+        }));
+        let permit = limiter.acquire("kiro", None).await.unwrap();
+
+        let limiter_clone = limiter.clone();
+        let waiter = tokio::spawn(priority::with_priority(
+            priority::RequestPriority::Batch,
+            async move {
+                let result = limiter_clone.acquire("kiro", None).await;
+                (result.is_ok(), priority::queue_wait_ms())
+            },
+        ));
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let snapshot = limiter.snapshot();
+        assert_eq!(snapshot[0].queued_batch, 1);
+        assert_eq!(snapshot[0].queued_interactive, 0);
+
+        drop(permit);
+        let (acquired, wait_ms) = waiter.await.unwrap();
+        assert!(acquired);
+        assert!(wait_ms.is_some_and(|ms| ms >= 10));
+    }
+}
@@ -1,16 +1,26 @@
 //! 容错机制模块
 //!
-//! 提供重试、故障转移和超时控制功能
+//! 提供重试、故障转移、超时控制、并发限制和上游限流感知功能
 
+mod concurrency;
 mod failover;
+pub mod priority;
+mod rate_limit;
 mod retry;
+mod switch_log;
 mod timeout;
 
+pub use concurrency::{
+    ConcurrencyConfig, ConcurrencyError, ConcurrencyLimiter, ConcurrencyPermit, InFlightSnapshot,
+};
 pub use failover::{
     Failover, FailoverConfig, FailoverManager, FailoverResult, FailureType, SwitchEvent,
     QUOTA_EXCEEDED_KEYWORDS, QUOTA_EXCEEDED_STATUS_CODES,
 };
+pub use priority::RequestPriority;
+pub use rate_limit::{CredentialRateLimit, RateLimitInfo, RateLimitTracker, RateLimitWindow};
 pub use retry::{Retrier, RetryConfig, RetryError};
+pub use switch_log::{SwitchLog, SwitchLogEntry, SwitchLogPage, SwitchLogQuery, SwitchTrigger};
 pub use timeout::{
     CancellationToken, StreamIdleDetector, StreamWithIdleTimeout, TimeoutConfig, TimeoutController,
     TimeoutError,
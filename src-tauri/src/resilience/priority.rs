@@ -0,0 +1,396 @@
+//! 请求优先级队列
+//!
+//! 并发已满时，交互式请求（IDE / CLI 客户端）优先于批量请求（脚本、回放、影子测试）获得槽位。
+//! 批量请求排队超过 `batch_max_wait` 后按到达顺序与交互式请求竞争，避免被饿死。
+//!
+//! 请求优先级通过 [`with_priority`] 在请求处理范围内设置（见 `RequestPriorityLayer`），
+//! 未设置时视为交互式请求。
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// 请求优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    /// 交互式请求（优先获得并发槽位）
+    #[default]
+    Interactive,
+    /// 批量请求
+    Batch,
+}
+
+impl RequestPriority {
+    /// 解析 `x-priority` 请求头，无法识别时返回 None
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "interactive" | "high" => Some(Self::Interactive),
+            "batch" | "low" | "background" => Some(Self::Batch),
+            _ => None,
+        }
+    }
+}
+
+struct QueueScope {
+    priority: RequestPriority,
+    /// 本次请求累计的排队时间（毫秒）
+    wait_ms: Cell<Option<u64>>,
+}
+
+tokio::task_local! {
+    static QUEUE_SCOPE: QueueScope;
+}
+
+/// 在指定优先级下执行请求
+pub async fn with_priority<F: Future>(priority: RequestPriority, fut: F) -> F::Output {
+    QUEUE_SCOPE
+        .scope(
+            QueueScope {
+                priority,
+                wait_ms: Cell::new(None),
+            },
+            fut,
+        )
+        .await
+}
+
+/// 当前请求的优先级
+pub fn current_priority() -> RequestPriority {
+    QUEUE_SCOPE
+        .try_with(|scope| scope.priority)
+        .unwrap_or_default()
+}
+
+/// 当前请求累计的排队时间（毫秒），未经过并发限制时返回 None
+pub fn queue_wait_ms() -> Option<u64> {
+    QUEUE_SCOPE
+        .try_with(|scope| scope.wait_ms.get())
+        .ok()
+        .flatten()
+}
+
+/// 不计排队时间的上游调用计时器
+///
+/// 扣除计时期间等待并发槽位的时间，相当于在获得许可后才开始计时，
+/// 避免凭证延迟随本地负载上升。
+pub struct UpstreamTimer {
+    start: Instant,
+    queued_before_ms: u64,
+}
+
+impl UpstreamTimer {
+    /// 开始计时
+    pub fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            queued_before_ms: queue_wait_ms().unwrap_or(0),
+        }
+    }
+
+    /// 扣除排队时间后的耗时（毫秒）
+    pub fn elapsed_ms(&self) -> u64 {
+        let queued_ms = queue_wait_ms()
+            .unwrap_or(0)
+            .saturating_sub(self.queued_before_ms);
+        (self.start.elapsed().as_millis() as u64).saturating_sub(queued_ms)
+    }
+}
+
+pub(super) fn record_queue_wait(wait: Duration) {
+    let _ = QUEUE_SCOPE.try_with(|scope| {
+        let total = scope.wait_ms.get().unwrap_or(0) + wait.as_millis() as u64;
+        scope.wait_ms.set(Some(total));
+    });
+}
+
+struct Waiter {
+    enqueued_at: Instant,
+    tx: oneshot::Sender<GatePermit>,
+}
+
+struct GateState {
+    limit: usize,
+    /// 已发放的许可数（缩小上限后可能暂时超过上限）
+    in_use: usize,
+    batch_max_wait: Duration,
+    interactive: VecDeque<Waiter>,
+    batch: VecDeque<Waiter>,
+}
+
+impl GateState {
+    /// 选择下一个获得槽位的等待者
+    fn next_waiter(&mut self) -> Option<Waiter> {
+        let batch_max_wait = self.batch_max_wait;
+        let batch_starved = self
+            .batch
+            .front()
+            .is_some_and(|w| w.enqueued_at.elapsed() >= batch_max_wait);
+        if batch_starved {
+            let batch_first = self
+                .interactive
+                .front()
+                .is_none_or(|i| self.batch[0].enqueued_at <= i.enqueued_at);
+            if batch_first {
+                return self.batch.pop_front();
+            }
+        }
+        self.interactive
+            .pop_front()
+            .or_else(|| self.batch.pop_front())
+    }
+
+    /// 把一个槽位交给下一个仍在等待的请求，没有等待者时返回 false
+    fn hand_off(&mut self, gate: &Arc<PriorityGate>) -> bool {
+        while let Some(waiter) = self.next_waiter() {
+            match waiter.tx.send(GatePermit::new(gate)) {
+                Ok(()) => return true,
+                // 等待者已离开，收回许可（持锁时不能经由 Drop 重入 release）
+                Err(permit) => permit.reclaim(),
+            }
+        }
+        false
+    }
+}
+
+/// 带优先级的并发槽位
+///
+/// 释放槽位时直接交给下一个等待者，而不是放回空闲计数，保证排队顺序。
+pub(super) struct PriorityGate {
+    state: Mutex<GateState>,
+}
+
+/// 槽位许可，Drop 时释放
+pub(super) struct GatePermit {
+    /// 被收回的许可为 None，Drop 时不再释放槽位
+    gate: Option<Arc<PriorityGate>>,
+}
+
+impl GatePermit {
+    fn new(gate: &Arc<PriorityGate>) -> Self {
+        Self {
+            gate: Some(gate.clone()),
+        }
+    }
+
+    /// 收回未送达的许可，槽位仍由调用方继续转交
+    fn reclaim(mut self) {
+        self.gate = None;
+    }
+}
+
+impl Drop for GatePermit {
+    fn drop(&mut self) {
+        if let Some(gate) = self.gate.take() {
+            PriorityGate::release(&gate);
+        }
+    }
+}
+
+impl PriorityGate {
+    pub(super) fn new(limit: usize, batch_max_wait: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(GateState {
+                limit,
+                in_use: 0,
+                batch_max_wait,
+                interactive: VecDeque::new(),
+                batch: VecDeque::new(),
+            }),
+        })
+    }
+
+    /// 原地调整槽位上限，已发放的许可不受影响
+    ///
+    /// 扩大时立即把新增槽位交给排队中的请求；缩小时超出新上限的许可在释放时回收，
+    /// 直到进行中的请求数回到上限以下才放行新请求。
+    pub(super) fn resize(gate: &Arc<Self>, limit: usize, batch_max_wait: Duration) {
+        let mut state = gate.state.lock();
+        state.limit = limit;
+        state.batch_max_wait = batch_max_wait;
+        while state.in_use < state.limit && state.hand_off(gate) {
+            state.in_use += 1;
+        }
+    }
+
+    /// 立即获取槽位，没有空闲槽位时返回 None
+    pub(super) fn try_acquire(gate: &Arc<Self>) -> Option<GatePermit> {
+        let mut state = gate.state.lock();
+        if state.in_use >= state.limit {
+            return None;
+        }
+        state.in_use += 1;
+        Some(GatePermit::new(gate))
+    }
+
+    /// 排队获取槽位，超时返回 None
+    pub(super) async fn acquire(
+        gate: &Arc<Self>,
+        priority: RequestPriority,
+        timeout: Duration,
+    ) -> Option<GatePermit> {
+        let mut rx = {
+            let mut state = gate.state.lock();
+            if state.in_use < state.limit {
+                state.in_use += 1;
+                return Some(GatePermit::new(gate));
+            }
+            let (tx, rx) = oneshot::channel();
+            let queue = match priority {
+                RequestPriority::Interactive => &mut state.interactive,
+                RequestPriority::Batch => &mut state.batch,
+            };
+            // 顺便清理已超时或被取消的等待者
+            queue.retain(|w| !w.tx.is_closed());
+            queue.push_back(Waiter {
+                enqueued_at: Instant::now(),
+                tx,
+            });
+            rx
+        };
+
+        match tokio::time::timeout(timeout, &mut rx).await {
+            Ok(Ok(permit)) => Some(permit),
+            Ok(Err(_)) => None,
+            Err(_) => {
+                // 关闭后仍可能收到超时前刚分配的许可，丢弃即归还
+                rx.close();
+                drop(rx.try_recv());
+                None
+            }
+        }
+    }
+
+    /// 排队中的（交互式, 批量）请求数
+    pub(super) fn queued(&self) -> (usize, usize) {
+        let state = self.state.lock();
+        let count = |queue: &VecDeque<Waiter>| queue.iter().filter(|w| !w.tx.is_closed()).count();
+        (count(&state.interactive), count(&state.batch))
+    }
+
+    fn release(gate: &Arc<Self>) {
+        let mut state = gate.state.lock();
+        // 缩小上限后超出的许可直接回收，不再转交
+        if state.in_use > state.limit || !state.hand_off(gate) {
+            state.in_use -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_priority() {
+        assert_eq!(
+            RequestPriority::parse(" Batch "),
+            Some(RequestPriority::Batch)
+        );
+        assert_eq!(
+            RequestPriority::parse("high"),
+            Some(RequestPriority::Interactive)
+        );
+        assert_eq!(RequestPriority::parse("urgent"), None);
+    }
+
+    /// 占满槽位后依次加入批量、交互式等待者，返回按获得槽位顺序排列的优先级
+    async fn grant_order(batch_max_wait: Duration) -> Vec<RequestPriority> {
+        let gate = PriorityGate::new(1, batch_max_wait);
+        let held = PriorityGate::try_acquire(&gate).unwrap();
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let mut waiters = Vec::new();
+        for priority in [RequestPriority::Batch, RequestPriority::Interactive] {
+            let gate = gate.clone();
+            let order_tx = order_tx.clone();
+            waiters.push(tokio::spawn(async move {
+                let permit = PriorityGate::acquire(&gate, priority, Duration::from_secs(5))
+                    .await
+                    .unwrap();
+                order_tx.send(priority).unwrap();
+                drop(permit);
+            }));
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(gate.queued(), (1, 1));
+
+        drop(held);
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        drop(order_tx);
+        let mut order = Vec::new();
+        while let Some(priority) = order_rx.recv().await {
+            order.push(priority);
+        }
+        order
+    }
+
+    #[tokio::test]
+    async fn test_interactive_jumps_ahead_of_batch() {
+        let order = grant_order(Duration::from_secs(60)).await;
+        assert_eq!(
+            order,
+            vec![RequestPriority::Interactive, RequestPriority::Batch]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_starved_batch_keeps_arrival_order() {
+        let order = grant_order(Duration::ZERO).await;
+        assert_eq!(
+            order,
+            vec![RequestPriority::Batch, RequestPriority::Interactive]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_waiter_does_not_leak_slot() {
+        let gate = PriorityGate::new(1, Duration::from_secs(60));
+        let held = PriorityGate::try_acquire(&gate).unwrap();
+
+        let timed_out =
+            PriorityGate::acquire(&gate, RequestPriority::Batch, Duration::from_millis(20)).await;
+        assert!(timed_out.is_none());
+        assert_eq!(gate.queued(), (0, 0));
+
+        drop(held);
+        // 转交给已离开的等待者失败后收回许可，不残留对 gate 的引用
+        assert_eq!(Arc::strong_count(&gate), 1);
+        assert!(PriorityGate::try_acquire(&gate).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_queue_wait_recorded_in_scope() {
+        assert_eq!(current_priority(), RequestPriority::Interactive);
+        let (priority, wait) = with_priority(RequestPriority::Batch, async {
+            record_queue_wait(Duration::from_millis(30));
+            record_queue_wait(Duration::from_millis(12));
+            (current_priority(), queue_wait_ms())
+        })
+        .await;
+        assert_eq!(priority, RequestPriority::Batch);
+        assert_eq!(wait, Some(42));
+        assert_eq!(queue_wait_ms(), None);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_timer_excludes_queue_wait() {
+        let elapsed_ms = with_priority(RequestPriority::Interactive, async {
+            // 计时前的排队时间不扣除
+            record_queue_wait(Duration::from_millis(500));
+            let timer = UpstreamTimer::start();
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            record_queue_wait(Duration::from_millis(40));
+            timer.elapsed_ms()
+        })
+        .await;
+        assert!((20..60).contains(&elapsed_ms), "elapsed_ms={}", elapsed_ms);
+    }
+}
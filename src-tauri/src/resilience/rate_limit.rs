@@ -0,0 +1,453 @@
+//! 上游限流信息
+//!
+//! 解析上游响应中的限流头，用于代替固定退避：
+//! - `retry-after`（秒数或 HTTP 日期）、`retry-after-ms`
+//! - OpenAI：`x-ratelimit-{limit,remaining,reset}-{requests,tokens}`，重置时间为 `6m0s` 形式的时长
+//! - Anthropic：`anthropic-ratelimit-{requests,tokens}-{limit,remaining,reset}`，重置时间为 RFC 3339 时间
+//!
+//! [`RateLimitTracker`] 按凭证记录最近一次观察到的限额。上游要求等待或剩余额度接近用尽时，
+//! 凭证在重置前被降低优先级，选择凭证时优先使用其他凭证。
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+
+/// 剩余额度低于上限的该比例时视为接近用尽
+const NEAR_LIMIT_RATIO: f64 = 0.05;
+
+/// 接近用尽但上游没有给出重置时间时的降级时长
+const NEAR_LIMIT_DEFAULT_HOLD: Duration = Duration::from_secs(10);
+
+/// 上游给出的等待或重置时长的上限，超出时按上限处理
+const MAX_RATE_LIMIT_DELAY: Duration = Duration::from_secs(24 * 3600);
+
+/// 单个限额窗口（请求数或 Token 数）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RateLimitWindow {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// 距离额度重置的毫秒数
+    pub reset_after_ms: Option<u64>,
+}
+
+impl RateLimitWindow {
+    fn is_empty(&self) -> bool {
+        self.limit.is_none() && self.remaining.is_none() && self.reset_after_ms.is_none()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    fn is_near_limit(&self) -> bool {
+        match (self.limit, self.remaining) {
+            (Some(limit), Some(remaining)) if limit > 0 => {
+                (remaining as f64) <= (limit as f64) * NEAR_LIMIT_RATIO
+            }
+            _ => false,
+        }
+    }
+
+    fn reset_after(&self) -> Option<Duration> {
+        self.reset_after_ms.map(Duration::from_millis)
+    }
+}
+
+/// 一次上游响应中的限流信息
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RateLimitInfo {
+    /// 上游要求的等待毫秒数（`retry-after` / `retry-after-ms`）
+    pub retry_after_ms: Option<u64>,
+    pub requests: RateLimitWindow,
+    pub tokens: RateLimitWindow,
+}
+
+impl RateLimitInfo {
+    /// 从上游响应头解析，没有任何限流头时返回 None
+    pub fn from_headers(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Self> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let number = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| header(name).and_then(|v| v.parse::<u64>().ok()))
+        };
+        let reset = |openai: &str, anthropic: &str| {
+            header(openai)
+                .and_then(parse_reset_duration)
+                .or_else(|| header(anthropic).and_then(|v| parse_reset_time(v, now)))
+                .map(|d| d.as_millis() as u64)
+        };
+
+        let retry_after_ms = header("retry-after-ms")
+            .and_then(|v| v.parse::<f64>().ok())
+            .and_then(|ms| capped_secs(ms.ceil() / 1000.0))
+            .or_else(|| header("retry-after").and_then(|v| parse_retry_after(v, now)))
+            .map(|d| d.as_millis() as u64);
+
+        let info = Self {
+            retry_after_ms,
+            requests: RateLimitWindow {
+                limit: number(&[
+                    "x-ratelimit-limit-requests",
+                    "anthropic-ratelimit-requests-limit",
+                ]),
+                remaining: number(&[
+                    "x-ratelimit-remaining-requests",
+                    "anthropic-ratelimit-requests-remaining",
+                ]),
+                reset_after_ms: reset(
+                    "x-ratelimit-reset-requests",
+                    "anthropic-ratelimit-requests-reset",
+                ),
+            },
+            tokens: RateLimitWindow {
+                limit: number(&[
+                    "x-ratelimit-limit-tokens",
+                    "anthropic-ratelimit-tokens-limit",
+                ]),
+                remaining: number(&[
+                    "x-ratelimit-remaining-tokens",
+                    "anthropic-ratelimit-tokens-remaining",
+                ]),
+                reset_after_ms: reset(
+                    "x-ratelimit-reset-tokens",
+                    "anthropic-ratelimit-tokens-reset",
+                ),
+            },
+        };
+        let empty =
+            info.retry_after_ms.is_none() && info.requests.is_empty() && info.tokens.is_empty();
+        (!empty).then_some(info)
+    }
+
+    /// 上游建议的重试等待时间：优先 `retry-after`，其次是已用尽额度的重置时间
+    pub fn suggested_delay(&self) -> Option<Duration> {
+        self.retry_after_ms.map(Duration::from_millis).or_else(|| {
+            [&self.requests, &self.tokens]
+                .into_iter()
+                .filter(|w| w.is_exhausted())
+                .filter_map(RateLimitWindow::reset_after)
+                .max()
+        })
+    }
+
+    /// 是否有额度接近用尽
+    pub fn is_near_limit(&self) -> bool {
+        self.requests.is_near_limit() || self.tokens.is_near_limit()
+    }
+
+    /// 凭证应被降低优先级的时长，不需要时返回 None
+    fn hold_duration(&self) -> Option<Duration> {
+        if let Some(delay) = self.suggested_delay() {
+            return Some(delay);
+        }
+        if !self.is_near_limit() {
+            return None;
+        }
+        let reset = [&self.requests, &self.tokens]
+            .into_iter()
+            .filter(|w| w.is_near_limit())
+            .filter_map(RateLimitWindow::reset_after)
+            .max();
+        Some(reset.unwrap_or(NEAR_LIMIT_DEFAULT_HOLD))
+    }
+}
+
+/// 秒数换算为时长，超出 [`MAX_RATE_LIMIT_DELAY`] 时取上限；负数或非有限值返回 None
+fn capped_secs(secs: f64) -> Option<Duration> {
+    if !secs.is_finite() || secs < 0.0 {
+        return None;
+    }
+    Some(
+        Duration::try_from_secs_f64(secs)
+            .map_or(MAX_RATE_LIMIT_DELAY, |d| d.min(MAX_RATE_LIMIT_DELAY)),
+    )
+}
+
+/// 距离指定时间的时长，已过去时为 0，超出上限时取上限
+fn capped_until(at: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+    (at - now)
+        .to_std()
+        .unwrap_or_default()
+        .min(MAX_RATE_LIMIT_DELAY)
+}
+
+/// `retry-after`：秒数或 HTTP 日期
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return capped_secs(secs);
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(capped_until(at.with_timezone(&Utc), now))
+}
+
+/// OpenAI 的重置时长，如 `1s`、`6m0s`、`20ms`、`1h2m3.5s`
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let amount: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += amount * seconds;
+        rest = &rest[unit_len..];
+    }
+    capped_secs(total)
+}
+
+/// Anthropic 的重置时间（RFC 3339），换算为距离现在的时长
+fn parse_reset_time(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let at = DateTime::parse_from_rfc3339(value).ok()?;
+    Some(capped_until(at.with_timezone(&Utc), now))
+}
+
+/// 凭证最近一次观察到的限流状态
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialRateLimit {
+    pub info: RateLimitInfo,
+    pub observed_at: DateTime<Utc>,
+    /// 在此之前凭证被降低优先级
+    pub deprioritized_until: Option<DateTime<Utc>>,
+}
+
+impl CredentialRateLimit {
+    fn is_deprioritized(&self, now: DateTime<Utc>) -> bool {
+        self.deprioritized_until.is_some_and(|until| until > now)
+    }
+}
+
+/// 按凭证记录的上游限流状态
+#[derive(Debug, Default)]
+pub struct RateLimitTracker {
+    entries: RwLock<HashMap<String, CredentialRateLimit>>,
+}
+
+impl RateLimitTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次上游响应中的限流信息，返回凭证被降低优先级的时长
+    pub fn record(&self, uuid: &str, info: RateLimitInfo, now: DateTime<Utc>) -> Option<Duration> {
+        let hold = info.hold_duration();
+        let deprioritized_until =
+            hold.and_then(|d| chrono::Duration::from_std(d).ok().map(|d| now + d));
+        self.entries.write().insert(
+            uuid.to_string(),
+            CredentialRateLimit {
+                info,
+                observed_at: now,
+                deprioritized_until,
+            },
+        );
+        hold
+    }
+
+    /// 凭证当前是否被降低优先级
+    pub fn is_deprioritized(&self, uuid: &str, now: DateTime<Utc>) -> bool {
+        self.entries
+            .read()
+            .get(uuid)
+            .is_some_and(|entry| entry.is_deprioritized(now))
+    }
+
+    /// 过滤掉被降低优先级的凭证；全部被降级时原样返回，由上游决定是否仍然限流
+    pub fn prefer_unthrottled<T>(
+        &self,
+        candidates: Vec<T>,
+        uuid_of: impl Fn(&T) -> &str,
+        now: DateTime<Utc>,
+    ) -> Vec<T> {
+        let entries = self.entries.read();
+        if entries.is_empty() {
+            return candidates;
+        }
+        let throttled = |c: &T| {
+            entries
+                .get(uuid_of(c))
+                .is_some_and(|entry| entry.is_deprioritized(now))
+        };
+        if candidates.iter().all(throttled) {
+            return candidates;
+        }
+        candidates.into_iter().filter(|c| !throttled(c)).collect()
+    }
+
+    /// 凭证最近一次观察到的限流状态
+    pub fn get(&self, uuid: &str) -> Option<CredentialRateLimit> {
+        self.entries.read().get(uuid).cloned()
+    }
+
+    /// 所有凭证的限流状态
+    pub fn snapshot(&self) -> HashMap<String, CredentialRateLimit> {
+        self.entries.read().clone()
+    }
+
+    /// 删除凭证的记录（凭证被删除时）
+    pub fn remove(&self, uuid: &str) {
+        self.entries.write().remove(uuid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-03-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let info = RateLimitInfo::from_headers(&headers(&[("retry-after", "7")]), now()).unwrap();
+        assert_eq!(info.suggested_delay(), Some(Duration::from_secs(7)));
+
+        let info = RateLimitInfo::from_headers(
+            &headers(&[("retry-after", "Sat, 01 Mar 2025 12:00:30 GMT")]),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(info.suggested_delay(), Some(Duration::from_secs(30)));
+
+        // retry-after-ms 更精确，优先使用
+        let info = RateLimitInfo::from_headers(
+            &headers(&[("retry-after", "1"), ("retry-after-ms", "1500")]),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(info.suggested_delay(), Some(Duration::from_millis(1500)));
+
+        assert!(RateLimitInfo::from_headers(&headers(&[("x-request-id", "abc")]), now()).is_none());
+    }
+
+    #[test]
+    fn test_huge_delays_are_capped() {
+        for (name, value) in [
+            ("retry-after", "1e30"),
+            ("retry-after-ms", "1e300"),
+            ("retry-after", "Thu, 01 Mar 2125 12:00:00 GMT"),
+            ("x-ratelimit-reset-requests", "99999999999999999999h"),
+            ("anthropic-ratelimit-requests-reset", "2999-01-01T00:00:00Z"),
+        ] {
+            let info = RateLimitInfo::from_headers(
+                &headers(&[(name, value), ("x-ratelimit-remaining-requests", "0")]),
+                now(),
+            )
+            .unwrap();
+            assert_eq!(
+                info.suggested_delay(),
+                Some(MAX_RATE_LIMIT_DELAY),
+                "{name}: {value}"
+            );
+        }
+
+        let info = RateLimitInfo::from_headers(&headers(&[("retry-after", "-5")]), now());
+        assert!(info.is_none());
+    }
+
+    #[test]
+    fn test_parse_openai_and_anthropic_limits() {
+        let openai = RateLimitInfo::from_headers(
+            &headers(&[
+                ("x-ratelimit-limit-requests", "500"),
+                ("x-ratelimit-remaining-requests", "0"),
+                ("x-ratelimit-reset-requests", "1m30.5s"),
+                ("x-ratelimit-limit-tokens", "30000"),
+                ("x-ratelimit-remaining-tokens", "29000"),
+                ("x-ratelimit-reset-tokens", "20ms"),
+            ]),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(openai.requests.reset_after_ms, Some(90_500));
+        assert_eq!(openai.tokens.reset_after_ms, Some(20));
+        assert_eq!(
+            openai.suggested_delay(),
+            Some(Duration::from_millis(90_500))
+        );
+
+        let anthropic = RateLimitInfo::from_headers(
+            &headers(&[
+                ("anthropic-ratelimit-requests-limit", "1000"),
+                ("anthropic-ratelimit-requests-remaining", "20"),
+                ("anthropic-ratelimit-requests-reset", "2025-03-01T12:00:45Z"),
+            ]),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(anthropic.requests.reset_after_ms, Some(45_000));
+        assert_eq!(anthropic.suggested_delay(), None);
+        assert!(anthropic.is_near_limit());
+
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(parse_reset_duration("3x"), None);
+    }
+
+    #[test]
+    fn test_tracker_deprioritizes_until_reset() {
+        let tracker = RateLimitTracker::new();
+        let info = RateLimitInfo::from_headers(&headers(&[("retry-after", "30")]), now()).unwrap();
+        assert_eq!(
+            tracker.record("a", info, now()),
+            Some(Duration::from_secs(30))
+        );
+        let healthy = RateLimitInfo::from_headers(
+            &headers(&[
+                ("x-ratelimit-limit-requests", "100"),
+                ("x-ratelimit-remaining-requests", "90"),
+            ]),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(tracker.record("b", healthy, now()), None);
+
+        assert!(tracker.is_deprioritized("a", now()));
+        assert!(!tracker.is_deprioritized("a", now() + chrono::Duration::seconds(31)));
+
+        fn uuid<'a>(s: &'a &str) -> &'a str {
+            s
+        }
+        assert_eq!(
+            tracker.prefer_unthrottled(vec!["a", "b", "c"], uuid, now()),
+            vec!["b", "c"]
+        );
+        // 全部被降级时不过滤
+        assert_eq!(
+            tracker.prefer_unthrottled(vec!["a"], uuid, now()),
+            vec!["a"]
+        );
+    }
+}
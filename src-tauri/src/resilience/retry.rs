@@ -1,13 +1,21 @@
 //! 重试机制实现
 //!
 //! 提供带指数退避和抖动的重试逻辑
+//!
+//! 只在确定安全的情况下重试，避免非幂等请求（流式输出、工具调用）产生重复副作用：
+//! - 未收到任何响应字节的连接错误（`retry_on_connection_error`，默认开启）
+//! - 429 以及 500 / 502 / 503 / 504（`retryable_status_codes`）
+//! - 流式请求的 5xx 默认不重试（`retry_streaming_server_errors`）
+//! - 流式输出开始后任何错误都不重试
+//!
+//! 408（请求超时）默认不重试：上游可能已经执行了请求。
 
 use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::time::Duration;
 
 /// 可重试的 HTTP 状态码
-pub const RETRYABLE_STATUS_CODES: &[u16] = &[408, 429, 500, 502, 503, 504];
+pub const RETRYABLE_STATUS_CODES: &[u16] = &[429, 500, 502, 503, 504];
 
 /// 重试配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -19,21 +27,33 @@ pub struct RetryConfig {
     /// 最大延迟（毫秒）
     pub max_delay_ms: u64,
     /// 可重试的状态码
-    #[serde(default = "default_retryable_codes")]
-    pub retryable_codes: Vec<u16>,
+    #[serde(default = "default_retryable_status_codes", alias = "retryable_codes")]
+    pub retryable_status_codes: Vec<u16>,
+    /// 未收到任何响应字节的连接错误是否重试
+    #[serde(default = "default_retry_on_connection_error")]
+    pub retry_on_connection_error: bool,
+    /// 流式请求在输出开始前收到 5xx 时是否重试
+    #[serde(default)]
+    pub retry_streaming_server_errors: bool,
 }
 
-fn default_retryable_codes() -> Vec<u16> {
+fn default_retryable_status_codes() -> Vec<u16> {
     RETRYABLE_STATUS_CODES.to_vec()
 }
 
+fn default_retry_on_connection_error() -> bool {
+    true
+}
+
 impl Default for RetryConfig {
     fn default() -> Self {
         Self {
             max_retries: 3,
             base_delay_ms: 1000,
             max_delay_ms: 30000,
-            retryable_codes: default_retryable_codes(),
+            retryable_status_codes: default_retryable_status_codes(),
+            retry_on_connection_error: default_retry_on_connection_error(),
+            retry_streaming_server_errors: false,
         }
     }
 }
@@ -45,13 +65,52 @@ impl RetryConfig {
             max_retries,
             base_delay_ms,
             max_delay_ms,
-            retryable_codes: default_retryable_codes(),
+            retryable_status_codes: default_retryable_status_codes(),
+            retry_on_connection_error: default_retry_on_connection_error(),
+            retry_streaming_server_errors: false,
         }
     }
 
     /// 检查状态码是否可重试
     pub fn is_retryable(&self, status_code: u16) -> bool {
-        self.retryable_codes.contains(&status_code)
+        self.retryable_status_codes.contains(&status_code)
+    }
+
+    /// 按重试策略判断一次失败是否可以重试
+    ///
+    /// # Arguments
+    /// * `status_code` - 上游状态码，None 表示未收到响应的连接错误
+    /// * `is_stream` - 是否为流式请求
+    /// * `output_started` - 是否已经向客户端输出内容
+    pub fn should_retry(
+        &self,
+        status_code: Option<u16>,
+        is_stream: bool,
+        output_started: bool,
+    ) -> bool {
+        if output_started {
+            return false;
+        }
+        match status_code {
+            None => self.retry_on_connection_error,
+            Some(code) if is_stream && (500..600).contains(&code) => {
+                self.retry_streaming_server_errors && self.is_retryable(code)
+            }
+            Some(code) => self.is_retryable(code),
+        }
+    }
+}
+
+impl From<&crate::config::RetrySettings> for RetryConfig {
+    fn from(settings: &crate::config::RetrySettings) -> Self {
+        Self {
+            max_retries: settings.max_retries,
+            base_delay_ms: settings.base_delay_ms,
+            max_delay_ms: settings.max_delay_ms,
+            retryable_status_codes: settings.retryable_status_codes.clone(),
+            retry_on_connection_error: settings.retry_on_connection_error,
+            retry_streaming_server_errors: settings.retry_streaming_server_errors,
+        }
     }
 }
 
@@ -127,10 +186,21 @@ impl Retrier {
         Duration::from_millis(delay as u64)
     }
 
-    /// 带重试执行异步操作
+    /// 计算重试等待时间，上游给出建议等待时间（如 `retry-after`）时优先使用
+    ///
+    /// 建议等待时间超过 `max_delay_ms` 时返回 None，调用方应放弃重试、改用其他凭证
+    pub fn delay_for(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        match retry_after {
+            Some(hint) if hint > Duration::from_millis(self.config.max_delay_ms) => None,
+            Some(hint) => Some(hint),
+            None => Some(self.backoff_delay(attempt)),
+        }
+    }
+
+    /// 带重试执行异步操作（按非流式请求的重试策略）
     ///
     /// 操作函数返回 `Result<T, (String, Option<u16>)>`，
-    /// 其中错误元组包含错误信息和可选的状态码
+    /// 其中错误元组包含错误信息和可选的状态码（None 表示连接错误）
     pub async fn execute<F, Fut, T>(&self, mut operation: F) -> Result<T, RetryError>
     where
         F: FnMut() -> Fut,
@@ -150,12 +220,7 @@ impl Retrier {
                     last_status_code = status_code;
 
                     // 检查是否应该重试
-                    let should_retry = if let Some(code) = status_code {
-                        self.config.is_retryable(code)
-                    } else {
-                        // 没有状态码的错误（如网络错误）默认可重试
-                        true
-                    };
+                    let should_retry = self.config.should_retry(status_code, false, false);
 
                     // 检查是否还有重试次数
                     if !should_retry || attempts > self.config.max_retries {
@@ -211,8 +276,21 @@ mod unit_tests {
         assert_eq!(config.max_retries, 3);
         assert_eq!(config.base_delay_ms, 1000);
         assert_eq!(config.max_delay_ms, 30000);
-        assert!(config.retryable_codes.contains(&429));
-        assert!(config.retryable_codes.contains(&503));
+        assert!(config.retryable_status_codes.contains(&429));
+        assert!(config.retryable_status_codes.contains(&503));
+    }
+
+    #[test]
+    fn test_delay_for_honors_retry_after() {
+        let retrier = Retrier::new(RetryConfig::new(3, 1000, 30000));
+        assert_eq!(
+            retrier.delay_for(0, Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+        // 超过最大退避时间时放弃重试
+        assert_eq!(retrier.delay_for(0, Some(Duration::from_secs(60))), None);
+        let delay = retrier.delay_for(1, None).unwrap();
+        assert!(delay >= Duration::from_millis(2000) && delay < Duration::from_millis(3000));
     }
 
     #[test]
@@ -220,7 +298,6 @@ mod unit_tests {
         let config = RetryConfig::default();
 
         // 可重试的状态码
-        assert!(config.is_retryable(408));
         assert!(config.is_retryable(429));
         assert!(config.is_retryable(500));
         assert!(config.is_retryable(502));
@@ -230,11 +307,40 @@ mod unit_tests {
         // 不可重试的状态码
         assert!(!config.is_retryable(200));
         assert!(!config.is_retryable(400));
+        assert!(!config.is_retryable(408));
         assert!(!config.is_retryable(401));
         assert!(!config.is_retryable(403));
         assert!(!config.is_retryable(404));
     }
 
+    #[test]
+    fn test_should_retry_policy() {
+        let config = RetryConfig::default();
+
+        // 连接错误、429、非流式 5xx 可重试
+        assert!(config.should_retry(None, false, false));
+        assert!(config.should_retry(Some(429), true, false));
+        assert!(config.should_retry(Some(503), false, false));
+
+        // 流式 5xx 默认不重试，可通过配置开启
+        assert!(!config.should_retry(Some(503), true, false));
+        let lenient = RetryConfig {
+            retry_streaming_server_errors: true,
+            ..RetryConfig::default()
+        };
+        assert!(lenient.should_retry(Some(503), true, false));
+
+        // 输出开始后一律不重试
+        assert!(!config.should_retry(None, true, true));
+        assert!(!lenient.should_retry(Some(429), true, true));
+
+        let no_conn = RetryConfig {
+            retry_on_connection_error: false,
+            ..RetryConfig::default()
+        };
+        assert!(!no_conn.should_retry(None, false, false));
+    }
+
     #[test]
     fn test_backoff_delay_no_jitter() {
         let config = RetryConfig::new(5, 1000, 30000);
@@ -0,0 +1,147 @@
+//! 切换日志
+//!
+//! 记录流量离开某个凭证的事件并写入数据库，供容错设置页查询和事后审计。
+//!
+//! 凭证失败（429、5xx、健康检查失败、手动禁用）时先记下原因；之后同一 Provider
+//! 选出了另一个凭证，才写入一条从原凭证到新凭证的切换记录。失败后凭证又请求成功
+//! 则丢弃记下的原因。凭证降级到其他 Provider 时直接写入记录。
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use parking_lot::RwLock;
+
+use crate::database::dao::switch_log::SwitchLogDao;
+pub use crate::database::dao::switch_log::{
+    SwitchLogEntry, SwitchLogPage, SwitchLogQuery, SwitchTrigger,
+};
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::ProviderCredential;
+
+/// 数据库中最多保留的切换记录数
+const MAX_SWITCH_LOG_ENTRIES: usize = 10_000;
+
+/// 凭证失败时记下的切换原因
+#[derive(Debug, Clone)]
+struct PendingSwitch {
+    trigger: SwitchTrigger,
+    request_id: Option<String>,
+    model: Option<String>,
+    detail: Option<String>,
+}
+
+/// 上一次选出的凭证
+#[derive(Debug, Clone)]
+struct LastSelected {
+    uuid: String,
+    provider: String,
+}
+
+/// 切换日志
+#[derive(Debug, Default)]
+pub struct SwitchLog {
+    /// 凭证 UUID -> 待归因的失败原因
+    pending: RwLock<HashMap<String, PendingSwitch>>,
+    /// 请求的 Provider 类型 -> 上一次选出的凭证
+    last_selected: RwLock<HashMap<String, LastSelected>>,
+}
+
+impl SwitchLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记下凭证失败的原因，后续选出其他凭证时写入切换记录
+    pub fn note_failure(
+        &self,
+        credential_uuid: &str,
+        trigger: SwitchTrigger,
+        request_id: Option<&str>,
+        model: Option<&str>,
+        detail: Option<&str>,
+    ) {
+        self.pending.write().insert(
+            credential_uuid.to_string(),
+            PendingSwitch {
+                trigger,
+                request_id: request_id.map(str::to_string),
+                model: model.map(str::to_string),
+                detail: detail.map(str::to_string),
+            },
+        );
+    }
+
+    /// 凭证恢复正常，丢弃记下的失败原因
+    pub fn clear_failure(&self, credential_uuid: &str) {
+        if self.pending.read().contains_key(credential_uuid) {
+            self.pending.write().remove(credential_uuid);
+        }
+    }
+
+    /// 记录一次凭证选择，与上次不同且上次的凭证有失败原因时写入切换记录
+    pub fn observe_selection(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        selected: &ProviderCredential,
+    ) {
+        let previous = self.last_selected.write().insert(
+            provider_type.to_string(),
+            LastSelected {
+                uuid: selected.uuid.clone(),
+                provider: selected.provider_type.to_string(),
+            },
+        );
+        let Some(previous) = previous.filter(|p| p.uuid != selected.uuid) else {
+            return;
+        };
+        let Some(pending) = self.pending.write().remove(&previous.uuid) else {
+            return;
+        };
+        self.record(
+            db,
+            SwitchLogEntry {
+                id: 0,
+                timestamp: Utc::now(),
+                from_provider: previous.provider,
+                to_provider: selected.provider_type.to_string(),
+                from_credential: Some(previous.uuid),
+                to_credential: Some(selected.uuid.clone()),
+                trigger: pending.trigger,
+                request_id: pending.request_id,
+                model: pending.model,
+                detail: pending.detail,
+            },
+        );
+    }
+
+    /// 写入一条切换记录（失败时只记录日志）
+    pub fn record(&self, db: &DbConnection, entry: SwitchLogEntry) {
+        let result = db.lock().map_err(|e| e.to_string()).and_then(|conn| {
+            SwitchLogDao::insert(&conn, &entry, MAX_SWITCH_LOG_ENTRIES).map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(_) => tracing::info!(
+                "[SWITCH] {} {} -> {} trigger={} request_id={}",
+                entry.from_provider,
+                entry.from_credential.as_deref().unwrap_or("-"),
+                entry.to_credential.as_deref().unwrap_or(&entry.to_provider),
+                entry.trigger.as_str(),
+                entry.request_id.as_deref().unwrap_or("-")
+            ),
+            Err(e) => tracing::warn!("[SWITCH] 写入切换日志失败: {}", e),
+        }
+    }
+
+    /// 按条件分页查询
+    pub fn list(db: &DbConnection, query: &SwitchLogQuery) -> Result<SwitchLogPage, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        SwitchLogDao::list(&conn, query).map_err(|e| e.to_string())
+    }
+
+    /// 清除所有记录，返回删除的条数
+    pub fn clear(&self, db: &DbConnection) -> Result<usize, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        SwitchLogDao::clear(&conn).map_err(|e| e.to_string())
+    }
+}
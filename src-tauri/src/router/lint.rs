@@ -0,0 +1,227 @@
+//! 路由规则检查
+//!
+//! 检查 `routing.tier_rules` 中永远不会生效的规则。规则按顺序匹配、首条命中生效，
+//! 因此排在前面且范围更广的通配符会完全遮蔽后面的规则。
+//!
+//! 检查项：
+//! - 重复的模式（后出现的规则不会命中）
+//! - 被前面更宽泛的模式完全遮蔽的规则
+//! - 指向没有健康凭证的层级的规则（命中后会回退到任意健康凭证）
+
+use crate::config::TierRule;
+use crate::models::provider_pool_model::{pattern_matches, ProviderCredential};
+use serde::{Deserialize, Serialize};
+
+/// 规则问题类型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RoutingRuleIssue {
+    /// 与前面的规则模式相同
+    Duplicate { first_index: usize },
+    /// 被前面更宽泛的规则完全遮蔽
+    Shadowed { by_index: usize, by_pattern: String },
+    /// 目标层级没有健康的凭证
+    NoHealthyCredentials { tier: String },
+}
+
+/// 路由规则检查警告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingRuleWarning {
+    /// 规则在 `tier_rules` 中的位置
+    pub index: usize,
+    /// 规则模式
+    pub pattern: String,
+    /// 问题类型
+    #[serde(flatten)]
+    pub issue: RoutingRuleIssue,
+    /// 提示信息
+    pub message: String,
+}
+
+/// 通配符模式的匹配范围，与 `pattern_matches` 支持的形式一一对应
+enum PatternShape<'a> {
+    Exact(&'a str),
+    Prefix(&'a str),
+    Suffix(&'a str),
+    Contains(&'a str),
+    PrefixSuffix(&'a str, &'a str),
+    /// 不支持的形式（多于两个 `*`），不会匹配任何模型
+    Never,
+}
+
+impl<'a> PatternShape<'a> {
+    fn parse(pattern: &'a str) -> Self {
+        if !pattern.contains('*') {
+            return Self::Exact(pattern);
+        }
+        let parts: Vec<&str> = pattern.split('*').collect();
+        match parts.as_slice() {
+            [prefix, ""] => Self::Prefix(prefix),
+            ["", suffix] => Self::Suffix(suffix),
+            ["", middle, ""] => Self::Contains(middle),
+            [prefix, suffix] => Self::PrefixSuffix(prefix, suffix),
+            _ => Self::Never,
+        }
+    }
+}
+
+/// `broad` 是否匹配 `narrow` 能匹配的所有模型
+fn covers(broad: &str, narrow: &str) -> bool {
+    use PatternShape::*;
+
+    let narrow_shape = PatternShape::parse(narrow);
+    if let Exact(model) = narrow_shape {
+        return pattern_matches(broad, model);
+    }
+    match (PatternShape::parse(broad), narrow_shape) {
+        // `*` 和 `**` 匹配任意模型
+        (Prefix("") | Contains(""), _) => true,
+        (Prefix(p), Prefix(q) | PrefixSuffix(q, _)) => q.starts_with(p),
+        (Suffix(s), Suffix(t) | PrefixSuffix(_, t)) => t.ends_with(s),
+        (Contains(m), Prefix(q) | Suffix(q) | Contains(q)) => q.contains(m),
+        (Contains(m), PrefixSuffix(q, t)) => q.contains(m) || t.contains(m),
+        (PrefixSuffix(p, s), PrefixSuffix(q, t)) => q.starts_with(p) && t.ends_with(s),
+        _ => false,
+    }
+}
+
+/// 检查层级规则，返回按规则顺序排列的警告
+pub fn lint_tier_rules(
+    rules: &[TierRule],
+    credentials: &[ProviderCredential],
+) -> Vec<RoutingRuleWarning> {
+    let mut warnings = Vec::new();
+
+    for (index, rule) in rules.iter().enumerate() {
+        let earlier = &rules[..index];
+        if let Some(first_index) = earlier.iter().position(|r| r.pattern == rule.pattern) {
+            warnings.push(RoutingRuleWarning {
+                index,
+                pattern: rule.pattern.clone(),
+                message: format!(
+                    "模式 '{}' 与第 {} 条规则重复，该规则不会生效",
+                    rule.pattern,
+                    first_index + 1
+                ),
+                issue: RoutingRuleIssue::Duplicate { first_index },
+            });
+        } else if let Some((by_index, by)) = earlier
+            .iter()
+            .enumerate()
+            .find(|(_, r)| covers(&r.pattern, &rule.pattern))
+        {
+            warnings.push(RoutingRuleWarning {
+                index,
+                pattern: rule.pattern.clone(),
+                message: format!(
+                    "模式 '{}' 被第 {} 条规则 '{}' 完全覆盖，该规则不会生效",
+                    rule.pattern,
+                    by_index + 1,
+                    by.pattern
+                ),
+                issue: RoutingRuleIssue::Shadowed {
+                    by_index,
+                    by_pattern: by.pattern.clone(),
+                },
+            });
+        }
+
+        let has_healthy = credentials
+            .iter()
+            .any(|c| c.is_available() && c.tier.as_deref() == Some(rule.tier.as_str()));
+        if !has_healthy {
+            warnings.push(RoutingRuleWarning {
+                index,
+                pattern: rule.pattern.clone(),
+                message: format!(
+                    "层级 '{}' 没有健康的凭证，命中该规则的请求会回退到任意健康凭证",
+                    rule.tier
+                ),
+                issue: RoutingRuleIssue::NoHealthyCredentials {
+                    tier: rule.tier.clone(),
+                },
+            });
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+    fn rule(pattern: &str, tier: &str) -> TierRule {
+        TierRule {
+            pattern: pattern.to_string(),
+            tier: tier.to_string(),
+        }
+    }
+
+    fn credential(tier: &str, healthy: bool) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::GeminiApiKey,
+            CredentialData::GeminiApiKey {
+                api_key: "key".to_string(),
+                base_url: None,
+                excluded_models: Vec::new(),
+            },
+        );
+        cred.tier = Some(tier.to_string());
+        cred.is_healthy = healthy;
+        cred
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(covers("gemini-*", "gemini-*-pro"));
+        assert!(covers("gemini-*", "gemini-2.5-pro"));
+        assert!(covers("*-pro", "gemini-*-pro"));
+        assert!(covers("*pro*", "*-pro"));
+        assert!(covers("*", "claude-*"));
+        assert!(!covers("gemini-*-pro", "gemini-*"));
+        assert!(!covers("*-flash", "gemini-*"));
+        assert!(!covers("claude-sonnet-4-5", "claude-*"));
+    }
+
+    #[test]
+    fn test_lint_tier_rules() {
+        let rules = vec![
+            rule("gemini-*", "paid"),
+            rule("gemini-*-pro", "paid"),
+            rule("*-flash", "paid"),
+            rule("*-flash", "free"),
+            rule("claude-*", "enterprise"),
+        ];
+        let credentials = vec![credential("paid", true), credential("enterprise", false)];
+
+        let warnings = lint_tier_rules(&rules, &credentials);
+        let issues: Vec<_> = warnings.iter().map(|w| (w.index, &w.issue)).collect();
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    1,
+                    &RoutingRuleIssue::Shadowed {
+                        by_index: 0,
+                        by_pattern: "gemini-*".to_string()
+                    }
+                ),
+                (3, &RoutingRuleIssue::Duplicate { first_index: 2 }),
+                (
+                    3,
+                    &RoutingRuleIssue::NoHealthyCredentials {
+                        tier: "free".to_string()
+                    }
+                ),
+                (
+                    4,
+                    &RoutingRuleIssue::NoHealthyCredentials {
+                        tier: "enterprise".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -1,6 +1,10 @@
 //! 模型映射器
 //!
 //! 提供模型别名映射和解析功能
+//!
+//! 匹配别名前可对模型名做规范化：去掉已知的日期后缀（如 `-20250929`、
+//! `-2025-09-29`、`@20250929`），使 `claude-sonnet-4-5-20250929` 与
+//! `claude-sonnet-4-5` 命中同一条别名规则。
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -16,11 +20,26 @@ pub struct ModelInfo {
     pub actual_model: Option<String>,
 }
 
+/// 模型解析过程（用于路由诊断）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelResolution {
+    /// 请求中的原始模型名
+    pub requested: String,
+    /// 用于匹配的规范化模型名（与原始模型名相同时为 None）
+    pub normalized: Option<String>,
+    /// 命中的别名规则（别名键）
+    pub matched_alias: Option<String>,
+    /// 解析后的实际模型名
+    pub resolved: String,
+}
+
 /// 模型映射器 - 管理模型别名映射
 #[derive(Debug, Clone, Default)]
 pub struct ModelMapper {
     /// 别名到实际模型的映射 (alias -> actual)
     aliases: HashMap<String, String>,
+    /// 是否在匹配前规范化模型名（去掉日期后缀）
+    normalize: bool,
 }
 
 impl ModelMapper {
@@ -28,22 +47,82 @@ impl ModelMapper {
     pub fn new() -> Self {
         Self {
             aliases: HashMap::new(),
+            normalize: false,
         }
     }
 
     /// 从别名映射创建模型映射器
     pub fn from_aliases(aliases: HashMap<String, String>) -> Self {
-        Self { aliases }
+        Self {
+            aliases,
+            normalize: false,
+        }
+    }
+
+    /// 设置是否在匹配前规范化模型名
+    pub fn with_normalization(mut self, enabled: bool) -> Self {
+        self.normalize = enabled;
+        self
+    }
+
+    /// 设置是否在匹配前规范化模型名
+    pub fn set_normalization(&mut self, enabled: bool) {
+        self.normalize = enabled;
+    }
+
+    /// 是否启用模型名规范化
+    pub fn normalization_enabled(&self) -> bool {
+        self.normalize
     }
 
     /// 解析模型名（别名 -> 实际名）
     ///
     /// 如果模型名是别名，返回实际模型名；否则返回原模型名
     pub fn resolve(&self, model: &str) -> String {
-        self.aliases
-            .get(model)
+        self.explain(model).resolved
+    }
+
+    /// 解析模型名并返回匹配过程
+    ///
+    /// 优先精确匹配别名；启用规范化时，再以规范化后的模型名匹配
+    /// （别名键同样按规范化后比较）。未命中时返回原模型名，不改写日期后缀。
+    pub fn explain(&self, model: &str) -> ModelResolution {
+        let normalized = if self.normalize {
+            Some(normalize_model_name(model)).filter(|n| n != model)
+        } else {
+            None
+        };
+
+        let matched_alias = if self.aliases.contains_key(model) {
+            Some(model.to_string())
+        } else if self.normalize {
+            let key = normalized.as_deref().unwrap_or(model);
+            if self.aliases.contains_key(key) {
+                Some(key.to_string())
+            } else {
+                // 别名键带日期后缀时，按规范化后的键匹配（取字典序最小者保证稳定）
+                self.aliases
+                    .keys()
+                    .filter(|alias| normalize_model_name(alias) == key)
+                    .min()
+                    .cloned()
+            }
+        } else {
+            None
+        };
+
+        let resolved = matched_alias
+            .as_ref()
+            .and_then(|alias| self.aliases.get(alias))
             .cloned()
-            .unwrap_or_else(|| model.to_string())
+            .unwrap_or_else(|| model.to_string());
+
+        ModelResolution {
+            requested: model.to_string(),
+            normalized,
+            matched_alias,
+            resolved,
+        }
     }
 
     /// 添加别名映射
@@ -114,6 +193,35 @@ impl ModelMapper {
     }
 }
 
+/// 规范化模型名：去掉已知格式的日期后缀
+///
+/// 支持 `-YYYYMMDD`、`-YYYY-MM-DD` 和 `@YYYYMMDD`（Vertex 风格）。
+pub fn normalize_model_name(model: &str) -> String {
+    let bytes = model.as_bytes();
+    let is_date = |digits: &[u8]| {
+        digits.len() == 8 && digits.starts_with(b"20") && digits.iter().all(u8::is_ascii_digit)
+    };
+
+    // -YYYY-MM-DD
+    if bytes.len() > 11 {
+        let tail = &bytes[bytes.len() - 11..];
+        let digits: Vec<u8> = [&tail[1..5], &tail[6..8], &tail[9..11]].concat();
+        if tail[0] == b'-' && tail[5] == b'-' && tail[8] == b'-' && is_date(&digits) {
+            return model[..bytes.len() - 11].to_string();
+        }
+    }
+
+    // -YYYYMMDD / @YYYYMMDD
+    if bytes.len() > 9 {
+        let tail = &bytes[bytes.len() - 9..];
+        if (tail[0] == b'-' || tail[0] == b'@') && is_date(&tail[1..]) {
+            return model[..bytes.len() - 9].to_string();
+        }
+    }
+
+    model.to_string()
+}
+
 #[cfg(test)]
 mod mapper_tests {
     use super::*;
@@ -149,6 +257,56 @@ mod mapper_tests {
         assert_eq!(mapper.resolve("gemini-2.5-flash"), "gemini-2.5-flash");
     }
 
+    #[test]
+    fn test_normalize_model_name() {
+        assert_eq!(
+            normalize_model_name("claude-sonnet-4-5-20250929"),
+            "claude-sonnet-4-5"
+        );
+        assert_eq!(
+            normalize_model_name("claude-3-5-sonnet@20241022"),
+            "claude-3-5-sonnet"
+        );
+        assert_eq!(normalize_model_name("gpt-4o-2024-08-06"), "gpt-4o");
+        assert_eq!(
+            normalize_model_name("claude-sonnet-4-5"),
+            "claude-sonnet-4-5"
+        );
+        assert_eq!(normalize_model_name("gemini-2.5-flash"), "gemini-2.5-flash");
+    }
+
+    #[test]
+    fn test_resolve_with_normalization() {
+        let mut mapper = ModelMapper::new().with_normalization(true);
+        mapper.add_alias("claude-sonnet-4-5", "claude-sonnet-4-5-20250929");
+        mapper.add_alias("gpt-4o-2024-08-06", "gemini-2.5-pro");
+
+        // 带日期后缀的请求命中不带后缀的别名
+        let resolution = mapper.explain("claude-sonnet-4-5-20250514");
+        assert_eq!(resolution.normalized.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(
+            resolution.matched_alias.as_deref(),
+            Some("claude-sonnet-4-5")
+        );
+        assert_eq!(resolution.resolved, "claude-sonnet-4-5-20250929");
+
+        // 不带后缀的请求命中带日期后缀的别名
+        assert_eq!(mapper.resolve("gpt-4o"), "gemini-2.5-pro");
+
+        // 未命中时保留原模型名（不去掉日期后缀）
+        assert_eq!(
+            mapper.resolve("claude-opus-4-20250514"),
+            "claude-opus-4-20250514"
+        );
+
+        // 关闭规范化后只做精确匹配
+        mapper.set_normalization(false);
+        assert_eq!(
+            mapper.resolve("claude-sonnet-4-5-20250514"),
+            "claude-sonnet-4-5-20250514"
+        );
+    }
+
     #[test]
     fn test_remove_alias() {
         let mut mapper = ModelMapper::new();
@@ -10,15 +10,22 @@
 //!
 //! 模型映射：
 //! - 支持模型别名映射（如 `gpt-4` -> `claude-sonnet-4-5-20250514`）
+//! - 支持匹配前去掉日期后缀（如 `claude-sonnet-4-5-20250929` -> `claude-sonnet-4-5`）
+//!
+//! 推荐预设：整体替换默认 Provider 和模型别名，应用前先做金丝雀请求（见 `presets`）
 
 mod amp_router;
+mod lint;
 mod mapper;
+mod presets;
 mod provider_router;
 mod route_registry;
 mod rules;
 
 pub use amp_router::{AmpRouteMatch, AmpRouter};
-pub use mapper::{ModelInfo, ModelMapper};
+pub use lint::{lint_tier_rules, RoutingRuleIssue, RoutingRuleWarning};
+pub use mapper::{normalize_model_name, ModelInfo, ModelMapper, ModelResolution};
+pub use presets::{find_preset, recommended_presets, CanaryTarget, RoutingPreset};
 pub use provider_router::ProviderRouter;
 pub use route_registry::{RegisteredRoute, RouteRegistry, RouteType};
 pub use rules::{RouteResult, Router};
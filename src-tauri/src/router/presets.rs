@@ -0,0 +1,177 @@
+//! 推荐路由预设
+//!
+//! 预设整体替换默认 Provider 和模型别名。应用前由 `apply_recommended_preset` 对预设涉及的
+//! 每个模型做一次金丝雀请求，全部通过（或强制应用）时才写入配置。
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use super::ModelMapper;
+use crate::config::RoutingConfig;
+
+/// 推荐路由预设
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RoutingPreset {
+    /// 预设标识
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// 说明
+    pub description: String,
+    /// 应用后的默认 Provider
+    pub default_provider: String,
+    /// 应用后的模型别名（整体替换现有别名）
+    pub model_aliases: HashMap<String, String>,
+}
+
+/// 金丝雀请求目标：同一 Provider 下解析到同一模型的别名合并为一次请求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanaryTarget {
+    /// 请求经过的 Provider
+    pub provider: String,
+    /// 解析后的模型
+    pub resolved_model: String,
+    /// 预设涉及的模型（别名）
+    pub models: Vec<String>,
+}
+
+fn preset(
+    id: &str,
+    name: &str,
+    description: &str,
+    default_provider: &str,
+    aliases: &[(&str, &str)],
+) -> RoutingPreset {
+    RoutingPreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        default_provider: default_provider.to_string(),
+        model_aliases: aliases
+            .iter()
+            .map(|(alias, model)| (alias.to_string(), model.to_string()))
+            .collect(),
+    }
+}
+
+/// 内置的推荐预设
+pub fn recommended_presets() -> Vec<RoutingPreset> {
+    vec![
+        preset(
+            "kiro-claude",
+            "Kiro Claude",
+            "默认使用 Kiro，常见 OpenAI 模型名映射到对应档位的 Claude 模型",
+            "kiro",
+            &[
+                ("gpt-4o", "claude-sonnet-4-5"),
+                ("gpt-4.1", "claude-sonnet-4-5"),
+                ("gpt-4o-mini", "claude-haiku-4-5"),
+                ("o3", "claude-opus-4-5"),
+            ],
+        ),
+        preset(
+            "antigravity-gemini",
+            "Antigravity Gemini",
+            "默认使用 Antigravity，OpenAI 和 Claude 模型名映射到 Gemini 3 与 Antigravity 托管的 Claude",
+            "antigravity",
+            &[
+                ("gpt-4o", "gemini-3-pro-preview"),
+                ("gpt-4o-mini", "gemini-3-flash-preview"),
+                ("claude-sonnet-4-5", "gemini-claude-sonnet-4-5"),
+            ],
+        ),
+        preset(
+            "gemini-cli",
+            "Gemini CLI",
+            "默认使用 Gemini OAuth，OpenAI 模型名映射到 Gemini 2.5",
+            "gemini",
+            &[
+                ("gpt-4o", "gemini-2.5-pro"),
+                ("gpt-4o-mini", "gemini-2.5-flash"),
+            ],
+        ),
+    ]
+}
+
+/// 按标识查找推荐预设
+pub fn find_preset(id: &str) -> Option<RoutingPreset> {
+    recommended_presets().into_iter().find(|p| p.id == id)
+}
+
+impl RoutingPreset {
+    /// 应用到路由配置：替换默认 Provider 和模型别名，其他路由设置保持不变
+    pub fn apply_to(&self, routing: &mut RoutingConfig) {
+        routing.default_provider = self.default_provider.clone();
+        routing.model_aliases = self.model_aliases.clone();
+    }
+
+    /// 金丝雀请求目标，按解析后的模型排序
+    pub fn canary_targets(&self, normalize_model_names: bool) -> Vec<CanaryTarget> {
+        let mapper = ModelMapper::from_aliases(self.model_aliases.clone())
+            .with_normalization(normalize_model_names);
+        let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for alias in self.model_aliases.keys() {
+            grouped
+                .entry(mapper.resolve(alias))
+                .or_default()
+                .push(alias.clone());
+        }
+        grouped
+            .into_iter()
+            .map(|(resolved_model, mut models)| {
+                models.sort();
+                CanaryTarget {
+                    provider: self.default_provider.clone(),
+                    resolved_model,
+                    models,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_unique_and_target_known_providers() {
+        let presets = recommended_presets();
+        for (i, p) in presets.iter().enumerate() {
+            assert!(presets[i + 1..].iter().all(|other| other.id != p.id));
+            assert!(p.default_provider.parse::<crate::ProviderType>().is_ok());
+            assert!(!p.model_aliases.is_empty());
+        }
+        assert!(find_preset("kiro-claude").is_some());
+        assert!(find_preset("missing").is_none());
+    }
+
+    #[test]
+    fn test_canary_targets_group_aliases_by_resolved_model() {
+        let preset = find_preset("kiro-claude").unwrap();
+        let targets = preset.canary_targets(true);
+        assert_eq!(targets.len(), 3);
+        let sonnet = targets
+            .iter()
+            .find(|t| t.resolved_model == "claude-sonnet-4-5")
+            .unwrap();
+        assert_eq!(sonnet.models, vec!["gpt-4.1", "gpt-4o"]);
+        assert!(targets.iter().all(|t| t.provider == "kiro"));
+    }
+
+    #[test]
+    fn test_apply_replaces_default_provider_and_aliases_only() {
+        let mut routing = RoutingConfig::default();
+        routing
+            .model_aliases
+            .insert("old".to_string(), "old-model".to_string());
+        routing.normalize_model_names = false;
+
+        let preset = find_preset("gemini-cli").unwrap();
+        preset.apply_to(&mut routing);
+        assert_eq!(routing.default_provider, "gemini");
+        assert_eq!(routing.model_aliases, preset.model_aliases);
+        assert!(!routing.normalize_model_names);
+    }
+}
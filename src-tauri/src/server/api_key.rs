@@ -0,0 +1,112 @@
+//! 服务器 API Key
+//!
+//! 运行中的服务器与 Tauri 状态共享同一个 `ServerApiKey`，
+//! 支持在不重启服务器的情况下轮换密钥，并在宽限期内继续接受旧密钥。
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct ApiKeyState {
+    current: String,
+    /// 轮换前的旧密钥及其失效时间
+    previous: Option<(String, Instant)>,
+}
+
+/// 可轮换的服务器 API Key
+#[derive(Debug, Clone)]
+pub struct ServerApiKey {
+    inner: Arc<RwLock<ApiKeyState>>,
+}
+
+impl ServerApiKey {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(ApiKeyState {
+                current: api_key.into(),
+                previous: None,
+            })),
+        }
+    }
+
+    /// 当前密钥
+    pub fn current(&self) -> String {
+        self.inner
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .current
+            .clone()
+    }
+
+    /// 校验客户端提供的密钥（当前密钥或宽限期内的旧密钥）
+    pub fn matches(&self, key: &str) -> bool {
+        let state = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        if key == state.current {
+            return true;
+        }
+        matches!(&state.previous, Some((old, expires_at)) if key == old && Instant::now() < *expires_at)
+    }
+
+    /// 轮换为新密钥，旧密钥在 `grace` 时间内仍然有效
+    ///
+    /// `grace` 为零时旧密钥立即失效。
+    pub fn rotate(&self, new_key: impl Into<String>, grace: Duration) {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        let old = std::mem::replace(&mut state.current, new_key.into());
+        state.previous = if grace.is_zero() {
+            None
+        } else {
+            Some((old, Instant::now() + grace))
+        };
+    }
+
+    /// 与配置中的密钥同步（服务器启动时调用）
+    ///
+    /// 密钥未变化时保留进行中的宽限期，否则直接替换且不保留旧密钥。
+    pub fn sync(&self, api_key: &str) {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        if state.current != api_key {
+            state.current = api_key.to_string();
+            state.previous = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_keeps_old_key_during_grace() {
+        let key = ServerApiKey::new("old");
+        key.rotate("new", Duration::from_secs(60));
+
+        assert_eq!(key.current(), "new");
+        assert!(key.matches("new"));
+        assert!(key.matches("old"));
+        assert!(!key.matches("other"));
+    }
+
+    #[test]
+    fn test_rotate_without_grace_rejects_old_key() {
+        let key = ServerApiKey::new("old");
+        key.rotate("new", Duration::ZERO);
+
+        assert!(key.matches("new"));
+        assert!(!key.matches("old"));
+    }
+
+    #[test]
+    fn test_sync_with_different_key_clears_grace() {
+        let key = ServerApiKey::new("old");
+        key.rotate("new", Duration::from_secs(60));
+
+        key.sync("new");
+        assert!(key.matches("old"));
+
+        key.sync("manual");
+        assert!(key.matches("manual"));
+        assert!(!key.matches("old"));
+        assert!(!key.matches("new"));
+    }
+}
@@ -356,6 +356,7 @@ mod property_tests {
                     windsurf,
                     kiro,
                     other,
+                    force_non_streaming: Vec::new(),
                 }
             })
     }
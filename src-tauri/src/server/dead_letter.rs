@@ -0,0 +1,211 @@
+//! 失败请求死信记录
+//!
+//! 启用后，Provider 调用返回错误（包括流式传输中途失败和没有可用凭证）时把请求连同
+//! Provider、凭证和错误写入 `dead_letters` 表，
+//! 即使没有开启 Flow 捕获也能在事后排查偶发故障，记录可通过 `FlowReplayer` 重放。
+//!
+//! 请求体写入前按字段名脱敏（API Key、Token 等），超过 `max_body_bytes` 时截断。
+//! 配置通过 [`configure`] 在启动和配置变更时更新，默认关闭。
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{Config, DeadLetterSettings};
+use crate::database::dao::dead_letter::{DeadLetter, DeadLetterDao};
+use crate::logger::sanitize_log_message;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::processor::ProcessError;
+use crate::server::AppState;
+
+/// 需要脱敏的字段名（忽略大小写和 `-` / `_`）
+const SENSITIVE_KEYS: &[&str] = &[
+    "apikey",
+    "authorization",
+    "accesstoken",
+    "refreshtoken",
+    "clientsecret",
+    "password",
+    "secret",
+    "token",
+];
+
+const REDACTED: &str = "***";
+
+static SETTINGS: Lazy<RwLock<DeadLetterSettings>> =
+    Lazy::new(|| RwLock::new(DeadLetterSettings::default()));
+
+/// 更新死信记录配置
+pub fn configure(config: &Config) {
+    *SETTINGS.write() = config.dead_letter.clone();
+}
+
+fn is_sensitive_key(key: &str) -> bool {
+    let normalized: String = key
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect();
+    SENSITIVE_KEYS.contains(&normalized.as_str())
+}
+
+/// 脱敏请求体，替换敏感字段的值
+pub fn sanitize(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if is_sensitive_key(key) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    sanitize(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(sanitize),
+        _ => {}
+    }
+}
+
+/// 按字节截断到字符边界，返回是否发生截断
+fn truncate_body(mut body: String, max_bytes: usize) -> (String, bool) {
+    if body.len() <= max_bytes {
+        return (body, false);
+    }
+    let mut end = max_bytes;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    body.truncate(end);
+    (body, true)
+}
+
+/// 记录一次失败的 Provider 调用（未启用时忽略）
+///
+/// 写入失败只记录警告，不影响返回给客户端的错误响应。
+pub fn record<T: Serialize>(
+    state: &AppState,
+    credential: &ProviderCredential,
+    path: &str,
+    model: &str,
+    request: &T,
+    error: &ProcessError,
+) {
+    write(
+        state,
+        Target {
+            provider: credential.provider_type.to_string(),
+            credential_uuid: Some(credential.uuid.clone()),
+            path,
+            model,
+        },
+        request,
+        error,
+    );
+}
+
+/// 记录一次因没有可用凭证而失败的请求（未启用时忽略）
+pub fn record_no_credential<T: Serialize>(
+    state: &AppState,
+    provider: &str,
+    path: &str,
+    model: &str,
+    request: &T,
+    error: &ProcessError,
+) {
+    write(
+        state,
+        Target {
+            provider: provider.to_string(),
+            credential_uuid: None,
+            path,
+            model,
+        },
+        request,
+        error,
+    );
+}
+
+/// 死信记录的请求目标
+struct Target<'a> {
+    provider: String,
+    credential_uuid: Option<String>,
+    path: &'a str,
+    model: &'a str,
+}
+
+fn write<T: Serialize>(state: &AppState, target: Target<'_>, request: &T, error: &ProcessError) {
+    let settings = SETTINGS.read().clone();
+    if !settings.enabled {
+        return;
+    }
+    let Some(db) = &state.db else {
+        return;
+    };
+
+    let mut body = serde_json::to_value(request).unwrap_or(Value::Null);
+    sanitize(&mut body);
+    let (request_body, truncated) = truncate_body(body.to_string(), settings.max_body_bytes);
+
+    let entry = DeadLetter {
+        id: uuid::Uuid::new_v4().to_string(),
+        created_at: Utc::now(),
+        provider: target.provider,
+        credential_uuid: target.credential_uuid,
+        model: target.model.to_string(),
+        path: target.path.to_string(),
+        status_code: error.status_code(),
+        error_type: error.error_type().to_string(),
+        error_message: sanitize_log_message(&error.to_string()),
+        request_body,
+        truncated,
+    };
+
+    let result = match db.lock() {
+        Ok(conn) => {
+            DeadLetterDao::insert(&conn, &entry, settings.max_entries).map_err(|e| e.to_string())
+        }
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("[DEAD_LETTER] 写入死信记录失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sanitize_redacts_nested_keys() {
+        let mut body = json!({
+            "model": "gpt-4o",
+            "api_key": "sk-123",
+            "metadata": { "Access-Token": "abc", "user": "alice" },
+            "messages": [{ "role": "user", "content": "hi", "password": "p" }]
+        });
+        sanitize(&mut body);
+        assert_eq!(body["api_key"], REDACTED);
+        assert_eq!(body["metadata"]["Access-Token"], REDACTED);
+        assert_eq!(body["metadata"]["user"], "alice");
+        assert_eq!(body["messages"][0]["password"], REDACTED);
+        assert_eq!(body["messages"][0]["content"], "hi");
+        // max_tokens 等普通字段不受影响
+        assert!(!is_sensitive_key("max_tokens"));
+    }
+
+    #[test]
+    fn test_truncate_body_respects_char_boundary() {
+        assert_eq!(
+            truncate_body("abc".to_string(), 3),
+            ("abc".to_string(), false)
+        );
+        // "你" 占 3 字节，截断到 4 字节时回退到字符边界
+        assert_eq!(
+            truncate_body("你好".to_string(), 4),
+            ("你".to_string(), true)
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! 默认 Provider 按权重分流
+//!
+//! 配置 `routing.default_provider_split` 后，未命中端点 Provider 配置的请求按权重随机选择
+//! Provider，替代单一的 `default_provider`，用于在多个 Provider 间分摊配额。
+//!
+//! 每次分流决策记录在请求追踪的 Provider 选择步骤（`source=default_split`），
+//! 同时计入进程内计数，可通过 [`stats`] 对比实际比例与配置权重；分流配置变化时计数清零。配置通过 [`configure`] 在启动和配置变更时更新。
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use rand::Rng;
+use serde::Serialize;
+
+use crate::config::{Config, ProviderWeight};
+
+static SPLIT: Lazy<RwLock<Vec<ProviderWeight>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Provider -> 分流次数
+static COUNTS: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 单个 Provider 的分流统计
+#[derive(Debug, Clone, Serialize)]
+pub struct SplitStat {
+    pub provider: String,
+    pub weight: u32,
+    /// 按权重应得的比例
+    pub expected_ratio: f64,
+    /// 实际分流次数
+    pub count: u64,
+    /// 实际比例
+    pub actual_ratio: f64,
+}
+
+/// 更新分流配置（忽略权重为 0 和名称为空的条目）
+pub fn configure(config: &Config) {
+    let split: Vec<ProviderWeight> = config
+        .routing
+        .default_provider_split
+        .iter()
+        .filter(|w| w.weight > 0 && !w.provider.trim().is_empty())
+        .map(|w| ProviderWeight {
+            provider: w.provider.trim().to_string(),
+            weight: w.weight,
+        })
+        .collect();
+
+    let mut current = SPLIT.write();
+    if *current != split {
+        COUNTS.lock().clear();
+        if !split.is_empty() {
+            tracing::info!(
+                "[ROUTE] 默认 Provider 分流: {}",
+                split
+                    .iter()
+                    .map(|w| format!("{}={}", w.provider, w.weight))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        *current = split;
+    }
+}
+
+/// 按权重选择默认 Provider 并计数，未配置分流时返回 None
+pub fn pick() -> Option<String> {
+    let split = SPLIT.read();
+    let total: u64 = split.iter().map(|w| u64::from(w.weight)).sum();
+    if total == 0 {
+        return None;
+    }
+    let point = rand::thread_rng().gen_range(0..total);
+    let provider = choose(&split, point)?.to_string();
+    drop(split);
+
+    *COUNTS.lock().entry(provider.clone()).or_insert(0) += 1;
+    tracing::debug!("[ROUTE] 默认 Provider 分流选择: {}", provider);
+    Some(provider)
+}
+
+/// 在累积权重上定位 `point`（0 <= point < 权重总和）
+fn choose(split: &[ProviderWeight], mut point: u64) -> Option<&str> {
+    for w in split {
+        let weight = u64::from(w.weight);
+        if point < weight {
+            return Some(&w.provider);
+        }
+        point -= weight;
+    }
+    None
+}
+
+/// 当前分流配置下各 Provider 的期望与实际比例
+pub fn stats() -> Vec<SplitStat> {
+    let split = SPLIT.read();
+    let counts = COUNTS.lock();
+    let total_weight: u64 = split.iter().map(|w| u64::from(w.weight)).sum();
+    let total_count: u64 = counts.values().sum();
+
+    split
+        .iter()
+        .map(|w| {
+            let count = counts.get(&w.provider).copied().unwrap_or(0);
+            SplitStat {
+                provider: w.provider.clone(),
+                weight: w.weight,
+                expected_ratio: ratio(u64::from(w.weight), total_weight),
+                count,
+                actual_ratio: ratio(count, total_count),
+            }
+        })
+        .collect()
+}
+
+/// 清零分流计数
+pub fn reset_stats() {
+    COUNTS.lock().clear();
+}
+
+fn ratio(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weight(provider: &str, weight: u32) -> ProviderWeight {
+        ProviderWeight {
+            provider: provider.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_choose_by_cumulative_weight() {
+        let split = vec![weight("kiro", 70), weight("antigravity", 30)];
+        assert_eq!(choose(&split, 0), Some("kiro"));
+        assert_eq!(choose(&split, 69), Some("kiro"));
+        assert_eq!(choose(&split, 70), Some("antigravity"));
+        assert_eq!(choose(&split, 99), Some("antigravity"));
+        assert_eq!(choose(&split, 100), None);
+
+        let counts = (0..100).fold(HashMap::new(), |mut acc, point| {
+            *acc.entry(choose(&split, point).unwrap()).or_insert(0) += 1;
+            acc
+        });
+        assert_eq!(counts["kiro"], 70);
+        assert_eq!(counts["antigravity"], 30);
+    }
+}
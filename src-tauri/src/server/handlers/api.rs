@@ -30,13 +30,23 @@ use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::flow_monitor::{
     ClientInfo, FlowError, FlowErrorType, FlowMetadata, FlowType, InterceptAction, InterceptType,
     LLMFlow, LLMRequest, LLMResponse, Message, MessageContent, MessageRole, RequestParameters,
-    RoutingInfo, TokenUsage,
+    RoutingInfo, TokenUsage, SESSION_ID_HEADERS,
 };
+use crate::http_client::{with_client_anthropic_headers, ClientAnthropicHeaders};
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
-use crate::processor::RequestContext;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::processor::{
+    with_step_capture, ErrorFormat, ProcessError, RequestContext, TraceStepKind,
+};
+use crate::resilience::priority::UpstreamTimer;
 use crate::server::client_detector::ClientType;
-use crate::server::{record_request_telemetry, record_token_usage, AppState};
+use crate::server::{
+    dead_letter, is_benchmark_request, measure_response_bytes, model_fallback,
+    record_anthropic_usage, record_request_telemetry, record_stream_anthropic_usage,
+    record_token_usage, request_limits, retry_policy, shadow, stream_coalesce, stream_resume,
+    tool_result_truncation, user_limits, AppState, ServerApiKey,
+};
 use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, message_content_len,
     parse_cw_response, safe_truncate,
@@ -44,7 +54,7 @@ use crate::server_utils::{
 use crate::streaming::StreamFormat as StreamingFormat;
 use crate::ProviderType;
 
-use super::{call_provider_anthropic, call_provider_openai};
+use super::{call_provider_anthropic, call_provider_openai, drop_unsupported_penalty_params};
 
 // ============================================================================
 // Flow 捕获辅助函数
@@ -286,6 +296,15 @@ fn build_flow_metadata(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
+    let session_id = SESSION_ID_HEADERS.iter().find_map(|name| {
+        headers
+            .get(*name)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+    });
+
     FlowMetadata {
         provider,
         provider_id: provider_id.map(|s| s.to_string()),
@@ -296,6 +315,7 @@ fn build_flow_metadata(
             ip: client_ip,
             user_agent,
             request_id: Some(request_id.to_string()),
+            session_id,
         },
         routing_info: RoutingInfo::default(),
         injected_params: None,
@@ -347,8 +367,11 @@ fn build_llm_response(status_code: u16, content: &str, usage: Option<(u32, u32)>
 /// - `state`: 应用状态，包含端点配置和默认 Provider
 ///
 /// # 返回
-/// 选择的 Provider 名称和检测到的客户端类型
-async fn select_provider_for_client(headers: &HeaderMap, state: &AppState) -> (String, ClientType) {
+/// 选择的 Provider 名称、检测到的客户端类型和选择依据（endpoint / default_split / default）
+async fn select_provider_for_client(
+    headers: &HeaderMap,
+    state: &AppState,
+) -> (String, ClientType, &'static str) {
     // 从 User-Agent 检测客户端类型
     let user_agent = headers
         .get("user-agent")
@@ -363,13 +386,27 @@ async fn select_provider_for_client(headers: &HeaderMap, state: &AppState) -> (S
     // 获取默认 Provider
     let default_provider = state.default_provider.read().await.clone();
 
-    // 选择 Provider：端点配置优先，否则使用默认
-    let selected_provider = match endpoint_provider {
-        Some(provider) => provider.clone(),
-        None => default_provider,
+    // 选择 Provider：端点配置优先，其次按权重分流，否则使用默认
+    let (selected_provider, source) = match endpoint_provider {
+        Some(provider) => (provider.clone(), "endpoint"),
+        None => match crate::server::default_split::pick() {
+            Some(provider) => (provider, "default_split"),
+            None => (default_provider, "default"),
+        },
     };
 
-    (selected_provider, client_type)
+    (selected_provider, client_type, source)
+}
+
+/// 客户端是否配置为强制非流式
+///
+/// 命中时由调用方将请求改为非流式，上游响应缓冲完整后一次性返回。
+async fn is_force_non_streaming(state: &AppState, client_type: ClientType) -> bool {
+    state
+        .endpoint_providers
+        .read()
+        .await
+        .is_force_non_streaming(client_type.config_key())
 }
 
 // ============================================================================
@@ -549,7 +586,7 @@ async fn check_response_intercept(
 /// OpenAI 格式的 API key 验证
 pub async fn verify_api_key(
     headers: &HeaderMap,
-    expected_key: &str,
+    api_key: &ServerApiKey,
 ) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
     let auth = headers
         .get("authorization")
@@ -567,7 +604,7 @@ pub async fn verify_api_key(
         }
     };
 
-    if key != expected_key {
+    if !api_key.matches(key) {
         return Err((
             StatusCode::UNAUTHORIZED,
             Json(serde_json::json!({"error": {"message": "Invalid API key"}})),
@@ -580,7 +617,7 @@ pub async fn verify_api_key(
 /// Anthropic 格式的 API key 验证
 pub async fn verify_api_key_anthropic(
     headers: &HeaderMap,
-    expected_key: &str,
+    api_key: &ServerApiKey,
 ) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
     let auth = headers
         .get("x-api-key")
@@ -604,7 +641,7 @@ pub async fn verify_api_key_anthropic(
         }
     };
 
-    if key != expected_key {
+    if !api_key.matches(key) {
         return Err((
             StatusCode::UNAUTHORIZED,
             Json(serde_json::json!({
@@ -620,6 +657,136 @@ pub async fn verify_api_key_anthropic(
     Ok(())
 }
 
+/// 响应缓存命中标记头
+const RESPONSE_CACHE_HEADER: &str = "x-proxycast-cache";
+
+/// 单次请求费用上限覆盖头（美元，`0` / `off` 表示不限制）
+const MAX_COST_HEADER: &str = "x-proxycast-max-cost";
+
+/// 请求体大小：优先使用 Content-Length，缺失时按序列化后的 JSON 计算
+fn request_body_bytes<T: serde::Serialize>(headers: &HeaderMap, request: &T) -> u64 {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| {
+            serde_json::to_vec(request)
+                .map(|v| v.len() as u64)
+                .unwrap_or(0)
+        })
+}
+
+/// 计算本次请求的费用上限：请求头覆盖优先，否则使用配置的默认值
+pub fn max_cost_from_headers(headers: &HeaderMap) -> Option<f64> {
+    let override_value = headers
+        .get(MAX_COST_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(crate::stream::cost_guard::parse_override);
+    crate::stream::cost_guard::resolve_max_cost(override_value)
+}
+
+/// 记录 Anthropic 原生凭证生效的版本请求头（其他凭证忽略）
+fn record_anthropic_headers(
+    ctx: &mut RequestContext,
+    cred: &ProviderCredential,
+    client: &ClientAnthropicHeaders,
+) {
+    if !cred.credential.is_anthropic_native() {
+        return;
+    }
+    let effective = crate::http_client::resolve_anthropic_headers(&cred.upstream_headers, client);
+    ctx.record_step(TraceStepKind::AnthropicHeaders {
+        version: effective.version,
+        beta: effective.beta,
+    });
+}
+
+/// 记录工具结果截断（日志与请求追踪），便于调整截断预算
+async fn record_tool_result_truncation(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    provider: String,
+    truncation: tool_result_truncation::Truncation,
+) {
+    tool_result_truncation::log(
+        state,
+        Some(&ctx.request_id),
+        &provider,
+        &ctx.resolved_model,
+        truncation,
+    )
+    .await;
+    ctx.record_step(TraceStepKind::ToolResultsTruncated {
+        provider,
+        count: truncation.count,
+        removed_bytes: truncation.removed_bytes,
+    });
+}
+
+/// 将缓存的响应转换为 HTTP 响应
+///
+/// `cache_status` 写入 `x-proxycast-cache` 头：正常命中为 `HIT`，离线降级为 `stale-offline`
+fn cached_response_into_response(
+    cached: crate::processor::CachedResponse,
+    cache_status: &'static str,
+) -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(
+            header::CONTENT_TYPE,
+            cached.content_type.as_deref().unwrap_or("application/json"),
+        )
+        .header(RESPONSE_CACHE_HEADER, cache_status)
+        .body(Body::from(cached.body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// 请求改写（如模型替换）后按新的请求体重新计算缓存键
+///
+/// 原请求不参与缓存（`key` 为 None）时保持不缓存；否则返回改写后请求的键，
+/// 避免备选模型的响应写入原模型的缓存条目。
+fn recompute_cache_key(
+    cache: &crate::processor::ResponseCache,
+    key: Option<String>,
+    provider: &str,
+    request: &ChatCompletionRequest,
+) -> Option<String> {
+    key.and_then(|_| cache.cache_key(provider, request))
+}
+
+/// 读取上游响应体写入响应缓存，并重新构建响应
+///
+/// 上游返回 SSE 时不缓存（例如 Provider 忽略了 stream=false）。
+async fn store_cached_response(state: &AppState, key: String, response: Response) -> Response {
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    if content_type
+        .as_deref()
+        .is_some_and(|ct| ct.starts_with("text/event-stream"))
+    {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => {
+            state
+                .processor
+                .response_cache
+                .insert(key, bytes.clone(), content_type);
+            Response::from_parts(parts, Body::from(bytes))
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": {"message": format!("Failed to read response body: {}", e)}})),
+        )
+            .into_response(),
+    }
+}
+
 pub async fn chat_completions(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -643,8 +810,17 @@ pub async fn chat_completions(
     }
     eprintln!("[CHAT_COMPLETIONS] 认证成功");
 
+    // 携带 x-request-id 和 Last-Event-ID 的重连请求直接续传
+    if let Some(response) = stream_resume::resume_from_headers(&state, &headers) {
+        return response;
+    }
+
     // 创建请求上下文
-    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    let mut ctx = RequestContext::new(request.model.clone())
+        .with_stream(request.stream)
+        .with_request_bytes(request_body_bytes(&headers, &request))
+        .with_trace_store(state.processor.traces.clone());
+    ctx.benchmark = is_benchmark_request(&headers);
     eprintln!("[CHAT_COMPLETIONS] 请求ID: {}", ctx.request_id);
 
     state.logs.write().await.add(
@@ -690,6 +866,10 @@ pub async fn chat_completions(
                     ctx.request_id, result.applied_rules, result.injected_params
                 ),
             );
+            ctx.record_step(TraceStepKind::InjectionApplied {
+                applied_rules: result.applied_rules.clone(),
+                injected_params: result.injected_params.clone(),
+            });
             // 更新请求
             if let Ok(updated) = serde_json::from_value(payload) {
                 request = updated;
@@ -697,9 +877,60 @@ pub async fn chat_completions(
         }
     }
 
+    // 终端用户标识（限流与配额由 UserLimitsLayer 在进入处理器前检查）
+    ctx.user_id = request.user.as_deref().and_then(user_limits::user_key);
+
+    // 检查工具数量与大小，超限时在调用上游前返回 400
+    match request_limits::enforce_openai(&mut request) {
+        Ok(0) => {}
+        Ok(truncated) => {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[LIMITS] request_id={} 截断了 {} 个工具描述",
+                    ctx.request_id, truncated
+                ),
+            );
+        }
+        Err(e) => {
+            state.logs.write().await.add(
+                "warn",
+                &format!("[LIMITS] request_id={} {}", ctx.request_id, e),
+            );
+            return e.into_response_for(ErrorFormat::OpenAI);
+        }
+    }
+
+    // 按模型配置填充或截断 max_tokens
+    if let Some((original, adjusted)) =
+        request_limits::adjust_max_tokens(&request.model, &mut request.max_tokens)
+    {
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[LIMITS] request_id={} model={} max_tokens {:?} -> {}",
+                ctx.request_id, request.model, original, adjusted
+            ),
+        );
+    }
+
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
-    let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    let (selected_provider, client_type, selection_source) =
+        select_provider_for_client(&headers, &state).await;
+
+    // 端点配置了强制非流式时，缓冲上游响应后一次性返回
+    if request.stream && is_force_non_streaming(&state, client_type).await {
+        request.stream = false;
+        ctx.is_stream = false;
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[STREAM] request_id={} client_type={} 强制非流式",
+                ctx.request_id, client_type
+            ),
+        );
+    }
     eprintln!(
         "[CHAT_COMPLETIONS] 客户端类型: {}, 选择的Provider: {}",
         client_type, selected_provider
@@ -718,8 +949,8 @@ pub async fn chat_completions(
     state.logs.write().await.add(
         "info",
         &format!(
-            "[ROUTE] request_id={} model={} provider={}",
-            ctx.request_id, ctx.resolved_model, selected_provider
+            "[ROUTE] request_id={} model={} provider={} source={}",
+            ctx.request_id, ctx.resolved_model, selected_provider, selection_source
         ),
     );
 
@@ -729,6 +960,42 @@ pub async fn chat_completions(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_lowercase());
 
+    ctx.record_step(TraceStepKind::ProviderSelected {
+        provider: provider_id_header
+            .clone()
+            .unwrap_or_else(|| selected_provider.clone()),
+        reason: match provider_id_header {
+            Some(_) => "x-provider-id header".to_string(),
+            None => format!("client_type={} source={}", client_type, selection_source),
+        },
+    });
+
+    // 响应缓存：确定性的非流式请求命中时直接返回（基准测试请求不使用缓存）
+    let cache_provider = provider_id_header
+        .clone()
+        .unwrap_or_else(|| selected_provider.clone());
+    let mut cache_key = if ctx.benchmark {
+        None
+    } else {
+        state
+            .processor
+            .response_cache
+            .cache_key(&cache_provider, &request)
+    };
+    if let Some(ref key) = cache_key {
+        if let Some(cached) = state.processor.response_cache.get(key) {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[CACHE] request_id={} model={} 命中响应缓存",
+                    ctx.request_id, request.model
+                ),
+            );
+            ctx.record_step(TraceStepKind::CacheHit);
+            return cached_response_into_response(cached, "HIT");
+        }
+    }
+
     // 尝试从凭证池中选择凭证
     // 如果指定了 X-Provider-Id，优先使用它（不降级）
     // 否则使用 selected_provider
@@ -759,45 +1026,45 @@ pub async fn chat_completions(
                             explicit_provider_id
                         ),
                     );
+                    ctx.record_step(TraceStepKind::Failed {
+                        error: format!("provider '{}' 没有可用凭证", explicit_provider_id),
+                    });
                     // 返回错误，不降级
-                    return (
-                        StatusCode::SERVICE_UNAVAILABLE,
-                        Json(json!({
-                            "error": {
-                                "message": format!("No available credentials for provider '{}'", explicit_provider_id),
-                                "type": "provider_unavailable",
-                                "code": "no_credentials"
-                            }
-                        })),
-                    )
-                        .into_response();
+                    let error = ProcessError::NoCredential(format!(
+                        "No available credentials for provider '{}'",
+                        explicit_provider_id
+                    ));
+                    dead_letter::record_no_credential(
+                        &state,
+                        &explicit_provider_id,
+                        "/v1/chat/completions",
+                        &request.model,
+                        &request,
+                        &error,
+                    );
+                    return error.into_response_for(ErrorFormat::OpenAI);
                 }
                 cred
             } else {
-                // 使用 selected_provider（从 API Server 配置中获取）
+                // 使用 selected_provider（从 API Server 配置中获取），无可用凭证时按降级链
+                // 和 API Key Provider 智能降级
                 eprintln!(
-                    "[CHAT_COMPLETIONS] 尝试从凭证池选择: provider={}, model={}",
+                    "[CHAT_COMPLETIONS] 尝试选择凭证（含降级）: provider={}, model={}",
                     selected_provider, request.model
                 );
-                let cred = state
-                    .pool_service
-                    .select_credential(db, &selected_provider, Some(&request.model))
-                    .ok()
-                    .flatten();
-
-                if cred.is_some() {
-                    eprintln!(
-                        "[CHAT_COMPLETIONS] 找到凭证: provider={}",
-                        selected_provider
-                    );
-                } else {
-                    eprintln!(
-                        "[CHAT_COMPLETIONS] 未找到凭证: provider={}",
-                        selected_provider
-                    );
+                match state.pool_service.select_credential_with_fallback(
+                    db,
+                    &state.api_key_service,
+                    &selected_provider,
+                    Some(&request.model),
+                    Some(&selected_provider.to_lowercase()),
+                ) {
+                    Ok(cred) => cred,
+                    Err(e) => {
+                        eprintln!("[CHAT_COMPLETIONS] 选择凭证失败: {}", e);
+                        None
+                    }
                 }
-
-                cred
             }
         }
         None => {
@@ -805,155 +1072,46 @@ pub async fn chat_completions(
             None
         }
     };
+    let credential_source = match &credential {
+        Some(cred) if cred.uuid.starts_with("fallback-") => "api_key_provider",
+        _ => "pool",
+    };
+    if credential_source == "api_key_provider" {
+        ctx.record_step(TraceStepKind::Failover {
+            from: "pool".to_string(),
+            to: credential_source.to_string(),
+            reason: "No available pool credential".to_string(),
+        });
+    }
 
-    // 如果 Provider Pool 中没有找到凭证，尝试从 API Key Provider 获取（智能降级）
-    let credential = if credential.is_none() {
-        eprintln!("[CHAT_COMPLETIONS] Provider Pool 中未找到凭证，尝试 API Key Provider...");
-
-        use crate::database::dao::api_key_provider::ApiProviderType;
-        let provider_id_lower = selected_provider.to_lowercase();
-
-        // 策略 1: 优先按 provider_id 直接查找（支持 deepseek, moonshot 等 60+ Provider）
-        // 这些 Provider 在 API Key Provider 中有独立配置
-        let mut found_credential: Option<crate::models::provider_pool_model::ProviderCredential> =
-            None;
-
-        if let Some(db) = &state.db {
-            // 先尝试按 provider_id 直接查找
-            eprintln!(
-                "[CHAT_COMPLETIONS] 尝试按 provider_id '{}' 直接查找凭证",
-                provider_id_lower
-            );
-
-            match state.api_key_service.get_fallback_credential(
-                db,
-                &crate::models::provider_pool_model::PoolProviderType::OpenAI,
-                Some(&provider_id_lower),
-            ) {
-                Ok(Some(cred)) => {
-                    eprintln!(
-                        "[CHAT_COMPLETIONS] 通过 provider_id '{}' 找到凭证: name={:?}",
-                        provider_id_lower, cred.name
-                    );
-                    state.logs.write().await.add(
-                        "info",
-                        &format!(
-                            "[ROUTE] Using API Key Provider credential by provider_id: {}",
-                            provider_id_lower
-                        ),
-                    );
-                    found_credential = Some(cred);
-                }
-                Ok(None) => {
-                    eprintln!(
-                        "[CHAT_COMPLETIONS] provider_id '{}' 未找到凭证，尝试按类型查找",
-                        provider_id_lower
-                    );
-                }
-                Err(e) => {
-                    eprintln!("[CHAT_COMPLETIONS] 按 provider_id 查找凭证失败: {}", e);
-                }
-            }
-
-            // 策略 2: 如果按 provider_id 未找到，按类型查找
-            if found_credential.is_none() {
-                let api_provider_type = match provider_id_lower.as_str() {
-                    "anthropic" | "claude" => Some(ApiProviderType::Anthropic),
-                    "openai" => Some(ApiProviderType::Openai),
-                    "gemini" => Some(ApiProviderType::Gemini),
-                    // 以下都是 OpenAI 兼容的 Provider，但优先按 provider_id 查找已在上面处理
-                    "deepseek" | "moonshot" | "groq" | "grok" | "mistral" | "perplexity"
-                    | "cohere" | "openrouter" | "silicon" => Some(ApiProviderType::Openai),
-                    _ => None,
-                };
-
-                if let Some(api_type) = api_provider_type {
-                    eprintln!(
-                        "[CHAT_COMPLETIONS] 尝试从 API Key Provider 类型 '{:?}' 获取凭证",
-                        api_type
-                    );
-
-                    match state.api_key_service.get_next_api_key_by_type(db, api_type) {
-                        Ok(Some((_key_id, api_key, provider_info))) => {
-                            eprintln!(
-                                "[CHAT_COMPLETIONS] 从 API Key Provider 获取到凭证: provider={}, api_host={}",
-                                provider_info.name,
-                                provider_info.api_host
-                            );
-
-                            let base_url = if provider_info.api_host.is_empty() {
-                                None
-                            } else {
-                                Some(provider_info.api_host.clone())
-                            };
-
-                            let provider_type = match provider_info.provider_type {
-                                ApiProviderType::Anthropic => crate::ProviderType::Anthropic,
-                                ApiProviderType::Openai | ApiProviderType::OpenaiResponse => {
-                                    crate::ProviderType::OpenAI
-                                }
-                                ApiProviderType::Gemini => crate::ProviderType::GeminiApiKey,
-                                _ => crate::ProviderType::OpenAI,
-                            };
-
-                            let credential_data = match provider_type {
-                                crate::ProviderType::Anthropic => {
-                                    crate::models::provider_pool_model::CredentialData::AnthropicKey {
-                                        api_key: api_key.clone(),
-                                        base_url,
-                                    }
-                                }
-                                crate::ProviderType::GeminiApiKey => {
-                                    crate::models::provider_pool_model::CredentialData::GeminiApiKey {
-                                        api_key: api_key.clone(),
-                                        base_url,
-                                        excluded_models: vec![],
-                                    }
-                                }
-                                _ => crate::models::provider_pool_model::CredentialData::OpenAIKey {
-                                    api_key: api_key.clone(),
-                                    base_url,
-                                },
-                            };
-
-                            let mut cred =
-                                crate::models::provider_pool_model::ProviderCredential::new(
-                                    provider_type,
-                                    credential_data,
-                                );
-                            cred.name = Some(provider_info.name.clone());
-
-                            state.logs.write().await.add(
-                                "info",
-                                &format!(
-                                    "[ROUTE] Using API Key Provider credential: provider={}, type={:?}",
-                                    provider_info.name, provider_info.provider_type
-                                ),
-                            );
-
-                            found_credential = Some(cred);
-                        }
-                        Ok(None) => {
-                            eprintln!(
-                                "[CHAT_COMPLETIONS] API Key Provider 类型 '{:?}' 没有可用的 API Key",
-                                api_type
-                            );
-                        }
-                        Err(e) => {
-                            eprintln!("[CHAT_COMPLETIONS] 从 API Key Provider 获取凭证失败: {}", e);
-                        }
-                    }
-                }
+    // 凭证降级后仍无可用凭证时，按 routing.model_fallbacks 改用备选模型
+    let mut model_substitution = None;
+    let credential = match credential {
+        Some(cred) => Some(cred),
+        None => match model_fallback::select(&state, &mut ctx, &selected_provider).await {
+            Some((cred, substitution)) => {
+                request.model = substitution.model.clone();
+                cache_key = recompute_cache_key(
+                    &state.processor.response_cache,
+                    cache_key,
+                    &cache_provider,
+                    &request,
+                );
+                model_substitution = Some(substitution);
+                Some(cred)
             }
-        }
-
-        found_credential
-    } else {
-        credential
+            None => None,
+        },
     };
 
     // 如果找到凭证池中的凭证，使用它
     if let Some(cred) = credential {
+        ctx.record_step(TraceStepKind::CredentialSelected {
+            provider: cred.provider_type.to_string(),
+            credential_id: Some(cred.uuid.clone()),
+            credential_name: cred.name.clone(),
+            source: credential_source.to_string(),
+        });
         eprintln!(
             "[CHAT_COMPLETIONS] 使用凭证: type={}, name={:?}, uuid={}",
             cred.provider_type,
@@ -1031,8 +1189,92 @@ pub async fn chat_completions(
             }
         }
 
+        // 影子测试：按采样比例在后台复制请求到影子凭证（基准测试请求除外）
+        if !ctx.benchmark {
+            shadow::spawn_openai(&state, &request, &cred.uuid, flow_id.as_deref());
+        }
+
         eprintln!("[CHAT_COMPLETIONS] 调用 Provider: {}", cred.provider_type);
-        let response = call_provider_openai(&state, &cred, &request, flow_id.as_deref()).await;
+        let dropped = drop_unsupported_penalty_params(&cred, &mut request);
+        if !dropped.is_empty() {
+            ctx.record_step(TraceStepKind::ParamsDropped {
+                provider: cred.provider_type.to_string(),
+                params: dropped.iter().map(|p| p.to_string()).collect(),
+            });
+        }
+        let provider = cred.provider_type.to_string();
+        if let Some(truncation) = tool_result_truncation::apply_openai(&provider, &mut request) {
+            record_tool_result_truncation(&state, &mut ctx, provider, truncation).await;
+        }
+        let client_anthropic = ClientAnthropicHeaders::from_headers(&headers);
+        record_anthropic_headers(&mut ctx, &cred, &client_anthropic);
+        let upstream_timer = UpstreamTimer::start();
+        let max_cost_usd = max_cost_from_headers(&headers);
+        let (result, steps) = with_step_capture(with_client_anthropic_headers(
+            client_anthropic,
+            retry_policy::call_with_retry(request.stream, || {
+                call_provider_openai(&state, &cred, &request, flow_id.as_deref(), max_cost_usd)
+            }),
+        ))
+        .await;
+        ctx.record_captured_steps(steps);
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                // 离线降级：上游连接失败时返回相同请求最近一次的缓存响应
+                let stale = cache_key
+                    .as_deref()
+                    .filter(|_| e.is_connection_failure())
+                    .and_then(|key| state.processor.response_cache.get_stale(key));
+                if let Some(cached) = stale {
+                    state.logs.write().await.add(
+                        "warn",
+                        &format!(
+                            "[CACHE] request_id={} model={} 上游连接失败，返回过期缓存: {}",
+                            ctx.request_id, request.model, e
+                        ),
+                    );
+                    ctx.record_step(TraceStepKind::CacheHit);
+                    // Flow、统计和死信记录真实的上游失败，客户端收到过期缓存
+                    if let Some(fid) = &flow_id {
+                        let error = FlowError::new(FlowErrorType::Network, &e.to_string());
+                        state.flow_monitor.fail_flow(fid, error).await;
+                    }
+                    ctx.upstream_elapsed_ms = Some(upstream_timer.elapsed_ms());
+                    state.pool_service.note_upstream_failure(
+                        &cred.uuid,
+                        e.status_code(),
+                        Some(&ctx.request_id),
+                        Some(&request.model),
+                    );
+                    record_request_telemetry(
+                        &state,
+                        &ctx,
+                        crate::telemetry::RequestStatus::Failed,
+                        Some(e.to_string()),
+                    );
+                    dead_letter::record(
+                        &state,
+                        &cred,
+                        "/v1/chat/completions",
+                        &request.model,
+                        &request,
+                        &e,
+                    );
+                    return cached_response_into_response(cached, "stale-offline");
+                }
+                dead_letter::record(
+                    &state,
+                    &cred,
+                    "/v1/chat/completions",
+                    &request.model,
+                    &request,
+                    &e,
+                );
+                e.into_response_for(ErrorFormat::OpenAI)
+            }
+        };
+        ctx.upstream_elapsed_ms = Some(upstream_timer.elapsed_ms());
         eprintln!(
             "[CHAT_COMPLETIONS] Provider 响应状态: {}",
             response.status()
@@ -1040,14 +1282,36 @@ pub async fn chat_completions(
 
         // 记录请求统计
         let is_success = response.status().is_success();
+        if let Some(elapsed_ms) = ctx.upstream_elapsed_ms.filter(|_| is_success) {
+            state.pool_service.record_latency(&cred.uuid, elapsed_ms);
+        }
+        state.pool_service.note_upstream_failure(
+            &cred.uuid,
+            response.status().as_u16(),
+            Some(&ctx.request_id),
+            Some(&request.model),
+        );
+        ctx.record_step(TraceStepKind::UpstreamResponse {
+            status_code: response.status().as_u16(),
+        });
         let status_code = response.status().as_u16();
         let status = if is_success {
             crate::telemetry::RequestStatus::Success
         } else {
             crate::telemetry::RequestStatus::Failed
         };
+        let response = stream_coalesce::apply(response);
+        let response = measure_response_bytes(&state, &mut ctx, response);
+        let response = state.active_streams.track(&ctx.request_id, response);
         record_request_telemetry(&state, &ctx, status, None);
 
+        // 缓存成功的确定性响应
+        let response = match cache_key {
+            Some(key) if is_success => store_cached_response(&state, key, response).await,
+            _ => response,
+        };
+        let response = model_fallback::annotate(response, model_substitution.as_ref());
+
         // 如果成功且需要 Flow 捕获，提取响应体内容和响应头
         // 注意：非流式响应需要读取 body，所以必须在这里处理
         if is_success && flow_id.is_some() && !request.stream {
@@ -1260,17 +1524,22 @@ pub async fn chat_completions(
                 selected_provider, client_type
             ),
         );
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(serde_json::json!({
-                "error": {
-                    "message": format!("没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。", selected_provider),
-                    "type": "no_credential_error",
-                    "code": "no_credential"
-                }
-            })),
-        )
-            .into_response();
+        ctx.record_step(TraceStepKind::Failed {
+            error: format!("没有找到可用的 '{}' 凭证", selected_provider),
+        });
+        let error = ProcessError::NoCredential(format!(
+            "No available credentials for provider '{}'. Please add credentials in the Provider Pool.",
+            selected_provider
+        ));
+        dead_letter::record_no_credential(
+            &state,
+            &selected_provider,
+            "/v1/chat/completions",
+            &request.model,
+            &request,
+            &error,
+        );
+        return error.into_response_for(ErrorFormat::OpenAI);
     }
 
     state.logs.write().await.add(
@@ -1435,6 +1704,8 @@ pub async fn chat_completions(
                             }
                         });
                         // 记录成功请求统计
+                        ctx.response_bytes =
+                            serde_json::to_vec(&response).map(|v| v.len() as u64).ok();
                         record_request_telemetry(
                             &state,
                             &ctx,
@@ -1564,6 +1835,11 @@ pub async fn chat_completions(
                             .await
                             .add("info", "[AUTH] Token refreshed successfully after reload");
                         // 重试请求
+                        ctx.record_step(TraceStepKind::Retry {
+                            attempt: 1,
+                            status_code: Some(status.as_u16()),
+                            error: status.canonical_reason().unwrap_or_default().to_string(),
+                        });
                         drop(kiro);
                         let kiro = state.kiro.read().await;
                         match kiro.call_api(&request).await {
@@ -1812,8 +2088,17 @@ pub async fn anthropic_messages(
         return e.into_response();
     }
 
+    // 携带 x-request-id 和 Last-Event-ID 的重连请求直接续传
+    if let Some(response) = stream_resume::resume_from_headers(&state, &headers) {
+        return response;
+    }
+
     // 创建请求上下文
-    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    let mut ctx = RequestContext::new(request.model.clone())
+        .with_stream(request.stream)
+        .with_request_bytes(request_body_bytes(&headers, &request))
+        .with_trace_store(state.processor.traces.clone());
+    ctx.benchmark = is_benchmark_request(&headers);
 
     // 详细记录请求信息
     let msg_count = request.messages.len();
@@ -1883,6 +2168,10 @@ pub async fn anthropic_messages(
                     ctx.request_id, result.applied_rules, result.injected_params
                 ),
             );
+            ctx.record_step(TraceStepKind::InjectionApplied {
+                applied_rules: result.applied_rules.clone(),
+                injected_params: result.injected_params.clone(),
+            });
             // 更新请求
             if let Ok(updated) = serde_json::from_value(payload) {
                 request = updated;
@@ -1890,9 +2179,60 @@ pub async fn anthropic_messages(
         }
     }
 
+    // 终端用户标识（限流与配额由 UserLimitsLayer 在进入处理器前检查）
+    ctx.user_id = request.user_id().and_then(user_limits::user_key);
+
+    // 检查工具数量与大小，超限时在调用上游前返回 400
+    match request_limits::enforce_anthropic(&mut request) {
+        Ok(0) => {}
+        Ok(truncated) => {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[LIMITS] request_id={} 截断了 {} 个工具描述",
+                    ctx.request_id, truncated
+                ),
+            );
+        }
+        Err(e) => {
+            state.logs.write().await.add(
+                "warn",
+                &format!("[LIMITS] request_id={} {}", ctx.request_id, e),
+            );
+            return e.into_response_for(ErrorFormat::Anthropic);
+        }
+    }
+
+    // 按模型配置填充或截断 max_tokens
+    if let Some((original, adjusted)) =
+        request_limits::adjust_max_tokens(&request.model, &mut request.max_tokens)
+    {
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[LIMITS] request_id={} model={} max_tokens {:?} -> {}",
+                ctx.request_id, request.model, original, adjusted
+            ),
+        );
+    }
+
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
-    let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    let (selected_provider, client_type, selection_source) =
+        select_provider_for_client(&headers, &state).await;
+
+    // 端点配置了强制非流式时，缓冲上游响应后一次性返回
+    if request.stream && is_force_non_streaming(&state, client_type).await {
+        request.stream = false;
+        ctx.is_stream = false;
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[STREAM] request_id={} client_type={} 强制非流式",
+                ctx.request_id, client_type
+            ),
+        );
+    }
 
     // 记录客户端检测和 Provider 选择结果
     state.logs.write().await.add(
@@ -1907,8 +2247,8 @@ pub async fn anthropic_messages(
     state.logs.write().await.add(
         "info",
         &format!(
-            "[ROUTE] request_id={} model={} provider={}",
-            ctx.request_id, ctx.resolved_model, selected_provider
+            "[ROUTE] request_id={} model={} provider={} source={}",
+            ctx.request_id, ctx.resolved_model, selected_provider, selection_source
         ),
     );
 
@@ -1918,6 +2258,16 @@ pub async fn anthropic_messages(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_lowercase());
 
+    ctx.record_step(TraceStepKind::ProviderSelected {
+        provider: provider_id_header
+            .clone()
+            .unwrap_or_else(|| selected_provider.clone()),
+        reason: match provider_id_header {
+            Some(_) => "x-provider-id header".to_string(),
+            None => format!("client_type={} source={}", client_type, selection_source),
+        },
+    });
+
     // 尝试从凭证池中选择凭证
     // 如果指定了 X-Provider-Id，优先使用它（不降级）
     // 否则使用 selected_provider
@@ -1947,44 +2297,45 @@ pub async fn anthropic_messages(
                             explicit_provider_id
                         ),
                     );
+                    ctx.record_step(TraceStepKind::Failed {
+                        error: format!("provider '{}' 没有可用凭证", explicit_provider_id),
+                    });
                     // 返回错误，不降级
-                    return (
-                        StatusCode::SERVICE_UNAVAILABLE,
-                        Json(json!({
-                            "error": {
-                                "type": "provider_unavailable",
-                                "message": format!("No available credentials for provider '{}'", explicit_provider_id)
-                            }
-                        })),
-                    )
-                        .into_response();
+                    let error = ProcessError::NoCredential(format!(
+                        "No available credentials for provider '{}'",
+                        explicit_provider_id
+                    ));
+                    dead_letter::record_no_credential(
+                        &state,
+                        &explicit_provider_id,
+                        "/v1/messages",
+                        &request.model,
+                        &request,
+                        &error,
+                    );
+                    return error.into_response_for(ErrorFormat::Anthropic);
                 }
                 cred
             } else {
-                // 使用 selected_provider（从 API Server 配置中获取）
+                // 使用 selected_provider（从 API Server 配置中获取），无可用凭证时按降级链
+                // 和 API Key Provider 智能降级
                 eprintln!(
-                    "[ANTHROPIC_MESSAGES] 尝试从凭证池选择: provider={}, model={}",
+                    "[ANTHROPIC_MESSAGES] 尝试选择凭证（含降级）: provider={}, model={}",
                     selected_provider, request.model
                 );
-                let cred = state
-                    .pool_service
-                    .select_credential(db, &selected_provider, Some(&request.model))
-                    .ok()
-                    .flatten();
-
-                if cred.is_some() {
-                    eprintln!(
-                        "[ANTHROPIC_MESSAGES] 找到凭证: provider={}",
-                        selected_provider
-                    );
-                } else {
-                    eprintln!(
-                        "[ANTHROPIC_MESSAGES] 未找到凭证: provider={}",
-                        selected_provider
-                    );
+                match state.pool_service.select_credential_with_fallback(
+                    db,
+                    &state.api_key_service,
+                    &selected_provider,
+                    Some(&request.model),
+                    Some(&selected_provider.to_lowercase()),
+                ) {
+                    Ok(cred) => cred,
+                    Err(e) => {
+                        eprintln!("[ANTHROPIC_MESSAGES] 选择凭证失败: {}", e);
+                        None
+                    }
                 }
-
-                cred
             }
         }
         None => {
@@ -1992,52 +2343,40 @@ pub async fn anthropic_messages(
             None
         }
     };
+    let credential_source = match &credential {
+        Some(cred) if cred.uuid.starts_with("fallback-") => "api_key_provider",
+        _ => "pool",
+    };
+    if credential_source == "api_key_provider" {
+        ctx.record_step(TraceStepKind::Failover {
+            from: "pool".to_string(),
+            to: credential_source.to_string(),
+            reason: "No available pool credential".to_string(),
+        });
+    }
 
-    // 如果 Provider Pool 中没有找到凭证，尝试从 API Key Provider 获取（智能降级）
-    let credential = if credential.is_none() {
-        eprintln!("[ANTHROPIC_MESSAGES] Provider Pool 中未找到凭证，尝试 API Key Provider...");
-
-        // 策略 1: 优先按 provider_id 直接查找（支持自定义 Provider）
-        let mut found_credential: Option<crate::models::provider_pool_model::ProviderCredential> =
-            None;
-
-        if let Some(db) = &state.db {
-            eprintln!(
-                "[ANTHROPIC_MESSAGES] 尝试按 provider_id '{}' 直接查找凭证",
-                selected_provider
-            );
-
-            match state.api_key_service.get_fallback_credential(
-                db,
-                &crate::models::provider_pool_model::PoolProviderType::Anthropic,
-                Some(&selected_provider),
-            ) {
-                Ok(Some(cred)) => {
-                    eprintln!(
-                        "[ANTHROPIC_MESSAGES] 通过 provider_id '{}' 找到凭证: name={:?}",
-                        selected_provider, cred.name
-                    );
-                    found_credential = Some(cred);
-                }
-                Ok(None) => {
-                    eprintln!(
-                        "[ANTHROPIC_MESSAGES] provider_id '{}' 未找到凭证",
-                        selected_provider
-                    );
-                }
-                Err(e) => {
-                    eprintln!("[ANTHROPIC_MESSAGES] 查找凭证时出错: {}", e);
-                }
+    // 凭证降级后仍无可用凭证时，按 routing.model_fallbacks 改用备选模型
+    let mut model_substitution = None;
+    let credential = match credential {
+        Some(cred) => Some(cred),
+        None => match model_fallback::select(&state, &mut ctx, &selected_provider).await {
+            Some((cred, substitution)) => {
+                request.model = substitution.model.clone();
+                model_substitution = Some(substitution);
+                Some(cred)
             }
-        }
-
-        found_credential
-    } else {
-        credential
+            None => None,
+        },
     };
 
     // 如果找到凭证池中的凭证，使用它
     if let Some(cred) = credential {
+        ctx.record_step(TraceStepKind::CredentialSelected {
+            provider: cred.provider_type.to_string(),
+            credential_id: Some(cred.uuid.clone()),
+            credential_name: cred.name.clone(),
+            source: credential_source.to_string(),
+        });
         state.logs.write().await.add(
             "info",
             &format!(
@@ -2114,15 +2453,63 @@ pub async fn anthropic_messages(
             }
         }
 
-        let response = call_provider_anthropic(&state, &cred, &request, flow_id.as_deref()).await;
+        // 影子测试：按采样比例在后台复制请求到影子凭证（基准测试请求除外）
+        if !ctx.benchmark {
+            shadow::spawn_anthropic(&state, &request, &cred.uuid, flow_id.as_deref());
+        }
+
+        let provider = cred.provider_type.to_string();
+        if let Some(truncation) = tool_result_truncation::apply_anthropic(&provider, &mut request) {
+            record_tool_result_truncation(&state, &mut ctx, provider, truncation).await;
+        }
+        let client_anthropic = ClientAnthropicHeaders::from_headers(&headers);
+        record_anthropic_headers(&mut ctx, &cred, &client_anthropic);
+        let upstream_timer = UpstreamTimer::start();
+        let max_cost_usd = max_cost_from_headers(&headers);
+        let (result, steps) = with_step_capture(with_client_anthropic_headers(
+            client_anthropic,
+            retry_policy::call_with_retry(request.stream, || {
+                call_provider_anthropic(&state, &cred, &request, flow_id.as_deref(), max_cost_usd)
+            }),
+        ))
+        .await;
+        ctx.record_captured_steps(steps);
+        let response = result.unwrap_or_else(|e| {
+            dead_letter::record(&state, &cred, "/v1/messages", &request.model, &request, &e);
+            e.into_response_for(ErrorFormat::Anthropic)
+        });
+        ctx.upstream_elapsed_ms = Some(upstream_timer.elapsed_ms());
+        // 上游返回的实际用量（Claude / Anthropic 非流式响应）
+        let upstream_usage = response
+            .extensions()
+            .get::<crate::telemetry::AnthropicUsage>()
+            .copied();
+        // 透传的 Anthropic SSE 流在流结束时记录实际用量
+        let (response, streamed_usage) = record_stream_anthropic_usage(&state, &ctx, response);
 
         // 记录请求统计
         let is_success = response.status().is_success();
+        if let Some(elapsed_ms) = ctx.upstream_elapsed_ms.filter(|_| is_success) {
+            state.pool_service.record_latency(&cred.uuid, elapsed_ms);
+        }
+        state.pool_service.note_upstream_failure(
+            &cred.uuid,
+            response.status().as_u16(),
+            Some(&ctx.request_id),
+            Some(&request.model),
+        );
+        ctx.record_step(TraceStepKind::UpstreamResponse {
+            status_code: response.status().as_u16(),
+        });
         let status = if is_success {
             crate::telemetry::RequestStatus::Success
         } else {
             crate::telemetry::RequestStatus::Failed
         };
+        let response = stream_coalesce::apply(response);
+        let response = measure_response_bytes(&state, &mut ctx, response);
+        let response = state.active_streams.track(&ctx.request_id, response);
+        let response = model_fallback::annotate(response, model_substitution.as_ref());
         record_request_telemetry(&state, &ctx, status, None);
 
         // 估算 Token 使用量
@@ -2145,12 +2532,16 @@ pub async fn anthropic_messages(
         let estimated_output_tokens = if is_success { 100u32 } else { 0u32 };
 
         if is_success {
-            record_token_usage(
-                &state,
-                &ctx,
-                Some(estimated_input_tokens),
-                Some(estimated_output_tokens),
-            );
+            match &upstream_usage {
+                Some(usage) => record_anthropic_usage(&state, &ctx, usage),
+                None if streamed_usage => {}
+                None => record_token_usage(
+                    &state,
+                    &ctx,
+                    Some(estimated_input_tokens),
+                    Some(estimated_output_tokens),
+                ),
+            }
         }
 
         // 完成 Flow 捕获并检查响应拦截
@@ -2237,17 +2628,22 @@ pub async fn anthropic_messages(
                 selected_provider, client_type
             ),
         );
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(serde_json::json!({
-                "type": "error",
-                "error": {
-                    "type": "no_credential_error",
-                    "message": format!("没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。", selected_provider)
-                }
-            })),
-        )
-            .into_response();
+        ctx.record_step(TraceStepKind::Failed {
+            error: format!("没有找到可用的 '{}' 凭证", selected_provider),
+        });
+        let error = ProcessError::NoCredential(format!(
+            "No available credentials for provider '{}'. Please add credentials in the Provider Pool.",
+            selected_provider
+        ));
+        dead_letter::record_no_credential(
+            &state,
+            &selected_provider,
+            "/v1/messages",
+            &request.model,
+            &request,
+            &error,
+        );
+        return error.into_response_for(ErrorFormat::Anthropic);
     }
 
     state.logs.write().await.add(
@@ -2602,6 +2998,11 @@ pub async fn anthropic_messages(
                             "info",
                             "[AUTH] Token refreshed successfully, retrying request...",
                         );
+                        ctx.record_step(TraceStepKind::Retry {
+                            attempt: 1,
+                            status_code: Some(status.as_u16()),
+                            error: status.canonical_reason().unwrap_or_default().to_string(),
+                        });
                         drop(kiro);
                         let kiro = state.kiro.read().await;
                         match kiro.call_api(&openai_request).await {
@@ -3024,7 +3425,11 @@ fn build_api_key_headers(
     match provider_type {
         ApiProviderType::Anthropic => {
             headers.insert("x-api-key".to_string(), api_key.to_string());
-            headers.insert("anthropic-version".to_string(), "2023-06-01".to_string());
+            let anthropic = crate::http_client::anthropic_headers();
+            headers.insert("anthropic-version".to_string(), anthropic.version);
+            if let Some(beta) = anthropic.beta {
+                headers.insert("anthropic-beta".to_string(), beta);
+            }
         }
         ApiProviderType::Gemini => {
             headers.insert("x-goog-api-key".to_string(), api_key.to_string());
@@ -3125,6 +3530,11 @@ fn convert_openai_to_anthropic(request: &ChatCompletionRequest) -> serde_json::V
         );
     }
 
+    let stop_sequences = request.stop_sequences();
+    if !stop_sequences.is_empty() {
+        result["stop_sequences"] = serde_json::json!(stop_sequences);
+    }
+
     result
 }
 
@@ -3166,3 +3576,96 @@ fn convert_anthropic_response_to_openai(anthropic_resp: &serde_json::Value, mode
 
     serde_json::to_string(&openai_resp).unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn response_json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn headers(api_key_header: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::HeaderName::from_bytes(api_key_header.as_bytes()).unwrap(),
+            "test-key".parse().unwrap(),
+        );
+        headers.insert("x-provider-id", "openai".parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_benchmark_header_requires_process_token() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_benchmark_request(&headers));
+        headers.insert(crate::server::BENCHMARK_HEADER, "1".parse().unwrap());
+        assert!(!is_benchmark_request(&headers));
+        headers.insert(
+            crate::server::BENCHMARK_HEADER,
+            crate::server::benchmark_token().parse().unwrap(),
+        );
+        assert!(is_benchmark_request(&headers));
+    }
+
+    #[test]
+    fn test_recompute_cache_key_after_model_substitution() {
+        let cache = crate::processor::ResponseCache::new(crate::processor::ResponseCacheConfig {
+            enabled: true,
+            ..Default::default()
+        });
+        let mut request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-opus-4-5",
+            "messages": [{"role": "user", "content": "hi"}],
+            "temperature": 0.0
+        }))
+        .unwrap();
+        let original = cache.cache_key("kiro", &request);
+        assert!(original.is_some());
+
+        request.model = "claude-sonnet-4-5".to_string();
+        let substituted = recompute_cache_key(&cache, original.clone(), "kiro", &request);
+        assert!(substituted.is_some());
+        assert_ne!(substituted, original);
+        assert_eq!(substituted, cache.cache_key("kiro", &request));
+
+        // 原请求不缓存时（如基准测试请求）替换后同样不缓存
+        assert_eq!(recompute_cache_key(&cache, None, "kiro", &request), None);
+    }
+
+    #[tokio::test]
+    async fn test_chat_completions_without_credentials_returns_no_credential() {
+        let state = AppState::for_tests("test-key");
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "hi"}]
+        }))
+        .unwrap();
+
+        let response = chat_completions(State(state), headers("x-api-key"), Json(request)).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = response_json(response).await;
+        assert_eq!(body["error"]["type"], "no_credential");
+        assert_eq!(body["error"]["code"], 503);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_messages_without_credentials_returns_overloaded() {
+        let state = AppState::for_tests("test-key");
+        let request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 16,
+            "messages": [{"role": "user", "content": "hi"}]
+        }))
+        .unwrap();
+
+        let response = anthropic_messages(State(state), headers("x-api-key"), Json(request)).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = response_json(response).await;
+        assert_eq!(body["type"], "error");
+        assert_eq!(body["error"]["type"], "overloaded_error");
+    }
+}
@@ -0,0 +1,490 @@
+//! Anthropic Message Batches 透传处理器
+//!
+//! 实现 `/v1/messages/batches` 系列端点（创建 / 列表 / 查询 / 结果 / 取消），
+//! 原样转发到支持 Anthropic 原生协议的凭证（ClaudeKey / AnthropicKey / ClaudeOAuth）。
+//!
+//! 批次属于具体账号，创建时记录 batch_id -> 凭证 UUID，
+//! 后续查询和获取结果使用同一凭证；未记录的批次回退为按默认 Provider 选择凭证。
+//! 批次提交在遥测中记录为 `RequestKind::Batch`，与同步请求区分。
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::processor::{ErrorFormat, ProcessError};
+use crate::providers::claude_custom::CLAUDE_OAUTH_BETA;
+use crate::server::handlers::verify_api_key_anthropic;
+use crate::server::AppState;
+use crate::telemetry::{RequestKind, RequestLog};
+use crate::ProviderType;
+
+/// 最多记录的批次数量
+const MAX_TRACKED_BATCHES: usize = 1000;
+
+/// 已提交批次的记录
+#[derive(Debug, Clone)]
+pub struct BatchRecord {
+    /// 提交批次使用的凭证 UUID
+    pub credential_uuid: String,
+    /// 提交时间
+    pub created_at: DateTime<Utc>,
+    /// 批次内请求数
+    pub request_count: usize,
+}
+
+/// 批次 ID 到凭证的映射
+#[derive(Debug, Default)]
+pub struct BatchRegistry {
+    batches: Mutex<HashMap<String, BatchRecord>>,
+}
+
+impl BatchRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录新提交的批次，超出容量时淘汰最早的记录
+    pub fn insert(&self, batch_id: String, record: BatchRecord) {
+        let mut batches = self.batches.lock();
+        if batches.len() >= MAX_TRACKED_BATCHES && !batches.contains_key(&batch_id) {
+            if let Some(oldest) = batches
+                .iter()
+                .min_by_key(|(_, r)| r.created_at)
+                .map(|(id, _)| id.clone())
+            {
+                batches.remove(&oldest);
+            }
+        }
+        batches.insert(batch_id, record);
+    }
+
+    /// 查询批次记录
+    pub fn get(&self, batch_id: &str) -> Option<BatchRecord> {
+        self.batches.lock().get(batch_id).cloned()
+    }
+}
+
+/// 上游认证方式
+enum BatchAuth {
+    ApiKey(String),
+    Bearer(String),
+}
+
+/// 批次请求的上游目标
+struct BatchUpstream {
+    credential: ProviderCredential,
+    base_url: String,
+    auth: BatchAuth,
+    provider: ProviderType,
+}
+
+impl BatchUpstream {
+    /// 构建 `/v1/messages/batches` 下的完整 URL（兼容 base_url 带或不带 /v1）
+    fn url(&self, suffix: &str) -> String {
+        let base = self.base_url.trim_end_matches('/');
+        let base = base.strip_suffix("/v1").unwrap_or(base);
+        format!("{}/v1/messages/batches{}", base, suffix)
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        suffix: &str,
+        query: &HashMap<String, String>,
+        body: Option<&serde_json::Value>,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let client = crate::http_client::client_for("claude");
+        let request = client.request(method, self.url(suffix)).query(query);
+        let mut headers = crate::http_client::anthropic_headers();
+        let mut request = match &self.auth {
+            BatchAuth::ApiKey(key) => request.header("x-api-key", key),
+            BatchAuth::Bearer(token) => {
+                headers.add_beta(CLAUDE_OAUTH_BETA);
+                request.bearer_auth(token)
+            }
+        };
+        request = headers.apply(request);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        request.send().await
+    }
+}
+
+fn error_response(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+/// 解析批次请求使用的凭证
+///
+/// 已记录的批次使用提交时的凭证，否则按默认 Provider 选择凭证；
+/// 凭证不支持 Anthropic 原生协议时返回 400。
+async fn resolve_upstream(
+    state: &AppState,
+    batch_id: Option<&str>,
+) -> Result<BatchUpstream, Response> {
+    let Some(db) = &state.db else {
+        return Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "api_error",
+            "Database not available".to_string(),
+        ));
+    };
+
+    let tracked = batch_id
+        .and_then(|id| state.batches.get(id))
+        .and_then(|record| {
+            tracing::debug!(
+                "[BATCH] 使用提交时的凭证 credential_uuid={} requests={} created_at={}",
+                record.credential_uuid,
+                record.request_count,
+                record.created_at
+            );
+            state
+                .pool_service
+                .get_by_uuid(db, &record.credential_uuid)
+                .ok()
+                .flatten()
+        });
+
+    let credential = match tracked {
+        Some(credential) => credential,
+        None => {
+            let provider = state.default_provider.read().await.clone();
+            match state.pool_service.select_credential(db, &provider, None) {
+                Ok(Some(credential)) => credential,
+                Ok(None) => {
+                    return Err(ProcessError::NoCredential(format!(
+                        "No available credentials for provider '{}'",
+                        provider
+                    ))
+                    .into_response_for(ErrorFormat::Anthropic))
+                }
+                Err(e) => {
+                    return Err(error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "api_error",
+                        format!("Failed to select credential: {}", e),
+                    ))
+                }
+            }
+        }
+    };
+
+    let (base_url, auth, provider) = match &credential.credential {
+        CredentialData::ClaudeKey { api_key, base_url } => (
+            base_url.clone(),
+            BatchAuth::ApiKey(api_key.clone()),
+            ProviderType::Claude,
+        ),
+        CredentialData::AnthropicKey { api_key, base_url } => (
+            base_url.clone(),
+            BatchAuth::ApiKey(api_key.clone()),
+            ProviderType::Anthropic,
+        ),
+        CredentialData::ClaudeOAuth { .. } => {
+            let token = state
+                .token_cache
+                .get_valid_token(db, &credential.uuid)
+                .await
+                .map_err(|e| {
+                    error_response(
+                        StatusCode::UNAUTHORIZED,
+                        "authentication_error",
+                        format!("Failed to get Claude OAuth token: {}", e),
+                    )
+                })?;
+            (None, BatchAuth::Bearer(token), ProviderType::ClaudeOAuth)
+        }
+        _ => {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                format!(
+                    "Provider {} does not support message batches",
+                    credential.provider_type
+                ),
+            ))
+        }
+    };
+
+    Ok(BatchUpstream {
+        credential,
+        base_url: base_url.unwrap_or_else(|| "https://api.anthropic.com".to_string()),
+        auth,
+        provider,
+    })
+}
+
+/// 将上游响应原样返回（保留状态码和 Content-Type，结果文件为 JSONL 流）
+fn passthrough(resp: reqwest::Response) -> Response {
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json")
+        .to_string();
+    Response::builder()
+        .status(status)
+        .header("content-type", content_type)
+        .body(Body::from_stream(resp.bytes_stream()))
+        .unwrap_or_else(|_| StatusCode::BAD_GATEWAY.into_response())
+}
+
+fn upstream_error(e: reqwest::Error) -> Response {
+    error_response(
+        StatusCode::BAD_GATEWAY,
+        "api_error",
+        format!("Upstream request failed: {}", e),
+    )
+}
+
+/// 创建批次 `POST /v1/messages/batches`
+pub async fn create_message_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+
+    let requests = body.get("requests").and_then(|r| r.as_array());
+    let request_count = requests.map_or(0, |r| r.len());
+    if request_count == 0 {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            "requests must be a non-empty array".to_string(),
+        );
+    }
+    let model = requests
+        .and_then(|r| r.first())
+        .and_then(|r| r.pointer("/params/model"))
+        .and_then(|m| m.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let upstream = match resolve_upstream(&state, None).await {
+        Ok(upstream) => upstream,
+        Err(resp) => return resp,
+    };
+
+    let start = std::time::Instant::now();
+    let mut log = RequestLog::new(
+        uuid::Uuid::new_v4().to_string(),
+        upstream.provider,
+        model,
+        false,
+    )
+    .with_kind(RequestKind::Batch);
+    log.set_credential_id(upstream.credential.uuid.clone());
+
+    let result = upstream
+        .send(reqwest::Method::POST, "", &HashMap::new(), Some(&body))
+        .await;
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    let response = match result {
+        Ok(resp) if resp.status().is_success() => {
+            let status = resp.status().as_u16();
+            match resp.json::<serde_json::Value>().await {
+                Ok(batch) => {
+                    if let Some(batch_id) = batch.get("id").and_then(|id| id.as_str()) {
+                        state.batches.insert(
+                            batch_id.to_string(),
+                            BatchRecord {
+                                credential_uuid: upstream.credential.uuid.clone(),
+                                created_at: Utc::now(),
+                                request_count,
+                            },
+                        );
+                        state.logs.write().await.add(
+                            "info",
+                            &format!(
+                                "[BATCH] 已提交批次 batch_id={} requests={} credential_uuid={}",
+                                batch_id,
+                                request_count,
+                                &upstream.credential.uuid[..8.min(upstream.credential.uuid.len())]
+                            ),
+                        );
+                    }
+                    log.mark_success(duration_ms, status);
+                    (StatusCode::OK, Json(batch)).into_response()
+                }
+                Err(e) => {
+                    log.mark_failed(duration_ms, Some(status), e.to_string());
+                    upstream_error(e)
+                }
+            }
+        }
+        Ok(resp) => {
+            let status = resp.status().as_u16();
+            log.mark_failed(
+                duration_ms,
+                Some(status),
+                format!("Upstream returned {}", status),
+            );
+            passthrough(resp)
+        }
+        Err(e) => {
+            log.mark_failed(duration_ms, None, e.to_string());
+            upstream_error(e)
+        }
+    };
+
+    record_batch_telemetry(&state, log);
+    response
+}
+
+/// 列出批次 `GET /v1/messages/batches`
+pub async fn list_message_batches(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    match resolve_upstream(&state, None).await {
+        Ok(upstream) => match upstream.send(reqwest::Method::GET, "", &query, None).await {
+            Ok(resp) => passthrough(resp),
+            Err(e) => upstream_error(e),
+        },
+        Err(resp) => resp,
+    }
+}
+
+/// 查询批次 `GET /v1/messages/batches/:batch_id`
+pub async fn get_message_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(batch_id): Path<String>,
+) -> Response {
+    forward_batch(&state, &headers, reqwest::Method::GET, &batch_id, "").await
+}
+
+/// 获取批次结果 `GET /v1/messages/batches/:batch_id/results`
+pub async fn get_message_batch_results(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(batch_id): Path<String>,
+) -> Response {
+    forward_batch(
+        &state,
+        &headers,
+        reqwest::Method::GET,
+        &batch_id,
+        "/results",
+    )
+    .await
+}
+
+/// 取消批次 `POST /v1/messages/batches/:batch_id/cancel`
+pub async fn cancel_message_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(batch_id): Path<String>,
+) -> Response {
+    forward_batch(
+        &state,
+        &headers,
+        reqwest::Method::POST,
+        &batch_id,
+        "/cancel",
+    )
+    .await
+}
+
+async fn forward_batch(
+    state: &AppState,
+    headers: &HeaderMap,
+    method: reqwest::Method,
+    batch_id: &str,
+    action: &str,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(headers, &state.api_key).await {
+        return e.into_response();
+    }
+    let upstream = match resolve_upstream(state, Some(batch_id)).await {
+        Ok(upstream) => upstream,
+        Err(resp) => return resp,
+    };
+    let suffix = format!("/{}{}", batch_id, action);
+    match upstream.send(method, &suffix, &HashMap::new(), None).await {
+        Ok(resp) => passthrough(resp),
+        Err(e) => upstream_error(e),
+    }
+}
+
+/// 记录批次提交到遥测系统
+fn record_batch_telemetry(state: &AppState, log: RequestLog) {
+    {
+        let stats = state.processor.stats.write();
+        stats.record(log.clone());
+    }
+    if let Some(logger) = &state.request_logger {
+        let _ = logger.record(log.clone());
+    }
+    tracing::info!(
+        "[TELEMETRY] batch request_id={} provider={:?} status={:?} duration_ms={}",
+        log.id,
+        log.provider,
+        log.status,
+        log.duration_ms
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(uuid: &str, secs_ago: i64) -> BatchRecord {
+        BatchRecord {
+            credential_uuid: uuid.to_string(),
+            created_at: Utc::now() - chrono::Duration::seconds(secs_ago),
+            request_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_registry_tracks_batch_credential() {
+        let registry = BatchRegistry::new();
+        registry.insert("msgbatch_1".to_string(), record("cred-a", 0));
+        assert_eq!(
+            registry.get("msgbatch_1").map(|r| r.credential_uuid),
+            Some("cred-a".to_string())
+        );
+        assert!(registry.get("msgbatch_2").is_none());
+    }
+
+    #[test]
+    fn test_registry_evicts_oldest_when_full() {
+        let registry = BatchRegistry::new();
+        for i in 0..MAX_TRACKED_BATCHES {
+            registry.insert(format!("b{}", i), record("cred", i as i64));
+        }
+        // b{MAX-1} 最早提交
+        registry.insert("new".to_string(), record("cred", 0));
+        assert!(registry.get("new").is_some());
+        assert!(registry
+            .get(&format!("b{}", MAX_TRACKED_BATCHES - 1))
+            .is_none());
+    }
+}
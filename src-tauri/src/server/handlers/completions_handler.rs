@@ -0,0 +1,335 @@
+//! 旧版文本补全 API 处理器
+//!
+//! 实现 OpenAI 兼容的 `/v1/completions` 端点：把 `prompt` 包装为单条 user 消息，
+//! 交给 `/v1/chat/completions` 的处理管道（路由、凭证选择、统计与对话补全相同），
+//! 再把响应转换为 `text_completion` 格式，流式响应逐个事件转换为 `text` 增量。
+//!
+//! 只支持单个字符串 prompt；`n` / `best_of` 大于 1、`echo`、`suffix`、`logprobs`
+//! 以及 token 数组形式的 prompt 返回 400。
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::BytesMut;
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::models::openai::{
+    ChatCompletionRequest, ChatMessage, CompletionRequest, MessageContent,
+};
+use crate::processor::{ErrorFormat, ProcessError};
+use crate::server::handlers::{chat_completions, verify_api_key};
+use crate::server::stream_resume::event_end;
+use crate::server::AppState;
+
+/// 非流式响应体的最大字节数
+const MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// 处理旧版文本补全请求
+///
+/// # 端点
+/// `POST /v1/completions`
+pub async fn legacy_completions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CompletionRequest>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        state
+            .logs
+            .write()
+            .await
+            .add("warn", "Unauthorized request to /v1/completions");
+        return e.into_response();
+    }
+
+    let chat_request = match to_chat_request(request) {
+        Ok(r) => r,
+        Err(message) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("warn", &format!("[COMPLETIONS] 不支持的请求: {}", message));
+            return ProcessError::InvalidRequest(message).into_response_for(ErrorFormat::OpenAI);
+        }
+    };
+
+    let response = chat_completions(State(state), headers, Json(chat_request)).await;
+    if !response.status().is_success() {
+        // 错误响应已经是 OpenAI 格式
+        return response;
+    }
+
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if is_sse {
+        convert_stream_response(response)
+    } else {
+        convert_json_response(response).await
+    }
+}
+
+/// 旧版补全请求 -> 对话补全请求
+///
+/// 不支持的参数返回错误信息。
+fn to_chat_request(request: CompletionRequest) -> Result<ChatCompletionRequest, String> {
+    if request.n.is_some_and(|n| n > 1) || request.best_of.is_some_and(|n| n > 1) {
+        return Err("n and best_of greater than 1 are not supported".to_string());
+    }
+    if request.echo {
+        return Err("echo is not supported".to_string());
+    }
+    if request.suffix.as_deref().is_some_and(|s| !s.is_empty()) {
+        return Err("suffix is not supported".to_string());
+    }
+    if request.logprobs.is_some() {
+        return Err("logprobs is not supported on /v1/completions".to_string());
+    }
+
+    let prompt = match request.prompt {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s,
+        Some(Value::Array(items)) => match items.as_slice() {
+            [] => String::new(),
+            [Value::String(s)] => s.clone(),
+            [Value::String(_), ..] => {
+                return Err(
+                    "multiple prompts are not supported, send one request per prompt".to_string(),
+                )
+            }
+            _ => return Err("token array prompts are not supported".to_string()),
+        },
+        Some(_) => return Err("prompt must be a string".to_string()),
+    };
+    if prompt.trim().is_empty() {
+        return Err("prompt is required and cannot be empty".to_string());
+    }
+
+    Ok(ChatCompletionRequest {
+        model: request.model,
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: Some(MessageContent::Text(prompt)),
+            tool_calls: None,
+            tool_call_id: None,
+        }],
+        temperature: request.temperature,
+        max_tokens: request.max_tokens,
+        top_p: request.top_p,
+        stream: request.stream,
+        tools: None,
+        tool_choice: None,
+        reasoning_effort: None,
+        seed: request.seed,
+        logprobs: None,
+        top_logprobs: None,
+        logit_bias: request.logit_bias,
+        frequency_penalty: request.frequency_penalty,
+        presence_penalty: request.presence_penalty,
+        user: request.user,
+        stop: request.stop,
+        parallel_tool_calls: None,
+    })
+}
+
+/// `chat.completion` / `chat.completion.chunk` -> `text_completion`
+///
+/// 不含 `choices` 的对象（如流中的错误事件）返回 None。
+fn to_text_completion(chat: &Value) -> Option<Value> {
+    let choices = chat.get("choices")?.as_array()?;
+    let choices: Vec<Value> = choices
+        .iter()
+        .map(|choice| {
+            let text = choice
+                .get("message")
+                .or_else(|| choice.get("delta"))
+                .and_then(|m| m.get("content"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            serde_json::json!({
+                "text": text,
+                "index": choice.get("index").cloned().unwrap_or(Value::from(0)),
+                "logprobs": null,
+                "finish_reason": choice.get("finish_reason").cloned().unwrap_or(Value::Null),
+            })
+        })
+        .collect();
+
+    let mut completion = serde_json::json!({
+        "id": chat.get("id").cloned().unwrap_or(Value::Null),
+        "object": "text_completion",
+        "created": chat.get("created").cloned().unwrap_or(Value::Null),
+        "model": chat.get("model").cloned().unwrap_or(Value::Null),
+        "choices": choices,
+    });
+    if let Some(usage) = chat.get("usage").filter(|u| !u.is_null()) {
+        completion["usage"] = usage.clone();
+    }
+    Some(completion)
+}
+
+/// 转换单个 SSE 事件的 `data:` 行，其他行（如 `id:`）保持不变
+fn convert_sse_event(event: &[u8]) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(event) else {
+        return event.to_vec();
+    };
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let converted = line
+            .strip_prefix("data:")
+            .map(|data| data.trim())
+            .filter(|data| *data != "[DONE]")
+            .and_then(|data| serde_json::from_str::<Value>(data).ok())
+            .and_then(|chunk| to_text_completion(&chunk));
+        match converted {
+            Some(completion) => {
+                out.push_str("data: ");
+                out.push_str(&completion.to_string());
+                out.push_str(if line.ends_with("\r\n") { "\r\n" } else { "\n" });
+            }
+            None => out.push_str(line),
+        }
+    }
+    out.into_bytes()
+}
+
+/// 把对话补全的 SSE 流逐个事件转换为 `text_completion` 事件
+fn convert_stream_response(response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    let stream = async_stream::stream! {
+        let mut upstream = body.into_data_stream();
+        let mut pending = BytesMut::new();
+        while let Some(chunk) = upstream.next().await {
+            match chunk {
+                Ok(bytes) => {
+                    pending.extend_from_slice(&bytes);
+                    let mut out = Vec::new();
+                    while let Some(end) = event_end(&pending) {
+                        out.extend(convert_sse_event(&pending.split_to(end)));
+                    }
+                    if !out.is_empty() {
+                        yield Ok(bytes::Bytes::from(out));
+                    }
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+        if !pending.is_empty() {
+            yield Ok(bytes::Bytes::from(convert_sse_event(&pending)));
+        }
+    };
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 把非流式对话补全响应转换为 `text_completion` 响应
+async fn convert_json_response(response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_RESPONSE_BYTES).await {
+        Ok(b) => b,
+        Err(e) => {
+            return ProcessError::Conversion(format!("读取响应失败: {}", e))
+                .into_response_for(ErrorFormat::OpenAI)
+        }
+    };
+    let converted = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|chat| to_text_completion(&chat));
+    match converted {
+        Some(completion) => {
+            let body = completion.to_string();
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: Value) -> CompletionRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_to_chat_request_wraps_prompt() {
+        let chat = to_chat_request(request(serde_json::json!({
+            "model": "gpt-3.5-turbo-instruct",
+            "prompt": ["Say hi"],
+            "max_tokens": 16,
+            "stop": "\n"
+        })))
+        .unwrap();
+
+        assert_eq!(chat.messages.len(), 1);
+        assert_eq!(chat.messages[0].role, "user");
+        assert_eq!(chat.messages[0].get_content_text(), "Say hi");
+        assert_eq!(chat.max_tokens, Some(16));
+        assert_eq!(chat.stop_sequences(), vec!["\n"]);
+    }
+
+    #[test]
+    fn test_to_chat_request_rejects_unsupported_features() {
+        for body in [
+            serde_json::json!({"model": "m", "prompt": "a", "n": 2}),
+            serde_json::json!({"model": "m", "prompt": "a", "echo": true}),
+            serde_json::json!({"model": "m", "prompt": "a", "suffix": "b"}),
+            serde_json::json!({"model": "m", "prompt": "a", "logprobs": 1}),
+            serde_json::json!({"model": "m", "prompt": ["a", "b"]}),
+            serde_json::json!({"model": "m", "prompt": [1, 2, 3]}),
+            serde_json::json!({"model": "m", "prompt": ""}),
+        ] {
+            assert!(to_chat_request(request(body.clone())).is_err(), "{}", body);
+        }
+    }
+
+    #[test]
+    fn test_to_text_completion() {
+        let completion = to_text_completion(&serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "gpt-4o",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "hi"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 2, "completion_tokens": 1, "total_tokens": 3}
+        }))
+        .unwrap();
+
+        assert_eq!(completion["object"], "text_completion");
+        assert_eq!(completion["choices"][0]["text"], "hi");
+        assert_eq!(completion["choices"][0]["finish_reason"], "stop");
+        assert_eq!(completion["usage"]["total_tokens"], 3);
+    }
+
+    #[test]
+    fn test_convert_sse_event_keeps_other_lines() {
+        let event = b"id: 3\ndata: {\"id\":\"c\",\"object\":\"chat.completion.chunk\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"he\"},\"finish_reason\":null}]}\n\n";
+        let converted = String::from_utf8(convert_sse_event(event)).unwrap();
+        assert!(converted.starts_with("id: 3\ndata: "));
+        assert!(converted.ends_with("\n\n"));
+
+        let data = converted
+            .lines()
+            .nth(1)
+            .unwrap()
+            .strip_prefix("data: ")
+            .unwrap();
+        let json: Value = serde_json::from_str(data).unwrap();
+        assert_eq!(json["object"], "text_completion");
+        assert_eq!(json["choices"][0]["text"], "he");
+
+        assert_eq!(convert_sse_event(b"data: [DONE]\n\n"), b"data: [DONE]\n\n");
+    }
+}
@@ -350,7 +350,11 @@ fn build_api_key_headers(
     match provider_type {
         ApiProviderType::Anthropic => {
             headers.insert("x-api-key".to_string(), api_key.to_string());
-            headers.insert("anthropic-version".to_string(), "2023-06-01".to_string());
+            let anthropic = crate::http_client::anthropic_headers();
+            headers.insert("anthropic-version".to_string(), anthropic.version);
+            if let Some(beta) = anthropic.beta {
+                headers.insert("anthropic-beta".to_string(), beta);
+            }
         }
         ApiProviderType::Gemini => {
             headers.insert("x-goog-api-key".to_string(), api_key.to_string());
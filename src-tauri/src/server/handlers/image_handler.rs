@@ -28,6 +28,7 @@ use crate::converter::openai_to_antigravity::{
 };
 use crate::models::openai::ImageGenerationRequest;
 use crate::models::provider_pool_model::CredentialData;
+use crate::processor::{ErrorFormat, ProcessError};
 use crate::providers::AntigravityProvider;
 use crate::server::handlers::verify_api_key;
 use crate::server::AppState;
@@ -130,17 +131,10 @@ pub async fn handle_image_generation(
                 .write()
                 .await
                 .add("error", "[IMAGE] 没有可用的 Antigravity 凭证");
-            return (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": "No Antigravity credentials available for image generation",
-                        "type": "server_error",
-                        "code": "no_credentials"
-                    }
-                })),
+            return ProcessError::NoCredential(
+                "No Antigravity credentials available for image generation".to_string(),
             )
-                .into_response();
+            .into_response_for(ErrorFormat::OpenAI);
         }
         Err(e) => {
             state
@@ -242,13 +236,13 @@ pub async fn handle_image_generation(
     }
 
     // 设置项目 ID
-    if let Some(pid) = project_id {
-        antigravity.project_id = Some(pid);
-    } else if let Err(e) = antigravity.discover_project().await {
-        tracing::warn!("[IMAGE] Failed to discover project: {}", e);
-    }
-
-    let proj_id = antigravity.project_id.clone().unwrap_or_default();
+    let proj_id = super::resolve_antigravity_project_id(
+        &state,
+        &credential,
+        project_id.as_deref(),
+        &mut antigravity,
+    )
+    .await;
 
     // 转换请求为 Antigravity 格式
     let antigravity_request = convert_image_request_to_antigravity(&request, &proj_id);
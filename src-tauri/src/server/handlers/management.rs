@@ -4,11 +4,27 @@
 
 #![allow(dead_code)]
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use futures::stream::SplitSink;
+use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{broadcast, Mutex};
 
+use crate::config::{validate_config, Config, ConfigChangeEvent, DiagnosticSeverity};
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::server::AppState;
+use crate::websocket::{WsApiResponse, WsError, WsMessage};
 
 // ============ Types ============
 
@@ -586,3 +602,237 @@ pub async fn management_update_config(
         )
     }
 }
+
+// ============ Config WebSocket ============
+
+/// 管理 WebSocket 发送端（请求响应与变更推送共享）
+type ConfigWsSender = Arc<Mutex<SplitSink<WebSocket, Message>>>;
+
+/// GET /v0/management/ws - 实时配置编辑
+///
+/// 认证由管理 API 中间件完成。支持的帧：
+/// - `config.get`：返回 `config.snapshot`
+/// - `config.subscribe` / `config.unsubscribe`：订阅后推送 `config.changed`（观察者系统的变更事件）
+/// - `config.patch`：以 JSON Merge Patch 修改配置，校验通过后保存并通知观察者，返回新的 `config.snapshot`
+pub async fn management_config_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_config_ws(socket, state))
+}
+
+async fn handle_config_ws(socket: WebSocket, state: AppState) {
+    let (sender, mut receiver) = socket.split();
+    let sender: ConfigWsSender = Arc::new(Mutex::new(sender));
+
+    let Some(manager) = state.config_manager.clone() else {
+        let error = WsMessage::Error(WsError::internal(None, "Config manager is not available"));
+        let _ = send_config_ws_message(&sender, &error).await;
+        return;
+    };
+
+    let mut subscription: Option<tokio::task::JoinHandle<()>> = None;
+    while let Some(Ok(msg)) = receiver.next().await {
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Ping(data) => {
+                if sender.lock().await.send(Message::Pong(data)).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let reply = match serde_json::from_str::<WsMessage>(&text) {
+            Ok(WsMessage::ConfigGet) => WsMessage::ConfigSnapshot {
+                config: Box::new(manager.config()),
+            },
+            Ok(WsMessage::ConfigSubscribe) => {
+                if subscription.is_none() {
+                    subscription =
+                        Some(spawn_config_forwarder(manager.subscribe(), sender.clone()));
+                }
+                WsMessage::Response(WsApiResponse {
+                    request_id: "config.subscribe".to_string(),
+                    payload: serde_json::json!({ "status": "subscribed" }),
+                })
+            }
+            Ok(WsMessage::ConfigUnsubscribe) => {
+                if let Some(task) = subscription.take() {
+                    task.abort();
+                }
+                WsMessage::Response(WsApiResponse {
+                    request_id: "config.unsubscribe".to_string(),
+                    payload: serde_json::json!({ "status": "unsubscribed" }),
+                })
+            }
+            Ok(WsMessage::ConfigPatch { patch }) => {
+                match apply_config_patch(&manager.config(), &patch) {
+                    Ok(config) => match manager.save_config(&config).await {
+                        Ok(()) => {
+                            state
+                                .logs
+                                .write()
+                                .await
+                                .add("info", "[MANAGEMENT] 通过 WebSocket 修改了配置");
+                            WsMessage::ConfigSnapshot {
+                                config: Box::new(config),
+                            }
+                        }
+                        Err(e) => WsMessage::Error(WsError::internal(None, e)),
+                    },
+                    Err(e) => WsMessage::Error(WsError::invalid_request(None, e)),
+                }
+            }
+            Ok(WsMessage::Ping { timestamp }) => WsMessage::Pong { timestamp },
+            Ok(_) => WsMessage::Error(WsError::invalid_request(
+                None,
+                "Only config frames are supported on the management WebSocket",
+            )),
+            Err(e) => WsMessage::Error(WsError::invalid_message(format!(
+                "Failed to parse message: {}",
+                e
+            ))),
+        };
+        if send_config_ws_message(&sender, &reply).await.is_err() {
+            break;
+        }
+    }
+
+    if let Some(task) = subscription {
+        task.abort();
+    }
+}
+
+async fn send_config_ws_message(
+    sender: &ConfigWsSender,
+    msg: &WsMessage,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(msg).unwrap_or_default();
+    sender.lock().await.send(Message::Text(text.into())).await
+}
+
+/// 启动配置变更转发任务
+fn spawn_config_forwarder(
+    mut receiver: broadcast::Receiver<ConfigChangeEvent>,
+    sender: ConfigWsSender,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let msg = WsMessage::ConfigChanged { event };
+                    if send_config_ws_message(&sender, &msg).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("[MANAGEMENT] Config event receiver lagged by {} events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+/// JSON Merge Patch（RFC 7386）：对象逐键合并，`null` 删除字段，其他值整体替换
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// 把 JSON Merge Patch 应用到配置并校验，返回修改后的配置
+///
+/// 与 `save_config` 命令一样不允许通过接口开启远程管理，因此拒绝修改 `remote_management`；
+/// 校验出现错误级别的诊断时拒绝整个修改。被删除的字段恢复为默认值。
+fn apply_config_patch(config: &Config, patch: &Value) -> Result<Config, String> {
+    if !patch.is_object() {
+        return Err("Config patch must be a JSON object".to_string());
+    }
+    if patch.get("remote_management").is_some() {
+        return Err(
+            "remote_management cannot be changed over the management WebSocket".to_string(),
+        );
+    }
+
+    let mut value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    merge_patch(&mut value, patch);
+    let patched: Config =
+        serde_json::from_value(value).map_err(|e| format!("Invalid config patch: {}", e))?;
+
+    let errors: Vec<String> = validate_config(&patched)
+        .into_iter()
+        .filter(|d| d.severity == DiagnosticSeverity::Error)
+        .map(|d| {
+            if d.path.is_empty() {
+                d.message
+            } else {
+                format!("{}: {}", d.path, d.message)
+            }
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!("Config validation failed: {}", errors.join("; ")));
+    }
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_config_patch() {
+        let config = Config::default();
+
+        let patched = apply_config_patch(
+            &config,
+            &serde_json::json!({ "server": { "port": 9100 }, "default_provider": "gemini" }),
+        )
+        .unwrap();
+        assert_eq!(patched.server.port, 9100);
+        assert_eq!(patched.server.host, config.server.host);
+        assert_eq!(patched.default_provider, "gemini");
+
+        // 校验失败（监听所有接口但使用默认 API Key）
+        let err = apply_config_patch(
+            &config,
+            &serde_json::json!({ "server": { "host": "0.0.0.0" } }),
+        )
+        .unwrap_err();
+        assert!(err.contains("server.api_key"));
+
+        assert!(apply_config_patch(
+            &config,
+            &serde_json::json!({ "remote_management": { "allow_remote": true } })
+        )
+        .is_err());
+        assert!(apply_config_patch(&config, &serde_json::json!([1])).is_err());
+    }
+
+    #[test]
+    fn test_merge_patch_removes_null_fields() {
+        let mut target = serde_json::json!({ "a": { "b": 1, "c": 2 }, "d": 3 });
+        merge_patch(
+            &mut target,
+            &serde_json::json!({ "a": { "b": null }, "d": [4] }),
+        );
+        assert_eq!(target, serde_json::json!({ "a": { "c": 2 }, "d": [4] }));
+    }
+}
@@ -3,6 +3,8 @@
 //! 将 server 中的各类处理器拆分到独立文件
 
 pub mod api;
+pub mod batch_handler;
+pub mod completions_handler;
 pub mod credentials_api;
 pub mod image_handler;
 pub mod kiro_credential;
@@ -11,6 +13,8 @@ pub mod provider_calls;
 pub mod websocket;
 
 pub use api::*;
+pub use batch_handler::*;
+pub use completions_handler::*;
 pub use credentials_api::*;
 pub use image_handler::*;
 pub use kiro_credential::*;
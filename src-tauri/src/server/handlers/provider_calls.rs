@@ -43,11 +43,13 @@
 
 use axum::{
     body::Body,
-    http::{header, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use futures::StreamExt;
+use std::borrow::Cow;
+use std::time::Duration;
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::converter::openai_to_antigravity::{
@@ -58,35 +60,368 @@ use crate::flow_monitor::stream_rebuilder::StreamFormat;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::processor::{record_captured_step, ErrorFormat, ProcessError, TraceStepKind};
 use crate::providers::{
     AntigravityProvider, ClaudeCustomProvider, IFlowProvider, KiroProvider, OpenAICustomProvider,
-    VertexProvider,
+    QwenProvider, VertexProvider,
 };
-use crate::server::AppState;
+use crate::resilience::{ConcurrencyPermit, RateLimitInfo};
+use crate::server::{dead_letter, AnthropicSseBody, AppState, CostAbortSlot};
 use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, parse_cw_response, safe_truncate,
     CWParsedResponse,
 };
-use crate::stream::{PipelineConfig, StreamPipeline};
+use crate::stream::{cost_guard, keep_first_tool_call, PipelineConfig, StreamPipeline};
 use crate::streaming::traits::StreamingProvider;
 use crate::streaming::{
     StreamConfig, StreamContext, StreamError, StreamFormat as StreamingFormat, StreamManager,
     StreamResponse,
 };
+use crate::telemetry::AnthropicUsage;
+use crate::ProviderType;
+
+/// 构建 Anthropic 非流式 JSON 响应
+///
+/// 解析响应体中的实际用量（含提示缓存 Token），放入响应扩展供调用方记录。
+fn anthropic_json_response(body: String) -> Result<Response, ProcessError> {
+    let usage = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|json| AnthropicUsage::from_response(&json));
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(usage) = usage {
+        builder = builder.extension(usage);
+    }
+    builder
+        .body(Body::from(body))
+        .map_err(|_| ProcessError::InternalError("Failed to build response".to_string()))
+}
+
+/// Provider 调用错误
+///
+/// 除处理错误外还带有白名单内的上游响应头，转换为错误响应时一并追加，
+/// 与成功响应保持一致（如上游的 request-id、限流头）
+#[derive(Debug)]
+pub struct ProviderCallError {
+    /// 处理错误
+    pub error: ProcessError,
+    /// 白名单内的上游响应头
+    pub forwarded: HeaderMap,
+}
+
+impl ProviderCallError {
+    /// 按指定格式转换为错误响应，并追加白名单内的上游响应头
+    pub fn into_response_for(self, format: ErrorFormat) -> Response {
+        let mut response = self.error.into_response_for(format);
+        append_forwarded_headers(&mut response, self.forwarded);
+        response
+    }
+}
+
+impl From<ProcessError> for ProviderCallError {
+    fn from(error: ProcessError) -> Self {
+        Self {
+            error,
+            forwarded: HeaderMap::new(),
+        }
+    }
+}
+
+impl std::ops::Deref for ProviderCallError {
+    type Target = ProcessError;
+
+    fn deref(&self) -> &ProcessError {
+        &self.error
+    }
+}
+
+impl std::fmt::Display for ProviderCallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+/// 透传上游 Anthropic SSE 流式响应
+///
+/// 标记 [`AnthropicSseBody`]，由调用方在流结束时记录流中的实际用量。
+fn anthropic_stream_response(resp: reqwest::Response) -> Result<Response, ProcessError> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")
+        .header("Connection", "keep-alive")
+        .header("X-Accel-Buffering", "no") // 禁用 nginx 等代理的缓冲
+        .header("Transfer-Encoding", "chunked")
+        .extension(AnthropicSseBody)
+        .body(Body::from_stream(resp.bytes_stream()))
+        .map_err(|_| ProcessError::InternalError("Failed to build stream response".to_string()))
+}
 
 /// 根据凭证调用 Provider (Anthropic 格式)
 ///
+/// 调用前按 Provider 类型获取并发许可，许可在响应体传输完毕后释放；
+/// 调用期间应用凭证级上游请求头，白名单内的上游响应头追加到返回的响应上；
+/// 上游限流头记录到凭证池，限流错误带上上游建议的等待时间
+///
 /// # 参数
 /// - `state`: 应用状态
 /// - `credential`: 凭证信息
 /// - `request`: Anthropic 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+/// - `max_cost_usd`: 单次请求费用上限（美元）。调用前按估算的输入费用收紧 `max_tokens`，
+///   输入已超出上限时返回 400；Kiro 流式响应另按实际输出截断
+///
+/// # 返回
+/// 成功时返回上游响应；失败时返回 `ProviderCallError`，由调用方按路由格式转换为错误响应
 pub async fn call_provider_anthropic(
     state: &AppState,
     credential: &ProviderCredential,
     request: &AnthropicMessagesRequest,
     flow_id: Option<&str>,
-) -> Response {
+    max_cost_usd: Option<f64>,
+) -> Result<Response, ProviderCallError> {
+    let request = &*limit_max_tokens_by_cost(request, max_cost_usd)?;
+    let permit = acquire_concurrency_permit(state, credential).await?;
+    call_provider_anthropic_with_permit(state, credential, request, flow_id, max_cost_usd, permit)
+        .await
+}
+
+/// 使用已获取的并发许可调用 Provider (Anthropic 格式)
+///
+/// 供自行获取许可的调用方使用（如影子请求只在有空闲槽位时发送）。
+/// 不按费用上限收紧 `max_tokens`，其余行为同 [`call_provider_anthropic`]
+pub async fn call_provider_anthropic_with_permit(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &AnthropicMessagesRequest,
+    flow_id: Option<&str>,
+    max_cost_usd: Option<f64>,
+    permit: ConcurrencyPermit,
+) -> Result<Response, ProviderCallError> {
+    let (response, captured) = crate::http_client::with_response_header_capture(
+        crate::http_client::with_credential_headers(
+            &credential.upstream_headers,
+            call_provider_anthropic_inner(state, credential, request, flow_id, max_cost_usd),
+        ),
+    )
+    .await;
+    let retry_after = record_rate_limit(state, credential, captured.rate_limit);
+    let mut response = response.map_err(|e| ProviderCallError {
+        error: e.with_retry_after(retry_after),
+        forwarded: captured.forwarded.clone(),
+    })?;
+    append_forwarded_headers(&mut response, captured.forwarded);
+    Ok(hold_permit_until_body_end(response, permit))
+}
+
+/// 根据凭证调用 Provider (OpenAI 格式)
+///
+/// 调用前按 Provider 类型获取并发许可，许可在响应体传输完毕后释放；
+/// 调用期间应用凭证级上游请求头，白名单内的上游响应头追加到返回的响应上；
+/// 上游限流头记录到凭证池，限流错误带上上游建议的等待时间
+///
+/// # 参数
+/// - `state`: 应用状态
+/// - `credential`: 凭证信息
+/// - `request`: OpenAI 格式请求
+/// - `flow_id`: Flow ID（可选，用于流式响应处理）
+/// - `max_cost_usd`: 单次请求费用上限（美元）。调用前按估算的输入费用收紧 `max_tokens`，
+///   输入已超出上限时返回 400；Kiro 流式响应另按实际输出截断
+///
+/// # 返回
+/// 成功时返回上游响应；失败时返回 `ProviderCallError`，由调用方按路由格式转换为错误响应
+pub async fn call_provider_openai(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+    max_cost_usd: Option<f64>,
+) -> Result<Response, ProviderCallError> {
+    let request = &*limit_max_tokens_by_cost(request, max_cost_usd)?;
+    let permit = acquire_concurrency_permit(state, credential).await?;
+    call_provider_openai_with_permit(state, credential, request, flow_id, max_cost_usd, permit)
+        .await
+}
+
+/// 使用已获取的并发许可调用 Provider (OpenAI 格式)
+///
+/// 供自行获取许可的调用方使用（如影子请求只在有空闲槽位时发送）。
+/// 不按费用上限收紧 `max_tokens`，其余行为同 [`call_provider_openai`]
+pub async fn call_provider_openai_with_permit(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+    max_cost_usd: Option<f64>,
+    permit: ConcurrencyPermit,
+) -> Result<Response, ProviderCallError> {
+    let (response, captured) = crate::http_client::with_response_header_capture(
+        crate::http_client::with_credential_headers(
+            &credential.upstream_headers,
+            call_provider_openai_inner(state, credential, request, flow_id, max_cost_usd),
+        ),
+    )
+    .await;
+    let retry_after = record_rate_limit(state, credential, captured.rate_limit);
+    let mut response = response.map_err(|e| ProviderCallError {
+        error: e.with_retry_after(retry_after),
+        forwarded: captured.forwarded.clone(),
+    })?;
+    append_forwarded_headers(&mut response, captured.forwarded);
+    Ok(hold_permit_until_body_end(response, permit))
+}
+
+/// 可按费用上限收紧输出 token 数的请求
+trait CostLimitedRequest: Clone {
+    fn model(&self) -> &str;
+    fn max_tokens(&self) -> Option<u32>;
+    fn max_tokens_mut(&mut self) -> &mut Option<u32>;
+    /// 按消息序列化长度估算的输入 token 数
+    fn estimated_input_tokens(&self) -> u32;
+}
+
+impl CostLimitedRequest for AnthropicMessagesRequest {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn max_tokens(&self) -> Option<u32> {
+        self.max_tokens
+    }
+
+    fn max_tokens_mut(&mut self) -> &mut Option<u32> {
+        &mut self.max_tokens
+    }
+
+    fn estimated_input_tokens(&self) -> u32 {
+        serde_json::to_string(&self.messages)
+            .map(|s| cost_guard::estimate_tokens(s.len()))
+            .unwrap_or(0)
+    }
+}
+
+impl CostLimitedRequest for ChatCompletionRequest {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn max_tokens(&self) -> Option<u32> {
+        self.max_tokens
+    }
+
+    fn max_tokens_mut(&mut self) -> &mut Option<u32> {
+        &mut self.max_tokens
+    }
+
+    fn estimated_input_tokens(&self) -> u32 {
+        serde_json::to_string(&self.messages)
+            .map(|s| cost_guard::estimate_tokens(s.len()))
+            .unwrap_or(0)
+    }
+}
+
+/// 按费用上限收紧请求的 `max_tokens`
+///
+/// 输入部分已超出上限时返回 400；未设置上限、模型价格未知或原值已在预算内时不复制请求。
+fn limit_max_tokens_by_cost<R: CostLimitedRequest>(
+    request: &R,
+    max_cost_usd: Option<f64>,
+) -> Result<Cow<'_, R>, ProcessError> {
+    if max_cost_usd.is_none() {
+        return Ok(Cow::Borrowed(request));
+    }
+    let budget = cost_guard::output_token_budget(
+        request.model(),
+        max_cost_usd,
+        request.estimated_input_tokens(),
+    )
+    .map_err(ProcessError::InvalidRequest)?;
+    let budget = match budget {
+        Some(budget) if !request.max_tokens().is_some_and(|t| t <= budget) => budget,
+        _ => return Ok(Cow::Borrowed(request)),
+    };
+    tracing::info!(
+        "[COST_GUARD] model={} max_tokens {:?} -> {}",
+        request.model(),
+        request.max_tokens(),
+        budget
+    );
+    let mut limited = request.clone();
+    *limited.max_tokens_mut() = Some(budget);
+    Ok(Cow::Owned(limited))
+}
+
+/// 记录上游返回的限流信息，返回上游建议的重试等待时间
+fn record_rate_limit(
+    state: &AppState,
+    credential: &ProviderCredential,
+    info: Option<RateLimitInfo>,
+) -> Option<Duration> {
+    let info = info?;
+    let delay = info.suggested_delay();
+    state.pool_service.record_rate_limit(&credential.uuid, info);
+    delay
+}
+
+/// 获取上游并发许可
+///
+/// 并发已满时根据配置排队或快速失败，失败时返回限流错误
+async fn acquire_concurrency_permit(
+    state: &AppState,
+    credential: &ProviderCredential,
+) -> Result<ConcurrencyPermit, ProcessError> {
+    let provider = credential.provider_type.to_string();
+    state
+        .processor
+        .concurrency
+        .acquire(&provider, Some(&credential.uuid))
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                "[CONCURRENCY] provider={} uuid={} error={}",
+                provider,
+                safe_truncate(&credential.uuid, 8),
+                e
+            );
+            ProcessError::from(e)
+        })
+}
+
+/// 追加白名单内的上游响应头，不覆盖响应上已有的同名响应头
+fn append_forwarded_headers(response: &mut Response, forwarded: HeaderMap) {
+    let headers = response.headers_mut();
+    for name in forwarded.keys() {
+        if headers.contains_key(name) {
+            continue;
+        }
+        for value in forwarded.get_all(name) {
+            headers.append(name.clone(), value.clone());
+        }
+    }
+}
+
+/// 将并发许可绑定到响应体上
+///
+/// 流式响应在 handler 返回后仍在传输，许可需要随响应体一起释放
+fn hold_permit_until_body_end(response: Response, permit: ConcurrencyPermit) -> Response {
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _ = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+async fn call_provider_anthropic_inner(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &AnthropicMessagesRequest,
+    flow_id: Option<&str>,
+    max_cost_usd: Option<f64>,
+) -> Result<Response, ProcessError> {
+    reject_remote_images(&credential.credential, request.first_remote_image_url())?;
+
     // 如果是流式请求且有 flow_id，设置流式状态
     if request.stream {
         if let Some(fid) = flow_id {
@@ -106,7 +441,7 @@ pub async fn call_provider_anthropic(
         CredentialData::KiroOAuth { creds_file_path } => {
             // 如果是流式请求，使用真正的流式处理（需求 1.1, 6.1）
             if request.stream {
-                return handle_kiro_stream(state, credential, request, flow_id).await;
+                return handle_kiro_stream(state, credential, request, flow_id, max_cost_usd).await;
             }
 
             // 非流式请求，使用现有的 call_api() 方法（需求 6.1, 6.2, 6.3）
@@ -114,11 +449,9 @@ pub async fn call_provider_anthropic(
             let db = match &state.db {
                 Some(db) => db,
                 None => {
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": "Database not available"}})),
-                    )
-                        .into_response();
+                    return Err(ProcessError::InternalError(
+                        "Database not available".to_string(),
+                    ));
                 }
             };
             // 获取缓存的 token
@@ -139,11 +472,10 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&format!("Failed to load credentials: {}", e)),
                         );
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::CredentialPoolError(format!(
+                            "Failed to load Kiro credentials: {}",
+                            e
+                        )));
                     }
                     if let Err(e) = kiro.refresh_token().await {
                         // 记录 Token 刷新失败
@@ -152,11 +484,10 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {}", e)),
                         );
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::AuthError(format!(
+                            "Token refresh failed: {}",
+                            e
+                        )));
                     }
                     kiro.credentials.access_token.unwrap_or_default()
                 }
@@ -170,7 +501,10 @@ pub async fn call_provider_anthropic(
             kiro.credentials.access_token = Some(token);
             let openai_request = convert_anthropic_to_openai(request);
             let resp = match kiro.call_api(&openai_request).await {
-                Ok(r) => r,
+                Ok(r) => {
+                    crate::http_client::capture_response_headers(r.headers());
+                    r
+                }
                 Err(e) => {
                     // 记录 API 调用失败
                     let _ = state.pool_service.mark_unhealthy(
@@ -178,11 +512,7 @@ pub async fn call_provider_anthropic(
                         &credential.uuid,
                         Some(&e.to_string()),
                     );
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response();
+                    return Err(ProcessError::provider_call(&*e, e.to_string()));
                 }
             };
             let status = resp.status();
@@ -199,7 +529,7 @@ pub async fn call_provider_anthropic(
                         );
                         let _ = state.pool_service.record_usage(db, &credential.uuid);
                         // 非流式请求返回完整 JSON 响应（需求 6.2）
-                        build_anthropic_response(&request.model, &parsed)
+                        Ok(build_anthropic_response(&request.model, &parsed))
                     }
                     Err(e) => {
                         let _ = state.pool_service.mark_unhealthy(
@@ -207,11 +537,7 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&e.to_string()),
                         );
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
-                            .into_response()
+                        Err(ProcessError::ProviderError(e.to_string()))
                     }
                 }
             } else if status.as_u16() == 401 || status.as_u16() == 403 {
@@ -234,17 +560,22 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {}", e)),
                         );
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::AuthError(format!(
+                            "Token refresh failed: {}",
+                            e
+                        )));
                     }
                 };
                 // 使用新 token 重试
+                record_captured_step(TraceStepKind::Retry {
+                    attempt: 1,
+                    status_code: Some(status.as_u16()),
+                    error: status.canonical_reason().unwrap_or_default().to_string(),
+                });
                 kiro.credentials.access_token = Some(new_token);
                 match kiro.call_api(&openai_request).await {
                     Ok(retry_resp) => {
+                        crate::http_client::capture_response_headers(retry_resp.headers());
                         if retry_resp.status().is_success() {
                             match retry_resp.bytes().await {
                                 Ok(bytes) => {
@@ -258,7 +589,7 @@ pub async fn call_provider_anthropic(
                                     );
                                     let _ = state.pool_service.record_usage(db, &credential.uuid);
                                     // 非流式请求返回完整 JSON 响应（需求 6.2）
-                                    build_anthropic_response(&request.model, &parsed)
+                                    Ok(build_anthropic_response(&request.model, &parsed))
                                 }
                                 Err(e) => {
                                     let _ = state.pool_service.mark_unhealthy(
@@ -266,25 +597,21 @@ pub async fn call_provider_anthropic(
                                         &credential.uuid,
                                         Some(&e.to_string()),
                                     );
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                                    )
-                                        .into_response()
+                                    Err(ProcessError::ProviderError(e.to_string()))
                                 }
                             }
                         } else {
+                            let retry_status = retry_resp.status().as_u16();
                             let body = retry_resp.text().await.unwrap_or_default();
                             let _ = state.pool_service.mark_unhealthy(
                                 db,
                                 &credential.uuid,
                                 Some(&format!("Retry failed: {}", body)),
                             );
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": format!("Retry failed: {}", body)}})),
-                            )
-                                .into_response()
+                            Err(ProcessError::upstream(
+                                retry_status,
+                                format!("Retry failed: {}", body),
+                            ))
                         }
                     }
                     Err(e) => {
@@ -293,11 +620,7 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&e.to_string()),
                         );
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
-                            .into_response()
+                        Err(ProcessError::provider_call(&*e, e.to_string()))
                     }
                 }
             } else {
@@ -305,28 +628,16 @@ pub async fn call_provider_anthropic(
                 let _ = state
                     .pool_service
                     .mark_unhealthy(db, &credential.uuid, Some(&body));
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": body}})),
-                )
-                    .into_response()
+                Err(ProcessError::upstream(status.as_u16(), body))
             }
         }
         CredentialData::GeminiOAuth { .. } => {
             // Gemini OAuth 路由暂不支持
-            (
-                StatusCode::NOT_IMPLEMENTED,
-                Json(serde_json::json!({"error": {"message": "Gemini OAuth routing not yet implemented. Use /v1/messages with Gemini models instead."}})),
-            )
-                .into_response()
+            Err(ProcessError::Unsupported("Gemini OAuth routing not yet implemented. Use /v1/messages with Gemini models instead.".to_string()))
         }
         CredentialData::QwenOAuth { .. } => {
             // Qwen OAuth 路由暂不支持
-            (
-                StatusCode::NOT_IMPLEMENTED,
-                Json(serde_json::json!({"error": {"message": "Qwen OAuth routing not yet implemented. Use /v1/messages with Qwen models instead."}})),
-            )
-                .into_response()
+            Err(ProcessError::Unsupported("Qwen OAuth routing not yet implemented. Use /v1/messages with Qwen models instead.".to_string()))
         }
         CredentialData::AntigravityOAuth {
             creds_file_path,
@@ -345,11 +656,10 @@ pub async fn call_provider_anthropic(
                         Some(&format!("Failed to load credentials: {}", e)),
                     );
                 }
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Antigravity credentials: {}", e)}})),
-                )
-                    .into_response();
+                return Err(ProcessError::CredentialPoolError(format!(
+                    "Failed to load Antigravity credentials: {}",
+                    e
+                )));
             }
 
             // 使用新的 validate_token() 方法检查 Token 状态
@@ -361,14 +671,13 @@ pub async fn call_provider_anthropic(
                 tracing::info!("[Antigravity] Token 需要刷新，开始刷新...");
                 match antigravity.refresh_token_with_retry(3).await {
                     Ok(new_token) => {
-                        tracing::info!("[Antigravity] Token 刷新成功，新 token 长度: {}", new_token.len());
+                        tracing::info!(
+                            "[Antigravity] Token 刷新成功，新 token 长度: {}",
+                            new_token.len()
+                        );
                         // 刷新成功，标记为健康
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(
-                                db,
-                                &credential.uuid,
-                                None,
-                            );
+                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, None);
                         }
                     }
                     Err(refresh_error) => {
@@ -382,33 +691,28 @@ pub async fn call_provider_anthropic(
                             );
                         }
 
-                        // 根据错误类型返回不同的状态码和消息
-                        let (status, message) = if refresh_error.requires_reauth() {
-                            (StatusCode::UNAUTHORIZED, refresh_error.user_message())
+                        // 需要重新授权时返回 401
+                        return Err(if refresh_error.requires_reauth() {
+                            ProcessError::AuthError(refresh_error.user_message())
                         } else {
-                            (StatusCode::INTERNAL_SERVER_ERROR, refresh_error.user_message())
-                        };
-
-                        return (
-                            status,
-                            Json(serde_json::json!({"error": {"message": message}})),
-                        )
-                            .into_response();
+                            ProcessError::ProviderError(refresh_error.user_message())
+                        });
                     }
                 }
             }
 
             // 设置项目 ID
-            if let Some(pid) = project_id {
-                antigravity.project_id = Some(pid.clone());
-            } else if let Err(e) = antigravity.discover_project().await {
-                tracing::warn!("[Antigravity] Failed to discover project: {}", e);
-            }
-            // 获取 project_id 用于请求
-            let proj_id = antigravity.project_id.clone().unwrap_or_default();
+            let proj_id = resolve_antigravity_project_id(
+                state,
+                credential,
+                project_id.as_deref(),
+                &mut antigravity,
+            )
+            .await;
             // 先转换为 OpenAI 格式，再转换为 Antigravity 格式
             let openai_request = convert_anthropic_to_openai(request);
-            let antigravity_request = convert_openai_to_antigravity_with_context(&openai_request, &proj_id);
+            let antigravity_request =
+                convert_openai_to_antigravity_with_context(&openai_request, &proj_id);
             match antigravity
                 .generate_content(&request.model, &antigravity_request)
                 .await
@@ -434,9 +738,9 @@ pub async fn call_provider_anthropic(
                         let _ = state.pool_service.record_usage(db, &credential.uuid);
                     }
                     if request.stream {
-                        build_anthropic_stream_response(&request.model, &parsed)
+                        Ok(build_anthropic_stream_response(&request.model, &parsed))
                     } else {
-                        build_anthropic_response(&request.model, &parsed)
+                        Ok(build_anthropic_response(&request.model, &parsed))
                     }
                 }
                 Err(e) => {
@@ -448,11 +752,7 @@ pub async fn call_provider_anthropic(
                             Some(&e.to_string()),
                         );
                     }
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response()
+                    Err(ProcessError::provider_call(&*e, e.to_string()))
                 }
             }
         }
@@ -461,11 +761,15 @@ pub async fn call_provider_anthropic(
             let openai_request = convert_anthropic_to_openai(request);
             match openai.call_api(&openai_request).await {
                 Ok(resp) => {
+                    crate::http_client::capture_response_headers(resp.headers());
                     if resp.status().is_success() {
                         match resp.text().await {
                             Ok(body) => {
                                 // 记录原始响应以便调试
-                                eprintln!("[PROVIDER_CALL] OpenAI 响应: {}", &body[..body.len().min(500)]);
+                                eprintln!(
+                                    "[PROVIDER_CALL] OpenAI 响应: {}",
+                                    &body[..body.len().min(500)]
+                                );
 
                                 if let Ok(openai_resp) =
                                     serde_json::from_str::<serde_json::Value>(&body)
@@ -490,13 +794,16 @@ pub async fn call_provider_anthropic(
                                             state.pool_service.record_usage(db, &credential.uuid);
                                     }
                                     if request.stream {
-                                        build_anthropic_stream_response(&request.model, &parsed)
+                                        Ok(build_anthropic_stream_response(&request.model, &parsed))
                                     } else {
-                                        build_anthropic_response(&request.model, &parsed)
+                                        Ok(build_anthropic_response(&request.model, &parsed))
                                     }
                                 } else {
                                     // 记录解析失败和原始响应
-                                    eprintln!("[PROVIDER_CALL] 解析 OpenAI 响应失败，原始响应: {}", &body);
+                                    eprintln!(
+                                        "[PROVIDER_CALL] 解析 OpenAI 响应失败，原始响应: {}",
+                                        &body
+                                    );
                                     if let Some(db) = &state.db {
                                         let _ = state.pool_service.mark_unhealthy(
                                             db,
@@ -504,11 +811,10 @@ pub async fn call_provider_anthropic(
                                             Some("Failed to parse OpenAI response"),
                                         );
                                     }
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": format!("Failed to parse OpenAI response. Body: {}", &body[..body.len().min(200)])}})),
-                                    )
-                                        .into_response()
+                                    Err(ProcessError::Conversion(format!(
+                                        "Failed to parse OpenAI response. Body: {}",
+                                        &body[..body.len().min(200)]
+                                    )))
                                 }
                             }
                             Err(e) => {
@@ -519,14 +825,11 @@ pub async fn call_provider_anthropic(
                                         Some(&e.to_string()),
                                     );
                                 }
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                                )
-                                    .into_response()
+                                Err(ProcessError::ProviderError(e.to_string()))
                             }
                         }
                     } else {
+                        let status = resp.status().as_u16();
                         let body = resp.text().await.unwrap_or_default();
                         if let Some(db) = &state.db {
                             let _ = state.pool_service.mark_unhealthy(
@@ -535,11 +838,7 @@ pub async fn call_provider_anthropic(
                                 Some(&body),
                             );
                         }
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": body}})),
-                        )
-                            .into_response()
+                        Err(ProcessError::upstream(status, body))
                     }
                 }
                 Err(e) => {
@@ -550,11 +849,7 @@ pub async fn call_provider_anthropic(
                             Some(&e.to_string()),
                         );
                     }
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response()
+                    Err(ProcessError::provider_call(&*e, e.to_string()))
                 }
             }
         }
@@ -584,24 +879,24 @@ pub async fn call_provider_anthropic(
             );
             match claude.call_api(request).await {
                 Ok(resp) => {
+                    crate::http_client::capture_response_headers(resp.headers());
                     let status = resp.status();
                     // 打印响应状态
                     state.logs.write().await.add(
                         "info",
                         &format!(
                             "[CLAUDE] 响应状态: status={} model={} stream={}",
-                            status,
-                            request.model,
-                            request.stream
+                            status, request.model, request.stream
                         ),
                     );
 
                     // 如果是流式请求，直接透传流式响应
                     if request.stream && status.is_success() {
-                        state.logs.write().await.add(
-                            "info",
-                            "[CLAUDE] 流式请求，透传 SSE 响应",
-                        );
+                        state
+                            .logs
+                            .write()
+                            .await
+                            .add("info", "[CLAUDE] 流式请求，透传 SSE 响应");
                         // 记录成功
                         if let Some(db) = &state.db {
                             let _ = state.pool_service.mark_healthy(
@@ -612,22 +907,7 @@ pub async fn call_provider_anthropic(
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
                         // 透传流式响应，保持 SSE 格式
-                        let stream = resp.bytes_stream();
-                        return Response::builder()
-                            .status(StatusCode::OK)
-                            .header(header::CONTENT_TYPE, "text/event-stream")
-                            .header(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")
-                            .header("Connection", "keep-alive")
-                            .header("X-Accel-Buffering", "no") // 禁用 nginx 等代理的缓冲
-                            .header("Transfer-Encoding", "chunked")
-                            .body(Body::from_stream(stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
-                                )
-                                    .into_response()
-                            });
+                        return anthropic_stream_response(resp);
                     }
 
                     // 非流式请求，读取完整响应
@@ -651,17 +931,7 @@ pub async fn call_provider_anthropic(
                                     );
                                     let _ = state.pool_service.record_usage(db, &credential.uuid);
                                 }
-                                Response::builder()
-                                    .status(StatusCode::OK)
-                                    .header(header::CONTENT_TYPE, "application/json")
-                                    .body(Body::from(body))
-                                    .unwrap_or_else(|_| {
-                                        (
-                                            StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
-                                        )
-                                            .into_response()
-                                    })
+                                anthropic_json_response(body)
                             } else {
                                 state.logs.write().await.add(
                                     "error",
@@ -678,19 +948,15 @@ pub async fn call_provider_anthropic(
                                         Some(&body),
                                     );
                                 }
-                                (
-                                    StatusCode::from_u16(status.as_u16())
-                                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                                    Json(serde_json::json!({"error": {"message": body}})),
-                                )
-                                    .into_response()
+                                Err(ProcessError::upstream(status.as_u16(), body))
                             }
                         }
                         Err(e) => {
-                            state.logs.write().await.add(
-                                "error",
-                                &format!("[CLAUDE] 读取响应失败: {}", e),
-                            );
+                            state
+                                .logs
+                                .write()
+                                .await
+                                .add("error", &format!("[CLAUDE] 读取响应失败: {}", e));
                             if let Some(db) = &state.db {
                                 let _ = state.pool_service.mark_unhealthy(
                                     db,
@@ -698,11 +964,7 @@ pub async fn call_provider_anthropic(
                                     Some(&e.to_string()),
                                 );
                             }
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                            )
-                                .into_response()
+                            Err(ProcessError::ProviderError(e.to_string()))
                         }
                     }
                 }
@@ -714,77 +976,89 @@ pub async fn call_provider_anthropic(
                             Some(&e.to_string()),
                         );
                     }
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response()
+                    Err(ProcessError::provider_call(&*e, e.to_string()))
                 }
             }
         }
-        CredentialData::VertexKey { api_key, base_url, .. } => {
+        CredentialData::VertexKey {
+            api_key, base_url, ..
+        } => {
             // Vertex AI uses Gemini-compatible API, convert Anthropic to OpenAI format first
             let openai_request = convert_anthropic_to_openai(request);
             let vertex = VertexProvider::with_config(api_key.clone(), base_url.clone());
-            match vertex.chat_completions(&serde_json::to_value(&openai_request).unwrap_or_default()).await {
+            match vertex
+                .chat_completions(&serde_json::to_value(&openai_request).unwrap_or_default())
+                .await
+            {
                 Ok(resp) => {
+                    crate::http_client::capture_response_headers(resp.headers());
                     let status = resp.status();
                     match resp.text().await {
                         Ok(body) => {
                             if status.is_success() {
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
+                                    let _ = state.pool_service.mark_healthy(
+                                        db,
+                                        &credential.uuid,
+                                        Some(&request.model),
+                                    );
                                     let _ = state.pool_service.record_usage(db, &credential.uuid);
                                 }
                                 Response::builder()
                                     .status(StatusCode::OK)
                                     .header(header::CONTENT_TYPE, "application/json")
                                     .body(Body::from(body))
-                                    .unwrap_or_else(|_| {
-                                        (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": "Failed to build response"}}))).into_response()
+                                    .map_err(|_| {
+                                        ProcessError::InternalError(
+                                            "Failed to build response".to_string(),
+                                        )
                                     })
                             } else {
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&body));
+                                    let _ = state.pool_service.mark_unhealthy(
+                                        db,
+                                        &credential.uuid,
+                                        Some(&body),
+                                    );
                                 }
-                                (StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR), Json(serde_json::json!({"error": {"message": body}}))).into_response()
+                                Err(ProcessError::upstream(status.as_u16(), body))
                             }
                         }
                         Err(e) => {
                             if let Some(db) = &state.db {
-                                let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                                let _ = state.pool_service.mark_unhealthy(
+                                    db,
+                                    &credential.uuid,
+                                    Some(&e.to_string()),
+                                );
                             }
-                            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response()
+                            Err(ProcessError::ProviderError(e.to_string()))
                         }
                     }
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &credential.uuid,
+                            Some(&e.to_string()),
+                        );
                     }
-                    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response()
+                    Err(ProcessError::provider_call(&*e, e.to_string()))
                 }
             }
         }
         // Gemini API Key credentials - not supported for Anthropic format
-        CredentialData::GeminiApiKey { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "Gemini API Key credentials do not support Anthropic format"}})),
-            )
-                .into_response()
-        }
+        CredentialData::GeminiApiKey { .. } => Err(ProcessError::Unsupported(
+            "Gemini API Key credentials do not support Anthropic format".to_string(),
+        )),
         // 新增的凭证类型暂不支持 Anthropic 格式
         CredentialData::CodexOAuth { .. }
         | CredentialData::ClaudeOAuth { .. }
         | CredentialData::IFlowOAuth { .. }
-        | CredentialData::IFlowCookie { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "This credential type does not support Anthropic format yet"}})),
-            )
-                .into_response()
-        }
+        | CredentialData::IFlowCookie { .. } => Err(ProcessError::Unsupported(
+            "This credential type does not support Anthropic format yet".to_string(),
+        )),
         // Anthropic API Key - 根据 base_url 决定调用方式
         CredentialData::AnthropicKey { api_key, base_url } => {
             // 使用 Anthropic 原生格式调用（无论是否有自定义 base_url）
@@ -801,23 +1075,23 @@ pub async fn call_provider_anthropic(
             );
             match claude.call_api(request).await {
                 Ok(resp) => {
+                    crate::http_client::capture_response_headers(resp.headers());
                     let status = resp.status();
                     state.logs.write().await.add(
                         "info",
                         &format!(
                             "[ANTHROPIC] 响应状态: status={} model={} stream={}",
-                            status,
-                            request.model,
-                            request.stream
+                            status, request.model, request.stream
                         ),
                     );
 
                     // 如果是流式请求，直接透传流式响应
                     if request.stream && status.is_success() {
-                        state.logs.write().await.add(
-                            "info",
-                            "[ANTHROPIC] 流式请求，透传 SSE 响应",
-                        );
+                        state
+                            .logs
+                            .write()
+                            .await
+                            .add("info", "[ANTHROPIC] 流式请求，透传 SSE 响应");
                         if let Some(db) = &state.db {
                             let _ = state.pool_service.mark_healthy(
                                 db,
@@ -826,22 +1100,7 @@ pub async fn call_provider_anthropic(
                             );
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
-                        let stream = resp.bytes_stream();
-                        return Response::builder()
-                            .status(StatusCode::OK)
-                            .header(header::CONTENT_TYPE, "text/event-stream")
-                            .header(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")
-                            .header("Connection", "keep-alive")
-                            .header("X-Accel-Buffering", "no") // 禁用 nginx 等代理的缓冲
-                            .header("Transfer-Encoding", "chunked")
-                            .body(Body::from_stream(stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
-                                )
-                                    .into_response()
-                            });
+                        return anthropic_stream_response(resp);
                     }
 
                     // 非流式请求，读取完整响应
@@ -856,17 +1115,7 @@ pub async fn call_provider_anthropic(
                                     );
                                     let _ = state.pool_service.record_usage(db, &credential.uuid);
                                 }
-                                Response::builder()
-                                    .status(StatusCode::OK)
-                                    .header(header::CONTENT_TYPE, "application/json")
-                                    .body(Body::from(body))
-                                    .unwrap_or_else(|_| {
-                                        (
-                                            StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
-                                        )
-                                            .into_response()
-                                    })
+                                anthropic_json_response(body)
                             } else {
                                 state.logs.write().await.add(
                                     "error",
@@ -883,19 +1132,13 @@ pub async fn call_provider_anthropic(
                                         Some(&format!("API error: {}", status)),
                                     );
                                 }
-                                (
-                                    StatusCode::from_u16(status.as_u16())
-                                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                                    Json(serde_json::json!({"error": {"message": body}})),
-                                )
-                                    .into_response()
+                                Err(ProcessError::upstream(status.as_u16(), body))
                             }
                         }
-                        Err(e) => (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to read response: {}", e)}})),
-                        )
-                            .into_response(),
+                        Err(e) => Err(ProcessError::ProviderError(format!(
+                            "Failed to read response: {}",
+                            e
+                        ))),
                     }
                 }
                 Err(e) => {
@@ -906,30 +1149,159 @@ pub async fn call_provider_anthropic(
                             Some(&format!("API call failed: {}", e)),
                         );
                     }
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": format!("Anthropic API call failed: {}", e)}})),
-                    )
-                        .into_response()
+                    Err(ProcessError::provider_call(
+                        &*e,
+                        format!("Anthropic API call failed: {}", e),
+                    ))
                 }
             }
         }
     }
 }
 
-/// 根据凭证调用 Provider (OpenAI 格式)
+/// 已提示过不支持 seed/logprobs 的凭证类型（每种类型只警告一次）
+static SAMPLING_CONTROLS_WARNED: once_cell::sync::Lazy<dashmap::DashSet<&'static str>> =
+    once_cell::sync::Lazy::new(dashmap::DashSet::new);
+
+/// 对不转发 seed/logprobs 的 Provider 输出一次性警告（参数会被静默丢弃，不影响请求）
+fn warn_dropped_sampling_controls(credential: &CredentialData) {
+    let provider = match credential {
+        // 直接透传 OpenAI 请求体或映射到 generationConfig 的 Provider
+        CredentialData::OpenAIKey { .. }
+        | CredentialData::VertexKey { .. }
+        | CredentialData::AntigravityOAuth { .. }
+        | CredentialData::QwenOAuth { .. }
+        | CredentialData::IFlowOAuth { .. }
+        | CredentialData::IFlowCookie { .. } => return,
+        CredentialData::KiroOAuth { .. } => "KiroOAuth",
+        CredentialData::ClaudeKey { .. } => "ClaudeKey",
+        CredentialData::AnthropicKey { .. } => "AnthropicKey",
+        CredentialData::GeminiOAuth { .. } => "GeminiOAuth",
+        CredentialData::GeminiApiKey { .. } => "GeminiApiKey",
+        _ => "Other",
+    };
+    if SAMPLING_CONTROLS_WARNED.insert(provider) {
+        tracing::warn!(
+            "[CALL_PROVIDER_OPENAI] {} 不支持 seed/logprobs/top_logprobs，已忽略这些参数",
+            provider
+        );
+    }
+}
+
+/// 凭证在 OpenAI 路由上是否直接透传请求体（因而能转发 logit_bias / 惩罚参数）
 ///
-/// # 参数
-/// - `state`: 应用状态
-/// - `credential`: 凭证信息
-/// - `request`: OpenAI 格式请求
-/// - `flow_id`: Flow ID（可选，用于流式响应处理）
-pub async fn call_provider_openai(
+/// 带自定义 base_url 的 Anthropic Key 按 OpenAI 兼容代理调用，同样透传。
+fn forwards_penalty_params(credential: &CredentialData) -> bool {
+    matches!(
+        credential,
+        CredentialData::OpenAIKey { .. }
+            | CredentialData::VertexKey { .. }
+            | CredentialData::QwenOAuth { .. }
+            | CredentialData::IFlowOAuth { .. }
+            | CredentialData::IFlowCookie { .. }
+            | CredentialData::AnthropicKey {
+                base_url: Some(_),
+                ..
+            }
+    )
+}
+
+/// 丢弃 Provider 不支持的 logit_bias / 惩罚参数，返回被丢弃的参数名
+///
+/// 只有直接透传 OpenAI 请求体的 Provider 会转发这些参数（见 [`forwards_penalty_params`]），
+/// 其余 Provider 在格式转换时无法表达，清除后输出一次警告，不影响请求。
+pub fn drop_unsupported_penalty_params(
+    credential: &ProviderCredential,
+    request: &mut ChatCompletionRequest,
+) -> Vec<&'static str> {
+    if forwards_penalty_params(&credential.credential) {
+        return Vec::new();
+    }
+    let dropped = request.penalty_params();
+    if !dropped.is_empty() {
+        request.clear_penalty_params();
+        tracing::warn!(
+            "[CALL_PROVIDER_OPENAI] {} 不支持 {}，已忽略这些参数",
+            credential.provider_type,
+            dropped.join("/")
+        );
+    }
+    dropped
+}
+
+/// 只接受内联（base64）图片的 Provider 收到图片 URL 时返回 400
+///
+/// Kiro 和 Antigravity 的请求格式只能携带图片数据，图片 URL 在转换时会被丢弃，
+/// 与其让模型在看不到图片的情况下回答，不如直接告诉客户端改用 base64 图片。
+fn reject_remote_images(
+    credential: &CredentialData,
+    remote_image_url: Option<&str>,
+) -> Result<(), ProcessError> {
+    let provider = match credential {
+        CredentialData::KiroOAuth { .. } => "Kiro",
+        CredentialData::AntigravityOAuth { .. } => "Antigravity",
+        _ => return Ok(()),
+    };
+    match remote_image_url {
+        Some(url) => Err(ProcessError::Unsupported(format!(
+            "{} does not accept image URLs, send the image as base64 data instead (got: {})",
+            provider,
+            url.chars().take(100).collect::<String>()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// 确定 Antigravity 请求使用的项目 ID
+///
+/// 优先使用凭证记录中缓存的项目 ID；否则（带重试）发现项目 ID 并写回凭证记录，
+/// 后续请求直接复用。发现失败时才生成随机 ID 作为最后手段。
+pub async fn resolve_antigravity_project_id(
+    state: &AppState,
+    credential: &ProviderCredential,
+    cached_project_id: Option<&str>,
+    antigravity: &mut AntigravityProvider,
+) -> String {
+    if let Some(pid) = cached_project_id {
+        antigravity.project_id = Some(pid.to_string());
+        return pid.to_string();
+    }
+
+    let from_creds_file = antigravity.project_id.is_some();
+    match antigravity.discover_project().await {
+        Ok(pid) => {
+            if !from_creds_file {
+                if let Some(db) = &state.db {
+                    match state.pool_service.set_antigravity_project_id(
+                        db,
+                        &credential.uuid,
+                        Some(pid.clone()),
+                    ) {
+                        Ok(_) => tracing::info!(
+                            "[Antigravity] 已缓存项目 ID {} 到凭证 {}",
+                            pid,
+                            &credential.uuid[..8.min(credential.uuid.len())]
+                        ),
+                        Err(e) => tracing::warn!("[Antigravity] 缓存项目 ID 失败: {}", e),
+                    }
+                }
+            }
+            pid
+        }
+        Err(e) => {
+            tracing::warn!("[Antigravity] Failed to discover project: {}", e);
+            antigravity.fallback_project_id()
+        }
+    }
+}
+
+async fn call_provider_openai_inner(
     state: &AppState,
     credential: &ProviderCredential,
     request: &ChatCompletionRequest,
-    _flow_id: Option<&str>,
-) -> Response {
+    flow_id: Option<&str>,
+    max_cost_usd: Option<f64>,
+) -> Result<Response, ProcessError> {
     let _start_time = std::time::Instant::now();
 
     // 调试：打印凭证类型
@@ -952,17 +1324,20 @@ pub async fn call_provider_openai(
         &credential.uuid[..8]
     );
 
+    if request.has_sampling_controls() {
+        warn_dropped_sampling_controls(&credential.credential);
+    }
+    reject_remote_images(&credential.credential, request.first_remote_image_url())?;
+
     match &credential.credential {
         CredentialData::KiroOAuth { creds_file_path } => {
             // 优先使用 token cache，避免每次都刷新 token
             let db = match &state.db {
                 Some(db) => db,
                 None => {
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": "Database not available"}})),
-                    )
-                        .into_response();
+                    return Err(ProcessError::InternalError(
+                        "Database not available".to_string(),
+                    ));
                 }
             };
 
@@ -983,11 +1358,10 @@ pub async fn call_provider_openai(
                             &credential.uuid,
                             Some(&format!("Failed to load credentials: {}", e)),
                         );
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::CredentialPoolError(format!(
+                            "Failed to load Kiro credentials: {}",
+                            e
+                        )));
                     }
                     if let Err(e) = kiro.refresh_token().await {
                         let _ = state.pool_service.mark_unhealthy(
@@ -995,11 +1369,10 @@ pub async fn call_provider_openai(
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {}", e)),
                         );
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::AuthError(format!(
+                            "Token refresh failed: {}",
+                            e
+                        )));
                     }
                     kiro.credentials.access_token.unwrap_or_default()
                 }
@@ -1013,7 +1386,11 @@ pub async fn call_provider_openai(
             // 使用缓存的 token 覆盖文件中的 token（缓存的 token 更新）
             kiro.credentials.access_token = Some(token);
 
-            tracing::info!("[CALL_PROVIDER_OPENAI] request.stream = {}, model = {}", request.stream, request.model);
+            tracing::info!(
+                "[CALL_PROVIDER_OPENAI] request.stream = {}, model = {}",
+                request.stream,
+                request.model
+            );
 
             // 检查是否为流式请求
             if request.stream {
@@ -1023,14 +1400,25 @@ pub async fn call_provider_openai(
                     Ok(stream_response) => {
                         // 记录成功
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
+                            let _ = state.pool_service.mark_healthy(
+                                db,
+                                &credential.uuid,
+                                Some(&request.model),
+                            );
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
 
                         tracing::info!("[OPENAI_STREAM] 开始转换流式响应");
 
                         // 使用新的统一流处理管道 (Kiro → OpenAI)
-                        let config = PipelineConfig::kiro_to_openai(request.model.clone());
+                        // Kiro 不支持服务端 stop 和 parallel_tool_calls，由管道在客户端处理
+                        let input_tokens = serde_json::to_string(&request.messages)
+                            .map(|s| cost_guard::estimate_tokens(s.len()))
+                            .unwrap_or(0);
+                        let config = PipelineConfig::kiro_to_openai(request.model.clone())
+                            .with_stop_sequences(request.stop_sequences())
+                            .with_single_tool_call(request.parallel_tool_calls_disabled())
+                            .with_cost_guard(max_cost_usd, input_tokens);
                         let pipeline = std::sync::Arc::new(tokio::sync::Mutex::new(
                             StreamPipeline::new(config),
                         ));
@@ -1038,6 +1426,9 @@ pub async fn call_provider_openai(
                         // 创建转换流
                         let pipeline_for_stream = pipeline.clone();
                         let pipeline_for_finalize = pipeline.clone();
+                        let cost_abort = CostAbortSlot::default();
+                        let cost_abort_for_stream = cost_abort.clone();
+                        let model = request.model.clone();
                         let final_stream = async_stream::stream! {
                             use futures::StreamExt;
 
@@ -1052,9 +1443,10 @@ pub async fn call_provider_openai(
                                         );
 
                                         // 使用 Pipeline 处理 chunk
-                                        let sse_events = {
+                                        let (sse_events, stopped) = {
                                             let mut pipeline_guard = pipeline_for_stream.lock().await;
-                                            pipeline_guard.process_chunk(&bytes)
+                                            let events = pipeline_guard.process_chunk(&bytes);
+                                            (events, pipeline_guard.is_stopped())
                                         };
 
                                         tracing::debug!(
@@ -1066,6 +1458,11 @@ pub async fn call_provider_openai(
                                         for sse_str in sse_events {
                                             yield Ok::<String, StreamError>(sse_str);
                                         }
+
+                                        if stopped {
+                                            tracing::info!("[OPENAI_STREAM] 命中停止序列或费用上限，提前结束读取");
+                                            break;
+                                        }
                                     }
                                     Err(e) => {
                                         tracing::error!("[OPENAI_STREAM] 流式传输错误: {}", e);
@@ -1080,6 +1477,9 @@ pub async fn call_provider_openai(
                             // 流结束，使用 Pipeline 生成结束事件
                             let final_events = {
                                 let mut pipeline_guard = pipeline_for_finalize.lock().await;
+                                if let Some(guard) = pipeline_guard.cost_aborted() {
+                                    cost_abort_for_stream.record(ProviderType::Kiro, &model, guard);
+                                }
                                 pipeline_guard.finish()
                             };
 
@@ -1093,12 +1493,14 @@ pub async fn call_provider_openai(
                         tracing::info!("[OPENAI_STREAM] 构建 SSE 响应");
 
                         // 转换为 Body 流
-                        let body_stream = final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-                            match result {
-                                Ok(event) => Ok(axum::body::Bytes::from(event)),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-                            }
-                        });
+                        let body_stream = final_stream.map(
+                            |result| -> Result<axum::body::Bytes, std::io::Error> {
+                                match result {
+                                    Ok(event) => Ok(axum::body::Bytes::from(event)),
+                                    Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                                }
+                            },
+                        );
 
                         // 构建 SSE 响应
                         return Response::builder()
@@ -1108,27 +1510,24 @@ pub async fn call_provider_openai(
                             .header(header::CONNECTION, "keep-alive")
                             .header(header::TRANSFER_ENCODING, "chunked")
                             .header("X-Accel-Buffering", "no")
+                            .extension(cost_abort)
                             .body(Body::from_stream(body_stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
+                            .map_err(|_| {
+                                ProcessError::InternalError(
+                                    "Failed to build streaming response".to_string(),
                                 )
-                                    .into_response()
                             });
                     }
                     Err(e) => {
                         // 记录请求错误
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&e.to_string()),
+                            );
                         }
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::provider_call(&e, e.to_string()));
                     }
                 }
             }
@@ -1136,11 +1535,16 @@ pub async fn call_provider_openai(
             // 非流式请求处理
             match kiro.call_api(request).await {
                 Ok(resp) => {
+                    crate::http_client::capture_response_headers(resp.headers());
                     let status = resp.status();
                     if status.is_success() {
                         // 记录成功
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
+                            let _ = state.pool_service.mark_healthy(
+                                db,
+                                &credential.uuid,
+                                Some(&request.model),
+                            );
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
                         match resp.text().await {
@@ -1168,7 +1572,7 @@ pub async fn call_provider_openai(
                                         "content": parsed.content
                                     })
                                 };
-                                Json(serde_json::json!({
+                                Ok(Json(serde_json::json!({
                                     "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
                                     "object": "chat.completion",
                                     "created": std::time::SystemTime::now()
@@ -1187,55 +1591,149 @@ pub async fn call_provider_openai(
                                         "total_tokens": 0
                                     }
                                 }))
-                                .into_response()
+                                .into_response())
                             }
-                            Err(e) => (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                            )
-                                .into_response(),
+                            Err(e) => Err(ProcessError::ProviderError(e.to_string())),
                         }
                     } else {
                         // 记录 API 调用失败
                         let body = resp.text().await.unwrap_or_default();
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&format!("HTTP {}: {}", status, safe_truncate(&body, 100))));
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&format!("HTTP {}: {}", status, safe_truncate(&body, 100))),
+                            );
                         }
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": body}})),
-                        )
-                            .into_response()
+                        Err(ProcessError::upstream(status.as_u16(), body))
                     }
                 }
                 Err(e) => {
                     // 记录请求错误
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &credential.uuid,
+                            Some(&e.to_string()),
+                        );
                     }
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response()
+                    Err(ProcessError::provider_call(&*e, e.to_string()))
                 }
             }
         }
-        CredentialData::GeminiOAuth { .. } => {
-            (
-                StatusCode::NOT_IMPLEMENTED,
-                Json(serde_json::json!({"error": {"message": "Gemini OAuth routing not yet implemented."}})),
-            )
-                .into_response()
-        }
-        CredentialData::QwenOAuth { .. } => {
-            (
-                StatusCode::NOT_IMPLEMENTED,
-                Json(serde_json::json!({"error": {"message": "Qwen OAuth routing not yet implemented."}})),
-            )
-                .into_response()
+        CredentialData::GeminiOAuth { .. } => Err(ProcessError::Unsupported(
+            "Gemini OAuth routing not yet implemented.".to_string(),
+        )),
+        CredentialData::QwenOAuth { creds_file_path } => {
+            let db = match &state.db {
+                Some(db) => db,
+                None => {
+                    return Err(ProcessError::InternalError(
+                        "Database not available".to_string(),
+                    ));
+                }
+            };
+
+            // 从源文件加载 resource_url 等配置，再使用缓存的 token 覆盖
+            let mut qwen = QwenProvider::new();
+            if let Err(e) = qwen.load_credentials_from_path(creds_file_path).await {
+                let _ = state.pool_service.mark_unhealthy(
+                    db,
+                    &credential.uuid,
+                    Some(&format!("Failed to load Qwen credentials: {}", e)),
+                );
+                return Err(ProcessError::CredentialPoolError(format!(
+                    "Failed to load Qwen credentials: {}",
+                    e
+                )));
+            }
+            match state
+                .token_cache
+                .get_valid_token(db, &credential.uuid)
+                .await
+            {
+                Ok(token) => qwen.credentials.access_token = Some(token),
+                Err(e) => {
+                    tracing::warn!(
+                        "[POOL] Qwen token cache miss, refreshing from source: {}",
+                        e
+                    );
+                    if let Err(e) = qwen.ensure_valid_token().await {
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &credential.uuid,
+                            Some(&format!("Qwen token refresh failed: {}", e)),
+                        );
+                        return Err(ProcessError::AuthError(format!(
+                            "Qwen token refresh failed: {}",
+                            e
+                        )));
+                    }
+                }
+            }
+
+            if request.stream {
+                return match qwen.call_api_stream(request).await {
+                    Ok(stream_response) => {
+                        openai_passthrough_stream_response(
+                            state,
+                            credential,
+                            &request.model,
+                            stream_response,
+                            flow_id,
+                        )
+                        .await
+                    }
+                    Err(e) => {
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &credential.uuid,
+                            Some(&e.to_string()),
+                        );
+                        Err(ProcessError::provider_call(&e, e.to_string()))
+                    }
+                };
+            }
+
+            let request_json = serde_json::to_value(request).unwrap_or_default();
+            match qwen.chat_completions(&request_json).await {
+                Ok(resp) => {
+                    crate::http_client::capture_response_headers(resp.headers());
+                    let status = resp.status();
+                    let body = resp.text().await.unwrap_or_default();
+                    if !status.is_success() {
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &credential.uuid,
+                            Some(&format!("HTTP {}: {}", status, safe_truncate(&body, 100))),
+                        );
+                        return Err(ProcessError::upstream(status.as_u16(), body));
+                    }
+                    let _ =
+                        state
+                            .pool_service
+                            .mark_healthy(db, &credential.uuid, Some(&request.model));
+                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                    match serde_json::from_str::<serde_json::Value>(&body) {
+                        Ok(json) => Ok(Json(json).into_response()),
+                        Err(_) => Err(ProcessError::Conversion(
+                            "Invalid JSON response".to_string(),
+                        )),
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("[Qwen] API call failed: {}", e);
+                    Err(ProcessError::provider_call(
+                        &*e,
+                        format!("Qwen API call failed: {}", e),
+                    ))
+                }
+            }
         }
-        CredentialData::AntigravityOAuth { creds_file_path, project_id } => {
+        CredentialData::AntigravityOAuth {
+            creds_file_path,
+            project_id,
+        } => {
             eprintln!("\n========== [ANTIGRAVITY] 开始处理 Antigravity 请求 ==========");
             eprintln!("[ANTIGRAVITY] 凭证文件: {}", creds_file_path);
             eprintln!("[ANTIGRAVITY] 项目ID: {:?}", project_id);
@@ -1243,7 +1741,10 @@ pub async fn call_provider_openai(
             eprintln!("[ANTIGRAVITY] 流式: {}", request.stream);
 
             let mut antigravity = AntigravityProvider::new();
-            if let Err(e) = antigravity.load_credentials_from_path(creds_file_path).await {
+            if let Err(e) = antigravity
+                .load_credentials_from_path(creds_file_path)
+                .await
+            {
                 eprintln!("[ANTIGRAVITY] 加载凭证失败: {}", e);
                 // 记录凭证加载失败
                 if let Some(db) = &state.db {
@@ -1253,18 +1754,20 @@ pub async fn call_provider_openai(
                         Some(&format!("Failed to load credentials: {}", e)),
                     );
                 }
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Antigravity credentials: {}", e)}})),
-                )
-                    .into_response();
+                return Err(ProcessError::CredentialPoolError(format!(
+                    "Failed to load Antigravity credentials: {}",
+                    e
+                )));
             }
             eprintln!("[ANTIGRAVITY] 凭证加载成功");
 
             // 使用新的 validate_token() 方法检查 Token 状态
             let validation_result = antigravity.validate_token();
             eprintln!("[ANTIGRAVITY] Token 验证结果: {:?}", validation_result);
-            eprintln!("[ANTIGRAVITY] needs_refresh() = {}", validation_result.needs_refresh());
+            eprintln!(
+                "[ANTIGRAVITY] needs_refresh() = {}",
+                validation_result.needs_refresh()
+            );
             tracing::info!("[Antigravity] Token 验证结果: {:?}", validation_result);
 
             // 根据验证结果决定是否刷新
@@ -1273,15 +1776,17 @@ pub async fn call_provider_openai(
                 tracing::info!("[Antigravity] Token 需要刷新，开始刷新...");
                 match antigravity.refresh_token_with_retry(3).await {
                     Ok(new_token) => {
-                        eprintln!("[ANTIGRAVITY] Token 刷新成功，新 token 长度: {}", new_token.len());
-                        tracing::info!("[Antigravity] Token 刷新成功，新 token 长度: {}", new_token.len());
+                        eprintln!(
+                            "[ANTIGRAVITY] Token 刷新成功，新 token 长度: {}",
+                            new_token.len()
+                        );
+                        tracing::info!(
+                            "[Antigravity] Token 刷新成功，新 token 长度: {}",
+                            new_token.len()
+                        );
                         // 刷新成功，标记为健康
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(
-                                db,
-                                &credential.uuid,
-                                None,
-                            );
+                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, None);
                         }
                     }
                     Err(refresh_error) => {
@@ -1296,18 +1801,12 @@ pub async fn call_provider_openai(
                             );
                         }
 
-                        // 根据错误类型返回不同的状态码和消息
-                        let (status, message) = if refresh_error.requires_reauth() {
-                            (StatusCode::UNAUTHORIZED, refresh_error.user_message())
+                        // 需要重新授权时返回 401
+                        return Err(if refresh_error.requires_reauth() {
+                            ProcessError::AuthError(refresh_error.user_message())
                         } else {
-                            (StatusCode::INTERNAL_SERVER_ERROR, refresh_error.user_message())
-                        };
-
-                        return (
-                            status,
-                            Json(serde_json::json!({"error": {"message": message}})),
-                        )
-                            .into_response();
+                            ProcessError::ProviderError(refresh_error.user_message())
+                        });
                     }
                 }
             } else {
@@ -1315,20 +1814,29 @@ pub async fn call_provider_openai(
             }
 
             // 设置项目 ID
-            if let Some(pid) = project_id {
-                antigravity.project_id = Some(pid.clone());
-            } else if let Err(e) = antigravity.discover_project().await {
-                tracing::warn!("[Antigravity] Failed to discover project: {}", e);
-            }
+            resolve_antigravity_project_id(
+                state,
+                credential,
+                project_id.as_deref(),
+                &mut antigravity,
+            )
+            .await;
 
-            tracing::info!("[ANTIGRAVITY] request.stream = {}, model = {}, project_id = {:?}",
-                request.stream, request.model, antigravity.project_id);
+            tracing::info!(
+                "[ANTIGRAVITY] request.stream = {}, model = {}, project_id = {:?}",
+                request.stream,
+                request.model,
+                antigravity.project_id
+            );
 
             // 检查是否为流式请求
             if request.stream {
                 tracing::info!("[ANTIGRAVITY_STREAM] ========== 开始处理流式请求 ==========");
-                tracing::info!("[ANTIGRAVITY_STREAM] model={}, has_token={}",
-                    request.model, antigravity.credentials.access_token.is_some());
+                tracing::info!(
+                    "[ANTIGRAVITY_STREAM] model={}, has_token={}",
+                    request.model,
+                    antigravity.credentials.access_token.is_some()
+                );
 
                 // 检查是否是图片生成模型
                 // 注意：gemini-3-pro-image-preview 是支持图片理解的模型，不是图片生成模型
@@ -1336,7 +1844,10 @@ pub async fn call_provider_openai(
                 let is_image_generation_model = request.model == "imagen"
                     || request.model.starts_with("imagen-")
                     || request.model.contains("image-generation");
-                tracing::info!("[ANTIGRAVITY_STREAM] is_image_generation_model={}", is_image_generation_model);
+                tracing::info!(
+                    "[ANTIGRAVITY_STREAM] is_image_generation_model={}",
+                    is_image_generation_model
+                );
 
                 // 对于图片生成模型，使用非流式请求然后模拟流式返回
                 if is_image_generation_model {
@@ -1345,10 +1856,14 @@ pub async fn call_provider_openai(
                     // 获取 project_id 用于请求
                     let proj_id = antigravity.project_id.clone().unwrap_or_default();
                     // 转换请求格式 - 这已经是完整的 Antigravity 请求格式
-                    let antigravity_request = convert_openai_to_antigravity_with_context(request, &proj_id);
+                    let antigravity_request =
+                        convert_openai_to_antigravity_with_context(request, &proj_id);
 
                     // 直接调用 call_api，因为 antigravity_request 已经是完整格式
-                    match antigravity.call_api("generateContent", &antigravity_request).await {
+                    match antigravity
+                        .call_api("generateContent", &antigravity_request)
+                        .await
+                    {
                         Ok(resp) => {
                             // 保存原始响应到文件用于调试
                             let resp_str = serde_json::to_string_pretty(&resp).unwrap_or_default();
@@ -1358,20 +1873,39 @@ pub async fn call_provider_openai(
                             let _ = std::fs::create_dir_all(&debug_dir);
                             let debug_file = debug_dir.join("antigravity_image_response.json");
                             let _ = std::fs::write(&debug_file, &resp_str);
-                            tracing::info!("[ANTIGRAVITY_STREAM] 原始响应已保存到: {:?}, 大小: {} bytes", debug_file, resp_str.len());
-                            eprintln!("[ANTIGRAVITY_STREAM] 原始响应已保存到: {:?}, 大小: {} bytes", debug_file, resp_str.len());
+                            tracing::info!(
+                                "[ANTIGRAVITY_STREAM] 原始响应已保存到: {:?}, 大小: {} bytes",
+                                debug_file,
+                                resp_str.len()
+                            );
+                            eprintln!(
+                                "[ANTIGRAVITY_STREAM] 原始响应已保存到: {:?}, 大小: {} bytes",
+                                debug_file,
+                                resp_str.len()
+                            );
 
                             tracing::info!("[ANTIGRAVITY_STREAM] 图片生成完成，转换为流式响应");
 
                             // 将非流式响应转换为 OpenAI 格式
-                            let openai_response = convert_antigravity_to_openai_response(&resp, &request.model);
+                            let openai_response =
+                                convert_antigravity_to_openai_response(&resp, &request.model);
 
                             // 保存转换后的响应到文件
-                            let openai_str = serde_json::to_string_pretty(&openai_response).unwrap_or_default();
-                            let openai_debug_file = debug_dir.join("antigravity_image_openai_response.json");
+                            let openai_str =
+                                serde_json::to_string_pretty(&openai_response).unwrap_or_default();
+                            let openai_debug_file =
+                                debug_dir.join("antigravity_image_openai_response.json");
                             let _ = std::fs::write(&openai_debug_file, &openai_str);
-                            tracing::info!("[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes", openai_debug_file, openai_str.len());
-                            eprintln!("[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes", openai_debug_file, openai_str.len());
+                            tracing::info!(
+                                "[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes",
+                                openai_debug_file,
+                                openai_str.len()
+                            );
+                            eprintln!(
+                                "[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes",
+                                openai_debug_file,
+                                openai_str.len()
+                            );
 
                             // 将非流式响应转换为流式 SSE 格式
                             let model = request.model.clone();
@@ -1388,7 +1922,10 @@ pub async fn call_provider_openai(
                                 .and_then(|c| c.as_str())
                                 .unwrap_or("");
 
-                            tracing::info!("[ANTIGRAVITY_STREAM] 图片内容长度: {} 字符", content.len());
+                            tracing::info!(
+                                "[ANTIGRAVITY_STREAM] 图片内容长度: {} 字符",
+                                content.len()
+                            );
                             eprintln!("[ANTIGRAVITY_STREAM] 图片内容长度: {} 字符", content.len());
 
                             // 构建 SSE 事件
@@ -1409,7 +1946,8 @@ pub async fn call_provider_openai(
                                         "finish_reason": null
                                     }]
                                 });
-                                sse_events.push_str(&format!("data: {}\n\n", chunk_response.to_string()));
+                                sse_events
+                                    .push_str(&format!("data: {}\n\n", chunk_response.to_string()));
                             }
 
                             // 发送结束 chunk
@@ -1424,7 +1962,8 @@ pub async fn call_provider_openai(
                                     "finish_reason": "stop"
                                 }]
                             });
-                            sse_events.push_str(&format!("data: {}\n\n", done_response.to_string()));
+                            sse_events
+                                .push_str(&format!("data: {}\n\n", done_response.to_string()));
                             sse_events.push_str("data: [DONE]\n\n");
 
                             return Response::builder()
@@ -1433,21 +1972,15 @@ pub async fn call_provider_openai(
                                 .header(header::CACHE_CONTROL, "no-cache")
                                 .header(header::CONNECTION, "keep-alive")
                                 .body(Body::from(sse_events))
-                                .unwrap_or_else(|_| {
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build streaming response"}})),
+                                .map_err(|_| {
+                                    ProcessError::InternalError(
+                                        "Failed to build streaming response".to_string(),
                                     )
-                                        .into_response()
                                 });
                         }
                         Err(e) => {
                             tracing::error!("[ANTIGRAVITY_STREAM] 图片生成失败: {}", e);
-                            return (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                            )
-                                .into_response();
+                            return Err(ProcessError::provider_call(&*e, e.to_string()));
                         }
                     }
                 }
@@ -1479,24 +2012,36 @@ pub async fn call_provider_openai(
                                         all_data.push_str(&text);
 
                                         if chunk_count <= 3 {
-                                            eprintln!("[ANTIGRAVITY_STREAM] 收集 chunk #{}: {} bytes", chunk_count, bytes.len());
+                                            eprintln!(
+                                                "[ANTIGRAVITY_STREAM] 收集 chunk #{}: {} bytes",
+                                                chunk_count,
+                                                bytes.len()
+                                            );
                                         } else if chunk_count % 200 == 0 {
                                             eprintln!("[ANTIGRAVITY_STREAM] 已收集 {} 个 chunk, 总大小: {} bytes", chunk_count, all_data.len());
                                         }
                                     }
                                     Err(e) => {
-                                        eprintln!("[ANTIGRAVITY_STREAM] chunk #{} 错误: {}", chunk_count, e);
+                                        eprintln!(
+                                            "[ANTIGRAVITY_STREAM] chunk #{} 错误: {}",
+                                            chunk_count, e
+                                        );
                                         let _ = tx.send(Err(e.to_string()));
                                         return;
                                     }
                                 }
                             }
 
-                            eprintln!("[ANTIGRAVITY_STREAM] 流结束，共收集 {} 个 chunk, 总大小: {} bytes", chunk_count, all_data.len());
+                            eprintln!(
+                                "[ANTIGRAVITY_STREAM] 流结束，共收集 {} 个 chunk, 总大小: {} bytes",
+                                chunk_count,
+                                all_data.len()
+                            );
 
                             // 尝试解析累积的 JSON 数据
                             // Antigravity 返回格式: { "response": { "candidates": [...] } }
-                            let result = parse_antigravity_accumulated_response(&all_data, &model_clone);
+                            let result =
+                                parse_antigravity_accumulated_response(&all_data, &model_clone);
                             let _ = tx.send(result);
                         });
 
@@ -1530,22 +2075,14 @@ pub async fn call_provider_openai(
                             .header(header::CONNECTION, "keep-alive")
                             .header("X-Accel-Buffering", "no")
                             .body(Body::from_stream(sse_stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
+                            .map_err(|_| {
+                                ProcessError::InternalError(
+                                    "Failed to build streaming response".to_string(),
                                 )
-                                    .into_response()
                             });
                     }
                     Err(e) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::provider_call(&e, e.to_string()));
                     }
                 }
             }
@@ -1555,22 +2092,36 @@ pub async fn call_provider_openai(
             let proj_id = antigravity.project_id.clone().unwrap_or_default();
             // 转换请求格式
             let antigravity_request = convert_openai_to_antigravity_with_context(request, &proj_id);
-            match antigravity.generate_content(&request.model, &antigravity_request).await {
+            match antigravity
+                .generate_content(&request.model, &antigravity_request)
+                .await
+            {
                 Ok(resp) => {
-                    let openai_response = convert_antigravity_to_openai_response(&resp, &request.model);
-                    Json(openai_response).into_response()
+                    let mut openai_response =
+                        convert_antigravity_to_openai_response(&resp, &request.model);
+                    // Antigravity 不支持 parallel_tool_calls，在响应中只保留第一个工具调用
+                    if request.parallel_tool_calls_disabled() {
+                        let dropped = keep_first_tool_call(&mut openai_response);
+                        if dropped > 0 {
+                            tracing::info!(
+                                "[ANTIGRAVITY] parallel_tool_calls=false，丢弃了 {} 个工具调用",
+                                dropped
+                            );
+                        }
+                    }
+                    Ok(Json(openai_response).into_response())
                 }
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                )
-                    .into_response(),
+                Err(e) => Err(ProcessError::provider_call(&*e, e.to_string())),
             }
         }
         CredentialData::OpenAIKey { api_key, base_url } => {
             let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
 
-            tracing::info!("[OPENAI_KEY] request.stream = {}, model = {}", request.stream, request.model);
+            tracing::info!(
+                "[OPENAI_KEY] request.stream = {}, model = {}",
+                request.stream,
+                request.model
+            );
 
             // 检查是否为流式请求
             if request.stream {
@@ -1580,12 +2131,14 @@ pub async fn call_provider_openai(
                         tracing::info!("[OPENAI_KEY_STREAM] 开始直接转发 OpenAI SSE 流");
 
                         // OpenAI 提供商已经返回 OpenAI SSE 格式，直接转发
-                        let body_stream = stream_response.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-                            match result {
-                                Ok(bytes) => Ok(bytes),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-                            }
-                        });
+                        let body_stream = stream_response.map(
+                            |result| -> Result<axum::body::Bytes, std::io::Error> {
+                                match result {
+                                    Ok(bytes) => Ok(bytes),
+                                    Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                                }
+                            },
+                        );
 
                         return Response::builder()
                             .status(StatusCode::OK)
@@ -1595,22 +2148,14 @@ pub async fn call_provider_openai(
                             .header(header::TRANSFER_ENCODING, "chunked")
                             .header("X-Accel-Buffering", "no")
                             .body(Body::from_stream(body_stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
+                            .map_err(|_| {
+                                ProcessError::InternalError(
+                                    "Failed to build streaming response".to_string(),
                                 )
-                                    .into_response()
                             });
                     }
                     Err(e) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::provider_call(&e, e.to_string()));
                     }
                 }
             }
@@ -1618,192 +2163,123 @@ pub async fn call_provider_openai(
             // 非流式请求处理
             match openai.call_api(request).await {
                 Ok(resp) => {
+                    crate::http_client::capture_response_headers(resp.headers());
                     if resp.status().is_success() {
                         match resp.text().await {
                             Ok(body) => {
                                 if let Ok(json) = serde_json::from_str::<serde_json::Value>(&body) {
-                                    Json(json).into_response()
+                                    Ok(Json(json).into_response())
                                 } else {
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Invalid JSON response"}})),
-                                    )
-                                        .into_response()
+                                    Err(ProcessError::Conversion(
+                                        "Invalid JSON response".to_string(),
+                                    ))
                                 }
                             }
-                            Err(e) => (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                            )
-                                .into_response(),
-                        }
-                    } else {
-                        let body = resp.text().await.unwrap_or_default();
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": body}})),
-                        )
-                            .into_response()
-                    }
-                }
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                )
-                    .into_response(),
-            }
-        }
-        CredentialData::ClaudeKey { api_key, base_url } => {
-            // 打印 Claude 代理 URL 用于调试
-            let actual_base_url = base_url.as_deref().unwrap_or("https://api.anthropic.com");
-            tracing::info!(
-                "[CLAUDE] 使用 Claude API 代理: base_url={} credential_uuid={} stream={}",
-                actual_base_url,
-                &credential.uuid[..8],
-                request.stream
-            );
-            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
-
-            // 检查是否为流式请求
-            if request.stream {
-                tracing::info!("[CLAUDE_KEY_STREAM] 处理流式请求, model={}", request.model);
-
-                match claude.call_api_stream(request).await {
-                    Ok(stream_response) => {
-                        tracing::info!("[CLAUDE_KEY_STREAM] 开始转换 Anthropic SSE 到 OpenAI SSE");
-
-                        // 创建 StreamConverter 将 Anthropic SSE 转换为 OpenAI SSE
-                        let converter = std::sync::Arc::new(tokio::sync::Mutex::new(
-                            crate::streaming::converter::StreamConverter::with_model(
-                                crate::streaming::converter::StreamFormat::AnthropicSse,
-                                crate::streaming::converter::StreamFormat::OpenAiSse,
-                                &request.model,
-                            ),
-                        ));
-
-                        let converter_for_stream = converter.clone();
-                        let final_stream = async_stream::stream! {
-                            use futures::StreamExt;
-
-                            let mut stream_response = stream_response;
-
-                            while let Some(chunk_result) = stream_response.next().await {
-                                match chunk_result {
-                                    Ok(bytes) => {
-                                        // 转换 Anthropic SSE 到 OpenAI SSE
-                                        let sse_events = {
-                                            let mut converter_guard = converter_for_stream.lock().await;
-                                            converter_guard.convert(&bytes)
-                                        };
-
-                                        for sse_str in sse_events {
-                                            yield Ok::<String, crate::streaming::StreamError>(sse_str);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        tracing::error!("[CLAUDE_KEY_STREAM] 流式传输错误: {}", e);
-                                        yield Err(e);
-                                        return;
-                                    }
-                                }
-                            }
-
-                            // 流结束，生成结束事件
-                            let final_events = {
-                                let mut converter_guard = converter_for_stream.lock().await;
-                                converter_guard.finish()
-                            };
-
-                            for sse_str in final_events {
-                                yield Ok::<String, crate::streaming::StreamError>(sse_str);
-                            }
-                        };
-
-                        let body_stream = final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-                            match result {
-                                Ok(event) => Ok(axum::body::Bytes::from(event)),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-                            }
-                        });
-
-                        return Response::builder()
-                            .status(StatusCode::OK)
-                            .header(header::CONTENT_TYPE, "text/event-stream")
-                            .header(header::CACHE_CONTROL, "no-cache")
-                            .header(header::CONNECTION, "keep-alive")
-                            .header(header::TRANSFER_ENCODING, "chunked")
-                            .header("X-Accel-Buffering", "no")
-                            .body(Body::from_stream(body_stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
-                                )
-                                    .into_response()
-                            });
-                    }
-                    Err(e) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
-                            .into_response();
+                            Err(e) => Err(ProcessError::ProviderError(e.to_string())),
+                        }
+                    } else {
+                        let status = resp.status().as_u16();
+                        let body = resp.text().await.unwrap_or_default();
+                        Err(ProcessError::upstream(status, body))
                     }
                 }
+                Err(e) => Err(ProcessError::provider_call(&*e, e.to_string())),
             }
-
-            // 非流式请求处理
-            match claude.call_openai_api(request).await {
-                Ok(resp) => Json(resp).into_response(),
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                )
-                    .into_response(),
-            }
         }
-        CredentialData::VertexKey { api_key, base_url, model_aliases } => {
+        CredentialData::ClaudeKey { api_key, base_url } => {
+            // 打印 Claude 代理 URL 用于调试
+            let actual_base_url = base_url.as_deref().unwrap_or("https://api.anthropic.com");
+            tracing::info!(
+                "[CLAUDE] 使用 Claude API 代理: base_url={} credential_uuid={} stream={}",
+                actual_base_url,
+                &credential.uuid[..8],
+                request.stream
+            );
+            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            call_claude_openai(credential, &claude, request).await
+        }
+        CredentialData::ClaudeOAuth { .. } => {
+            let db = state
+                .db
+                .as_ref()
+                .ok_or_else(|| ProcessError::InternalError("Database not available".to_string()))?;
+            let token = match state
+                .token_cache
+                .get_valid_token(db, &credential.uuid)
+                .await
+            {
+                Ok(token) => token,
+                Err(e) => {
+                    let _ = state.pool_service.mark_unhealthy(
+                        db,
+                        &credential.uuid,
+                        Some(&format!("Token refresh failed: {}", e)),
+                    );
+                    return Err(ProcessError::AuthError(format!(
+                        "Failed to get Claude OAuth token: {}",
+                        e
+                    )));
+                }
+            };
+            tracing::info!(
+                "[CLAUDE_OAUTH] 使用 Claude OAuth 凭证: credential_uuid={} stream={}",
+                &credential.uuid[..8],
+                request.stream
+            );
+            let claude = ClaudeCustomProvider::with_oauth_token(token);
+            call_claude_openai(credential, &claude, request).await
+        }
+        CredentialData::VertexKey {
+            api_key,
+            base_url,
+            model_aliases,
+        } => {
             // Resolve model alias if present
-            let resolved_model = model_aliases.get(&request.model).cloned().unwrap_or_else(|| request.model.clone());
+            let resolved_model = model_aliases
+                .get(&request.model)
+                .cloned()
+                .unwrap_or_else(|| request.model.clone());
             let mut modified_request = request.clone();
             modified_request.model = resolved_model;
             let vertex = VertexProvider::with_config(api_key.clone(), base_url.clone());
-            match vertex.chat_completions(&serde_json::to_value(&modified_request).unwrap_or_default()).await {
+            match vertex
+                .chat_completions(&serde_json::to_value(&modified_request).unwrap_or_default())
+                .await
+            {
                 Ok(resp) => {
+                    crate::http_client::capture_response_headers(resp.headers());
                     if resp.status().is_success() {
                         match resp.text().await {
                             Ok(body) => {
                                 if let Ok(json) = serde_json::from_str::<serde_json::Value>(&body) {
-                                    Json(json).into_response()
+                                    Ok(Json(json).into_response())
                                 } else {
-                                    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": "Invalid JSON response"}}))).into_response()
+                                    Err(ProcessError::Conversion(
+                                        "Invalid JSON response".to_string(),
+                                    ))
                                 }
                             }
-                            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response(),
+                            Err(e) => Err(ProcessError::ProviderError(e.to_string())),
                         }
                     } else {
+                        let status = resp.status().as_u16();
                         let body = resp.text().await.unwrap_or_default();
-                        (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": body}}))).into_response()
+                        Err(ProcessError::upstream(status, body))
                     }
                 }
-                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response(),
+                Err(e) => Err(ProcessError::provider_call(&*e, e.to_string())),
             }
         }
         // Gemini API Key credentials - not supported for OpenAI format yet
-        CredentialData::GeminiApiKey { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "Gemini API Key credentials do not support OpenAI format yet"}})),
-            )
-                .into_response()
-        }
+        CredentialData::GeminiApiKey { .. } => Err(ProcessError::Unsupported(
+            "Gemini API Key credentials do not support OpenAI format yet".to_string(),
+        )),
         // AnthropicKey - 如果有自定义 base_url，使用 OpenAI 兼容格式调用
         CredentialData::AnthropicKey { api_key, base_url } => {
             // 如果有自定义 base_url，假设是 OpenAI 兼容的代理服务器
             if let Some(custom_url) = base_url {
-                let openai = OpenAICustomProvider::with_config(api_key.clone(), Some(custom_url.clone()));
+                let openai =
+                    OpenAICustomProvider::with_config(api_key.clone(), Some(custom_url.clone()));
                 state.logs.write().await.add(
                     "info",
                     &format!(
@@ -1815,22 +2291,22 @@ pub async fn call_provider_openai(
                 );
                 match openai.call_api(request).await {
                     Ok(resp) => {
+                        crate::http_client::capture_response_headers(resp.headers());
                         let status = resp.status();
                         state.logs.write().await.add(
                             "info",
                             &format!(
                                 "[OPENAI_COMPAT] 响应状态: status={} model={} stream={}",
-                                status,
-                                request.model,
-                                request.stream
+                                status, request.model, request.stream
                             ),
                         );
 
                         if request.stream && status.is_success() {
-                            state.logs.write().await.add(
-                                "info",
-                                "[OPENAI_COMPAT] 流式请求，透传 SSE 响应",
-                            );
+                            state
+                                .logs
+                                .write()
+                                .await
+                                .add("info", "[OPENAI_COMPAT] 流式请求，透传 SSE 响应");
                             if let Some(db) = &state.db {
                                 let _ = state.pool_service.mark_healthy(
                                     db,
@@ -1843,17 +2319,18 @@ pub async fn call_provider_openai(
                             return Response::builder()
                                 .status(StatusCode::OK)
                                 .header(header::CONTENT_TYPE, "text/event-stream")
-                                .header(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")
+                                .header(
+                                    header::CACHE_CONTROL,
+                                    "no-cache, no-store, must-revalidate",
+                                )
                                 .header("Connection", "keep-alive")
                                 .header("X-Accel-Buffering", "no") // 禁用 nginx 等代理的缓冲
                                 .header("Transfer-Encoding", "chunked")
                                 .body(Body::from_stream(stream))
-                                .unwrap_or_else(|_| {
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
+                                .map_err(|_| {
+                                    ProcessError::InternalError(
+                                        "Failed to build stream response".to_string(),
                                     )
-                                        .into_response()
                                 });
                         }
 
@@ -1882,18 +2359,15 @@ pub async fn call_provider_openai(
                                 .status(status)
                                 .header(header::CONTENT_TYPE, "application/json")
                                 .body(Body::from(body))
-                                .unwrap_or_else(|_| {
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
+                                .map_err(|_| {
+                                    ProcessError::InternalError(
+                                        "Failed to build response".to_string(),
                                     )
-                                        .into_response()
                                 }),
-                            Err(e) => (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": format!("Failed to read response: {}", e)}})),
-                            )
-                                .into_response(),
+                            Err(e) => Err(ProcessError::ProviderError(format!(
+                                "Failed to read response: {}",
+                                e
+                            ))),
                         }
                     }
                     Err(e) => {
@@ -1904,20 +2378,15 @@ pub async fn call_provider_openai(
                                 Some(&format!("API call failed: {}", e)),
                             );
                         }
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("OpenAI compatible API call failed: {}", e)}})),
-                        )
-                            .into_response()
+                        Err(ProcessError::provider_call(
+                            &*e,
+                            format!("OpenAI compatible API call failed: {}", e),
+                        ))
                     }
                 }
             } else {
                 // 没有自定义 base_url，不支持 OpenAI 格式
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({"error": {"message": "AnthropicKey without custom base_url does not support OpenAI format. Use Anthropic format endpoint instead."}})),
-                )
-                    .into_response()
+                Err(ProcessError::Unsupported("AnthropicKey without custom base_url does not support OpenAI format. Use Anthropic format endpoint instead.".to_string()))
             }
         }
         // IFlow 凭证类型 - 支持 OpenAI 格式
@@ -1925,11 +2394,9 @@ pub async fn call_provider_openai(
             let db = match &state.db {
                 Some(db) => db,
                 None => {
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": "Database not available"}})),
-                    )
-                        .into_response();
+                    return Err(ProcessError::InternalError(
+                        "Database not available".to_string(),
+                    ));
                 }
             };
 
@@ -1949,11 +2416,10 @@ pub async fn call_provider_openai(
                             &credential.uuid,
                             Some(&format!("Failed to load IFlow credentials: {}", e)),
                         );
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load IFlow credentials: {}", e)}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::CredentialPoolError(format!(
+                            "Failed to load IFlow credentials: {}",
+                            e
+                        )));
                     }
                     if let Err(e) = iflow.ensure_valid_token().await {
                         let _ = state.pool_service.mark_unhealthy(
@@ -1961,11 +2427,10 @@ pub async fn call_provider_openai(
                             &credential.uuid,
                             Some(&format!("IFlow token refresh failed: {}", e)),
                         );
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("IFlow token refresh failed: {}", e)}})),
-                        )
-                            .into_response();
+                        return Err(ProcessError::AuthError(format!(
+                            "IFlow token refresh failed: {}",
+                            e
+                        )));
                     }
                     iflow.credentials.access_token.unwrap_or_default()
                 }
@@ -1974,6 +2439,35 @@ pub async fn call_provider_openai(
             let mut iflow = IFlowProvider::new();
             iflow.credentials.access_token = Some(token);
 
+            if request.stream {
+                return match iflow.call_api_stream(request).await {
+                    Ok(stream_response) => {
+                        openai_passthrough_stream_response(
+                            state,
+                            credential,
+                            &request.model,
+                            stream_response,
+                            flow_id,
+                        )
+                        .await
+                    }
+                    Err(e) => {
+                        tracing::error!("[IFlow] Streaming API call failed: {}", e);
+                        if let Some(db) = &state.db {
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&format!("Stream error: {}", e)),
+                            );
+                        }
+                        Err(ProcessError::provider_call(
+                            &e,
+                            format!("IFlow API call failed: {}", e),
+                        ))
+                    }
+                };
+            }
+
             let request_json = serde_json::to_value(request).unwrap_or_default();
             match iflow.call_api(&request_json).await {
                 Ok(response) => {
@@ -1986,38 +2480,64 @@ pub async fn call_provider_openai(
                             for (key, value) in headers.iter() {
                                 response_builder = response_builder.header(key, value);
                             }
-                            response_builder.body(Body::from(body)).unwrap_or_else(|_| {
-                                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response()
+                            response_builder.body(Body::from(body)).map_err(|_| {
+                                ProcessError::InternalError("Failed to build response".to_string())
                             })
                         }
                         Err(e) => {
                             tracing::error!("[IFlow] Failed to read response body: {}", e);
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": format!("Failed to read IFlow response: {}", e)}})),
-                            )
-                                .into_response()
+                            Err(ProcessError::ProviderError(format!(
+                                "Failed to read IFlow response: {}",
+                                e
+                            )))
                         }
                     }
                 }
                 Err(e) => {
                     tracing::error!("[IFlow] API call failed: {}", e);
-                    (
-                        StatusCode::BAD_GATEWAY,
-                        Json(serde_json::json!({"error": {"message": format!("IFlow API call failed: {}", e)}})),
-                    )
-                        .into_response()
+                    Err(ProcessError::provider_call(
+                        &*e,
+                        format!("IFlow API call failed: {}", e),
+                    ))
                 }
             }
         }
         CredentialData::IFlowCookie { creds_file_path } => {
             let mut iflow = IFlowProvider::new();
             if let Err(e) = iflow.load_credentials_from_path(creds_file_path).await {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load IFlow credentials: {}", e)}})),
-                )
-                    .into_response();
+                return Err(ProcessError::CredentialPoolError(format!(
+                    "Failed to load IFlow credentials: {}",
+                    e
+                )));
+            }
+
+            if request.stream {
+                return match iflow.call_api_stream(request).await {
+                    Ok(stream_response) => {
+                        openai_passthrough_stream_response(
+                            state,
+                            credential,
+                            &request.model,
+                            stream_response,
+                            flow_id,
+                        )
+                        .await
+                    }
+                    Err(e) => {
+                        tracing::error!("[IFlow] Streaming API call failed: {}", e);
+                        if let Some(db) = &state.db {
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&format!("Stream error: {}", e)),
+                            );
+                        }
+                        Err(ProcessError::provider_call(
+                            &e,
+                            format!("IFlow API call failed: {}", e),
+                        ))
+                    }
+                };
             }
 
             let request_json = serde_json::to_value(request).unwrap_or_default();
@@ -2032,40 +2552,136 @@ pub async fn call_provider_openai(
                             for (key, value) in headers.iter() {
                                 response_builder = response_builder.header(key, value);
                             }
-                            response_builder.body(Body::from(body)).unwrap_or_else(|_| {
-                                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response()
+                            response_builder.body(Body::from(body)).map_err(|_| {
+                                ProcessError::InternalError("Failed to build response".to_string())
                             })
                         }
                         Err(e) => {
                             tracing::error!("[IFlow] Failed to read response body: {}", e);
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": format!("Failed to read IFlow response: {}", e)}})),
-                            )
-                                .into_response()
+                            Err(ProcessError::ProviderError(format!(
+                                "Failed to read IFlow response: {}",
+                                e
+                            )))
                         }
                     }
                 }
                 Err(e) => {
                     tracing::error!("[IFlow] API call failed: {}", e);
-                    (
-                        StatusCode::BAD_GATEWAY,
-                        Json(serde_json::json!({"error": {"message": format!("IFlow API call failed: {}", e)}})),
-                    )
-                        .into_response()
+                    Err(ProcessError::provider_call(
+                        &*e,
+                        format!("IFlow API call failed: {}", e),
+                    ))
                 }
             }
         }
         // 新增的凭证类型暂不支持 OpenAI 格式
-        CredentialData::CodexOAuth { .. }
-        | CredentialData::ClaudeOAuth { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "This credential type does not support OpenAI format yet"}})),
-            )
-                .into_response()
+        CredentialData::CodexOAuth { .. } => Err(ProcessError::Unsupported(
+            "This credential type does not support OpenAI format yet".to_string(),
+        )),
+    }
+}
+
+/// 以 OpenAI 格式调用 Claude（API Key 或 OAuth）
+///
+/// 流式响应的 Anthropic SSE 转换为 `chat.completion.chunk` 事件。
+async fn call_claude_openai(
+    credential: &ProviderCredential,
+    claude: &ClaudeCustomProvider,
+    request: &ChatCompletionRequest,
+) -> Result<Response, ProcessError> {
+    // 检查是否为流式请求
+    if request.stream {
+        tracing::info!("[CLAUDE_KEY_STREAM] 处理流式请求, model={}", request.model);
+
+        match claude.call_api_stream(request).await {
+            Ok(stream_response) => {
+                // 客户端请求的是 OpenAI 端点，上游返回 Anthropic SSE，
+                // 需要转换为 chat.completion.chunk 事件
+                let source_format = get_stream_format_for_credential(credential);
+                tracing::info!(
+                    "[CLAUDE_KEY_STREAM] 开始转换 {:?} 到 OpenAI SSE",
+                    source_format
+                );
+
+                let converter = std::sync::Arc::new(tokio::sync::Mutex::new(
+                    crate::streaming::converter::StreamConverter::with_model(
+                        source_format,
+                        StreamingFormat::OpenAiSse,
+                        &request.model,
+                    ),
+                ));
+
+                let converter_for_stream = converter.clone();
+                let final_stream = async_stream::stream! {
+                    use futures::StreamExt;
+
+                    let mut stream_response = stream_response;
+
+                    while let Some(chunk_result) = stream_response.next().await {
+                        match chunk_result {
+                            Ok(bytes) => {
+                                // 转换 Anthropic SSE 到 OpenAI SSE
+                                let sse_events = {
+                                    let mut converter_guard = converter_for_stream.lock().await;
+                                    converter_guard.convert(&bytes)
+                                };
+
+                                for sse_str in sse_events {
+                                    yield Ok::<String, crate::streaming::StreamError>(sse_str);
+                                }
+                            }
+                            Err(e) => {
+                                tracing::error!("[CLAUDE_KEY_STREAM] 流式传输错误: {}", e);
+                                yield Err(e);
+                                return;
+                            }
+                        }
+                    }
+
+                    // 流结束，生成结束事件
+                    let final_events = {
+                        let mut converter_guard = converter_for_stream.lock().await;
+                        converter_guard.finish()
+                    };
+
+                    for sse_str in final_events {
+                        yield Ok::<String, crate::streaming::StreamError>(sse_str);
+                    }
+                };
+
+                let body_stream =
+                    final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
+                        match result {
+                            Ok(event) => Ok(axum::body::Bytes::from(event)),
+                            Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                        }
+                    });
+
+                return Response::builder()
+                    .status(StatusCode::OK)
+                    .header(header::CONTENT_TYPE, "text/event-stream")
+                    .header(header::CACHE_CONTROL, "no-cache")
+                    .header(header::CONNECTION, "keep-alive")
+                    .header(header::TRANSFER_ENCODING, "chunked")
+                    .header("X-Accel-Buffering", "no")
+                    .body(Body::from_stream(body_stream))
+                    .map_err(|_| {
+                        ProcessError::InternalError(
+                            "Failed to build streaming response".to_string(),
+                        )
+                    });
+            }
+            Err(e) => {
+                return Err(ProcessError::provider_call(&e, e.to_string()));
+            }
         }
     }
+
+    // 非流式请求处理
+    match claude.call_openai_api(request).await {
+        Ok(resp) => Ok(Json(resp).into_response()),
+        Err(e) => Err(ProcessError::provider_call(&*e, e.to_string())),
+    }
 }
 
 // ============================================================================
@@ -2084,7 +2700,9 @@ pub async fn call_provider_openai(
 pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> StreamingFormat {
     match &credential.credential {
         CredentialData::KiroOAuth { .. } => StreamingFormat::AwsEventStream,
-        CredentialData::ClaudeKey { .. } => StreamingFormat::AnthropicSse,
+        CredentialData::ClaudeKey { .. } | CredentialData::ClaudeOAuth { .. } => {
+            StreamingFormat::AnthropicSse
+        }
         CredentialData::OpenAIKey { .. } => StreamingFormat::OpenAiSse,
         // TODO: 任务 6 完成后，将这些改为 GeminiStream
         CredentialData::AntigravityOAuth { .. } => StreamingFormat::OpenAiSse,
@@ -2203,12 +2821,7 @@ pub async fn handle_streaming_response(
         .header("X-Accel-Buffering", "no")
         .body(managed_stream)
         .unwrap_or_else(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
-            )
+            ProcessError::InternalError("Failed to build streaming response".to_string())
                 .into_response()
         })
 }
@@ -2316,12 +2929,7 @@ pub async fn handle_streaming_response_with_timeout(
         .header("X-Accel-Buffering", "no")
         .body(Body::from_stream(body_stream))
         .unwrap_or_else(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
-            )
+            ProcessError::InternalError("Failed to build streaming response".to_string())
                 .into_response()
         })
 }
@@ -2483,12 +3091,7 @@ pub async fn handle_streaming_with_disconnect_detection(
         .header("X-Accel-Buffering", "no")
         .body(body_stream)
         .unwrap_or_else(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
-            )
+            ProcessError::InternalError("Failed to build streaming response".to_string())
                 .into_response()
         })
 }
@@ -2600,7 +3203,8 @@ pub async fn handle_kiro_stream(
     credential: &ProviderCredential,
     request: &AnthropicMessagesRequest,
     flow_id: Option<&str>,
-) -> Response {
+    max_cost_usd: Option<f64>,
+) -> Result<Response, ProcessError> {
     tracing::info!(
         "[KIRO_STREAM] handle_kiro_stream 被调用, model={}, flow_id={:?}",
         request.model,
@@ -2612,11 +3216,9 @@ pub async fn handle_kiro_stream(
         CredentialData::KiroOAuth { creds_file_path } => creds_file_path.clone(),
         _ => {
             tracing::error!("[KIRO_STREAM] 无效的凭证类型");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": "Invalid credential type for Kiro stream"}})),
-            )
-                .into_response();
+            return Err(ProcessError::InternalError(
+                "Invalid credential type for Kiro stream".to_string(),
+            ));
         }
     };
 
@@ -2627,11 +3229,9 @@ pub async fn handle_kiro_stream(
         Some(db) => db,
         None => {
             tracing::error!("[KIRO_STREAM] 数据库不可用");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": "Database not available"}})),
-            )
-                .into_response();
+            return Err(ProcessError::InternalError(
+                "Database not available".to_string(),
+            ));
         }
     };
 
@@ -2655,11 +3255,10 @@ pub async fn handle_kiro_stream(
                     &credential.uuid,
                     Some(&format!("Failed to load credentials: {}", e)),
                 );
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
-                )
-                    .into_response();
+                return Err(ProcessError::CredentialPoolError(format!(
+                    "Failed to load Kiro credentials: {}",
+                    e
+                )));
             }
             if let Err(e) = kiro.refresh_token().await {
                 let _ = state.pool_service.mark_unhealthy(
@@ -2667,11 +3266,10 @@ pub async fn handle_kiro_stream(
                     &credential.uuid,
                     Some(&format!("Token refresh failed: {}", e)),
                 );
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
-                )
-                    .into_response();
+                return Err(ProcessError::AuthError(format!(
+                    "Token refresh failed: {}",
+                    e
+                )));
             }
             kiro.credentials.access_token.unwrap_or_default()
         }
@@ -2722,20 +3320,19 @@ pub async fn handle_kiro_stream(
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {}", refresh_err)),
                         );
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({
-                                "error": {
-                                    "type": "authentication_error",
-                                    "message": format!("Token refresh failed: {}", refresh_err)
-                                }
-                            })),
-                        )
-                            .into_response();
+                        return Err(ProcessError::AuthError(format!(
+                            "Token refresh failed: {}",
+                            refresh_err
+                        )));
                     }
                 };
 
                 // 使用新 token 重试（需求 4.2）
+                record_captured_step(TraceStepKind::Retry {
+                    attempt: 1,
+                    status_code: None,
+                    error: e.to_string(),
+                });
                 kiro.credentials.access_token = Some(new_token);
                 match kiro.call_api_stream_anthropic(request).await {
                     Ok(stream) => stream,
@@ -2745,16 +3342,10 @@ pub async fn handle_kiro_stream(
                             &credential.uuid,
                             Some(&retry_err.to_string()),
                         );
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({
-                                "error": {
-                                    "type": "api_error",
-                                    "message": format!("Retry failed after token refresh: {}", retry_err)
-                                }
-                            })),
-                        )
-                            .into_response();
+                        return Err(ProcessError::provider_call(
+                            &retry_err,
+                            format!("Retry failed after token refresh: {}", retry_err),
+                        ));
                     }
                 }
             } else {
@@ -2762,16 +3353,7 @@ pub async fn handle_kiro_stream(
                     state
                         .pool_service
                         .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "error": {
-                            "type": "api_error",
-                            "message": e.to_string()
-                        }
-                    })),
-                )
-                    .into_response();
+                return Err(ProcessError::provider_call(&e, e.to_string()));
             }
         }
     };
@@ -2789,7 +3371,15 @@ pub async fn handle_kiro_stream(
     );
 
     // 使用新的统一流处理管道 (Kiro → Anthropic)
-    let config = PipelineConfig::kiro_to_anthropic(request.model.clone());
+    // Kiro 不支持服务端 stop_sequences 和 disable_parallel_tool_use，由管道在客户端处理
+    // 费用上限按请求消息长度估算输入 token
+    let input_tokens = serde_json::to_string(&request.messages)
+        .map(|s| cost_guard::estimate_tokens(s.len()))
+        .unwrap_or(0);
+    let config = PipelineConfig::kiro_to_anthropic(request.model.clone())
+        .with_stop_sequences(request.stop_sequences.clone().unwrap_or_default())
+        .with_single_tool_call(request.parallel_tool_use_disabled())
+        .with_cost_guard(max_cost_usd, input_tokens);
     let pipeline = std::sync::Arc::new(tokio::sync::Mutex::new(StreamPipeline::new(config)));
 
     // 获取 flow_id 的克隆用于回调
@@ -2805,6 +3395,15 @@ pub async fn handle_kiro_stream(
     let flow_id_for_finalize = flow_id_owned.clone();
     let flow_monitor_for_finalize = flow_monitor.clone();
 
+    let cost_abort = CostAbortSlot::default();
+    let cost_abort_for_stream = cost_abort.clone();
+    let model = request.model.clone();
+
+    // 流式传输中途失败时写入死信记录
+    let state_for_dead_letter = state.clone();
+    let credential_for_dead_letter = credential.clone();
+    let request_for_dead_letter = request.clone();
+
     let final_stream = async_stream::stream! {
         use futures::StreamExt;
 
@@ -2820,9 +3419,10 @@ pub async fn handle_kiro_stream(
                     );
 
                     // 使用 Pipeline 处理字节块
-                    let sse_strings = {
+                    let (sse_strings, stopped) = {
                         let mut pipeline_guard = pipeline_clone.lock().await;
-                        pipeline_guard.process_chunk(&bytes)
+                        let events = pipeline_guard.process_chunk(&bytes);
+                        (events, pipeline_guard.is_stopped())
                     };
 
                     // 调试日志：记录生成的 SSE 事件数量
@@ -2868,23 +3468,18 @@ pub async fn handle_kiro_stream(
                         // 立即 yield SSE 事件
                         yield Ok::<String, StreamError>(sse_str);
                     }
+
+                    if stopped {
+                        tracing::info!("[KIRO_STREAM] 命中停止序列或费用上限，提前结束读取");
+                        break;
+                    }
                 }
                 Err(e) => {
                     // 需求 5.1, 5.3: 流式传输期间发生错误时，发出错误事件并以失败状态完成 flow
                     tracing::error!("[KIRO_STREAM] 流式传输期间发生错误: {}", e);
 
                     // 根据 StreamError 类型映射到 FlowErrorType
-                    let flow_error_type = match &e {
-                        StreamError::Network(_) => FlowErrorType::Network,
-                        StreamError::Timeout => FlowErrorType::Timeout,
-                        StreamError::ProviderError { status, .. } => {
-                            FlowErrorType::from_status_code(*status)
-                        }
-                        StreamError::ParseError(_) => FlowErrorType::Other,
-                        StreamError::ClientDisconnected => FlowErrorType::Cancelled,
-                        StreamError::BufferOverflow => FlowErrorType::Other,
-                        StreamError::Internal(_) => FlowErrorType::ServerError,
-                    };
+                    let flow_error_type = flow_error_type_for_stream_error(&e);
 
                     // 调用 FlowMonitor.fail_flow() 标记失败
                     if let Some(ref fid) = flow_id_for_stream {
@@ -2895,6 +3490,17 @@ pub async fn handle_kiro_stream(
                         flow_monitor_for_stream.fail_flow(fid, flow_error).await;
                     }
 
+                    if !matches!(e, StreamError::ClientDisconnected) {
+                        dead_letter::record(
+                            &state_for_dead_letter,
+                            &credential_for_dead_letter,
+                            "/v1/messages",
+                            &request_for_dead_letter.model,
+                            &request_for_dead_letter,
+                            &ProcessError::ProviderError(format!("流式传输错误: {}", e)),
+                        );
+                    }
+
                     // 发送 SSE 错误事件
                     yield Err(e);
                     return;
@@ -2907,6 +3513,9 @@ pub async fn handle_kiro_stream(
         // 流结束，使用 Pipeline 生成 finalize 事件
         let final_events = {
             let mut pipeline_guard = pipeline_for_finalize.lock().await;
+            if let Some(guard) = pipeline_guard.cost_aborted() {
+                cost_abort_for_stream.record(ProviderType::Kiro, &model, guard);
+            }
             pipeline_guard.finish()
         };
 
@@ -2963,16 +3572,138 @@ pub async fn handle_kiro_stream(
         .header(header::CONNECTION, "keep-alive")
         .header(header::TRANSFER_ENCODING, "chunked")
         .header("X-Accel-Buffering", "no")
+        .extension(cost_abort)
         .body(Body::from_stream(body_stream))
-        .unwrap_or_else(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
-            )
-                .into_response()
-        })
+        .map_err(|_| ProcessError::InternalError("Failed to build streaming response".to_string()))
+}
+
+/// 将 StreamError 映射为 Flow 错误类型
+fn flow_error_type_for_stream_error(e: &StreamError) -> FlowErrorType {
+    match e {
+        StreamError::Network(_) => FlowErrorType::Network,
+        StreamError::Timeout => FlowErrorType::Timeout,
+        StreamError::ProviderError { status, .. } => FlowErrorType::from_status_code(*status),
+        StreamError::ParseError(_) => FlowErrorType::Other,
+        StreamError::ClientDisconnected => FlowErrorType::Cancelled,
+        StreamError::BufferOverflow => FlowErrorType::Other,
+        StreamError::Internal(_) => FlowErrorType::ServerError,
+    }
+}
+
+/// 将一个 SSE 事件记录到 Flow Monitor（异步执行，不阻塞转发）
+fn record_flow_sse_event(state: &AppState, flow_id: &str, sse_str: &str) {
+    let mut event_type: Option<&str> = None;
+    let mut data: Option<&str> = None;
+    for line in sse_str.lines() {
+        if let Some(value) = line.strip_prefix("event: ") {
+            event_type = Some(value);
+        } else if let Some(value) = line.strip_prefix("data: ") {
+            data = Some(value);
+        }
+    }
+
+    if let Some(d) = data {
+        let flow_monitor = state.flow_monitor.clone();
+        let fid = flow_id.to_string();
+        let event_type = event_type.map(|s| s.to_string());
+        let data = d.to_string();
+        tokio::spawn(async move {
+            flow_monitor
+                .process_chunk(&fid, event_type.as_deref(), &data)
+                .await;
+        });
+    }
+}
+
+/// 直通 OpenAI 兼容上游（Qwen、iFlow）的 SSE 流
+///
+/// 通过 StreamPipeline（OpenAI → OpenAI 直通）按完整事件转发，逐事件记录到 Flow Monitor，
+/// 流结束时补齐未以空行结尾的最后一个事件；传输出错时以失败状态结束 flow。
+/// 凭证健康状态在流结束时更新：正常结束标记为健康，传输出错标记为不健康。
+async fn openai_passthrough_stream_response(
+    state: &AppState,
+    credential: &ProviderCredential,
+    model: &str,
+    stream_response: StreamResponse,
+    flow_id: Option<&str>,
+) -> Result<Response, ProcessError> {
+    if let Some(db) = &state.db {
+        let _ = state.pool_service.record_usage(db, &credential.uuid);
+    }
+    if let Some(fid) = flow_id {
+        state
+            .flow_monitor
+            .set_streaming(fid, StreamFormat::OpenAI)
+            .await;
+    }
+
+    let mut pipeline = StreamPipeline::new(PipelineConfig::openai_passthrough(model.to_string()));
+    let state = state.clone();
+    let uuid = credential.uuid.clone();
+    let model = model.to_string();
+    let flow_id = flow_id.map(|s| s.to_string());
+    let final_stream = async_stream::stream! {
+        let mut stream_response = stream_response;
+
+        while let Some(chunk_result) = stream_response.next().await {
+            match chunk_result {
+                Ok(bytes) => {
+                    for sse_str in pipeline.process_chunk(&bytes) {
+                        if let Some(ref fid) = flow_id {
+                            record_flow_sse_event(&state, fid, &sse_str);
+                        }
+                        yield Ok::<String, StreamError>(sse_str);
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("[OPENAI_PASSTHROUGH] 流式传输期间发生错误: {}", e);
+                    if let Some(db) = &state.db {
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &uuid,
+                            Some(&format!("Stream error: {}", e)),
+                        );
+                    }
+                    if let Some(ref fid) = flow_id {
+                        let flow_error = FlowError::new(
+                            flow_error_type_for_stream_error(&e),
+                            format!("流式传输错误: {}", e),
+                        );
+                        state.flow_monitor.fail_flow(fid, flow_error).await;
+                    }
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+
+        if let Some(db) = &state.db {
+            let _ = state.pool_service.mark_healthy(db, &uuid, Some(&model));
+        }
+        for sse_str in pipeline.finish() {
+            if let Some(ref fid) = flow_id {
+                record_flow_sse_event(&state, fid, &sse_str);
+            }
+            yield Ok::<String, StreamError>(sse_str);
+        }
+    };
+
+    let body_stream = final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
+        match result {
+            Ok(event) => Ok(axum::body::Bytes::from(event)),
+            Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .header(header::TRANSFER_ENCODING, "chunked")
+        .header("X-Accel-Buffering", "no")
+        .body(Body::from_stream(body_stream))
+        .map_err(|_| ProcessError::InternalError("Failed to build streaming response".to_string()))
 }
 
 /// 解析 Antigravity 累积的流式响应数据
@@ -3027,8 +3758,7 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
     // 如果失败，尝试按行解析，找到包含 candidates 的 JSON
     eprintln!("[ANTIGRAVITY_PARSE] 单个 JSON 解析失败，尝试按行解析");
 
-    let mut all_text = String::new();
-    let mut all_images: Vec<(String, String)> = Vec::new(); // (mime_type, data)
+    let mut all_content = ExtractedContent::default();
     let mut found_any = false;
 
     for line in data.lines() {
@@ -3039,9 +3769,8 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
 
         // 尝试解析每一行
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
-            if let Some((text, images)) = extract_content_from_json(&json) {
-                all_text.push_str(&text);
-                all_images.extend(images);
+            if let Some(content) = extract_content_from_json(&json) {
+                all_content.append(content);
                 found_any = true;
             }
         }
@@ -3050,10 +3779,10 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
     if found_any {
         eprintln!(
             "[ANTIGRAVITY_PARSE] 按行解析成功，文本长度: {}, 图片数: {}",
-            all_text.len(),
-            all_images.len()
+            all_content.text.len(),
+            all_content.images.len()
         );
-        return build_sse_response(&all_text, &all_images, model);
+        return build_sse_response(&all_content, model);
     }
 
     // 如果还是失败，尝试找到 JSON 对象的边界
@@ -3077,8 +3806,30 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
     Err(format!("无法解析响应数据，请查看 {:?}", debug_file))
 }
 
+/// 从 JSON 中提取的响应内容
+#[derive(Default)]
+struct ExtractedContent {
+    text: String,
+    /// 思维链内容（`thought: true` 的部分）
+    reasoning: String,
+    /// (mime_type, base64 data)
+    images: Vec<(String, String)>,
+}
+
+impl ExtractedContent {
+    fn append(&mut self, other: ExtractedContent) {
+        self.text.push_str(&other.text);
+        self.reasoning.push_str(&other.reasoning);
+        self.images.extend(other.images);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.reasoning.is_empty() && self.images.is_empty()
+    }
+}
+
 /// 从 JSON 中提取内容
-fn extract_content_from_json(json: &serde_json::Value) -> Option<(String, Vec<(String, String)>)> {
+fn extract_content_from_json(json: &serde_json::Value) -> Option<ExtractedContent> {
     // 尝试多种路径
     let candidates = json
         .get("response")
@@ -3147,19 +3898,16 @@ fn extract_content_from_json(json: &serde_json::Value) -> Option<(String, Vec<(S
         }
     }
 
-    // 如果有 thinking 内容，用 <thinking> 标签包裹并放在前面
-    let mut final_text = String::new();
-    if !thinking_text.is_empty() {
-        final_text.push_str("<thinking>");
-        final_text.push_str(&thinking_text);
-        final_text.push_str("</thinking>\n\n");
-    }
-    final_text.push_str(&text);
-
-    if final_text.is_empty() && images.is_empty() {
+    // thinking 内容单独返回，不混入正文
+    let extracted = ExtractedContent {
+        text,
+        reasoning: thinking_text,
+        images,
+    };
+    if extracted.is_empty() {
         None
     } else {
-        Some((final_text, images))
+        Some(extracted)
     }
 }
 
@@ -3183,25 +3931,23 @@ fn parse_antigravity_json(json: &serde_json::Value, model: &str) -> Result<Strin
         );
     }
 
-    if let Some((text, images)) = extract_content_from_json(json) {
-        return build_sse_response(&text, &images, model);
+    if let Some(content) = extract_content_from_json(json) {
+        return build_sse_response(&content, model);
     }
 
     // 如果是数组，尝试处理每个元素
     if let Some(arr) = json.as_array() {
         eprintln!("[ANTIGRAVITY_PARSE] 顶层是数组，长度: {}", arr.len());
-        let mut all_text = String::new();
-        let mut all_images = Vec::new();
+        let mut all_content = ExtractedContent::default();
 
         for item in arr {
-            if let Some((text, images)) = extract_content_from_json(item) {
-                all_text.push_str(&text);
-                all_images.extend(images);
+            if let Some(content) = extract_content_from_json(item) {
+                all_content.append(content);
             }
         }
 
-        if !all_text.is_empty() || !all_images.is_empty() {
-            return build_sse_response(&all_text, &all_images, model);
+        if !all_content.is_empty() {
+            return build_sse_response(&all_content, model);
         }
     }
 
@@ -3209,15 +3955,11 @@ fn parse_antigravity_json(json: &serde_json::Value, model: &str) -> Result<Strin
 }
 
 /// 构建 SSE 响应
-fn build_sse_response(
-    text: &str,
-    images: &[(String, String)],
-    model: &str,
-) -> Result<String, String> {
-    let mut content = text.to_string();
+fn build_sse_response(extracted: &ExtractedContent, model: &str) -> Result<String, String> {
+    let mut content = extracted.text.clone();
 
     // 添加图片
-    for (mime, data) in images {
+    for (mime, data) in &extracted.images {
         let image_url = format!("data:{};base64,{}", mime, data);
         content.push_str(&format!("\n\n![Generated Image]({})", image_url));
     }
@@ -3229,6 +3971,22 @@ fn build_sse_response(
 
     let mut sse_output = String::new();
 
+    // 思维链内容放在 reasoning_content 中先行输出
+    if !extracted.reasoning.is_empty() {
+        let reasoning_chunk = serde_json::json!({
+            "id": &chunk_id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "delta": { "reasoning_content": extracted.reasoning },
+                "finish_reason": serde_json::Value::Null
+            }]
+        });
+        sse_output.push_str(&format!("data: {}\n\n", reasoning_chunk.to_string()));
+    }
+
     if !content.is_empty() {
         let content_chunk = serde_json::json!({
             "id": &chunk_id,
@@ -3281,8 +4039,9 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
 
     let candidate = &candidates[0];
 
-    // 提取文本内容
+    // 提取文本内容（思维链单独放入 reasoning_content）
     let mut content_delta: Option<String> = None;
+    let mut reasoning_delta: Option<String> = None;
     let mut has_image = false;
     let mut image_data: Option<String> = None;
 
@@ -3291,7 +4050,15 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
             for part in parts {
                 // 处理文本
                 if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
-                    content_delta = Some(text.to_string());
+                    let is_thought = part
+                        .get("thought")
+                        .and_then(|t| t.as_bool())
+                        .unwrap_or(false);
+                    if is_thought {
+                        reasoning_delta = Some(text.to_string());
+                    } else {
+                        content_delta = Some(text.to_string());
+                    }
                 }
 
                 // 处理图片（inlineData）
@@ -3328,7 +4095,8 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
         });
 
     // 如果没有内容变化且没有 finish_reason，跳过
-    if content_delta.is_none() && !has_image && finish_reason.is_none() {
+    if content_delta.is_none() && reasoning_delta.is_none() && !has_image && finish_reason.is_none()
+    {
         return None;
     }
 
@@ -3345,6 +4113,9 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
     if let Some(content) = final_content {
         delta["content"] = serde_json::Value::String(content);
     }
+    if let Some(reasoning) = reasoning_delta {
+        delta["reasoning_content"] = serde_json::Value::String(reasoning);
+    }
 
     // 构建完整的 SSE 事件
     let chunk_id = format!("chatcmpl-{}", uuid::Uuid::new_v4());
@@ -3435,3 +4206,92 @@ fn convert_openai_response_to_anthropic(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_call_error_keeps_forwarded_headers() {
+        let mut forwarded = HeaderMap::new();
+        forwarded.insert("request-id", "req_123".parse().unwrap());
+        forwarded.insert(header::CONTENT_TYPE, "text/plain".parse().unwrap());
+        let error = ProviderCallError {
+            error: ProcessError::upstream(529, "overloaded".to_string()),
+            forwarded,
+        };
+
+        let response = error.into_response_for(ErrorFormat::Anthropic);
+        assert_eq!(response.headers()["request-id"], "req_123");
+        // 错误响应自身的 Content-Type 不被覆盖
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    fn penalty_request() -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "hi"}],
+            "logit_bias": {"50256": -100},
+            "frequency_penalty": 0.5,
+            "presence_penalty": 0.2
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_penalty_params_forwarded_by_passthrough_providers() {
+        let file = || "/tmp/creds.json".to_string();
+        let passthrough = [
+            CredentialData::OpenAIKey {
+                api_key: "sk".to_string(),
+                base_url: None,
+            },
+            CredentialData::QwenOAuth {
+                creds_file_path: file(),
+            },
+            CredentialData::IFlowOAuth {
+                creds_file_path: file(),
+            },
+            CredentialData::IFlowCookie {
+                creds_file_path: file(),
+            },
+            CredentialData::AnthropicKey {
+                api_key: "sk-ant".to_string(),
+                base_url: Some("https://proxy.example.com/v1".to_string()),
+            },
+        ];
+        for data in passthrough {
+            let mut request = penalty_request();
+            let credential = ProviderCredential::new(data.provider_type(), data);
+            assert!(drop_unsupported_penalty_params(&credential, &mut request).is_empty());
+            assert_eq!(request.frequency_penalty, Some(0.5));
+            assert!(request.logit_bias.is_some());
+        }
+    }
+
+    #[test]
+    fn test_penalty_params_dropped_by_converting_providers() {
+        let converting = [
+            CredentialData::KiroOAuth {
+                creds_file_path: "/tmp/kiro.json".to_string(),
+            },
+            CredentialData::ClaudeKey {
+                api_key: "sk-ant".to_string(),
+                base_url: None,
+            },
+            CredentialData::AnthropicKey {
+                api_key: "sk-ant".to_string(),
+                base_url: None,
+            },
+        ];
+        for data in converting {
+            let mut request = penalty_request();
+            let credential = ProviderCredential::new(data.provider_type(), data);
+            assert_eq!(
+                drop_unsupported_penalty_params(&credential, &mut request),
+                vec!["logit_bias", "frequency_penalty", "presence_penalty"]
+            );
+            assert!(request.penalty_params().is_empty());
+        }
+    }
+}
@@ -5,34 +5,44 @@
 use axum::{
     body::Body,
     extract::{
-        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
-        Query, State,
+        ws::{Message as WsMessage, WebSocketUpgrade},
+        FromRequestParts, Query, Request, State,
     },
-    http::HeaderMap,
+    http::{header, HeaderMap, HeaderValue},
     response::IntoResponse,
+    Json,
 };
-use futures::{SinkExt, StreamExt as FuturesStreamExt};
+use dashmap::DashSet;
+use futures::stream::SplitSink;
+use futures::{SinkExt, Stream, StreamExt as FuturesStreamExt};
 use serde::Deserialize;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio::task::JoinSet;
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
 };
+use crate::flow_monitor::FlowEventFilter;
+use crate::logger::LogFilter;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::models::provider_pool_model::ProviderCredential;
-use crate::processor::RequestContext;
+use crate::processor::{ProcessError, RequestContext};
 use crate::providers::{
     AntigravityProvider, ClaudeCustomProvider, KiroProvider, OpenAICustomProvider,
 };
-use crate::server::AppState;
+use crate::server::{user_limits, AppState};
 use crate::server_utils::parse_cw_response;
 use crate::websocket::{
-    WsApiRequest, WsApiResponse, WsEndpoint, WsError, WsFlowEvent, WsMessage as WsProtoMessage,
+    compression, deflate_upgrade, MessageProcessor, StreamForwarder, WsApiRequest, WsApiResponse,
+    WsEndpoint, WsError, WsFlowEvent, WsMessage as WsProtoMessage, WsSocket,
 };
 
+/// WebSocket 发送端（多个流式请求共享）
+type WsSender = Arc<Mutex<SplitSink<WsSocket, WsMessage>>>;
+
 /// WebSocket 查询参数
 #[derive(Debug, Deserialize, Default)]
 pub struct WsQueryParams {
@@ -43,11 +53,13 @@ pub struct WsQueryParams {
 }
 
 /// WebSocket 升级处理器
+///
+/// 开启压缩且客户端提供 `permessage-deflate` 扩展时使用支持压缩的连接，否则使用 axum 内置的 WebSocket。
 pub async fn ws_upgrade_handler(
-    ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Query(params): Query<WsQueryParams>,
     headers: HeaderMap,
+    request: Request,
 ) -> impl IntoResponse {
     // 验证 API 密钥：优先从 header 获取，其次从 URL 参数获取
     let auth = headers
@@ -64,10 +76,9 @@ pub async fn ws_upgrade_handler(
         }
     };
 
-    // 如果没有提供任何认证信息，允许连接（用于内部 Flow Monitor）
-    // 但会在日志中记录
-    let authenticated = match key {
-        Some(k) if k == state.api_key => true,
+    // 与 HTTP 端点使用相同的 API Key 认证
+    match key {
+        Some(k) if state.api_key.matches(k) => {}
         Some(_) => {
             return axum::http::Response::builder()
                 .status(401)
@@ -76,11 +87,13 @@ pub async fn ws_upgrade_handler(
                 .into_response();
         }
         None => {
-            // 允许无认证连接（仅用于本地 Flow Monitor UI）
-            tracing::debug!("[WS] Allowing unauthenticated connection for Flow Monitor");
-            false
+            return axum::http::Response::builder()
+                .status(401)
+                .body(Body::from("No API key provided"))
+                .unwrap()
+                .into_response();
         }
-    };
+    }
 
     // 获取客户端信息
     let client_info = headers
@@ -88,16 +101,26 @@ pub async fn ws_upgrade_handler(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    ws.on_upgrade(move |socket| handle_websocket(socket, state, client_info, authenticated))
+    let ws_config = state.ws_manager.config();
+    if ws_config.compression {
+        if let Some(deflate) = compression::negotiate(&headers) {
+            let max_message_size = ws_config.max_message_size;
+            return deflate_upgrade(request, deflate, max_message_size, move |socket| {
+                handle_websocket(WsSocket::Deflate(socket), state, client_info)
+            });
+        }
+    }
+
+    let (mut parts, _body) = request.into_parts();
+    let ws = match WebSocketUpgrade::from_request_parts(&mut parts, &state).await {
+        Ok(ws) => ws,
+        Err(rejection) => return rejection.into_response(),
+    };
+    ws.on_upgrade(move |socket| handle_websocket(WsSocket::Plain(socket), state, client_info))
 }
 
 /// 处理 WebSocket 连接
-pub async fn handle_websocket(
-    socket: WebSocket,
-    state: AppState,
-    client_info: Option<String>,
-    authenticated: bool,
-) {
+pub async fn handle_websocket(socket: WsSocket, state: AppState, client_info: Option<String>) {
     let conn_id = uuid::Uuid::new_v4().to_string();
 
     // 注册连接
@@ -115,22 +138,22 @@ pub async fn handle_websocket(
     state.logs.write().await.add(
         "info",
         &format!(
-            "[WS] New connection: {} (client: {:?}, authenticated: {})",
+            "[WS] New connection: {} (client: {:?})",
             &conn_id[..8],
-            client_info,
-            authenticated
+            client_info
         ),
     );
 
     let (sender, mut receiver) = socket.split();
     let sender = Arc::new(Mutex::new(sender));
 
-    // Flow 事件订阅状态
-    let flow_subscribed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    // Flow 事件订阅状态（None 表示未订阅）
+    let flow_subscription: Arc<std::sync::RwLock<Option<FlowEventFilter>>> =
+        Arc::new(std::sync::RwLock::new(None));
 
     // 启动 Flow 事件转发任务
     let flow_sender = sender.clone();
-    let flow_subscribed_clone = flow_subscribed.clone();
+    let flow_subscription_clone = flow_subscription.clone();
     let flow_monitor = state.flow_monitor.clone();
     let conn_id_clone = conn_id.clone();
     let _logs_clone = state.logs.clone();
@@ -141,8 +164,13 @@ pub async fn handle_websocket(
         loop {
             match flow_receiver.recv().await {
                 Ok(event) => {
-                    // 只有在订阅状态下才转发事件
-                    if !flow_subscribed_clone.load(std::sync::atomic::Ordering::Relaxed) {
+                    // 只有在订阅状态下才转发匹配的事件
+                    let matched = flow_subscription_clone
+                        .read()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .as_ref()
+                        .is_some_and(|filter| filter.matches(&event));
+                    if !matched {
                         continue;
                     }
 
@@ -183,17 +211,79 @@ pub async fn handle_websocket(
         }
     });
 
+    // 日志订阅状态（None 表示未订阅）
+    let log_subscription: Arc<std::sync::RwLock<Option<LogFilter>>> =
+        Arc::new(std::sync::RwLock::new(None));
+    let log_task = spawn_log_forwarder(
+        state.logs.read().await.subscribe(),
+        log_subscription.clone(),
+        sender.clone(),
+        conn_id.clone(),
+    );
+
+    // 进行中的流式请求（按 request_id 多路复用）
+    let active_streams: Arc<DashSet<String>> = Arc::new(DashSet::new());
+    let mut stream_tasks = JoinSet::new();
+
     // 消息处理循环
     while let Some(msg) = receiver.next().await {
+        // 回收已结束的流式任务
+        while stream_tasks.try_join_next().is_some() {}
+
         match msg {
             Ok(WsMessage::Text(text)) => {
                 state.ws_manager.on_message();
                 state.ws_manager.increment_request_count(&conn_id);
 
                 match serde_json::from_str::<WsProtoMessage>(&text) {
+                    Ok(WsProtoMessage::Request(request))
+                        if state.proxy_paused.load(std::sync::atomic::Ordering::SeqCst) =>
+                    {
+                        let error = WsProtoMessage::Error(WsError::internal(
+                            Some(request.request_id),
+                            "Proxy is paused by administrator",
+                        ));
+                        if send_ws_message(&sender, &error).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(WsProtoMessage::Request(request)) if is_streaming_chat_request(&request) => {
+                        if !active_streams.insert(request.request_id.clone()) {
+                            let error = WsProtoMessage::Error(WsError::invalid_request(
+                                Some(request.request_id),
+                                "A stream with this request_id is already in progress",
+                            ));
+                            if send_ws_message(&sender, &error).await.is_err() {
+                                break;
+                            }
+                            continue;
+                        }
+
+                        state.logs.write().await.add(
+                            "info",
+                            &format!(
+                                "[WS] Stream request from {}: id={}",
+                                &conn_id[..8],
+                                request.request_id
+                            ),
+                        );
+                        stream_tasks.spawn(stream_ws_chat_completions(
+                            state.clone(),
+                            request,
+                            sender.clone(),
+                            active_streams.clone(),
+                            client_info.clone(),
+                        ));
+                    }
                     Ok(ws_msg) => {
-                        let response =
-                            handle_ws_message(&state, &conn_id, ws_msg, &flow_subscribed).await;
+                        let response = handle_ws_message(
+                            &state,
+                            &conn_id,
+                            ws_msg,
+                            &flow_subscription,
+                            &log_subscription,
+                        )
+                        .await;
                         if let Some(resp) = response {
                             let resp_text = serde_json::to_string(&resp).unwrap_or_default();
                             let mut sender_guard = sender.lock().await;
@@ -261,8 +351,10 @@ pub async fn handle_websocket(
         }
     }
 
-    // 取消 Flow 事件转发任务
+    // 取消事件转发任务和进行中的流式请求
     flow_task.abort();
+    log_task.abort();
+    stream_tasks.abort_all();
 
     // 清理连接
     state.ws_manager.unregister(&conn_id);
@@ -272,24 +364,196 @@ pub async fn handle_websocket(
     );
 }
 
+/// 发送单条协议消息
+async fn send_ws_message(sender: &WsSender, msg: &WsProtoMessage) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(msg).unwrap_or_default();
+    sender.lock().await.send(WsMessage::Text(text.into())).await
+}
+
+/// 是否为需要流式返回的 chat completions 请求
+fn is_streaming_chat_request(request: &WsApiRequest) -> bool {
+    request.endpoint == WsEndpoint::ChatCompletions
+        && request
+            .payload
+            .get("stream")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+}
+
+/// 处理流式 chat completions 请求
+///
+/// 内部直接复用 HTTP `/v1/chat/completions` 处理流程（路由、凭证选择、
+/// Flow 监控、并发限制等），再将 SSE 响应逐块转换为 `stream_chunk` 帧，
+/// 最后发送 `stream_end` 帧。
+async fn stream_ws_chat_completions(
+    state: AppState,
+    request: WsApiRequest,
+    sender: WsSender,
+    active_streams: Arc<DashSet<String>>,
+    client_info: Option<String>,
+) {
+    let request_id = request.request_id.clone();
+
+    if let Err(error) = forward_chat_stream(&state, &request, &sender, client_info).await {
+        let _ = send_ws_message(&sender, &WsProtoMessage::Error(error)).await;
+    }
+
+    active_streams.remove(&request_id);
+}
+
+async fn forward_chat_stream(
+    state: &AppState,
+    request: &WsApiRequest,
+    sender: &WsSender,
+    client_info: Option<String>,
+) -> Result<(), WsError> {
+    let request_id = request.request_id.clone();
+    let chat_request = MessageProcessor::parse_chat_completions(&request.payload)
+        .map_err(|e| WsError::invalid_request(Some(request_id.clone()), e.message))?;
+
+    // 连接已在升级时认证，这里以服务端 API Key 调用 HTTP 处理流程
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&format!("Bearer {}", state.api_key.current())) {
+        headers.insert(header::AUTHORIZATION, value);
+    }
+    if let Some(value) = client_info.and_then(|ua| HeaderValue::from_str(&ua).ok()) {
+        headers.insert(header::USER_AGENT, value);
+    }
+
+    let response =
+        super::api::chat_completions(State(state.clone()), headers, Json(chat_request)).await;
+    let status = response.status();
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.contains("text/event-stream"))
+        .unwrap_or(false);
+
+    if !status.is_success() || !is_sse {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .map_err(|e| WsError::upstream(Some(request_id.clone()), e.to_string()))?;
+
+        if !status.is_success() {
+            return Err(WsError::upstream(
+                Some(request_id),
+                format!(
+                    "Upstream error ({}): {}",
+                    status,
+                    String::from_utf8_lossy(&body)
+                ),
+            ));
+        }
+
+        // 上游返回了非流式响应，作为普通响应帧发送
+        let payload = serde_json::from_slice(&body).unwrap_or_else(|_| {
+            serde_json::Value::String(String::from_utf8_lossy(&body).into_owned())
+        });
+        let msg = MessageProcessor::create_response(&request_id, payload);
+        return send_ws_message(sender, &msg)
+            .await
+            .map_err(|e| WsError::internal(Some(request_id), e.to_string()));
+    }
+
+    let forwarder = StreamForwarder::new(request_id.clone());
+    let (tx, mut rx) = forwarder.create_channel();
+    let body_stream = Box::pin(utf8_text_stream(response.into_body().into_data_stream()));
+
+    let forward = forwarder.forward_string_stream(body_stream, tx);
+    let send_loop = async {
+        while let Some(msg) = rx.recv().await {
+            if send_ws_message(sender, &msg).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    let (result, _) = tokio::join!(forward, send_loop);
+    result.map(|_| ())
+}
+
+/// 将字节流解码为 UTF-8 文本流，跨块截断的多字节字符会留到下一块再解码
+fn utf8_text_stream<S, E>(stream: S) -> impl Stream<Item = Result<String, E>>
+where
+    S: Stream<Item = Result<bytes::Bytes, E>>,
+{
+    let mut pending: Vec<u8> = Vec::new();
+    stream.map(move |chunk| {
+        chunk.map(|bytes| {
+            pending.extend_from_slice(&bytes);
+            let valid = match std::str::from_utf8(&pending) {
+                Ok(text) => text.len(),
+                Err(e) => e.valid_up_to(),
+            };
+            let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+            pending.drain(..valid);
+            text
+        })
+    })
+}
+
+/// 启动日志转发任务，订阅状态下把匹配过滤条件的新日志推送给客户端
+fn spawn_log_forwarder(
+    mut log_receiver: tokio::sync::broadcast::Receiver<crate::logger::LogEntry>,
+    log_subscription: Arc<std::sync::RwLock<Option<LogFilter>>>,
+    sender: WsSender,
+    conn_id: String,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match log_receiver.recv().await {
+                Ok(entry) => {
+                    let matched = log_subscription
+                        .read()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .as_ref()
+                        .is_some_and(|filter| filter.matches(&entry));
+                    if !matched {
+                        continue;
+                    }
+                    if send_ws_message(&sender, &WsProtoMessage::LogEntry(entry))
+                        .await
+                        .is_err()
+                    {
+                        tracing::debug!("[WS] Log send failed for connection {}", &conn_id[..8]);
+                        break;
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!(
+                        "[WS] Log receiver lagged by {} entries for connection {}",
+                        n,
+                        &conn_id[..8]
+                    );
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
 /// 处理 WebSocket 消息
 async fn handle_ws_message(
     state: &AppState,
     conn_id: &str,
     msg: WsProtoMessage,
-    flow_subscribed: &Arc<std::sync::atomic::AtomicBool>,
+    flow_subscription: &Arc<std::sync::RwLock<Option<FlowEventFilter>>>,
+    log_subscription: &Arc<std::sync::RwLock<Option<LogFilter>>>,
 ) -> Option<WsProtoMessage> {
     match msg {
         WsProtoMessage::Ping { timestamp } => Some(WsProtoMessage::Pong { timestamp }),
         WsProtoMessage::Pong { .. } => None,
-        WsProtoMessage::SubscribeFlowEvents => {
-            // 订阅 Flow 事件
-            flow_subscribed.store(true, std::sync::atomic::Ordering::Relaxed);
+        WsProtoMessage::SubscribeFlowEvents { filter } => {
+            // 订阅 Flow 事件（指定会话时只推送该会话的事件）
+            let session_id = filter.session_id.clone();
+            *flow_subscription.write().unwrap_or_else(|e| e.into_inner()) = Some(filter);
             state.logs.write().await.add(
                 "info",
                 &format!(
-                    "[WS] Connection {} subscribed to flow events",
-                    &conn_id[..8]
+                    "[WS] Connection {} subscribed to flow events (session: {:?})",
+                    &conn_id[..8],
+                    session_id
                 ),
             );
             // 返回确认消息
@@ -297,13 +561,14 @@ async fn handle_ws_message(
                 request_id: "subscribe_flow_events".to_string(),
                 payload: serde_json::json!({
                     "status": "subscribed",
+                    "session_id": session_id,
                     "message": "Successfully subscribed to flow events"
                 }),
             }))
         }
         WsProtoMessage::UnsubscribeFlowEvents => {
             // 取消订阅 Flow 事件
-            flow_subscribed.store(false, std::sync::atomic::Ordering::Relaxed);
+            *flow_subscription.write().unwrap_or_else(|e| e.into_inner()) = None;
             state.logs.write().await.add(
                 "info",
                 &format!(
@@ -375,6 +640,53 @@ async fn handle_ws_message(
                 "KiroCredentialEvent messages are server-to-client only",
             )))
         }
+        WsProtoMessage::SubscribeLogs { filter } => {
+            let level = filter.level.clone();
+            *log_subscription.write().unwrap_or_else(|e| e.into_inner()) = Some(filter);
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[WS] Connection {} subscribed to logs (level: {:?})",
+                    &conn_id[..8],
+                    level
+                ),
+            );
+            Some(WsProtoMessage::Response(WsApiResponse {
+                request_id: "subscribe_logs".to_string(),
+                payload: serde_json::json!({
+                    "status": "subscribed",
+                    "level": level,
+                    "message": "Successfully subscribed to logs"
+                }),
+            }))
+        }
+        WsProtoMessage::UnsubscribeLogs => {
+            *log_subscription.write().unwrap_or_else(|e| e.into_inner()) = None;
+            Some(WsProtoMessage::Response(WsApiResponse {
+                request_id: "unsubscribe_logs".to_string(),
+                payload: serde_json::json!({
+                    "status": "unsubscribed",
+                    "message": "Successfully unsubscribed from logs"
+                }),
+            }))
+        }
+        WsProtoMessage::LogEntry(_) => Some(WsProtoMessage::Error(WsError::invalid_message(
+            "LogEntry messages are server-to-client only",
+        ))),
+        WsProtoMessage::ConfigGet
+        | WsProtoMessage::ConfigSubscribe
+        | WsProtoMessage::ConfigUnsubscribe
+        | WsProtoMessage::ConfigPatch { .. } => {
+            // API Key 连接不能读写配置，需使用带管理认证的 /v0/management/ws
+            Some(WsProtoMessage::Error(WsError::unauthorized(
+                "Config frames require management auth, connect to /v0/management/ws",
+            )))
+        }
+        WsProtoMessage::ConfigSnapshot { .. } | WsProtoMessage::ConfigChanged { .. } => {
+            Some(WsProtoMessage::Error(WsError::invalid_message(
+                "Config snapshot/changed messages are server-to-client only",
+            )))
+        }
     }
 }
 
@@ -426,14 +738,37 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
     }
 }
 
+/// 按终端用户检查频率与配额，通过时计入本次请求
+fn check_user_limits(request_id: &str, user: Option<&str>) -> Result<(), WsError> {
+    let Some(user) = user.and_then(user_limits::user_key) else {
+        return Ok(());
+    };
+    user_limits::check_and_record(&user).map_err(|e| {
+        tracing::warn!(
+            "[USER_LIMITS] ws request_id={} user={} {}",
+            request_id,
+            user,
+            e
+        );
+        WsError::invalid_request(Some(request_id.to_string()), e.to_string())
+    })
+}
+
 /// 处理 WebSocket chat completions 请求
 async fn handle_ws_chat_completions(
     state: &AppState,
     request_id: &str,
     mut request: ChatCompletionRequest,
 ) -> WsProtoMessage {
+    // 按终端用户限流与配额（HTTP 路由由 UserLimitsLayer 检查）
+    if let Err(e) = check_user_limits(request_id, request.user.as_deref()) {
+        return WsProtoMessage::Error(e);
+    }
+
     // 创建请求上下文
-    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    let mut ctx = RequestContext::new(request.model.clone())
+        .with_stream(request.stream)
+        .with_trace_store(state.processor.traces.clone());
 
     // 使用 RequestProcessor 解析模型别名和路由
     let _provider = state.processor.resolve_and_route(&mut ctx).await;
@@ -484,10 +819,11 @@ async fn handle_ws_chat_completions(
         // 不再回退到 Kiro provider，直接返回错误
         WsProtoMessage::Error(WsError::internal(
             Some(request_id.to_string()),
-            format!(
+            ProcessError::NoCredential(format!(
                 "No available credentials for provider '{}'. Please add credentials in the Provider Pool.",
                 default_provider
-            ),
+            ))
+            .to_string(),
         ))
     }
 }
@@ -498,8 +834,15 @@ async fn handle_ws_anthropic_messages(
     request_id: &str,
     mut request: AnthropicMessagesRequest,
 ) -> WsProtoMessage {
+    // 按终端用户限流与配额（HTTP 路由由 UserLimitsLayer 检查）
+    if let Err(e) = check_user_limits(request_id, request.user_id()) {
+        return WsProtoMessage::Error(e);
+    }
+
     // 创建请求上下文
-    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    let mut ctx = RequestContext::new(request.model.clone())
+        .with_stream(request.stream)
+        .with_trace_store(state.processor.traces.clone());
 
     // 使用 RequestProcessor 解析模型别名和路由
     let _provider = state.processor.resolve_and_route(&mut ctx).await;
@@ -548,19 +891,34 @@ async fn handle_ws_anthropic_messages(
         // 不再回退到 Kiro provider，直接返回错误
         WsProtoMessage::Error(WsError::internal(
             Some(request_id.to_string()),
-            format!(
+            ProcessError::NoCredential(format!(
                 "No available credentials for provider '{}'. Please add credentials in the Provider Pool.",
                 default_provider
-            ),
+            ))
+            .to_string(),
         ))
     }
 }
 
 /// WebSocket 专用的 OpenAI 格式 Provider 调用
+///
+/// 调用期间应用凭证级上游请求头
 pub async fn call_provider_openai_for_ws(
     state: &AppState,
     credential: &ProviderCredential,
     request: &ChatCompletionRequest,
+) -> Result<serde_json::Value, String> {
+    crate::http_client::with_credential_headers(
+        &credential.upstream_headers,
+        call_provider_openai_for_ws_inner(state, credential, request),
+    )
+    .await
+}
+
+async fn call_provider_openai_for_ws_inner(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
 ) -> Result<serde_json::Value, String> {
     use crate::models::provider_pool_model::CredentialData;
 
@@ -790,10 +1148,13 @@ pub async fn call_provider_openai_for_ws(
             }
 
             // 设置项目 ID
-            if let Some(pid) = project_id {
-                antigravity.project_id = Some(pid.clone());
-            }
-            let proj_id = antigravity.project_id.clone().unwrap_or_default();
+            let proj_id = crate::server::handlers::resolve_antigravity_project_id(
+                state,
+                credential,
+                project_id.as_deref(),
+                &mut antigravity,
+            )
+            .await;
 
             let antigravity_request = convert_openai_to_antigravity_with_context(request, &proj_id);
             match antigravity
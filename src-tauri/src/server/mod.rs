@@ -1,10 +1,22 @@
 //! HTTP API 服务器
 
+pub mod api_key;
 pub mod client_detector;
+pub mod dead_letter;
+pub mod default_split;
+pub mod model_fallback;
+pub mod request_limits;
+pub mod retry_policy;
+pub mod shadow;
+pub mod stream_coalesce;
+pub mod stream_resume;
+pub mod tool_result_truncation;
+pub mod user_limits;
 
 use crate::config::{
-    Config, ConfigChangeKind, ConfigManager, EndpointProvidersConfig, FileChangeEvent, FileWatcher,
-    HotReloadManager, ReloadResult,
+    observer::WsConfigObserver, Config, ConfigChangeKind, ConfigChangeSource, ConfigManager,
+    EndpointProvidersConfig, FileChangeEvent, FileWatcher, GlobalConfigManager, HotReloadManager,
+    ReloadResult,
 };
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::credential::CredentialSyncService;
@@ -16,14 +28,17 @@ use crate::logger::LogStore;
 use crate::models::anthropic::*;
 use crate::models::openai::*;
 use crate::models::provider_pool_model::CredentialData;
-use crate::models::route_model::{RouteInfo, RouteListResponse};
-use crate::processor::{RequestContext, RequestProcessor};
+use crate::models::route_model::{RouteListResponse, RouteQuery};
+use crate::processor::{
+    ErrorFormat, ProcessError, RequestContext, RequestProcessor, RequestTraceStore, ResponseCache,
+};
 use crate::providers::antigravity::AntigravityProvider;
 use crate::providers::claude_custom::ClaudeCustomProvider;
 use crate::providers::gemini::GeminiProvider;
 use crate::providers::kiro::KiroProvider;
 use crate::providers::openai_custom::OpenAICustomProvider;
 use crate::providers::qwen::QwenProvider;
+use crate::resilience::ConcurrencyLimiter;
 use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_gemini_native_request, health,
     models, parse_cw_response,
@@ -32,9 +47,10 @@ use crate::services::kiro_event_service::KiroEventService;
 use crate::services::provider_pool_service::ProviderPoolService;
 use crate::services::token_cache_service::TokenCacheService;
 use crate::websocket::{WsConfig, WsConnectionManager, WsStats};
+pub use api_key::ServerApiKey;
 use axum::{
     body::Body,
-    extract::{DefaultBodyLimit, Path, State},
+    extract::{DefaultBodyLimit, Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     routing::{get, post},
@@ -42,10 +58,36 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::{oneshot, RwLock};
+use tokio::sync::{mpsc, oneshot, RwLock};
+
+/// 基准测试请求标记头
+///
+/// 值与进程内的基准测试令牌一致时，请求不计入请求统计和 Token 统计，也不读写响应缓存；
+/// 凭证选择和配额限制与普通请求相同。
+pub const BENCHMARK_HEADER: &str = "x-proxycast-benchmark";
+
+/// 进程启动时随机生成的基准测试令牌，只有应用内部的基准测试请求携带
+static BENCHMARK_TOKEN: once_cell::sync::Lazy<String> =
+    once_cell::sync::Lazy::new(|| uuid::Uuid::new_v4().simple().to_string());
+
+/// 基准测试标记头应携带的值
+pub fn benchmark_token() -> &'static str {
+    &BENCHMARK_TOKEN
+}
 
-/// 记录请求统计到遥测系统
+/// 是否为应用内部发出的基准测试请求
+///
+/// 外部客户端无法得知令牌，伪造标记头不能隐藏统计或绕过响应缓存。
+pub fn is_benchmark_request(headers: &HeaderMap) -> bool {
+    headers
+        .get(BENCHMARK_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v == benchmark_token())
+}
+
+/// 记录请求统计到遥测系统（基准测试请求除外）
 pub fn record_request_telemetry(
     state: &AppState,
     ctx: &RequestContext,
@@ -54,6 +96,14 @@ pub fn record_request_telemetry(
 ) {
     use crate::telemetry::RequestLog;
 
+    if ctx.benchmark {
+        tracing::debug!(
+            "[TELEMETRY] request_id={} 基准测试请求，不计入统计",
+            ctx.request_id
+        );
+        return;
+    }
+
     let provider = ctx.provider.unwrap_or(crate::ProviderType::Kiro);
     let mut log = RequestLog::new(
         ctx.request_id.clone(),
@@ -84,6 +134,11 @@ pub fn record_request_telemetry(
 
     // 设置重试次数
     log.retry_count = ctx.retry_count;
+    log.user_id = ctx.user_id.clone();
+    log.request_bytes = ctx.request_bytes;
+    log.response_bytes = ctx.response_bytes;
+    log.priority = Some(crate::resilience::priority::current_priority());
+    log.queue_wait_ms = crate::resilience::priority::queue_wait_ms();
 
     // 记录到统计聚合器
     {
@@ -96,6 +151,8 @@ pub fn record_request_telemetry(
         let _ = logger.record(log.clone());
     }
 
+    crate::telemetry::otlp::export_request(ctx, status, error_message.as_deref());
+
     tracing::info!(
         "[TELEMETRY] request_id={} provider={:?} model={} status={:?} duration_ms={}",
         ctx.request_id,
@@ -106,6 +163,114 @@ pub fn record_request_telemetry(
     );
 }
 
+/// 统计响应体大小
+///
+/// 大小已知的响应（非流式）直接写入 `ctx.response_bytes`，需在 [`record_request_telemetry`] 之前调用；
+/// 流式响应包装响应体，在流结束（或客户端断开）时把累计发送的字节数回填到已记录的请求日志；
+/// 响应带有 [`CostAbortSlot`] 时同时回填费用上限中止状态。
+pub fn measure_response_bytes(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    response: Response,
+) -> Response {
+    use axum::body::HttpBody;
+    use futures::StreamExt;
+
+    if let Some(size) = response.body().size_hint().exact() {
+        ctx.response_bytes = Some(size);
+        return response;
+    }
+
+    /// 流结束时回填累计字节数
+    struct ResponseBytesRecorder {
+        state: AppState,
+        request_id: String,
+        bytes: u64,
+        cost_abort: Option<CostAbortSlot>,
+    }
+
+    impl Drop for ResponseBytesRecorder {
+        fn drop(&mut self) {
+            self.state
+                .processor
+                .stats
+                .read()
+                .set_response_bytes(&self.request_id, self.bytes);
+            if let Some(logger) = &self.state.request_logger {
+                logger.set_response_bytes(&self.request_id, self.bytes);
+                if let Some(abort) = self.cost_abort.as_ref().and_then(CostAbortSlot::take) {
+                    logger.set_cost_aborted(
+                        &self.request_id,
+                        abort.message,
+                        abort.input_tokens,
+                        abort.output_tokens,
+                    );
+                }
+            }
+        }
+    }
+
+    let mut recorder = ResponseBytesRecorder {
+        state: state.clone(),
+        request_id: ctx.request_id.clone(),
+        bytes: 0,
+        cost_abort: response.extensions().get::<CostAbortSlot>().cloned(),
+    };
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            recorder.bytes += bytes.len() as u64;
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 流式响应因费用上限中止的记录
+///
+/// 流处理管道中止上游流时写入，作为响应扩展传给 [`measure_response_bytes`]，
+/// 在流结束时回填到该请求已记录的请求日志。
+#[derive(Clone, Default)]
+pub struct CostAbortSlot(Arc<parking_lot::Mutex<Option<CostAbort>>>);
+
+#[derive(Debug, Clone)]
+struct CostAbort {
+    message: String,
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+impl CostAbortSlot {
+    /// 记录费用上限中止
+    pub fn record(
+        &self,
+        provider: crate::ProviderType,
+        model: &str,
+        guard: &crate::stream::CostGuard,
+    ) {
+        tracing::warn!(
+            "[TELEMETRY] cost_abort provider={:?} model={} estimated_cost={:.4} max_cost={:.4}",
+            provider,
+            model,
+            guard.estimated_cost_usd(),
+            guard.max_cost_usd()
+        );
+        *self.0.lock() = Some(CostAbort {
+            message: format!(
+                "预估费用 ${:.4} 超过单次请求上限 ${:.4}，已中止上游流",
+                guard.estimated_cost_usd(),
+                guard.max_cost_usd()
+            ),
+            input_tokens: guard.input_tokens(),
+            output_tokens: guard.output_tokens(),
+        });
+    }
+
+    fn take(&self) -> Option<CostAbort> {
+        self.0.lock().take()
+    }
+}
+
 /// 记录 Token 使用量到遥测系统
 pub fn record_token_usage(
     state: &AppState,
@@ -131,11 +296,17 @@ pub fn record_token_usage(
     )
     .with_request_id(ctx.request_id.clone());
 
-    // 记录到 Token 追踪器
-    {
+    // 记录到 Token 追踪器（基准测试请求只计入用户配额）
+    if !ctx.benchmark {
         let tokens = state.processor.tokens.write();
         tokens.record(record);
     }
+    if let Some(user) = &ctx.user_id {
+        user_limits::record_tokens(
+            user,
+            u64::from(input_tokens.unwrap_or(0)) + u64::from(output_tokens.unwrap_or(0)),
+        );
+    }
 
     tracing::debug!(
         "[TOKEN] request_id={} input={} output={}",
@@ -145,6 +316,92 @@ pub fn record_token_usage(
     );
 }
 
+/// 记录上游 Anthropic 响应返回的实际 Token 使用量（含提示缓存 Token）
+pub fn record_anthropic_usage(
+    state: &AppState,
+    ctx: &RequestContext,
+    usage: &crate::telemetry::AnthropicUsage,
+) {
+    let provider = ctx.provider.unwrap_or(crate::ProviderType::Claude);
+    if !ctx.benchmark {
+        state.processor.tokens.write().record_anthropic_usage(
+            ctx.request_id.clone(),
+            provider,
+            ctx.resolved_model.clone(),
+            usage,
+        );
+    }
+    if let Some(user) = &ctx.user_id {
+        user_limits::record_tokens(
+            user,
+            u64::from(usage.input_tokens) + u64::from(usage.output_tokens),
+        );
+    }
+
+    tracing::debug!(
+        "[TOKEN] request_id={} input={} output={} cache_creation={} cache_read={}",
+        ctx.request_id,
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_creation_input_tokens,
+        usage.cache_read_input_tokens
+    );
+}
+
+/// 响应体为上游透传的 Anthropic SSE 流
+///
+/// 由 Provider 调用作为响应扩展设置，[`record_stream_anthropic_usage`] 据此解析流中的用量。
+#[derive(Debug, Clone, Copy)]
+pub struct AnthropicSseBody;
+
+/// 透传的 Anthropic SSE 流结束时记录实际 Token 使用量（含提示缓存 Token）
+///
+/// 响应不带 [`AnthropicSseBody`] 时原样返回并返回 false，调用方自行记录用量。
+pub fn record_stream_anthropic_usage(
+    state: &AppState,
+    ctx: &RequestContext,
+    response: Response,
+) -> (Response, bool) {
+    use futures::StreamExt;
+
+    if response.extensions().get::<AnthropicSseBody>().is_none() {
+        return (response, false);
+    }
+
+    /// 流结束时记录累计的用量
+    struct StreamUsageRecorder {
+        state: AppState,
+        ctx: RequestContext,
+        usage: crate::telemetry::AnthropicStreamUsage,
+    }
+
+    impl Drop for StreamUsageRecorder {
+        fn drop(&mut self) {
+            match self.usage.usage() {
+                Some(usage) => record_anthropic_usage(&self.state, &self.ctx, &usage),
+                None => tracing::debug!(
+                    "[TOKEN] request_id={} 流中没有用量事件",
+                    self.ctx.request_id
+                ),
+            }
+        }
+    }
+
+    let mut recorder = StreamUsageRecorder {
+        state: state.clone(),
+        ctx: ctx.clone(),
+        usage: Default::default(),
+    };
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            recorder.usage.feed(bytes);
+        }
+        chunk
+    });
+    (Response::from_parts(parts, Body::from_stream(stream)), true)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerStatus {
     pub running: bool,
@@ -152,6 +409,31 @@ pub struct ServerStatus {
     pub port: u16,
     pub requests: u64,
     pub uptime_secs: u64,
+    /// 代理是否已暂停（全局熔断开关）
+    pub proxy_paused: bool,
+}
+
+/// 服务器任务退出通知
+#[derive(Debug, Clone)]
+pub struct ServerExit {
+    /// 对应的启动序号（用于忽略已被重启或停止的旧任务）
+    pub generation: u64,
+    /// 退出原因（正常返回时为 None）
+    pub error: Option<String>,
+}
+
+/// 启动服务器时使用的共享实例（自动重启时复用）
+#[derive(Clone)]
+struct ServerStartArgs {
+    logs: Arc<RwLock<LogStore>>,
+    pool_service: Arc<ProviderPoolService>,
+    token_cache: Arc<TokenCacheService>,
+    db: Option<DbConnection>,
+    shared_stats: Option<Arc<parking_lot::RwLock<crate::telemetry::StatsAggregator>>>,
+    shared_tokens: Option<Arc<parking_lot::RwLock<crate::telemetry::TokenTracker>>>,
+    shared_logger: Option<Arc<crate::telemetry::RequestLogger>>,
+    shared_flow_monitor: Option<Arc<FlowMonitor>>,
+    shared_flow_interceptor: Option<Arc<FlowInterceptor>>,
 }
 
 pub struct ServerState {
@@ -171,6 +453,33 @@ pub struct ServerState {
     /// 服务器运行时使用的 API key（启动时从配置复制）
     /// 用于 test_api 命令，确保测试使用的 API key 和服务器一致
     pub running_api_key: Option<String>,
+    /// 代理暂停开关（全局熔断），与运行中的服务器共享
+    pub proxy_paused: Arc<AtomicBool>,
+    /// 上游并发限制器（跨服务器重启保留，用于遥测查询进行中请求数）
+    pub concurrency_limiter: Arc<ConcurrencyLimiter>,
+    /// 服务器 API Key（与运行中的服务器共享，支持运行时轮换）
+    pub server_api_key: ServerApiKey,
+    /// 响应缓存（跨服务器重启保留，供缓存统计与清理命令使用）
+    pub response_cache: Arc<ResponseCache>,
+    /// 请求管道追踪（跨服务器重启保留，供 `get_request_trace` 命令查询）
+    pub request_traces: Arc<RequestTraceStore>,
+    /// 参数注入器（跨服务器重启保留，注入配置观察者据此实时更新规则）
+    pub injector: Arc<RwLock<Injector>>,
+    /// 参数注入开关（与运行中的服务器共享）
+    pub injection_enabled: Arc<RwLock<bool>>,
+    /// 配置重载器（服务器运行时存在，供 `reload_config` 命令手动触发重载）
+    pub config_reloader: Option<Arc<ConfigReloader>>,
+    /// 全局配置管理器（应用初始化时设置，供管理 WebSocket 读取、订阅和修改配置）
+    pub config_manager: Option<Arc<GlobalConfigManager>>,
+    /// 启动序号（每次启动递增）
+    generation: u64,
+    /// 服务器任务退出通知
+    exit_tx: mpsc::UnboundedSender<ServerExit>,
+    exit_rx: Option<mpsc::UnboundedReceiver<ServerExit>>,
+    /// 最近一次启动使用的共享实例
+    last_start: Option<ServerStartArgs>,
+    /// 当前服务器任务结束通知（优雅停止时等待进行中的请求完成）
+    server_done: Option<oneshot::Receiver<()>>,
 }
 
 impl ServerState {
@@ -181,6 +490,13 @@ impl ServerState {
         let openai_custom = OpenAICustomProvider::new();
         let claude_custom = ClaudeCustomProvider::new();
         let default_provider_ref = Arc::new(RwLock::new(config.default_provider.clone()));
+        let concurrency_limiter = Arc::new(ConcurrencyLimiter::new((&config.concurrency).into()));
+        let server_api_key = ServerApiKey::new(config.server.api_key.clone());
+        let response_cache = Arc::new(ResponseCache::new((&config.response_cache).into()));
+        let request_traces = Arc::new(RequestTraceStore::with_defaults());
+        let injector = Arc::new(RwLock::new(Injector::new()));
+        let injection_enabled = Arc::new(RwLock::new(config.injection.enabled));
+        let (exit_tx, exit_rx) = mpsc::unbounded_channel();
 
         Self {
             config,
@@ -196,9 +512,33 @@ impl ServerState {
             router_ref: None,
             shutdown_tx: None,
             running_api_key: None,
+            proxy_paused: Arc::new(AtomicBool::new(false)),
+            concurrency_limiter,
+            server_api_key,
+            response_cache,
+            request_traces,
+            injector,
+            injection_enabled,
+            config_reloader: None,
+            config_manager: None,
+            generation: 0,
+            exit_tx,
+            exit_rx: Some(exit_rx),
+            last_start: None,
+            server_done: None,
         }
     }
 
+    /// 取出服务器任务退出通知的接收端（只能取一次，由监控任务持有）
+    pub fn take_exit_receiver(&mut self) -> Option<mpsc::UnboundedReceiver<ServerExit>> {
+        self.exit_rx.take()
+    }
+
+    /// 退出通知是否对应当前运行中的服务器（而不是已停止或已被重启的旧任务）
+    pub fn is_current_run(&self, exit: &ServerExit) -> bool {
+        self.running && exit.generation == self.generation
+    }
+
     pub fn status(&self) -> ServerStatus {
         ServerStatus {
             running: self.running,
@@ -206,9 +546,23 @@ impl ServerState {
             port: self.config.server.port,
             requests: self.requests,
             uptime_secs: self.start_time.map(|t| t.elapsed().as_secs()).unwrap_or(0),
+            proxy_paused: self.is_proxy_paused(),
         }
     }
 
+    /// 代理是否已暂停
+    pub fn is_proxy_paused(&self) -> bool {
+        self.proxy_paused.load(Ordering::SeqCst)
+    }
+
+    /// 设置代理暂停状态
+    ///
+    /// 暂停后所有补全路由返回 503，`/health` 和管理端点不受影响。
+    /// 返回设置前的状态。
+    pub fn set_proxy_paused(&self, paused: bool) -> bool {
+        self.proxy_paused.swap(paused, Ordering::SeqCst)
+    }
+
     /// 增加请求计数
     pub fn increment_request_count(&mut self) {
         self.requests = self.requests.saturating_add(1);
@@ -274,21 +628,37 @@ impl ServerState {
             return Ok(());
         }
 
+        self.last_start = Some(ServerStartArgs {
+            logs: logs.clone(),
+            pool_service: pool_service.clone(),
+            token_cache: token_cache.clone(),
+            db: db.clone(),
+            shared_stats: shared_stats.clone(),
+            shared_tokens: shared_tokens.clone(),
+            shared_logger: shared_logger.clone(),
+            shared_flow_monitor: shared_flow_monitor.clone(),
+            shared_flow_interceptor: shared_flow_interceptor.clone(),
+        });
+
         let (tx, rx) = oneshot::channel();
         self.shutdown_tx = Some(tx);
 
         let host = self.config.server.host.clone();
         let port = self.config.server.port;
-        let api_key = self.config.server.api_key.clone();
-        let api_key_for_state = api_key.clone(); // 用于保存到 running_api_key
+        let api_key_for_state = self.config.server.api_key.clone(); // 用于保存到 running_api_key
+        self.server_api_key.sync(&api_key_for_state);
+        let api_key = self.server_api_key.clone();
         let default_provider_ref = self.default_provider_ref.clone();
+        let proxy_paused = self.proxy_paused.clone();
+        let config_manager = self.config_manager.clone();
 
         // 重新加载凭证
         let _ = self.kiro_provider.load_credentials().await;
         let kiro = self.kiro_provider.clone();
 
-        // 创建参数注入器
-        let injection_enabled = self.config.injection.enabled;
+        // 创建参数注入器（规则在 run_server 中同步到共享注入器）
+        let injection_enabled = self.injection_enabled.clone();
+        *injection_enabled.write().await = self.config.injection.enabled;
         let injector = Injector::with_rules(
             self.config
                 .injection
@@ -304,13 +674,48 @@ impl ServerState {
 
         // 创建请求处理器（在 spawn 之前创建，以便保存 router_ref）
         let processor = match (&shared_stats, &shared_tokens) {
-            (Some(stats), Some(tokens)) => Arc::new(RequestProcessor::with_shared_telemetry(
+            (Some(stats), Some(tokens)) => RequestProcessor::with_shared_telemetry(
                 pool_service.clone(),
                 stats.clone(),
                 tokens.clone(),
-            )),
-            _ => Arc::new(RequestProcessor::with_defaults(pool_service.clone())),
+            ),
+            _ => RequestProcessor::with_defaults(pool_service.clone()),
         };
+        let processor = Arc::new(
+            processor
+                .with_concurrency_limiter(self.concurrency_limiter.clone())
+                .with_response_cache(self.response_cache.clone())
+                .with_request_traces(self.request_traces.clone())
+                .with_injector(self.injector.clone()),
+        );
+
+        // 从配置初始化并发限制和响应缓存
+        self.concurrency_limiter
+            .update_config((&config.concurrency).into());
+        self.response_cache
+            .update_config((&config.response_cache).into());
+
+        // 从配置初始化模型别名与规范化开关
+        {
+            let mut mapper = processor.mapper.write().await;
+            for (alias, model) in &config.routing.model_aliases {
+                mapper.add_alias(alias, model);
+            }
+            mapper.set_normalization(config.routing.normalize_model_names);
+        }
+        processor
+            .pool_service
+            .set_tier_rules(config.routing.tier_rules.clone());
+        processor
+            .pool_service
+            .set_health_scoring(config.health_scoring.clone());
+        processor.pool_service.set_selection_strategy(
+            config.routing.selection_strategy,
+            config.routing.latency_exploration_rate,
+        );
+        processor
+            .pool_service
+            .set_credential_fallback(&config.routing.credential_fallback);
 
         // 从配置初始化 Router 的默认 Provider
         {
@@ -346,11 +751,30 @@ impl ServerState {
         // 保存 router_ref 以便后续动态更新
         self.router_ref = Some(processor.router.clone());
 
-        tokio::spawn(async move {
-            if let Err(e) = run_server(
+        // 创建配置重载器（文件监控与 reload_config 命令共用）
+        let config_reloader = Arc::new(
+            ConfigReloader::new(
+                &config,
+                config_path.clone(),
+                processor.clone(),
+                logs.clone(),
+                db.clone(),
+            )
+            .with_observers(config_manager.clone()),
+        );
+        self.config_reloader = Some(config_reloader.clone());
+
+        self.generation += 1;
+        let generation = self.generation;
+        let exit_tx = self.exit_tx.clone();
+        let (done_tx, done_rx) = oneshot::channel();
+        self.server_done = Some(done_rx);
+
+        let server_task = tokio::spawn(async move {
+            run_server(
                 &host,
                 port,
-                &api_key,
+                api_key,
                 default_provider_ref,
                 kiro,
                 logs,
@@ -368,11 +792,28 @@ impl ServerState {
                 Some(config),
                 Some(config_path),
                 Some(processor),
+                Some(config_reloader),
+                proxy_paused,
+                config_manager,
             )
             .await
-            {
-                tracing::error!("Server error: {}", e);
-            }
+            .map_err(|e| e.to_string())
+        });
+        // 等待服务器任务结束（包括 panic），通知监控任务
+        tokio::spawn(async move {
+            let error = match server_task.await {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => {
+                    tracing::error!("Server error: {}", e);
+                    Some(e)
+                }
+                Err(e) => {
+                    tracing::error!("[SERVER] 服务器任务异常退出: {}", e);
+                    Some(e.to_string())
+                }
+            };
+            let _ = exit_tx.send(ServerExit { generation, error });
+            let _ = done_tx.send(());
         });
 
         self.running = true;
@@ -382,6 +823,27 @@ impl ServerState {
         Ok(())
     }
 
+    /// 使用上一次启动的共享实例重新启动服务器
+    pub async fn restart(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let args = self
+            .last_start
+            .clone()
+            .ok_or("服务器从未启动过，无法重启")?;
+        self.stop().await;
+        self.start_with_telemetry_and_flow_monitor(
+            args.logs,
+            args.pool_service,
+            args.token_cache,
+            args.db,
+            args.shared_stats,
+            args.shared_tokens,
+            args.shared_logger,
+            args.shared_flow_monitor,
+            args.shared_flow_interceptor,
+        )
+        .await
+    }
+
     pub async fn stop(&mut self) {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
@@ -390,6 +852,18 @@ impl ServerState {
         self.start_time = None;
         self.running_api_key = None;
         self.router_ref = None;
+        self.config_reloader = None;
+        self.server_done = None;
+    }
+
+    /// 停止服务器并返回服务器任务结束通知
+    ///
+    /// 服务器停止接受新连接后会等待进行中的请求完成才结束任务；调用方应释放
+    /// `ServerState` 的锁后再等待该通知。服务器未运行时返回 None。
+    pub async fn stop_graceful(&mut self) -> Option<oneshot::Receiver<()>> {
+        let done = self.server_done.take();
+        self.stop().await;
+        done
     }
 }
 
@@ -397,7 +871,6 @@ impl Clone for KiroProvider {
     fn clone(&self) -> Self {
         Self {
             credentials: self.credentials.clone(),
-            client: reqwest::Client::new(),
             creds_path: self.creds_path.clone(),
         }
     }
@@ -408,7 +881,7 @@ pub mod handlers;
 #[derive(Clone)]
 #[allow(dead_code)]
 pub struct AppState {
-    pub api_key: String,
+    pub api_key: ServerApiKey,
     pub base_url: String,
     pub default_provider: Arc<RwLock<String>>,
     pub kiro: Arc<RwLock<KiroProvider>>,
@@ -445,6 +918,184 @@ pub struct AppState {
     pub kiro_event_service: Arc<KiroEventService>,
     /// API Key Provider 服务（用于智能降级）
     pub api_key_service: Arc<crate::services::api_key_provider_service::ApiKeyProviderService>,
+    /// 代理暂停开关（全局熔断）
+    pub proxy_paused: Arc<AtomicBool>,
+    /// 已提交的 Message Batches（batch_id -> 凭证）
+    pub batches: Arc<handlers::BatchRegistry>,
+    /// 进行中的流式响应（request_id -> 事件缓冲），用于 SSE 断线续传
+    pub active_streams: Arc<stream_resume::ActiveStreamRegistry>,
+    /// 全局配置管理器（管理 WebSocket 的配置读取、订阅和修改）
+    pub config_manager: Option<Arc<GlobalConfigManager>>,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// 测试用状态：内存数据库、空凭证池，其余组件使用默认配置
+    pub(crate) fn for_tests(api_key: &str) -> Self {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        let pool_service = Arc::new(ProviderPoolService::new());
+        let ws_manager = Arc::new(WsConnectionManager::new(WsConfig::default()));
+        Self {
+            api_key: ServerApiKey::new(api_key),
+            base_url: "http://127.0.0.1:0".to_string(),
+            default_provider: Arc::new(RwLock::new("kiro".to_string())),
+            kiro: Arc::new(RwLock::new(KiroProvider::new())),
+            logs: Arc::new(RwLock::new(LogStore::default())),
+            kiro_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+            gemini_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+            qwen_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+            pool_service: pool_service.clone(),
+            token_cache: Arc::new(TokenCacheService::new()),
+            db: Some(Arc::new(std::sync::Mutex::new(conn))),
+            injector: Arc::new(RwLock::new(Injector::new())),
+            injection_enabled: Arc::new(RwLock::new(false)),
+            processor: Arc::new(RequestProcessor::with_defaults(pool_service)),
+            ws_stats: ws_manager.stats().clone(),
+            ws_manager,
+            hot_reload_manager: None,
+            request_logger: None,
+            amp_router: Arc::new(crate::router::AmpRouter::new(Default::default())),
+            flow_monitor: Arc::new(FlowMonitor::new(FlowMonitorConfig::default(), None)),
+            flow_interceptor: Arc::new(FlowInterceptor::default()),
+            endpoint_providers: Arc::new(RwLock::new(EndpointProvidersConfig::default())),
+            kiro_event_service: Arc::new(KiroEventService::new()),
+            api_key_service: Arc::new(
+                crate::services::api_key_provider_service::ApiKeyProviderService::new(),
+            ),
+            proxy_paused: Arc::new(AtomicBool::new(false)),
+            batches: Arc::new(handlers::BatchRegistry::new()),
+            active_streams: Arc::new(stream_resume::ActiveStreamRegistry::new()),
+            config_manager: None,
+        }
+    }
+}
+
+/// 配置重载器
+///
+/// 封装一次完整的配置重载：从磁盘重新加载（失败时自动回滚）、更新处理器组件、同步凭证池。
+/// 由配置文件监控和 `reload_config` 命令共用；`HotReloadManager` 保证同一时间只有一次重载，
+/// 组件更新均为原子替换，可在请求处理过程中安全调用。
+pub struct ConfigReloader {
+    hot_reload_manager: Arc<HotReloadManager>,
+    config_manager: Arc<std::sync::RwLock<ConfigManager>>,
+    processor: Arc<RequestProcessor>,
+    logs: Arc<RwLock<LogStore>>,
+    db: Option<DbConnection>,
+    /// 重载成功后通知的配置观察者（功能设置、凭证池等）
+    observers: Option<Arc<GlobalConfigManager>>,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        config: &Config,
+        config_path: PathBuf,
+        processor: Arc<RequestProcessor>,
+        logs: Arc<RwLock<LogStore>>,
+        db: Option<DbConnection>,
+    ) -> Self {
+        Self {
+            hot_reload_manager: Arc::new(HotReloadManager::new(
+                config.clone(),
+                config_path.clone(),
+            )),
+            config_manager: Arc::new(std::sync::RwLock::new(ConfigManager::with_config(
+                config.clone(),
+                config_path,
+            ))),
+            processor,
+            logs,
+            db,
+            observers: None,
+        }
+    }
+
+    /// 重载成功后通知全局配置管理器中的观察者
+    pub fn with_observers(mut self, observers: Option<Arc<GlobalConfigManager>>) -> Self {
+        self.observers = observers;
+        self
+    }
+
+    /// 热重载管理器
+    pub fn hot_reload_manager(&self) -> Arc<HotReloadManager> {
+        self.hot_reload_manager.clone()
+    }
+
+    /// 当前生效的配置
+    pub fn config(&self) -> Config {
+        self.hot_reload_manager.config()
+    }
+
+    /// 从磁盘重新加载配置并应用
+    pub async fn reload(&self) -> ReloadResult {
+        let result = self.hot_reload_manager.reload();
+        match &result {
+            ReloadResult::Success { .. } => {
+                tracing::info!("[HOT_RELOAD] 配置热重载成功");
+                self.logs
+                    .write()
+                    .await
+                    .add("info", "[HOT_RELOAD] 配置热重载成功");
+
+                // 更新处理器中的组件
+                let new_config = self.hot_reload_manager.config();
+                update_processor_config(&self.processor, &new_config).await;
+                if let Some(observers) = &self.observers {
+                    observers
+                        .update_config(new_config.clone(), ConfigChangeSource::HotReload)
+                        .await;
+                }
+
+                // 同步凭证池
+                if let Some(ref db) = self.db {
+                    match sync_credential_pool_from_config(db, &self.config_manager, &self.logs)
+                        .await
+                    {
+                        Ok(count) => {
+                            tracing::info!("[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证", count);
+                            self.logs.write().await.add(
+                                "info",
+                                &format!("[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证", count),
+                            );
+                        }
+                        Err(e) => {
+                            tracing::warn!("[HOT_RELOAD] 凭证池同步失败: {}", e);
+                            self.logs
+                                .write()
+                                .await
+                                .add("warn", &format!("[HOT_RELOAD] 凭证池同步失败: {}", e));
+                        }
+                    }
+                }
+            }
+            ReloadResult::RolledBack { error, .. } => {
+                tracing::warn!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error);
+                self.logs.write().await.add(
+                    "warn",
+                    &format!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error),
+                );
+            }
+            ReloadResult::Failed {
+                error,
+                rollback_error,
+                ..
+            } => {
+                tracing::error!(
+                    "[HOT_RELOAD] 配置热重载失败: {}, 回滚错误: {:?}",
+                    error,
+                    rollback_error
+                );
+                self.logs.write().await.add(
+                    "error",
+                    &format!(
+                        "[HOT_RELOAD] 配置热重载失败: {}, 回滚错误: {:?}",
+                        error, rollback_error
+                    ),
+                );
+            }
+        }
+        result
+    }
 }
 
 /// 启动配置文件监控
@@ -460,11 +1111,8 @@ pub struct AppState {
 /// - HTTP 和 WebSocket 连接保持活跃
 async fn start_config_watcher(
     config_path: PathBuf,
-    hot_reload_manager: Option<Arc<HotReloadManager>>,
-    processor: Arc<RequestProcessor>,
+    config_reloader: Option<Arc<ConfigReloader>>,
     logs: Arc<RwLock<LogStore>>,
-    db: Option<DbConnection>,
-    config_manager: Option<Arc<std::sync::RwLock<ConfigManager>>>,
 ) -> Option<FileWatcher> {
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<FileChangeEvent>();
 
@@ -486,12 +1134,6 @@ async fn start_config_watcher(
     tracing::info!("[HOT_RELOAD] 配置文件监控已启动: {:?}", config_path);
 
     // 启动事件处理任务
-    let hot_reload_manager_clone = hot_reload_manager.clone();
-    let processor_clone = processor.clone();
-    let logs_clone = logs.clone();
-    let db_clone = db.clone();
-    let config_manager_clone = config_manager.clone();
-
     tokio::spawn(async move {
         while let Some(event) = rx.recv().await {
             // 只处理修改事件
@@ -500,82 +1142,14 @@ async fn start_config_watcher(
             }
 
             tracing::info!("[HOT_RELOAD] 检测到配置文件变更: {:?}", event.path);
-            logs_clone.write().await.add(
+            logs.write().await.add(
                 "info",
                 &format!("[HOT_RELOAD] 检测到配置文件变更: {:?}", event.path),
             );
 
             // 执行热重载
-            if let Some(ref manager) = hot_reload_manager_clone {
-                let result = manager.reload();
-                match &result {
-                    ReloadResult::Success { .. } => {
-                        tracing::info!("[HOT_RELOAD] 配置热重载成功");
-                        logs_clone
-                            .write()
-                            .await
-                            .add("info", "[HOT_RELOAD] 配置热重载成功");
-
-                        // 更新处理器中的组件
-                        let new_config = manager.config();
-                        update_processor_config(&processor_clone, &new_config).await;
-
-                        // 同步凭证池
-                        if let (Some(ref db), Some(ref cfg_manager)) =
-                            (&db_clone, &config_manager_clone)
-                        {
-                            match sync_credential_pool_from_config(db, cfg_manager, &logs_clone)
-                                .await
-                            {
-                                Ok(count) => {
-                                    tracing::info!(
-                                        "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证",
-                                        count
-                                    );
-                                    logs_clone.write().await.add(
-                                        "info",
-                                        &format!(
-                                            "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证",
-                                            count
-                                        ),
-                                    );
-                                }
-                                Err(e) => {
-                                    tracing::warn!("[HOT_RELOAD] 凭证池同步失败: {}", e);
-                                    logs_clone.write().await.add(
-                                        "warn",
-                                        &format!("[HOT_RELOAD] 凭证池同步失败: {}", e),
-                                    );
-                                }
-                            }
-                        }
-                    }
-                    ReloadResult::RolledBack { error, .. } => {
-                        tracing::warn!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error);
-                        logs_clone.write().await.add(
-                            "warn",
-                            &format!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error),
-                        );
-                    }
-                    ReloadResult::Failed {
-                        error,
-                        rollback_error,
-                        ..
-                    } => {
-                        tracing::error!(
-                            "[HOT_RELOAD] 配置热重载失败: {}, 回滚错误: {:?}",
-                            error,
-                            rollback_error
-                        );
-                        logs_clone.write().await.add(
-                            "error",
-                            &format!(
-                                "[HOT_RELOAD] 配置热重载失败: {}, 回滚错误: {:?}",
-                                error, rollback_error
-                            ),
-                        );
-                    }
-                }
+            if let Some(ref reloader) = config_reloader {
+                reloader.reload().await;
             }
         }
     });
@@ -643,12 +1217,39 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
         for (alias, model) in &config.routing.model_aliases {
             mapper.add_alias(alias, model);
         }
+        mapper.set_normalization(config.routing.normalize_model_names);
         tracing::debug!(
             "[HOT_RELOAD] 模型别名已更新: {} 个别名",
             config.routing.model_aliases.len()
         );
     }
 
+    // 功能模块的运行时设置由配置观察者更新（见 ConfigReloader::reload）
+
+    // 更新凭证层级偏好、健康评分、选择策略与降级链配置
+    processor
+        .pool_service
+        .set_tier_rules(config.routing.tier_rules.clone());
+    processor
+        .pool_service
+        .set_health_scoring(config.health_scoring.clone());
+    processor.pool_service.set_selection_strategy(
+        config.routing.selection_strategy,
+        config.routing.latency_exploration_rate,
+    );
+    processor
+        .pool_service
+        .set_credential_fallback(&config.routing.credential_fallback);
+
+    // 更新并发限制
+    processor
+        .concurrency
+        .update_config((&config.concurrency).into());
+    tracing::debug!(
+        "[HOT_RELOAD] 并发限制已更新: {} 个 Provider",
+        config.concurrency.per_provider.len()
+    );
+
     // 注意：重试配置目前不支持热更新，因为 Retrier 是不可变的
     // 如果需要更新重试配置，需要重启服务器
     tracing::debug!(
@@ -715,7 +1316,7 @@ async fn sync_credential_pool_from_config(
 async fn run_server(
     host: &str,
     port: u16,
-    api_key: &str,
+    api_key: ServerApiKey,
     default_provider: Arc<RwLock<String>>,
     kiro: KiroProvider,
     logs: Arc<RwLock<LogStore>>,
@@ -724,7 +1325,7 @@ async fn run_server(
     token_cache: Arc<TokenCacheService>,
     db: Option<DbConnection>,
     injector: Injector,
-    injection_enabled: bool,
+    injection_enabled: Arc<RwLock<bool>>,
     shared_stats: Option<Arc<parking_lot::RwLock<crate::telemetry::StatsAggregator>>>,
     shared_tokens: Option<Arc<parking_lot::RwLock<crate::telemetry::TokenTracker>>>,
     shared_logger: Option<Arc<crate::telemetry::RequestLogger>>,
@@ -733,6 +1334,9 @@ async fn run_server(
     config: Option<Config>,
     config_path: Option<PathBuf>,
     processor: Option<Arc<RequestProcessor>>,
+    config_reloader: Option<Arc<ConfigReloader>>,
+    proxy_paused: Arc<AtomicBool>,
+    config_manager: Option<Arc<GlobalConfigManager>>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let base_url = format!("http://{}:{}", host, port);
 
@@ -749,9 +1353,10 @@ async fn run_server(
         },
     };
 
-    // 将注入器规则同步到处理器
+    // 将注入器规则同步到处理器（注入器跨重启共享，先清空旧规则）
     {
         let mut proc_injector = processor.injector.write().await;
+        proc_injector.clear();
         for rule in injector.rules() {
             proc_injector.add_rule(rule.clone());
         }
@@ -786,27 +1391,36 @@ async fn run_server(
         }
     }
 
-    // 初始化 WebSocket 管理器
-    let ws_manager = Arc::new(WsConnectionManager::new(WsConfig::default()));
+    // 初始化 WebSocket 管理器，压缩开关随配置变更更新
+    let ws_manager = Arc::new(WsConnectionManager::new(WsConfig {
+        compression: config.as_ref().is_some_and(|c| c.websocket.compression),
+        ..WsConfig::default()
+    }));
     let ws_stats = ws_manager.stats().clone();
+    if let Some(manager) = &config_manager {
+        manager.unregister_observer(WsConfigObserver::NAME);
+        manager.register_observer(Arc::new(WsConfigObserver::new(ws_manager.clone())));
+    }
 
-    // 初始化热重载管理器
-    let hot_reload_manager = match (&config, &config_path) {
-        (Some(cfg), Some(path)) => Some(Arc::new(HotReloadManager::new(cfg.clone(), path.clone()))),
+    // 初始化配置重载器（未传入时从配置创建）
+    let config_reloader = config_reloader.or_else(|| match (&config, &config_path) {
+        (Some(cfg), Some(path)) => Some(Arc::new(
+            ConfigReloader::new(
+                cfg,
+                path.clone(),
+                processor.clone(),
+                logs.clone(),
+                db.clone(),
+            )
+            .with_observers(config_manager.clone()),
+        )),
         _ => None,
-    };
-
-    // 初始化配置管理器（用于凭证池同步）
-    let config_manager: Option<Arc<std::sync::RwLock<ConfigManager>>> =
-        match (&config, &config_path) {
-            (Some(cfg), Some(path)) => Some(Arc::new(std::sync::RwLock::new(
-                ConfigManager::with_config(cfg.clone(), path.clone()),
-            ))),
-            _ => None,
-        };
+    });
+    let hot_reload_manager = config_reloader
+        .as_ref()
+        .map(|reloader| reloader.hot_reload_manager());
 
     let logs_clone = logs.clone();
-    let db_clone = db.clone();
 
     // 初始化 Amp CLI 路由器
     let amp_router = Arc::new(crate::router::AmpRouter::new(
@@ -840,7 +1454,7 @@ async fn run_server(
         Arc::new(crate::services::api_key_provider_service::ApiKeyProviderService::new());
 
     let state = AppState {
-        api_key: api_key.to_string(),
+        api_key,
         base_url,
         default_provider,
         kiro: Arc::new(RwLock::new(kiro)),
@@ -852,11 +1466,11 @@ async fn run_server(
         token_cache,
         db,
         injector: Arc::new(RwLock::new(injector)),
-        injection_enabled: Arc::new(RwLock::new(injection_enabled)),
+        injection_enabled,
         processor: processor.clone(),
         ws_manager,
         ws_stats,
-        hot_reload_manager: hot_reload_manager.clone(),
+        hot_reload_manager,
         request_logger: shared_logger,
         amp_router,
         flow_monitor,
@@ -864,6 +1478,10 @@ async fn run_server(
         endpoint_providers,
         kiro_event_service,
         api_key_service,
+        proxy_paused: proxy_paused.clone(),
+        batches: Arc::new(handlers::BatchRegistry::new()),
+        active_streams: Arc::new(stream_resume::ActiveStreamRegistry::new()),
+        config_manager,
     };
 
     // ========== 开发模式：启动独立的 HTTP 桥接服务器 ==========
@@ -890,15 +1508,7 @@ async fn run_server(
 
     // 启动配置文件监控
     let _file_watcher = if let Some(path) = config_path {
-        start_config_watcher(
-            path,
-            hot_reload_manager,
-            processor,
-            logs_clone,
-            db_clone,
-            config_manager,
-        )
-        .await
+        start_config_watcher(path, config_reloader, logs_clone).await
     } else {
         None
     };
@@ -930,6 +1540,7 @@ async fn run_server(
             "/v0/management/config",
             axum::routing::put(handlers::management_update_config),
         )
+        .route("/v0/management/ws", get(handlers::management_config_ws))
         .layer(crate::middleware::ManagementAuthLayer::new(
             management_config,
         ));
@@ -961,13 +1572,28 @@ async fn run_server(
             get(handlers::credentials_get_token),
         );
 
-    let app = Router::new()
-        .route("/health", get(health))
-        .route("/v1/models", get(models))
-        .route("/v1/routes", get(list_routes))
+    // 补全类路由（会向上游发送请求，受代理暂停开关控制）
+    let completion_routes = Router::new()
         .route("/v1/chat/completions", post(handlers::chat_completions))
+        .route("/v1/completions", post(handlers::legacy_completions))
         .route("/v1/messages", post(handlers::anthropic_messages))
-        .route("/v1/messages/count_tokens", post(count_tokens))
+        // Anthropic Message Batches 透传
+        .route(
+            "/v1/messages/batches",
+            get(handlers::list_message_batches).post(handlers::create_message_batch),
+        )
+        .route(
+            "/v1/messages/batches/:batch_id",
+            get(handlers::get_message_batch),
+        )
+        .route(
+            "/v1/messages/batches/:batch_id/results",
+            get(handlers::get_message_batch_results),
+        )
+        .route(
+            "/v1/messages/batches/:batch_id/cancel",
+            post(handlers::cancel_message_batch),
+        )
         // 图像生成 API 路由
         .route(
             "/v1/images/generations",
@@ -993,6 +1619,18 @@ async fn run_server(
             post(amp_chat_completions),
         )
         .route("/api/provider/:provider/v1/messages", post(amp_messages))
+        .route_layer(crate::middleware::UserLimitsLayer::new(body_limit))
+        .route_layer(crate::middleware::RequestPriorityLayer::new())
+        .route_layer(crate::middleware::ProxyPauseLayer::new(proxy_paused));
+
+    let app = Router::new()
+        .route("/health", get(health))
+        .route("/v1/models", get(models))
+        .route("/v1/routes", get(list_routes))
+        .route("/v1/messages/count_tokens", post(count_tokens))
+        // SSE 断线续传
+        .route("/v1/streams/:request_id", get(stream_resume::resume_stream))
+        .merge(completion_routes)
         // Amp CLI 管理代理路由
         .route(
             "/api/auth/*path",
@@ -1111,15 +1749,11 @@ async fn gemini_generate_content(
     let cred = match credential {
         Some(c) => c,
         None => {
-            return (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("No available credentials for provider '{}'. Please add credentials in the Provider Pool.", default_provider)
-                    }
-                })),
-            )
-                .into_response();
+            return ProcessError::NoCredential(format!(
+                "No available credentials for provider '{}'. Please add credentials in the Provider Pool.",
+                default_provider
+            ))
+            .into_response_for(ErrorFormat::OpenAI);
         }
     };
 
@@ -1198,30 +1832,14 @@ async fn gemini_generate_content(
                 }
             }
 
-            // 设置项目 ID
-            if let Some(pid) = project_id {
-                antigravity.project_id = Some(pid.clone());
-            } else if antigravity.project_id.is_none() {
-                // 如果凭证中没有 project_id，尝试从 API 获取或生成随机 ID
-                if let Err(e) = antigravity.discover_project().await {
-                    tracing::warn!("[Antigravity] 获取项目 ID 失败: {}，使用随机生成的 ID", e);
-                    // 生成随机项目 ID
-                    let uuid = uuid::Uuid::new_v4();
-                    let bytes = uuid.as_bytes();
-                    let adjectives = ["useful", "bright", "swift", "calm", "bold"];
-                    let nouns = ["fuze", "wave", "spark", "flow", "core"];
-                    let adj = adjectives[(bytes[0] as usize) % adjectives.len()];
-                    let noun = nouns[(bytes[1] as usize) % nouns.len()];
-                    let random_part: String = uuid.to_string()[..5].to_lowercase();
-                    antigravity.project_id = Some(format!("{}-{}-{}", adj, noun, random_part));
-                }
-            }
-
-            let proj_id = antigravity.project_id.clone().unwrap_or_else(|| {
-                // 最后的后备：生成随机 ID
-                let uuid = uuid::Uuid::new_v4();
-                format!("proxycast-{}", &uuid.to_string()[..8])
-            });
+            // 设置项目 ID（凭证缓存 → 自动发现 → 随机 ID）
+            let proj_id = handlers::resolve_antigravity_project_id(
+                &state,
+                &cred,
+                project_id.as_deref(),
+                &mut antigravity,
+            )
+            .await;
 
             state
                 .logs
@@ -1306,8 +1924,13 @@ async fn gemini_generate_content(
     }
 }
 
-/// 列出所有可用路由
-async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
+/// 列出可用路由
+///
+/// 支持查询参数 `provider_type`、`tag` 过滤以及 `limit` / `offset` 分页，默认路由始终排在第一位。
+async fn list_routes(
+    State(state): State<AppState>,
+    Query(query): Query<RouteQuery>,
+) -> impl IntoResponse {
     let routes = match &state.db {
         Some(db) => state
             .pool_service
@@ -1319,35 +1942,12 @@ async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
     // 获取默认 Provider
     let default_provider = state.default_provider.read().await.clone();
 
-    // 添加默认路由
-    let mut all_routes = vec![RouteInfo {
-        selector: "default".to_string(),
-        provider_type: default_provider.clone(),
-        credential_count: 1,
-        endpoints: vec![
-            crate::models::route_model::RouteEndpoint {
-                path: "/v1/messages".to_string(),
-                protocol: "claude".to_string(),
-                url: format!("{}/v1/messages", state.base_url),
-            },
-            crate::models::route_model::RouteEndpoint {
-                path: "/v1/chat/completions".to_string(),
-                protocol: "openai".to_string(),
-                url: format!("{}/v1/chat/completions", state.base_url),
-            },
-        ],
-        tags: vec!["默认".to_string()],
-        enabled: true,
-    }];
-    all_routes.extend(routes);
-
-    let response = RouteListResponse {
-        base_url: state.base_url.clone(),
+    Json(RouteListResponse::build(
+        state.base_url.clone(),
         default_provider,
-        routes: all_routes,
-    };
-
-    Json(response)
+        routes,
+        &query,
+    ))
 }
 
 /// 带选择器的 Anthropic messages 处理
@@ -1355,7 +1955,7 @@ async fn anthropic_messages_with_selector(
     State(state): State<AppState>,
     Path(selector): Path<String>,
     headers: HeaderMap,
-    Json(request): Json<AnthropicMessagesRequest>,
+    Json(mut request): Json<AnthropicMessagesRequest>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证
     if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
@@ -1413,7 +2013,16 @@ async fn anthropic_messages_with_selector(
 
             // 根据凭证类型调用相应的 Provider
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
+            tool_result_truncation::apply_and_log_anthropic(&state, &cred, &mut request).await;
+            let max_cost_usd = handlers::max_cost_from_headers(&headers);
+            retry_policy::call_with_retry(request.stream, || {
+                handlers::call_provider_anthropic(&state, &cred, &request, None, max_cost_usd)
+            })
+            .await
+            .unwrap_or_else(|e| {
+                dead_letter::record(&state, &cred, "/v1/messages", &request.model, &request, &e);
+                e.into_response_for(ErrorFormat::Anthropic)
+            })
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1424,16 +2033,19 @@ async fn anthropic_messages_with_selector(
                     selector
                 ),
             );
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "type": "provider_unavailable",
-                        "message": format!("No available credentials for selector '{}'", selector)
-                    }
-                })),
-            )
-                .into_response()
+            let error = ProcessError::NoCredential(format!(
+                "No available credentials for selector '{}'",
+                selector
+            ));
+            dead_letter::record_no_credential(
+                &state,
+                &selector,
+                "/v1/messages",
+                &request.model,
+                &request,
+                &error,
+            );
+            error.into_response_for(ErrorFormat::Anthropic)
         }
     }
 }
@@ -1443,7 +2055,7 @@ async fn chat_completions_with_selector(
     State(state): State<AppState>,
     Path(selector): Path<String>,
     headers: HeaderMap,
-    Json(request): Json<ChatCompletionRequest>,
+    Json(mut request): Json<ChatCompletionRequest>,
 ) -> Response {
     if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
         state.logs.write().await.add(
@@ -1494,7 +2106,24 @@ async fn chat_completions_with_selector(
             );
 
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
+            handlers::drop_unsupported_penalty_params(&cred, &mut request);
+            tool_result_truncation::apply_and_log_openai(&state, &cred, &mut request).await;
+            let max_cost_usd = handlers::max_cost_from_headers(&headers);
+            retry_policy::call_with_retry(request.stream, || {
+                handlers::call_provider_openai(&state, &cred, &request, None, max_cost_usd)
+            })
+            .await
+            .unwrap_or_else(|e| {
+                dead_letter::record(
+                    &state,
+                    &cred,
+                    "/v1/chat/completions",
+                    &request.model,
+                    &request,
+                    &e,
+                );
+                e.into_response_for(ErrorFormat::OpenAI)
+            })
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1505,17 +2134,19 @@ async fn chat_completions_with_selector(
                     selector
                 ),
             );
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("No available credentials for selector '{}'", selector),
-                        "type": "provider_unavailable",
-                        "code": "no_credentials"
-                    }
-                })),
-            )
-                .into_response()
+            let error = ProcessError::NoCredential(format!(
+                "No available credentials for selector '{}'",
+                selector
+            ));
+            dead_letter::record_no_credential(
+                &state,
+                &selector,
+                "/v1/chat/completions",
+                &request.model,
+                &request,
+                &error,
+            );
+            error.into_response_for(ErrorFormat::OpenAI)
         }
     }
 }
@@ -1650,7 +2281,24 @@ async fn amp_chat_completions(
                 ),
             );
             // 注意：这里没有 Flow 捕获，因为是通过 AMP CLI 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
+            handlers::drop_unsupported_penalty_params(&cred, &mut request);
+            tool_result_truncation::apply_and_log_openai(&state, &cred, &mut request).await;
+            let max_cost_usd = handlers::max_cost_from_headers(&headers);
+            retry_policy::call_with_retry(request.stream, || {
+                handlers::call_provider_openai(&state, &cred, &request, None, max_cost_usd)
+            })
+            .await
+            .unwrap_or_else(|e| {
+                dead_letter::record(
+                    &state,
+                    &cred,
+                    "/v1/chat/completions",
+                    &request.model,
+                    &request,
+                    &e,
+                );
+                e.into_response_for(ErrorFormat::OpenAI)
+            })
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1661,17 +2309,19 @@ async fn amp_chat_completions(
                     provider
                 ),
             );
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("No available credentials for provider '{}'", provider),
-                        "type": "provider_unavailable",
-                        "code": "no_credentials"
-                    }
-                })),
-            )
-                .into_response()
+            let error = ProcessError::NoCredential(format!(
+                "No available credentials for provider '{}'",
+                provider
+            ));
+            dead_letter::record_no_credential(
+                &state,
+                &provider,
+                "/v1/chat/completions",
+                &request.model,
+                &request,
+                &error,
+            );
+            error.into_response_for(ErrorFormat::OpenAI)
         }
     }
 }
@@ -1789,7 +2439,16 @@ async fn amp_messages(
                 ),
             );
             // 注意：这里没有 Flow 捕获，因为是通过 AMP CLI 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
+            tool_result_truncation::apply_and_log_anthropic(&state, &cred, &mut request).await;
+            let max_cost_usd = handlers::max_cost_from_headers(&headers);
+            retry_policy::call_with_retry(request.stream, || {
+                handlers::call_provider_anthropic(&state, &cred, &request, None, max_cost_usd)
+            })
+            .await
+            .unwrap_or_else(|e| {
+                dead_letter::record(&state, &cred, "/v1/messages", &request.model, &request, &e);
+                e.into_response_for(ErrorFormat::Anthropic)
+            })
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1800,16 +2459,19 @@ async fn amp_messages(
                     provider
                 ),
             );
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "type": "provider_unavailable",
-                        "message": format!("No available credentials for provider '{}'", provider)
-                    }
-                })),
-            )
-                .into_response()
+            let error = ProcessError::NoCredential(format!(
+                "No available credentials for provider '{}'",
+                provider
+            ));
+            dead_letter::record_no_credential(
+                &state,
+                &provider,
+                "/v1/messages",
+                &request.model,
+                &request,
+                &error,
+            );
+            error.into_response_for(ErrorFormat::Anthropic)
         }
     }
 }
@@ -1923,8 +2585,8 @@ async fn amp_management_proxy_internal(
         ),
     );
 
-    // 创建 HTTP 客户端
-    let client = reqwest::Client::new();
+    // 使用共享 HTTP 客户端（复用连接池，按当前出站代理配置获取）
+    let client = crate::http_client::shared_client();
 
     // 构建请求
     let mut request_builder = match method {
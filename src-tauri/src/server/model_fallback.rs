@@ -0,0 +1,166 @@
+//! 模型降级链
+//!
+//! 配置 `routing.model_fallbacks` 后，请求模型在当前 Provider 上（包括凭证降级链）找不到
+//! 可用凭证时，按配置顺序改用备选模型，如 `claude-opus-4-5` 依次降级到 `claude-sonnet-4-5`、
+//! `gemini-2.5-pro`。备选模型同样走完整的凭证降级逻辑。
+//!
+//! 发生替换时改写 `ctx.resolved_model` 并写入日志，响应带上 [`MODEL_FALLBACK_HEADER`]
+//! 让客户端知道实际使用的模型。配置通过 [`configure`] 在启动和配置变更时更新。
+
+use std::collections::HashMap;
+
+use axum::http::HeaderValue;
+use axum::response::Response;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+use crate::config::Config;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::processor::{RequestContext, TraceStepKind};
+use crate::server::AppState;
+
+/// 发生模型替换时的响应头，值为 `<请求模型> -> <实际模型>`
+pub const MODEL_FALLBACK_HEADER: &str = "x-proxycast-model-fallback";
+
+/// 模型 -> 备选模型（按顺序）
+static FALLBACKS: Lazy<RwLock<HashMap<String, Vec<String>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 一次模型替换
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelSubstitution {
+    /// 客户端请求的模型（别名解析后）
+    pub requested: String,
+    /// 实际使用的模型
+    pub model: String,
+}
+
+/// 更新模型降级链（忽略空名称、指向自身和重复的备选模型）
+pub fn configure(config: &Config) {
+    let mut fallbacks = HashMap::new();
+    for (model, chain) in &config.routing.model_fallbacks {
+        let model = model.trim();
+        let mut targets: Vec<String> = Vec::new();
+        for target in chain.iter().map(|t| t.trim()) {
+            if !target.is_empty() && target != model && !targets.iter().any(|t| t == target) {
+                targets.push(target.to_string());
+            }
+        }
+        if !model.is_empty() && !targets.is_empty() {
+            fallbacks.insert(model.to_string(), targets);
+        }
+    }
+    *FALLBACKS.write() = fallbacks;
+}
+
+/// 指定模型的备选模型
+pub fn chain_for(model: &str) -> Vec<String> {
+    FALLBACKS.read().get(model).cloned().unwrap_or_default()
+}
+
+/// 依次为备选模型选择凭证，找到时改写 `ctx.resolved_model` 并返回凭证和替换记录
+///
+/// 应在请求模型的凭证选择（含凭证降级）失败后调用。
+pub async fn select(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    provider: &str,
+) -> Option<(ProviderCredential, ModelSubstitution)> {
+    let db = state.db.as_ref()?;
+    let requested = ctx.resolved_model.clone();
+    for model in chain_for(&requested) {
+        let credential = state
+            .pool_service
+            .select_credential_with_fallback(
+                db,
+                &state.api_key_service,
+                provider,
+                Some(&model),
+                None,
+            )
+            .ok()
+            .flatten();
+        let Some(credential) = credential else {
+            continue;
+        };
+
+        state.logs.write().await.add(
+            "warn",
+            &format!(
+                "[MODEL_FALLBACK] request_id={} model={} 无可用凭证，改用 {}",
+                ctx.request_id, requested, model
+            ),
+        );
+        ctx.record_step(TraceStepKind::Failover {
+            from: requested.clone(),
+            to: model.clone(),
+            reason: "No available credential for model".to_string(),
+        });
+        ctx.set_resolved_model(model.clone());
+        return Some((credential, ModelSubstitution { requested, model }));
+    }
+    None
+}
+
+/// 发生模型替换时在响应中加上 [`MODEL_FALLBACK_HEADER`]
+pub fn annotate(mut response: Response, substitution: Option<&ModelSubstitution>) -> Response {
+    if let Some(substitution) = substitution {
+        let value = format!("{} -> {}", substitution.requested, substitution.model);
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(MODEL_FALLBACK_HEADER, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    #[test]
+    fn test_configure_normalizes_chains() {
+        let mut config = Config::default();
+        config.routing.model_fallbacks.insert(
+            "claude-opus-4-5".to_string(),
+            vec![
+                " claude-sonnet-4-5 ".to_string(),
+                "claude-opus-4-5".to_string(),
+                "".to_string(),
+                "claude-sonnet-4-5".to_string(),
+                "gemini-2.5-pro".to_string(),
+            ],
+        );
+        config
+            .routing
+            .model_fallbacks
+            .insert("gpt-4o".to_string(), vec!["gpt-4o".to_string()]);
+        configure(&config);
+
+        assert_eq!(
+            chain_for("claude-opus-4-5"),
+            vec!["claude-sonnet-4-5", "gemini-2.5-pro"]
+        );
+        assert!(chain_for("gpt-4o").is_empty());
+        assert!(chain_for("claude-sonnet-4-5").is_empty());
+
+        configure(&Config::default());
+        assert!(chain_for("claude-opus-4-5").is_empty());
+    }
+
+    #[test]
+    fn test_annotate_adds_header_only_on_substitution() {
+        let substitution = ModelSubstitution {
+            requested: "claude-opus-4-5".to_string(),
+            model: "claude-sonnet-4-5".to_string(),
+        };
+        let response = annotate(Response::new(Body::empty()), Some(&substitution));
+        assert_eq!(
+            response.headers().get(MODEL_FALLBACK_HEADER).unwrap(),
+            "claude-opus-4-5 -> claude-sonnet-4-5"
+        );
+
+        let response = annotate(Response::new(Body::empty()), None);
+        assert!(response.headers().get(MODEL_FALLBACK_HEADER).is_none());
+    }
+}
@@ -0,0 +1,250 @@
+//! 请求限制
+//!
+//! 在调用上游之前检查工具数量和单个工具定义的大小，超出时返回 400 并列出超限项，
+//! 避免上游返回含义不明的错误。开启 `truncate_tool_descriptions` 时先把过长的工具描述
+//! 截断到软上限，再检查大小。
+//!
+//! 同时按模型配置（`models.providers.*.models[]`）的 `default_max_tokens` /
+//! `max_output_tokens` 填充缺失的 `max_tokens` 或截断超出模型上限的值。
+//!
+//! 配置通过 [`configure`] 在启动和配置变更时更新，默认不限制。
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use crate::config::{Config, RequestLimitsSettings};
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::{ChatCompletionRequest, Tool};
+use crate::processor::ProcessError;
+use crate::server_utils::safe_truncate;
+
+/// 超限时错误信息中最多列出的工具数
+const MAX_LISTED_TOOLS: usize = 5;
+
+static SETTINGS: Lazy<RwLock<RequestLimitsSettings>> =
+    Lazy::new(|| RwLock::new(RequestLimitsSettings::default()));
+
+/// 模型 ID -> max_tokens 限制（只包含配置了限制的模型）
+static MODEL_TOKEN_LIMITS: Lazy<RwLock<HashMap<String, TokenLimits>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 单个模型的 max_tokens 限制
+#[derive(Debug, Clone, Copy, Default)]
+struct TokenLimits {
+    default_max_tokens: Option<u32>,
+    max_output_tokens: Option<u32>,
+}
+
+/// 更新请求限制配置
+pub fn configure(config: &Config) {
+    *SETTINGS.write() = config.limits.clone();
+
+    // 同一模型出现在多个 Provider 下时，按 Provider 名排序取第一个配置了限制的
+    let mut providers: Vec<_> = config.models.providers.iter().collect();
+    providers.sort_by_key(|(name, _)| *name);
+    let mut limits = HashMap::new();
+    for model in providers.into_iter().flat_map(|(_, p)| &p.models) {
+        if model.default_max_tokens.is_none() && model.max_output_tokens.is_none() {
+            continue;
+        }
+        limits.entry(model.id.clone()).or_insert(TokenLimits {
+            default_max_tokens: model.default_max_tokens,
+            max_output_tokens: model.max_output_tokens,
+        });
+    }
+    *MODEL_TOKEN_LIMITS.write() = limits;
+}
+
+/// 按限制计算新的 max_tokens，无需调整时返回 None
+fn adjusted_max_tokens(limits: TokenLimits, max_tokens: Option<u32>) -> Option<u32> {
+    match (max_tokens, limits.max_output_tokens) {
+        (Some(requested), Some(max)) if requested > max => Some(max),
+        (Some(_), _) => None,
+        (None, max) => limits
+            .default_max_tokens
+            .map(|default| max.map_or(default, |max| default.min(max))),
+    }
+}
+
+/// 按模型配置填充或截断 `max_tokens`
+///
+/// 返回调整前的值和调整后的值，未调整时返回 None。
+pub fn adjust_max_tokens(model: &str, max_tokens: &mut Option<u32>) -> Option<(Option<u32>, u32)> {
+    let limits = *MODEL_TOKEN_LIMITS.read().get(model)?;
+    let adjusted = adjusted_max_tokens(limits, *max_tokens)?;
+    let original = max_tokens.replace(adjusted);
+    Some((original, adjusted))
+}
+
+/// 截断描述到不超过 `max_bytes` 的完整字符，返回是否发生截断
+fn truncate_description(description: &mut Option<String>, max_bytes: usize) -> bool {
+    let Some(text) = description else {
+        return false;
+    };
+    if text.len() <= max_bytes {
+        return false;
+    }
+    let max_chars = text
+        .char_indices()
+        .take_while(|(i, c)| i + c.len_utf8() <= max_bytes)
+        .count();
+    *text = safe_truncate(text, max_chars);
+    true
+}
+
+fn schema_bytes<T: Serialize>(tool: &T) -> usize {
+    serde_json::to_vec(tool).map(|v| v.len()).unwrap_or(0)
+}
+
+/// 检查工具数量和大小，`tools` 为 (工具名, 序列化后字节数)
+fn check(settings: &RequestLimitsSettings, tools: &[(&str, usize)]) -> Result<(), ProcessError> {
+    if let Some(max_tools) = settings.max_tools {
+        if tools.len() > max_tools {
+            return Err(ProcessError::InvalidRequest(format!(
+                "请求包含 {} 个工具，超过上限 {}",
+                tools.len(),
+                max_tools
+            )));
+        }
+    }
+
+    if let Some(max_bytes) = settings.max_tool_schema_bytes {
+        let oversized: Vec<_> = tools.iter().filter(|(_, size)| *size > max_bytes).collect();
+        if !oversized.is_empty() {
+            let mut listed: Vec<String> = oversized
+                .iter()
+                .take(MAX_LISTED_TOOLS)
+                .map(|(name, size)| format!("{} ({} 字节)", name, size))
+                .collect();
+            if oversized.len() > MAX_LISTED_TOOLS {
+                listed.push(format!("等 {} 个", oversized.len()));
+            }
+            return Err(ProcessError::InvalidRequest(format!(
+                "工具定义超过 {} 字节上限: {}",
+                max_bytes,
+                listed.join(", ")
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// 检查 OpenAI 格式请求的工具，返回被截断描述的工具数
+pub fn enforce_openai(request: &mut ChatCompletionRequest) -> Result<usize, ProcessError> {
+    let settings = SETTINGS.read().clone();
+    let Some(tools) = request.tools.as_mut() else {
+        return Ok(0);
+    };
+
+    let mut truncated = 0;
+    if settings.truncate_tool_descriptions {
+        for tool in tools.iter_mut() {
+            if let Tool::Function { function } = tool {
+                if truncate_description(
+                    &mut function.description,
+                    settings.max_tool_description_bytes,
+                ) {
+                    truncated += 1;
+                }
+            }
+        }
+    }
+
+    let sizes: Vec<(&str, usize)> = tools
+        .iter()
+        .map(|tool| {
+            let name = match tool {
+                Tool::Function { function } => function.name.as_str(),
+                Tool::WebSearch => "web_search",
+                Tool::WebSearch20250305 => "web_search_20250305",
+            };
+            (name, schema_bytes(tool))
+        })
+        .collect();
+    check(&settings, &sizes)?;
+    Ok(truncated)
+}
+
+/// 检查 Anthropic 格式请求的工具，返回被截断描述的工具数
+pub fn enforce_anthropic(request: &mut AnthropicMessagesRequest) -> Result<usize, ProcessError> {
+    let settings = SETTINGS.read().clone();
+    let Some(tools) = request.tools.as_mut() else {
+        return Ok(0);
+    };
+
+    let mut truncated = 0;
+    if settings.truncate_tool_descriptions {
+        for tool in tools.iter_mut() {
+            if truncate_description(&mut tool.description, settings.max_tool_description_bytes) {
+                truncated += 1;
+            }
+        }
+    }
+
+    let sizes: Vec<(&str, usize)> = tools
+        .iter()
+        .map(|tool| (tool.name.as_str(), schema_bytes(tool)))
+        .collect();
+    check(&settings, &sizes)?;
+    Ok(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_reports_offending_count_and_tools() {
+        let settings = RequestLimitsSettings {
+            max_tools: Some(2),
+            max_tool_schema_bytes: Some(100),
+            ..Default::default()
+        };
+
+        let err = check(&settings, &[("a", 10), ("b", 10), ("c", 10)]).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("3 个工具，超过上限 2"));
+
+        let err = check(&settings, &[("small", 10), ("big", 150)]).unwrap_err();
+        assert!(err.to_string().contains("big (150 字节)"));
+        assert!(!err.to_string().contains("small"));
+
+        assert!(check(&settings, &[("a", 100)]).is_ok());
+        assert!(check(&RequestLimitsSettings::default(), &[("a", usize::MAX)]).is_ok());
+    }
+
+    #[test]
+    fn test_adjusted_max_tokens() {
+        let limits = TokenLimits {
+            default_max_tokens: Some(8192),
+            max_output_tokens: Some(4096),
+        };
+        // 缺失时填充默认值（不超过上限）
+        assert_eq!(adjusted_max_tokens(limits, None), Some(4096));
+        // 超出上限时截断
+        assert_eq!(adjusted_max_tokens(limits, Some(100_000)), Some(4096));
+        // 上限内的值保持不变
+        assert_eq!(adjusted_max_tokens(limits, Some(100)), None);
+
+        let default_only = TokenLimits {
+            default_max_tokens: Some(2048),
+            max_output_tokens: None,
+        };
+        assert_eq!(adjusted_max_tokens(default_only, None), Some(2048));
+        assert_eq!(adjusted_max_tokens(default_only, Some(100_000)), None);
+        assert_eq!(adjusted_max_tokens(TokenLimits::default(), None), None);
+    }
+
+    #[test]
+    fn test_truncate_description_respects_char_boundary() {
+        let mut description = Some("你好世界".to_string());
+        assert!(truncate_description(&mut description, 7));
+        assert_eq!(description.as_deref(), Some("你好"));
+        assert!(!truncate_description(&mut description, 7));
+        assert!(!truncate_description(&mut None, 1));
+    }
+}
@@ -0,0 +1,168 @@
+//! 请求路径的上游重试策略
+//!
+//! Provider 调用失败且符合 `retry` 配置的策略（见 [`RetryConfig::should_retry`]）时，
+//! 退避后使用同一凭证重试：连接失败、429 以及配置的 5xx。调用返回错误时客户端尚未收到任何输出，
+//! 流式传输中途的失败在响应体内报告，不会进入重试。
+//!
+//! 每次重试作为 `Retry` 步骤记录到请求追踪。配置通过 [`configure`] 在启动和配置变更时更新，
+//! 每个请求开始时读取，修改后无需重启服务器。
+
+use std::future::Future;
+use std::time::Duration;
+
+use axum::response::Response;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+use crate::config::Config;
+use crate::processor::{record_captured_step, ProcessError, TraceStepKind};
+use crate::resilience::{Retrier, RetryConfig};
+use crate::server::handlers::ProviderCallError;
+
+static POLICY: Lazy<RwLock<RetryConfig>> = Lazy::new(|| RwLock::new(RetryConfig::default()));
+
+/// 更新重试策略
+pub fn configure(config: &Config) {
+    *POLICY.write() = (&config.retry).into();
+}
+
+/// 错误对应的重试判断依据：(上游状态码，None 表示连接失败；上游建议的等待时间)
+///
+/// 返回 None 表示该错误与上游可用性无关（认证、请求无效等），不重试。
+fn retry_signal(error: &ProcessError) -> Option<(Option<u16>, Option<Duration>)> {
+    match error {
+        ProcessError::RateLimited {
+            retry_after_secs, ..
+        } => Some((Some(429), retry_after_secs.map(Duration::from_secs))),
+        ProcessError::Upstream { status, .. } => Some((Some(*status), None)),
+        e if e.is_connection_failure() => Some((None, None)),
+        _ => None,
+    }
+}
+
+/// 按重试策略执行 Provider 调用
+///
+/// 在 [`crate::processor::with_step_capture`] 范围内调用时记录重试步骤。
+pub async fn call_with_retry<F, Fut>(
+    is_stream: bool,
+    mut call: F,
+) -> Result<Response, ProviderCallError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Response, ProviderCallError>>,
+{
+    let retrier = Retrier::new(POLICY.read().clone());
+    let mut attempts = 0u32;
+    loop {
+        let error = match call().await {
+            Ok(response) => return Ok(response),
+            Err(error) => error,
+        };
+        attempts += 1;
+
+        let Some((status_code, retry_after)) = retry_signal(&error) else {
+            return Err(error);
+        };
+        if attempts > retrier.config().max_retries
+            || !retrier.config().should_retry(status_code, is_stream, false)
+        {
+            return Err(error);
+        }
+        // 上游要求等待的时间超过最大退避时间时不再重试，交给故障转移
+        let Some(delay) = retrier.delay_for(attempts - 1, retry_after) else {
+            return Err(error);
+        };
+
+        tracing::warn!(
+            "[RETRY] attempt={}/{} status={:?} delay={:?} error={}",
+            attempts,
+            retrier.config().max_retries + 1,
+            status_code,
+            delay,
+            *error
+        );
+        record_captured_step(TraceStepKind::Retry {
+            attempt: attempts,
+            status_code,
+            error: error.to_string(),
+        });
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_retry_signal() {
+        assert_eq!(
+            retry_signal(&ProcessError::upstream(503, "unavailable")),
+            Some((Some(503), None))
+        );
+        assert_eq!(
+            retry_signal(&ProcessError::RateLimited {
+                message: "slow down".to_string(),
+                retry_after_secs: Some(2),
+            }),
+            Some((Some(429), Some(Duration::from_secs(2))))
+        );
+        assert_eq!(
+            retry_signal(&ProcessError::Connection("connection refused".to_string())),
+            Some((None, None))
+        );
+        // 读取响应体、Token 刷新等失败不是连接失败
+        assert_eq!(
+            retry_signal(&ProcessError::ProviderError(
+                "error decoding response body".to_string()
+            )),
+            None
+        );
+        assert_eq!(
+            retry_signal(&ProcessError::AuthError("bad key".to_string())),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_with_retry_follows_policy() {
+        *POLICY.write() = RetryConfig::new(2, 1, 5);
+
+        // 503 两次后成功
+        let calls = AtomicU32::new(0);
+        let result = call_with_retry(false, || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(ProcessError::upstream(503, "unavailable").into())
+            } else {
+                Ok(Response::new(Body::empty()))
+            }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // 流式请求的 5xx 默认不重试
+        let calls = AtomicU32::new(0);
+        let result = call_with_retry(true, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(ProcessError::upstream(503, "unavailable").into())
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 400 不重试
+        let calls = AtomicU32::new(0);
+        let result = call_with_retry(false, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(ProcessError::upstream(400, "bad request").into())
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        *POLICY.write() = RetryConfig::default();
+    }
+}
@@ -0,0 +1,388 @@
+//! 凭证影子测试
+//!
+//! 按采样比例把请求复制一份（非流式）发给影子凭证，影子响应只用于和实际返回给客户端的
+//! 响应对比，结果写入对应 Flow 的 `shadow` 字段。影子请求在后台任务中执行，
+//! 不会延迟或修改客户端响应。
+//!
+//! 影子凭证不可用（不健康、已禁用、不支持该模型）、所在 Provider 或凭证没有空闲的并发槽位，
+//! 或最近返回过配额超限错误时跳过。配置通过 [`configure`] 在启动和配置变更时更新。
+//! 影子请求不排队等待并发槽位，不会让实际请求因影子流量而排队。
+
+use std::time::{Duration, Instant};
+
+use axum::response::Response;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde_json::Value;
+
+use crate::config::{Config, ShadowTestSettings};
+use crate::credential::QuotaManager;
+use crate::flow_monitor::models::{FunctionCall, LLMResponse, TokenUsage, ToolCall};
+use crate::flow_monitor::stream_rebuilder::StreamRebuilder;
+use crate::flow_monitor::ShadowResponse;
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::processor::ErrorFormat;
+use crate::resilience::priority::{with_priority, RequestPriority};
+use crate::resilience::ConcurrencyPermit;
+use crate::server::handlers::{
+    call_provider_anthropic_with_permit, call_provider_openai_with_permit,
+};
+use crate::server::AppState;
+use crate::server_utils::safe_truncate;
+
+/// 影子凭证返回配额超限后暂停采样的时长
+const QUOTA_PAUSE: Duration = Duration::from_secs(300);
+
+static SETTINGS: Lazy<RwLock<ShadowTestSettings>> =
+    Lazy::new(|| RwLock::new(ShadowTestSettings::default()));
+
+/// 配额超限后的暂停截止时间
+static PAUSED_UNTIL: Lazy<RwLock<Option<Instant>>> = Lazy::new(|| RwLock::new(None));
+
+/// 更新影子测试配置（更换影子凭证时清除配额暂停）
+pub fn configure(config: &Config) {
+    let mut settings = SETTINGS.write();
+    if settings.credential_uuid != config.shadow_test.credential_uuid {
+        *PAUSED_UNTIL.write() = None;
+    }
+    *settings = config.shadow_test.clone();
+}
+
+/// 按采样比例决定是否发送影子请求，返回影子凭证 UUID
+///
+/// 影子凭证和实际使用的凭证相同时不采样。
+fn sample(served_credential_uuid: &str) -> Option<String> {
+    let settings = SETTINGS.read();
+    if !settings.enabled {
+        return None;
+    }
+    let uuid = settings.credential_uuid.as_deref()?;
+    if uuid == served_credential_uuid {
+        return None;
+    }
+    if PAUSED_UNTIL
+        .read()
+        .is_some_and(|until| Instant::now() < until)
+    {
+        return None;
+    }
+    if !is_sampled(settings.sample_rate, rand::random::<f64>()) {
+        return None;
+    }
+    Some(uuid.to_string())
+}
+
+/// `roll` 为 [0, 1) 的随机数
+fn is_sampled(sample_rate: f64, roll: f64) -> bool {
+    roll < sample_rate
+}
+
+/// 在后台发送 OpenAI 格式的影子请求
+///
+/// 没有 Flow（未被监控）时不发送，结果无处记录。
+pub fn spawn_openai(
+    state: &AppState,
+    request: &ChatCompletionRequest,
+    served_credential_uuid: &str,
+    flow_id: Option<&str>,
+) {
+    let Some(flow_id) = flow_id else {
+        return;
+    };
+    let Some(uuid) = sample(served_credential_uuid) else {
+        return;
+    };
+    let state = state.clone();
+    let flow_id = flow_id.to_string();
+    let mut request = request.clone();
+    request.stream = false;
+
+    tokio::spawn(with_priority(RequestPriority::Batch, async move {
+        let Some((credential, permit)) = shadow_credential(&state, &uuid, &request.model) else {
+            return;
+        };
+        let start = Instant::now();
+        let response =
+            call_provider_openai_with_permit(&state, &credential, &request, None, None, permit)
+                .await
+                .unwrap_or_else(|e| e.into_response_for(ErrorFormat::OpenAI));
+        let shadow = read_shadow_response(&credential, response, start, openai_response).await;
+        state
+            .flow_monitor
+            .record_shadow_response(&flow_id, shadow)
+            .await;
+    }));
+}
+
+/// 在后台发送 Anthropic 格式的影子请求
+///
+/// 没有 Flow（未被监控）时不发送，结果无处记录。
+pub fn spawn_anthropic(
+    state: &AppState,
+    request: &AnthropicMessagesRequest,
+    served_credential_uuid: &str,
+    flow_id: Option<&str>,
+) {
+    let Some(flow_id) = flow_id else {
+        return;
+    };
+    let Some(uuid) = sample(served_credential_uuid) else {
+        return;
+    };
+    let state = state.clone();
+    let flow_id = flow_id.to_string();
+    let mut request = request.clone();
+    request.stream = false;
+
+    tokio::spawn(with_priority(RequestPriority::Batch, async move {
+        let Some((credential, permit)) = shadow_credential(&state, &uuid, &request.model) else {
+            return;
+        };
+        let start = Instant::now();
+        let response =
+            call_provider_anthropic_with_permit(&state, &credential, &request, None, None, permit)
+                .await
+                .unwrap_or_else(|e| e.into_response_for(ErrorFormat::Anthropic));
+        let shadow = read_shadow_response(&credential, response, start, anthropic_response).await;
+        state
+            .flow_monitor
+            .record_shadow_response(&flow_id, shadow)
+            .await;
+    }));
+}
+
+/// 获取可用的影子凭证和并发许可
+///
+/// 没有空闲的并发槽位时跳过（不排队），避免影子请求占用实际请求的并发额度。
+fn shadow_credential(
+    state: &AppState,
+    uuid: &str,
+    model: &str,
+) -> Option<(ProviderCredential, ConcurrencyPermit)> {
+    let db = state.db.as_ref()?;
+    let credential = match state.pool_service.get_by_uuid(db, uuid) {
+        Ok(Some(credential)) => credential,
+        Ok(None) => {
+            tracing::warn!("[SHADOW] 影子凭证不存在: {}", safe_truncate(uuid, 8));
+            return None;
+        }
+        Err(e) => {
+            tracing::warn!("[SHADOW] 获取影子凭证失败: {}", e);
+            return None;
+        }
+    };
+    if !credential.is_available() || !credential.supports_model(model) {
+        tracing::debug!(
+            "[SHADOW] 影子凭证不可用或不支持模型 {}: {}",
+            model,
+            safe_truncate(uuid, 8)
+        );
+        return None;
+    }
+
+    let provider = credential.provider_type.to_string();
+    let Some(permit) = state
+        .processor
+        .concurrency
+        .try_acquire(&provider, Some(&credential.uuid))
+    else {
+        tracing::debug!("[SHADOW] Provider {} 并发已满，跳过影子请求", provider);
+        return None;
+    };
+    Some((credential, permit))
+}
+
+/// 读取影子响应，配额超限时暂停采样
+async fn read_shadow_response(
+    credential: &ProviderCredential,
+    response: Response,
+    start: Instant,
+    parse: fn(u16, Value, usize) -> LLMResponse,
+) -> ShadowResponse {
+    let status = response.status().as_u16();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+    let mut shadow = ShadowResponse {
+        credential_id: credential.uuid.clone(),
+        credential_name: credential.name.clone(),
+        response: None,
+        error: None,
+        latency_ms,
+    };
+
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => {
+            shadow.error = Some(format!("读取影子响应失败: {}", e));
+            return shadow;
+        }
+    };
+    let text = String::from_utf8_lossy(&body);
+    if !(200..300).contains(&status) {
+        if QuotaManager::is_quota_exceeded_error(Some(status), &text) {
+            tracing::warn!(
+                "[SHADOW] 影子凭证配额超限，暂停影子测试 {} 秒",
+                QUOTA_PAUSE.as_secs()
+            );
+            *PAUSED_UNTIL.write() = Some(Instant::now() + QUOTA_PAUSE);
+        }
+        shadow.error = Some(format!("HTTP {}: {}", status, safe_truncate(&text, 500)));
+        return shadow;
+    }
+
+    match serde_json::from_slice::<Value>(&body) {
+        Ok(json) => shadow.response = Some(parse(status, json, body.len())),
+        Err(e) => shadow.error = Some(format!("解析影子响应失败: {}", e)),
+    }
+    shadow
+}
+
+/// 从 OpenAI 格式响应体构建 LLMResponse
+fn openai_response(status_code: u16, body: Value, size_bytes: usize) -> LLMResponse {
+    let message = &body["choices"][0]["message"];
+    let tool_calls = message["tool_calls"]
+        .as_array()
+        .map(|calls| {
+            calls
+                .iter()
+                .map(|call| ToolCall {
+                    id: call["id"].as_str().unwrap_or_default().to_string(),
+                    tool_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: call["function"]["name"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        arguments: call["function"]["arguments"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                    },
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let stop_reason = body["choices"][0]["finish_reason"]
+        .as_str()
+        .map(StreamRebuilder::parse_openai_stop_reason);
+
+    LLMResponse {
+        status_code,
+        content: message["content"].as_str().unwrap_or_default().to_string(),
+        tool_calls,
+        usage: usage(
+            &body["usage"]["prompt_tokens"],
+            &body["usage"]["completion_tokens"],
+        ),
+        stop_reason,
+        size_bytes,
+        timestamp_end: Utc::now(),
+        body,
+        ..Default::default()
+    }
+}
+
+/// 从 Anthropic 格式响应体构建 LLMResponse
+fn anthropic_response(status_code: u16, body: Value, size_bytes: usize) -> LLMResponse {
+    let mut content = String::new();
+    let mut tool_calls = Vec::new();
+    for block in body["content"].as_array().into_iter().flatten() {
+        match block["type"].as_str() {
+            Some("text") => content.push_str(block["text"].as_str().unwrap_or_default()),
+            Some("tool_use") => tool_calls.push(ToolCall {
+                id: block["id"].as_str().unwrap_or_default().to_string(),
+                tool_type: "function".to_string(),
+                function: FunctionCall {
+                    name: block["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: block["input"].to_string(),
+                },
+            }),
+            _ => {}
+        }
+    }
+    let stop_reason = body["stop_reason"]
+        .as_str()
+        .map(StreamRebuilder::parse_anthropic_stop_reason);
+
+    LLMResponse {
+        status_code,
+        content,
+        tool_calls,
+        usage: usage(
+            &body["usage"]["input_tokens"],
+            &body["usage"]["output_tokens"],
+        ),
+        stop_reason,
+        size_bytes,
+        timestamp_end: Utc::now(),
+        body,
+        ..Default::default()
+    }
+}
+
+fn usage(input: &Value, output: &Value) -> TokenUsage {
+    let input_tokens = input.as_u64().unwrap_or(0) as u32;
+    let output_tokens = output.as_u64().unwrap_or(0) as u32;
+    TokenUsage {
+        input_tokens,
+        output_tokens,
+        total_tokens: input_tokens + output_tokens,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_monitor::models::StopReason;
+    use serde_json::json;
+
+    #[test]
+    fn test_is_sampled_bounds() {
+        assert!(!is_sampled(0.0, 0.0));
+        assert!(is_sampled(1.0, 0.999));
+        assert!(is_sampled(0.25, 0.1));
+        assert!(!is_sampled(0.25, 0.5));
+    }
+
+    #[test]
+    fn test_parse_shadow_responses() {
+        let openai = openai_response(
+            200,
+            json!({
+                "choices": [{
+                    "message": {"content": "hi", "tool_calls": [
+                        {"id": "call_1", "function": {"name": "search", "arguments": "{}"}}
+                    ]},
+                    "finish_reason": "tool_calls"
+                }],
+                "usage": {"prompt_tokens": 12, "completion_tokens": 3}
+            }),
+            100,
+        );
+        assert_eq!(openai.content, "hi");
+        assert_eq!(openai.tool_calls[0].function.name, "search");
+        assert_eq!(openai.stop_reason, Some(StopReason::ToolCalls));
+        assert_eq!(openai.usage.total_tokens, 15);
+
+        let anthropic = anthropic_response(
+            200,
+            json!({
+                "content": [
+                    {"type": "text", "text": "Hello "},
+                    {"type": "text", "text": "world"}
+                ],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 8, "output_tokens": 2}
+            }),
+            100,
+        );
+        assert_eq!(anthropic.content, "Hello world");
+        assert!(anthropic.tool_calls.is_empty());
+        assert_eq!(anthropic.stop_reason, Some(StopReason::EndTurn));
+        assert_eq!(anthropic.usage.output_tokens, 2);
+    }
+}
@@ -0,0 +1,198 @@
+//! 流式内容增量合并
+//!
+//! 逐 token 输出的上游每个 SSE 事件只有几个字节，逐个转发会放大单事件开销并带来抖动。
+//! 设置 `stream.coalesce_ms` 后，窗口内的文本/思考增量事件合并为一次下游写入
+//! （事件本身保持不变，只是一起发送），遇到工具调用、结束等其他事件时立即连同已缓冲的增量发送。
+//!
+//! 取舍：窗口越大吞吐越好、下游写入越少，但每段文本最多延迟一个窗口。默认 0，逐个事件透传。
+//! 配置通过 [`configure`] 在启动和配置变更时更新。
+
+use std::time::Duration;
+
+use axum::{body::Body, http::header, response::Response};
+use bytes::BytesMut;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde_json::Value;
+
+use crate::config::{Config, StreamSettings};
+use crate::server::stream_resume::event_end;
+
+static SETTINGS: Lazy<RwLock<StreamSettings>> =
+    Lazy::new(|| RwLock::new(StreamSettings::default()));
+
+/// 更新流式转发配置
+pub fn configure(config: &Config) {
+    *SETTINGS.write() = config.stream.clone();
+}
+
+/// 事件是否为可合并的内容增量
+///
+/// OpenAI：`choices[].delta` 只含文本/思考内容，且没有 `finish_reason` 和 `usage`；
+/// Anthropic：`text_delta` / `thinking_delta` 类型的 `content_block_delta`。
+fn is_content_delta(event: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(event) else {
+        return false;
+    };
+    let data: String = text
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim_start)
+        .collect();
+    let Ok(json) = serde_json::from_str::<Value>(&data) else {
+        return false;
+    };
+
+    if json.get("type").and_then(Value::as_str) == Some("content_block_delta") {
+        return matches!(
+            json.pointer("/delta/type").and_then(Value::as_str),
+            Some("text_delta" | "thinking_delta")
+        );
+    }
+
+    let Some(choices) = json.get("choices").and_then(Value::as_array) else {
+        return false;
+    };
+    json.get("usage").is_none_or(Value::is_null)
+        && !choices.is_empty()
+        && choices.iter().all(|choice| {
+            choice.get("finish_reason").is_none_or(Value::is_null)
+                && choice
+                    .get("delta")
+                    .and_then(Value::as_object)
+                    .is_some_and(|delta| delta.get("tool_calls").is_none_or(Value::is_null))
+        })
+}
+
+/// 按配置合并 SSE 响应中的内容增量（未启用或非 SSE 响应原样返回）
+pub fn apply(response: Response) -> Response {
+    let coalesce_ms = SETTINGS.read().coalesce_ms;
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if coalesce_ms == 0 || !response.status().is_success() || !is_sse {
+        return response;
+    }
+
+    let window = Duration::from_millis(coalesce_ms);
+    let (parts, body) = response.into_parts();
+    let stream = async_stream::stream! {
+        let mut upstream = body.into_data_stream();
+        // 未完整的事件
+        let mut pending = BytesMut::new();
+        // 已完整、等待发送的事件
+        let mut batch = BytesMut::new();
+        let mut deadline: Option<tokio::time::Instant> = None;
+
+        loop {
+            let next = match deadline {
+                Some(at) => match tokio::time::timeout_at(at, upstream.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        deadline = None;
+                        yield Ok(batch.split().freeze());
+                        continue;
+                    }
+                },
+                None => upstream.next().await,
+            };
+
+            match next {
+                Some(Ok(bytes)) => {
+                    pending.extend_from_slice(&bytes);
+                    let mut flush = false;
+                    while let Some(end) = event_end(&pending) {
+                        let event = pending.split_to(end);
+                        flush |= !is_content_delta(&event);
+                        batch.extend_from_slice(&event);
+                    }
+                    if flush {
+                        deadline = None;
+                        yield Ok(batch.split().freeze());
+                    } else if !batch.is_empty() && deadline.is_none() {
+                        deadline = Some(tokio::time::Instant::now() + window);
+                    }
+                }
+                Some(Err(e)) => {
+                    if !batch.is_empty() {
+                        yield Ok(batch.split().freeze());
+                    }
+                    yield Err(e);
+                    break;
+                }
+                None => {
+                    batch.extend_from_slice(&pending);
+                    if !batch.is_empty() {
+                        yield Ok(batch.split().freeze());
+                    }
+                    break;
+                }
+            }
+        }
+    };
+
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_content_delta() {
+        assert!(is_content_delta(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"hi\"},\"finish_reason\":null}]}\n\n"
+        ));
+        assert!(!is_content_delta(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0}]}}]}\n\n"
+        ));
+        assert!(!is_content_delta(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n"
+        ));
+        assert!(!is_content_delta(b"data: [DONE]\n\n"));
+
+        assert!(is_content_delta(
+            b"event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"hi\"}}\n\n"
+        ));
+        assert!(!is_content_delta(
+            b"event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\"}}\n\n"
+        ));
+        assert!(!is_content_delta(
+            b"event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_apply_batches_deltas_and_flushes_on_finish() {
+        SETTINGS.write().coalesce_ms = 50;
+
+        let delta = "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"a\"}}]}\n\n";
+        let finish =
+            "data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n";
+        let chunks: Vec<Result<String, std::io::Error>> = vec![
+            Ok(delta.to_string()),
+            Ok(delta.to_string()),
+            Ok(finish.to_string()),
+            Ok("data: [DONE]\n\n".to_string()),
+        ];
+        let upstream = Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::from_stream(futures::stream::iter(chunks)))
+            .unwrap();
+        let response = apply(upstream);
+        SETTINGS.write().coalesce_ms = 0;
+
+        let frames: Vec<_> = response
+            .into_body()
+            .into_data_stream()
+            .map(|frame| frame.unwrap())
+            .collect()
+            .await;
+        // 两个增量与结束事件合并为一次写入，[DONE] 单独发送
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], format!("{}{}{}", delta, delta, finish));
+    }
+}
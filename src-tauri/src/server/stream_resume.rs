@@ -0,0 +1,420 @@
+//! SSE 断线续传
+//!
+//! 启用后，流式响应的每个事件都带上递增的 `id:` 字段，并在请求存活期间按 `request_id`
+//! 缓冲在 [`ActiveStreamRegistry`] 中（容量有上限，超出后丢弃最早的事件）。
+//! 响应头 `x-request-id` 返回请求 ID，客户端断线后可通过以下方式续传：
+//!
+//! - `GET /v1/streams/:request_id`，携带 `Last-Event-ID`
+//! - 重新发起原请求，携带 `x-request-id` 和 `Last-Event-ID`
+//!
+//! 上游响应由后台任务读取，客户端断开不会中止上游流；流结束后缓冲区保留
+//! `retention_secs` 秒供最后一次重连。配置通过 [`configure`] 在启动和配置变更时更新，默认关闭。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::watch;
+
+use crate::config::{Config, StreamResumeSettings};
+use crate::server::handlers::verify_api_key;
+use crate::server::AppState;
+
+/// 返回请求 ID 的响应头（续传时作为请求头传回）
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// 客户端已收到的最后一个事件 ID
+pub const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+static SETTINGS: Lazy<RwLock<StreamResumeSettings>> =
+    Lazy::new(|| RwLock::new(StreamResumeSettings::default()));
+
+/// 更新断线续传配置
+pub fn configure(config: &Config) {
+    *SETTINGS.write() = config.stream_resume.clone();
+}
+
+/// 查找 SSE 事件结束位置（包含结尾的空行），兼容 `\n\n` 和 `\r\n\r\n`
+pub(crate) fn event_end(data: &[u8]) -> Option<usize> {
+    let lf = data.windows(2).position(|w| w == b"\n\n").map(|i| i + 2);
+    let crlf = data
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4);
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 单个流的事件缓冲
+struct EventBuffer {
+    /// (事件 ID, 带 `id:` 行的事件内容)
+    events: VecDeque<(u64, Bytes)>,
+    next_id: u64,
+    capacity: usize,
+    finished: bool,
+}
+
+impl EventBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            next_id: 1,
+            capacity: capacity.max(1),
+            finished: false,
+        }
+    }
+
+    /// 追加事件并分配 ID，超出容量时丢弃最早的事件
+    fn push(&mut self, event: &[u8]) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let prefix = format!("id: {}\n", id);
+        let mut data = BytesMut::with_capacity(prefix.len() + event.len());
+        data.extend_from_slice(prefix.as_bytes());
+        data.extend_from_slice(event);
+
+        if self.events.len() >= self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back((id, data.freeze()));
+        id
+    }
+
+    /// `last_event_id` 之后的事件；其后的事件已被丢弃时返回 `None`
+    fn events_after(&self, last_event_id: u64) -> Option<Vec<(u64, Bytes)>> {
+        let oldest = self.events.front().map_or(self.next_id, |(id, _)| *id);
+        if last_event_id + 1 < oldest {
+            return None;
+        }
+        Some(
+            self.events
+                .iter()
+                .filter(|(id, _)| *id > last_event_id)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// 进行中的流
+struct ActiveStream {
+    buffer: Mutex<EventBuffer>,
+    /// 最新的事件 ID，用于唤醒等待中的订阅者
+    latest: watch::Sender<u64>,
+}
+
+impl ActiveStream {
+    fn push(&self, event: &[u8]) {
+        let id = self.buffer.lock().push(event);
+        self.latest.send_replace(id);
+    }
+
+    fn finish(&self) {
+        self.buffer.lock().finished = true;
+        self.latest.send_modify(|_| {});
+    }
+}
+
+/// 续传失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeError {
+    /// 流不存在或已过保留期
+    NotFound,
+    /// 断点之后的事件已被丢弃
+    Evicted,
+}
+
+impl IntoResponse for ResumeError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ResumeError::NotFound => (StatusCode::NOT_FOUND, "Stream not found or expired"),
+            ResumeError::Evicted => (
+                StatusCode::GONE,
+                "Events after Last-Event-ID are no longer buffered",
+            ),
+        };
+        (
+            status,
+            Json(serde_json::json!({"error": {"message": message}})),
+        )
+            .into_response()
+    }
+}
+
+/// 进行中的流式响应注册表（request_id -> 事件缓冲）
+#[derive(Default)]
+pub struct ActiveStreamRegistry {
+    streams: Mutex<HashMap<String, Arc<ActiveStream>>>,
+}
+
+impl ActiveStreamRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记流式响应并返回带事件 ID 的响应（未启用或非 SSE 响应原样返回）
+    pub fn track(self: &Arc<Self>, request_id: &str, response: Response) -> Response {
+        let settings = SETTINGS.read().clone();
+        let is_sse = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if !settings.enabled || !response.status().is_success() || !is_sse {
+            return response;
+        }
+
+        let stream = Arc::new(ActiveStream {
+            buffer: Mutex::new(EventBuffer::new(settings.max_buffered_events)),
+            latest: watch::channel(0).0,
+        });
+        self.streams
+            .lock()
+            .insert(request_id.to_string(), stream.clone());
+
+        let (mut parts, body) = response.into_parts();
+        if let Ok(value) = HeaderValue::from_str(request_id) {
+            parts.headers.insert(REQUEST_ID_HEADER, value);
+        }
+        parts.headers.remove(header::CONTENT_LENGTH);
+
+        tokio::spawn(pump(
+            self.clone(),
+            request_id.to_string(),
+            stream.clone(),
+            body,
+            Duration::from_secs(settings.retention_secs),
+        ));
+
+        Response::from_parts(parts, subscribe(stream, 0))
+    }
+
+    /// 从 `last_event_id` 之后续传
+    pub fn resume(&self, request_id: &str, last_event_id: u64) -> Result<Response, ResumeError> {
+        let stream = self
+            .streams
+            .lock()
+            .get(request_id)
+            .cloned()
+            .ok_or(ResumeError::NotFound)?;
+        if stream.buffer.lock().events_after(last_event_id).is_none() {
+            return Err(ResumeError::Evicted);
+        }
+
+        let mut response = Response::new(subscribe(stream, last_event_id));
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/event-stream"),
+        );
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        if let Ok(value) = HeaderValue::from_str(request_id) {
+            headers.insert(REQUEST_ID_HEADER, value);
+        }
+        Ok(response)
+    }
+
+    fn remove(&self, request_id: &str, stream: &Arc<ActiveStream>) {
+        let mut streams = self.streams.lock();
+        // 同一 request_id 可能已被新的流替换
+        if streams
+            .get(request_id)
+            .is_some_and(|s| Arc::ptr_eq(s, stream))
+        {
+            streams.remove(request_id);
+        }
+    }
+}
+
+/// 读取上游响应体，按事件切分写入缓冲区
+async fn pump(
+    registry: Arc<ActiveStreamRegistry>,
+    request_id: String,
+    stream: Arc<ActiveStream>,
+    body: Body,
+    retention: Duration,
+) {
+    let mut upstream = body.into_data_stream();
+    let mut pending = BytesMut::new();
+
+    while let Some(chunk) = upstream.next().await {
+        match chunk {
+            Ok(bytes) => {
+                pending.extend_from_slice(&bytes);
+                while let Some(end) = event_end(&pending) {
+                    let event = pending.split_to(end);
+                    stream.push(&event);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("[STREAM_RESUME] 读取上游流失败 {}: {}", request_id, e);
+                break;
+            }
+        }
+    }
+    if !pending.iter().all(u8::is_ascii_whitespace) {
+        pending.extend_from_slice(b"\n\n");
+        stream.push(&pending);
+    }
+    stream.finish();
+
+    tokio::time::sleep(retention).await;
+    registry.remove(&request_id, &stream);
+}
+
+/// 订阅 `last_event_id` 之后的事件，直到流结束
+fn subscribe(stream: Arc<ActiveStream>, last_event_id: u64) -> Body {
+    let rx = stream.latest.subscribe();
+    let events = futures::stream::unfold(Some((stream, rx, last_event_id)), |state| async move {
+        let (stream, mut rx, mut cursor) = state?;
+        loop {
+            rx.borrow_and_update();
+            let (events, finished) = {
+                let buffer = stream.buffer.lock();
+                (buffer.events_after(cursor), buffer.finished)
+            };
+            let Some(events) = events else {
+                // 消费过慢，断点之后的事件已被丢弃
+                let error = std::io::Error::other("stream resume buffer overflowed");
+                return Some((Err(error), None));
+            };
+            if let Some((last, _)) = events.last() {
+                cursor = *last;
+                let mut data = BytesMut::new();
+                for (_, event) in &events {
+                    data.extend_from_slice(event);
+                }
+                return Some((Ok(data.freeze()), Some((stream, rx, cursor))));
+            }
+            if finished || rx.changed().await.is_err() {
+                return None;
+            }
+        }
+    });
+    Body::from_stream(events)
+}
+
+fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// 补全请求携带 `x-request-id` 和 `Last-Event-ID` 时直接续传，不再调用上游
+pub fn resume_from_headers(state: &AppState, headers: &HeaderMap) -> Option<Response> {
+    if !SETTINGS.read().enabled {
+        return None;
+    }
+    let request_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())?;
+    let last_event_id = last_event_id(headers)?;
+    tracing::info!(
+        "[STREAM_RESUME] 续传 request_id={} last_event_id={}",
+        request_id,
+        last_event_id
+    );
+    Some(
+        match state.active_streams.resume(request_id, last_event_id) {
+            Ok(response) => response,
+            Err(e) => e.into_response(),
+        },
+    )
+}
+
+/// 续传流式响应 `GET /v1/streams/:request_id`
+pub async fn resume_stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(request_id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if !SETTINGS.read().enabled {
+        return ResumeError::NotFound.into_response();
+    }
+    match state
+        .active_streams
+        .resume(&request_id, last_event_id(&headers).unwrap_or(0))
+    {
+        Ok(response) => response,
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_end() {
+        assert_eq!(event_end(b"data: a\n\ndata: b"), Some(9));
+        assert_eq!(event_end(b"data: a\r\n\r\n"), Some(11));
+        assert_eq!(event_end(b"data: a\n"), None);
+    }
+
+    #[test]
+    fn test_event_buffer_ids_and_eviction() {
+        let mut buffer = EventBuffer::new(2);
+        assert_eq!(buffer.push(b"data: a\n\n"), 1);
+        assert_eq!(buffer.push(b"data: b\n\n"), 2);
+        assert_eq!(buffer.push(b"data: c\n\n"), 3);
+
+        let events = buffer.events_after(1).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(&events[0].1[..], b"id: 2\ndata: b\n\n");
+        assert!(buffer.events_after(3).unwrap().is_empty());
+        // 事件 1 已被丢弃，从 0 续传会缺失事件
+        assert!(buffer.events_after(0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_track_and_resume() {
+        *SETTINGS.write() = StreamResumeSettings {
+            enabled: true,
+            ..Default::default()
+        };
+
+        let registry = Arc::new(ActiveStreamRegistry::new());
+        let upstream = Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::from("data: a\n\ndata: b\n\ndata: [DONE]\n\n"))
+            .unwrap();
+        let response = registry.track("req-1", upstream);
+        *SETTINGS.write() = StreamResumeSettings::default();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "req-1");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            &body[..],
+            b"id: 1\ndata: a\n\nid: 2\ndata: b\n\nid: 3\ndata: [DONE]\n\n"
+        );
+
+        let resumed = registry.resume("req-1", 2).unwrap();
+        let body = axum::body::to_bytes(resumed.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"id: 3\ndata: [DONE]\n\n");
+        assert_eq!(
+            registry.resume("req-2", 0).unwrap_err(),
+            ResumeError::NotFound
+        );
+    }
+}
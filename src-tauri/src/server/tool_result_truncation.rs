@@ -0,0 +1,324 @@
+//! 工具结果截断
+//!
+//! 大块的工具输出（如读取整个文件）会在下一轮请求中撑满上下文窗口。开启
+//! `tool_result_truncation` 后，调用上游之前把超出预算的 OpenAI `tool` 消息和
+//! Anthropic `tool_result` 内容截断为开头 + 结尾两段，中间插入说明被省略字节数的标记。
+//!
+//! 预算按字节计算，也可以按 token 估算（约 4 字节 / token）给出，两者都设置时取较小值。
+//! `providers` 中可以按 Provider 类型覆盖开关和预算。发生截断时写入 `[TOOL_TRUNCATE]` 日志，
+//! 便于按实际情况调整预算。
+//!
+//! 配置通过 [`configure`] 在启动和配置变更时更新，默认关闭。
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde_json::Value;
+
+use crate::config::{Config, ToolResultTruncationSettings};
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::{ChatCompletionRequest, ContentPart, MessageContent};
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::server::AppState;
+
+/// 估算 token 预算时每个 token 对应的字节数
+const BYTES_PER_TOKEN: usize = 4;
+
+/// 保留开头部分占预算的比例（其余保留结尾）
+const HEAD_RATIO: f64 = 0.6;
+
+static SETTINGS: Lazy<RwLock<ToolResultTruncationSettings>> =
+    Lazy::new(|| RwLock::new(ToolResultTruncationSettings::default()));
+
+/// 一次请求中的截断结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Truncation {
+    /// 被截断的工具结果数
+    pub count: usize,
+    /// 共省略的字节数
+    pub removed_bytes: usize,
+}
+
+impl Truncation {
+    fn add(&mut self, removed: Option<usize>) {
+        if let Some(removed) = removed {
+            self.count += 1;
+            self.removed_bytes += removed;
+        }
+    }
+}
+
+/// 更新工具结果截断配置
+pub fn configure(config: &Config) {
+    *SETTINGS.write() = config.tool_result_truncation.clone();
+}
+
+/// 指定 Provider 的截断预算（字节），未启用时返回 None
+fn budget_for(settings: &ToolResultTruncationSettings, provider: &str) -> Option<usize> {
+    let provider = provider.to_ascii_lowercase();
+    let budget = settings
+        .providers
+        .iter()
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case(&provider))
+        .map(|(_, budget)| budget);
+
+    let enabled = budget.and_then(|b| b.enabled).unwrap_or(settings.enabled);
+    if !enabled {
+        return None;
+    }
+
+    let max_bytes = budget
+        .and_then(|b| b.max_bytes)
+        .unwrap_or(settings.max_bytes);
+    let max_tokens = budget.and_then(|b| b.max_tokens).or(settings.max_tokens);
+    let bytes = match max_tokens {
+        Some(tokens) => max_bytes.min(tokens as usize * BYTES_PER_TOKEN),
+        None => max_bytes,
+    };
+    Some(bytes.max(1))
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// 把文本截断为开头 + 标记 + 结尾，返回省略的字节数
+///
+/// 省略的字节数不超过标记本身的长度时截断只会让文本变长，保持原样。
+fn truncate_text(text: &mut String, budget: usize) -> Option<usize> {
+    if text.len() <= budget {
+        return None;
+    }
+    let head_end = floor_char_boundary(text, (budget as f64 * HEAD_RATIO) as usize);
+    let tail_start = ceil_char_boundary(text, text.len() - (budget - head_end));
+    let removed = tail_start - head_end;
+    let marker = format!("\n\n[... {} bytes truncated by proxycast ...]\n\n", removed);
+    if removed <= marker.len() {
+        return None;
+    }
+    text.replace_range(head_end..tail_start, &marker);
+    Some(removed)
+}
+
+/// 截断 JSON 内容（字符串或内容块数组中的文本块）
+fn truncate_value(content: &mut Value, budget: usize, truncation: &mut Truncation) {
+    match content {
+        Value::String(text) => truncation.add(truncate_text(text, budget)),
+        Value::Array(blocks) => {
+            for block in blocks {
+                if block.get("type").and_then(Value::as_str) == Some("text") {
+                    if let Some(Value::String(text)) = block.get_mut("text") {
+                        truncation.add(truncate_text(text, budget));
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 截断 OpenAI 格式请求中的 `tool` / `function` 消息内容
+pub fn apply_openai(provider: &str, request: &mut ChatCompletionRequest) -> Option<Truncation> {
+    let budget = budget_for(&SETTINGS.read(), provider)?;
+    let mut truncation = Truncation::default();
+    for message in &mut request.messages {
+        if message.role != "tool" && message.role != "function" {
+            continue;
+        }
+        match &mut message.content {
+            Some(MessageContent::Text(text)) => truncation.add(truncate_text(text, budget)),
+            Some(MessageContent::Parts(parts)) => {
+                for part in parts {
+                    if let ContentPart::Text { text } = part {
+                        truncation.add(truncate_text(text, budget));
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+    (truncation.count > 0).then_some(truncation)
+}
+
+/// 截断 Anthropic 格式请求中的 `tool_result` 内容
+pub fn apply_anthropic(
+    provider: &str,
+    request: &mut AnthropicMessagesRequest,
+) -> Option<Truncation> {
+    let budget = budget_for(&SETTINGS.read(), provider)?;
+    let mut truncation = Truncation::default();
+    for message in &mut request.messages {
+        let Value::Array(blocks) = &mut message.content else {
+            continue;
+        };
+        for block in blocks {
+            if block.get("type").and_then(Value::as_str) != Some("tool_result") {
+                continue;
+            }
+            if let Some(content) = block.get_mut("content") {
+                truncate_value(content, budget, &mut truncation);
+            }
+        }
+    }
+    (truncation.count > 0).then_some(truncation)
+}
+
+/// 写入截断日志
+pub async fn log(
+    state: &AppState,
+    request_id: Option<&str>,
+    provider: &str,
+    model: &str,
+    truncation: Truncation,
+) {
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[TOOL_TRUNCATE] request_id={} provider={} model={} 截断了 {} 个工具结果，共省略 {} 字节",
+            request_id.unwrap_or("-"),
+            provider,
+            model,
+            truncation.count,
+            truncation.removed_bytes
+        ),
+    );
+}
+
+/// 按凭证的 Provider 截断 OpenAI 格式请求并写入日志（用于没有请求追踪的路由）
+pub async fn apply_and_log_openai(
+    state: &AppState,
+    cred: &ProviderCredential,
+    request: &mut ChatCompletionRequest,
+) {
+    let provider = cred.provider_type.to_string();
+    if let Some(truncation) = apply_openai(&provider, request) {
+        log(state, None, &provider, &request.model, truncation).await;
+    }
+}
+
+/// 按凭证的 Provider 截断 Anthropic 格式请求并写入日志（用于没有请求追踪的路由）
+pub async fn apply_and_log_anthropic(
+    state: &AppState,
+    cred: &ProviderCredential,
+    request: &mut AnthropicMessagesRequest,
+) {
+    let provider = cred.provider_type.to_string();
+    if let Some(truncation) = apply_anthropic(&provider, request) {
+        log(state, None, &provider, &request.model, truncation).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ToolResultBudget;
+    use serde_json::json;
+
+    #[test]
+    fn test_truncate_text_keeps_head_and_tail() {
+        let mut text = "a".repeat(60) + &"b".repeat(100) + &"c".repeat(40);
+        let removed = truncate_text(&mut text, 100).unwrap();
+        assert_eq!(removed, 100);
+        assert!(text.starts_with(&"a".repeat(60)));
+        assert!(text.ends_with(&"c".repeat(40)));
+        assert!(text.contains("[... 100 bytes truncated by proxycast ...]"));
+
+        let mut short = "short".to_string();
+        assert_eq!(truncate_text(&mut short, 100), None);
+        assert_eq!(short, "short");
+
+        // 多字节字符不会被截断到一半
+        let mut cjk = "工具".repeat(50);
+        truncate_text(&mut cjk, 31).unwrap();
+        assert!(cjk.starts_with("工具工具工具"));
+    }
+
+    #[test]
+    fn test_truncate_text_skips_when_marker_is_longer() {
+        // 超出预算 10 字节，插入标记反而更长
+        let original = "x".repeat(110);
+        let mut text = original.clone();
+        assert_eq!(truncate_text(&mut text, 100), None);
+        assert_eq!(text, original);
+
+        // 预算很小时只要省略量超过标记长度仍然截断，且结果比原文短
+        let mut text = "y".repeat(200);
+        let removed = truncate_text(&mut text, 10).unwrap();
+        assert_eq!(removed, 190);
+        assert!(text.len() < 200);
+        assert!(text.starts_with("yyyyyy\n\n[... 190 bytes"));
+        assert!(text.ends_with("yyyy"));
+    }
+
+    #[test]
+    fn test_truncate_text_respects_utf8_boundaries() {
+        // 每个字符 3 字节，开头和结尾的切点都落在字符中间
+        let original = "工".repeat(40) + &"具".repeat(40);
+        for budget in 1..=original.len() {
+            let mut text = original.clone();
+            let Some(removed) = truncate_text(&mut text, budget) else {
+                continue;
+            };
+            let (head, rest) = text.split_once("\n\n[... ").unwrap();
+            let (_, tail) = rest.split_once(" ...]\n\n").unwrap();
+            assert_eq!(head.len() + removed + tail.len(), original.len());
+            assert!(head.len() + tail.len() <= budget);
+            assert!(original.starts_with(head));
+            assert!(original.ends_with(tail));
+        }
+    }
+
+    #[test]
+    fn test_budget_for_provider_overrides() {
+        let mut settings = ToolResultTruncationSettings {
+            enabled: true,
+            max_bytes: 1000,
+            max_tokens: Some(100),
+            ..Default::default()
+        };
+        settings.providers.insert(
+            "Kiro".to_string(),
+            ToolResultBudget {
+                max_bytes: Some(50),
+                ..Default::default()
+            },
+        );
+        settings.providers.insert(
+            "openai".to_string(),
+            ToolResultBudget {
+                enabled: Some(false),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(budget_for(&settings, "claude"), Some(400));
+        assert_eq!(budget_for(&settings, "kiro"), Some(50));
+        assert_eq!(budget_for(&settings, "openai"), None);
+
+        settings.enabled = false;
+        assert_eq!(budget_for(&settings, "claude"), None);
+        assert_eq!(budget_for(&settings, "kiro"), None);
+    }
+
+    #[test]
+    fn test_truncate_value_only_touches_text_blocks() {
+        let mut content = json!([
+            {"type": "text", "text": "x".repeat(200)},
+            {"type": "image", "source": {"type": "base64", "data": "y".repeat(200)}}
+        ]);
+        let mut truncation = Truncation::default();
+        truncate_value(&mut content, 50, &mut truncation);
+        assert_eq!(truncation.count, 1);
+        assert_eq!(truncation.removed_bytes, 150);
+        assert_eq!(content[1]["source"]["data"].as_str().unwrap().len(), 200);
+    }
+}
@@ -0,0 +1,239 @@
+//! 按终端用户的标识与限制
+//!
+//! 用户标识来自请求体（OpenAI `user` / Anthropic `metadata.user_id`），记录到请求上下文
+//! 和请求日志中用于按用户统计。配置 `limits.per_user` 后按用户限制每分钟请求数和
+//! 每天（UTC）消耗的 Token 数，超出时返回 429；未携带标识的请求不受限制。HTTP 补全路由由
+//! [`crate::middleware::UserLimitsLayer`] 统一检查，WebSocket 请求由 WebSocket 处理器检查。
+//!
+//! 开启 `hash_user_ids` 时日志、统计和限额计数中只使用标识的哈希。
+//! 计数保存在进程内存中，重启后清零。配置通过 [`configure`] 在启动和配置变更时更新。
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use sha2::{Digest, Sha256};
+
+use crate::config::{Config, UserLimitsSettings};
+use crate::processor::ProcessError;
+use crate::server_utils::safe_truncate;
+
+/// 请求频率统计窗口
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// 用户标识的最大字符数（超出部分截断）
+const MAX_USER_ID_CHARS: usize = 256;
+
+/// 哈希后保留的十六进制字符数
+const HASHED_ID_LEN: usize = 16;
+
+/// 计数表超过该数量时清理已空闲的用户
+const MAX_TRACKED_USERS: usize = 10_000;
+
+static SETTINGS: Lazy<RwLock<UserLimitsSettings>> =
+    Lazy::new(|| RwLock::new(UserLimitsSettings::default()));
+
+/// 用户标识 -> 计数
+static USAGE: Lazy<Mutex<HashMap<String, UserUsage>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 单个用户的计数
+#[derive(Debug, Default)]
+struct UserUsage {
+    /// 统计窗口内的请求时间
+    requests: VecDeque<Instant>,
+    /// Token 计数所属日期（UTC）
+    day: Option<NaiveDate>,
+    /// 当天已消耗的 Token 数
+    tokens: u64,
+}
+
+impl UserUsage {
+    /// 指定日期的 Token 计数（日期变化时清零）
+    fn tokens_on(&mut self, day: NaiveDate) -> &mut u64 {
+        if self.day != Some(day) {
+            self.day = Some(day);
+            self.tokens = 0;
+        }
+        &mut self.tokens
+    }
+
+    fn prune_requests(&mut self, now: Instant) {
+        while self
+            .requests
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW)
+        {
+            self.requests.pop_front();
+        }
+    }
+
+    fn is_idle(&self, today: NaiveDate) -> bool {
+        self.requests.is_empty() && (self.day != Some(today) || self.tokens == 0)
+    }
+}
+
+/// 更新按用户限制配置
+pub fn configure(config: &Config) {
+    *SETTINGS.write() = config.limits.per_user.clone();
+}
+
+/// 是否配置了按用户的频率或配额限制
+pub fn is_enabled() -> bool {
+    let settings = SETTINGS.read();
+    settings.requests_per_minute.is_some() || settings.daily_token_quota.is_some()
+}
+
+/// 规范化请求携带的用户标识，隐私模式下返回哈希
+///
+/// 标识为空时返回 None。
+pub fn user_key(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    if SETTINGS.read().hash_user_ids {
+        Some(hash_user_id(raw))
+    } else {
+        Some(safe_truncate(raw, MAX_USER_ID_CHARS))
+    }
+}
+
+fn hash_user_id(raw: &str) -> String {
+    let mut hex = hex::encode(Sha256::digest(raw.as_bytes()));
+    hex.truncate(HASHED_ID_LEN);
+    format!("sha256:{}", hex)
+}
+
+/// 检查用户的请求频率和当天 Token 配额，通过时计入本次请求
+pub fn check_and_record(user: &str) -> Result<(), ProcessError> {
+    let settings = SETTINGS.read().clone();
+    if settings.requests_per_minute.is_none() && settings.daily_token_quota.is_none() {
+        return Ok(());
+    }
+
+    let now = Instant::now();
+    let now_utc = Utc::now();
+    let mut usage = USAGE.lock();
+    if usage.len() > MAX_TRACKED_USERS {
+        let today = now_utc.date_naive();
+        usage.retain(|_, u| {
+            u.prune_requests(now);
+            !u.is_idle(today)
+        });
+    }
+    check(
+        &settings,
+        usage.entry(user.to_string()).or_default(),
+        now,
+        now_utc,
+    )
+}
+
+fn check(
+    settings: &UserLimitsSettings,
+    usage: &mut UserUsage,
+    now: Instant,
+    now_utc: DateTime<Utc>,
+) -> Result<(), ProcessError> {
+    if let Some(quota) = settings.daily_token_quota {
+        let used = *usage.tokens_on(now_utc.date_naive());
+        if used >= quota {
+            return Err(ProcessError::RateLimited {
+                message: format!("用户今日 Token 配额已用完（{}/{}）", used, quota),
+                retry_after_secs: Some(86_400 - u64::from(now_utc.num_seconds_from_midnight())),
+            });
+        }
+    }
+
+    if let Some(limit) = settings.requests_per_minute {
+        usage.prune_requests(now);
+        if usage.requests.len() >= limit as usize {
+            let retry_after = usage.requests.front().map(|t| {
+                RATE_WINDOW
+                    .saturating_sub(now.duration_since(*t))
+                    .as_secs()
+                    .max(1)
+            });
+            return Err(ProcessError::RateLimited {
+                message: format!("用户请求频率超限（每分钟 {} 次）", limit),
+                retry_after_secs: retry_after,
+            });
+        }
+        usage.requests.push_back(now);
+    }
+
+    Ok(())
+}
+
+/// 计入用户消耗的 Token（仅在配置了每日配额时）
+pub fn record_tokens(user: &str, tokens: u64) {
+    if SETTINGS.read().daily_token_quota.is_none() || tokens == 0 {
+        return;
+    }
+    let today = Utc::now().date_naive();
+    *USAGE
+        .lock()
+        .entry(user.to_string())
+        .or_default()
+        .tokens_on(today) += tokens;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_requests_per_minute() {
+        let settings = UserLimitsSettings {
+            requests_per_minute: Some(2),
+            ..Default::default()
+        };
+        let mut usage = UserUsage::default();
+        let start = Instant::now();
+        let now_utc = Utc::now();
+
+        assert!(check(&settings, &mut usage, start, now_utc).is_ok());
+        assert!(check(&settings, &mut usage, start, now_utc).is_ok());
+        let err = check(&settings, &mut usage, start, now_utc).unwrap_err();
+        assert_eq!(err.status_code(), 429);
+        assert!(matches!(
+            err,
+            ProcessError::RateLimited {
+                retry_after_secs: Some(60),
+                ..
+            }
+        ));
+
+        // 窗口过后恢复
+        assert!(check(&settings, &mut usage, start + RATE_WINDOW, now_utc).is_ok());
+    }
+
+    #[test]
+    fn test_check_daily_token_quota_resets_next_day() {
+        let settings = UserLimitsSettings {
+            daily_token_quota: Some(1000),
+            ..Default::default()
+        };
+        let mut usage = UserUsage::default();
+        let now = Instant::now();
+        let today = Utc::now();
+
+        *usage.tokens_on(today.date_naive()) += 1000;
+        let err = check(&settings, &mut usage, now, today).unwrap_err();
+        assert!(err.to_string().contains("1000/1000"));
+
+        let tomorrow = today + chrono::Duration::days(1);
+        assert!(check(&settings, &mut usage, now, tomorrow).is_ok());
+    }
+
+    #[test]
+    fn test_hash_user_id_is_stable_and_short() {
+        let hashed = hash_user_id("alice@example.com");
+        assert_eq!(hashed, hash_user_id("alice@example.com"));
+        assert_ne!(hashed, hash_user_id("bob@example.com"));
+        assert_eq!(hashed.len(), "sha256:".len() + HASHED_ID_LEN);
+        assert!(!hashed.contains("alice"));
+    }
+}
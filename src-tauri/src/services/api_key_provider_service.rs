@@ -25,7 +25,7 @@ use std::sync::RwLock;
 /// 简单的 API Key 加密服务
 /// 使用 XOR 加密 + Base64 编码
 /// 注意：这是一个简单的混淆方案，不是强加密
-struct EncryptionService {
+pub(crate) struct EncryptionService {
     /// 加密密钥（从机器 ID 派生）
     key: Vec<u8>,
 }
@@ -44,7 +44,7 @@ impl EncryptionService {
     }
 
     /// 获取机器 ID
-    fn get_machine_id() -> String {
+    pub(crate) fn get_machine_id() -> String {
         // 尝试获取机器 ID，失败则使用默认值
         if let Ok(id) = std::fs::read_to_string("/etc/machine-id") {
             return id.trim().to_string();
@@ -1097,6 +1097,11 @@ impl ApiKeyProviderService {
             cached_token: None,
             source: CredentialSource::Imported,
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         })
     }
 
@@ -1154,6 +1159,11 @@ impl ApiKeyProviderService {
             cached_token: None,
             source: CredentialSource::Imported, // 标记为导入来源
             proxy_url: None,
+            tier: None,
+            health_score: 1.0,
+            upstream_headers: HashMap::new(),
+            parked_until: None,
+            unhealthy_since: None,
         })
     }
 }
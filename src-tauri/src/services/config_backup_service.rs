@@ -0,0 +1,262 @@
+//! 配置备份服务
+//!
+//! 定期将完整配置与凭证导出包（不脱敏）加密写入备份目录，
+//! 仅保留最近 N 份，并支持从备份恢复。
+//!
+//! 导出包只包含配置文件及其引用的 OAuth token 文件，不包含 SQLite 中的凭证池
+//! （`provider_pool_credentials`），凭证池由数据库备份（`BackupService`）负责。
+
+use crate::config::{expand_tilde, BackupSettings, Config, ExportOptions, ExportService};
+use crate::services::api_key_provider_service::EncryptionService;
+use chrono::Utc;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 备份文件头（用于识别格式版本）
+const BACKUP_MAGIC: &[u8] = b"PCBAK1";
+/// AES-GCM nonce 长度
+const NONCE_LEN: usize = 12;
+/// AES-GCM tag 长度
+const TAG_LEN: usize = 16;
+/// 备份文件扩展名
+const BACKUP_EXTENSION: &str = "pcbak";
+
+#[derive(Clone)]
+pub struct ConfigBackupService {
+    backup_dir: PathBuf,
+    keep_last: usize,
+    key: [u8; 32],
+}
+
+impl ConfigBackupService {
+    pub fn new(backup_dir: PathBuf, keep_last: usize) -> Result<Self, String> {
+        std::fs::create_dir_all(&backup_dir)
+            .map_err(|e| format!("无法创建备份目录 {:?}: {}", backup_dir, e))?;
+        Ok(Self {
+            backup_dir,
+            keep_last: keep_last.max(1),
+            key: Self::derive_key(),
+        })
+    }
+
+    pub fn from_settings(settings: &BackupSettings) -> Result<Self, String> {
+        Self::new(expand_tilde(&settings.directory), settings.keep_last)
+    }
+
+    /// 备份密钥（从机器 ID 派生，备份仅能在本机恢复）
+    fn derive_key() -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(EncryptionService::get_machine_id().as_bytes());
+        hasher.update(b"proxycast-config-backup-salt");
+        hasher.finalize().into()
+    }
+
+    /// 创建一份加密备份并清理多余的旧备份
+    pub fn create_backup(&self, config: &Config) -> Result<PathBuf, String> {
+        let bundle =
+            ExportService::export(config, &ExportOptions::full(), env!("CARGO_PKG_VERSION"))
+                .map_err(|e| format!("导出配置失败: {}", e))?;
+        let json = bundle.to_json().map_err(|e| e.to_string())?;
+
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+        let backup_path = self.backup_dir.join(format!(
+            "proxycast_config_{}.{}",
+            timestamp, BACKUP_EXTENSION
+        ));
+
+        std::fs::write(&backup_path, self.encrypt(json.as_bytes())?)
+            .map_err(|e| format!("写入备份失败: {}", e))?;
+
+        self.cleanup_old_backups()?;
+        Ok(backup_path)
+    }
+
+    /// 读取并解密备份，返回导出包 JSON
+    pub fn read_backup(&self, backup_path: &Path) -> Result<String, String> {
+        // 只允许读取备份目录内的文件
+        let canonical_backup = backup_path
+            .canonicalize()
+            .map_err(|e| format!("无法解析备份路径: {}", e))?;
+        let canonical_backup_dir = self
+            .backup_dir
+            .canonicalize()
+            .map_err(|e| format!("无法解析备份目录: {}", e))?;
+
+        if !canonical_backup.starts_with(&canonical_backup_dir) {
+            return Err("安全限制：只能从备份目录恢复配置".to_string());
+        }
+
+        let data = std::fs::read(&canonical_backup).map_err(|e| format!("读取备份失败: {}", e))?;
+        let plaintext = self.decrypt(&data)?;
+        String::from_utf8(plaintext).map_err(|e| format!("备份内容不是有效的 UTF-8: {}", e))
+    }
+
+    /// 列出所有备份（按时间升序）
+    pub fn list_backups(&self) -> Result<Vec<PathBuf>, String> {
+        let mut backups = Vec::new();
+        let entries =
+            std::fs::read_dir(&self.backup_dir).map_err(|e| format!("无法读取备份目录: {}", e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path
+                .extension()
+                .map(|e| e == BACKUP_EXTENSION)
+                .unwrap_or(false)
+            {
+                backups.push(path);
+            }
+        }
+        backups.sort();
+        Ok(backups)
+    }
+
+    /// 仅保留最近 `keep_last` 份备份
+    pub fn cleanup_old_backups(&self) -> Result<usize, String> {
+        let backups = self.list_backups()?;
+        let excess = backups.len().saturating_sub(self.keep_last);
+        for path in backups.iter().take(excess) {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(excess)
+    }
+
+    /// 距离下一次定时备份还需等待的时间
+    ///
+    /// 最近一份备份（包括手动创建的）距今不足 `interval` 时返回剩余时间，否则返回 0。
+    pub fn time_until_next_backup(&self, interval: Duration) -> Duration {
+        let elapsed = self
+            .list_backups()
+            .ok()
+            .and_then(|backups| backups.last().cloned())
+            .and_then(|path| std::fs::metadata(path).ok())
+            .and_then(|meta| meta.modified().ok())
+            .and_then(|modified| modified.elapsed().ok());
+        match elapsed {
+            Some(elapsed) if elapsed < interval => interval - elapsed,
+            _ => Duration::ZERO,
+        }
+    }
+
+    pub fn backup_dir(&self) -> &PathBuf {
+        &self.backup_dir
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let mut tag = [0u8; TAG_LEN];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key,
+            Some(&nonce),
+            BACKUP_MAGIC,
+            plaintext,
+            &mut tag,
+        )
+        .map_err(|e| format!("加密备份失败: {}", e))?;
+
+        let mut out =
+            Vec::with_capacity(BACKUP_MAGIC.len() + NONCE_LEN + TAG_LEN + ciphertext.len());
+        out.extend_from_slice(BACKUP_MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&tag);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let header_len = BACKUP_MAGIC.len() + NONCE_LEN + TAG_LEN;
+        if data.len() < header_len || !data.starts_with(BACKUP_MAGIC) {
+            return Err("不是有效的配置备份文件".to_string());
+        }
+
+        let nonce = &data[BACKUP_MAGIC.len()..BACKUP_MAGIC.len() + NONCE_LEN];
+        let tag = &data[BACKUP_MAGIC.len() + NONCE_LEN..header_len];
+        decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key,
+            Some(nonce),
+            BACKUP_MAGIC,
+            &data[header_len..],
+            tag,
+        )
+        .map_err(|_| "解密备份失败：文件已损坏或不是本机创建的备份".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ImportService;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_backup_roundtrip_is_encrypted_and_valid() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = ConfigBackupService::new(temp_dir.path().to_path_buf(), 3).unwrap();
+
+        let mut config = Config::default();
+        config.server.api_key = "sk-backup-secret".to_string();
+
+        let path = service.create_backup(&config).unwrap();
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("sk-backup-secret"));
+
+        let content = service.read_backup(&path).unwrap();
+        assert!(content.contains("sk-backup-secret"));
+        let validation = ImportService::validate(&content);
+        assert!(validation.valid);
+        assert!(!validation.redacted);
+    }
+
+    #[test]
+    fn test_time_until_next_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = ConfigBackupService::new(temp_dir.path().to_path_buf(), 3).unwrap();
+        let interval = Duration::from_secs(3600);
+
+        // 没有备份时立即备份
+        assert_eq!(service.time_until_next_backup(interval), Duration::ZERO);
+
+        service.create_backup(&Config::default()).unwrap();
+        let wait = service.time_until_next_backup(interval);
+        assert!(wait > Duration::from_secs(3500) && wait <= interval);
+        assert_eq!(
+            service.time_until_next_backup(Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_cleanup_keeps_last_n() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = ConfigBackupService::new(temp_dir.path().to_path_buf(), 2).unwrap();
+
+        for i in 0..4 {
+            let path = temp_dir
+                .path()
+                .join(format!("proxycast_config_2024010{}_000000.pcbak", i));
+            std::fs::write(path, b"x").unwrap();
+        }
+
+        assert_eq!(service.cleanup_old_backups().unwrap(), 2);
+        let remaining = service.list_backups().unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining[0].ends_with("proxycast_config_20240102_000000.pcbak"));
+    }
+
+    #[test]
+    fn test_rejects_tampered_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = ConfigBackupService::new(temp_dir.path().to_path_buf(), 3).unwrap();
+
+        let path = service.create_backup(&Config::default()).unwrap();
+        let mut raw = std::fs::read(&path).unwrap();
+        let last = raw.len() - 1;
+        raw[last] ^= 0xff;
+        std::fs::write(&path, raw).unwrap();
+
+        assert!(service.read_backup(&path).is_err());
+    }
+}
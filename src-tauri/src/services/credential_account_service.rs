@@ -0,0 +1,405 @@
+//! 凭证账号信息
+//!
+//! 查询凭证对应的上游账号（邮箱、账号 ID、组织），用于在凭证池界面区分同一 Provider 的多个账号：
+//! - Claude OAuth：Anthropic OAuth profile 接口
+//! - Gemini / Antigravity OAuth：Google userinfo 接口，组织为 GCP 项目 ID
+//! - OpenAI API Key：`/v1/me` 接口
+//! - Codex / iFlow / Kiro：凭证文件中登录时记录的账号信息（Codex 还会解析 id_token）
+//! - 其他 API Key：上游没有身份接口，返回掩码后的 Key
+//!
+//! 身份接口失败时回退到凭证文件中的信息。结果按凭证缓存 5 分钟。
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::expand_tilde;
+use crate::models::provider_pool_model::{CredentialData, PoolProviderType, ProviderCredential};
+
+/// 账号信息缓存时长
+const CACHE_TTL: Duration = Duration::from_secs(300);
+/// 身份接口请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+const CLAUDE_PROFILE_URL: &str = "https://api.anthropic.com/api/oauth/profile";
+const GOOGLE_USERINFO_URL: &str = "https://www.googleapis.com/oauth2/v2/userinfo";
+
+/// 凭证 UUID -> (查询时间, 账号信息)
+static CACHE: Lazy<RwLock<HashMap<String, (Instant, CredentialAccountInfo)>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 账号信息来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountInfoSource {
+    /// 上游身份接口
+    Api,
+    /// 凭证文件中登录时记录的信息
+    CredentialFile,
+    /// 上游没有身份接口，只能显示掩码后的 API Key
+    ApiKey,
+}
+
+/// 凭证对应的账号信息
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialAccountInfo {
+    pub uuid: String,
+    pub provider_type: PoolProviderType,
+    /// 账号标识（账号 ID 或邮箱）
+    pub account_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// 组织 / 项目
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    pub source: AccountInfoSource,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// 从上游或凭证文件解析出的身份字段
+#[derive(Debug, Default, PartialEq)]
+struct Identity {
+    account_id: Option<String>,
+    email: Option<String>,
+    display_name: Option<String>,
+    organization: Option<String>,
+}
+
+impl Identity {
+    fn is_empty(&self) -> bool {
+        self.account_id.is_none() && self.email.is_none()
+    }
+}
+
+fn str_field(json: &Value, pointer: &str) -> Option<String> {
+    json.pointer(pointer)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// 按顺序取第一个存在的字段
+fn first_field(json: &Value, pointers: &[&str]) -> Option<String> {
+    pointers.iter().find_map(|p| str_field(json, p))
+}
+
+fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 12 {
+        "****".to_string()
+    } else {
+        let prefix: String = chars[..6].iter().collect();
+        let suffix: String = chars[chars.len() - 4..].iter().collect();
+        format!("{prefix}****{suffix}")
+    }
+}
+
+/// 解析 Anthropic OAuth profile 响应
+fn parse_claude_profile(json: &Value) -> Identity {
+    Identity {
+        account_id: str_field(json, "/account/uuid"),
+        email: str_field(json, "/account/email_address")
+            .or_else(|| str_field(json, "/account/email")),
+        display_name: first_field(json, &["/account/display_name", "/account/full_name"]),
+        organization: str_field(json, "/organization/name"),
+    }
+}
+
+/// 解析 Google userinfo 响应
+fn parse_google_userinfo(json: &Value) -> Identity {
+    Identity {
+        account_id: str_field(json, "/id"),
+        email: str_field(json, "/email"),
+        display_name: str_field(json, "/name"),
+        organization: None,
+    }
+}
+
+/// 解析 OpenAI `/v1/me` 响应
+fn parse_openai_me(json: &Value) -> Identity {
+    Identity {
+        account_id: str_field(json, "/id"),
+        email: str_field(json, "/email"),
+        display_name: str_field(json, "/name"),
+        organization: first_field(json, &["/orgs/data/0/title", "/orgs/data/0/name"]),
+    }
+}
+
+/// 从凭证文件内容解析登录时记录的账号信息
+fn parse_credential_file(provider_type: PoolProviderType, json: &Value) -> Identity {
+    match provider_type {
+        PoolProviderType::Codex => {
+            let mut identity = Identity {
+                account_id: first_field(json, &["/account_id", "/accountId"]),
+                email: str_field(json, "/email"),
+                ..Default::default()
+            };
+            if identity.account_id.is_none() || identity.email.is_none() {
+                if let Some(id_token) = first_field(json, &["/id_token", "/idToken"]) {
+                    let (account_id, email) = crate::providers::codex::parse_jwt_claims(&id_token);
+                    identity.account_id = identity.account_id.or(account_id);
+                    identity.email = identity.email.or(email);
+                }
+            }
+            identity
+        }
+        PoolProviderType::IFlow => Identity {
+            account_id: first_field(json, &["/user_id", "/userId"]),
+            email: str_field(json, "/email"),
+            ..Default::default()
+        },
+        PoolProviderType::Kiro => Identity {
+            account_id: first_field(json, &["/profileArn", "/profile_arn"]),
+            ..Default::default()
+        },
+        _ => Identity {
+            email: str_field(json, "/email"),
+            organization: first_field(json, &["/project_id", "/projectId"]),
+            ..Default::default()
+        },
+    }
+}
+
+fn read_credential_file(path: &str) -> Result<Value, String> {
+    let content = std::fs::read_to_string(expand_tilde(path))
+        .map_err(|e| format!("读取凭证文件失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析凭证失败: {}", e))
+}
+
+async fn get_json(request: reqwest::RequestBuilder) -> Result<Value, String> {
+    let response = request
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "HTTP {} - {}",
+            status,
+            body.chars().take(200).collect::<String>()
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("解析响应失败: {}", e))
+}
+
+/// 调用身份接口，失败时回退到凭证文件中的信息
+async fn identity_with_fallback(
+    provider_type: PoolProviderType,
+    file: &Value,
+    request: Option<reqwest::RequestBuilder>,
+    parse: fn(&Value) -> Identity,
+) -> (Identity, AccountInfoSource) {
+    if let Some(request) = request {
+        match get_json(request).await {
+            Ok(json) => {
+                let identity = parse(&json);
+                if !identity.is_empty() {
+                    return (identity, AccountInfoSource::Api);
+                }
+            }
+            Err(e) => tracing::warn!(
+                "[ACCOUNT_INFO] {} 身份接口调用失败，使用凭证文件中的信息: {}",
+                provider_type,
+                e
+            ),
+        }
+    }
+    (
+        parse_credential_file(provider_type, file),
+        AccountInfoSource::CredentialFile,
+    )
+}
+
+async fn fetch_identity(
+    cred: &ProviderCredential,
+) -> Result<(Identity, AccountInfoSource), String> {
+    let client = crate::http_client::client_for(&cred.provider_type.to_string());
+
+    match &cred.credential {
+        CredentialData::ClaudeOAuth { creds_file_path } => {
+            let file = read_credential_file(creds_file_path)?;
+            let request = first_field(&file, &["/access_token", "/accessToken"]).map(|token| {
+                client
+                    .get(CLAUDE_PROFILE_URL)
+                    .bearer_auth(token)
+                    .header("anthropic-beta", "oauth-2025-04-20")
+            });
+            Ok(
+                identity_with_fallback(cred.provider_type, &file, request, parse_claude_profile)
+                    .await,
+            )
+        }
+        CredentialData::GeminiOAuth {
+            creds_file_path,
+            project_id,
+        }
+        | CredentialData::AntigravityOAuth {
+            creds_file_path,
+            project_id,
+        } => {
+            let file = read_credential_file(creds_file_path)?;
+            let request = first_field(&file, &["/access_token", "/accessToken"])
+                .map(|token| client.get(GOOGLE_USERINFO_URL).bearer_auth(token));
+            let (mut identity, source) =
+                identity_with_fallback(cred.provider_type, &file, request, parse_google_userinfo)
+                    .await;
+            identity.organization = project_id
+                .clone()
+                .or(identity.organization)
+                .or_else(|| first_field(&file, &["/project_id", "/projectId"]));
+            Ok((identity, source))
+        }
+        CredentialData::OpenAIKey { api_key, base_url } => {
+            let base = base_url
+                .as_deref()
+                .unwrap_or("https://api.openai.com")
+                .trim_end_matches('/');
+            let url = if base.ends_with("/v1") {
+                format!("{}/me", base)
+            } else {
+                format!("{}/v1/me", base)
+            };
+            match get_json(client.get(&url).bearer_auth(api_key)).await {
+                Ok(json) => {
+                    let identity = parse_openai_me(&json);
+                    if !identity.is_empty() {
+                        return Ok((identity, AccountInfoSource::Api));
+                    }
+                }
+                // 兼容接口通常没有 /v1/me
+                Err(e) => tracing::warn!("[ACCOUNT_INFO] OpenAI /v1/me 调用失败: {}", e),
+            }
+            Ok(masked_key_identity(api_key))
+        }
+        CredentialData::CodexOAuth {
+            creds_file_path, ..
+        }
+        | CredentialData::IFlowOAuth { creds_file_path }
+        | CredentialData::IFlowCookie { creds_file_path }
+        | CredentialData::KiroOAuth { creds_file_path } => {
+            let file = read_credential_file(creds_file_path)?;
+            Ok((
+                parse_credential_file(cred.provider_type, &file),
+                AccountInfoSource::CredentialFile,
+            ))
+        }
+        CredentialData::QwenOAuth { .. } => Err("Qwen 凭证不包含账号信息".to_string()),
+        CredentialData::ClaudeKey { api_key, .. }
+        | CredentialData::AnthropicKey { api_key, .. }
+        | CredentialData::VertexKey { api_key, .. }
+        | CredentialData::GeminiApiKey { api_key, .. } => Ok(masked_key_identity(api_key)),
+    }
+}
+
+fn masked_key_identity(api_key: &str) -> (Identity, AccountInfoSource) {
+    (
+        Identity {
+            account_id: Some(mask_key(api_key)),
+            ..Default::default()
+        },
+        AccountInfoSource::ApiKey,
+    )
+}
+
+/// 查询凭证对应的账号信息（带缓存）
+pub async fn get_account_info(cred: &ProviderCredential) -> Result<CredentialAccountInfo, String> {
+    if let Some((at, info)) = CACHE.read().get(&cred.uuid) {
+        if at.elapsed() < CACHE_TTL {
+            return Ok(info.clone());
+        }
+    }
+
+    if cred.credential.needs_secret() {
+        return Err("凭证尚未填写机密".to_string());
+    }
+
+    let (identity, source) = fetch_identity(cred).await?;
+    let account_id = identity
+        .account_id
+        .clone()
+        .or_else(|| identity.email.clone())
+        .ok_or_else(|| format!("未获取到 {} 凭证的账号标识", cred.provider_type))?;
+    let info = CredentialAccountInfo {
+        uuid: cred.uuid.clone(),
+        provider_type: cred.provider_type,
+        account_id,
+        email: identity.email,
+        display_name: identity.display_name,
+        organization: identity.organization,
+        source,
+        fetched_at: Utc::now(),
+    };
+
+    CACHE
+        .write()
+        .insert(cred.uuid.clone(), (Instant::now(), info.clone()));
+    Ok(info)
+}
+
+/// 清除凭证的账号信息缓存（凭证更新或删除后调用）
+pub fn invalidate(uuid: &str) {
+    CACHE.write().remove(uuid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_identity_responses() {
+        let profile = serde_json::json!({
+            "account": {"uuid": "acc-1", "email_address": "a@example.com", "display_name": "A"},
+            "organization": {"uuid": "org-1", "name": "Team"}
+        });
+        assert_eq!(
+            parse_claude_profile(&profile),
+            Identity {
+                account_id: Some("acc-1".to_string()),
+                email: Some("a@example.com".to_string()),
+                display_name: Some("A".to_string()),
+                organization: Some("Team".to_string()),
+            }
+        );
+
+        let me = serde_json::json!({
+            "id": "user-1",
+            "email": "b@example.com",
+            "orgs": {"data": [{"title": "Personal"}]}
+        });
+        let identity = parse_openai_me(&me);
+        assert_eq!(identity.account_id.as_deref(), Some("user-1"));
+        assert_eq!(identity.organization.as_deref(), Some("Personal"));
+
+        assert!(parse_google_userinfo(&serde_json::json!({"error": "x"})).is_empty());
+    }
+
+    #[test]
+    fn test_parse_credential_file() {
+        let kiro = serde_json::json!({"accessToken": "t", "profileArn": "arn:aws:codewhisperer:1"});
+        assert_eq!(
+            parse_credential_file(PoolProviderType::Kiro, &kiro)
+                .account_id
+                .as_deref(),
+            Some("arn:aws:codewhisperer:1")
+        );
+
+        let iflow = serde_json::json!({"email": "c@example.com", "user_id": ""});
+        let identity = parse_credential_file(PoolProviderType::IFlow, &iflow);
+        assert_eq!(identity.email.as_deref(), Some("c@example.com"));
+        assert!(identity.account_id.is_none());
+
+        assert_eq!(mask_key("sk-ant-1234567890abcd"), "sk-ant****abcd");
+    }
+}
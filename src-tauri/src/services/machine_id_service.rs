@@ -4,7 +4,7 @@ use crate::models::machine_id::*;
 use dirs;
 use serde_json;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use tracing;
 use uuid::Uuid;
@@ -417,22 +417,7 @@ impl MachineIdService {
         }
 
         // 读取系统原始 UUID
-        let output = Command::new("ioreg")
-            .args(&["-rd1", "-c", "IOPlatformExpertDevice"])
-            .output()
-            .map_err(|e| format!("Failed to execute ioreg: {}", e))?;
-
-        let output_str = String::from_utf8_lossy(&output.stdout);
-
-        for line in output_str.lines() {
-            if line.contains("IOPlatformUUID") {
-                if let Some(uuid_part) = line.split('"').nth(3) {
-                    return Ok(uuid_part.to_string());
-                }
-            }
-        }
-
-        Err("Failed to find IOPlatformUUID in ioreg output".to_string())
+        read_macos_platform_uuid()
     }
 
     #[cfg(target_os = "macos")]
@@ -581,10 +566,13 @@ impl MachineIdService {
         let hex_id = new_id.replace("-", "").to_lowercase();
 
         // 尝试写入 /etc/machine-id
-        match fs::write("/etc/machine-id", &hex_id) {
+        let content = format!("{}\n", hex_id);
+        match write_file_atomically(Path::new("/etc/machine-id"), &content) {
             Ok(_) => {
                 // 同时更新 /var/lib/dbus/machine-id（如果存在）
-                let _ = fs::write("/var/lib/dbus/machine-id", &hex_id);
+                if Path::new("/var/lib/dbus/machine-id").exists() {
+                    let _ = write_file_atomically(Path::new("/var/lib/dbus/machine-id"), &content);
+                }
 
                 Ok(MachineIdResult {
                     success: true,
@@ -690,6 +678,197 @@ impl MachineIdService {
         Ok(())
     }
 
+    // === 多位置一致性审计与修复 ===
+
+    /// 读取当前平台所有已知位置的机器码并报告不一致
+    pub async fn audit_machine_id(&self) -> Result<MachineIdAudit, String> {
+        let locations = MachineIdLocationKind::for_current_os()
+            .iter()
+            .map(|kind| kind.snapshot())
+            .collect();
+        Ok(build_audit(Self::get_os_type(), locations))
+    }
+
+    /// 将规范机器码写入当前平台所有可写位置
+    ///
+    /// 写入前检查管理员权限并备份各位置原值；任一位置写入失败时回滚已写入的位置。
+    pub async fn repair_machine_id(
+        &self,
+        canonical_id: &str,
+    ) -> Result<MachineIdRepairResult, String> {
+        let validation = self.validate_machine_id(canonical_id)?;
+        let Some(formatted_id) = validation.formatted_id else {
+            return Ok(MachineIdRepairResult::failed(
+                validation
+                    .error_message
+                    .unwrap_or("Invalid machine ID format".to_string()),
+                false,
+                None,
+            ));
+        };
+
+        let kinds = MachineIdLocationKind::for_current_os();
+        if kinds.is_empty() {
+            return Ok(MachineIdRepairResult::failed(
+                format!("Unsupported operating system: {}", Self::get_os_type()),
+                false,
+                None,
+            ));
+        }
+
+        let admin_status = self.check_admin_privileges().await?;
+        if !admin_status.is_admin {
+            return Ok(MachineIdRepairResult::failed(
+                "Administrator privileges required to repair machine ID".to_string(),
+                true,
+                None,
+            ));
+        }
+
+        let snapshots: Vec<MachineIdLocation> = kinds.iter().map(|kind| kind.snapshot()).collect();
+        let backup_path = self.write_repair_backup(&snapshots)?;
+        let backup_path_str = backup_path.to_string_lossy().to_string();
+
+        let hex_id = normalize_machine_id(&formatted_id);
+        let mut written: Vec<usize> = Vec::new();
+        for (index, (kind, snapshot)) in kinds.iter().zip(&snapshots).enumerate() {
+            if !kind.should_write(snapshot) {
+                continue;
+            }
+            if let Err(e) = kind.write(&hex_id) {
+                tracing::error!("写入机器码位置 {} 失败: {}", snapshot.location, e);
+                for &done in written.iter().rev() {
+                    if let Err(rollback_err) = kinds[done].restore(snapshots[done].value.as_deref())
+                    {
+                        tracing::error!(
+                            "回滚机器码位置 {} 失败: {}",
+                            snapshots[done].location,
+                            rollback_err
+                        );
+                    }
+                }
+                return Ok(MachineIdRepairResult::failed(
+                    format!(
+                        "Failed to write {}: {}. Previous values have been restored.",
+                        snapshot.location, e
+                    ),
+                    false,
+                    Some(backup_path_str),
+                ));
+            }
+            written.push(index);
+        }
+
+        if let Err(e) = self.add_history_record(formatted_id, Some(backup_path_str.clone())) {
+            tracing::warn!("Failed to add history record: {}", e);
+        }
+
+        Ok(MachineIdRepairResult {
+            success: true,
+            message: "Machine ID synchronized across all locations. Restart may be required for some applications.".to_string(),
+            requires_admin: false,
+            backup_path: Some(backup_path_str),
+            updated_locations: written
+                .into_iter()
+                .map(|index| snapshots[index].location.clone())
+                .collect(),
+        })
+    }
+
+    /// 从修复备份恢复各位置的原值
+    pub async fn undo_machine_id_repair(
+        &self,
+        backup_path: &str,
+    ) -> Result<MachineIdRepairResult, String> {
+        let backup = self.read_repair_backup(backup_path)?;
+        if backup.platform != Self::get_os_type() {
+            return Ok(MachineIdRepairResult::failed(
+                format!(
+                    "Backup was created on {}, cannot restore on {}",
+                    backup.platform,
+                    Self::get_os_type()
+                ),
+                false,
+                None,
+            ));
+        }
+
+        let admin_status = self.check_admin_privileges().await?;
+        if !admin_status.is_admin {
+            return Ok(MachineIdRepairResult::failed(
+                "Administrator privileges required to restore machine ID".to_string(),
+                true,
+                None,
+            ));
+        }
+
+        let mut restored = Vec::new();
+        let mut errors = Vec::new();
+        for kind in MachineIdLocationKind::for_current_os() {
+            if !kind.writable() {
+                continue;
+            }
+            let location = kind.location();
+            let Some(snapshot) = backup.locations.iter().find(|l| l.location == location) else {
+                continue;
+            };
+            match kind.restore(snapshot.value.as_deref()) {
+                Ok(()) => restored.push(location),
+                Err(e) => errors.push(format!("{}: {}", location, e)),
+            }
+        }
+
+        Ok(MachineIdRepairResult {
+            success: errors.is_empty(),
+            message: if errors.is_empty() {
+                "Machine ID locations restored from backup".to_string()
+            } else {
+                format!("Failed to restore some locations: {}", errors.join("; "))
+            },
+            requires_admin: false,
+            backup_path: Some(backup_path.to_string()),
+            updated_locations: restored,
+        })
+    }
+
+    fn write_repair_backup(&self, locations: &[MachineIdLocation]) -> Result<PathBuf, String> {
+        let now = chrono::Utc::now();
+        let backup = MachineIdRepairBackup {
+            timestamp: now.timestamp(),
+            platform: Self::get_os_type(),
+            locations: locations.to_vec(),
+        };
+        let backup_json = serde_json::to_string_pretty(&backup)
+            .map_err(|e| format!("Failed to serialize repair backup: {}", e))?;
+
+        let backup_file = self
+            .backup_dir
+            .join(format!("repair_{}.json", now.format("%Y%m%d_%H%M%S_%3f")));
+        fs::write(&backup_file, backup_json)
+            .map_err(|e| format!("Failed to write repair backup: {}", e))?;
+        Ok(backup_file)
+    }
+
+    fn read_repair_backup(&self, backup_path: &str) -> Result<MachineIdRepairBackup, String> {
+        // 只允许读取备份目录内的文件
+        let canonical_path = PathBuf::from(backup_path)
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve backup path: {}", e))?;
+        let canonical_dir = self
+            .backup_dir
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve backup directory: {}", e))?;
+        if !canonical_path.starts_with(&canonical_dir) {
+            return Err(
+                "Repair backups can only be restored from the backup directory".to_string(),
+            );
+        }
+
+        let content = fs::read_to_string(&canonical_path)
+            .map_err(|e| format!("Failed to read repair backup: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse repair backup: {}", e))
+    }
+
     // === 历史记录管理 ===
 
     /// 加载历史记录
@@ -750,3 +929,381 @@ impl MachineIdService {
         self.load_history()
     }
 }
+
+impl MachineIdRepairResult {
+    fn failed(message: String, requires_admin: bool, backup_path: Option<String>) -> Self {
+        Self {
+            success: false,
+            message,
+            requires_admin,
+            backup_path,
+            updated_locations: Vec::new(),
+        }
+    }
+}
+
+/// 机器码在操作系统中的存储位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MachineIdLocationKind {
+    /// HKLM\SOFTWARE\Microsoft\Cryptography\MachineGuid
+    WindowsCryptography,
+    /// HKLM\SOFTWARE\Microsoft\SQMClient\MachineId（`{UUID}` 大写格式）
+    WindowsSqmClient,
+    /// IOPlatformUUID（只读）
+    MacosPlatformUuid,
+    /// 应用层覆盖文件
+    MacosOverride,
+    /// /etc/machine-id
+    LinuxEtc,
+    /// /var/lib/dbus/machine-id
+    LinuxDbus,
+}
+
+impl MachineIdLocationKind {
+    fn for_current_os() -> Vec<Self> {
+        match std::env::consts::OS {
+            "windows" => vec![Self::WindowsCryptography, Self::WindowsSqmClient],
+            "macos" => vec![Self::MacosPlatformUuid, Self::MacosOverride],
+            "linux" => vec![Self::LinuxEtc, Self::LinuxDbus],
+            _ => vec![],
+        }
+    }
+
+    fn location(&self) -> String {
+        match self {
+            Self::WindowsCryptography => {
+                "HKLM\\SOFTWARE\\Microsoft\\Cryptography\\MachineGuid".to_string()
+            }
+            Self::WindowsSqmClient => "HKLM\\SOFTWARE\\Microsoft\\SQMClient\\MachineId".to_string(),
+            Self::MacosPlatformUuid => "IOPlatformExpertDevice/IOPlatformUUID".to_string(),
+            Self::MacosOverride => macos_override_path()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "machine-id-override".to_string()),
+            Self::LinuxEtc => "/etc/machine-id".to_string(),
+            Self::LinuxDbus => "/var/lib/dbus/machine-id".to_string(),
+        }
+    }
+
+    fn writable(&self) -> bool {
+        !matches!(self, Self::MacosPlatformUuid)
+    }
+
+    /// 位置不存在时是否创建（其他位置仅在已存在时更新）
+    fn create_if_missing(&self) -> bool {
+        matches!(self, Self::MacosOverride)
+    }
+
+    fn should_write(&self, snapshot: &MachineIdLocation) -> bool {
+        self.writable() && (snapshot.value.is_some() || self.create_if_missing())
+    }
+
+    fn snapshot(&self) -> MachineIdLocation {
+        let (value, error) = match self.read() {
+            Ok(value) => (Some(value), None),
+            Err(e) => (None, Some(e)),
+        };
+        MachineIdLocation {
+            location: self.location(),
+            value,
+            writable: self.writable(),
+            error,
+        }
+    }
+
+    fn read(&self) -> Result<String, String> {
+        let value = match self {
+            Self::WindowsCryptography => {
+                read_registry_string("SOFTWARE\\Microsoft\\Cryptography", "MachineGuid")?
+            }
+            Self::WindowsSqmClient => {
+                read_registry_string("SOFTWARE\\Microsoft\\SQMClient", "MachineId")?
+            }
+            Self::MacosPlatformUuid => read_macos_platform_uuid()?,
+            Self::MacosOverride => fs::read_to_string(macos_override_path()?)
+                .map_err(|e| format!("No override file found: {}", e))?,
+            Self::LinuxEtc | Self::LinuxDbus => fs::read_to_string(self.location())
+                .map_err(|e| format!("Failed to read {}: {}", self.location(), e))?,
+        };
+        Ok(value.trim().to_string())
+    }
+
+    /// 按该位置的原生格式写入规范机器码（32 位小写十六进制）
+    fn write(&self, hex_id: &str) -> Result<(), String> {
+        let uuid = MachineIdFormat::Uuid.format_machine_id(hex_id)?;
+        let value = match self {
+            Self::WindowsSqmClient => format!("{{{}}}", uuid.to_uppercase()),
+            Self::LinuxEtc | Self::LinuxDbus => hex_id.to_string(),
+            _ => uuid,
+        };
+        self.write_raw(&value)
+    }
+
+    /// 恢复原值；原本不存在的位置会被删除
+    fn restore(&self, previous: Option<&str>) -> Result<(), String> {
+        match previous {
+            Some(value) => self.write_raw(value),
+            None if self.create_if_missing() => match fs::remove_file(macos_override_path()?) {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(format!("Failed to remove override file: {}", e)),
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn write_raw(&self, value: &str) -> Result<(), String> {
+        match self {
+            Self::WindowsCryptography => {
+                write_registry_string("SOFTWARE\\Microsoft\\Cryptography", "MachineGuid", value)
+            }
+            Self::WindowsSqmClient => {
+                write_registry_string("SOFTWARE\\Microsoft\\SQMClient", "MachineId", value)
+            }
+            Self::MacosPlatformUuid => Err("IOPlatformUUID is read-only".to_string()),
+            Self::MacosOverride => {
+                let path = macos_override_path()?;
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create override directory: {}", e))?;
+                }
+                fs::write(path, value).map_err(|e| format!("Failed to write override file: {}", e))
+            }
+            Self::LinuxEtc | Self::LinuxDbus => {
+                write_file_atomically(Path::new(&self.location()), &format!("{}\n", value))
+                    .map_err(|e| format!("Failed to write {}: {}", self.location(), e))
+            }
+        }
+    }
+}
+
+/// 归一化机器码用于比较（去除花括号、连字符和空白，转小写）
+fn normalize_machine_id(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '{' | '}' | '-') && !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn build_audit(platform: String, locations: Vec<MachineIdLocation>) -> MachineIdAudit {
+    let mut distinct_values: Vec<String> = Vec::new();
+    for value in locations.iter().filter_map(|l| l.value.as_deref()) {
+        let normalized = normalize_machine_id(value);
+        if !distinct_values.contains(&normalized) {
+            distinct_values.push(normalized);
+        }
+    }
+
+    MachineIdAudit {
+        platform,
+        consistent: distinct_values.len() <= 1,
+        locations,
+        distinct_values,
+    }
+}
+
+/// 原子替换文件内容
+///
+/// 先写入同目录下的临时文件并 fsync，再 rename 覆盖目标，避免中途失败时
+/// 留下空的或截断的机器码文件。目标是符号链接时（如 /var/lib/dbus/machine-id
+/// 指向 /etc/machine-id）替换链接指向的文件，并保留原文件权限。
+fn write_file_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = target.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        fs::rename(&temp_path, &target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // 同步目录项，确保 rename 在断电后仍然生效
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+fn macos_override_path() -> Result<PathBuf, String> {
+    Ok(dirs::data_dir()
+        .ok_or("Failed to get app data directory")?
+        .join("proxycast")
+        .join("machine-id-override"))
+}
+
+fn read_macos_platform_uuid() -> Result<String, String> {
+    let output = Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .map_err(|e| format!("Failed to execute ioreg: {}", e))?;
+
+    let output_str = String::from_utf8_lossy(&output.stdout);
+
+    for line in output_str.lines() {
+        if line.contains("IOPlatformUUID") {
+            if let Some(uuid_part) = line.split('"').nth(3) {
+                return Ok(uuid_part.to_string());
+            }
+        }
+    }
+
+    Err("Failed to find IOPlatformUUID in ioreg output".to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn read_registry_string(subkey: &str, name: &str) -> Result<String, String> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let key = hklm
+        .open_subkey_with_flags(subkey, KEY_READ)
+        .map_err(|e| format!("Failed to open registry key {}: {}", subkey, e))?;
+    key.get_value(name)
+        .map_err(|e| format!("Failed to read registry value {}: {}", name, e))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn read_registry_string(_subkey: &str, _name: &str) -> Result<String, String> {
+    Err("Windows registry reading not supported on this platform".to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn write_registry_string(subkey: &str, name: &str, value: &str) -> Result<(), String> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let (key, _) = hklm
+        .create_subkey(subkey)
+        .map_err(|e| format!("Failed to create/open registry key {}: {}", subkey, e))?;
+    key.set_value(name, &value)
+        .map_err(|e| format!("Failed to set registry value {}: {}", name, e))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn write_registry_string(_subkey: &str, _name: &str, _value: &str) -> Result<(), String> {
+    Err("Windows registry modification not supported on this platform".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(name: &str, value: Option<&str>) -> MachineIdLocation {
+        MachineIdLocation {
+            location: name.to_string(),
+            value: value.map(String::from),
+            writable: true,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_normalize_ignores_braces_hyphens_and_case() {
+        assert_eq!(
+            normalize_machine_id("{A1B2C3D4-E5F6-4711-8899-AABBCCDDEEFF}"),
+            "a1b2c3d4e5f647118899aabbccddeeff"
+        );
+        assert_eq!(
+            normalize_machine_id("a1b2c3d4e5f647118899aabbccddeeff\n"),
+            "a1b2c3d4e5f647118899aabbccddeeff"
+        );
+    }
+
+    #[test]
+    fn test_audit_treats_format_differences_as_consistent() {
+        let audit = build_audit(
+            "windows".to_string(),
+            vec![
+                location("guid", Some("a1b2c3d4-e5f6-4711-8899-aabbccddeeff")),
+                location("sqm", Some("{A1B2C3D4-E5F6-4711-8899-AABBCCDDEEFF}")),
+                location("missing", None),
+            ],
+        );
+        assert!(audit.consistent);
+        assert_eq!(audit.distinct_values.len(), 1);
+    }
+
+    #[test]
+    fn test_audit_reports_mismatch() {
+        let audit = build_audit(
+            "linux".to_string(),
+            vec![
+                location("/etc/machine-id", Some("a1b2c3d4e5f647118899aabbccddeeff")),
+                location(
+                    "/var/lib/dbus/machine-id",
+                    Some("00000000000000000000000000000000"),
+                ),
+            ],
+        );
+        assert!(!audit.consistent);
+        assert_eq!(
+            audit.distinct_values,
+            vec![
+                "a1b2c3d4e5f647118899aabbccddeeff".to_string(),
+                "00000000000000000000000000000000".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_file_atomically_replaces_content() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("machine-id");
+        fs::write(&path, "00000000000000000000000000000000\n").unwrap();
+
+        write_file_atomically(&path, "a1b2c3d4e5f647118899aabbccddeeff\n").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "a1b2c3d4e5f647118899aabbccddeeff\n"
+        );
+        // 临时文件已被 rename，目录中只剩目标文件
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_atomically_follows_symlink_and_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let target = temp_dir.path().join("machine-id");
+        let link = temp_dir.path().join("dbus-machine-id");
+        fs::write(&target, "old\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o644)).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_file_atomically(&link, "new\n").unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new\n");
+        assert_eq!(
+            fs::metadata(&target).unwrap().permissions().mode() & 0o777,
+            0o644
+        );
+    }
+
+    #[test]
+    fn test_only_override_is_created_when_missing() {
+        let missing = location("x", None);
+        assert!(MachineIdLocationKind::MacosOverride.should_write(&missing));
+        assert!(!MachineIdLocationKind::LinuxDbus.should_write(&missing));
+        assert!(!MachineIdLocationKind::MacosPlatformUuid
+            .should_write(&location("x", Some("a1b2c3d4-e5f6-4711-8899-aabbccddeeff"))));
+    }
+}
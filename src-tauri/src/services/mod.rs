@@ -1,5 +1,7 @@
 pub mod api_key_provider_service;
 pub mod backup_service;
+pub mod config_backup_service;
+pub mod credential_account_service;
 pub mod file_browser_service;
 pub mod kiro_event_service;
 pub mod live_sync;
@@ -7,6 +9,8 @@ pub mod machine_id_service;
 pub mod mcp_service;
 pub mod mcp_sync;
 pub mod model_registry_service;
+pub mod pool_dedupe_service;
+pub mod pool_template_service;
 pub mod prompt_service;
 pub mod prompt_sync;
 pub mod provider_pool_service;
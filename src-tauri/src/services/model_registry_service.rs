@@ -136,6 +136,9 @@ impl ModelRegistryService {
             let mut cache = self.models_cache.write().await;
             *cache = models.clone();
         }
+        crate::telemetry::pricing::set_prices(crate::telemetry::pricing::prices_from_models(
+            &models,
+        ));
         {
             let mut cache = self.aliases_cache.write().await;
             *cache = aliases;
@@ -549,6 +552,9 @@ impl ModelRegistryService {
             let mut cache = self.models_cache.write().await;
             *cache = models.clone();
         }
+        crate::telemetry::pricing::set_prices(crate::telemetry::pricing::prices_from_models(
+            &models,
+        ));
         {
             let mut cache = self.aliases_cache.write().await;
             *cache = aliases;
@@ -0,0 +1,231 @@
+//! 凭证池去重
+//!
+//! 多次导入后同一凭证可能以不同 UUID 出现多次，轮询时会被重复选中。这里按内容指纹识别重复：
+//! - API Key 类凭证：凭证数据完全相同（Key、base_url 等）
+//! - OAuth 类凭证：凭证文件中的 refresh token 相同；文件没有 refresh token 时比较整个文件内容。
+//!   Kiro 凭证还要求设备指纹（[`crate::providers::kiro::credential_fingerprint`]）相同
+//!
+//! 每组重复保留创建最早的一条，合并使用统计和健康状态后删除其余条目。
+//! 指纹无法计算（文件不存在、等待填写机密）的凭证不参与去重。
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::expand_tilde;
+use crate::models::provider_pool_model::{CredentialData, PoolProviderType, ProviderCredential};
+use crate::providers::kiro::{self, KiroCredentials};
+
+/// 凭证文件中 refresh token 的字段名
+const REFRESH_TOKEN_KEYS: &[&str] = &["refreshToken", "refresh_token"];
+
+/// 被合并的凭证
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedCredential {
+    pub uuid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// 一组重复凭证
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub provider_type: PoolProviderType,
+    /// 保留的凭证（合并统计后）
+    pub kept: ProviderCredential,
+    /// 合并后删除的凭证
+    pub merged: Vec<MergedCredential>,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// 读取凭证文件并计算指纹
+fn file_fingerprint(path: &str) -> Option<String> {
+    let content = std::fs::read(expand_tilde(path)).ok()?;
+    let refresh_token = serde_json::from_slice::<serde_json::Value>(&content)
+        .ok()
+        .and_then(|json| {
+            REFRESH_TOKEN_KEYS
+                .iter()
+                .find_map(|key| json.get(*key)?.as_str().map(str::to_string))
+        })
+        .filter(|token| !token.is_empty());
+    Some(match refresh_token {
+        Some(token) => sha256_hex(format!("refresh:{}", token).as_bytes()),
+        None => sha256_hex(&content),
+    })
+}
+
+/// Kiro 凭证文件指纹：设备指纹 + 文件指纹
+fn kiro_file_fingerprint(path: &str) -> Option<String> {
+    let content = file_fingerprint(path)?;
+    let creds = std::fs::read(expand_tilde(path))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<KiroCredentials>(&bytes).ok())
+        .unwrap_or_default();
+    let (machine_id, _) = kiro::credential_fingerprint(&creds);
+    Some(format!("{}:{}", machine_id, content))
+}
+
+/// 计算凭证的内容指纹，无法计算时返回 None
+pub fn fingerprint(cred: &ProviderCredential) -> Option<String> {
+    if cred.credential.needs_secret() {
+        return None;
+    }
+    let content = if let CredentialData::KiroOAuth { creds_file_path } = &cred.credential {
+        kiro_file_fingerprint(creds_file_path)?
+    } else if cred.credential.is_file_based() {
+        file_fingerprint(cred.credential.secret())?
+    } else {
+        sha256_hex(&serde_json::to_vec(&cred.credential).ok()?)
+    };
+    Some(format!("{}:{}", cred.provider_type, content))
+}
+
+/// 把重复凭证的统计合并到保留的凭证
+fn merge_stats(kept: &mut ProviderCredential, dup: &ProviderCredential) {
+    kept.usage_count += dup.usage_count;
+    kept.error_count += dup.error_count;
+    kept.last_used = kept.last_used.max(dup.last_used);
+    if dup.last_error_time > kept.last_error_time {
+        kept.last_error_time = dup.last_error_time;
+        kept.last_error_message = dup.last_error_message.clone();
+    }
+    if dup.last_health_check_time > kept.last_health_check_time {
+        kept.last_health_check_time = dup.last_health_check_time;
+        kept.last_health_check_model = dup.last_health_check_model.clone();
+    }
+    // 任意一条健康/启用即视为健康/启用，避免合并后可用凭证变少
+    kept.is_healthy |= dup.is_healthy;
+    kept.is_disabled &= dup.is_disabled;
+    kept.health_score = kept.health_score.max(dup.health_score);
+    if kept.name.is_none() {
+        kept.name = dup.name.clone();
+    }
+    for model in &dup.not_supported_models {
+        if !kept.not_supported_models.contains(model) {
+            kept.not_supported_models.push(model.clone());
+        }
+    }
+}
+
+/// 按指纹分组并合并统计，返回重复组（保留创建最早的凭证）
+pub fn find_duplicates(credentials: &[ProviderCredential]) -> Vec<DuplicateGroup> {
+    let mut groups: HashMap<String, Vec<&ProviderCredential>> = HashMap::new();
+    let mut order = Vec::new();
+    for cred in credentials {
+        let Some(key) = fingerprint(cred) else {
+            continue;
+        };
+        let group = groups.entry(key.clone()).or_default();
+        if group.is_empty() {
+            order.push(key);
+        }
+        group.push(cred);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|key| {
+            let mut group = groups.remove(&key)?;
+            if group.len() < 2 {
+                return None;
+            }
+            group.sort_by_key(|c| c.created_at);
+            let mut kept = group[0].clone();
+            let merged = group[1..]
+                .iter()
+                .map(|dup| {
+                    merge_stats(&mut kept, dup);
+                    MergedCredential {
+                        uuid: dup.uuid.clone(),
+                        name: dup.name.clone(),
+                    }
+                })
+                .collect();
+            Some(DuplicateGroup {
+                provider_type: kept.provider_type,
+                kept,
+                merged,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_credential(api_key: &str, usage: u64, secs_ago: i64) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: api_key.to_string(),
+                base_url: None,
+            },
+        );
+        cred.usage_count = usage;
+        cred.created_at = chrono::Utc::now() - chrono::Duration::seconds(secs_ago);
+        cred
+    }
+
+    #[test]
+    fn test_find_duplicates_merges_into_oldest() {
+        let newer = key_credential("sk-a", 3, 10);
+        let mut older = key_credential("sk-a", 5, 100);
+        older.is_healthy = false;
+        let other = key_credential("sk-b", 1, 50);
+
+        let groups = find_duplicates(&[newer.clone(), older.clone(), other]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].kept.uuid, older.uuid);
+        assert_eq!(groups[0].kept.usage_count, 8);
+        assert!(groups[0].kept.is_healthy);
+        assert_eq!(groups[0].merged.len(), 1);
+        assert_eq!(groups[0].merged[0].uuid, newer.uuid);
+    }
+
+    #[test]
+    fn test_file_fingerprint_uses_refresh_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.json");
+        let b = dir.path().join("b.json");
+        std::fs::write(&a, r#"{"accessToken":"x","refreshToken":"r1"}"#).unwrap();
+        std::fs::write(&b, r#"{"accessToken":"y","refreshToken":"r1"}"#).unwrap();
+        assert_eq!(
+            file_fingerprint(a.to_str().unwrap()),
+            file_fingerprint(b.to_str().unwrap())
+        );
+        assert!(file_fingerprint(dir.path().join("missing.json").to_str().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_kiro_fingerprint_requires_same_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, body).unwrap();
+            ProviderCredential::new(
+                PoolProviderType::Kiro,
+                CredentialData::KiroOAuth {
+                    creds_file_path: path.to_str().unwrap().to_string(),
+                },
+            )
+        };
+        let a = write("a.json", r#"{"refreshToken":"r1","profileArn":"arn:a"}"#);
+        let b = write("b.json", r#"{"refreshToken":"r1","profileArn":"arn:a"}"#);
+        let c = write("c.json", r#"{"refreshToken":"r1","profileArn":"arn:c"}"#);
+
+        assert_eq!(fingerprint(&a), fingerprint(&b));
+        assert_ne!(fingerprint(&a), fingerprint(&c));
+        let (machine_id, source) = kiro::credential_fingerprint(&KiroCredentials {
+            profile_arn: Some("arn:a".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(source, "profileArn");
+        assert!(fingerprint(&a).unwrap().contains(&machine_id));
+    }
+}
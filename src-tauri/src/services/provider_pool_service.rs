@@ -4,22 +4,28 @@
 
 #![allow(dead_code)]
 
+use crate::config::{CredentialSelectionStrategy, HealthScoringSettings, TierRule};
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{
-    get_default_check_model, get_oauth_creds_path, CredentialData, CredentialDisplay,
-    HealthCheckResult, OAuthStatus, PoolProviderType, PoolStats, ProviderCredential,
-    ProviderPoolOverview,
+    get_default_check_model, get_oauth_creds_path, pattern_matches, CredentialData,
+    CredentialDisplay, HealthCheckResult, OAuthStatus, PoolProviderType, PoolStats,
+    ProviderCredential, ProviderPoolOverview,
 };
 use crate::models::route_model::RouteInfo;
 use crate::providers::antigravity::TokenRefreshError;
+use crate::providers::claude_custom::CLAUDE_OAUTH_BETA;
 use crate::providers::kiro::KiroProvider;
+use crate::resilience::{
+    CredentialRateLimit, RateLimitInfo, RateLimitTracker, SwitchLog, SwitchLogEntry, SwitchTrigger,
+};
 use crate::services::api_key_provider_service::ApiKeyProviderService;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// 凭证健康信息
@@ -56,18 +62,131 @@ pub enum SelectionError {
     ModelNotSupported { model: String },
 }
 
+/// 批量更新凭证的内容
+///
+/// 未设置的字段保持不变。`rename_pattern` 中 `{n}` 替换为序号（按创建时间排序，
+/// 从 `start_index` 开始，默认 1），`{name}` 替换为原名称。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkCredentialUpdate {
+    /// 目标凭证 UUID，为空表示该类型的全部凭证
+    #[serde(default)]
+    pub uuids: Vec<String>,
+    /// 重命名模板，如 `kiro-{n}`
+    #[serde(default)]
+    pub rename_pattern: Option<String>,
+    /// 重命名起始序号
+    #[serde(default)]
+    pub start_index: Option<u32>,
+    /// 启用/禁用
+    #[serde(default)]
+    pub is_disabled: Option<bool>,
+    /// 层级（空字符串表示清除）
+    #[serde(default)]
+    pub tier: Option<String>,
+}
+
+/// 计算批量更新后发生变化的凭证
+///
+/// `credentials` 为同一类型的全部凭证（按创建时间排序）。重命名后名称在该类型内必须唯一，
+/// 以免按名称选择凭证时出现歧义。
+fn plan_bulk_update(
+    credentials: &[ProviderCredential],
+    update: &BulkCredentialUpdate,
+) -> Result<Vec<ProviderCredential>, String> {
+    for uuid in &update.uuids {
+        if !credentials.iter().any(|c| &c.uuid == uuid) {
+            return Err(format!("Credential not found: {}", uuid));
+        }
+    }
+    let pattern = update
+        .rename_pattern
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty());
+    let tier = update
+        .tier
+        .as_ref()
+        .map(|t| Some(t.trim().to_string()).filter(|t| !t.is_empty()));
+
+    let mut index = update.start_index.unwrap_or(1);
+    let mut changed = Vec::new();
+    for cred in credentials {
+        if !update.uuids.is_empty() && !update.uuids.contains(&cred.uuid) {
+            continue;
+        }
+        let mut next = cred.clone();
+        if let Some(pattern) = pattern {
+            let name = pattern
+                .replace("{n}", &index.to_string())
+                .replace("{name}", cred.name.as_deref().unwrap_or(""));
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(format!("Empty credential name for {}", cred.uuid));
+            }
+            next.name = Some(name.to_string());
+            index += 1;
+        }
+        if let Some(is_disabled) = update.is_disabled {
+            next.set_disabled(is_disabled);
+        }
+        if let Some(ref tier) = tier {
+            next.tier = tier.clone();
+        }
+        if next.name != cred.name || next.is_disabled != cred.is_disabled || next.tier != cred.tier
+        {
+            changed.push(next);
+        }
+    }
+
+    if pattern.is_some() {
+        let final_name = |c: &ProviderCredential| {
+            changed
+                .iter()
+                .find(|n| n.uuid == c.uuid)
+                .map_or(c.name.clone(), |n| n.name.clone())
+        };
+        for renamed in &changed {
+            let Some(ref name) = renamed.name else {
+                continue;
+            };
+            let count = credentials
+                .iter()
+                .filter(|c| final_name(c).as_ref() == Some(name))
+                .count();
+            if count > 1 {
+                return Err(format!("Duplicate credential name: {}", name));
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
 /// 凭证池管理服务
 pub struct ProviderPoolService {
-    /// HTTP 客户端（用于健康检测）
-    client: Client,
     /// 轮询索引（按 provider_type 和可选的 model 分组）
     round_robin_index: std::sync::RwLock<HashMap<String, AtomicUsize>>,
-    /// 最大错误次数（超过后标记为不健康）
-    max_error_count: u32,
+    /// 健康评分配置（EMA 平滑系数与禁用下限）
+    health_scoring: std::sync::RwLock<HealthScoringSettings>,
     /// 健康检查超时时间
     health_check_timeout: Duration,
+    /// 模型到凭证层级的偏好规则（来自 `routing.tier_rules`）
+    tier_rules: std::sync::RwLock<Vec<TierRule>>,
+    /// 凭证选择策略与延迟探索概率（来自 `routing`）
+    selection_strategy: std::sync::RwLock<(CredentialSelectionStrategy, f64)>,
+    /// 各凭证上游延迟的 EMA（毫秒，按凭证 UUID）
+    latency_ema: std::sync::RwLock<HashMap<String, f64>>,
+    /// 凭证降级链（来自 `routing.credential_fallback`）
+    credential_fallback: std::sync::RwLock<HashMap<PoolProviderType, Vec<PoolProviderType>>>,
+    /// 切换日志（与容错设置页共享，记录凭证切换和降级）
+    switch_log: Arc<SwitchLog>,
+    /// 各凭证最近观察到的上游限流状态
+    rate_limits: RateLimitTracker,
 }
 
+/// 上游延迟 EMA 的平滑系数
+const LATENCY_EMA_ALPHA: f64 = 0.3;
+
 impl Default for ProviderPoolService {
     fn default() -> Self {
         Self::new()
@@ -77,14 +196,347 @@ impl Default for ProviderPoolService {
 impl ProviderPoolService {
     pub fn new() -> Self {
         Self {
-            client: Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()
-                .unwrap_or_default(),
             round_robin_index: std::sync::RwLock::new(HashMap::new()),
-            max_error_count: 3,
+            health_scoring: std::sync::RwLock::new(HealthScoringSettings::default()),
             health_check_timeout: Duration::from_secs(30),
+            tier_rules: std::sync::RwLock::new(Vec::new()),
+            selection_strategy: std::sync::RwLock::new((
+                CredentialSelectionStrategy::default(),
+                0.0,
+            )),
+            latency_ema: std::sync::RwLock::new(HashMap::new()),
+            credential_fallback: std::sync::RwLock::new(HashMap::new()),
+            switch_log: Arc::new(SwitchLog::new()),
+            rate_limits: RateLimitTracker::new(),
+        }
+    }
+
+    /// 健康检测使用的 HTTP 客户端，每次按当前出站代理配置获取，代理变更立即生效
+    fn client(&self) -> Client {
+        crate::http_client::client_for("provider_pool")
+    }
+
+    /// 切换日志
+    pub fn switch_log(&self) -> Arc<SwitchLog> {
+        self.switch_log.clone()
+    }
+
+    /// 记下上游失败（429、5xx），后续请求换用其他凭证时写入切换日志
+    pub fn note_upstream_failure(
+        &self,
+        uuid: &str,
+        status: u16,
+        request_id: Option<&str>,
+        model: Option<&str>,
+    ) {
+        if let Some(trigger) = SwitchTrigger::from_status(status) {
+            self.switch_log.note_failure(
+                uuid,
+                trigger,
+                request_id,
+                model,
+                Some(&format!("HTTP {}", status)),
+            );
+        }
+    }
+
+    fn note_manual_disable(&self, uuid: &str) {
+        self.switch_log
+            .note_failure(uuid, SwitchTrigger::Manual, None, None, None);
+    }
+
+    /// 写入一条降级到其他 Provider 的切换记录
+    fn record_fallback(
+        &self,
+        db: &DbConnection,
+        from_provider: &str,
+        cred: &ProviderCredential,
+        model: Option<&str>,
+    ) {
+        self.switch_log.record(
+            db,
+            SwitchLogEntry {
+                id: 0,
+                timestamp: Utc::now(),
+                from_provider: from_provider.to_string(),
+                to_provider: cred.provider_type.to_string(),
+                from_credential: None,
+                to_credential: Some(cred.uuid.clone()),
+                trigger: SwitchTrigger::CredentialsUnavailable,
+                request_id: None,
+                model: model.map(str::to_string),
+                detail: None,
+            },
+        );
+    }
+
+    /// 更新凭证降级链（无法解析的 Provider 类型和 OAuth 类型的降级目标被忽略）
+    pub fn set_credential_fallback(&self, chains: &HashMap<String, Vec<String>>) {
+        let parsed = chains
+            .iter()
+            .filter_map(|(provider, chain)| {
+                let provider = provider.parse::<PoolProviderType>().ok()?;
+                let chain = chain
+                    .iter()
+                    .filter_map(|target| target.parse::<PoolProviderType>().ok())
+                    .filter(|target| !target.is_oauth())
+                    .collect();
+                Some((provider, chain))
+            })
+            .collect();
+        if let Ok(mut credential_fallback) = self.credential_fallback.write() {
+            *credential_fallback = parsed;
+        }
+    }
+
+    fn fallback_chain(&self, provider_type: PoolProviderType) -> Vec<PoolProviderType> {
+        self.credential_fallback
+            .read()
+            .ok()
+            .and_then(|chains| chains.get(&provider_type).cloned())
+            .unwrap_or_default()
+    }
+
+    /// 更新凭证选择策略
+    pub fn set_selection_strategy(
+        &self,
+        strategy: CredentialSelectionStrategy,
+        exploration_rate: f64,
+    ) {
+        if let Ok(mut selection_strategy) = self.selection_strategy.write() {
+            *selection_strategy = (strategy, exploration_rate.clamp(0.0, 1.0));
+        }
+    }
+
+    /// 记录一次成功请求的上游延迟，更新该凭证的延迟 EMA
+    pub fn record_latency(&self, uuid: &str, latency_ms: u64) {
+        if let Ok(mut latency_ema) = self.latency_ema.write() {
+            let sample = latency_ms as f64;
+            latency_ema
+                .entry(uuid.to_string())
+                .and_modify(|ema| *ema += LATENCY_EMA_ALPHA * (sample - *ema))
+                .or_insert(sample);
+        }
+    }
+
+    /// 凭证的上游延迟 EMA（毫秒），尚无样本时返回 None
+    pub fn latency_ema(&self, uuid: &str) -> Option<f64> {
+        self.latency_ema.read().ok()?.get(uuid).copied()
+    }
+
+    /// 记录上游响应中的限流信息
+    ///
+    /// 上游要求等待或额度接近用尽时，凭证在重置前被降低优先级，返回降级时长
+    pub fn record_rate_limit(&self, uuid: &str, info: RateLimitInfo) -> Option<Duration> {
+        let hold = self.rate_limits.record(uuid, info, Utc::now());
+        if let Some(hold) = hold {
+            tracing::info!(
+                "[RATE_LIMIT] 凭证 {} 接近或达到上游限额，{} 毫秒内降低优先级",
+                crate::server_utils::safe_truncate(uuid, 8),
+                hold.as_millis()
+            );
+        }
+        hold
+    }
+
+    /// 凭证最近观察到的上游限流状态
+    pub fn rate_limit(&self, uuid: &str) -> Option<CredentialRateLimit> {
+        self.rate_limits.get(uuid)
+    }
+
+    /// 所有凭证最近观察到的上游限流状态
+    pub fn rate_limits(&self) -> HashMap<String, CredentialRateLimit> {
+        self.rate_limits.snapshot()
+    }
+
+    /// 更新凭证层级偏好规则
+    pub fn set_tier_rules(&self, rules: Vec<TierRule>) {
+        if let Ok(mut tier_rules) = self.tier_rules.write() {
+            *tier_rules = rules;
+        }
+    }
+
+    /// 更新健康评分配置
+    pub fn set_health_scoring(&self, settings: HealthScoringSettings) {
+        if let Ok(mut health_scoring) = self.health_scoring.write() {
+            *health_scoring = settings;
+        }
+    }
+
+    fn health_scoring(&self) -> HealthScoringSettings {
+        self.health_scoring
+            .read()
+            .map(|s| s.clone())
+            .unwrap_or_default()
+    }
+
+    /// 获取模型偏好的凭证层级
+    pub fn preferred_tier(&self, model: &str) -> Option<String> {
+        let rules = self.tier_rules.read().ok()?;
+        rules
+            .iter()
+            .find(|rule| pattern_matches(&rule.pattern, model))
+            .map(|rule| rule.tier.clone())
+    }
+
+    /// 设置凭证层级（None 或空字符串表示清除）
+    pub fn set_credential_tier(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        tier: Option<String>,
+    ) -> Result<ProviderCredential, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let mut cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {}", uuid))?;
+
+        cred.tier = tier.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+        cred.updated_at = Utc::now();
+
+        ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
+        Ok(cred)
+    }
+
+    /// 暂停凭证直到指定时间
+    ///
+    /// 暂停期间选择时跳过该凭证，到期后自动恢复；不改变启用和健康状态。
+    pub fn park_credential(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        until: DateTime<Utc>,
+    ) -> Result<ProviderCredential, String> {
+        if until <= Utc::now() {
+            return Err(format!(
+                "Park time must be in the future: {}",
+                until.to_rfc3339()
+            ));
         }
+        let cred = self.set_parked_until(db, uuid, Some(until))?;
+        self.note_manual_disable(uuid);
+        tracing::info!(
+            "[POOL] 凭证 {} 暂停到 {}",
+            crate::server_utils::safe_truncate(uuid, 8),
+            until.to_rfc3339()
+        );
+        Ok(cred)
+    }
+
+    /// 立即取消凭证暂停
+    pub fn unpark_credential(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+    ) -> Result<ProviderCredential, String> {
+        self.set_parked_until(db, uuid, None)
+    }
+
+    fn set_parked_until(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<ProviderCredential, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let mut cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {}", uuid))?;
+
+        cred.parked_until = until;
+        cred.updated_at = Utc::now();
+
+        ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
+        Ok(cred)
+    }
+
+    /// 批量重命名、启用/禁用或设置层级，返回实际变化的凭证 UUID
+    ///
+    /// 任一校验失败（UUID 不存在、名称重复）时不修改任何凭证。
+    pub fn bulk_update_credentials(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        update: &BulkCredentialUpdate,
+    ) -> Result<Vec<String>, String> {
+        let pt: PoolProviderType = provider_type.parse().map_err(|e: String| e)?;
+        let mut conn = db.lock().map_err(|e| e.to_string())?;
+        let credentials = ProviderPoolDao::get_by_type(&conn, &pt).map_err(|e| e.to_string())?;
+        let changed = plan_bulk_update(&credentials, update)?;
+
+        let now = Utc::now();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for cred in &changed {
+            let mut cred = cred.clone();
+            cred.updated_at = now;
+            ProviderPoolDao::update(&tx, &cred).map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+
+        for cred in changed.iter().filter(|c| c.is_disabled) {
+            let was_disabled = credentials
+                .iter()
+                .any(|c| c.uuid == cred.uuid && c.is_disabled);
+            if !was_disabled {
+                self.note_manual_disable(&cred.uuid);
+            }
+        }
+
+        Ok(changed.into_iter().map(|c| c.uuid).collect())
+    }
+
+    /// 设置凭证级上游请求头（空映射表示清除）
+    ///
+    /// 请求头名称不区分大小写，值为空的条目会被忽略。
+    pub fn set_credential_upstream_headers(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        headers: HashMap<String, String>,
+    ) -> Result<ProviderCredential, String> {
+        let headers = crate::http_client::normalize_headers(&headers)?;
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let mut cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {}", uuid))?;
+
+        cred.upstream_headers = headers;
+        cred.updated_at = Utc::now();
+
+        ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
+        Ok(cred)
+    }
+
+    /// 设置 Antigravity 凭证缓存的项目 ID（None 或空字符串表示清除）
+    ///
+    /// 清除后下次请求会重新发现项目 ID。
+    pub fn set_antigravity_project_id(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        project_id: Option<String>,
+    ) -> Result<ProviderCredential, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let mut cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {}", uuid))?;
+
+        let CredentialData::AntigravityOAuth {
+            project_id: cached, ..
+        } = &mut cred.credential
+        else {
+            return Err(format!(
+                "Credential is not an Antigravity credential: {}",
+                uuid
+            ));
+        };
+        *cached = project_id
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty());
+        cred.updated_at = Utc::now();
+
+        ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
+        Ok(cred)
     }
 
     /// 获取所有凭证概览
@@ -182,7 +634,10 @@ impl ProviderPoolService {
             cred.name = if n.is_empty() { None } else { Some(n) };
         }
         if let Some(d) = is_disabled {
-            cred.is_disabled = d;
+            if d && !cred.is_disabled {
+                self.note_manual_disable(uuid);
+            }
+            cred.set_disabled(d);
         }
         if let Some(c) = check_health {
             cred.check_health = c;
@@ -207,6 +662,7 @@ impl ProviderPoolService {
     /// 删除凭证
     pub fn delete_credential(&self, db: &DbConnection, uuid: &str) -> Result<bool, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
+        self.rate_limits.remove(uuid);
         ProviderPoolDao::delete(&conn, uuid).map_err(|e| e.to_string())
     }
 
@@ -308,6 +764,16 @@ impl ProviderPoolService {
             });
         }
 
+        // 层级偏好（routing.tier_rules）
+        if let Some(tier) = model.and_then(|m| self.preferred_tier(m)) {
+            available = prefer_tier(available, &tier);
+        }
+
+        // 上游限流：避开尚在重置前或额度接近用尽的凭证，全部受限时不过滤
+        available = self
+            .rate_limits
+            .prefer_unthrottled(available, |c| c.uuid.as_str(), Utc::now());
+
         eprintln!(
             "[SELECT_CREDENTIAL] final available count: {}",
             available.len()
@@ -319,18 +785,66 @@ impl ProviderPoolService {
 
         // 如果只有一个可用凭证，直接返回
         if available.len() == 1 {
-            return Ok(Some(available.into_iter().next().unwrap()));
+            let selected = available.into_iter().next().unwrap();
+            self.switch_log
+                .observe_selection(db, provider_type, &selected);
+            return Ok(Some(selected));
         }
 
-        // 智能选择：基于权重分数选择最优凭证
-        let selected = self.select_best_credential_by_weight(&available);
+        let (strategy, exploration_rate) = self
+            .selection_strategy
+            .read()
+            .map(|s| *s)
+            .unwrap_or_default();
+        let selected = match strategy {
+            CredentialSelectionStrategy::LatencyAware => {
+                self.select_lowest_latency(&available, exploration_rate, rand::random::<f64>())
+            }
+            // 智能选择：基于权重分数选择最优凭证
+            CredentialSelectionStrategy::Weighted => {
+                self.select_best_credential_by_weight(&available)
+            }
+        };
 
+        self.switch_log
+            .observe_selection(db, provider_type, &selected);
         Ok(Some(selected))
     }
 
+    /// 可用于指定模型的凭证数量（健康、未禁用且支持该模型）
+    ///
+    /// 与 `select_credential` 使用相同的候选范围（Anthropic 与 Claude 共享凭证），
+    /// 但不更新轮询状态，供路由模拟使用。
+    pub fn available_credential_count(
+        &self,
+        db: &DbConnection,
+        provider_type: PoolProviderType,
+        model: &str,
+    ) -> Result<usize, String> {
+        let mut types = vec![provider_type];
+        match provider_type {
+            PoolProviderType::Anthropic => types.push(PoolProviderType::Claude),
+            PoolProviderType::Claude => types.push(PoolProviderType::Anthropic),
+            _ => {}
+        }
+
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let mut count = 0;
+        for pt in types {
+            count += ProviderPoolDao::get_by_type(&conn, &pt)
+                .map_err(|e| e.to_string())?
+                .iter()
+                .filter(|c| c.is_available() && c.supports_model(model))
+                .count();
+        }
+        Ok(count)
+    }
+
     /// 带智能降级的凭证选择
     ///
-    /// 当 Provider Pool 无可用凭证时，自动从 API Key Provider 降级查找
+    /// 当 Provider Pool 无可用凭证时，先按 `routing.credential_fallback` 配置的降级链
+    /// 依次查找 API Key 凭证，再按内置映射从 API Key Provider 降级查找。
+    /// 每次降级都会写入日志和切换日志。
     ///
     /// # 参数
     /// - `db`: 数据库连接
@@ -364,9 +878,41 @@ impl ProviderPoolService {
             );
             return Ok(Some(cred));
         }
-        eprintln!("[select_credential_with_fallback] Provider Pool 未找到凭证，尝试智能降级");
+        eprintln!("[select_credential_with_fallback] Provider Pool 未找到凭证，尝试降级");
+
+        // Step 2: 按配置的降级链依次尝试 API Key 凭证（先凭证池，再 API Key Provider）
+        if let Ok(source) = provider_type.parse::<PoolProviderType>() {
+            let chain = self.fallback_chain(source);
+            for (step, target) in chain.iter().enumerate() {
+                let cred = match self.select_credential(db, &target.to_string(), model)? {
+                    Some(cred) => Some(cred),
+                    None => api_key_service.get_fallback_credential(db, target, None)?,
+                };
+                match cred {
+                    Some(cred) => {
+                        tracing::warn!(
+                            "[FALLBACK] {} 无可用凭证，降级到 {} ({}/{}): {}",
+                            source,
+                            target,
+                            step + 1,
+                            chain.len(),
+                            cred.name.as_deref().unwrap_or(&cred.uuid)
+                        );
+                        self.record_fallback(db, &source.to_string(), &cred, model);
+                        return Ok(Some(cred));
+                    }
+                    None => tracing::info!(
+                        "[FALLBACK] {} 降级到 {} 失败 ({}/{}): 无可用凭证",
+                        source,
+                        target,
+                        step + 1,
+                        chain.len()
+                    ),
+                }
+            }
+        }
 
-        // Step 2: 智能降级到 API Key Provider
+        // Step 3: 智能降级到 API Key Provider（内置类型映射）
         let pt: PoolProviderType = provider_type.parse().unwrap_or(PoolProviderType::OpenAI);
         eprintln!(
             "[select_credential_with_fallback] 解析 provider_type '{}' -> {:?}",
@@ -380,10 +926,17 @@ impl ProviderPoolService {
                 "[select_credential_with_fallback] 智能降级成功: {:?}",
                 cred.name
             );
+            tracing::warn!(
+                "[FALLBACK] {} 无可用凭证，智能降级到 {}: {}",
+                provider_type,
+                cred.provider_type,
+                cred.name.as_deref().unwrap_or(&cred.uuid)
+            );
+            self.record_fallback(db, provider_type, &cred, model);
             return Ok(Some(cred));
         }
 
-        // Step 3: 都没有找到
+        // Step 4: 都没有找到
         eprintln!(
             "[select_credential_with_fallback] 未找到任何凭证 for provider_type='{}'",
             provider_type
@@ -391,6 +944,32 @@ impl ProviderPoolService {
         Ok(None)
     }
 
+    /// 选择上游延迟 EMA 最低的凭证
+    ///
+    /// 没有延迟样本的凭证优先，以便尽快测得延迟；`roll` 小于探索概率时随机选择，
+    /// 避免暂时变慢的凭证再也得不到新样本。
+    fn select_lowest_latency(
+        &self,
+        credentials: &[ProviderCredential],
+        exploration_rate: f64,
+        roll: f64,
+    ) -> ProviderCredential {
+        if roll < exploration_rate {
+            let index = ((roll / exploration_rate) * credentials.len() as f64) as usize;
+            return credentials[index.min(credentials.len() - 1)].clone();
+        }
+
+        credentials
+            .iter()
+            .min_by(|a, b| {
+                let a = self.latency_ema(&a.uuid).unwrap_or(0.0);
+                let b = self.latency_ema(&b.uuid).unwrap_or(0.0);
+                a.total_cmp(&b)
+            })
+            .unwrap()
+            .clone()
+    }
+
     /// 基于权重分数选择最优凭证
     fn select_best_credential_by_weight(
         &self,
@@ -421,9 +1000,9 @@ impl ProviderPoolService {
     ) -> f64 {
         let mut score = 0.0;
 
-        // 1. 健康状态权重 (40分)
+        // 1. 健康状态权重 (40分) - 按健康分折算
         if cred.is_healthy {
-            score += 40.0;
+            score += 40.0 * cred.health_score.clamp(0.0, 1.0);
         } else {
             score -= 20.0; // 不健康的凭证严重扣分
         }
@@ -481,17 +1060,30 @@ impl ProviderPoolService {
     }
 
     /// 标记凭证为健康
+    ///
+    /// 健康分按 EMA 上升，且不低于禁用下限，保证凭证立即恢复可用。
     pub fn mark_healthy(
         &self,
         db: &DbConnection,
         uuid: &str,
         check_model: Option<&str>,
     ) -> Result<(), String> {
+        self.switch_log.clear_failure(uuid);
         let conn = db.lock().map_err(|e| e.to_string())?;
+        let cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {}", uuid))?;
+
+        let scoring = self.health_scoring();
+        let health_score = scoring
+            .next_score(cred.health_score, true)
+            .max(scoring.disable_below);
+
         ProviderPoolDao::update_health_status(
             &conn,
             uuid,
             true,
+            health_score,
             0,
             None,
             None,
@@ -501,7 +1093,9 @@ impl ProviderPoolService {
         .map_err(|e| e.to_string())
     }
 
-    /// 标记凭证为不健康
+    /// 记录一次失败
+    ///
+    /// 健康分按 EMA 下降，低于禁用下限时才标记为不健康。
     pub fn mark_unhealthy(
         &self,
         db: &DbConnection,
@@ -514,12 +1108,15 @@ impl ProviderPoolService {
             .ok_or_else(|| format!("Credential not found: {}", uuid))?;
 
         let new_error_count = cred.error_count + 1;
-        let is_healthy = new_error_count < self.max_error_count;
+        let scoring = self.health_scoring();
+        let health_score = scoring.next_score(cred.health_score, false);
+        let is_healthy = health_score >= scoring.disable_below;
 
         ProviderPoolDao::update_health_status(
             &conn,
             uuid,
             is_healthy,
+            health_score,
             new_error_count,
             Some(Utc::now()),
             error_message,
@@ -546,6 +1143,49 @@ impl ProviderPoolService {
         ProviderPoolDao::reset_health_by_type(&conn, &pt).map_err(|e| e.to_string())
     }
 
+    /// 自动禁用连续不健康超过 `health_scoring.auto_disable_after_days` 天的凭证
+    ///
+    /// 只禁用不删除；暂停中或配额耗尽（上游限额降级中、最近错误为配额超限）的凭证不处理。
+    /// 返回本次被禁用的凭证，未开启该策略时返回空列表。
+    pub fn auto_disable_unhealthy(
+        &self,
+        db: &DbConnection,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ProviderCredential>, String> {
+        let Some(threshold) = self.health_scoring().auto_disable_after() else {
+            return Ok(Vec::new());
+        };
+
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+
+        let mut disabled = Vec::new();
+        for mut cred in credentials {
+            if cred.is_disabled || cred.is_healthy || cred.is_parked(now) {
+                continue;
+            }
+            let quota_exhausted = self.rate_limits.is_deprioritized(&cred.uuid, now)
+                || cred.last_error_message.as_deref().is_some_and(|msg| {
+                    crate::credential::QuotaManager::is_quota_exceeded_error(None, msg)
+                });
+            if quota_exhausted {
+                continue;
+            }
+            if !cred
+                .unhealthy_since
+                .is_some_and(|since| now - since >= threshold)
+            {
+                continue;
+            }
+
+            cred.is_disabled = true;
+            cred.updated_at = now;
+            ProviderPoolDao::update(&conn, &cred).map_err(|e| e.to_string())?;
+            disabled.push(cred);
+        }
+        Ok(disabled)
+    }
+
     /// 获取凭证健康状态
     /// Requirements: 3.2
     pub fn get_credential_health(
@@ -617,12 +1257,10 @@ impl ProviderPoolService {
             .ok_or_else(|| format!("Credential not found: {}", uuid))?;
 
         let new_error_count = cred.error_count + 1;
+        let scoring = self.health_scoring();
+        let health_score = scoring.next_score(cred.health_score, false);
         // 如果需要重新授权，直接标记为不健康
-        let is_healthy = if requires_reauth {
-            false
-        } else {
-            new_error_count < self.max_error_count
-        };
+        let is_healthy = !requires_reauth && health_score >= scoring.disable_below;
 
         let error_msg = if requires_reauth {
             format!("[需要重新授权] {}", error_message)
@@ -634,6 +1272,7 @@ impl ProviderPoolService {
             &conn,
             uuid,
             is_healthy,
+            health_score,
             new_error_count,
             Some(Utc::now()),
             Some(&error_msg),
@@ -757,11 +1396,29 @@ impl ProviderPoolService {
 
     /// 执行单个凭证的健康检查
     ///
-    /// 如果遇到 401 错误，会自动尝试刷新 token 后重试
+    /// 如果遇到 401 错误，会自动尝试刷新 token 后重试；检查失败时记入切换原因
     pub async fn check_credential_health(
         &self,
         db: &DbConnection,
         uuid: &str,
+    ) -> Result<HealthCheckResult, String> {
+        let result = self.run_credential_health_check(db, uuid).await?;
+        if !result.success {
+            self.switch_log.note_failure(
+                uuid,
+                SwitchTrigger::Health,
+                None,
+                result.model.as_deref(),
+                result.message.as_deref(),
+            );
+        }
+        Ok(result)
+    }
+
+    async fn run_credential_health_check(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
     ) -> Result<HealthCheckResult, String> {
         let cred = {
             let conn = db.lock().map_err(|e| e.to_string())?;
@@ -1051,7 +1708,7 @@ impl ProviderPoolService {
         tracing::debug!("[KIRO HEALTH] 请求体已构建");
 
         let response = self
-            .client
+            .client()
             .post(&health_check_url)
             .bearer_auth(access_token)
             .header("Content-Type", "application/json")
@@ -1114,7 +1771,7 @@ impl ProviderPoolService {
         });
 
         let response = self
-            .client
+            .client()
             .post(url)
             .bearer_auth(access_token)
             .header("Content-Type", "application/json")
@@ -1164,7 +1821,7 @@ impl ProviderPoolService {
         let url = format!("{}/chat/completions", base_url);
 
         let response = self
-            .client
+            .client()
             .post(&url)
             .bearer_auth(access_token)
             .header("Content-Type", "application/json")
@@ -1203,7 +1860,7 @@ impl ProviderPoolService {
             "https://daily-cloudcode-pa.sandbox.googleapis.com/v1internal:fetchAvailableModels";
 
         let response = self
-            .client
+            .client()
             .post(url)
             .bearer_auth(access_token)
             .header("User-Agent", "antigravity/1.11.5 windows/amd64")
@@ -1249,7 +1906,7 @@ impl ProviderPoolService {
         tracing::debug!("[HEALTH_CHECK] OpenAI API URL: {}, model: {}", url, model);
 
         let response = self
-            .client
+            .client()
             .post(&url)
             .bearer_auth(api_key)
             .json(&request_body)
@@ -1300,11 +1957,8 @@ impl ProviderPoolService {
 
         tracing::debug!("[HEALTH_CHECK] Claude API URL: {}, model: {}", url, model);
 
-        let response = self
-            .client
-            .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
+        let response = crate::http_client::anthropic_headers()
+            .apply(self.client().post(&url).header("x-api-key", api_key))
             .json(&request_body)
             .timeout(self.health_check_timeout)
             .send()
@@ -1340,7 +1994,7 @@ impl ProviderPoolService {
         });
 
         let response = self
-            .client
+            .client()
             .post(&url)
             .header("x-goog-api-key", api_key)
             .json(&request_body)
@@ -1372,7 +2026,7 @@ impl ProviderPoolService {
         });
 
         let response = self
-            .client
+            .client()
             .post(&url)
             .header("x-goog-api-key", api_key)
             .json(&request_body)
@@ -1448,7 +2102,7 @@ impl ProviderPoolService {
                 );
 
                 let response = self
-                    .client
+                    .client()
                     .post(&url)
                     .bearer_auth(&token)
                     .header("Content-Type", "application/json")
@@ -1509,11 +2163,10 @@ impl ProviderPoolService {
             "max_tokens": 10
         });
 
-        let response = self
-            .client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", token))
-            .header("anthropic-version", "2023-06-01")
+        let mut headers = crate::http_client::anthropic_headers();
+        headers.add_beta(CLAUDE_OAUTH_BETA);
+        let response = headers
+            .apply(self.client().post(url).bearer_auth(&token))
             .json(&request_body)
             .timeout(self.health_check_timeout)
             .send()
@@ -1551,7 +2204,7 @@ impl ProviderPoolService {
         });
 
         let response = self
-            .client
+            .client()
             .post(url)
             .header("Authorization", format!("Bearer {}", token))
             .json(&request_body)
@@ -1592,7 +2245,7 @@ impl ProviderPoolService {
         });
 
         let response = self
-            .client
+            .client()
             .post(url)
             .header("Authorization", format!("Bearer {}", api_key))
             .json(&request_body)
@@ -1640,16 +2293,17 @@ impl ProviderPoolService {
 
         let mut routes = Vec::new();
 
-        // 为每种 Provider 类型创建路由
+        // 为每种 Provider 类型创建路由（包含暂时不健康的凭证，由健康数量区分）
         for (provider_type, credentials) in &grouped {
-            let available: Vec<_> = credentials.iter().filter(|c| c.is_available()).collect();
-            if available.is_empty() {
+            let enabled: Vec<_> = credentials.iter().filter(|c| !c.is_disabled).collect();
+            if enabled.is_empty() {
                 continue;
             }
 
             // Provider 类型路由 (轮询)
             let mut route = RouteInfo::new(provider_type.to_string(), provider_type.to_string());
-            route.credential_count = available.len();
+            route.credential_count = enabled.len();
+            route.healthy_credential_count = enabled.iter().filter(|c| c.is_available()).count();
             route.add_endpoint(base_url, "claude");
             route.add_endpoint(base_url, "openai");
             route.tags.push("轮询".to_string());
@@ -1660,10 +2314,11 @@ impl ProviderPoolService {
         for credentials in grouped.values() {
             for cred in credentials {
                 if let Some(name) = &cred.name {
-                    if cred.is_available() {
+                    if !cred.is_disabled {
                         let mut route =
                             RouteInfo::new(name.clone(), cred.provider_type.to_string());
                         route.credential_count = 1;
+                        route.healthy_credential_count = usize::from(cred.is_available());
                         route.enabled = !cred.is_disabled;
                         route.add_endpoint(base_url, "claude");
                         route.add_endpoint(base_url, "openai");
@@ -2091,12 +2746,88 @@ pub struct MigrationResult {
     pub errors: Vec<String>,
 }
 
+/// 按层级偏好筛选凭证（软偏好）
+///
+/// 存在指定层级的凭证时只保留这些凭证，否则原样返回。
+fn prefer_tier(credentials: Vec<ProviderCredential>, tier: &str) -> Vec<ProviderCredential> {
+    if !credentials.iter().any(|c| c.tier.as_deref() == Some(tier)) {
+        eprintln!(
+            "[SELECT_CREDENTIAL] 没有层级为 '{}' 的可用凭证，回退到全部可用凭证",
+            tier
+        );
+        return credentials;
+    }
+    credentials
+        .into_iter()
+        .filter(|c| c.tier.as_deref() == Some(tier))
+        .collect()
+}
+
 // ==================== 测试模块 ====================
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn kiro_credential(name: &str) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: format!("/tmp/{}.json", name),
+            },
+        );
+        cred.name = Some(name.to_string());
+        cred
+    }
+
+    #[test]
+    fn test_plan_bulk_update_rename_and_uniqueness() {
+        let creds = vec![
+            kiro_credential("a"),
+            kiro_credential("b"),
+            kiro_credential("c"),
+        ];
+
+        let update = BulkCredentialUpdate {
+            rename_pattern: Some("kiro-{n}".to_string()),
+            tier: Some("premium".to_string()),
+            ..Default::default()
+        };
+        let changed = plan_bulk_update(&creds, &update).unwrap();
+        let names: Vec<_> = changed.iter().filter_map(|c| c.name.as_deref()).collect();
+        assert_eq!(names, vec!["kiro-1", "kiro-2", "kiro-3"]);
+        assert!(changed.iter().all(|c| c.tier.as_deref() == Some("premium")));
+
+        // 只重命名部分凭证时与其余凭证重名
+        let update = BulkCredentialUpdate {
+            uuids: vec![creds[0].uuid.clone()],
+            rename_pattern: Some("b".to_string()),
+            ..Default::default()
+        };
+        let err = plan_bulk_update(&creds, &update).unwrap_err();
+        assert!(err.contains("Duplicate credential name: b"));
+
+        // 无 {n} 的模板作用于多个凭证
+        let update = BulkCredentialUpdate {
+            rename_pattern: Some("same".to_string()),
+            ..Default::default()
+        };
+        assert!(plan_bulk_update(&creds, &update).is_err());
+
+        // 未变化的凭证不返回
+        let update = BulkCredentialUpdate {
+            is_disabled: Some(false),
+            ..Default::default()
+        };
+        assert!(plan_bulk_update(&creds, &update).unwrap().is_empty());
+
+        let update = BulkCredentialUpdate {
+            uuids: vec!["missing".to_string()],
+            ..Default::default()
+        };
+        assert!(plan_bulk_update(&creds, &update).is_err());
+    }
+
     // ==================== Property 3: 不健康凭证排除 ====================
     // Feature: antigravity-token-refresh, Property 3: 不健康凭证排除
     // Validates: Requirements 2.4, 3.3
@@ -2226,4 +2957,496 @@ mod tests {
         assert_eq!(deserialized.uuid, info.uuid);
         assert_eq!(deserialized.is_healthy, info.is_healthy);
     }
+
+    fn tiered(tier: Option<&str>) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::GeminiApiKey,
+            CredentialData::GeminiApiKey {
+                api_key: "key".to_string(),
+                base_url: None,
+                excluded_models: Vec::new(),
+            },
+        );
+        cred.tier = tier.map(|t| t.to_string());
+        cred
+    }
+
+    #[test]
+    fn test_preferred_tier_first_matching_rule_wins() {
+        let service = ProviderPoolService::new();
+        service.set_tier_rules(vec![
+            TierRule {
+                pattern: "gemini-*-pro".to_string(),
+                tier: "paid".to_string(),
+            },
+            TierRule {
+                pattern: "*-flash".to_string(),
+                tier: "free".to_string(),
+            },
+        ]);
+
+        assert_eq!(
+            service.preferred_tier("gemini-2.5-pro").as_deref(),
+            Some("paid")
+        );
+        assert_eq!(
+            service.preferred_tier("gemini-2.5-flash").as_deref(),
+            Some("free")
+        );
+        assert!(service.preferred_tier("claude-sonnet-4-5").is_none());
+    }
+
+    #[test]
+    fn test_prefer_tier_falls_back_when_no_match() {
+        let creds = vec![tiered(Some("free")), tiered(None), tiered(Some("paid"))];
+
+        let paid = prefer_tier(creds.clone(), "paid");
+        assert_eq!(paid.len(), 1);
+        assert_eq!(paid[0].tier.as_deref(), Some("paid"));
+
+        // 没有该层级的凭证时保留全部可用凭证
+        assert_eq!(prefer_tier(creds, "enterprise").len(), 3);
+    }
+
+    #[test]
+    fn test_select_credential_prefers_tier_with_fallback() {
+        let service = ProviderPoolService::new();
+        service.set_tier_rules(vec![TierRule {
+            pattern: "gemini-*-pro".to_string(),
+            tier: "paid".to_string(),
+        }]);
+        let free = tiered(Some("free"));
+        let mut paid = tiered(Some("paid"));
+        let db = test_db_with(&free);
+        ProviderPoolDao::insert(&db.lock().unwrap(), &paid).unwrap();
+
+        let select = |model: &str| {
+            service
+                .select_credential(&db, "gemini_api_key", Some(model))
+                .unwrap()
+                .unwrap()
+                .uuid
+        };
+        assert_eq!(select("gemini-2.5-pro"), paid.uuid);
+
+        // 偏好层级的凭证不可用时回退到其他可用凭证
+        paid.is_healthy = false;
+        ProviderPoolDao::update(&db.lock().unwrap(), &paid).unwrap();
+        assert_eq!(select("gemini-2.5-pro"), free.uuid);
+
+        // 规则热更新后立即生效
+        service.set_tier_rules(vec![TierRule {
+            pattern: "*".to_string(),
+            tier: "free".to_string(),
+        }]);
+        assert_eq!(select("gemini-2.5-flash"), free.uuid);
+    }
+
+    #[test]
+    fn test_set_credential_tier_normalizes_and_persists() {
+        let service = ProviderPoolService::new();
+        let cred = tiered(None);
+        let db = test_db_with(&cred);
+
+        let updated = service
+            .set_credential_tier(&db, &cred.uuid, Some("  paid ".to_string()))
+            .unwrap();
+        assert_eq!(updated.tier.as_deref(), Some("paid"));
+        assert_eq!(stored(&db, &cred.uuid).tier.as_deref(), Some("paid"));
+
+        // 空字符串清除层级
+        service
+            .set_credential_tier(&db, &cred.uuid, Some("   ".to_string()))
+            .unwrap();
+        assert_eq!(stored(&db, &cred.uuid).tier, None);
+
+        assert!(service
+            .set_credential_tier(&db, "missing", Some("paid".to_string()))
+            .is_err());
+    }
+
+    fn test_db_with(cred: &ProviderCredential) -> DbConnection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        ProviderPoolDao::insert(&conn, cred).unwrap();
+        std::sync::Arc::new(std::sync::Mutex::new(conn))
+    }
+
+    fn stored(db: &DbConnection, uuid: &str) -> ProviderCredential {
+        let conn = db.lock().unwrap();
+        ProviderPoolDao::get_by_uuid(&conn, uuid).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_available_routes_count_healthy_credentials() {
+        let service = ProviderPoolService::new();
+        let healthy = kiro_credential("healthy");
+        let mut unhealthy = kiro_credential("unhealthy");
+        unhealthy.is_healthy = false;
+        let mut disabled = kiro_credential("disabled");
+        disabled.is_disabled = true;
+
+        let db = test_db_with(&healthy);
+        for cred in [&unhealthy, &disabled] {
+            ProviderPoolDao::insert(&db.lock().unwrap(), cred).unwrap();
+        }
+
+        let routes = service
+            .get_available_routes(&db, "http://127.0.0.1:8999")
+            .unwrap();
+        let counts = |selector: &str| {
+            routes
+                .iter()
+                .find(|r| r.selector == selector)
+                .map(|r| (r.credential_count, r.healthy_credential_count))
+        };
+        assert_eq!(counts("kiro"), Some((2, 1)));
+        assert_eq!(counts("healthy"), Some((1, 1)));
+        assert_eq!(counts("unhealthy"), Some((1, 0)));
+        assert_eq!(counts("disabled"), None);
+    }
+
+    #[test]
+    fn test_health_score_decays_and_disables_below_floor() {
+        let service = ProviderPoolService::new();
+        service.set_health_scoring(HealthScoringSettings {
+            ema_alpha: 0.5,
+            disable_below: 0.2,
+            auto_disable_after_days: None,
+        });
+        let cred = tiered(None);
+        let db = test_db_with(&cred);
+
+        // 1.0 -> 0.5 -> 0.25，仍高于下限
+        service
+            .mark_unhealthy(&db, &cred.uuid, Some("timeout"))
+            .unwrap();
+        service
+            .mark_unhealthy(&db, &cred.uuid, Some("timeout"))
+            .unwrap();
+        let current = stored(&db, &cred.uuid);
+        assert!((current.health_score - 0.25).abs() < 1e-9);
+        assert!(current.is_healthy);
+        assert_eq!(current.error_count, 2);
+
+        // 0.125 低于下限，标记为不健康
+        service
+            .mark_unhealthy(&db, &cred.uuid, Some("timeout"))
+            .unwrap();
+        assert!(!stored(&db, &cred.uuid).is_healthy);
+
+        // 成功后恢复可用，分数不低于下限
+        service.mark_healthy(&db, &cred.uuid, None).unwrap();
+        let recovered = stored(&db, &cred.uuid);
+        assert!(recovered.is_healthy);
+        assert_eq!(recovered.error_count, 0);
+        assert!(recovered.health_score >= 0.2 && recovered.health_score < 1.0);
+
+        // 重置计数器同时重置健康分
+        service.reset_counters(&db, &cred.uuid).unwrap();
+        assert_eq!(stored(&db, &cred.uuid).health_score, 1.0);
+    }
+
+    #[test]
+    fn test_credential_fallback_chain_records_switch() {
+        let service = ProviderPoolService::new();
+        let api_key_service = ApiKeyProviderService::new();
+        let mut oauth = ProviderCredential::new(
+            PoolProviderType::ClaudeOAuth,
+            CredentialData::ClaudeOAuth {
+                creds_file_path: "/tmp/claude-oauth.json".to_string(),
+            },
+        );
+        oauth.is_healthy = false;
+        let key = ProviderCredential::new(
+            PoolProviderType::Claude,
+            CredentialData::ClaudeKey {
+                api_key: "sk-ant-test".to_string(),
+                base_url: None,
+            },
+        );
+        let db = test_db_with(&oauth);
+        ProviderPoolDao::insert(&db.lock().unwrap(), &key).unwrap();
+
+        // 未配置降级链时，Claude OAuth 没有内置降级目标
+        let selected = service
+            .select_credential_with_fallback(&db, &api_key_service, "claude_oauth", None, None)
+            .unwrap();
+        assert!(selected.is_none());
+
+        service.set_credential_fallback(&HashMap::from([(
+            "claude_oauth".to_string(),
+            vec!["kiro".to_string(), "anthropic".to_string()],
+        )]));
+        let selected = service
+            .select_credential_with_fallback(&db, &api_key_service, "claude_oauth", None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(selected.uuid, key.uuid);
+
+        let log = SwitchLog::list(&db, &Default::default()).unwrap();
+        assert_eq!(log.total, 1);
+        assert_eq!(log.entries[0].from_provider, "claude_oauth");
+        assert_eq!(log.entries[0].to_provider, "anthropic");
+        assert_eq!(
+            log.entries[0].to_credential.as_deref(),
+            Some(key.uuid.as_str())
+        );
+        assert_eq!(
+            log.entries[0].trigger,
+            SwitchTrigger::CredentialsUnavailable
+        );
+    }
+
+    #[test]
+    fn test_switch_log_attributes_rate_limit_to_next_selection() {
+        let service = ProviderPoolService::new();
+        let first = kiro_credential("first");
+        let second = kiro_credential("second");
+        let db = test_db_with(&first);
+
+        let selected = service
+            .select_credential(&db, "kiro", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(selected.uuid, first.uuid);
+        service.note_upstream_failure(&first.uuid, 429, Some("req-1"), Some("claude-sonnet-4"));
+
+        // 首个凭证不可用后选出第二个凭证，写入一条限流切换记录
+        {
+            let conn = db.lock().unwrap();
+            let mut disabled = first.clone();
+            disabled.is_disabled = true;
+            ProviderPoolDao::update(&conn, &disabled).unwrap();
+            ProviderPoolDao::insert(&conn, &second).unwrap();
+        }
+        let selected = service
+            .select_credential(&db, "kiro", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(selected.uuid, second.uuid);
+
+        let log = SwitchLog::list(&db, &Default::default()).unwrap();
+        assert_eq!(log.total, 1);
+        let entry = &log.entries[0];
+        assert_eq!(entry.trigger, SwitchTrigger::RateLimit);
+        assert_eq!(entry.from_credential.as_deref(), Some(first.uuid.as_str()));
+        assert_eq!(entry.to_credential.as_deref(), Some(second.uuid.as_str()));
+        assert_eq!(entry.request_id.as_deref(), Some("req-1"));
+        assert_eq!(entry.model.as_deref(), Some("claude-sonnet-4"));
+
+        // 没有失败原因的轮换不记录
+        service.select_credential(&db, "kiro", None).unwrap();
+        assert_eq!(SwitchLog::list(&db, &Default::default()).unwrap().total, 1);
+    }
+
+    #[test]
+    fn test_parked_credential_skipped_until_expiry() {
+        let service = ProviderPoolService::new();
+        let first = kiro_credential("first");
+        let second = kiro_credential("second");
+        let db = test_db_with(&first);
+        ProviderPoolDao::insert(&db.lock().unwrap(), &second).unwrap();
+
+        assert!(service
+            .park_credential(&db, &first.uuid, Utc::now() - chrono::Duration::minutes(1))
+            .is_err());
+
+        let parked = service
+            .park_credential(&db, &first.uuid, Utc::now() + chrono::Duration::hours(1))
+            .unwrap();
+        assert!(parked.is_parked(Utc::now()));
+        assert!(!parked.is_disabled && parked.is_healthy);
+        for _ in 0..3 {
+            let selected = service
+                .select_credential(&db, "kiro", None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(selected.uuid, second.uuid);
+        }
+
+        let overview = service.get_overview(&db).unwrap();
+        assert_eq!(overview[0].stats.parked_count, 1);
+        assert_eq!(overview[0].stats.disabled_count, 0);
+        let display = overview[0]
+            .credentials
+            .iter()
+            .find(|c| c.uuid == first.uuid)
+            .unwrap();
+        assert!(display.is_parked);
+        assert!(display.parked_until.is_some());
+
+        // 暂停到期后自动恢复
+        {
+            let conn = db.lock().unwrap();
+            let mut expired = ProviderPoolDao::get_by_uuid(&conn, &first.uuid)
+                .unwrap()
+                .unwrap();
+            expired.parked_until = Some(Utc::now() - chrono::Duration::seconds(1));
+            ProviderPoolDao::update(&conn, &expired).unwrap();
+        }
+        assert!(stored(&db, &first.uuid).is_available());
+
+        service
+            .park_credential(&db, &first.uuid, Utc::now() + chrono::Duration::hours(1))
+            .unwrap();
+        let unparked = service.unpark_credential(&db, &first.uuid).unwrap();
+        assert!(unparked.parked_until.is_none());
+        assert!(stored(&db, &first.uuid).is_available());
+    }
+
+    #[test]
+    fn test_auto_disable_long_unhealthy_credentials() {
+        let service = ProviderPoolService::new();
+        let now = Utc::now();
+        let unhealthy = |name: &str, days: i64| {
+            let mut cred = kiro_credential(name);
+            cred.is_healthy = false;
+            cred.unhealthy_since = Some(now - chrono::Duration::days(days));
+            cred.last_error_message = Some("connection reset".to_string());
+            cred
+        };
+        let stale = unhealthy("stale", 4);
+        let recent = unhealthy("recent", 1);
+        let mut parked = unhealthy("parked", 4);
+        parked.parked_until = Some(now + chrono::Duration::hours(1));
+        let mut quota = unhealthy("quota", 4);
+        quota.last_error_message = Some("Monthly quota exceeded".to_string());
+        let healthy = kiro_credential("healthy");
+
+        let db = test_db_with(&stale);
+        for cred in [&recent, &parked, &quota, &healthy] {
+            ProviderPoolDao::insert(&db.lock().unwrap(), cred).unwrap();
+        }
+
+        // 未开启时不处理
+        assert!(service.auto_disable_unhealthy(&db, now).unwrap().is_empty());
+
+        service.set_health_scoring(HealthScoringSettings {
+            auto_disable_after_days: Some(3),
+            ..Default::default()
+        });
+        let disabled = service.auto_disable_unhealthy(&db, now).unwrap();
+        assert_eq!(disabled.len(), 1);
+        assert_eq!(disabled[0].uuid, stale.uuid);
+        // 只禁用不删除
+        assert!(stored(&db, &stale.uuid).is_disabled);
+        for cred in [&recent, &parked, &quota, &healthy] {
+            assert!(!stored(&db, &cred.uuid).is_disabled);
+        }
+        assert!(service.auto_disable_unhealthy(&db, now).unwrap().is_empty());
+
+        // 手动重新启用后重新计时，不会在下一轮再次被禁用
+        service
+            .update_credential(&db, &stale.uuid, None, Some(false), None, None, None, None)
+            .unwrap();
+        let reenabled = stored(&db, &stale.uuid);
+        assert!(!reenabled.is_disabled && !reenabled.is_healthy);
+        assert!(reenabled.unhealthy_since.unwrap() >= now - chrono::Duration::seconds(1));
+        assert!(service.auto_disable_unhealthy(&db, now).unwrap().is_empty());
+
+        // 恢复健康后清除开始时间，再次不健康时重新计时
+        service.mark_healthy(&db, &recent.uuid, None).unwrap();
+        assert!(stored(&db, &recent.uuid).unhealthy_since.is_none());
+        while stored(&db, &recent.uuid).is_healthy {
+            service
+                .mark_unhealthy(&db, &recent.uuid, Some("boom"))
+                .unwrap();
+        }
+        let since = stored(&db, &recent.uuid).unhealthy_since.unwrap();
+        assert!(since >= now - chrono::Duration::seconds(1));
+        service
+            .mark_unhealthy(&db, &recent.uuid, Some("boom"))
+            .unwrap();
+        assert_eq!(stored(&db, &recent.uuid).unhealthy_since, Some(since));
+    }
+
+    #[test]
+    fn test_set_antigravity_project_id() {
+        let service = ProviderPoolService::new();
+        let cred = ProviderCredential::new(
+            PoolProviderType::Antigravity,
+            CredentialData::AntigravityOAuth {
+                creds_file_path: "/tmp/antigravity.json".to_string(),
+                project_id: None,
+            },
+        );
+        let db = test_db_with(&cred);
+        let project_id = |db: &DbConnection| match stored(db, &cred.uuid).credential {
+            CredentialData::AntigravityOAuth { project_id, .. } => project_id,
+            _ => unreachable!(),
+        };
+
+        service
+            .set_antigravity_project_id(&db, &cred.uuid, Some(" bright-wave-1a2b3 ".to_string()))
+            .unwrap();
+        assert_eq!(project_id(&db).as_deref(), Some("bright-wave-1a2b3"));
+
+        // 空字符串等同于清除
+        service
+            .set_antigravity_project_id(&db, &cred.uuid, Some(String::new()))
+            .unwrap();
+        assert_eq!(project_id(&db), None);
+
+        // 非 Antigravity 凭证报错
+        let other = tiered(None);
+        let db = test_db_with(&other);
+        assert!(service
+            .set_antigravity_project_id(&db, &other.uuid, Some("p".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_credential_score_prefers_higher_health_score() {
+        let service = ProviderPoolService::new();
+        let strong = tiered(None);
+        let mut weak = tiered(None);
+        weak.health_score = 0.4;
+        let all = vec![strong.clone(), weak.clone()];
+        let now = Utc::now();
+
+        assert!(
+            service.calculate_credential_score(&strong, now, &all)
+                > service.calculate_credential_score(&weak, now, &all)
+        );
+    }
+
+    #[test]
+    fn test_latency_ema_updates() {
+        let service = ProviderPoolService::new();
+        assert_eq!(service.latency_ema("a"), None);
+
+        service.record_latency("a", 1000);
+        assert_eq!(service.latency_ema("a"), Some(1000.0));
+        service.record_latency("a", 0);
+        assert!((service.latency_ema("a").unwrap() - 700.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_select_lowest_latency_with_exploration() {
+        let service = ProviderPoolService::new();
+        let fast = tiered(None);
+        let slow = tiered(None);
+        let unmeasured = tiered(None);
+        service.record_latency(&fast.uuid, 200);
+        service.record_latency(&slow.uuid, 2000);
+
+        let creds = vec![slow.clone(), fast.clone()];
+        assert_eq!(
+            service.select_lowest_latency(&creds, 0.1, 0.5).uuid,
+            fast.uuid
+        );
+        // 命中探索概率时按 roll 随机选择，慢凭证也有机会获得新样本
+        assert_eq!(
+            service.select_lowest_latency(&creds, 0.1, 0.01).uuid,
+            slow.uuid
+        );
+
+        // 没有延迟样本的凭证优先
+        let creds = vec![fast, unmeasured.clone()];
+        assert_eq!(
+            service.select_lowest_latency(&creds, 0.0, 0.5).uuid,
+            unmeasured.uuid
+        );
+    }
 }
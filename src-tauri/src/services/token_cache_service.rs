@@ -7,11 +7,14 @@
 #![allow(dead_code)]
 //! - 按需刷新即将过期的 Token
 //! - 处理 401/403 错误时的强制刷新
+//! - 协调并发刷新：同一凭证的并发刷新合并为一次，每个 Provider 限制同时刷新数并加随机抖动，
+//!   避免批量导入的凭证同时过期时集中请求认证端点
+//! - 统计每个凭证的缓存命中/未命中次数，支持手动清除缓存以便排查认证问题
 
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{
-    CachedTokenInfo, CredentialData, PoolProviderType, ProviderCredential,
+    CachedTokenInfo, CredentialData, PoolProviderType, ProviderCredential, TokenCacheStatus,
 };
 use crate::providers::gemini::GeminiProvider;
 use crate::providers::kiro::KiroProvider;
@@ -19,8 +22,16 @@ use crate::providers::qwen::QwenProvider;
 use crate::services::kiro_event_service::KiroEventService;
 use chrono::Utc;
 use dashmap::DashMap;
+use serde::Serialize;
+use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Mutex, OnceCell, OwnedSemaphorePermit, Semaphore};
+
+/// 每个 Provider 同时进行的 Token 刷新数上限
+const MAX_CONCURRENT_REFRESHES_PER_PROVIDER: usize = 2;
+/// 非强制刷新前的最大随机等待时间
+const MAX_REFRESH_JITTER: Duration = Duration::from_secs(3);
 
 /// Token 刷新错误类型
 #[derive(Debug, Clone, PartialEq)]
@@ -54,10 +65,103 @@ pub struct RefreshErrorClassification {
     pub should_disable_credential: bool,
 }
 
+/// Token 刷新协调器
+///
+/// - 同一 key 的并发调用只执行一次刷新，其余调用方等待并复用结果
+/// - 每个 Provider 通过信号量限制同时刷新数，获取许可前可随机等待以错开请求
+pub struct RefreshCoordinator {
+    /// 进行中的刷新
+    in_flight: DashMap<String, Arc<OnceCell<Result<String, String>>>>,
+    /// 每个 Provider 的刷新许可
+    provider_permits: DashMap<PoolProviderType, Arc<Semaphore>>,
+    max_concurrent_per_provider: usize,
+    max_jitter: Duration,
+}
+
+impl Default for RefreshCoordinator {
+    fn default() -> Self {
+        Self::new(MAX_CONCURRENT_REFRESHES_PER_PROVIDER, MAX_REFRESH_JITTER)
+    }
+}
+
+impl RefreshCoordinator {
+    pub fn new(max_concurrent_per_provider: usize, max_jitter: Duration) -> Self {
+        Self {
+            in_flight: DashMap::new(),
+            provider_permits: DashMap::new(),
+            max_concurrent_per_provider: max_concurrent_per_provider.max(1),
+            max_jitter,
+        }
+    }
+
+    /// 执行刷新；同一 key 已有刷新在进行时等待并返回其结果
+    pub async fn run<F, Fut>(&self, key: &str, refresh: F) -> Result<String, String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String, String>>,
+    {
+        let cell = self.in_flight.entry(key.to_string()).or_default().clone();
+        let result = cell.get_or_init(refresh).await.clone();
+        // 结果只在本轮等待者之间共享，之后的调用重新检查缓存
+        self.in_flight
+            .remove_if(key, |_, current| Arc::ptr_eq(current, &cell));
+        result
+    }
+
+    /// 获取 Provider 的刷新许可，`jitter` 为 true 时先随机等待
+    pub async fn acquire(&self, provider: PoolProviderType, jitter: bool) -> OwnedSemaphorePermit {
+        if jitter && !self.max_jitter.is_zero() {
+            let max_ms = self.max_jitter.as_millis() as u64;
+            let delay_ms = rand::random::<u64>() % max_ms;
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        }
+        let semaphore = self
+            .provider_permits
+            .entry(provider)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_concurrent_per_provider)))
+            .clone();
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("刷新信号量不会被关闭")
+    }
+
+    /// 正在进行的刷新数
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight.len()
+    }
+}
+
+/// 单个凭证的缓存命中计数（进程内，重启后清零）
+#[derive(Debug, Default, Clone, Copy)]
+struct CacheCounters {
+    hits: u64,
+    misses: u64,
+}
+
+/// 凭证的 Token 缓存诊断信息（不包含 Token 本身）
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialTokenCacheStatus {
+    pub uuid: String,
+    pub name: Option<String>,
+    pub provider_type: String,
+    /// 数据库中的缓存状态
+    #[serde(flatten)]
+    pub cache: TokenCacheStatus,
+    /// 缓存命中次数（直接使用缓存 Token）
+    pub hits: u64,
+    /// 缓存未命中次数（无缓存、已过期或即将过期而触发刷新）
+    pub misses: u64,
+}
+
 /// Token 缓存服务
 pub struct TokenCacheService {
     /// 每凭证一把锁，防止并发刷新
     locks: DashMap<String, Arc<Mutex<()>>>,
+    /// 全局刷新协调器
+    coordinator: RefreshCoordinator,
+    /// 每凭证的缓存命中计数
+    counters: DashMap<String, CacheCounters>,
 }
 
 impl Default for TokenCacheService {
@@ -70,6 +174,18 @@ impl TokenCacheService {
     pub fn new() -> Self {
         Self {
             locks: DashMap::new(),
+            coordinator: RefreshCoordinator::default(),
+            counters: DashMap::new(),
+        }
+    }
+
+    /// 记录一次缓存查询结果
+    fn record_lookup(&self, uuid: &str, hit: bool) {
+        let mut counters = self.counters.entry(uuid.to_string()).or_default();
+        if hit {
+            counters.hits += 1;
+        } else {
+            counters.misses += 1;
         }
     }
 
@@ -95,12 +211,14 @@ impl TokenCacheService {
                         &uuid[..8],
                         cache.expiry_time
                     );
+                    self.record_lookup(uuid, true);
                     return Ok(token.clone());
                 }
             }
         }
 
         // 需要刷新（无缓存、已过期或即将过期）
+        self.record_lookup(uuid, false);
         match self.refresh_and_cache(db, uuid, false).await {
             Ok(token) => Ok(token),
             Err(refresh_error) => {
@@ -220,7 +338,8 @@ impl TokenCacheService {
     /// - force: 是否强制刷新（忽略缓存状态）
     /// - kiro_event_service: 可选的事件服务，用于发送 Kiro 凭证刷新事件
     ///
-    /// 优化说明：添加了随机延迟机制，避免多个凭证同时刷新造成请求过于集中
+    /// 同一凭证的并发调用共享一次刷新结果；OAuth 凭证刷新前获取 Provider 刷新许可，
+    /// 非强制刷新还会随机等待，避免多个凭证同时刷新造成请求过于集中
     pub async fn refresh_and_cache_with_events(
         &self,
         db: &DbConnection,
@@ -228,20 +347,22 @@ impl TokenCacheService {
         force: bool,
         kiro_event_service: Option<Arc<KiroEventService>>,
     ) -> Result<String, String> {
-        // 添加随机延迟，避免多个凭证同时刷新
-        // 基于凭证UUID生成0-30秒的随机延迟，确保同一凭证的延迟时间一致但不同凭证间分散
-        if !force {
-            let delay_ms = self.calculate_refresh_delay(uuid);
-            if delay_ms > 0 {
-                tracing::debug!(
-                    "[TOKEN_CACHE] Adding {}ms delay before refreshing token for {}",
-                    delay_ms,
-                    &uuid[..8]
-                );
-                tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-            }
-        }
+        let key = format!("{}:{}", uuid, if force { "force" } else { "auto" });
+        self.coordinator
+            .run(&key, || {
+                self.refresh_locked(db, uuid, force, kiro_event_service)
+            })
+            .await
+    }
 
+    /// 持有凭证锁执行刷新
+    async fn refresh_locked(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        force: bool,
+        kiro_event_service: Option<Arc<KiroEventService>>,
+    ) -> Result<String, String> {
         // 获取该凭证的锁
         let lock = self
             .locks
@@ -294,6 +415,17 @@ impl TokenCacheService {
             }
         }
 
+        // OAuth 凭证需要请求认证端点，按 Provider 限制并发
+        let _permit = if credential.provider_type.is_oauth() {
+            Some(
+                self.coordinator
+                    .acquire(credential.provider_type, !force)
+                    .await,
+            )
+        } else {
+            None
+        };
+
         // 执行刷新
         match self.do_refresh(&credential).await {
             Ok(token_info) => {
@@ -1103,23 +1235,6 @@ impl TokenCacheService {
         ProviderPoolDao::get_token_cache(&conn, uuid).map_err(|e| e.to_string())
     }
 
-    /// 计算刷新延迟时间（毫秒）
-    ///
-    /// 基于凭证UUID生成确定性但分散的延迟时间，避免多个凭证同时刷新
-    /// 延迟范围：0-30秒，确保同一凭证每次的延迟一致
-    fn calculate_refresh_delay(&self, uuid: &str) -> u64 {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        // 使用凭证UUID作为种子生成确定性的延迟
-        let mut hasher = DefaultHasher::new();
-        uuid.hash(&mut hasher);
-        let hash_value = hasher.finish();
-
-        // 生成0-30秒的延迟（转换为毫秒）
-        (hash_value % 30000) as u64
-    }
-
     /// 智能错误分类方法
     ///
     /// 基于错误信息智能识别错误类型，提供针对性的处理建议
@@ -1270,6 +1385,7 @@ impl TokenCacheService {
                         &uuid[..8],
                         cache.expiry_time
                     );
+                    self.record_lookup(uuid, true);
                     return Ok(token.clone());
                 }
             }
@@ -1285,6 +1401,158 @@ impl TokenCacheService {
         }
 
         // 需要刷新（无缓存、已过期或即将过期）
+        self.record_lookup(uuid, false);
         self.refresh_and_cache(db, uuid, false).await
     }
+
+    /// 获取所有 OAuth 凭证（以及有缓存的凭证）的 Token 缓存状态
+    pub fn list_cache_status(
+        &self,
+        db: &DbConnection,
+    ) -> Result<Vec<CredentialTokenCacheStatus>, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+
+        let mut statuses = Vec::new();
+        for credential in credentials {
+            let cache = ProviderPoolDao::get_token_cache(&conn, &credential.uuid)
+                .map_err(|e| e.to_string())?;
+            if cache.is_none() && !credential.credential.is_file_based() {
+                continue;
+            }
+            statuses.push(self.build_status(&credential, cache.as_ref()));
+        }
+        Ok(statuses)
+    }
+
+    fn build_status(
+        &self,
+        credential: &ProviderCredential,
+        cache: Option<&CachedTokenInfo>,
+    ) -> CredentialTokenCacheStatus {
+        let counters = self
+            .counters
+            .get(&credential.uuid)
+            .map(|c| *c)
+            .unwrap_or_default();
+        CredentialTokenCacheStatus {
+            uuid: credential.uuid.clone(),
+            name: credential.name.clone(),
+            provider_type: credential.provider_type.to_string(),
+            cache: cache
+                .map(TokenCacheStatus::from)
+                .unwrap_or_else(|| TokenCacheStatus::from(&CachedTokenInfo::default())),
+            hits: counters.hits,
+            misses: counters.misses,
+        }
+    }
+
+    /// 清除 Token 缓存，下次使用时重新从凭证文件获取/刷新
+    ///
+    /// 指定 `uuid` 时只清除该凭证，否则清除所有凭证。返回清除的凭证数。
+    /// 用于凭证在外部重新授权后，丢弃仍在使用的旧 Token。
+    pub fn invalidate(&self, db: &DbConnection, uuid: Option<&str>) -> Result<usize, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let uuids = match uuid {
+            Some(uuid) => {
+                ProviderPoolDao::get_by_uuid(&conn, uuid)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("Credential not found: {}", uuid))?;
+                vec![uuid.to_string()]
+            }
+            None => ProviderPoolDao::get_all(&conn)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|c| c.uuid)
+                .collect(),
+        };
+
+        for uuid in &uuids {
+            ProviderPoolDao::clear_token_cache(&conn, uuid).map_err(|e| e.to_string())?;
+        }
+        tracing::info!("[TOKEN_CACHE] 已清除 {} 个凭证的 Token 缓存", uuids.len());
+        Ok(uuids.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_build_status_reports_counters_without_cache() {
+        let service = TokenCacheService::new();
+        let credential = ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/tmp/kiro.json".to_string(),
+            },
+        );
+        service.record_lookup(&credential.uuid, true);
+        service.record_lookup(&credential.uuid, true);
+        service.record_lookup(&credential.uuid, false);
+
+        let status = service.build_status(&credential, None);
+        assert!(!status.cache.has_cached_token);
+        assert_eq!((status.hits, status.misses), (2, 1));
+
+        let cache = CachedTokenInfo {
+            access_token: Some("token".to_string()),
+            expiry_time: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        let status = service.build_status(&credential, Some(&cache));
+        assert!(status.cache.has_cached_token && status.cache.is_valid);
+        let json = serde_json::to_value(&status).unwrap();
+        assert!(json.get("expiry_time").is_some());
+        assert!(!json.to_string().contains("\"token\""));
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_shares_in_flight_refresh() {
+        let coordinator = Arc::new(RefreshCoordinator::new(1, Duration::ZERO));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..5)
+            .map(|_| {
+                let coordinator = coordinator.clone();
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    coordinator
+                        .run("cred-1:auto", || async move {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok("token".to_string())
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), Ok("token".to_string()));
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(coordinator.in_flight_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_limits_concurrency_per_provider() {
+        let coordinator = RefreshCoordinator::new(2, Duration::ZERO);
+        let first = coordinator.acquire(PoolProviderType::Kiro, false).await;
+        let _second = coordinator.acquire(PoolProviderType::Kiro, false).await;
+
+        // 同一 Provider 已达上限，其他 Provider 不受影响
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(20),
+            coordinator.acquire(PoolProviderType::Kiro, false),
+        )
+        .await;
+        assert!(blocked.is_err());
+        let _other = coordinator.acquire(PoolProviderType::Gemini, false).await;
+
+        drop(first);
+        let _third = coordinator.acquire(PoolProviderType::Kiro, false).await;
+    }
 }
@@ -0,0 +1,370 @@
+//! 单次请求费用上限
+//!
+//! 调用上游前按请求估算的输入费用收紧 `max_tokens`（[`output_token_budget`]，所有 Provider 生效）；
+//! 流式响应另按价格表和流式 token 数估算请求费用，超过上限时截断输出：
+//! 在打开的文本块末尾追加说明，关闭内容块并以 `StopReason::MaxTokens` 结束消息
+//! （OpenAI 格式为 `finish_reason: "length"`）。调用方随后停止读取上游流。
+//!
+//! 输出 token 在收到后端 usage 前按文本长度估算（约 4 字节 / token）。
+//! 默认上限来自 `spend_guard.max_cost_usd`，启动和配置变更时通过 [`configure`] 更新。
+
+use crate::config::Config;
+use crate::stream::events::{ContentBlockType, StopReason, StreamEvent};
+use crate::telemetry::pricing::{self, ModelPrice};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+static DEFAULT_MAX_COST: Lazy<RwLock<Option<f64>>> = Lazy::new(|| RwLock::new(None));
+
+/// 更新默认费用上限
+pub fn configure(config: &Config) {
+    let max_cost = config.spend_guard.max_cost_usd.filter(|max| *max > 0.0);
+    *DEFAULT_MAX_COST.write() = max_cost;
+}
+
+/// 解析请求级覆盖值（`x-proxycast-max-cost` 头）
+///
+/// `0`、`off`、`none` 表示不限制，返回 `Some(0.0)`；无法解析时返回 None（使用默认值）。
+pub fn parse_override(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if ["off", "none"]
+        .iter()
+        .any(|v| value.eq_ignore_ascii_case(v))
+    {
+        return Some(0.0);
+    }
+    value.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// 计算本次请求生效的费用上限：请求覆盖优先，否则使用默认值
+pub fn resolve_max_cost(override_value: Option<f64>) -> Option<f64> {
+    match override_value {
+        Some(max) if max > 0.0 => Some(max),
+        Some(_) => None,
+        None => *DEFAULT_MAX_COST.read(),
+    }
+}
+
+/// 费用上限内允许的最大输出 token 数
+///
+/// 未设置上限或模型价格未知时返回 `Ok(None)`；输入部分的估算费用已达到上限时返回错误说明。
+pub fn output_token_budget(
+    model: &str,
+    max_cost_usd: Option<f64>,
+    input_tokens: u32,
+) -> Result<Option<u32>, String> {
+    let Some(max_cost_usd) = max_cost_usd else {
+        return Ok(None);
+    };
+    let Some(price) = pricing::price_for(model) else {
+        return Ok(None);
+    };
+    budget_for_price(&price, max_cost_usd, input_tokens)
+}
+
+fn budget_for_price(
+    price: &ModelPrice,
+    max_cost_usd: f64,
+    input_tokens: u32,
+) -> Result<Option<u32>, String> {
+    let input_cost = price.cost_usd(input_tokens, 0);
+    if price.output_per_million <= 0.0 {
+        return Ok(None);
+    }
+    let budget = ((max_cost_usd - input_cost) * 1_000_000.0 / price.output_per_million).floor();
+    if budget < 1.0 {
+        return Err(format!(
+            "Estimated input cost ${:.4} exceeds the per-request limit of ${:.2}",
+            input_cost, max_cost_usd
+        ));
+    }
+    Ok(Some(budget.min(u32::MAX as f64) as u32))
+}
+
+/// 按文本长度估算 token 数
+pub fn estimate_tokens(len: usize) -> u32 {
+    (len / 4) as u32
+}
+
+/// 费用上限过滤器
+#[derive(Debug, Clone)]
+pub struct CostGuard {
+    /// 费用上限（美元）
+    max_cost_usd: f64,
+    /// 模型价格
+    price: ModelPrice,
+    /// 输入 token 数（请求估算值，收到 usage 后更新）
+    input_tokens: u32,
+    /// 已输出的文本字节数
+    output_bytes: usize,
+    /// 后端报告的输出 token 数
+    reported_output_tokens: u32,
+    /// 当前打开的内容块（索引，是否为文本块）
+    open_blocks: Vec<(u32, bool)>,
+    /// 是否已因超出上限中止
+    aborted: bool,
+}
+
+impl CostGuard {
+    /// 创建过滤器
+    pub fn new(max_cost_usd: f64, price: ModelPrice, input_tokens: u32) -> Self {
+        Self {
+            max_cost_usd,
+            price,
+            input_tokens,
+            output_bytes: 0,
+            reported_output_tokens: 0,
+            open_blocks: Vec::new(),
+            aborted: false,
+        }
+    }
+
+    /// 按模型价格创建过滤器，未设置上限或价格未知时返回 None
+    pub fn for_model(model: &str, max_cost_usd: Option<f64>, input_tokens: u32) -> Option<Self> {
+        let max_cost_usd = max_cost_usd?;
+        let Some(price) = pricing::price_for(model) else {
+            tracing::debug!("[COST_GUARD] 模型 {} 没有价格信息，跳过费用上限", model);
+            return None;
+        };
+        Some(Self::new(max_cost_usd, price, input_tokens))
+    }
+
+    /// 费用上限（美元）
+    pub fn max_cost_usd(&self) -> f64 {
+        self.max_cost_usd
+    }
+
+    /// 当前估算的输出 token 数
+    pub fn output_tokens(&self) -> u32 {
+        self.reported_output_tokens
+            .max(estimate_tokens(self.output_bytes))
+    }
+
+    /// 当前估算的输入 token 数
+    pub fn input_tokens(&self) -> u32 {
+        self.input_tokens
+    }
+
+    /// 当前估算费用（美元）
+    pub fn estimated_cost_usd(&self) -> f64 {
+        self.price.cost_usd(self.input_tokens, self.output_tokens())
+    }
+
+    /// 是否已因超出上限中止（之后的事件都会被丢弃）
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// 过滤一批事件
+    pub fn filter(&mut self, events: Vec<StreamEvent>) -> Vec<StreamEvent> {
+        let mut output = Vec::with_capacity(events.len());
+        for event in events {
+            if self.aborted {
+                break;
+            }
+            match &event {
+                StreamEvent::ContentBlockStart { index, block_type } => self
+                    .open_blocks
+                    .push((*index, matches!(block_type, ContentBlockType::Text))),
+                StreamEvent::ContentBlockStop { index } => {
+                    self.open_blocks.retain(|(i, _)| i != index)
+                }
+                StreamEvent::TextDelta { text } => self.output_bytes += text.len(),
+                StreamEvent::ToolUseInputDelta { partial_json, .. } => {
+                    self.output_bytes += partial_json.len()
+                }
+                StreamEvent::Usage {
+                    input_tokens,
+                    output_tokens,
+                    ..
+                } => {
+                    if *input_tokens > 0 {
+                        self.input_tokens = *input_tokens;
+                    }
+                    self.reported_output_tokens = *output_tokens;
+                }
+                _ => {}
+            }
+            let is_output = matches!(
+                event,
+                StreamEvent::TextDelta { .. } | StreamEvent::ToolUseInputDelta { .. }
+            );
+            output.push(event);
+
+            // 只在输出增长时检查，后端在末尾报告的 usage 不会截断已完成的消息
+            if is_output && self.estimated_cost_usd() > self.max_cost_usd {
+                self.abort(&mut output);
+            }
+        }
+        output
+    }
+
+    fn abort(&mut self, output: &mut Vec<StreamEvent>) {
+        tracing::warn!(
+            "[COST_GUARD] 预估费用 ${:.4} 超过上限 ${:.4}，中止上游流",
+            self.estimated_cost_usd(),
+            self.max_cost_usd
+        );
+        // 说明只追加到文本块，避免破坏工具调用参数
+        if self.open_blocks.last().is_some_and(|(_, is_text)| *is_text) {
+            output.push(StreamEvent::TextDelta {
+                text: format!(
+                    "\n\n[proxycast] Output truncated: estimated cost exceeded the per-request limit of ${:.2}.",
+                    self.max_cost_usd
+                ),
+            });
+        }
+        for (index, _) in std::mem::take(&mut self.open_blocks).into_iter().rev() {
+            output.push(StreamEvent::ContentBlockStop { index });
+        }
+        output.push(StreamEvent::MessageStop {
+            stop_reason: StopReason::MaxTokens,
+        });
+        self.aborted = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(max_cost_usd: f64) -> CostGuard {
+        // 输出 1 美元 / 1000 token，便于计算
+        let price = ModelPrice {
+            input_per_million: 0.0,
+            output_per_million: 1000.0,
+            cache_write_per_million: None,
+            cache_read_per_million: None,
+        };
+        CostGuard::new(max_cost_usd, price, 0)
+    }
+
+    #[test]
+    fn test_parse_override() {
+        assert_eq!(parse_override(" 0.25 "), Some(0.25));
+        assert_eq!(parse_override("off"), Some(0.0));
+        assert_eq!(parse_override("NONE"), Some(0.0));
+        assert_eq!(parse_override("abc"), None);
+        assert_eq!(parse_override("inf"), None);
+    }
+
+    #[test]
+    fn test_resolve_max_cost_override_wins() {
+        assert_eq!(resolve_max_cost(Some(0.5)), Some(0.5));
+        assert_eq!(resolve_max_cost(Some(0.0)), None);
+    }
+
+    #[test]
+    fn test_output_token_budget() {
+        let price = ModelPrice {
+            input_per_million: 1000.0,
+            output_per_million: 2000.0,
+            cache_write_per_million: None,
+            cache_read_per_million: None,
+        };
+        // 输入 $0.5，剩余 $0.5 ≈ 250 个输出 token
+        assert_eq!(budget_for_price(&price, 1.0, 500), Ok(Some(250)));
+        assert!(budget_for_price(&price, 1.0, 1000)
+            .unwrap_err()
+            .contains("exceeds the per-request limit"));
+        assert_eq!(
+            output_token_budget("claude-sonnet-4-5", None, 1000),
+            Ok(None)
+        );
+        assert_eq!(
+            output_token_budget("unknown-model", Some(1.0), 1000),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_aborts_when_estimated_cost_exceeds_limit() {
+        let mut guard = guard(1.0);
+        let events = guard.filter(vec![
+            StreamEvent::ContentBlockStart {
+                index: 0,
+                block_type: ContentBlockType::Text,
+            },
+            // 2000 字节 ≈ 500 token ≈ $0.5
+            StreamEvent::TextDelta {
+                text: "a".repeat(2000),
+            },
+        ]);
+        assert_eq!(events.len(), 2);
+        assert!(!guard.is_aborted());
+
+        let events = guard.filter(vec![
+            StreamEvent::TextDelta {
+                text: "b".repeat(4000),
+            },
+            StreamEvent::TextDelta {
+                text: "dropped".to_string(),
+            },
+        ]);
+        assert!(guard.is_aborted());
+        assert!(guard.estimated_cost_usd() > 1.0);
+        assert!(matches!(
+            &events[1],
+            StreamEvent::TextDelta { text } if text.contains("Output truncated")
+        ));
+        assert!(matches!(
+            &events[2..],
+            [
+                StreamEvent::ContentBlockStop { index: 0 },
+                StreamEvent::MessageStop {
+                    stop_reason: StopReason::MaxTokens
+                }
+            ]
+        ));
+        assert!(guard
+            .filter(vec![StreamEvent::ContentBlockStop { index: 0 }])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_no_note_inside_tool_block() {
+        let mut guard = guard(0.1);
+        let events = guard.filter(vec![
+            StreamEvent::ContentBlockStart {
+                index: 1,
+                block_type: ContentBlockType::ToolUse {
+                    id: "tool_1".to_string(),
+                    name: "search".to_string(),
+                },
+            },
+            StreamEvent::ToolUseInputDelta {
+                id: "tool_1".to_string(),
+                partial_json: "x".repeat(1000),
+            },
+        ]);
+        assert!(guard.is_aborted());
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, StreamEvent::TextDelta { .. })));
+        assert!(matches!(
+            events.last(),
+            Some(StreamEvent::MessageStop {
+                stop_reason: StopReason::MaxTokens
+            })
+        ));
+    }
+
+    #[test]
+    fn test_trailing_usage_does_not_truncate() {
+        let mut guard = guard(0.1);
+        let events = guard.filter(vec![
+            StreamEvent::Usage {
+                input_tokens: 10,
+                output_tokens: 1000,
+                cache_read_input_tokens: None,
+                cache_creation_input_tokens: None,
+            },
+            StreamEvent::MessageStop {
+                stop_reason: StopReason::EndTurn,
+            },
+        ]);
+        assert_eq!(events.len(), 2);
+        assert!(!guard.is_aborted());
+        assert_eq!(guard.output_tokens(), 1000);
+    }
+}
@@ -23,14 +23,26 @@
 //! - `generators`: 前端流格式生成器
 //!   - `openai_sse`: OpenAI SSE 格式生成器
 //!   - `anthropic_sse`: Anthropic SSE 格式生成器
+//! - `stop_sequences`: 客户端停止序列截断
+//! - `tool_calls`: 禁用并行工具调用时只保留第一个工具调用
+//! - `cost_guard`: 单次请求费用上限
+//! - `utf8`: 跨 chunk 的增量 UTF-8 解码
 
+pub mod cost_guard;
 pub mod events;
 pub mod generators;
 pub mod parsers;
 pub mod pipeline;
+pub mod stop_sequences;
+pub mod tool_calls;
+pub mod utf8;
 
 // 重新导出核心类型
+pub use cost_guard::CostGuard;
 pub use events::{ContentBlockType, StopReason, StreamContext, StreamEvent};
 pub use generators::{AnthropicSseGenerator, OpenAiSseGenerator};
 pub use parsers::{AwsEventStreamParser, ParserState};
 pub use pipeline::{create_sse_stream, BackendType, FrontendType, PipelineConfig, StreamPipeline};
+pub use stop_sequences::StopSequenceFilter;
+pub use tool_calls::{keep_first_tool_call, SingleToolCallFilter};
+pub use utf8::Utf8ChunkDecoder;
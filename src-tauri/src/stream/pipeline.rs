@@ -14,9 +14,12 @@
 //! let sse_stream = pipeline.process_stream(byte_stream);
 //! ```
 
+use crate::stream::cost_guard::CostGuard;
 use crate::stream::events::StreamEvent;
 use crate::stream::generators::{AnthropicSseGenerator, OpenAiSseGenerator};
 use crate::stream::parsers::AwsEventStreamParser;
+use crate::stream::stop_sequences::StopSequenceFilter;
+use crate::stream::tool_calls::SingleToolCallFilter;
 use bytes::Bytes;
 use futures::{Stream, StreamExt};
 
@@ -51,6 +54,14 @@ pub struct PipelineConfig {
     pub model: String,
     /// 消息 ID（可选）
     pub message_id: Option<String>,
+    /// 客户端截断的停止序列（后端不支持服务端 stop 时使用）
+    pub stop_sequences: Vec<String>,
+    /// 只保留第一个工具调用（请求禁用并行工具调用且后端不支持该参数时使用）
+    pub single_tool_call: bool,
+    /// 单次请求费用上限（美元）
+    pub max_cost_usd: Option<f64>,
+    /// 请求输入 token 估算值（用于费用估算）
+    pub input_tokens: u32,
 }
 
 impl PipelineConfig {
@@ -61,6 +72,10 @@ impl PipelineConfig {
             frontend: FrontendType::Anthropic,
             model,
             message_id: None,
+            stop_sequences: Vec::new(),
+            single_tool_call: false,
+            max_cost_usd: None,
+            input_tokens: 0,
         }
     }
 
@@ -71,14 +86,62 @@ impl PipelineConfig {
             frontend: FrontendType::OpenAi,
             model,
             message_id: None,
+            stop_sequences: Vec::new(),
+            single_tool_call: false,
+            max_cost_usd: None,
+            input_tokens: 0,
         }
     }
 
+    /// 创建 OpenAI → OpenAI 直通配置
+    ///
+    /// 上游已是 OpenAI SSE（如 Qwen、iFlow），按完整事件原样转发
+    pub fn openai_passthrough(model: String) -> Self {
+        Self {
+            backend: BackendType::OpenAi,
+            frontend: FrontendType::OpenAi,
+            model,
+            message_id: None,
+            stop_sequences: Vec::new(),
+            single_tool_call: false,
+            max_cost_usd: None,
+            input_tokens: 0,
+        }
+    }
+
+    /// 是否为 OpenAI → OpenAI 直通
+    pub fn is_passthrough(&self) -> bool {
+        self.backend == BackendType::OpenAi && self.frontend == FrontendType::OpenAi
+    }
+
     /// 设置消息 ID
     pub fn with_message_id(mut self, id: String) -> Self {
         self.message_id = Some(id);
         self
     }
+
+    /// 设置停止序列（命中后截断流并以 stop_sequence 结束）
+    pub fn with_stop_sequences(mut self, sequences: Vec<String>) -> Self {
+        self.stop_sequences = sequences;
+        self
+    }
+
+    /// 设置是否只保留第一个工具调用
+    pub fn with_single_tool_call(mut self, enabled: bool) -> Self {
+        self.single_tool_call = enabled;
+        self
+    }
+
+    /// 设置费用上限（超出后截断流并以 max_tokens 结束）
+    pub fn with_cost_guard(mut self, max_cost_usd: Option<f64>, input_tokens: u32) -> Self {
+        self.max_cost_usd = max_cost_usd;
+        self.input_tokens = input_tokens;
+        self
+    }
+
+    fn cost_guard(&self) -> Option<CostGuard> {
+        CostGuard::for_model(&self.model, self.max_cost_usd, self.input_tokens)
+    }
 }
 
 /// SSE 生成器封装
@@ -106,6 +169,14 @@ pub struct StreamPipeline {
     aws_parser: Option<AwsEventStreamParser>,
     /// SSE 生成器
     generator: SseGenerator,
+    /// 停止序列过滤器
+    stop_filter: Option<StopSequenceFilter>,
+    /// 单工具调用过滤器
+    tool_call_filter: Option<SingleToolCallFilter>,
+    /// 费用上限过滤器
+    cost_guard: Option<CostGuard>,
+    /// 直通模式下未组成完整事件的字节
+    passthrough_buffer: Option<Vec<u8>>,
 }
 
 impl StreamPipeline {
@@ -139,10 +210,19 @@ impl StreamPipeline {
             }
         };
 
+        let stop_filter = StopSequenceFilter::new(config.stop_sequences.clone());
+        let tool_call_filter = config.single_tool_call.then(SingleToolCallFilter::new);
+        let cost_guard = config.cost_guard();
+        let passthrough_buffer = config.is_passthrough().then(Vec::new);
+
         Self {
             config,
             aws_parser,
             generator,
+            stop_filter,
+            tool_call_filter,
+            cost_guard,
+            passthrough_buffer,
         }
     }
 
@@ -152,7 +232,19 @@ impl StreamPipeline {
     ///
     /// 生成的 SSE 字符串列表
     pub fn process_chunk(&mut self, bytes: &[u8]) -> Vec<String> {
+        if let Some(buffer) = &mut self.passthrough_buffer {
+            buffer.extend_from_slice(bytes);
+            let mut events = Vec::new();
+            while let Some(end) = crate::server::stream_resume::event_end(buffer) {
+                let event: Vec<u8> = buffer.drain(..end).collect();
+                events.push(String::from_utf8_lossy(&event).into_owned());
+            }
+            return events;
+        }
         let events = self.parse_bytes(bytes);
+        let events = self.apply_tool_call_filter(events);
+        let events = self.apply_stop_filter(events);
+        let events = self.apply_cost_guard(events);
         self.generate_sse(&events)
     }
 
@@ -162,10 +254,61 @@ impl StreamPipeline {
     ///
     /// 最终的 SSE 字符串列表
     pub fn finish(&mut self) -> Vec<String> {
+        if let Some(buffer) = &mut self.passthrough_buffer {
+            // 上游结尾缺少空行时补齐，保证客户端能解析最后一个事件
+            let rest = String::from_utf8_lossy(&std::mem::take(buffer)).into_owned();
+            return if rest.trim().is_empty() {
+                Vec::new()
+            } else {
+                vec![format!("{}\n\n", rest.trim_end())]
+            };
+        }
         let events = self.finish_parsing();
+        let events = self.apply_tool_call_filter(events);
+        let events = self.apply_stop_filter(events);
+        let events = self.apply_cost_guard(events);
         self.generate_sse(&events)
     }
 
+    /// 是否已命中停止序列或超出费用上限
+    ///
+    /// 命中后上游剩余数据不会再产生输出，调用方可以提前结束读取。
+    pub fn is_stopped(&self) -> bool {
+        self.stop_filter
+            .as_ref()
+            .is_some_and(StopSequenceFilter::is_stopped)
+            || self.cost_aborted().is_some()
+    }
+
+    /// 因超出费用上限中止时返回过滤器（包含估算费用与 token 数）
+    pub fn cost_aborted(&self) -> Option<&CostGuard> {
+        self.cost_guard.as_ref().filter(|guard| guard.is_aborted())
+    }
+
+    /// 按费用上限截断事件
+    fn apply_cost_guard(&mut self, events: Vec<StreamEvent>) -> Vec<StreamEvent> {
+        match &mut self.cost_guard {
+            Some(guard) => guard.filter(events),
+            None => events,
+        }
+    }
+
+    /// 只保留第一个工具调用
+    fn apply_tool_call_filter(&mut self, events: Vec<StreamEvent>) -> Vec<StreamEvent> {
+        match &mut self.tool_call_filter {
+            Some(filter) => filter.filter(events),
+            None => events,
+        }
+    }
+
+    /// 按停止序列截断事件
+    fn apply_stop_filter(&mut self, events: Vec<StreamEvent>) -> Vec<StreamEvent> {
+        match &mut self.stop_filter {
+            Some(filter) => filter.filter(events),
+            None => events,
+        }
+    }
+
     /// 解析字节为 StreamEvent
     fn parse_bytes(&mut self, bytes: &[u8]) -> Vec<StreamEvent> {
         match &mut self.aws_parser {
@@ -210,6 +353,10 @@ impl StreamPipeline {
                 SseGenerator::OpenAi(OpenAiSseGenerator::new(self.config.model.clone()))
             }
         };
+        self.stop_filter = StopSequenceFilter::new(self.config.stop_sequences.clone());
+        self.tool_call_filter = self.config.single_tool_call.then(SingleToolCallFilter::new);
+        self.cost_guard = self.config.cost_guard();
+        self.passthrough_buffer = self.config.is_passthrough().then(Vec::new);
     }
 }
 
@@ -270,6 +417,24 @@ mod tests {
         assert_eq!(config.frontend, FrontendType::OpenAi);
     }
 
+    #[test]
+    fn test_pipeline_openai_passthrough_splits_events() {
+        let config = PipelineConfig::openai_passthrough("qwen3-coder-plus".to_string());
+        assert!(config.is_passthrough());
+        let mut pipeline = StreamPipeline::new(config);
+
+        let first = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n";
+        let mut sse = pipeline.process_chunk(first[..10].as_bytes());
+        assert!(sse.is_empty());
+        sse.extend(pipeline.process_chunk(format!("{}data: [DO", &first[10..]).as_bytes()));
+        assert_eq!(sse, vec![first.to_string()]);
+
+        sse = pipeline.process_chunk(b"NE]");
+        assert!(sse.is_empty());
+        assert_eq!(pipeline.finish(), vec!["data: [DONE]\n\n".to_string()]);
+        assert!(pipeline.finish().is_empty());
+    }
+
     #[test]
     fn test_pipeline_process_content() {
         let config = PipelineConfig::kiro_to_anthropic("claude-sonnet-4-5".to_string());
@@ -324,4 +489,66 @@ mod tests {
         assert!(sse.iter().any(|s| s.starts_with("data: ")));
         assert!(sse.iter().any(|s| s.contains("\"content\":\"Hello\"")));
     }
+
+    #[test]
+    fn test_pipeline_content_split_mid_codepoint() {
+        let config = PipelineConfig::kiro_to_openai("gpt-4".to_string());
+        let mut pipeline = StreamPipeline::new(config);
+
+        let payload = r#"{"content":"中文回复 🎉 完成"}"#.as_bytes();
+        // 在 emoji 的第二个字节处切分
+        let split = r#"{"content":"中文回复 "#.len() + 2;
+
+        let mut sse = pipeline.process_chunk(&payload[..split]);
+        sse.extend(pipeline.process_chunk(&payload[split..]));
+        sse.extend(pipeline.finish());
+
+        assert!(sse.iter().any(|s| s.contains("中文回复 🎉 完成")));
+        assert!(!sse.iter().any(|s| s.contains(char::REPLACEMENT_CHARACTER)));
+    }
+
+    #[test]
+    fn test_pipeline_truncates_at_stop_sequence() {
+        let config = PipelineConfig::kiro_to_openai("gpt-4".to_string())
+            .with_stop_sequences(vec!["###".to_string()]);
+        let mut pipeline = StreamPipeline::new(config);
+
+        let sse = pipeline.process_chunk(br#"{"content":"Hello ### ignored"}"#);
+        assert!(pipeline.is_stopped());
+        assert!(sse.iter().any(|s| s.contains("\"content\":\"Hello \"")));
+        assert!(!sse.iter().any(|s| s.contains("ignored")));
+        assert!(sse.iter().any(|s| s.contains("\"finish_reason\":\"stop\"")));
+
+        // 截断后不再输出任何事件
+        assert!(pipeline.process_chunk(br#"{"content":"more"}"#).is_empty());
+        assert!(pipeline.finish().is_empty());
+    }
+
+    #[test]
+    fn test_pipeline_single_tool_call() {
+        let config =
+            PipelineConfig::kiro_to_openai("gpt-4".to_string()).with_single_tool_call(true);
+        let mut pipeline = StreamPipeline::new(config);
+
+        let mut sse = Vec::new();
+        for id in ["tool_1", "tool_2"] {
+            sse.extend(
+                pipeline.process_chunk(
+                    format!(r#"{{"toolUseId":"{id}","name":"read_file"}}"#).as_bytes(),
+                ),
+            );
+            sse.extend(
+                pipeline
+                    .process_chunk(format!(r#"{{"toolUseId":"{id}","input":"{{}}"}}"#).as_bytes()),
+            );
+            sse.extend(
+                pipeline.process_chunk(format!(r#"{{"toolUseId":"{id}","stop":true}}"#).as_bytes()),
+            );
+        }
+        sse.extend(pipeline.finish());
+
+        assert!(sse.iter().any(|s| s.contains("tool_1")));
+        assert!(!sse.iter().any(|s| s.contains("tool_2")));
+        assert!(!sse.iter().any(|s| s.contains("\"index\":1")));
+    }
 }
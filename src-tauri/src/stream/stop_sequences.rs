@@ -0,0 +1,206 @@
+//! 客户端停止序列截断
+//!
+//! 用于不支持服务端 stop 参数的后端（如 Kiro）：在文本流中匹配停止序列，
+//! 命中后截断输出、关闭已打开的内容块，并以 `StopReason::StopSequence` 结束消息。
+//!
+//! 可能构成停止序列前缀的文本尾部会暂存，直到确认不匹配后再输出，
+//! 保证跨 chunk 的停止序列也不会泄露给客户端。
+
+use crate::stream::events::{StopReason, StreamEvent};
+
+/// 停止序列过滤器
+#[derive(Debug, Clone)]
+pub struct StopSequenceFilter {
+    /// 停止序列（已去除空串）
+    sequences: Vec<String>,
+    /// 暂存的文本尾部（可能是停止序列的前缀）
+    pending: String,
+    /// 当前打开的内容块索引
+    open_blocks: Vec<u32>,
+    /// 是否已命中停止序列
+    stopped: bool,
+}
+
+impl StopSequenceFilter {
+    /// 创建过滤器，没有有效停止序列时返回 None
+    pub fn new(sequences: Vec<String>) -> Option<Self> {
+        let sequences: Vec<String> = sequences.into_iter().filter(|s| !s.is_empty()).collect();
+        if sequences.is_empty() {
+            return None;
+        }
+        Some(Self {
+            sequences,
+            pending: String::new(),
+            open_blocks: Vec::new(),
+            stopped: false,
+        })
+    }
+
+    /// 是否已命中停止序列（之后的事件都会被丢弃）
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// 过滤一批事件
+    pub fn filter(&mut self, events: Vec<StreamEvent>) -> Vec<StreamEvent> {
+        let mut output = Vec::with_capacity(events.len());
+        for event in events {
+            if self.stopped {
+                break;
+            }
+            match event {
+                StreamEvent::TextDelta { text } => self.push_text(&text, &mut output),
+                other => {
+                    // 非文本事件之前先输出暂存文本，保持事件顺序
+                    self.flush_pending(&mut output);
+                    match &other {
+                        StreamEvent::ContentBlockStart { index, .. } => {
+                            self.open_blocks.push(*index)
+                        }
+                        StreamEvent::ContentBlockStop { index } => {
+                            self.open_blocks.retain(|i| i != index)
+                        }
+                        _ => {}
+                    }
+                    output.push(other);
+                }
+            }
+        }
+        output
+    }
+
+    fn push_text(&mut self, text: &str, output: &mut Vec<StreamEvent>) {
+        self.pending.push_str(text);
+
+        let matched = self
+            .sequences
+            .iter()
+            .filter_map(|seq| self.pending.find(seq.as_str()))
+            .min();
+        if let Some(pos) = matched {
+            let before = self.pending[..pos].to_string();
+            self.pending.clear();
+            if !before.is_empty() {
+                output.push(StreamEvent::TextDelta { text: before });
+            }
+            for index in std::mem::take(&mut self.open_blocks) {
+                output.push(StreamEvent::ContentBlockStop { index });
+            }
+            output.push(StreamEvent::MessageStop {
+                stop_reason: StopReason::StopSequence,
+            });
+            self.stopped = true;
+            return;
+        }
+
+        let hold = self.partial_match_len();
+        let emit_len = self.pending.len() - hold;
+        if emit_len > 0 {
+            let rest = self.pending.split_off(emit_len);
+            let emitted = std::mem::replace(&mut self.pending, rest);
+            output.push(StreamEvent::TextDelta { text: emitted });
+        }
+    }
+
+    /// 暂存文本末尾与任一停止序列前缀重合的最大长度
+    fn partial_match_len(&self) -> usize {
+        self.sequences
+            .iter()
+            .filter_map(|seq| {
+                (1..seq.len())
+                    .rev()
+                    .filter(|&k| seq.is_char_boundary(k))
+                    .find(|&k| self.pending.ends_with(&seq[..k]))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn flush_pending(&mut self, output: &mut Vec<StreamEvent>) {
+        if !self.pending.is_empty() {
+            output.push(StreamEvent::TextDelta {
+                text: std::mem::take(&mut self.pending),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::events::ContentBlockType;
+
+    fn text(s: &str) -> StreamEvent {
+        StreamEvent::TextDelta {
+            text: s.to_string(),
+        }
+    }
+
+    fn collect_text(events: &[StreamEvent]) -> String {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                StreamEvent::TextDelta { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_new_ignores_empty_sequences() {
+        assert!(StopSequenceFilter::new(vec![]).is_none());
+        assert!(StopSequenceFilter::new(vec![String::new()]).is_none());
+    }
+
+    #[test]
+    fn test_stop_sequence_split_across_chunks() {
+        let mut filter = StopSequenceFilter::new(vec!["END".to_string()]).unwrap();
+        let mut events = filter.filter(vec![
+            StreamEvent::ContentBlockStart {
+                index: 0,
+                block_type: ContentBlockType::Text,
+            },
+            text("Hello E"),
+        ]);
+        assert_eq!(collect_text(&events), "Hello ");
+        assert!(!filter.is_stopped());
+
+        events.extend(filter.filter(vec![text("ND world")]));
+        assert!(filter.is_stopped());
+        assert_eq!(collect_text(&events), "Hello ");
+        assert!(matches!(
+            &events[events.len() - 2..],
+            [
+                StreamEvent::ContentBlockStop { index: 0 },
+                StreamEvent::MessageStop {
+                    stop_reason: StopReason::StopSequence
+                }
+            ]
+        ));
+
+        // 命中后的事件全部丢弃
+        assert!(filter
+            .filter(vec![
+                text("more"),
+                StreamEvent::ContentBlockStop { index: 0 }
+            ])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_pending_prefix_flushed_when_not_matched() {
+        let mut filter = StopSequenceFilter::new(vec!["STOP".to_string()]).unwrap();
+        let mut events = filter.filter(vec![text("ST")]);
+        assert!(events.is_empty());
+
+        events.extend(filter.filter(vec![
+            StreamEvent::ContentBlockStop { index: 0 },
+            StreamEvent::MessageStop {
+                stop_reason: StopReason::EndTurn,
+            },
+        ]));
+        assert_eq!(collect_text(&events), "ST");
+        assert!(matches!(events[0], StreamEvent::TextDelta { .. }));
+        assert!(!filter.is_stopped());
+    }
+}
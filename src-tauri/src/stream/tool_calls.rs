@@ -0,0 +1,217 @@
+//! 单工具调用过滤
+//!
+//! 用于请求设置了 `parallel_tool_calls: false`（或 Anthropic 的
+//! `disable_parallel_tool_use`），但后端不支持该参数的情况：
+//! 只保留第一个工具调用，之后的工具调用全部丢弃，文本内容不受影响。
+//!
+//! - [`SingleToolCallFilter`] 作用于解析出的 [`StreamEvent`]，目前只有 Kiro 流式响应经过
+//!   统一管道解析，因此只在 Kiro 的流式路径生效
+//! - [`keep_first_tool_call`] 作用于 OpenAI 格式的非流式响应，用于 Antigravity
+//!
+//! Antigravity 的流式响应在服务端聚合后只输出文本、思维链和图片，不包含工具调用；
+//! Gemini OAuth 不经过这两条路由；OpenAI 兼容和 Claude 后端直接把参数转发给上游。
+
+use serde_json::Value;
+
+use crate::stream::events::{ContentBlockType, StreamEvent};
+
+/// 单工具调用过滤器
+#[derive(Debug, Clone, Default)]
+pub struct SingleToolCallFilter {
+    /// 保留的工具调用 ID
+    kept: Option<String>,
+    /// 被丢弃的工具调用 ID
+    dropped_ids: Vec<String>,
+    /// 被丢弃的内容块索引
+    dropped_blocks: Vec<u32>,
+}
+
+impl SingleToolCallFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 丢弃的工具调用数量
+    pub fn dropped_count(&self) -> usize {
+        self.dropped_ids.len()
+    }
+
+    /// 过滤一批事件
+    pub fn filter(&mut self, events: Vec<StreamEvent>) -> Vec<StreamEvent> {
+        events
+            .into_iter()
+            .filter(|event| self.keep(event))
+            .collect()
+    }
+
+    fn keep(&mut self, event: &StreamEvent) -> bool {
+        match event {
+            StreamEvent::ContentBlockStart {
+                index,
+                block_type: ContentBlockType::ToolUse { id, .. },
+            } => {
+                if self.accept_tool(id) {
+                    true
+                } else {
+                    self.dropped_blocks.push(*index);
+                    false
+                }
+            }
+            StreamEvent::ToolUseStart { id, .. } => self.accept_tool(id),
+            StreamEvent::ToolUseInputDelta { id, .. } | StreamEvent::ToolUseStop { id } => {
+                !self.dropped_ids.contains(id)
+            }
+            StreamEvent::ContentBlockStop { index } => !self.dropped_blocks.contains(index),
+            _ => true,
+        }
+    }
+
+    /// 第一个工具调用被保留，之后出现的工具调用记为丢弃
+    fn accept_tool(&mut self, id: &str) -> bool {
+        match &self.kept {
+            None => {
+                self.kept = Some(id.to_string());
+                true
+            }
+            Some(kept) if kept == id => true,
+            Some(_) => {
+                if !self.dropped_ids.iter().any(|d| d == id) {
+                    self.dropped_ids.push(id.to_string());
+                }
+                false
+            }
+        }
+    }
+}
+
+/// 只保留 OpenAI 格式响应中每个 choice 的第一个工具调用，返回丢弃的数量
+pub fn keep_first_tool_call(response: &mut Value) -> usize {
+    let Some(choices) = response.get_mut("choices").and_then(Value::as_array_mut) else {
+        return 0;
+    };
+    let mut dropped = 0;
+    for choice in choices {
+        if let Some(Value::Array(tool_calls)) = choice.pointer_mut("/message/tool_calls") {
+            dropped += tool_calls.len().saturating_sub(1);
+            tool_calls.truncate(1);
+        }
+    }
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::events::StopReason;
+
+    fn tool_block(index: u32, id: &str) -> Vec<StreamEvent> {
+        vec![
+            StreamEvent::ContentBlockStart {
+                index,
+                block_type: ContentBlockType::ToolUse {
+                    id: id.to_string(),
+                    name: "read_file".to_string(),
+                },
+            },
+            StreamEvent::ToolUseStart {
+                id: id.to_string(),
+                name: "read_file".to_string(),
+            },
+            StreamEvent::ToolUseInputDelta {
+                id: id.to_string(),
+                partial_json: "{}".to_string(),
+            },
+            StreamEvent::ToolUseStop { id: id.to_string() },
+            StreamEvent::ContentBlockStop { index },
+        ]
+    }
+
+    fn tool_ids(events: &[StreamEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                StreamEvent::ToolUseStart { id, .. } => Some(id.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keeps_only_first_tool_call() {
+        let mut filter = SingleToolCallFilter::new();
+        let mut events = vec![
+            StreamEvent::ContentBlockStart {
+                index: 0,
+                block_type: ContentBlockType::Text,
+            },
+            StreamEvent::TextDelta {
+                text: "Reading files".to_string(),
+            },
+            StreamEvent::ContentBlockStop { index: 0 },
+        ];
+        events.extend(tool_block(1, "tool_a"));
+        let mut output = filter.filter(events);
+
+        // 第二个工具调用跨批次到达
+        let mut rest = tool_block(2, "tool_b");
+        rest.push(StreamEvent::MessageStop {
+            stop_reason: StopReason::ToolUse,
+        });
+        output.extend(filter.filter(rest));
+
+        assert_eq!(tool_ids(&output), vec!["tool_a"]);
+        assert_eq!(filter.dropped_count(), 1);
+        assert!(!output.iter().any(|e| matches!(
+            e,
+            StreamEvent::ContentBlockStart { index: 2, .. }
+                | StreamEvent::ContentBlockStop { index: 2 }
+        )));
+        assert!(!output.iter().any(|e| matches!(
+            e,
+            StreamEvent::ToolUseInputDelta { id, .. } | StreamEvent::ToolUseStop { id }
+                if id == "tool_b"
+        )));
+        assert!(output
+            .iter()
+            .any(|e| matches!(e, StreamEvent::TextDelta { text } if text == "Reading files")));
+        assert!(matches!(
+            output.last(),
+            Some(StreamEvent::MessageStop {
+                stop_reason: StopReason::ToolUse
+            })
+        ));
+    }
+
+    #[test]
+    fn test_keep_first_tool_call_in_response() {
+        let mut response = serde_json::json!({
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "Reading files",
+                    "tool_calls": [
+                        {"id": "tool_a", "type": "function", "function": {"name": "read_file", "arguments": "{}"}},
+                        {"id": "tool_b", "type": "function", "function": {"name": "read_file", "arguments": "{}"}}
+                    ]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        });
+        assert_eq!(keep_first_tool_call(&mut response), 1);
+
+        let message = &response["choices"][0]["message"];
+        let tool_calls = message["tool_calls"].as_array().unwrap();
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0]["id"], "tool_a");
+        assert_eq!(message["content"], "Reading files");
+
+        // 没有工具调用的响应保持不变
+        let mut text_only = serde_json::json!({
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "hi"}}]
+        });
+        let original = text_only.clone();
+        assert_eq!(keep_first_tool_call(&mut text_only), 0);
+        assert_eq!(text_only, original);
+    }
+}
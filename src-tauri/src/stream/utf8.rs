@@ -0,0 +1,116 @@
+//! 跨 chunk 的 UTF-8 解码
+//!
+//! 上游字节流的 chunk 边界可能落在多字节字符（中文、emoji 等）中间，
+//! 逐 chunk 调用 `String::from_utf8` 会失败或产生替换字符。
+//! 解码器暂存末尾不完整的字节序列，只输出完整字符，流结束时再输出剩余部分。
+
+/// 增量 UTF-8 解码器
+#[derive(Debug, Clone, Default)]
+pub struct Utf8ChunkDecoder {
+    /// 上一个 chunk 末尾不完整的字节序列（最多 3 字节）
+    pending: Vec<u8>,
+}
+
+impl Utf8ChunkDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 解码一个 chunk，返回其中完整的字符
+    ///
+    /// 末尾不完整的字节序列暂存到下一次调用；
+    /// 中间的非法字节替换为 U+FFFD，与 `from_utf8_lossy` 一致。
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(bytes);
+
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(s) => {
+                    output.push_str(s);
+                    break;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    // valid_up_to 之前的字节已验证为合法 UTF-8
+                    output.push_str(std::str::from_utf8(&rest[..valid]).unwrap_or_default());
+                    match e.error_len() {
+                        Some(len) => {
+                            output.push(char::REPLACEMENT_CHARACTER);
+                            rest = &rest[valid + len..];
+                        }
+                        None => {
+                            // 末尾字符不完整，等待下一个 chunk
+                            self.pending = rest[valid..].to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        output
+    }
+
+    /// 流结束时输出暂存的剩余字节（不完整的字符替换为 U+FFFD）
+    pub fn finish(&mut self) -> String {
+        let pending = std::mem::take(&mut self.pending);
+        String::from_utf8_lossy(&pending).into_owned()
+    }
+
+    /// 是否有暂存的不完整字节
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// 清空暂存
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_split_multibyte_characters() {
+        let text = "你好，世界 👋🏽 done";
+        let bytes = text.as_bytes();
+
+        // 按 1 字节切分，每个多字节字符都会跨 chunk
+        let mut decoder = Utf8ChunkDecoder::new();
+        let mut output = String::new();
+        for chunk in bytes.chunks(1) {
+            output.push_str(&decoder.decode(chunk));
+        }
+        output.push_str(&decoder.finish());
+
+        assert_eq!(output, text);
+        assert!(!output.contains(char::REPLACEMENT_CHARACTER));
+    }
+
+    #[test]
+    fn test_decode_holds_incomplete_tail() {
+        let mut decoder = Utf8ChunkDecoder::new();
+        let emoji = "😀".as_bytes();
+
+        assert_eq!(decoder.decode(&[b'a', emoji[0], emoji[1]]), "a");
+        assert!(decoder.has_pending());
+        assert_eq!(decoder.decode(&emoji[2..]), "😀");
+        assert!(!decoder.has_pending());
+    }
+
+    #[test]
+    fn test_invalid_bytes_replaced_and_truncated_tail_flushed() {
+        let mut decoder = Utf8ChunkDecoder::new();
+        assert_eq!(decoder.decode(&[b'a', 0xff, b'b']), "a\u{FFFD}b");
+
+        // 流结束时不完整的字符替换为 U+FFFD
+        let cjk = "中".as_bytes();
+        assert_eq!(decoder.decode(&cjk[..2]), "");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+        assert!(!decoder.has_pending());
+    }
+}
@@ -11,6 +11,7 @@
 //! - 需求 3.3: Anthropic SSE 到 OpenAI SSE 转换
 //! - 需求 3.5: 处理工具调用参数中的部分 JSON
 
+use crate::stream::Utf8ChunkDecoder;
 use crate::streaming::aws_parser::{AwsEvent, AwsEventStreamParser};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -164,6 +165,16 @@ pub struct StreamConverter {
     message_started: bool,
     /// 累积的内容（用于重建完整响应）
     accumulated_content: String,
+    /// SSE 源的增量 UTF-8 解码器（多字节字符可能跨 chunk）
+    utf8: Utf8ChunkDecoder,
+    /// Anthropic SSE 转 OpenAI 时暂存的不完整行（一行可能跨 chunk）
+    pending_line: String,
+    /// 上游 message_delta 给出的结束原因（已映射为 OpenAI finish_reason）
+    finish_reason: Option<&'static str>,
+    /// 上游报告的 Token 用量（输入、输出）
+    usage: Option<(u64, u64)>,
+    /// 是否已发送 OpenAI 结束 chunk 和 [DONE]
+    openai_finished: bool,
 }
 
 impl StreamConverter {
@@ -186,6 +197,11 @@ impl StreamConverter {
             next_content_block_index: 0,
             message_started: false,
             accumulated_content: String::new(),
+            utf8: Utf8ChunkDecoder::new(),
+            pending_line: String::new(),
+            finish_reason: None,
+            usage: None,
+            openai_finished: false,
         }
     }
 
@@ -222,6 +238,11 @@ impl StreamConverter {
         self.next_content_block_index = 0;
         self.message_started = false;
         self.accumulated_content.clear();
+        self.utf8.reset();
+        self.pending_line.clear();
+        self.finish_reason = None;
+        self.usage = None;
+        self.openai_finished = false;
     }
 
     /// 转换 chunk
@@ -261,6 +282,22 @@ impl StreamConverter {
             }
         }
 
+        // 输出 SSE 源暂存的不完整字节
+        let remainder = self.utf8.finish();
+        if !remainder.is_empty() {
+            match self.source_format {
+                StreamFormat::AnthropicSse => events.extend(self.convert_anthropic_text(remainder)),
+                StreamFormat::OpenAiSse => events.push(remainder),
+                StreamFormat::AwsEventStream => {}
+            }
+        }
+
+        // 处理缺少结尾换行的最后一行
+        if !self.pending_line.is_empty() {
+            let line = std::mem::take(&mut self.pending_line);
+            events.extend(self.anthropic_to_openai(&format!("{}\n", line)));
+        }
+
         // 生成结束事件
         events.extend(self.generate_end_events());
 
@@ -446,12 +483,16 @@ impl StreamConverter {
 
     /// 转换 Anthropic SSE（直通或转换为 OpenAI）
     fn convert_anthropic_sse(&mut self, chunk: &[u8]) -> Vec<String> {
-        // 解析 SSE 数据
-        let data = match String::from_utf8(chunk.to_vec()) {
-            Ok(s) => s,
-            Err(_) => return vec![],
-        };
+        // 解析 SSE 数据（只解码完整字符，跨 chunk 的多字节字符留待下一个 chunk）
+        let data = self.utf8.decode(chunk);
+        if data.is_empty() {
+            return vec![];
+        }
+        self.convert_anthropic_text(data)
+    }
 
+    /// 转换已解码的 Anthropic SSE 文本
+    fn convert_anthropic_text(&mut self, data: String) -> Vec<String> {
         match self.target_format {
             StreamFormat::AnthropicSse => {
                 // 直通
@@ -470,15 +511,29 @@ impl StreamConverter {
 
     /// Anthropic SSE 到 OpenAI SSE 转换
     ///
-    /// 对应需求 3.3
+    /// 对应需求 3.3。只处理完整的行，未以换行结尾的部分留待下一个 chunk；
+    /// 结束原因和用量取自 message_delta，在 message_stop 时随结束 chunk 一起发送。
     fn anthropic_to_openai(&mut self, data: &str) -> Vec<String> {
         let mut sse_events = Vec::new();
 
+        self.pending_line.push_str(data);
+        let complete = match self.pending_line.rfind('\n') {
+            Some(pos) => {
+                let rest = self.pending_line.split_off(pos + 1);
+                std::mem::replace(&mut self.pending_line, rest)
+            }
+            None => return sse_events,
+        };
+
         // 解析 SSE 事件
-        for line in data.lines() {
-            if let Some(json_str) = line.strip_prefix("data: ") {
+        for line in complete.lines() {
+            let line = line.trim_end_matches('\r');
+            if let Some(json_str) = line
+                .strip_prefix("data:")
+                .map(|rest| rest.strip_prefix(' ').unwrap_or(rest))
+            {
                 if json_str == "[DONE]" {
-                    sse_events.push("data: [DONE]\n\n".to_string());
+                    sse_events.extend(self.finish_openai_stream());
                     continue;
                 }
 
@@ -491,6 +546,12 @@ impl StreamConverter {
                                         self.accumulated_content.push_str(text);
                                         sse_events
                                             .push(self.create_openai_content_chunk(text, false));
+                                    } else if let Some(thinking) =
+                                        delta.get("thinking").and_then(|t| t.as_str())
+                                    {
+                                        // 思维链增量，单独放入 reasoning_content
+                                        sse_events
+                                            .push(self.create_openai_reasoning_chunk(thinking));
                                     } else if let Some(partial_json) =
                                         delta.get("partial_json").and_then(|t| t.as_str())
                                     {
@@ -554,9 +615,50 @@ impl StreamConverter {
                                     }
                                 }
                             }
+                            "message_start" => {
+                                if let Some(input) = event
+                                    .pointer("/message/usage/input_tokens")
+                                    .and_then(|v| v.as_u64())
+                                {
+                                    self.usage = Some((input, 0));
+                                }
+                                if !self.message_started {
+                                    self.message_started = true;
+                                    sse_events.push(self.create_openai_content_chunk("", true));
+                                }
+                            }
+                            "message_delta" => {
+                                if let Some(reason) =
+                                    event.pointer("/delta/stop_reason").and_then(|v| v.as_str())
+                                {
+                                    self.finish_reason = Some(map_anthropic_stop_reason(reason));
+                                }
+                                if let Some(output) = event
+                                    .pointer("/usage/output_tokens")
+                                    .and_then(|v| v.as_u64())
+                                {
+                                    let input = event
+                                        .pointer("/usage/input_tokens")
+                                        .and_then(|v| v.as_u64())
+                                        .or(self.usage.map(|(input, _)| input))
+                                        .unwrap_or(0);
+                                    self.usage = Some((input, output));
+                                }
+                            }
                             "message_stop" => {
-                                sse_events.push(self.create_openai_finish_chunk("stop"));
-                                sse_events.push("data: [DONE]\n\n".to_string());
+                                sse_events.extend(self.finish_openai_stream());
+                            }
+                            "error" => {
+                                let error = event.get("error").cloned().unwrap_or_else(|| {
+                                    serde_json::json!({
+                                        "type": "api_error",
+                                        "message": "Upstream stream error"
+                                    })
+                                });
+                                sse_events.push(format!(
+                                    "data: {}\n\n",
+                                    serde_json::json!({ "error": error })
+                                ));
                             }
                             _ => {}
                         }
@@ -570,9 +672,11 @@ impl StreamConverter {
 
     /// 转换 OpenAI SSE（直通）
     fn convert_openai_sse(&mut self, chunk: &[u8]) -> Vec<String> {
-        match String::from_utf8(chunk.to_vec()) {
-            Ok(s) => vec![s],
-            Err(_) => vec![],
+        let data = self.utf8.decode(chunk);
+        if data.is_empty() {
+            vec![]
+        } else {
+            vec![data]
         }
     }
 
@@ -587,23 +691,32 @@ impl StreamConverter {
                 events.push(self.create_anthropic_message_stop());
                 events
             }
-            StreamFormat::OpenAiSse => {
-                let finish_reason = if self.tool_accumulators.is_empty() {
-                    "stop"
-                } else {
-                    "tool_calls"
-                };
-                vec![
-                    self.create_openai_finish_chunk(finish_reason),
-                    "data: [DONE]\n\n".to_string(),
-                ]
-            }
+            StreamFormat::OpenAiSse => self.finish_openai_stream(),
             StreamFormat::AwsEventStream => {
                 vec![]
             }
         }
     }
 
+    /// 生成 OpenAI 结束 chunk 和 [DONE]（每个流只生成一次）
+    fn finish_openai_stream(&mut self) -> Vec<String> {
+        if self.openai_finished {
+            return vec![];
+        }
+        self.openai_finished = true;
+        let finish_reason = self
+            .finish_reason
+            .unwrap_or(if self.tool_accumulators.is_empty() {
+                "stop"
+            } else {
+                "tool_calls"
+            });
+        vec![
+            self.create_openai_finish_chunk(finish_reason),
+            "data: [DONE]\n\n".to_string(),
+        ]
+    }
+
     // ========================================================================
     // Anthropic SSE 事件创建辅助方法
     // ========================================================================
@@ -741,6 +854,23 @@ impl StreamConverter {
         format!("data: {}\n\n", chunk)
     }
 
+    fn create_openai_reasoning_chunk(&self, reasoning: &str) -> String {
+        let chunk = serde_json::json!({
+            "id": self.response_id,
+            "object": "chat.completion.chunk",
+            "created": self.get_created_timestamp(),
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": {
+                    "reasoning_content": reasoning
+                },
+                "finish_reason": null
+            }]
+        });
+        format!("data: {}\n\n", chunk)
+    }
+
     fn create_openai_tool_call_chunk(
         &self,
         index: u32,
@@ -785,7 +915,7 @@ impl StreamConverter {
     }
 
     fn create_openai_finish_chunk(&self, finish_reason: &str) -> String {
-        let chunk = serde_json::json!({
+        let mut chunk = serde_json::json!({
             "id": self.response_id,
             "object": "chat.completion.chunk",
             "created": self.get_created_timestamp(),
@@ -796,10 +926,27 @@ impl StreamConverter {
                 "finish_reason": finish_reason
             }]
         });
+        if let Some((input, output)) = self.usage {
+            chunk["usage"] = serde_json::json!({
+                "prompt_tokens": input,
+                "completion_tokens": output,
+                "total_tokens": input + output
+            });
+        }
         format!("data: {}\n\n", chunk)
     }
 }
 
+/// 将 Anthropic stop_reason 映射为 OpenAI finish_reason
+fn map_anthropic_stop_reason(reason: &str) -> &'static str {
+    match reason {
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "refusal" => "content_filter",
+        _ => "stop",
+    }
+}
+
 // ============================================================================
 // 辅助函数
 // ============================================================================
@@ -1149,6 +1296,86 @@ mod tests {
         assert_eq!(content, "Hello, world!");
     }
 
+    #[test]
+    fn test_anthropic_to_openai_thinking_delta() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AnthropicSse,
+            StreamFormat::OpenAiSse,
+            "claude-sonnet-4-5",
+        );
+
+        let events = converter.convert(
+            b"event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"Let me think\"}}\n\n\
+event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"text_delta\",\"text\":\"Answer\"}}\n\n",
+        );
+        assert_eq!(events.len(), 2);
+
+        let reasoning: serde_json::Value =
+            serde_json::from_str(events[0].trim().strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(
+            reasoning["choices"][0]["delta"]["reasoning_content"],
+            "Let me think"
+        );
+        assert!(reasoning["choices"][0]["delta"].get("content").is_none());
+
+        // 思维链不会混入正文
+        let content = extract_content_from_sse(&events, StreamFormat::OpenAiSse);
+        assert_eq!(content, "Answer");
+        assert_eq!(converter.accumulated_content(), "Answer");
+    }
+
+    #[test]
+    fn test_anthropic_to_openai_split_lines_and_stop_reason() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AnthropicSse,
+            StreamFormat::OpenAiSse,
+            "claude-sonnet-4-5",
+        );
+        let sse = "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n\
+event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n\
+event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n\
+event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"max_tokens\"},\"usage\":{\"output_tokens\":5}}\n\n\
+event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+
+        // 在 text_delta 的 JSON 中间切分
+        let split = sse.find("Hello").unwrap() + 2;
+        let mut events = converter.convert(&sse.as_bytes()[..split]);
+        events.extend(converter.convert(&sse.as_bytes()[split..]));
+        events.extend(converter.finish());
+
+        let chunks: Vec<serde_json::Value> = events
+            .iter()
+            .filter_map(|e| e.trim().strip_prefix("data: "))
+            .filter(|d| *d != "[DONE]")
+            .map(|d| serde_json::from_str(d).unwrap())
+            .collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0]["object"], "chat.completion.chunk");
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(chunks[1]["choices"][0]["delta"]["content"], "Hello");
+        assert_eq!(chunks[2]["choices"][0]["finish_reason"], "length");
+        assert_eq!(chunks[2]["usage"]["prompt_tokens"], 12);
+        assert_eq!(chunks[2]["usage"]["completion_tokens"], 5);
+
+        // message_stop 之后 finish() 不再重复发送结束事件
+        assert_eq!(events.iter().filter(|e| e.contains("[DONE]")).count(), 1);
+    }
+
+    #[test]
+    fn test_openai_passthrough_split_multibyte() {
+        let mut converter = StreamConverter::new(StreamFormat::OpenAiSse, StreamFormat::OpenAiSse);
+        let sse = "data: {\"choices\":[{\"delta\":{\"content\":\"你好🌏\"}}]}\n\n";
+        let bytes = sse.as_bytes();
+        // 在 "好" 的第二个字节处切分
+        let split = sse.find('好').unwrap() + 1;
+
+        let mut output = converter.convert(&bytes[..split]).concat();
+        output.push_str(&converter.convert(&bytes[split..]).concat());
+
+        assert_eq!(output, sse);
+        assert!(!output.contains(char::REPLACEMENT_CHARACTER));
+    }
+
     #[test]
     fn test_incremental_conversion() {
         let mut converter = StreamConverter::with_model(
@@ -126,6 +126,8 @@ impl StreamFormat {
 pub fn reqwest_stream_to_stream_response(response: reqwest::Response) -> StreamResponse {
     use futures::StreamExt;
 
+    crate::http_client::capture_response_headers(response.headers());
+
     let stream = response
         .bytes_stream()
         .map(|result| result.map_err(|e| StreamError::from(e)));
@@ -198,6 +198,41 @@ impl RequestLogger {
             .collect()
     }
 
+    /// 更新内存中日志的响应体大小（流式响应结束后回填）
+    ///
+    /// 日志文件中的记录保持写入时的值。返回是否找到对应的日志。
+    pub fn set_response_bytes(&self, id: &str, bytes: u64) -> bool {
+        let mut logs = self.logs.write();
+        match logs.iter_mut().rev().find(|l| l.id == id) {
+            Some(log) => {
+                log.response_bytes = Some(bytes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 将内存中的日志标记为因费用上限中止（流式响应结束后回填）
+    ///
+    /// 日志文件中的记录保持写入时的值。返回是否找到对应的日志。
+    pub fn set_cost_aborted(
+        &self,
+        id: &str,
+        message: String,
+        input_tokens: u32,
+        output_tokens: u32,
+    ) -> bool {
+        let mut logs = self.logs.write();
+        match logs.iter_mut().rev().find(|l| l.id == id) {
+            Some(log) => {
+                log.mark_cost_aborted(log.duration_ms, message);
+                log.set_tokens(Some(input_tokens), Some(output_tokens));
+                true
+            }
+            None => false,
+        }
+    }
+
     /// 获取指定 ID 的日志
     pub fn get_by_id(&self, id: &str) -> Option<RequestLog> {
         self.logs.read().iter().find(|log| log.id == id).cloned()
@@ -209,7 +244,7 @@ impl RequestLogger {
             Some(r) => self.get_by_time_range(r),
             None => self.get_all(),
         };
-        StatsSummary::from_logs(&logs)
+        StatsSummary::from_logs_with_model_bytes(&logs)
     }
 
     /// 按 Provider 分组统计
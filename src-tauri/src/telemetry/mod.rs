@@ -1,19 +1,25 @@
 //! 监控与日志模块
 //!
-//! 提供请求日志记录、统计聚合和 Token 追踪功能
+//! 提供请求日志记录、统计聚合、Token 追踪和 OpenTelemetry 链路导出功能
 
 mod logger;
+pub mod otlp;
+pub mod pricing;
 mod stats;
 mod tokens;
 mod types;
 
 pub use logger::{LogRotationConfig, LoggerError, RequestLogger};
+pub use pricing::ModelPrice;
 pub use stats::StatsAggregator;
 pub use tokens::{
-    ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenSource, TokenStatsSummary,
-    TokenTracker, TokenUsageRecord,
+    AnthropicStreamUsage, AnthropicUsage, ModelTokenStats, PeriodTokenStats, ProviderTokenStats,
+    SessionCostSummary, TokenSource, TokenStatsSummary, TokenTracker, TokenUsageRecord,
+};
+pub use types::{
+    ModelBytes, ModelStats, ProviderStats, RequestKind, RequestLog, RequestStatus, StatsSummary,
+    TimeRange, UserStats,
 };
-pub use types::{ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange};
 
 #[cfg(test)]
 mod tests;
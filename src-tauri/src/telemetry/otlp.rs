@@ -0,0 +1,485 @@
+//! OpenTelemetry 链路导出
+//!
+//! 每个请求导出为一条链路：根 span `proxycast.request` 携带 provider、model、status、
+//! credential、retries 属性，子 span 由请求管道追踪步骤推算：
+//! `routing`（别名解析与 Provider 选择）、`injection`（参数注入）、
+//! `upstream_call`（每次选择凭证后的上游调用，重试和故障转移记为事件）。
+//!
+//! 需要以 `otlp` 特性编译，且配置了 `telemetry.otlp_endpoint` 时才导出（OTLP/HTTP），
+//! 由批处理器在后台上报，不阻塞请求。配置通过 [`configure`] 在启动和配置变更时更新。
+
+use crate::config::Config;
+use crate::processor::RequestContext;
+use crate::telemetry::RequestStatus;
+
+#[cfg(any(feature = "otlp", test))]
+use crate::processor::{TraceStep, TraceStepKind};
+#[cfg(any(feature = "otlp", test))]
+use chrono::{DateTime, Utc};
+
+/// 更新导出配置（地址或服务名变化时重建导出器）
+pub fn configure(config: &Config) {
+    #[cfg(feature = "otlp")]
+    exporter::configure(&config.telemetry);
+
+    #[cfg(not(feature = "otlp"))]
+    if config.telemetry.otlp_endpoint.is_some() {
+        tracing::warn!("[OTLP] 当前构建未启用 otlp 特性，忽略 telemetry.otlp_endpoint");
+    }
+}
+
+/// 导出一个已结束的请求
+pub fn export_request(ctx: &RequestContext, status: RequestStatus, error_message: Option<&str>) {
+    #[cfg(feature = "otlp")]
+    exporter::export(ctx, status, error_message);
+
+    #[cfg(not(feature = "otlp"))]
+    let _ = (ctx, status, error_message);
+}
+
+/// 根据追踪步骤推算出的子 span
+#[cfg(any(feature = "otlp", test))]
+#[derive(Debug, Clone, PartialEq)]
+struct PlannedSpan {
+    name: &'static str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    attributes: Vec<(&'static str, String)>,
+    events: Vec<PlannedEvent>,
+}
+
+#[cfg(any(feature = "otlp", test))]
+#[derive(Debug, Clone, PartialEq)]
+struct PlannedEvent {
+    name: &'static str,
+    timestamp: DateTime<Utc>,
+    attributes: Vec<(&'static str, String)>,
+}
+
+/// 由追踪步骤推算子 span
+///
+/// 步骤只记录发生时刻，单步 span 的起点取上一步的时刻（第一步取请求开始时间）；
+/// `upstream_call` 从选择凭证开始，到上游响应、请求失败或下一次选择凭证为止。
+#[cfg(any(feature = "otlp", test))]
+fn plan_spans(
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    steps: &[TraceStep],
+) -> Vec<PlannedSpan> {
+    let mut spans = Vec::new();
+    let mut routing: Option<PlannedSpan> = None;
+    let mut upstream: Option<PlannedSpan> = None;
+    let mut previous = started_at;
+
+    for step in steps {
+        let at = step.timestamp;
+        match &step.kind {
+            TraceStepKind::AliasResolved { from, to } => {
+                let span = routing.get_or_insert_with(|| new_span("routing", started_at));
+                span.end = at;
+                span.attributes.push(("model.alias_from", from.clone()));
+                span.attributes.push(("model.alias_to", to.clone()));
+            }
+            TraceStepKind::ProviderSelected { provider, reason } => {
+                let span = routing.get_or_insert_with(|| new_span("routing", started_at));
+                span.end = at;
+                span.attributes.push(("provider", provider.clone()));
+                span.attributes.push(("routing.reason", reason.clone()));
+            }
+            TraceStepKind::CacheHit => {
+                let span = routing.get_or_insert_with(|| new_span("routing", started_at));
+                span.end = at;
+                span.attributes.push(("cache.hit", "true".to_string()));
+            }
+            TraceStepKind::InjectionApplied {
+                applied_rules,
+                injected_params,
+            } => {
+                let mut injection = new_span("injection", previous);
+                injection.end = at;
+                injection
+                    .attributes
+                    .push(("injection.rules", applied_rules.join(",")));
+                injection
+                    .attributes
+                    .push(("injection.params", injected_params.join(",")));
+                spans.push(injection);
+            }
+            TraceStepKind::CredentialSelected {
+                provider,
+                credential_id,
+                source,
+                ..
+            } => {
+                if let Some(mut call) = upstream.take() {
+                    call.end = at;
+                    spans.push(call);
+                }
+                let mut call = new_span("upstream_call", at);
+                call.attributes.push(("provider", provider.clone()));
+                if let Some(id) = credential_id {
+                    call.attributes.push(("credential", id.clone()));
+                }
+                call.attributes.push(("credential.source", source.clone()));
+                upstream = Some(call);
+            }
+            TraceStepKind::AnthropicHeaders { version, beta } => {
+                if let Some(call) = upstream.as_mut() {
+                    call.attributes.push(("anthropic.version", version.clone()));
+                    if let Some(beta) = beta {
+                        call.attributes.push(("anthropic.beta", beta.clone()));
+                    }
+                }
+            }
+            TraceStepKind::Retry {
+                attempt,
+                status_code,
+                error,
+            } => {
+                if let Some(call) = upstream.as_mut() {
+                    let mut attributes =
+                        vec![("attempt", attempt.to_string()), ("error", error.clone())];
+                    if let Some(code) = status_code {
+                        attributes.push(("http.status_code", code.to_string()));
+                    }
+                    call.events.push(PlannedEvent {
+                        name: "retry",
+                        timestamp: at,
+                        attributes,
+                    });
+                }
+            }
+            TraceStepKind::Failover { from, to, reason } => {
+                if let Some(call) = upstream.as_mut() {
+                    call.events.push(PlannedEvent {
+                        name: "failover",
+                        timestamp: at,
+                        attributes: vec![
+                            ("from", from.clone()),
+                            ("to", to.clone()),
+                            ("reason", reason.clone()),
+                        ],
+                    });
+                }
+            }
+            TraceStepKind::UpstreamResponse { status_code } => {
+                if let Some(mut call) = upstream.take() {
+                    call.end = at;
+                    call.attributes
+                        .push(("http.status_code", status_code.to_string()));
+                    spans.push(call);
+                }
+            }
+            TraceStepKind::Failed { error } => {
+                if let Some(mut call) = upstream.take() {
+                    call.end = at;
+                    call.attributes.push(("error", error.clone()));
+                    spans.push(call);
+                }
+            }
+        }
+        previous = at;
+    }
+
+    if let Some(mut call) = upstream {
+        call.end = ended_at;
+        spans.push(call);
+    }
+    if let Some(routing) = routing {
+        spans.insert(0, routing);
+    }
+    spans
+}
+
+#[cfg(any(feature = "otlp", test))]
+fn new_span(name: &'static str, start: DateTime<Utc>) -> PlannedSpan {
+    PlannedSpan {
+        name,
+        start,
+        end: start,
+        attributes: Vec::new(),
+        events: Vec::new(),
+    }
+}
+
+/// 补全 OTLP/HTTP 链路路径（配置中通常只写到端口）
+#[cfg(any(feature = "otlp", test))]
+fn traces_endpoint(endpoint: &str) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    }
+}
+
+#[cfg(feature = "otlp")]
+mod exporter {
+    use super::*;
+    use crate::config::TelemetrySettings;
+    use once_cell::sync::Lazy;
+    use opentelemetry::trace::{
+        Span, SpanKind, Status, TraceContextExt, Tracer, TracerProvider as _,
+    };
+    use opentelemetry::{Context, KeyValue};
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::trace::{self as sdktrace, TracerProvider};
+    use opentelemetry_sdk::{runtime, Resource};
+    use parking_lot::Mutex;
+    use std::time::SystemTime;
+
+    #[derive(Default)]
+    struct ExporterState {
+        settings: TelemetrySettings,
+        /// 首次导出时在 tokio 运行时内创建（批处理器需要运行时）
+        provider: Option<TracerProvider>,
+    }
+
+    static STATE: Lazy<Mutex<ExporterState>> = Lazy::new(|| Mutex::new(ExporterState::default()));
+
+    pub(super) fn configure(settings: &TelemetrySettings) {
+        let mut state = STATE.lock();
+        if state.settings == *settings {
+            return;
+        }
+        state.settings = settings.clone();
+        if let Some(provider) = state.provider.take() {
+            shutdown(provider);
+        }
+        match &settings.otlp_endpoint {
+            Some(endpoint) => tracing::info!("[OTLP] 链路导出地址: {}", endpoint),
+            None => tracing::info!("[OTLP] 链路导出已关闭"),
+        }
+    }
+
+    /// 关闭旧的导出器
+    ///
+    /// shutdown 会阻塞等待剩余 span 上报完成，放到独立线程避免阻塞调用方。
+    fn shutdown(provider: TracerProvider) {
+        std::thread::spawn(move || {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("[OTLP] 关闭导出器失败: {}", e);
+            }
+        });
+    }
+
+    fn tracer() -> Option<sdktrace::Tracer> {
+        let mut state = STATE.lock();
+        let endpoint = state.settings.otlp_endpoint.clone()?;
+        if state.provider.is_none() {
+            match build_provider(&endpoint, &state.settings.service_name) {
+                Ok(provider) => state.provider = Some(provider),
+                Err(e) => {
+                    // 清除地址，避免每个请求重复创建；配置变更后重试
+                    tracing::warn!("[OTLP] 创建导出器失败，已停用链路导出: {}", e);
+                    state.settings.otlp_endpoint = None;
+                    return None;
+                }
+            }
+        }
+        state
+            .provider
+            .as_ref()
+            .map(|provider| provider.tracer("proxycast"))
+    }
+
+    fn build_provider(
+        endpoint: &str,
+        service_name: &str,
+    ) -> Result<TracerProvider, opentelemetry::trace::TraceError> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(traces_endpoint(endpoint))
+            .build()?;
+        Ok(TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                service_name.to_string(),
+            )]))
+            .build())
+    }
+
+    pub(super) fn export(ctx: &RequestContext, status: RequestStatus, error_message: Option<&str>) {
+        let Some(tracer) = tracer() else {
+            return;
+        };
+        let ended_at = Utc::now();
+
+        let mut attributes = vec![
+            KeyValue::new("request_id", ctx.request_id.clone()),
+            KeyValue::new(
+                "provider",
+                ctx.provider.map(|p| p.to_string()).unwrap_or_default(),
+            ),
+            KeyValue::new("model", ctx.resolved_model.clone()),
+            KeyValue::new("status", status.to_string()),
+            KeyValue::new("retries", ctx.retry_count as i64),
+            KeyValue::new("stream", ctx.is_stream),
+        ];
+        if let Some(credential_id) = &ctx.credential_id {
+            attributes.push(KeyValue::new("credential", credential_id.clone()));
+        }
+        if ctx.original_model != ctx.resolved_model {
+            attributes.push(KeyValue::new("model.requested", ctx.original_model.clone()));
+        }
+
+        let mut root = tracer
+            .span_builder("proxycast.request")
+            .with_kind(SpanKind::Server)
+            .with_start_time(SystemTime::from(ctx.timestamp))
+            .with_attributes(attributes)
+            .start(&tracer);
+        if status != RequestStatus::Success {
+            root.set_status(Status::error(error_message.unwrap_or_default().to_string()));
+        }
+        let cx = Context::new().with_span(root);
+
+        for planned in plan_spans(ctx.timestamp, ended_at, &ctx.trace) {
+            let kind = if planned.name == "upstream_call" {
+                SpanKind::Client
+            } else {
+                SpanKind::Internal
+            };
+            let mut span = tracer
+                .span_builder(planned.name)
+                .with_kind(kind)
+                .with_start_time(SystemTime::from(planned.start))
+                .with_attributes(
+                    planned
+                        .attributes
+                        .into_iter()
+                        .map(|(key, value)| KeyValue::new(key, value)),
+                )
+                .start_with_context(&tracer, &cx);
+            for event in planned.events {
+                span.add_event_with_timestamp(
+                    event.name,
+                    SystemTime::from(event.timestamp),
+                    event
+                        .attributes
+                        .into_iter()
+                        .map(|(key, value)| KeyValue::new(key, value))
+                        .collect(),
+                );
+            }
+            span.end_with_timestamp(SystemTime::from(planned.end));
+        }
+
+        cx.span().end_with_timestamp(SystemTime::from(ended_at));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn step(started_at: DateTime<Utc>, ms: i64, kind: TraceStepKind) -> TraceStep {
+        TraceStep {
+            elapsed_ms: ms as u64,
+            timestamp: started_at + Duration::milliseconds(ms),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_plan_spans_from_trace_steps() {
+        let t0 = Utc::now();
+        let steps = vec![
+            step(
+                t0,
+                2,
+                TraceStepKind::InjectionApplied {
+                    applied_rules: vec!["r1".to_string()],
+                    injected_params: vec!["temperature".to_string()],
+                },
+            ),
+            step(
+                t0,
+                5,
+                TraceStepKind::ProviderSelected {
+                    provider: "kiro".to_string(),
+                    reason: "default".to_string(),
+                },
+            ),
+            step(
+                t0,
+                6,
+                TraceStepKind::CredentialSelected {
+                    provider: "kiro".to_string(),
+                    credential_id: Some("cred-1".to_string()),
+                    credential_name: None,
+                    source: "pool".to_string(),
+                },
+            ),
+            step(
+                t0,
+                50,
+                TraceStepKind::Retry {
+                    attempt: 1,
+                    status_code: Some(429),
+                    error: "rate limited".to_string(),
+                },
+            ),
+            step(
+                t0,
+                120,
+                TraceStepKind::UpstreamResponse { status_code: 200 },
+            ),
+        ];
+
+        let spans = plan_spans(t0, t0 + Duration::milliseconds(130), &steps);
+        let names: Vec<_> = spans.iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["routing", "injection", "upstream_call"]);
+
+        assert_eq!(spans[0].start, t0);
+        assert_eq!(spans[0].end, t0 + Duration::milliseconds(5));
+        assert_eq!(spans[1].start, t0);
+        assert_eq!(spans[1].end, t0 + Duration::milliseconds(2));
+
+        let call = &spans[2];
+        assert_eq!(call.start, t0 + Duration::milliseconds(6));
+        assert_eq!(call.end, t0 + Duration::milliseconds(120));
+        assert!(call
+            .attributes
+            .contains(&("credential", "cred-1".to_string())));
+        assert!(call
+            .attributes
+            .contains(&("http.status_code", "200".to_string())));
+        assert_eq!(call.events.len(), 1);
+        assert_eq!(call.events[0].name, "retry");
+    }
+
+    #[test]
+    fn test_unfinished_upstream_call_ends_with_request() {
+        let t0 = Utc::now();
+        let end = t0 + Duration::milliseconds(300);
+        let steps = vec![step(
+            t0,
+            10,
+            TraceStepKind::CredentialSelected {
+                provider: "openai".to_string(),
+                credential_id: None,
+                credential_name: None,
+                source: "api_key_provider".to_string(),
+            },
+        )];
+
+        let spans = plan_spans(t0, end, &steps);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].end, end);
+    }
+
+    #[test]
+    fn test_traces_endpoint() {
+        assert_eq!(
+            traces_endpoint("http://localhost:4318"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_endpoint("http://tempo:4318/v1/traces/"),
+            "http://tempo:4318/v1/traces"
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! 模型价格表
+//!
+//! 按模型 ID 查询每百万 token 的美元价格，用于估算请求费用。
+//! 价格来自模型注册表，加载或重新加载模型数据时通过 [`set_prices`] 更新；
+//! 配置中的 `price_table` 通过 [`configure`] 覆盖同名模型的价格，可在运行时热更新。
+
+use crate::config::{Config, PriceTableEntry};
+use crate::models::model_registry::EnhancedModelMetadata;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+
+/// 价格表未提供缓存写入价格时，按输入价格的倍率计算（Anthropic 5 分钟缓存）
+const CACHE_WRITE_MULTIPLIER: f64 = 1.25;
+/// 价格表未提供缓存读取价格时，按输入价格的倍率计算
+const CACHE_READ_MULTIPLIER: f64 = 0.1;
+
+/// 模型价格（美元 / 百万 token）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    /// 输入价格
+    pub input_per_million: f64,
+    /// 输出价格
+    pub output_per_million: f64,
+    /// 缓存写入价格
+    pub cache_write_per_million: Option<f64>,
+    /// 缓存读取价格
+    pub cache_read_per_million: Option<f64>,
+}
+
+impl ModelPrice {
+    /// 估算费用（美元）
+    pub fn cost_usd(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (input_tokens as f64 * self.input_per_million
+            + output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+
+    /// 估算包含提示缓存的费用（美元）
+    ///
+    /// `input_tokens` 不含缓存 token（与 Anthropic `usage` 一致）。
+    pub fn cost_usd_with_cache(
+        &self,
+        input_tokens: u32,
+        output_tokens: u32,
+        cache_creation_input_tokens: u32,
+        cache_read_input_tokens: u32,
+    ) -> f64 {
+        let cache_write = self
+            .cache_write_per_million
+            .unwrap_or(self.input_per_million * CACHE_WRITE_MULTIPLIER);
+        let cache_read = self
+            .cache_read_per_million
+            .unwrap_or(self.input_per_million * CACHE_READ_MULTIPLIER);
+        self.cost_usd(input_tokens, output_tokens)
+            + (cache_creation_input_tokens as f64 * cache_write
+                + cache_read_input_tokens as f64 * cache_read)
+                / 1_000_000.0
+    }
+}
+
+impl From<PriceTableEntry> for ModelPrice {
+    fn from(entry: PriceTableEntry) -> Self {
+        Self {
+            input_per_million: entry.input_per_million,
+            output_per_million: entry.output_per_million,
+            cache_write_per_million: entry.cache_write_per_million,
+            cache_read_per_million: entry.cache_read_per_million,
+        }
+    }
+}
+
+fn max_price(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[derive(Default)]
+struct PriceTables {
+    /// 来自模型注册表的价格
+    registry: HashMap<String, ModelPrice>,
+    /// 来自配置的价格
+    overrides: HashMap<String, ModelPrice>,
+    /// 合并后的价格（配置优先）
+    effective: HashMap<String, ModelPrice>,
+}
+
+impl PriceTables {
+    fn rebuild(&mut self) {
+        let mut effective = self.registry.clone();
+        effective.extend(self.overrides.iter().map(|(k, v)| (k.clone(), *v)));
+        self.effective = effective;
+    }
+}
+
+/// 模型 ID（小写）-> 价格
+static PRICES: Lazy<RwLock<PriceTables>> = Lazy::new(|| RwLock::new(PriceTables::default()));
+
+/// 替换模型注册表提供的价格表
+pub fn set_prices(prices: HashMap<String, ModelPrice>) {
+    tracing::info!("[PRICING] 更新价格表: {} 个模型", prices.len());
+    let mut tables = PRICES.write();
+    tables.registry = prices;
+    tables.rebuild();
+}
+
+/// 从配置更新价格覆盖，并检查路由引用的模型是否都有价格
+pub fn configure(config: &Config) {
+    let overrides: HashMap<String, ModelPrice> = config
+        .price_table
+        .iter()
+        .filter(|(_, entry)| entry.is_valid())
+        .map(|(model, entry)| (model.to_lowercase(), ModelPrice::from(*entry)))
+        .collect();
+    {
+        let mut tables = PRICES.write();
+        if tables.overrides != overrides {
+            tracing::info!("[PRICING] 更新配置价格表: {} 个模型", overrides.len());
+            tables.overrides = overrides;
+            tables.rebuild();
+        }
+    }
+
+    let missing = missing_prices(&routing_models(config));
+    if !missing.is_empty() {
+        tracing::warn!(
+            "[PRICING] 以下路由模型没有价格，费用统计将为 0: {}",
+            missing.join(", ")
+        );
+    }
+}
+
+/// 路由配置中引用的模型（模型别名的目标模型），已去重排序
+pub fn routing_models(config: &Config) -> Vec<String> {
+    let mut models: Vec<String> = config
+        .routing
+        .model_aliases
+        .values()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+    models.sort();
+    models.dedup();
+    models
+}
+
+/// 返回没有价格的模型
+pub fn missing_prices(models: &[String]) -> Vec<String> {
+    let tables = PRICES.read();
+    models
+        .iter()
+        .filter(|m| lookup(&tables.effective, m).is_none())
+        .cloned()
+        .collect()
+}
+
+/// 从模型注册表数据构建价格表
+///
+/// 只使用美元定价；同一模型有多个来源时取最高价格，估算偏保守。
+pub fn prices_from_models(models: &[EnhancedModelMetadata]) -> HashMap<String, ModelPrice> {
+    let mut prices: HashMap<String, ModelPrice> = HashMap::new();
+    for model in models {
+        let Some(pricing) = &model.pricing else {
+            continue;
+        };
+        if !pricing.currency.eq_ignore_ascii_case("USD") {
+            continue;
+        }
+        let (Some(input), Some(output)) = (pricing.input_per_million, pricing.output_per_million)
+        else {
+            continue;
+        };
+        let price = ModelPrice {
+            input_per_million: input,
+            output_per_million: output,
+            cache_write_per_million: pricing.cache_write_per_million,
+            cache_read_per_million: pricing.cache_read_per_million,
+        };
+        prices
+            .entry(model.id.to_lowercase())
+            .and_modify(|existing| {
+                existing.input_per_million = existing.input_per_million.max(input);
+                existing.output_per_million = existing.output_per_million.max(output);
+                existing.cache_write_per_million = max_price(
+                    existing.cache_write_per_million,
+                    price.cache_write_per_million,
+                );
+                existing.cache_read_per_million = max_price(
+                    existing.cache_read_per_million,
+                    price.cache_read_per_million,
+                );
+            })
+            .or_insert(price);
+    }
+    prices
+}
+
+/// 查询模型价格
+///
+/// 先精确匹配，再匹配最长的模型 ID 前缀（兼容带日期后缀的模型名）。
+pub fn price_for(model: &str) -> Option<ModelPrice> {
+    lookup(&PRICES.read().effective, model)
+}
+
+/// 估算请求费用（美元），价格未知时返回 None
+pub fn estimate_cost_usd(model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+    price_for(model).map(|p| p.cost_usd(input_tokens, output_tokens))
+}
+
+fn lookup(prices: &HashMap<String, ModelPrice>, model: &str) -> Option<ModelPrice> {
+    let model = model.to_lowercase();
+    if let Some(price) = prices.get(&model) {
+        return Some(*price);
+    }
+    prices
+        .iter()
+        .filter(|(id, _)| model.starts_with(id.as_str()))
+        .max_by_key(|(id, _)| id.len())
+        .map(|(_, price)| *price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(input: f64, output: f64) -> ModelPrice {
+        ModelPrice {
+            input_per_million: input,
+            output_per_million: output,
+            cache_write_per_million: None,
+            cache_read_per_million: None,
+        }
+    }
+
+    #[test]
+    fn test_cost_usd() {
+        let p = price(3.0, 15.0);
+        let cost = p.cost_usd(1_000_000, 100_000);
+        assert!((cost - 4.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cost_usd_with_cache() {
+        // 未配置缓存价格时按输入价格倍率计算：写入 3.75，读取 0.3
+        let p = price(3.0, 15.0);
+        let cost = p.cost_usd_with_cache(0, 0, 1_000_000, 1_000_000);
+        assert!((cost - 4.05).abs() < 1e-9);
+
+        let p = ModelPrice {
+            cache_write_per_million: Some(6.0),
+            cache_read_per_million: Some(0.5),
+            ..price(3.0, 15.0)
+        };
+        let cost = p.cost_usd_with_cache(1_000_000, 0, 1_000_000, 2_000_000);
+        assert!((cost - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lookup_exact_then_longest_prefix() {
+        let mut prices = HashMap::new();
+        prices.insert("claude-sonnet-4".to_string(), price(3.0, 15.0));
+        prices.insert("claude-sonnet-4-5".to_string(), price(4.0, 20.0));
+
+        assert_eq!(lookup(&prices, "Claude-Sonnet-4"), Some(price(3.0, 15.0)));
+        assert_eq!(
+            lookup(&prices, "claude-sonnet-4-5-20250929"),
+            Some(price(4.0, 20.0))
+        );
+        assert!(lookup(&prices, "gpt-4o").is_none());
+    }
+
+    #[test]
+    fn test_overrides_take_precedence_over_registry() {
+        let mut tables = PriceTables::default();
+        tables
+            .registry
+            .insert("claude-sonnet-4".to_string(), price(3.0, 15.0));
+        tables
+            .registry
+            .insert("gpt-4o".to_string(), price(2.5, 10.0));
+        tables
+            .overrides
+            .insert("claude-sonnet-4".to_string(), price(1.0, 5.0));
+        tables.rebuild();
+
+        assert_eq!(
+            lookup(&tables.effective, "claude-sonnet-4-20250514"),
+            Some(price(1.0, 5.0))
+        );
+        assert_eq!(lookup(&tables.effective, "gpt-4o"), Some(price(2.5, 10.0)));
+
+        // 移除覆盖后恢复注册表价格
+        tables.overrides.clear();
+        tables.rebuild();
+        assert_eq!(
+            lookup(&tables.effective, "claude-sonnet-4"),
+            Some(price(3.0, 15.0))
+        );
+    }
+
+    #[test]
+    fn test_routing_models_dedup() {
+        let mut config = Config::default();
+        config
+            .routing
+            .model_aliases
+            .insert("sonnet".to_string(), "claude-sonnet-4".to_string());
+        config
+            .routing
+            .model_aliases
+            .insert("default".to_string(), "claude-sonnet-4".to_string());
+        config
+            .routing
+            .model_aliases
+            .insert("fast".to_string(), "gemini-2.5-flash".to_string());
+
+        assert_eq!(
+            routing_models(&config),
+            vec![
+                "claude-sonnet-4".to_string(),
+                "gemini-2.5-flash".to_string()
+            ]
+        );
+    }
+}
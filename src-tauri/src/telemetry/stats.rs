@@ -3,7 +3,7 @@
 //! 提供请求统计的聚合、分组和查询功能
 
 use crate::telemetry::types::{
-    ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange,
+    ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange, UserStats,
 };
 use crate::ProviderType;
 use chrono::{Duration, Utc};
@@ -70,7 +70,7 @@ impl StatsAggregator {
     /// * `range` - 可选的时间范围，如果为 None 则统计所有日志
     pub fn summary(&self, range: Option<TimeRange>) -> StatsSummary {
         let logs = self.get_logs_in_range(range);
-        StatsSummary::from_logs(&logs)
+        StatsSummary::from_logs_with_model_bytes(&logs)
     }
 
     /// 获取指定时间范围内的日志
@@ -86,6 +86,20 @@ impl StatsAggregator {
         }
     }
 
+    /// 更新已记录请求的响应体大小（流式响应结束后回填）
+    ///
+    /// 返回是否找到对应的日志
+    pub fn set_response_bytes(&self, id: &str, bytes: u64) -> bool {
+        let mut logs = self.logs.write();
+        match logs.iter_mut().rev().find(|l| l.id == id) {
+            Some(log) => {
+                log.response_bytes = Some(bytes);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// 获取所有日志
     pub fn get_all(&self) -> Vec<RequestLog> {
         self.logs.read().iter().cloned().collect()
@@ -184,6 +198,28 @@ impl StatsAggregator {
             .collect()
     }
 
+    /// 按终端用户分组统计
+    ///
+    /// 只统计携带用户标识的请求
+    pub fn by_user(&self, range: Option<TimeRange>) -> HashMap<String, UserStats> {
+        let logs = self.get_logs_in_range(range);
+
+        let mut grouped: HashMap<String, Vec<RequestLog>> = HashMap::new();
+        for log in logs {
+            if let Some(user_id) = log.user_id.clone() {
+                grouped.entry(user_id).or_default().push(log);
+            }
+        }
+
+        grouped
+            .into_iter()
+            .map(|(user_id, logs)| {
+                let stats = UserStats::from_logs(user_id.clone(), &logs);
+                (user_id, stats)
+            })
+            .collect()
+    }
+
     /// 按 Provider 和模型分组统计
     ///
     /// # Arguments
@@ -499,6 +499,29 @@ fn create_test_aggregator() -> StatsAggregator {
     StatsAggregator::new(Duration::days(7), 10000)
 }
 
+#[test]
+fn test_stats_by_user_skips_anonymous_requests() {
+    let aggregator = create_test_aggregator();
+
+    for user in [Some("alice"), Some("bob"), Some("alice"), None] {
+        let mut log = RequestLog::new(
+            uuid::Uuid::new_v4().to_string(),
+            ProviderType::Kiro,
+            "model".to_string(),
+            false,
+        );
+        log.user_id = user.map(str::to_string);
+        log.mark_success(100, 200);
+        aggregator.record(log);
+    }
+
+    let stats = aggregator.by_user(None);
+
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats["alice"].summary.total_requests, 2);
+    assert_eq!(stats["bob"].summary.total_requests, 1);
+}
+
 proptest! {
     /// **Feature: enhancement-roadmap, Property 14: 统计准确性**
     /// *对于任意* 时间范围，统计的总请求数应等于该范围内日志条目数
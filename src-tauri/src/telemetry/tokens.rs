@@ -4,11 +4,12 @@
 
 #![allow(dead_code)]
 
+use super::pricing;
 use crate::ProviderType;
 use chrono::{DateTime, Duration, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Token 使用记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,8 +26,14 @@ pub struct TokenUsageRecord {
     pub input_tokens: u32,
     /// 输出 Token 数
     pub output_tokens: u32,
-    /// 总 Token 数
+    /// 总 Token 数（输入 + 输出，不含缓存 token）
     pub total_tokens: u32,
+    /// 写入提示缓存的输入 Token 数（Anthropic `cache_creation_input_tokens`）
+    #[serde(default)]
+    pub cache_creation_input_tokens: u32,
+    /// 命中提示缓存的输入 Token 数（Anthropic `cache_read_input_tokens`）
+    #[serde(default)]
+    pub cache_read_input_tokens: u32,
     /// Token 来源（实际值或估算值）
     pub source: TokenSource,
     /// 关联的请求 ID
@@ -51,16 +58,153 @@ impl TokenUsageRecord {
             input_tokens,
             output_tokens,
             total_tokens: input_tokens + output_tokens,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
             source,
             request_id: None,
         }
     }
 
+    /// 从 Anthropic 响应的 usage 创建记录
+    pub fn from_anthropic_usage(
+        id: String,
+        provider: ProviderType,
+        model: String,
+        usage: &AnthropicUsage,
+    ) -> Self {
+        Self::new(
+            id,
+            provider,
+            model,
+            usage.input_tokens,
+            usage.output_tokens,
+            TokenSource::Actual,
+        )
+        .with_cache_tokens(
+            usage.cache_creation_input_tokens,
+            usage.cache_read_input_tokens,
+        )
+    }
+
     /// 设置关联的请求 ID
     pub fn with_request_id(mut self, request_id: String) -> Self {
         self.request_id = Some(request_id);
         self
     }
+
+    /// 设置提示缓存 Token 数
+    pub fn with_cache_tokens(mut self, creation: u32, read: u32) -> Self {
+        self.cache_creation_input_tokens = creation;
+        self.cache_read_input_tokens = read;
+        self
+    }
+
+    /// 按价格表估算费用（美元），价格未知时返回 None
+    pub fn estimated_cost_usd(&self) -> Option<f64> {
+        pricing::price_for(&self.model).map(|price| {
+            price.cost_usd_with_cache(
+                self.input_tokens,
+                self.output_tokens,
+                self.cache_creation_input_tokens,
+                self.cache_read_input_tokens,
+            )
+        })
+    }
+}
+
+/// Anthropic 响应中的 Token 用量
+///
+/// Anthropic 的 `input_tokens` 不含缓存部分，缓存写入和命中按不同价格计费，需分别记录。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnthropicUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cache_creation_input_tokens: u32,
+    pub cache_read_input_tokens: u32,
+}
+
+impl AnthropicUsage {
+    /// 从 `usage` 对象解析，没有 `input_tokens` 和 `output_tokens` 时返回 None
+    pub fn from_usage(usage: &serde_json::Value) -> Option<Self> {
+        let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).map(|v| v as u32);
+        let input_tokens = field("input_tokens");
+        let output_tokens = field("output_tokens");
+        if input_tokens.is_none() && output_tokens.is_none() {
+            return None;
+        }
+        Some(Self {
+            input_tokens: input_tokens.unwrap_or(0),
+            output_tokens: output_tokens.unwrap_or(0),
+            cache_creation_input_tokens: field("cache_creation_input_tokens").unwrap_or(0),
+            cache_read_input_tokens: field("cache_read_input_tokens").unwrap_or(0),
+        })
+    }
+
+    /// 从完整的 Anthropic 响应体解析
+    pub fn from_response(response: &serde_json::Value) -> Option<Self> {
+        response.get("usage").and_then(Self::from_usage)
+    }
+}
+
+/// 从 Anthropic SSE 流中累计 Token 用量
+///
+/// 流式响应的用量分散在事件中：`message_start` 携带输入与缓存 Token，
+/// `message_delta` 携带累计输出 Token（部分上游也会重复输入与缓存 Token）。
+#[derive(Debug, Default)]
+pub struct AnthropicStreamUsage {
+    /// 未结束的行
+    buffer: Vec<u8>,
+    usage: Option<AnthropicUsage>,
+}
+
+impl AnthropicStreamUsage {
+    /// 输入一段 SSE 字节（可在任意位置切分）
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            self.parse_line(&line);
+        }
+    }
+
+    fn parse_line(&mut self, line: &[u8]) {
+        let Ok(line) = std::str::from_utf8(line) else {
+            return;
+        };
+        let Some(data) = line.trim().strip_prefix("data:") else {
+            return;
+        };
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(data.trim()) else {
+            return;
+        };
+        let usage = match event.get("type").and_then(|t| t.as_str()) {
+            Some("message_start") => event.get("message").and_then(|m| m.get("usage")),
+            Some("message_delta") => event.get("usage"),
+            _ => None,
+        };
+        let Some(usage) = usage else {
+            return;
+        };
+        let current = self.usage.get_or_insert_with(AnthropicUsage::default);
+        let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).map(|v| v as u32);
+        if let Some(v) = field("input_tokens") {
+            current.input_tokens = v;
+        }
+        if let Some(v) = field("output_tokens") {
+            current.output_tokens = v;
+        }
+        if let Some(v) = field("cache_creation_input_tokens") {
+            current.cache_creation_input_tokens = v;
+        }
+        if let Some(v) = field("cache_read_input_tokens") {
+            current.cache_read_input_tokens = v;
+        }
+    }
+
+    /// 已解析到的用量，流中没有用量事件时返回 None
+    pub fn usage(&self) -> Option<AnthropicUsage> {
+        self.usage
+    }
 }
 
 /// Token 来源
@@ -91,6 +235,12 @@ pub struct TokenStatsSummary {
     pub total_output_tokens: u64,
     /// 总 Token 数
     pub total_tokens: u64,
+    /// 总缓存写入 Token 数
+    pub total_cache_creation_input_tokens: u64,
+    /// 总缓存命中 Token 数
+    pub total_cache_read_input_tokens: u64,
+    /// 估算费用（美元，按缓存价格计算；所有记录的模型价格都未知时为 None）
+    pub estimated_cost_usd: Option<f64>,
     /// 记录数量
     pub record_count: u64,
     /// 实际值记录数
@@ -114,6 +264,20 @@ impl TokenStatsSummary {
         let total_input_tokens: u64 = records.iter().map(|r| r.input_tokens as u64).sum();
         let total_output_tokens: u64 = records.iter().map(|r| r.output_tokens as u64).sum();
         let total_tokens = total_input_tokens + total_output_tokens;
+        let total_cache_creation_input_tokens: u64 = records
+            .iter()
+            .map(|r| r.cache_creation_input_tokens as u64)
+            .sum();
+        let total_cache_read_input_tokens: u64 = records
+            .iter()
+            .map(|r| r.cache_read_input_tokens as u64)
+            .sum();
+        let estimated_cost_usd = records
+            .iter()
+            .filter_map(TokenUsageRecord::estimated_cost_usd)
+            .fold(None, |total: Option<f64>, cost| {
+                Some(total.unwrap_or(0.0) + cost)
+            });
         let actual_count = records
             .iter()
             .filter(|r| r.source == TokenSource::Actual)
@@ -127,6 +291,9 @@ impl TokenStatsSummary {
             total_input_tokens,
             total_output_tokens,
             total_tokens,
+            total_cache_creation_input_tokens,
+            total_cache_read_input_tokens,
+            estimated_cost_usd,
             record_count,
             actual_count,
             estimated_count,
@@ -188,6 +355,23 @@ pub struct PeriodTokenStats {
     pub summary: TokenStatsSummary,
 }
 
+/// 会话费用汇总
+///
+/// 会话中各 Flow 按 `request_id` 关联到 Token 记录后汇总。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionCostSummary {
+    /// 会话 ID
+    pub session_id: String,
+    /// 会话中的 Flow 数
+    pub flow_count: usize,
+    /// 没有对应 Token 记录的 Flow 数（记录已过期、Flow 已删除或上游未返回用量）
+    pub unmatched_flow_count: usize,
+    /// 合计
+    pub total: TokenStatsSummary,
+    /// 按模型分组（按估算费用降序，费用未知的按 Token 数排在后面）
+    pub by_model: Vec<ModelTokenStats>,
+}
+
 /// Token 追踪器
 ///
 /// 管理 Token 使用记录的存储、查询和统计
@@ -267,6 +451,24 @@ impl TokenTracker {
         }
     }
 
+    /// 从 Anthropic 响应的 usage 记录 Token 使用（含缓存 token）
+    pub fn record_anthropic_usage(
+        &self,
+        request_id: String,
+        provider: ProviderType,
+        model: String,
+        usage: &AnthropicUsage,
+    ) {
+        let record = TokenUsageRecord::from_anthropic_usage(
+            uuid::Uuid::new_v4().to_string(),
+            provider,
+            model,
+            usage,
+        )
+        .with_request_id(request_id);
+        self.record(record);
+    }
+
     /// 获取所有记录
     pub fn get_all(&self) -> Vec<TokenUsageRecord> {
         self.records.read().iter().cloned().collect()
@@ -389,6 +591,70 @@ impl TokenTracker {
             .collect()
     }
 
+    /// 汇总会话的 Token 用量和费用
+    ///
+    /// # Arguments
+    /// * `session_id` - 会话 ID
+    /// * `flow_request_ids` - 会话中每个 Flow 的请求 ID（Flow 不存在或没有请求 ID 时为 None）
+    pub fn session_cost_summary(
+        &self,
+        session_id: &str,
+        flow_request_ids: &[Option<String>],
+    ) -> SessionCostSummary {
+        let request_ids: HashSet<&str> = flow_request_ids
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        let records: Vec<TokenUsageRecord> = self
+            .records
+            .read()
+            .iter()
+            .filter(|r| {
+                r.request_id
+                    .as_deref()
+                    .is_some_and(|id| request_ids.contains(id))
+            })
+            .cloned()
+            .collect();
+
+        let matched: HashSet<&str> = records
+            .iter()
+            .filter_map(|r| r.request_id.as_deref())
+            .collect();
+        let unmatched_flow_count = flow_request_ids
+            .iter()
+            .filter(|id| !id.as_deref().is_some_and(|id| matched.contains(id)))
+            .count();
+
+        let mut grouped: HashMap<&str, Vec<TokenUsageRecord>> = HashMap::new();
+        for record in &records {
+            grouped
+                .entry(record.model.as_str())
+                .or_default()
+                .push(record.clone());
+        }
+        let mut by_model: Vec<ModelTokenStats> = grouped
+            .into_iter()
+            .map(|(model, records)| ModelTokenStats::from_records(model.to_string(), &records))
+            .collect();
+        by_model.sort_by(|a, b| {
+            let cost = |s: &ModelTokenStats| s.summary.estimated_cost_usd.unwrap_or(-1.0);
+            cost(b)
+                .total_cmp(&cost(a))
+                .then(b.summary.total_tokens.cmp(&a.summary.total_tokens))
+                .then_with(|| a.model.cmp(&b.model))
+        });
+
+        SessionCostSummary {
+            session_id: session_id.to_string(),
+            flow_count: flow_request_ids.len(),
+            unmatched_flow_count,
+            total: TokenStatsSummary::from_records(&records),
+            by_model,
+        }
+    }
+
     /// 按时间段汇总（按天）
     pub fn by_day(&self, days: i64) -> Vec<PeriodTokenStats> {
         let now = Utc::now();
@@ -565,6 +831,34 @@ impl ChatMessage {
 mod token_tests {
     use super::*;
 
+    #[test]
+    fn test_anthropic_stream_usage_collects_cache_tokens() {
+        let events = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12,",
+            "\"cache_creation_input_tokens\":300,\"cache_read_input_tokens\":2000,\"output_tokens\":1}}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"hi\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":42}}\n\n",
+        );
+        let mut acc = AnthropicStreamUsage::default();
+        assert!(acc.usage().is_none());
+        // 按任意位置切分输入
+        for chunk in events.as_bytes().chunks(7) {
+            acc.feed(chunk);
+        }
+        assert_eq!(
+            acc.usage(),
+            Some(AnthropicUsage {
+                input_tokens: 12,
+                output_tokens: 42,
+                cache_creation_input_tokens: 300,
+                cache_read_input_tokens: 2000,
+            })
+        );
+    }
+
     #[test]
     fn test_token_usage_record_new() {
         let record = TokenUsageRecord::new(
@@ -586,6 +880,49 @@ mod token_tests {
         assert!(record.request_id.is_none());
     }
 
+    #[test]
+    fn test_session_cost_summary_correlates_request_ids() {
+        let tracker = TokenTracker::with_defaults();
+        let record = |id: &str, model: &str, input: u32, request_id: &str| {
+            TokenUsageRecord::new(
+                id.to_string(),
+                ProviderType::Claude,
+                model.to_string(),
+                input,
+                10,
+                TokenSource::Actual,
+            )
+            .with_request_id(request_id.to_string())
+        };
+        tracker.record(record("1", "session-test-small", 100, "req-1"));
+        tracker.record(record("2", "session-test-large", 1000, "req-2").with_cache_tokens(0, 500));
+        tracker.record(record("3", "session-test-small", 200, "req-3"));
+        tracker.record(record("4", "session-test-large", 5000, "req-other"));
+
+        let summary = tracker.session_cost_summary(
+            "session-1",
+            &[
+                Some("req-1".to_string()),
+                Some("req-2".to_string()),
+                Some("req-3".to_string()),
+                Some("req-missing".to_string()),
+                None,
+            ],
+        );
+
+        assert_eq!(summary.session_id, "session-1");
+        assert_eq!(summary.flow_count, 5);
+        assert_eq!(summary.unmatched_flow_count, 2);
+        assert_eq!(summary.total.record_count, 3);
+        assert_eq!(summary.total.total_input_tokens, 1300);
+        assert_eq!(summary.total.total_output_tokens, 30);
+        assert_eq!(summary.total.total_cache_read_input_tokens, 500);
+        // 价格未知时按 Token 数排序
+        let models: Vec<&str> = summary.by_model.iter().map(|m| m.model.as_str()).collect();
+        assert_eq!(models, ["session-test-large", "session-test-small"]);
+        assert_eq!(summary.by_model[1].summary.total_input_tokens, 300);
+    }
+
     #[test]
     fn test_token_usage_record_with_request_id() {
         let record = TokenUsageRecord::new(
@@ -682,6 +1019,51 @@ mod token_tests {
         assert_eq!(records[0].request_id, Some("req-1".to_string()));
     }
 
+    #[test]
+    fn test_token_tracker_records_anthropic_cache_tokens() {
+        let response = serde_json::json!({
+            "type": "message",
+            "usage": {
+                "input_tokens": 20,
+                "output_tokens": 10,
+                "cache_creation_input_tokens": 300,
+                "cache_read_input_tokens": 4000
+            }
+        });
+        let usage = AnthropicUsage::from_response(&response).unwrap();
+        assert_eq!(usage.cache_creation_input_tokens, 300);
+        assert_eq!(usage.cache_read_input_tokens, 4000);
+        assert!(AnthropicUsage::from_response(&serde_json::json!({"usage": {}})).is_none());
+
+        let tracker = TokenTracker::with_defaults();
+        tracker.record_anthropic_usage(
+            "req-1".to_string(),
+            ProviderType::Claude,
+            "claude-sonnet".to_string(),
+            &usage,
+        );
+        tracker.record_anthropic_usage(
+            "req-2".to_string(),
+            ProviderType::Claude,
+            "claude-sonnet".to_string(),
+            &usage,
+        );
+
+        let summary = tracker.summary(None, None);
+        assert_eq!(summary.total_tokens, 60);
+        assert_eq!(summary.total_cache_creation_input_tokens, 600);
+        assert_eq!(summary.total_cache_read_input_tokens, 8000);
+        assert_eq!(summary.actual_count, 2);
+
+        let by_model = tracker.by_model(None, None);
+        assert_eq!(
+            by_model["claude-sonnet"]
+                .summary
+                .total_cache_read_input_tokens,
+            8000
+        );
+    }
+
     #[test]
     fn test_token_tracker_by_provider() {
         let tracker = TokenTracker::with_defaults();
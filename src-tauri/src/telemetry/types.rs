@@ -2,6 +2,7 @@
 //!
 //! 定义请求日志、统计数据等核心类型
 
+use crate::resilience::RequestPriority;
 use crate::ProviderType;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -34,6 +35,17 @@ impl std::fmt::Display for RequestStatus {
     }
 }
 
+/// 请求类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestKind {
+    /// 同步请求（普通对话 / 补全）
+    #[default]
+    Sync,
+    /// 批次提交（Message Batches）
+    Batch,
+}
+
 /// 请求日志条目
 ///
 /// 记录每个 API 请求的详细信息，包括时间戳、Provider、模型、持续时间和状态
@@ -65,8 +77,29 @@ pub struct RequestLog {
     pub is_streaming: bool,
     /// 使用的凭证 ID（如果有）
     pub credential_id: Option<String>,
+    /// 终端用户标识（隐私模式下为哈希）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
     /// 重试次数
     pub retry_count: u32,
+    /// 请求类型
+    #[serde(default)]
+    pub kind: RequestKind,
+    /// 是否因超出单次请求费用上限而中止
+    #[serde(default)]
+    pub aborted_for_cost: bool,
+    /// 请求体大小（字节）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_bytes: Option<u64>,
+    /// 响应体大小（字节，流式响应为发送给客户端的累计字节数）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_bytes: Option<u64>,
+    /// 请求优先级
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<RequestPriority>,
+    /// 等待并发槽位的时间（毫秒，未受并发限制时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_wait_ms: Option<u64>,
 }
 
 impl RequestLog {
@@ -86,10 +119,23 @@ impl RequestLog {
             error_message: None,
             is_streaming,
             credential_id: None,
+            user_id: None,
             retry_count: 0,
+            kind: RequestKind::Sync,
+            aborted_for_cost: false,
+            request_bytes: None,
+            response_bytes: None,
+            priority: None,
+            queue_wait_ms: None,
         }
     }
 
+    /// 设置请求类型
+    pub fn with_kind(mut self, kind: RequestKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
     /// 标记请求成功
     pub fn mark_success(&mut self, duration_ms: u64, http_status: u16) {
         self.status = RequestStatus::Success;
@@ -118,6 +164,13 @@ impl RequestLog {
         self.duration_ms = duration_ms;
     }
 
+    /// 标记请求因超出费用上限而中止
+    pub fn mark_cost_aborted(&mut self, duration_ms: u64, message: String) {
+        self.mark_cancelled(duration_ms);
+        self.aborted_for_cost = true;
+        self.error_message = Some(message);
+    }
+
     /// 设置 Token 使用信息
     pub fn set_tokens(&mut self, input: Option<u32>, output: Option<u32>) {
         self.input_tokens = input;
@@ -204,6 +257,76 @@ pub struct StatsSummary {
     pub total_output_tokens: u64,
     /// 总 Token 数
     pub total_tokens: u64,
+    /// 总请求体大小（字节）
+    #[serde(default)]
+    pub total_request_bytes: u64,
+    /// 总响应体大小（字节）
+    #[serde(default)]
+    pub total_response_bytes: u64,
+    /// 平均请求体大小（字节，只统计有记录的请求）
+    #[serde(default)]
+    pub avg_request_bytes: f64,
+    /// 平均响应体大小（字节，只统计有记录的请求）
+    #[serde(default)]
+    pub avg_response_bytes: f64,
+    /// 平均排队时间（毫秒，只统计受并发限制的请求）
+    #[serde(default)]
+    pub avg_queue_wait_ms: f64,
+    /// 最大排队时间（毫秒）
+    #[serde(default)]
+    pub max_queue_wait_ms: u64,
+    /// 按模型的请求/响应体大小（只在总体摘要中填充）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bytes_by_model: Vec<ModelBytes>,
+}
+
+/// 单个模型的请求/响应体大小
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelBytes {
+    /// 模型名称
+    pub model: String,
+    /// 总请求体大小（字节）
+    pub total_request_bytes: u64,
+    /// 总响应体大小（字节）
+    pub total_response_bytes: u64,
+    /// 平均请求体大小（字节，只统计有记录的请求）
+    pub avg_request_bytes: f64,
+    /// 平均响应体大小（字节，只统计有记录的请求）
+    pub avg_response_bytes: f64,
+}
+
+impl ModelBytes {
+    /// 按模型汇总请求/响应体大小，按总请求体大小降序排列
+    pub fn from_logs(logs: &[RequestLog]) -> Vec<Self> {
+        let mut grouped: std::collections::HashMap<&str, Vec<&RequestLog>> =
+            std::collections::HashMap::new();
+        for log in logs {
+            grouped.entry(log.model.as_str()).or_default().push(log);
+        }
+
+        let mut by_model: Vec<Self> = grouped
+            .into_iter()
+            .map(|(model, logs)| {
+                let (total_request_bytes, avg_request_bytes) =
+                    sum_and_avg(logs.iter().filter_map(|l| l.request_bytes));
+                let (total_response_bytes, avg_response_bytes) =
+                    sum_and_avg(logs.iter().filter_map(|l| l.response_bytes));
+                Self {
+                    model: model.to_string(),
+                    total_request_bytes,
+                    total_response_bytes,
+                    avg_request_bytes,
+                    avg_response_bytes,
+                }
+            })
+            .collect();
+        by_model.sort_by(|a, b| {
+            b.total_request_bytes
+                .cmp(&a.total_request_bytes)
+                .then_with(|| a.model.cmp(&b.model))
+        });
+        by_model
+    }
 }
 
 impl StatsSummary {
@@ -252,6 +375,17 @@ impl StatsSummary {
             .sum();
         let total_tokens = total_input_tokens + total_output_tokens;
 
+        let (total_request_bytes, avg_request_bytes) =
+            sum_and_avg(logs.iter().filter_map(|l| l.request_bytes));
+        let (total_response_bytes, avg_response_bytes) =
+            sum_and_avg(logs.iter().filter_map(|l| l.response_bytes));
+        let (_, avg_queue_wait_ms) = sum_and_avg(logs.iter().filter_map(|l| l.queue_wait_ms));
+        let max_queue_wait_ms = logs
+            .iter()
+            .filter_map(|l| l.queue_wait_ms)
+            .max()
+            .unwrap_or(0);
+
         Self {
             total_requests,
             successful_requests,
@@ -264,10 +398,36 @@ impl StatsSummary {
             total_input_tokens,
             total_output_tokens,
             total_tokens,
+            total_request_bytes,
+            total_response_bytes,
+            avg_request_bytes,
+            avg_response_bytes,
+            avg_queue_wait_ms,
+            max_queue_wait_ms,
+            bytes_by_model: Vec::new(),
+        }
+    }
+
+    /// 计算统计摘要，并附带按模型的请求/响应体大小
+    pub fn from_logs_with_model_bytes(logs: &[RequestLog]) -> Self {
+        Self {
+            bytes_by_model: ModelBytes::from_logs(logs),
+            ..Self::from_logs(logs)
         }
     }
 }
 
+/// 求和与平均值（没有数据时平均值为 0）
+fn sum_and_avg(values: impl Iterator<Item = u64>) -> (u64, f64) {
+    let (sum, count) = values.fold((0u64, 0u64), |(sum, count), v| (sum + v, count + 1));
+    let avg = if count > 0 {
+        sum as f64 / count as f64
+    } else {
+        0.0
+    };
+    (sum, avg)
+}
+
 /// Provider 统计
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProviderStats {
@@ -308,6 +468,26 @@ impl ModelStats {
     }
 }
 
+/// 终端用户统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserStats {
+    /// 用户标识（隐私模式下为哈希）
+    pub user_id: String,
+    /// 统计摘要
+    #[serde(flatten)]
+    pub summary: StatsSummary,
+}
+
+impl UserStats {
+    /// 从日志列表计算用户统计
+    pub fn from_logs(user_id: String, logs: &[RequestLog]) -> Self {
+        Self {
+            user_id,
+            summary: StatsSummary::from_logs(logs),
+        }
+    }
+}
+
 #[cfg(test)]
 mod type_tests {
     use super::*;
@@ -327,6 +507,127 @@ mod type_tests {
         assert!(!log.is_streaming);
         assert_eq!(log.status, RequestStatus::Retrying);
         assert_eq!(log.retry_count, 0);
+        assert_eq!(log.kind, RequestKind::Sync);
+    }
+
+    #[test]
+    fn test_request_log_kind_defaults_to_sync_when_missing() {
+        let log = RequestLog::new(
+            "batch-id".to_string(),
+            ProviderType::Claude,
+            "claude-sonnet".to_string(),
+            false,
+        )
+        .with_kind(RequestKind::Batch);
+        let mut value = serde_json::to_value(&log).unwrap();
+        assert_eq!(value["kind"], "batch");
+
+        // 旧版本记录的日志没有 kind 字段
+        value.as_object_mut().unwrap().remove("kind");
+        let old: RequestLog = serde_json::from_value(value).unwrap();
+        assert_eq!(old.kind, RequestKind::Sync);
+    }
+
+    #[test]
+    fn test_request_log_mark_cost_aborted() {
+        let mut log = RequestLog::new(
+            "cost-id".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet".to_string(),
+            true,
+        );
+
+        log.mark_cost_aborted(1200, "预估费用超过上限".to_string());
+
+        assert_eq!(log.status, RequestStatus::Cancelled);
+        assert!(log.aborted_for_cost);
+        assert_eq!(log.duration_ms, 1200);
+        assert!(log.error_message.is_some());
+    }
+
+    #[test]
+    fn test_stats_summary_bytes() {
+        let mut logs = Vec::new();
+        for (request_bytes, response_bytes) in [(Some(100), Some(1000)), (Some(300), None)] {
+            let mut log = RequestLog::new(
+                uuid::Uuid::new_v4().to_string(),
+                ProviderType::Kiro,
+                "claude-sonnet".to_string(),
+                false,
+            );
+            log.request_bytes = request_bytes;
+            log.response_bytes = response_bytes;
+            logs.push(log);
+        }
+
+        let summary = StatsSummary::from_logs(&logs);
+        assert_eq!(summary.total_request_bytes, 400);
+        assert_eq!(summary.avg_request_bytes, 200.0);
+        assert_eq!(summary.total_response_bytes, 1000);
+        // 没有记录响应大小的请求不计入平均值
+        assert_eq!(summary.avg_response_bytes, 1000.0);
+        assert!(summary.bytes_by_model.is_empty());
+    }
+
+    #[test]
+    fn test_stats_summary_bytes_by_model() {
+        let mut logs = Vec::new();
+        for (model, request_bytes, response_bytes) in [
+            ("claude-sonnet", Some(100), Some(1000)),
+            ("gpt-4o", Some(5000), Some(200)),
+            ("claude-sonnet", Some(300), None),
+        ] {
+            let mut log = RequestLog::new(
+                uuid::Uuid::new_v4().to_string(),
+                ProviderType::Kiro,
+                model.to_string(),
+                false,
+            );
+            log.request_bytes = request_bytes;
+            log.response_bytes = response_bytes;
+            logs.push(log);
+        }
+
+        let summary = StatsSummary::from_logs_with_model_bytes(&logs);
+        assert_eq!(summary.total_request_bytes, 5400);
+        assert_eq!(
+            summary.bytes_by_model,
+            vec![
+                ModelBytes {
+                    model: "gpt-4o".to_string(),
+                    total_request_bytes: 5000,
+                    total_response_bytes: 200,
+                    avg_request_bytes: 5000.0,
+                    avg_response_bytes: 200.0,
+                },
+                ModelBytes {
+                    model: "claude-sonnet".to_string(),
+                    total_request_bytes: 400,
+                    total_response_bytes: 1000,
+                    avg_request_bytes: 200.0,
+                    avg_response_bytes: 1000.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_summary_queue_wait() {
+        let mut logs = Vec::new();
+        for queue_wait_ms in [Some(40), Some(0), None] {
+            let mut log = RequestLog::new(
+                uuid::Uuid::new_v4().to_string(),
+                ProviderType::Kiro,
+                "claude-sonnet".to_string(),
+                false,
+            );
+            log.queue_wait_ms = queue_wait_ms;
+            logs.push(log);
+        }
+
+        let summary = StatsSummary::from_logs(&logs);
+        assert_eq!(summary.avg_queue_wait_ms, 20.0);
+        assert_eq!(summary.max_queue_wait_ms, 40);
     }
 
     #[test]
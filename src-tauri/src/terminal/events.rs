@@ -5,6 +5,7 @@
 //! ## 事件列表
 //! - `terminal:output` - 终端输出数据
 //! - `terminal:status` - 终端状态变化
+//! - `terminal:scrollback` - 重新连接会话时回放的输出历史
 //! - `terminal:shell-integration` - Shell 集成状态变化
 //! - `terminal:clipboard-write` - 剪贴板写入请求
 //! - `terminal:conn-change` - 连接状态变化
@@ -78,6 +79,8 @@ pub mod event_names {
     pub const TERMINAL_OUTPUT: &str = "terminal:output";
     /// 终端状态事件名
     pub const TERMINAL_STATUS: &str = "terminal:status";
+    /// 终端输出历史回放事件名
+    pub const TERMINAL_SCROLLBACK: &str = "terminal:scrollback";
     /// Shell 集成状态事件名
     pub const SHELL_INTEGRATION_STATUS: &str = "terminal:shell-integration";
     /// 剪贴板写入事件名
@@ -6,6 +6,7 @@
 //! - `error` - 错误类型定义
 //! - `events` - Tauri 事件定义
 //! - `pty_session` - PTY 会话封装
+//! - `scrollback` - 输出回滚缓冲区（重新连接时回放）
 //! - `session_manager` - 会话管理器
 //! - `persistence` - 持久化存储（块文件、会话元数据）
 //! - `block_controller` - 块控制器抽象层
@@ -28,6 +29,7 @@ pub mod events;
 pub mod integration;
 pub mod persistence;
 pub mod pty_session;
+pub mod scrollback;
 pub mod session_manager;
 
 #[cfg(test)]
@@ -47,4 +49,5 @@ pub use integration::{
 };
 pub use persistence::{BlockFile, SessionMetadataStore, SessionRecord};
 pub use pty_session::{PtySession, DEFAULT_COLS, DEFAULT_ROWS};
+pub use scrollback::TerminalScrollback;
 pub use session_manager::{SessionMetadata, TerminalSessionManager};
@@ -7,11 +7,11 @@
 //! - 异步读取 PTY 输出并通过 Tauri Event 推送
 //! - 处理 PTY 输入写入
 //! - 监控进程退出状态
-//! - 保存输出历史（循环缓冲区）
+//! - 保存输出历史（回滚缓冲区，容量见 [`super::scrollback`]）
 //!
 //! ## 架构说明
 //! PTY 在后端预创建，使用默认大小 (24x80)。前端连接后通过 resize 同步实际大小。
-//! 输出历史保存在循环缓冲区中，前端连接时可以获取历史数据。
+//! 输出历史保存在回滚缓冲区中，前端重新连接时可以获取历史数据。
 
 use std::io::{Read, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -25,52 +25,12 @@ use tokio::sync::RwLock;
 
 use super::error::TerminalError;
 use super::events::{event_names, SessionStatus, TerminalOutputEvent, TerminalStatusEvent};
+use super::scrollback::{self, ScrollbackBuffer, TerminalScrollback};
 
 /// 默认终端行数
 pub const DEFAULT_ROWS: u16 = 24;
 /// 默认终端列数
 pub const DEFAULT_COLS: u16 = 80;
-/// 输出历史缓冲区最大大小 (1MB)
-const OUTPUT_BUFFER_MAX_SIZE: usize = 1024 * 1024;
-
-/// 循环缓冲区，用于存储终端输出历史
-struct CircularBuffer {
-    data: Vec<u8>,
-    max_size: usize,
-}
-
-impl CircularBuffer {
-    fn new(max_size: usize) -> Self {
-        Self {
-            data: Vec::with_capacity(max_size),
-            max_size,
-        }
-    }
-
-    fn append(&mut self, new_data: &[u8]) {
-        // 如果新数据超过最大大小，只保留最后 max_size 字节
-        if new_data.len() >= self.max_size {
-            self.data.clear();
-            self.data
-                .extend_from_slice(&new_data[new_data.len() - self.max_size..]);
-            return;
-        }
-
-        // 追加新数据
-        self.data.extend_from_slice(new_data);
-
-        // 如果超过最大大小，移除开头的数据
-        if self.data.len() > self.max_size {
-            let excess = self.data.len() - self.max_size;
-            self.data.drain(0..excess);
-        }
-    }
-
-    fn get_all(&self) -> Vec<u8> {
-        self.data.clone()
-    }
-}
-
 /// PTY 会话
 pub struct PtySession {
     /// 会话 ID
@@ -84,7 +44,7 @@ pub struct PtySession {
     /// 关闭标志
     shutdown_flag: Arc<AtomicBool>,
     /// 输出历史缓冲区
-    output_buffer: Arc<Mutex<CircularBuffer>>,
+    output_buffer: Arc<Mutex<ScrollbackBuffer>>,
 }
 
 impl PtySession {
@@ -228,7 +188,7 @@ impl PtySession {
         let shutdown_flag_clone = shutdown_flag.clone();
 
         // 创建输出缓冲区
-        let output_buffer = Arc::new(Mutex::new(CircularBuffer::new(OUTPUT_BUFFER_MAX_SIZE)));
+        let output_buffer = Arc::new(Mutex::new(ScrollbackBuffer::new(scrollback::capacity())));
         let output_buffer_clone = output_buffer.clone();
 
         // 获取当前 tokio runtime handle（在主线程中获取）
@@ -364,6 +324,16 @@ impl PtySession {
         BASE64.encode(&data)
     }
 
+    /// 获取最近至多 `max_bytes` 字节的输出历史（不指定时返回全部）
+    pub fn scrollback(&self, max_bytes: Option<usize>) -> TerminalScrollback {
+        let (data, truncated) = self.output_buffer.lock().tail(max_bytes);
+        TerminalScrollback {
+            session_id: self.id.clone(),
+            data: BASE64.encode(&data),
+            truncated,
+        }
+    }
+
     /// 关闭会话
     pub async fn close(&self) -> Result<(), TerminalError> {
         // 设置关闭标志
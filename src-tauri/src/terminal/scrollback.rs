@@ -0,0 +1,132 @@
+//! 终端回滚缓冲区
+//!
+//! 每个 PTY 会话保留最近的输出（按字节计），前端重新连接到已有会话时用来恢复历史，
+//! 超出容量时丢弃最旧的数据。
+//!
+//! 容量来自 `terminal.scrollback_bytes` 配置，通过 [`configure`] 在启动和配置变更时更新，
+//! 只影响之后创建的会话。
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, DEFAULT_TERMINAL_SCROLLBACK_BYTES};
+
+/// 新建会话的回滚缓冲区容量（字节）
+static SCROLLBACK_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_TERMINAL_SCROLLBACK_BYTES);
+
+/// 更新回滚缓冲区容量
+pub fn configure(config: &Config) {
+    SCROLLBACK_BYTES.store(config.terminal.scrollback_bytes.max(1), Ordering::Relaxed);
+}
+
+/// 新建会话使用的回滚缓冲区容量（字节）
+pub fn capacity() -> usize {
+    SCROLLBACK_BYTES.load(Ordering::Relaxed)
+}
+
+/// 回滚内容快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalScrollback {
+    /// 会话 ID
+    pub session_id: String,
+    /// 输出数据（Base64 编码）
+    pub data: String,
+    /// 是否有更早的输出被丢弃（超出缓冲区容量或 `max_bytes`）
+    pub truncated: bool,
+}
+
+/// 固定容量的输出缓冲区，超出容量时丢弃最旧的数据
+pub(crate) struct ScrollbackBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+    /// 是否丢弃过数据
+    trimmed: bool,
+}
+
+impl ScrollbackBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            data: VecDeque::with_capacity(capacity.min(64 * 1024)),
+            capacity,
+            trimmed: false,
+        }
+    }
+
+    pub(crate) fn append(&mut self, new_data: &[u8]) {
+        let new_data = if new_data.len() > self.capacity {
+            self.trimmed = true;
+            self.data.clear();
+            &new_data[new_data.len() - self.capacity..]
+        } else {
+            new_data
+        };
+
+        let overflow = (self.data.len() + new_data.len()).saturating_sub(self.capacity);
+        if overflow > 0 {
+            self.trimmed = true;
+            self.data.drain(..overflow);
+        }
+        self.data.extend(new_data);
+    }
+
+    /// 全部缓冲内容
+    pub(crate) fn get_all(&self) -> Vec<u8> {
+        self.data.iter().copied().collect()
+    }
+
+    /// 最近至多 `max_bytes` 字节的内容，以及是否有更早的输出被省略
+    ///
+    /// 截断时跳过开头不完整的 UTF-8 字符。
+    pub(crate) fn tail(&self, max_bytes: Option<usize>) -> (Vec<u8>, bool) {
+        let len = self.data.len();
+        let take = max_bytes.map_or(len, |max| max.min(len));
+        let mut start = len - take;
+        if start > 0 {
+            while start < len && is_utf8_continuation(self.data[start]) {
+                start += 1;
+            }
+        }
+        let bytes = self.data.range(start..).copied().collect();
+        (bytes, self.trimmed || start > 0)
+    }
+}
+
+fn is_utf8_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_trims_oldest_data() {
+        let mut buffer = ScrollbackBuffer::new(8);
+        buffer.append(b"hello");
+        assert_eq!(buffer.tail(None), (b"hello".to_vec(), false));
+
+        buffer.append(b" world");
+        assert_eq!(buffer.get_all(), b"lo world");
+        assert!(buffer.tail(None).1);
+
+        buffer.append(b"0123456789");
+        assert_eq!(buffer.get_all(), b"23456789");
+    }
+
+    #[test]
+    fn test_tail_limits_bytes_on_char_boundary() {
+        let mut buffer = ScrollbackBuffer::new(64);
+        buffer.append("ab终端".as_bytes());
+
+        assert_eq!(buffer.tail(Some(3)), ("端".as_bytes().to_vec(), true));
+        // 从字符中间截断时跳过不完整的部分
+        assert_eq!(buffer.tail(Some(5)), ("端".as_bytes().to_vec(), true));
+        assert_eq!(
+            buffer.tail(Some(100)),
+            ("ab终端".as_bytes().to_vec(), false)
+        );
+    }
+}
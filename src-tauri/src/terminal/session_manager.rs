@@ -8,6 +8,7 @@
 //! - 集成 BlockFile 进行输出持久化
 //! - 集成 SessionMetadataStore 进行元数据存储
 //! - 支持会话状态生命周期管理
+//! - 重新连接会话时回放输出历史
 //!
 //! ## Requirements
 //! - 3.1: 终端会话创建时创建对应的 Block_File
@@ -23,6 +24,7 @@ use std::sync::Arc;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use tauri::Emitter;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
@@ -30,9 +32,10 @@ use crate::database::DbConnection;
 
 use super::block_controller::ControllerRegistry;
 use super::error::TerminalError;
-use super::events::SessionStatus;
+use super::events::{event_names, SessionStatus};
 use super::persistence::{BlockFile, SessionMetadataStore, SessionRecord};
 use super::pty_session::{PtySession, DEFAULT_COLS, DEFAULT_ROWS};
+use super::scrollback::TerminalScrollback;
 
 /// 会话元数据（用于前端展示）
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -413,6 +416,65 @@ impl TerminalSessionManager {
         sessions.get(session_id).map(|s| s.metadata.clone())
     }
 
+    /// 获取会话最近的输出历史
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `max_bytes`: 最多返回的字节数（不指定时返回整个回滚缓冲区）
+    pub async fn get_session_scrollback(
+        &self,
+        session_id: &str,
+        max_bytes: Option<usize>,
+    ) -> Result<TerminalScrollback, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+
+        Ok(match &session.legacy_pty {
+            Some(pty) => pty.scrollback(max_bytes),
+            None => TerminalScrollback {
+                session_id: session_id.to_string(),
+                data: String::new(),
+                truncated: false,
+            },
+        })
+    }
+
+    /// 重新连接到已有会话
+    ///
+    /// 通过 `terminal:scrollback` 事件推送最近的输出历史，让前端在继续接收实时输出前
+    /// 先恢复之前的内容。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `max_bytes`: 最多回放的字节数（不指定时回放整个回滚缓冲区）
+    pub async fn attach_session(
+        &self,
+        session_id: &str,
+        max_bytes: Option<usize>,
+    ) -> Result<SessionMetadata, TerminalError> {
+        let scrollback = self.get_session_scrollback(session_id, max_bytes).await?;
+        let metadata = self
+            .get_session(session_id)
+            .await
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+
+        if let Err(e) = self
+            .app_handle
+            .emit(event_names::TERMINAL_SCROLLBACK, &scrollback)
+        {
+            tracing::warn!("[终端] 会话 {} 推送输出历史失败: {}", session_id, e);
+        }
+        tracing::debug!(
+            "[终端] 会话 {} 重新连接，回放 {} 字节（Base64）",
+            session_id,
+            scrollback.data.len()
+        );
+
+        Ok(metadata)
+    }
+
     /// 恢复会话（从持久化存储）
     ///
     /// # 参数
@@ -206,6 +206,7 @@ mod tests {
         use super::super::events::event_names;
         assert_eq!(event_names::TERMINAL_OUTPUT, "terminal:output");
         assert_eq!(event_names::TERMINAL_STATUS, "terminal:status");
+        assert_eq!(event_names::TERMINAL_SCROLLBACK, "terminal:scrollback");
     }
 }
 
@@ -700,6 +700,8 @@ mod tests {
             temperature: None,
             tools: None,
             tool_choice: None,
+            stop_sequences: None,
+            metadata: None,
         };
 
         let translator = AnthropicRequestTranslator::new();
@@ -614,6 +614,8 @@ fn is_tool_choice_required(tool_choice: &Option<serde_json::Value>) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+    use crate::models::anthropic::AnthropicMessagesRequest;
 
     #[test]
     fn test_model_mapping() {
@@ -642,6 +644,15 @@ mod tests {
             top_p: None,
             tool_choice: None,
             reasoning_effort: None,
+            seed: None,
+            logprobs: None,
+            top_logprobs: None,
+            logit_bias: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            user: None,
+            stop: None,
+            parallel_tool_calls: None,
         };
 
         let translator = OpenAiRequestTranslator::new();
@@ -658,4 +669,78 @@ mod tests {
             "CLAUDE_SONNET_4_5_20250929_V1_0"
         );
     }
+
+    /// 1x1 透明 PNG
+    const PNG_BASE64: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    fn current_images(request: ChatCompletionRequest) -> Vec<CWImage> {
+        let cw_request = OpenAiRequestTranslator::new()
+            .translate_request(request)
+            .unwrap();
+        cw_request
+            .conversation_state
+            .current_message
+            .user_input_message
+            .images
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_chat_completions_base64_image() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "What is this?"},
+                {"type": "image_url", "image_url": {
+                    "url": format!("data:image/png;base64,{PNG_BASE64}")
+                }}
+            ]}]
+        }))
+        .unwrap();
+        assert_eq!(request.first_remote_image_url(), None);
+
+        let images = current_images(request);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].format, "png");
+        assert_eq!(images[0].source.bytes, PNG_BASE64);
+    }
+
+    #[test]
+    fn test_messages_base64_image_survives_openai_conversion() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": [
+                {"type": "image", "source": {
+                    "type": "base64", "media_type": "image/png", "data": PNG_BASE64
+                }},
+                {"type": "text", "text": "What is this?"}
+            ]}]
+        }))
+        .unwrap();
+        assert_eq!(request.first_remote_image_url(), None);
+
+        let images = current_images(convert_anthropic_to_openai(&request));
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].format, "png");
+        assert_eq!(images[0].source.bytes, PNG_BASE64);
+    }
+
+    #[test]
+    fn test_remote_image_urls_detected() {
+        let url = "https://example.com/cat.png";
+        let anthropic: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": [
+                    {"type": "image", "source": {"type": "url", "url": url}}
+                ]}
+            ]}]
+        }))
+        .unwrap();
+        assert_eq!(anthropic.first_remote_image_url(), Some(url));
+
+        // 转换后的 OpenAI 请求同样能识别出图片 URL
+        let openai = convert_anthropic_to_openai(&anthropic);
+        assert_eq!(openai.first_remote_image_url(), Some(url));
+    }
 }
@@ -0,0 +1,283 @@
+//! WebSocket permessage-deflate 压缩（RFC 7692）
+//!
+//! 开启 `websocket.compression` 后，客户端在升级请求的 `Sec-WebSocket-Extensions` 中提供
+//! `permessage-deflate` 时协商启用压缩，文本和二进制消息以 raw DEFLATE 压缩后发送（帧头 RSV1 置位）。
+//! 客户端未提供该扩展或参数无法满足时回退为不压缩的连接。
+//!
+//! 开关保存在连接管理器的 [`WsConfig`](super::WsConfig) 中，默认关闭，只影响之后建立的连接。
+
+use axum::http::{header, HeaderMap};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+
+/// 扩展名称
+pub const EXTENSION_NAME: &str = "permessage-deflate";
+
+/// 每条压缩消息末尾被省略的空 stored 块（RFC 7692 7.2.1）
+const DEFLATE_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// 支持的 LZ77 窗口大小（flate2 raw DEFLATE 固定为 2^15）
+const MAX_WINDOW_BITS: u8 = 15;
+
+/// 协商得到的 permessage-deflate 参数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeflateParams {
+    /// 服务端每条消息后重置压缩上下文
+    pub server_no_context_takeover: bool,
+    /// 客户端每条消息后重置压缩上下文
+    pub client_no_context_takeover: bool,
+}
+
+impl DeflateParams {
+    /// 响应头 `Sec-WebSocket-Extensions` 的值
+    pub fn response_header(&self) -> String {
+        let mut value = EXTENSION_NAME.to_string();
+        if self.server_no_context_takeover {
+            value.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            value.push_str("; client_no_context_takeover");
+        }
+        value
+    }
+}
+
+/// 从升级请求中协商 permessage-deflate
+///
+/// 按客户端给出的顺序选择第一个可接受的 offer，没有可接受的 offer 时返回 None。
+pub fn negotiate(headers: &HeaderMap) -> Option<DeflateParams> {
+    headers
+        .get_all(header::SEC_WEBSOCKET_EXTENSIONS)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(parse_offer)
+}
+
+/// 解析单个扩展 offer，不是 permessage-deflate 或参数无法满足时返回 None
+fn parse_offer(offer: &str) -> Option<DeflateParams> {
+    let mut parts = offer.split(';').map(str::trim);
+    if !parts.next()?.eq_ignore_ascii_case(EXTENSION_NAME) {
+        return None;
+    }
+
+    let mut params = DeflateParams::default();
+    let mut seen = Vec::new();
+    for part in parts.filter(|p| !p.is_empty()) {
+        let (name, value) = match part.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (part, None),
+        };
+        let name = name.to_ascii_lowercase();
+        // 同一参数出现多次时拒绝该 offer
+        if seen.contains(&name) {
+            return None;
+        }
+        match (name.as_str(), value) {
+            ("server_no_context_takeover", None) => params.server_no_context_takeover = true,
+            ("client_no_context_takeover", None) => params.client_no_context_takeover = true,
+            ("server_max_window_bits", Some(bits)) => {
+                // 只能使用 15 位窗口压缩，客户端要求更小的窗口时拒绝该 offer
+                if parse_window_bits(bits)? < MAX_WINDOW_BITS {
+                    return None;
+                }
+            }
+            // 客户端窗口不超过 2^15，解压不受影响，无需回应
+            ("client_max_window_bits", None) => {}
+            ("client_max_window_bits", Some(bits)) => {
+                parse_window_bits(bits)?;
+            }
+            _ => return None,
+        }
+        seen.push(name);
+    }
+    Some(params)
+}
+
+fn parse_window_bits(value: &str) -> Option<u8> {
+    value
+        .parse::<u8>()
+        .ok()
+        .filter(|bits| (8..=MAX_WINDOW_BITS).contains(bits))
+}
+
+/// 发送方向的压缩器
+pub struct Deflater {
+    compress: Compress,
+    no_context_takeover: bool,
+}
+
+impl Deflater {
+    pub fn new(no_context_takeover: bool) -> Self {
+        Self {
+            compress: Compress::new(Compression::default(), false),
+            no_context_takeover,
+        }
+    }
+
+    /// 压缩一条消息的负载（已去掉末尾的 `00 00 ff ff`）
+    pub fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let start = self.compress.total_in();
+        let mut output = Vec::with_capacity(data.len() / 2 + 64);
+        loop {
+            if output.len() == output.capacity() {
+                output.reserve(output.capacity().max(64));
+            }
+            let consumed = (self.compress.total_in() - start) as usize;
+            self.compress
+                .compress_vec(&data[consumed..], &mut output, FlushCompress::Sync)
+                .map_err(|e| format!("Deflate failed: {}", e))?;
+            let consumed = (self.compress.total_in() - start) as usize;
+            // 输入已全部消费且输出缓冲区未写满，说明同步刷新已完成
+            if consumed == data.len() && output.len() < output.capacity() {
+                break;
+            }
+        }
+
+        if output.ends_with(&DEFLATE_TRAILER) {
+            output.truncate(output.len() - DEFLATE_TRAILER.len());
+        }
+        if self.no_context_takeover {
+            self.compress.reset();
+        }
+        Ok(output)
+    }
+}
+
+/// 接收方向的解压器
+pub struct Inflater {
+    decompress: Decompress,
+    no_context_takeover: bool,
+}
+
+impl Inflater {
+    pub fn new(no_context_takeover: bool) -> Self {
+        Self {
+            decompress: Decompress::new(false),
+            no_context_takeover,
+        }
+    }
+
+    /// 解压一条消息的负载，解压后超过 `max_size` 字节时返回错误
+    pub fn decompress(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, String> {
+        let mut input = Vec::with_capacity(data.len() + DEFLATE_TRAILER.len());
+        input.extend_from_slice(data);
+        input.extend_from_slice(&DEFLATE_TRAILER);
+
+        let start = self.decompress.total_in();
+        let mut output = Vec::with_capacity((data.len() * 4).clamp(64, max_size.max(64)));
+        let mut stream_end = false;
+        loop {
+            if output.len() == output.capacity() {
+                if output.len() > max_size {
+                    return Err(format!("Message exceeds {} bytes", max_size));
+                }
+                output.reserve(output.capacity());
+            }
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let written = output.len();
+            let status = self
+                .decompress
+                .decompress_vec(&input[consumed..], &mut output, FlushDecompress::Sync)
+                .map_err(|e| format!("Inflate failed: {}", e))?;
+            let now_consumed = (self.decompress.total_in() - start) as usize;
+            if status == Status::StreamEnd {
+                stream_end = true;
+                break;
+            }
+            if now_consumed == input.len() && output.len() < output.capacity() {
+                break;
+            }
+            if now_consumed == consumed && output.len() == written && status == Status::BufError {
+                return Err("Inflate failed: truncated deflate data".to_string());
+            }
+        }
+
+        if output.len() > max_size {
+            return Err(format!("Message exceeds {} bytes", max_size));
+        }
+        // 客户端发送了 BFINAL 块时上下文已结束，也需要重置
+        if self.no_context_takeover || stream_end {
+            self.decompress.reset(false);
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn offer(value: &str) -> Option<DeflateParams> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::SEC_WEBSOCKET_EXTENSIONS,
+            HeaderValue::from_str(value).unwrap(),
+        );
+        negotiate(&headers)
+    }
+
+    #[test]
+    fn test_negotiate_offers() {
+        assert_eq!(offer("permessage-deflate"), Some(DeflateParams::default()));
+        // 浏览器的默认 offer
+        assert_eq!(
+            offer("permessage-deflate; client_max_window_bits"),
+            Some(DeflateParams::default())
+        );
+        let params =
+            offer("permessage-deflate; server_no_context_takeover; client_no_context_takeover")
+                .unwrap();
+        assert!(params.server_no_context_takeover && params.client_no_context_takeover);
+        assert_eq!(
+            params.response_header(),
+            "permessage-deflate; server_no_context_takeover; client_no_context_takeover"
+        );
+
+        // 无法满足的 offer 跳过，继续尝试后面的 offer
+        assert_eq!(
+            offer("permessage-deflate; server_max_window_bits=10, permessage-deflate; server_max_window_bits=\"15\""),
+            Some(DeflateParams::default())
+        );
+        assert_eq!(offer("permessage-deflate; server_max_window_bits=10"), None);
+        assert_eq!(offer("permessage-deflate; unknown_param"), None);
+        assert_eq!(
+            offer("permessage-deflate; server_no_context_takeover; server_no_context_takeover"),
+            None
+        );
+        assert_eq!(offer("x-webkit-deflate-frame"), None);
+        assert_eq!(negotiate(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_roundtrip_with_context_takeover() {
+        let mut deflater = Deflater::new(false);
+        let mut inflater = Inflater::new(false);
+        let message = r#"{"type":"stream_chunk","request_id":"r1","data":"hello hello hello"}"#;
+
+        let first = deflater.compress(message.as_bytes()).unwrap();
+        let second = deflater.compress(message.as_bytes()).unwrap();
+        // 第二条消息可以引用第一条的内容
+        assert!(second.len() < first.len());
+        assert!(!first.ends_with(&DEFLATE_TRAILER));
+
+        assert_eq!(
+            inflater.decompress(&first, 1024).unwrap(),
+            message.as_bytes()
+        );
+        assert_eq!(
+            inflater.decompress(&second, 1024).unwrap(),
+            message.as_bytes()
+        );
+        assert!(inflater.decompress(&first, 8).is_err());
+    }
+
+    #[test]
+    fn test_inflate_rfc_example() {
+        // RFC 7692 7.2.3.1 中 "Hello" 的压缩负载
+        let mut inflater = Inflater::new(true);
+        let payload = [0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00];
+        assert_eq!(inflater.decompress(&payload, 1024).unwrap(), b"Hello");
+        assert_eq!(inflater.decompress(&payload, 1024).unwrap(), b"Hello");
+    }
+}
@@ -0,0 +1,567 @@
+//! 支持 permessage-deflate 的 WebSocket 连接
+//!
+//! axum 内置的 WebSocket 不支持扩展（收到 RSV1 置位的帧会直接断开），协商启用压缩的连接
+//! 由这里完成升级握手和帧编解码，并通过 [`WsSocket`] 与普通连接统一为同一套 Stream / Sink 接口。
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use axum::body::Body;
+use axum::extract::ws::{CloseFrame, Message, WebSocket};
+use axum::extract::Request;
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use bytes::{Buf, BufMut, BytesMut};
+use futures::{Sink, Stream};
+use hyper_util::rt::TokioIo;
+use sha1::{Digest, Sha1};
+use tokio_util::codec::{Decoder, Encoder, Framed};
+
+use super::compression::{DeflateParams, Deflater, Inflater};
+
+/// 计算 `Sec-WebSocket-Accept` 使用的 GUID（RFC 6455）
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// 单个 WebSocket 帧
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Frame {
+    fin: bool,
+    /// permessage-deflate 中表示消息已压缩
+    rsv1: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    fn new(opcode: u8, payload: Vec<u8>) -> Self {
+        Self {
+            fin: true,
+            rsv1: false,
+            opcode,
+            payload,
+        }
+    }
+}
+
+/// 服务端帧编解码（解码客户端的掩码帧，编码不带掩码的服务端帧）
+struct FrameCodec {
+    max_payload: usize,
+}
+
+fn protocol_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+impl Decoder for FrameCodec {
+    type Item = Frame;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, io::Error> {
+        if src.len() < 2 {
+            return Ok(None);
+        }
+        let (b0, b1) = (src[0], src[1]);
+        let (len, header_len) = match b1 & 0x7f {
+            126 => {
+                if src.len() < 4 {
+                    return Ok(None);
+                }
+                (u16::from_be_bytes([src[2], src[3]]) as u64, 4)
+            }
+            127 => {
+                if src.len() < 10 {
+                    return Ok(None);
+                }
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&src[2..10]);
+                (u64::from_be_bytes(bytes), 10)
+            }
+            len => (len as u64, 2),
+        };
+
+        let fin = b0 & 0x80 != 0;
+        let rsv1 = b0 & 0x40 != 0;
+        let opcode = b0 & 0x0f;
+        if b0 & 0x30 != 0 {
+            return Err(protocol_error("Reserved bits are non-zero"));
+        }
+        if b1 & 0x80 == 0 {
+            return Err(protocol_error("Client frames must be masked"));
+        }
+        if opcode >= OPCODE_CLOSE && (!fin || rsv1 || len > 125) {
+            return Err(protocol_error("Invalid control frame"));
+        }
+        if len > self.max_payload as u64 {
+            return Err(protocol_error(format!(
+                "Frame exceeds {} bytes",
+                self.max_payload
+            )));
+        }
+
+        let len = len as usize;
+        let total = header_len + 4 + len;
+        if src.len() < total {
+            src.reserve(total - src.len());
+            return Ok(None);
+        }
+
+        let mut mask = [0u8; 4];
+        mask.copy_from_slice(&src[header_len..header_len + 4]);
+        src.advance(header_len + 4);
+        let mut payload = src.split_to(len).to_vec();
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+
+        Ok(Some(Frame {
+            fin,
+            rsv1,
+            opcode,
+            payload,
+        }))
+    }
+}
+
+impl Encoder<Frame> for FrameCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> Result<(), io::Error> {
+        let len = frame.payload.len();
+        dst.reserve(10 + len);
+        dst.put_u8((frame.fin as u8) << 7 | (frame.rsv1 as u8) << 6 | frame.opcode);
+        if len < 126 {
+            dst.put_u8(len as u8);
+        } else if len <= u16::MAX as usize {
+            dst.put_u8(126);
+            dst.put_u16(len as u16);
+        } else {
+            dst.put_u8(127);
+            dst.put_u64(len as u64);
+        }
+        dst.extend_from_slice(&frame.payload);
+        Ok(())
+    }
+}
+
+/// 正在接收的分片消息
+struct PartialMessage {
+    opcode: u8,
+    compressed: bool,
+    data: Vec<u8>,
+}
+
+/// 启用 permessage-deflate 的 WebSocket 连接
+pub struct DeflateWebSocket {
+    inner: Framed<TokioIo<hyper::upgrade::Upgraded>, FrameCodec>,
+    deflater: Deflater,
+    inflater: Inflater,
+    max_message_size: usize,
+    partial: Option<PartialMessage>,
+}
+
+impl DeflateWebSocket {
+    fn new(
+        io: TokioIo<hyper::upgrade::Upgraded>,
+        params: DeflateParams,
+        max_message_size: usize,
+    ) -> Self {
+        Self {
+            inner: Framed::new(
+                io,
+                FrameCodec {
+                    max_payload: max_message_size,
+                },
+            ),
+            deflater: Deflater::new(params.server_no_context_takeover),
+            inflater: Inflater::new(params.client_no_context_takeover),
+            max_message_size,
+            partial: None,
+        }
+    }
+
+    /// 把完整的数据消息转换为 [`Message`]
+    fn finish_message(
+        &mut self,
+        opcode: u8,
+        compressed: bool,
+        data: Vec<u8>,
+    ) -> Result<Message, axum::Error> {
+        let data = if compressed {
+            self.inflater
+                .decompress(&data, self.max_message_size)
+                .map_err(axum::Error::new)?
+        } else {
+            data
+        };
+        if opcode == OPCODE_TEXT {
+            String::from_utf8(data)
+                .map(Message::Text)
+                .map_err(axum::Error::new)
+        } else {
+            Ok(Message::Binary(data))
+        }
+    }
+
+    /// 处理一个数据帧，消息未接收完整时返回 None
+    fn on_data_frame(&mut self, frame: Frame) -> Result<Option<Message>, axum::Error> {
+        if frame.opcode == OPCODE_CONTINUATION {
+            let Some(partial) = self.partial.as_mut() else {
+                return Err(axum::Error::new(protocol_error(
+                    "Continuation frame without a started message",
+                )));
+            };
+            if frame.rsv1 {
+                return Err(axum::Error::new(protocol_error(
+                    "RSV1 set on continuation frame",
+                )));
+            }
+            partial.data.extend_from_slice(&frame.payload);
+            if partial.data.len() > self.max_message_size {
+                return Err(axum::Error::new(protocol_error(format!(
+                    "Message exceeds {} bytes",
+                    self.max_message_size
+                ))));
+            }
+            if !frame.fin {
+                return Ok(None);
+            }
+            let partial = self.partial.take().expect("partial message checked above");
+            return self
+                .finish_message(partial.opcode, partial.compressed, partial.data)
+                .map(Some);
+        }
+
+        if self.partial.is_some() {
+            return Err(axum::Error::new(protocol_error(
+                "New message started before the previous one finished",
+            )));
+        }
+        if !frame.fin {
+            self.partial = Some(PartialMessage {
+                opcode: frame.opcode,
+                compressed: frame.rsv1,
+                data: frame.payload,
+            });
+            return Ok(None);
+        }
+        self.finish_message(frame.opcode, frame.rsv1, frame.payload)
+            .map(Some)
+    }
+
+    /// 构造数据帧，非空负载压缩后发送
+    fn data_frame(&mut self, opcode: u8, data: Vec<u8>) -> Result<Frame, axum::Error> {
+        if data.is_empty() {
+            return Ok(Frame::new(opcode, data));
+        }
+        let payload = self.deflater.compress(&data).map_err(axum::Error::new)?;
+        Ok(Frame {
+            rsv1: true,
+            ..Frame::new(opcode, payload)
+        })
+    }
+
+    /// 回应客户端的关闭帧（尽力发送，失败时由连接关闭兜底）
+    fn reply_close(&mut self, cx: &mut Context<'_>, payload: &[u8]) {
+        let mut inner = Pin::new(&mut self.inner);
+        if let Poll::Ready(Ok(())) = inner.as_mut().poll_ready(cx) {
+            let code = payload.get(..2).map(<[u8]>::to_vec).unwrap_or_default();
+            if inner
+                .as_mut()
+                .start_send(Frame::new(OPCODE_CLOSE, code))
+                .is_ok()
+            {
+                let _ = inner.poll_flush(cx);
+            }
+        }
+    }
+}
+
+impl Stream for DeflateWebSocket {
+    type Item = Result<Message, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let frame = match ready!(Pin::new(&mut self.inner).poll_next(cx)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(e)) => return Poll::Ready(Some(Err(axum::Error::new(e)))),
+                None => return Poll::Ready(None),
+            };
+
+            let message = match frame.opcode {
+                OPCODE_CONTINUATION | OPCODE_TEXT | OPCODE_BINARY => {
+                    match self.on_data_frame(frame) {
+                        Ok(Some(message)) => message,
+                        Ok(None) => continue,
+                        Err(e) => return Poll::Ready(Some(Err(e))),
+                    }
+                }
+                OPCODE_CLOSE => {
+                    self.reply_close(cx, &frame.payload);
+                    let close = (frame.payload.len() >= 2).then(|| CloseFrame {
+                        code: u16::from_be_bytes([frame.payload[0], frame.payload[1]]),
+                        reason: Cow::Owned(
+                            String::from_utf8_lossy(&frame.payload[2..]).into_owned(),
+                        ),
+                    });
+                    Message::Close(close)
+                }
+                OPCODE_PING => Message::Ping(frame.payload),
+                OPCODE_PONG => Message::Pong(frame.payload),
+                opcode => {
+                    return Poll::Ready(Some(Err(axum::Error::new(protocol_error(format!(
+                        "Unknown opcode {}",
+                        opcode
+                    ))))))
+                }
+            };
+            return Poll::Ready(Some(Ok(message)));
+        }
+    }
+}
+
+impl Sink<Message> for DeflateWebSocket {
+    type Error = axum::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner)
+            .poll_ready(cx)
+            .map_err(axum::Error::new)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        let frame = match item {
+            Message::Text(text) => self.data_frame(OPCODE_TEXT, text.into_bytes())?,
+            Message::Binary(data) => self.data_frame(OPCODE_BINARY, data)?,
+            Message::Ping(data) => Frame::new(OPCODE_PING, data),
+            Message::Pong(data) => Frame::new(OPCODE_PONG, data),
+            Message::Close(close) => {
+                let payload = close
+                    .map(|close| {
+                        let mut payload = close.code.to_be_bytes().to_vec();
+                        payload.extend_from_slice(close.reason.as_bytes());
+                        payload
+                    })
+                    .unwrap_or_default();
+                Frame::new(OPCODE_CLOSE, payload)
+            }
+        };
+        Pin::new(&mut self.inner)
+            .start_send(frame)
+            .map_err(axum::Error::new)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner)
+            .poll_flush(cx)
+            .map_err(axum::Error::new)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner)
+            .poll_close(cx)
+            .map_err(axum::Error::new)
+    }
+}
+
+/// `/v1/ws` 使用的 WebSocket 连接（普通连接或启用压缩的连接）
+pub enum WsSocket {
+    Plain(WebSocket),
+    Deflate(DeflateWebSocket),
+}
+
+impl Stream for WsSocket {
+    type Item = Result<Message, axum::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut() {
+            WsSocket::Plain(socket) => Pin::new(socket).poll_next(cx),
+            WsSocket::Deflate(socket) => Pin::new(socket).poll_next(cx),
+        }
+    }
+}
+
+impl Sink<Message> for WsSocket {
+    type Error = axum::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.get_mut() {
+            WsSocket::Plain(socket) => Pin::new(socket).poll_ready(cx),
+            WsSocket::Deflate(socket) => Pin::new(socket).poll_ready(cx),
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        match self.get_mut() {
+            WsSocket::Plain(socket) => Pin::new(socket).start_send(item),
+            WsSocket::Deflate(socket) => Pin::new(socket).start_send(item),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.get_mut() {
+            WsSocket::Plain(socket) => Pin::new(socket).poll_flush(cx),
+            WsSocket::Deflate(socket) => Pin::new(socket).poll_flush(cx),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.get_mut() {
+            WsSocket::Plain(socket) => Pin::new(socket).poll_close(cx),
+            WsSocket::Deflate(socket) => Pin::new(socket).poll_close(cx),
+        }
+    }
+}
+
+/// 计算 `Sec-WebSocket-Accept`
+fn accept_key(key: &[u8]) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(key);
+    sha1.update(WS_GUID.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(sha1.finalize())
+}
+
+fn header_contains(headers: &HeaderMap, name: header::HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|value| value.trim().eq_ignore_ascii_case(token))
+}
+
+fn bad_request(message: &'static str) -> Response {
+    (StatusCode::BAD_REQUEST, message).into_response()
+}
+
+/// 完成启用 permessage-deflate 的升级握手
+///
+/// 校验升级请求后返回 101 响应，连接升级完成后在新任务中调用 `callback`。
+pub fn upgrade<F, Fut>(
+    mut request: Request,
+    params: DeflateParams,
+    max_message_size: usize,
+    callback: F,
+) -> Response
+where
+    F: FnOnce(DeflateWebSocket) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    if request.method() != Method::GET {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Request method must be `GET`",
+        )
+            .into_response();
+    }
+    let headers = request.headers();
+    if !header_contains(headers, header::CONNECTION, "upgrade") {
+        return bad_request("Connection header did not include 'upgrade'");
+    }
+    if !header_contains(headers, header::UPGRADE, "websocket") {
+        return bad_request("`Upgrade` header did not include 'websocket'");
+    }
+    if headers
+        .get(header::SEC_WEBSOCKET_VERSION)
+        .and_then(|version| version.to_str().ok())
+        != Some("13")
+    {
+        return bad_request("`Sec-WebSocket-Version` header did not include '13'");
+    }
+    let Some(key) = headers.get(header::SEC_WEBSOCKET_KEY) else {
+        return bad_request("`Sec-WebSocket-Key` header missing");
+    };
+    let accept = accept_key(key.as_bytes());
+
+    let Some(on_upgrade) = request
+        .extensions_mut()
+        .remove::<hyper::upgrade::OnUpgrade>()
+    else {
+        return (
+            StatusCode::UPGRADE_REQUIRED,
+            "WebSocket request couldn't be upgraded since no upgrade state was present",
+        )
+            .into_response();
+    };
+
+    tokio::spawn(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => {
+                let socket =
+                    DeflateWebSocket::new(TokioIo::new(upgraded), params, max_message_size);
+                callback(socket).await;
+            }
+            Err(e) => tracing::debug!("[WS] 连接升级失败: {}", e),
+        }
+    });
+
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    let headers = response.headers_mut();
+    headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    if let Ok(value) = HeaderValue::from_str(&accept) {
+        headers.insert(header::SEC_WEBSOCKET_ACCEPT, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&params.response_header()) {
+        headers.insert(header::SEC_WEBSOCKET_EXTENSIONS, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_key() {
+        // RFC 6455 1.3 中的示例
+        assert_eq!(
+            accept_key(b"dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_frame_codec_roundtrip() {
+        let mut codec = FrameCodec { max_payload: 1024 };
+        // 客户端发送的掩码文本帧 "Hello"（RFC 6455 5.7）
+        let mut src = BytesMut::from(
+            &[
+                0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+            ][..],
+        );
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(frame, Frame::new(OPCODE_TEXT, b"Hello".to_vec()));
+        assert!(src.is_empty());
+
+        let mut dst = BytesMut::new();
+        codec
+            .encode(
+                Frame {
+                    rsv1: true,
+                    ..Frame::new(OPCODE_TEXT, vec![0u8; 300])
+                },
+                &mut dst,
+            )
+            .unwrap();
+        assert_eq!(&dst[..4], &[0xc1, 126, 0x01, 0x2c]);
+        assert_eq!(dst.len(), 304);
+
+        // 未掩码的帧和超出大小限制的帧被拒绝
+        let mut unmasked = BytesMut::from(&[0x81, 0x05, b'H', b'e', b'l', b'l', b'o'][..]);
+        assert!(codec.decode(&mut unmasked).is_err());
+        let mut oversized = BytesMut::from(&[0x82, 0xfe, 0x08, 0x00][..]);
+        assert!(codec.decode(&mut oversized).is_err());
+    }
+}
@@ -246,7 +246,7 @@ async fn handle_message(
             // 忽略客户端发送的错误消息
             None
         }
-        WsMessage::SubscribeFlowEvents | WsMessage::UnsubscribeFlowEvents => {
+        WsMessage::SubscribeFlowEvents { .. } | WsMessage::UnsubscribeFlowEvents => {
             // Flow 事件订阅在 server/handlers/websocket.rs 中处理
             // 这里的 handler 是旧的实现，暂时返回不支持的错误
             Some(WsMessage::Error(WsError::invalid_request(
@@ -275,6 +275,30 @@ async fn handle_message(
                 "KiroCredentialEvent messages are server-to-client only",
             )))
         }
+        WsMessage::SubscribeLogs { .. } | WsMessage::UnsubscribeLogs => {
+            // 日志订阅在 server/handlers/websocket.rs 中处理
+            Some(WsMessage::Error(WsError::invalid_request(
+                None,
+                "Log subscription is not supported in this handler",
+            )))
+        }
+        WsMessage::LogEntry(_) => Some(WsMessage::Error(WsError::invalid_message(
+            "LogEntry messages are server-to-client only",
+        ))),
+        WsMessage::ConfigGet
+        | WsMessage::ConfigSubscribe
+        | WsMessage::ConfigUnsubscribe
+        | WsMessage::ConfigPatch { .. } => {
+            // 配置帧只在管理 WebSocket（/v0/management/ws）中处理
+            Some(WsMessage::Error(WsError::unauthorized(
+                "Config frames require the management WebSocket",
+            )))
+        }
+        WsMessage::ConfigSnapshot { .. } | WsMessage::ConfigChanged { .. } => {
+            Some(WsMessage::Error(WsError::invalid_message(
+                "Config snapshot/changed messages are server-to-client only",
+            )))
+        }
     }
 }
 
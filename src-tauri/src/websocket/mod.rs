@@ -5,13 +5,28 @@
 //! - 消息解析和处理
 //! - 流式响应转发
 //! - 心跳检测和连接生命周期管理
+//!
+//! ## 流式 Chat Completions 协议
+//!
+//! 客户端发送 `{"type":"request","request_id":"...","endpoint":"chat_completions","payload":{..., "stream": true}}`，
+//! 服务端按 `request_id` 返回若干 `stream_chunk` 帧和一个 `stream_end` 帧，
+//! 出错时返回 `error` 帧。同一连接可同时进行多个流。
+//! 完整帧格式见 `docs/content/04.api-reference/2.openai-api.md`。
+//!
+//! ## 压缩
+//!
+//! 开启 `websocket.compression` 后，与提供 `permessage-deflate` 扩展的客户端协商压缩，
+//! 见 [`compression`]。
 
+pub mod compression;
+mod deflate_socket;
 mod handler;
 mod lifecycle;
 mod processor;
 mod stream;
 mod types;
 
+pub use deflate_socket::{upgrade as deflate_upgrade, DeflateWebSocket, WsSocket};
 pub use handler::{parse_message, serialize_message, ws_handler, WsHandlerState};
 pub use lifecycle::{
     ConnectionLifecycle, GracefulShutdown, HeartbeatManager, LifecycleState, ResourceCleaner,
@@ -33,7 +48,7 @@ pub struct WsConnectionManager {
     /// 活跃连接映射
     connections: DashMap<String, WsConnection>,
     /// 配置
-    config: WsConfig,
+    config: parking_lot::RwLock<WsConfig>,
     /// 统计信息
     stats: Arc<WsStats>,
 }
@@ -43,7 +58,7 @@ impl WsConnectionManager {
     pub fn new(config: WsConfig) -> Self {
         Self {
             connections: DashMap::new(),
-            config,
+            config: parking_lot::RwLock::new(config),
             stats: Arc::new(WsStats::new()),
         }
     }
@@ -109,8 +124,13 @@ impl WsConnectionManager {
     }
 
     /// 获取配置
-    pub fn config(&self) -> &WsConfig {
-        &self.config
+    pub fn config(&self) -> WsConfig {
+        self.config.read().clone()
+    }
+
+    /// 更新压缩开关，只影响之后建立的连接
+    pub fn set_compression(&self, enabled: bool) {
+        self.config.write().compression = enabled;
     }
 
     /// 记录消息
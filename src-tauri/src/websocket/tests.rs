@@ -64,6 +64,52 @@ fn test_ws_message_deserialization() {
     }
 }
 
+#[test]
+fn test_ws_log_subscription_messages() {
+    let json = r#"{"type":"subscribe_logs","filter":{"level":"warn"}}"#;
+    match serde_json::from_str::<WsMessage>(json).unwrap() {
+        WsMessage::SubscribeLogs { filter } => assert_eq!(filter.level.as_deref(), Some("warn")),
+        _ => panic!("Expected SubscribeLogs message"),
+    }
+    match serde_json::from_str::<WsMessage>(r#"{"type":"subscribe_logs"}"#).unwrap() {
+        WsMessage::SubscribeLogs { filter } => assert!(filter.level.is_none()),
+        _ => panic!("Expected SubscribeLogs message"),
+    }
+
+    let entry = WsMessage::LogEntry(crate::logger::LogEntry {
+        timestamp: "2026-01-01T00:00:00Z".to_string(),
+        level: "error".to_string(),
+        message: "boom".to_string(),
+    });
+    let json = serde_json::to_string(&entry).unwrap();
+    assert!(json.contains("\"type\":\"log_entry\""));
+    assert!(json.contains("\"message\":\"boom\""));
+}
+
+#[test]
+fn test_ws_config_messages() {
+    assert!(matches!(
+        serde_json::from_str::<WsMessage>(r#"{"type":"config.get"}"#).unwrap(),
+        WsMessage::ConfigGet
+    ));
+    match serde_json::from_str::<WsMessage>(
+        r#"{"type":"config.patch","patch":{"server":{"port":9000}}}"#,
+    )
+    .unwrap()
+    {
+        WsMessage::ConfigPatch { patch } => assert_eq!(patch["server"]["port"], 9000),
+        _ => panic!("Expected ConfigPatch message"),
+    }
+
+    let event =
+        crate::config::ConfigChangeEvent::FullReload(crate::config::observer::FullReloadEvent {
+            timestamp_ms: 0,
+            source: crate::config::ConfigChangeSource::ApiCall,
+        });
+    let json = serde_json::to_string(&WsMessage::ConfigChanged { event }).unwrap();
+    assert!(json.starts_with(r#"{"type":"config.changed","event":{"type":"FullReload""#));
+}
+
 #[test]
 fn test_ws_error_constructors() {
     let err = WsError::invalid_message("bad format");
@@ -628,3 +674,16 @@ proptest! {
         prop_assert!(forwarder.convert_sse_line("data: [DONE]", index).is_none());
     }
 }
+
+#[test]
+fn test_ws_connection_manager_compression_toggle() {
+    let manager = WsConnectionManager::with_defaults();
+    assert!(!manager.config().compression);
+
+    manager.set_compression(true);
+    assert!(manager.config().compression);
+    assert_eq!(
+        manager.config().max_message_size,
+        WsConfig::default().max_message_size
+    );
+}
@@ -6,10 +6,13 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::config::{Config, ConfigChangeEvent};
 use crate::flow_monitor::models::FlowError;
 use crate::flow_monitor::monitor::{
-    FlowEvent, FlowSummary, FlowUpdate, NotificationEvent, ThresholdCheckResult,
+    AlertEvent, FlowEvent, FlowEventFilter, FlowSummary, FlowUpdate, NotificationEvent,
+    ThresholdCheckResult,
 };
+use crate::logger::{LogEntry, LogFilter};
 
 /// WebSocket 连接信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,8 +77,11 @@ pub enum WsMessage {
     Ping { timestamp: i64 },
     /// 心跳响应
     Pong { timestamp: i64 },
-    /// 订阅 Flow 事件
-    SubscribeFlowEvents,
+    /// 订阅 Flow 事件（可通过 `filter.session_id` 跟随单个会话）
+    SubscribeFlowEvents {
+        #[serde(default)]
+        filter: FlowEventFilter,
+    },
     /// 取消订阅 Flow 事件
     UnsubscribeFlowEvents,
     /// Flow 事件通知
@@ -86,6 +92,33 @@ pub enum WsMessage {
     UnsubscribeKiroEvents,
     /// Kiro 凭证状态事件通知
     KiroCredentialEvent(WsKiroEvent),
+    /// 订阅新日志（可通过 `filter.level` 指定最低级别）
+    SubscribeLogs {
+        #[serde(default)]
+        filter: LogFilter,
+    },
+    /// 取消订阅日志
+    UnsubscribeLogs,
+    /// 新日志通知
+    LogEntry(LogEntry),
+    /// 读取当前配置（仅管理 WebSocket）
+    #[serde(rename = "config.get")]
+    ConfigGet,
+    /// 订阅配置变更（仅管理 WebSocket）
+    #[serde(rename = "config.subscribe")]
+    ConfigSubscribe,
+    /// 取消订阅配置变更
+    #[serde(rename = "config.unsubscribe")]
+    ConfigUnsubscribe,
+    /// 以 JSON Merge Patch（RFC 7386）修改配置，校验通过后保存并通知观察者
+    #[serde(rename = "config.patch")]
+    ConfigPatch { patch: serde_json::Value },
+    /// 当前配置（`config.get` / `config.patch` 的响应）
+    #[serde(rename = "config.snapshot")]
+    ConfigSnapshot { config: Box<Config> },
+    /// 配置变更通知
+    #[serde(rename = "config.changed")]
+    ConfigChanged { event: ConfigChangeEvent },
 }
 
 /// WebSocket API 请求
@@ -234,6 +267,9 @@ pub struct WsConfig {
     /// 消息大小限制（字节）
     #[serde(default = "default_max_message_size")]
     pub max_message_size: usize,
+    /// 启用 permessage-deflate 压缩（客户端在升级请求中提供该扩展时生效）
+    #[serde(default)]
+    pub compression: bool,
 }
 
 fn default_enabled() -> bool {
@@ -264,6 +300,7 @@ impl Default for WsConfig {
             heartbeat_timeout_secs: default_heartbeat_timeout(),
             max_connections: default_max_connections(),
             max_message_size: default_max_message_size(),
+            compression: false,
         }
     }
 }
@@ -342,36 +379,73 @@ pub enum WsFlowEvent {
     /// Flow 开始
     FlowStarted { flow: FlowSummary },
     /// Flow 更新
-    FlowUpdated { id: String, update: FlowUpdate },
+    FlowUpdated {
+        id: String,
+        update: FlowUpdate,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+    },
     /// Flow 完成
     FlowCompleted { id: String, summary: FlowSummary },
     /// Flow 失败
-    FlowFailed { id: String, error: FlowError },
+    FlowFailed {
+        id: String,
+        error: FlowError,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+    },
     /// 阈值警告
     ThresholdWarning {
         id: String,
         result: ThresholdCheckResult,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
     },
     /// 通知事件
     Notification { notification: NotificationEvent },
     /// 请求速率更新
     RequestRateUpdate { rate: f64, count: usize },
+    /// 异常告警
+    AlertTriggered { alert: AlertEvent },
 }
 
 impl From<FlowEvent> for WsFlowEvent {
     fn from(event: FlowEvent) -> Self {
         match event {
             FlowEvent::FlowStarted { flow } => WsFlowEvent::FlowStarted { flow },
-            FlowEvent::FlowUpdated { id, update } => WsFlowEvent::FlowUpdated { id, update },
+            FlowEvent::FlowUpdated {
+                id,
+                update,
+                session_id,
+            } => WsFlowEvent::FlowUpdated {
+                id,
+                update,
+                session_id,
+            },
             FlowEvent::FlowCompleted { id, summary } => WsFlowEvent::FlowCompleted { id, summary },
-            FlowEvent::FlowFailed { id, error } => WsFlowEvent::FlowFailed { id, error },
-            FlowEvent::ThresholdWarning { id, result } => {
-                WsFlowEvent::ThresholdWarning { id, result }
-            }
+            FlowEvent::FlowFailed {
+                id,
+                error,
+                session_id,
+            } => WsFlowEvent::FlowFailed {
+                id,
+                error,
+                session_id,
+            },
+            FlowEvent::ThresholdWarning {
+                id,
+                result,
+                session_id,
+            } => WsFlowEvent::ThresholdWarning {
+                id,
+                result,
+                session_id,
+            },
             FlowEvent::Notification { notification } => WsFlowEvent::Notification { notification },
             FlowEvent::RequestRateUpdate { rate, count } => {
                 WsFlowEvent::RequestRateUpdate { rate, count }
             }
+            FlowEvent::AlertTriggered { alert } => WsFlowEvent::AlertTriggered { alert },
         }
     }
 }
@@ -149,6 +149,7 @@ impl E2ETestContext {
             },
             state: FlowState::Completed,
             annotations: FlowAnnotations::default(),
+            shadow: None,
         }
     }
 
@@ -0,0 +1,345 @@
+//! permessage-deflate WebSocket 互通测试
+//!
+//! 启动真实的 axum 服务端，通过 `deflate_upgrade` 完成握手；客户端使用 tungstenite 的
+//! 握手工具和帧编解码，压缩由 flate2 独立实现，验证：
+//! - 握手响应和扩展协商
+//! - 两种上下文接管模式下的压缩消息收发
+//! - 分片消息（含分片之间插入的控制帧）
+//! - ping/pong 和关闭握手
+
+use std::io::Cursor;
+use std::net::SocketAddr;
+
+use axum::extract::ws::Message;
+use axum::extract::Request;
+use axum::routing::get;
+use axum::Router;
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use futures::{SinkExt, StreamExt};
+use proxycast_lib::websocket::{compression, deflate_upgrade, DeflateWebSocket};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tungstenite::handshake::client::generate_key;
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::frame::coding::{Control, Data, OpCode};
+use tungstenite::protocol::frame::{Frame, FrameHeader};
+
+/// 每条压缩消息末尾被省略的空 stored 块（RFC 7692 7.2.1）
+const DEFLATE_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+const TEXT: OpCode = OpCode::Data(Data::Text);
+const BINARY: OpCode = OpCode::Data(Data::Binary);
+const CONTINUE: OpCode = OpCode::Data(Data::Continue);
+const PING: OpCode = OpCode::Control(Control::Ping);
+const PONG: OpCode = OpCode::Control(Control::Pong);
+const CLOSE: OpCode = OpCode::Control(Control::Close);
+
+/// 回显连接：数据消息原样返回，ping 回复 pong，收到关闭帧后结束
+async fn echo(mut socket: DeflateWebSocket) {
+    while let Some(Ok(message)) = socket.next().await {
+        let reply = match message {
+            Message::Ping(data) => Message::Pong(data),
+            Message::Close(_) => break,
+            other => other,
+        };
+        if socket.send(reply).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// 启动只接受压缩连接的回显服务
+async fn serve() -> SocketAddr {
+    let app = Router::new().route(
+        "/ws",
+        get(|request: Request| async move {
+            let params = compression::negotiate(request.headers())
+                .expect("client offers permessage-deflate");
+            deflate_upgrade(request, params, 1 << 20, echo)
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+/// 收到的一个帧
+struct Received {
+    opcode: OpCode,
+    fin: bool,
+    rsv1: bool,
+    /// 线上负载（压缩消息为压缩后的数据）
+    wire: Vec<u8>,
+    /// 解压后的负载
+    data: Vec<u8>,
+}
+
+/// 最小的 permessage-deflate 客户端
+struct Client {
+    stream: TcpStream,
+    buf: Vec<u8>,
+    compress: Compress,
+    decompress: Decompress,
+    client_no_context_takeover: bool,
+    server_no_context_takeover: bool,
+}
+
+impl Client {
+    /// 以给定的扩展 offer 发起握手，返回客户端和响应的 `Sec-WebSocket-Extensions`
+    async fn connect(addr: SocketAddr, offer: &str) -> (Self, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let key = generate_key();
+        let request = format!(
+            "GET /ws HTTP/1.1\r\nHost: {addr}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: {key}\r\n\
+             Sec-WebSocket-Extensions: {offer}\r\n\r\n"
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut buf = Vec::new();
+        let header_end = loop {
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            let mut chunk = [0u8; 1024];
+            let n = stream.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "connection closed during handshake");
+            buf.extend_from_slice(&chunk[..n]);
+        };
+        let head = String::from_utf8(buf[..header_end].to_vec()).unwrap();
+        let rest = buf[header_end..].to_vec();
+
+        let mut lines = head.lines();
+        assert!(lines.next().unwrap().starts_with("HTTP/1.1 101"), "{head}");
+        let header = |name: &str| {
+            head.lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(':'))
+                .find(|(n, _)| n.trim().eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.trim().to_string())
+        };
+        assert_eq!(
+            header("sec-websocket-accept").as_deref(),
+            Some(derive_accept_key(key.as_bytes()).as_str())
+        );
+        let extensions = header("sec-websocket-extensions").unwrap_or_default();
+
+        let client = Self {
+            stream,
+            buf: rest,
+            compress: Compress::new(Compression::default(), false),
+            decompress: Decompress::new(false),
+            client_no_context_takeover: extensions.contains("client_no_context_takeover"),
+            server_no_context_takeover: extensions.contains("server_no_context_takeover"),
+        };
+        (client, extensions)
+    }
+
+    fn deflate(&mut self, data: &[u8]) -> Vec<u8> {
+        let start = self.compress.total_in();
+        let mut output = Vec::with_capacity(data.len() * 2 + 64);
+        self.compress
+            .compress_vec(data, &mut output, FlushCompress::Sync)
+            .unwrap();
+        assert_eq!((self.compress.total_in() - start) as usize, data.len());
+        assert!(output.ends_with(&DEFLATE_TRAILER));
+        output.truncate(output.len() - DEFLATE_TRAILER.len());
+        if self.client_no_context_takeover {
+            self.compress.reset();
+        }
+        output
+    }
+
+    fn inflate(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut input = data.to_vec();
+        input.extend_from_slice(&DEFLATE_TRAILER);
+        let mut output = Vec::with_capacity(1 << 20);
+        self.decompress
+            .decompress_vec(&input, &mut output, FlushDecompress::Sync)
+            .unwrap();
+        // 服务端声明不接管上下文时，解压器重置后仍必须能解出下一条消息
+        if self.server_no_context_takeover {
+            self.decompress.reset(false);
+        }
+        output
+    }
+
+    async fn send_frame(&mut self, opcode: OpCode, fin: bool, rsv1: bool, payload: Vec<u8>) {
+        let header = FrameHeader {
+            is_final: fin,
+            rsv1,
+            opcode,
+            mask: Some([0x37, 0xfa, 0x21, 0x3d]),
+            ..FrameHeader::default()
+        };
+        let mut output = Vec::new();
+        Frame::from_payload(header, payload)
+            .format(&mut output)
+            .unwrap();
+        self.stream.write_all(&output).await.unwrap();
+    }
+
+    /// 压缩后以单帧发送一条文本消息，返回压缩后的负载长度
+    async fn send_compressed_text(&mut self, text: &str) -> usize {
+        let payload = self.deflate(text.as_bytes());
+        let len = payload.len();
+        self.send_frame(TEXT, true, true, payload).await;
+        len
+    }
+
+    /// 读取下一个帧，连接已关闭时返回 None
+    async fn recv(&mut self) -> Option<Received> {
+        loop {
+            let mut cursor = Cursor::new(&self.buf[..]);
+            if let Some((header, len)) = FrameHeader::parse(&mut cursor).unwrap() {
+                let start = cursor.position() as usize;
+                let end = start + len as usize;
+                if self.buf.len() >= end {
+                    let wire = self.buf[start..end].to_vec();
+                    self.buf.drain(..end);
+                    assert!(header.mask.is_none(), "server frames must not be masked");
+                    let data = if header.rsv1 {
+                        self.inflate(&wire)
+                    } else {
+                        wire.clone()
+                    };
+                    return Some(Received {
+                        opcode: header.opcode,
+                        fin: header.is_final,
+                        rsv1: header.rsv1,
+                        wire,
+                        data,
+                    });
+                }
+            }
+            let mut chunk = [0u8; 4096];
+            let n = self.stream.read(&mut chunk).await.unwrap();
+            if n == 0 {
+                return None;
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    async fn expect(&mut self) -> Received {
+        self.recv().await.expect("connection closed unexpectedly")
+    }
+}
+
+fn sample_text() -> String {
+    "The quick brown fox jumps over the lazy dog. ".repeat(20)
+}
+
+#[tokio::test]
+async fn test_context_takeover_roundtrip() {
+    let addr = serve().await;
+    let (mut client, extensions) =
+        Client::connect(addr, "permessage-deflate; client_max_window_bits").await;
+    assert_eq!(extensions, "permessage-deflate");
+
+    let text = sample_text();
+    // 客户端保留上下文：第二条消息引用第一条的内容，服务端解压器必须保留窗口
+    let first_sent = client.send_compressed_text(&text).await;
+    let second_sent = client.send_compressed_text(&text).await;
+    assert!(second_sent < first_sent);
+
+    let first = client.expect().await;
+    let second = client.expect().await;
+    for reply in [&first, &second] {
+        assert_eq!(reply.opcode, TEXT);
+        assert!(reply.fin && reply.rsv1);
+        assert_eq!(reply.data, text.as_bytes());
+    }
+    // 服务端保留上下文：重复的消息压缩得更小
+    assert!(second.wire.len() < first.wire.len());
+}
+
+#[tokio::test]
+async fn test_no_context_takeover_roundtrip() {
+    let addr = serve().await;
+    let (mut client, extensions) = Client::connect(
+        addr,
+        "permessage-deflate; server_no_context_takeover; client_no_context_takeover",
+    )
+    .await;
+    assert_eq!(
+        extensions,
+        "permessage-deflate; server_no_context_takeover; client_no_context_takeover"
+    );
+
+    let text = sample_text();
+    let first_sent = client.send_compressed_text(&text).await;
+    let second_sent = client.send_compressed_text(&text).await;
+    assert_eq!(first_sent, second_sent);
+
+    let first = client.expect().await;
+    let second = client.expect().await;
+    assert_eq!(first.data, text.as_bytes());
+    assert_eq!(second.data, text.as_bytes());
+    // 每条消息独立压缩，结果相同
+    assert_eq!(first.wire, second.wire);
+}
+
+#[tokio::test]
+async fn test_fragmented_messages() {
+    let addr = serve().await;
+    let (mut client, _) = Client::connect(addr, "permessage-deflate").await;
+
+    // 压缩消息分成三帧发送，只有首帧设置 RSV1，分片之间插入一个 ping
+    let text = sample_text();
+    let payload = client.deflate(text.as_bytes());
+    let (a, rest) = payload.split_at(payload.len() / 3);
+    let (b, c) = rest.split_at(rest.len() / 2);
+    client.send_frame(TEXT, false, true, a.to_vec()).await;
+    client.send_frame(CONTINUE, false, false, b.to_vec()).await;
+    client.send_frame(PING, true, false, b"mid".to_vec()).await;
+    client.send_frame(CONTINUE, true, false, c.to_vec()).await;
+
+    let pong = client.expect().await;
+    assert_eq!(pong.opcode, PONG);
+    assert_eq!(pong.data, b"mid");
+    let reply = client.expect().await;
+    assert_eq!(reply.opcode, TEXT);
+    assert_eq!(reply.data, text.as_bytes());
+
+    // 未压缩的分片二进制消息，回显时被压缩
+    let binary: Vec<u8> = (0..=255u8).cycle().take(600).collect();
+    client
+        .send_frame(BINARY, false, false, binary[..200].to_vec())
+        .await;
+    client
+        .send_frame(CONTINUE, true, false, binary[200..].to_vec())
+        .await;
+    let reply = client.expect().await;
+    assert_eq!(reply.opcode, BINARY);
+    assert!(reply.rsv1);
+    assert_eq!(reply.data, binary);
+}
+
+#[tokio::test]
+async fn test_ping_pong_and_close() {
+    let addr = serve().await;
+    let (mut client, _) = Client::connect(addr, "permessage-deflate").await;
+
+    client.send_frame(PING, true, false, b"ping".to_vec()).await;
+    let pong = client.expect().await;
+    assert_eq!(pong.opcode, PONG);
+    assert!(!pong.rsv1);
+    assert_eq!(pong.data, b"ping");
+
+    // 空消息不压缩
+    client.send_frame(TEXT, true, false, Vec::new()).await;
+    let empty = client.expect().await;
+    assert_eq!(empty.opcode, TEXT);
+    assert!(!empty.rsv1);
+    assert!(empty.data.is_empty());
+
+    let mut close = 1000u16.to_be_bytes().to_vec();
+    close.extend_from_slice(b"bye");
+    client.send_frame(CLOSE, true, false, close).await;
+    let reply = client.expect().await;
+    assert_eq!(reply.opcode, CLOSE);
+    assert_eq!(reply.data[..2], 1000u16.to_be_bytes());
+    assert!(client.recv().await.is_none());
+}
@@ -16,6 +16,7 @@ import {
   stopServer,
   getServerStatus,
   getConfig,
+  revealApiKey,
   saveConfig,
   reloadCredentials,
   testApi,
@@ -68,6 +69,7 @@ export function ApiServerPage() {
   const [editPort, setEditPort] = useState<string>("");
   const [editHost, setEditHost] = useState<string>("");
   const [editApiKey, setEditApiKey] = useState<string>("");
+  const [apiKey, setApiKey] = useState<string>("");
   const [defaultProvider, setDefaultProviderState] = useState<string>("kiro");
 
   const [message, setMessage] = useState<{
@@ -104,6 +106,7 @@ export function ApiServerPage() {
       setEditPort(c.server.port.toString());
       setEditHost(c.server.host);
       setEditApiKey(c.server.api_key);
+      setApiKey(await revealApiKey());
     } catch (e) {
       console.error(e);
     }
@@ -457,7 +460,6 @@ export function ApiServerPage() {
     ? status.port
     : parseInt(editPort) || 8999;
   const serverUrl = getTestUrl(currentHost, currentPort);
-  const apiKey = config?.server.api_key ?? "";
 
   // 根据 Provider 类型获取测试模型
   const getTestModel = (provider: string): string => {
@@ -25,14 +25,35 @@ import {
 import { cn } from "@/lib/utils";
 import type { ExportFormat, LLMFlow } from "@/lib/api/flowMonitor";
 
+export interface BatchItemResult {
+  flow_id: string;
+  success: boolean;
+  error?: string;
+}
+
 export interface BatchResult {
   total: number;
   success: number;
   failed: number;
   errors: [string, string][];
+  /** 每个 Flow 的结果（按请求顺序） */
+  items: BatchItemResult[];
   export_data?: string;
 }
 
+/** 失败项摘要，最多列出前 3 项 */
+function describeFailures(result: BatchResult): string {
+  const failures = result.items.filter((item) => !item.success);
+  const shown = failures
+    .slice(0, 3)
+    .map(
+      (item) => `${item.flow_id.slice(0, 8)}: ${item.error ?? "未知错误"}`,
+    );
+  const more =
+    failures.length > shown.length ? ` 等 ${failures.length} 项` : "";
+  return `${result.failed}/${result.total} 项失败（已保留选中失败项）：${shown.join("；")}${more}`;
+}
+
 export interface SessionInfo {
   id: string;
   name: string;
@@ -115,9 +136,21 @@ export function BatchOperations({
         setError(null);
         const result = await safeInvoke<BatchResult>(command, { request });
         onOperationComplete?.(result, op);
-        onSuccess?.();
+        if (result.failed > 0) {
+          // 部分失败：成功项已生效，只保留失败项的选中状态便于重试
+          setError(describeFailures(result));
+          onSelectionChange(
+            new Set(
+              result.items
+                .filter((item) => !item.success)
+                .map((item) => item.flow_id),
+            ),
+          );
+        } else {
+          onSuccess?.();
+          setShowMenu(false);
+        }
         onRefresh?.();
-        setShowMenu(false);
       } catch (e) {
         setError(e instanceof Error ? e.message : `批量${op}失败`);
       } finally {
@@ -125,7 +158,7 @@ export function BatchOperations({
         setCurrentOp(null);
       }
     },
-    [selectedCount, onOperationComplete, onRefresh],
+    [selectedCount, onOperationComplete, onSelectionChange, onRefresh],
   );
 
   const handleBatchStar = () =>
@@ -187,6 +220,9 @@ export function BatchOperations({
         URL.revokeObjectURL(url);
       }
       onOperationComplete?.(result, "export");
+      if (result.failed > 0) {
+        setError(describeFailures(result));
+      }
       setShowExportDialog(false);
       setShowMenu(false);
     } catch (e) {
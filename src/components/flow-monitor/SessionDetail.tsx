@@ -27,8 +27,13 @@ import {
   ChevronDown,
   ChevronUp,
   ExternalLink,
+  DollarSign,
 } from "lucide-react";
 import { cn } from "@/lib/utils";
+import {
+  getSessionCostSummary,
+  type SessionCostSummary,
+} from "@/lib/api/telemetry";
 import type { LLMFlow, ExportFormat } from "@/lib/api/flowMonitor";
 import type { FlowSession, SessionExportResult } from "./SessionPanel";
 
@@ -89,6 +94,11 @@ export function SessionDetail({
   // 展开/折叠状态
   const [expandedFlowId, setExpandedFlowId] = useState<string | null>(null);
 
+  // 费用汇总
+  const [costSummary, setCostSummary] = useState<SessionCostSummary | null>(
+    null,
+  );
+
   // 加载会话中的 Flow
   const loadFlows = useCallback(async () => {
     if (session.flow_ids.length === 0) {
@@ -121,6 +131,16 @@ export function SessionDetail({
     loadFlows();
   }, [loadFlows]);
 
+  // 加载费用汇总
+  useEffect(() => {
+    getSessionCostSummary(session.id)
+      .then(setCostSummary)
+      .catch((e) => {
+        console.error("加载会话费用失败:", e);
+        setCostSummary(null);
+      });
+  }, [session.id, session.flow_ids]);
+
   // 更新会话信息
   const handleSave = useCallback(async () => {
     if (!editName.trim()) return;
@@ -438,6 +458,27 @@ export function SessionDetail({
             <FileText className="h-3 w-3" />
             {session.flow_ids.length} 个 Flow
           </span>
+          {costSummary && costSummary.total.record_count > 0 && (
+            <span
+              className="flex items-center gap-1"
+              title={costSummary.by_model
+                .map(
+                  (m) =>
+                    `${m.model}: ${m.total_tokens.toLocaleString()} tokens` +
+                    (m.estimated_cost_usd != null
+                      ? ` / $${m.estimated_cost_usd.toFixed(4)}`
+                      : ""),
+                )
+                .join("\n")}
+            >
+              <DollarSign className="h-3 w-3" />
+              {costSummary.total.estimated_cost_usd != null
+                ? `$${costSummary.total.estimated_cost_usd.toFixed(4)}`
+                : "费用未知"}
+              {" · "}
+              {costSummary.total.total_tokens.toLocaleString()} tokens
+            </span>
+          )}
           <span className="flex items-center gap-1">
             <Clock className="h-3 w-3" />
             创建于 {formatDate(session.created_at)}
@@ -1,7 +1,7 @@
 /**
  * 统计报告导出组件
  *
- * 提供统计报告的导出功能，支持 JSON、Markdown 和 CSV 格式。
+ * 提供统计报告的导出功能，支持 JSON、Markdown、CSV 和 HTML 格式。
  *
  * **Validates: Requirements 9.7**
  */
@@ -12,6 +12,7 @@ import {
   FileJson,
   FileText,
   Table,
+  Globe,
   Loader2,
   Check,
   AlertCircle,
@@ -71,6 +72,14 @@ const exportOptions: ExportOption[] = [
     extension: "csv",
     mimeType: "text/csv",
   },
+  {
+    format: "html",
+    label: "HTML",
+    icon: <Globe className="h-4 w-4" />,
+    description: "自包含的图表报告，可在浏览器中打开或通过邮件分享",
+    extension: "html",
+    mimeType: "text/html",
+  },
 ];
 
 export function StatsExport({
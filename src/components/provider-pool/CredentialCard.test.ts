@@ -211,6 +211,9 @@ const oauthCredentialArbitrary: fc.Arbitrary<CredentialDisplay> = fc.record({
     nil: undefined,
   }),
   proxy_url: fc.option(fc.webUrl(), { nil: undefined }),
+  health_score: fc.double({ min: 0, max: 1, noNaN: true }),
+  needs_secret: fc.constant(false),
+  is_parked: fc.constant(false),
 });
 
 // ============================================================================
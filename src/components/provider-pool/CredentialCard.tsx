@@ -350,6 +350,15 @@ export function CredentialCard({
                 代理
               </span>
             )}
+            {credential.is_parked && credential.parked_until && (
+              <span
+                className="rounded-full px-2.5 py-1 text-xs font-medium inline-flex items-center gap-1.5 whitespace-nowrap bg-amber-100 text-amber-700 dark:bg-amber-900/30 dark:text-amber-400"
+                title="暂停期间不参与凭证选择，到期后自动恢复"
+              >
+                <Timer className="h-3 w-3 shrink-0" />
+                暂停至 {new Date(credential.parked_until).toLocaleString()}
+              </span>
+            )}
           </div>
         </div>
 
@@ -10,9 +10,12 @@ import {
   resilienceApi,
   type FailoverConfig,
   type SwitchLogEntry,
+  type SwitchTrigger,
 } from "@/lib/api/resilience";
 import { HelpTip } from "@/components/HelpTip";
 
+const SWITCH_LOG_PAGE_SIZE = 50;
+
 interface FailoverSettingsProps {
   onSave?: () => void;
 }
@@ -23,6 +26,8 @@ export function FailoverSettings({ onSave }: FailoverSettingsProps) {
     switch_on_quota: true,
   });
   const [switchLog, setSwitchLog] = useState<SwitchLogEntry[]>([]);
+  const [switchLogTotal, setSwitchLogTotal] = useState(0);
+  const [triggerFilter, setTriggerFilter] = useState<SwitchTrigger | "">("");
   const [loading, setLoading] = useState(false);
   const [saving, setSaving] = useState(false);
   const [error, setError] = useState<string | null>(null);
@@ -38,11 +43,12 @@ export function FailoverSettings({ onSave }: FailoverSettingsProps) {
     try {
       const [configData, logData] = await Promise.all([
         resilienceApi.getFailoverConfig(),
-        resilienceApi.getSwitchLog(),
+        resilienceApi.getSwitchLog({ limit: SWITCH_LOG_PAGE_SIZE }),
       ]);
       setConfig(configData);
       setOriginalConfig(configData);
-      setSwitchLog(logData);
+      setSwitchLog(logData.entries);
+      setSwitchLogTotal(logData.total);
       setHasChanges(false);
     } catch (e) {
       setError(e instanceof Error ? e.message : String(e));
@@ -55,6 +61,30 @@ export function FailoverSettings({ onSave }: FailoverSettingsProps) {
     loadData();
   }, []);
 
+  const loadSwitchLog = async (
+    trigger: SwitchTrigger | "",
+    offset: number,
+  ) => {
+    try {
+      const page = await resilienceApi.getSwitchLog({
+        trigger: trigger || undefined,
+        offset,
+        limit: SWITCH_LOG_PAGE_SIZE,
+      });
+      setSwitchLog((prev) =>
+        offset === 0 ? page.entries : [...prev, ...page.entries],
+      );
+      setSwitchLogTotal(page.total);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  const handleTriggerFilterChange = (trigger: SwitchTrigger | "") => {
+    setTriggerFilter(trigger);
+    loadSwitchLog(trigger, 0);
+  };
+
   const handleSave = async () => {
     setSaving(true);
     setError(null);
@@ -84,6 +114,7 @@ export function FailoverSettings({ onSave }: FailoverSettingsProps) {
     try {
       await resilienceApi.clearSwitchLog();
       setSwitchLog([]);
+      setSwitchLogTotal(0);
     } catch (e) {
       setError(e instanceof Error ? e.message : String(e));
     }
@@ -94,32 +125,39 @@ export function FailoverSettings({ onSave }: FailoverSettingsProps) {
     setHasChanges(true);
   };
 
-  const getFailureTypeLabel = (type: string): string => {
-    switch (type) {
-      case "QuotaExceeded":
-        return "配额超限";
-      case "ServiceUnavailable":
-        return "服务不可用";
-      case "AuthenticationFailed":
-        return "认证失败";
+  const getTriggerLabel = (trigger: SwitchTrigger): string => {
+    switch (trigger) {
+      case "rate_limit":
+        return "限流";
+      case "server_error":
+        return "上游 5xx";
+      case "health":
+        return "健康检查失败";
+      case "manual":
+        return "手动禁用";
+      case "credentials_unavailable":
+        return "无可用凭证";
       default:
-        return type;
+        return trigger;
     }
   };
 
-  const getFailureTypeColor = (type: string): string => {
-    switch (type) {
-      case "QuotaExceeded":
+  const getTriggerColor = (trigger: SwitchTrigger): string => {
+    switch (trigger) {
+      case "rate_limit":
         return "text-yellow-600 bg-yellow-50 dark:bg-yellow-950/30";
-      case "ServiceUnavailable":
+      case "server_error":
         return "text-red-600 bg-red-50 dark:bg-red-950/30";
-      case "AuthenticationFailed":
+      case "health":
         return "text-orange-600 bg-orange-50 dark:bg-orange-950/30";
       default:
         return "text-gray-600 bg-gray-50 dark:bg-gray-950/30";
     }
   };
 
+  const shortId = (id: string | null): string | null =>
+    id ? id.slice(0, 8) : null;
+
   if (loading) {
     return (
       <div className="flex items-center justify-center py-8">
@@ -220,21 +258,37 @@ export function FailoverSettings({ onSave }: FailoverSettingsProps) {
           <h3 className="text-sm font-medium flex items-center gap-2">
             <ArrowRightLeft className="h-4 w-4" />
             切换日志
-            {switchLog.length > 0 && (
+            {switchLogTotal > 0 && (
               <span className="rounded-full bg-muted px-2 py-0.5 text-xs">
-                {switchLog.length}
+                {switchLogTotal}
               </span>
             )}
           </h3>
-          {switchLog.length > 0 && (
-            <button
-              onClick={handleClearLog}
-              className="flex items-center gap-1 text-xs text-muted-foreground hover:text-foreground"
+          <div className="flex items-center gap-3">
+            <select
+              value={triggerFilter}
+              onChange={(e) =>
+                handleTriggerFilterChange(e.target.value as SwitchTrigger | "")
+              }
+              className="rounded-md border bg-background px-2 py-1 text-xs"
             >
-              <Trash2 className="h-3 w-3" />
-              清除日志
-            </button>
-          )}
+              <option value="">全部原因</option>
+              <option value="rate_limit">限流</option>
+              <option value="server_error">上游 5xx</option>
+              <option value="health">健康检查失败</option>
+              <option value="manual">手动禁用</option>
+              <option value="credentials_unavailable">无可用凭证</option>
+            </select>
+            {switchLog.length > 0 && (
+              <button
+                onClick={handleClearLog}
+                className="flex items-center gap-1 text-xs text-muted-foreground hover:text-foreground"
+              >
+                <Trash2 className="h-3 w-3" />
+                清除日志
+              </button>
+            )}
+          </div>
         </div>
 
         {switchLog.length === 0 ? (
@@ -246,35 +300,60 @@ export function FailoverSettings({ onSave }: FailoverSettingsProps) {
           </div>
         ) : (
           <div className="space-y-2 max-h-64 overflow-y-auto">
-            {switchLog
-              .slice()
-              .reverse()
-              .map((entry, index) => (
-                <div
-                  key={index}
-                  className="flex items-center gap-3 rounded-lg border p-3 text-sm"
-                >
-                  <div className="flex items-center gap-2 flex-1">
+            {switchLog.map((entry) => (
+              <div
+                key={entry.id}
+                className="flex items-center gap-3 rounded-lg border p-3 text-sm"
+                title={entry.detail ?? undefined}
+              >
+                <div className="flex-1 min-w-0">
+                  <div className="flex items-center gap-2">
                     <span className="font-medium capitalize">
                       {entry.from_provider}
                     </span>
+                    {entry.from_credential && (
+                      <span className="font-mono text-xs text-muted-foreground">
+                        {shortId(entry.from_credential)}
+                      </span>
+                    )}
                     <ArrowRightLeft className="h-4 w-4 text-muted-foreground" />
                     <span className="font-medium capitalize">
                       {entry.to_provider}
                     </span>
+                    {entry.to_credential && (
+                      <span className="font-mono text-xs text-muted-foreground">
+                        {shortId(entry.to_credential)}
+                      </span>
+                    )}
                   </div>
-                  <span
-                    className={`rounded-md px-2 py-0.5 text-xs ${getFailureTypeColor(
-                      entry.failure_type,
-                    )}`}
-                  >
-                    {getFailureTypeLabel(entry.failure_type)}
-                  </span>
-                  <span className="text-xs text-muted-foreground">
-                    {entry.timestamp}
-                  </span>
+                  {(entry.model || entry.request_id) && (
+                    <p className="text-xs text-muted-foreground truncate">
+                      {[entry.model, entry.request_id]
+                        .filter(Boolean)
+                        .join(" · ")}
+                    </p>
+                  )}
                 </div>
-              ))}
+                <span
+                  className={`rounded-md px-2 py-0.5 text-xs ${getTriggerColor(
+                    entry.trigger,
+                  )}`}
+                >
+                  {getTriggerLabel(entry.trigger)}
+                </span>
+                <span className="text-xs text-muted-foreground">
+                  {new Date(entry.timestamp).toLocaleString()}
+                </span>
+              </div>
+            ))}
+            {switchLog.length < switchLogTotal && (
+              <button
+                onClick={() => loadSwitchLog(triggerFilter, switchLog.length)}
+                className="w-full rounded-lg border py-2 text-xs text-muted-foreground hover:bg-muted"
+              >
+                加载更多（{switchLog.length}/{switchLogTotal}）
+              </button>
+            )}
           </div>
         )}
       </div>
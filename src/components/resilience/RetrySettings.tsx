@@ -7,15 +7,15 @@ interface RetrySettingsProps {
   onSave?: () => void;
 }
 
-// Default retryable status codes
-const DEFAULT_RETRYABLE_CODES = [408, 429, 500, 502, 503, 504];
+// Default retryable status codes (408 is not retried by default: upstream may have run the request)
+const DEFAULT_RETRYABLE_CODES = [429, 500, 502, 503, 504];
 
 export function RetrySettings({ onSave }: RetrySettingsProps) {
   const [config, setConfig] = useState<RetryConfig>({
     max_retries: 3,
     base_delay_ms: 1000,
     max_delay_ms: 30000,
-    retryable_codes: DEFAULT_RETRYABLE_CODES,
+    retryable_status_codes: DEFAULT_RETRYABLE_CODES,
   });
   const [loading, setLoading] = useState(false);
   const [saving, setSaving] = useState(false);
@@ -75,7 +75,7 @@ export function RetrySettings({ onSave }: RetrySettingsProps) {
       max_retries: 3,
       base_delay_ms: 1000,
       max_delay_ms: 30000,
-      retryable_codes: DEFAULT_RETRYABLE_CODES,
+      retryable_status_codes: DEFAULT_RETRYABLE_CODES,
     };
     setConfig(defaults);
     setHasChanges(true);
@@ -87,10 +87,10 @@ export function RetrySettings({ onSave }: RetrySettingsProps) {
   };
 
   const toggleRetryableCode = (code: number) => {
-    const codes = config.retryable_codes.includes(code)
-      ? config.retryable_codes.filter((c) => c !== code)
-      : [...config.retryable_codes, code].sort((a, b) => a - b);
-    updateConfig({ retryable_codes: codes });
+    const codes = config.retryable_status_codes.includes(code)
+      ? config.retryable_status_codes.filter((c) => c !== code)
+      : [...config.retryable_status_codes, code].sort((a, b) => a - b);
+    updateConfig({ retryable_status_codes: codes });
   };
 
   // Calculate preview of backoff delays
@@ -257,7 +257,7 @@ export function RetrySettings({ onSave }: RetrySettingsProps) {
               key={code}
               onClick={() => toggleRetryableCode(code)}
               className={`rounded-lg border px-3 py-1.5 text-sm transition-colors ${
-                config.retryable_codes.includes(code)
+                config.retryable_status_codes.includes(code)
                   ? "border-primary bg-primary/10 text-primary"
                   : "border-border hover:border-muted-foreground/50"
               }`}
@@ -13,6 +13,7 @@ import {
 } from "lucide-react";
 import {
   getConfig,
+  revealApiKey,
   saveConfig,
   Config,
   checkApiCompatibility,
@@ -64,9 +65,9 @@ export function ProxySettings() {
     setSaving(false);
   };
 
-  const copyApiKey = () => {
+  const copyApiKey = async () => {
     if (config) {
-      navigator.clipboard.writeText(config.server.api_key);
+      navigator.clipboard.writeText(await revealApiKey());
       setCopied(true);
       setTimeout(() => setCopied(false), 2000);
     }
@@ -1,7 +1,7 @@
 import React, { useState, useMemo, useCallback } from "react";
 import { X, ExternalLink, Wand2, Eye, EyeOff } from "lucide-react";
 import { Provider, AppType } from "@/lib/api/switch";
-import { getConfig } from "@/hooks/useTauri";
+import { getConfig, revealApiKey } from "@/hooks/useTauri";
 import { cn } from "@/lib/utils";
 import { ProviderIcon } from "@/icons/providers";
 
@@ -485,7 +485,7 @@ export function ProviderForm({
       if (preset.id === "proxycast") {
         try {
           const config = await getConfig();
-          const proxyApiKey = config.server.api_key || "";
+          const proxyApiKey = (await revealApiKey()) || "";
           const proxyHost = config.server.host || "127.0.0.1";
           const proxyPort = config.server.port || 8999;
           const proxyBaseUrl = `http://${proxyHost}:${proxyPort}`;
@@ -6,6 +6,7 @@ import type {
   FlowUpdate,
   FlowError,
   ThresholdCheckResult,
+  AlertEvent,
 } from "@/lib/api/flowMonitor";
 
 interface UseFlowEventsOptions {
@@ -17,6 +18,7 @@ interface UseFlowEventsOptions {
   onFlowCompleted?: (id: string, summary: FlowSummary) => void;
   onFlowFailed?: (id: string, error: FlowError) => void;
   onThresholdWarning?: (id: string, result: ThresholdCheckResult) => void;
+  onAlertTriggered?: (alert: AlertEvent) => void;
 }
 
 interface UseFlowEventsReturn {
@@ -54,6 +56,7 @@ export function useFlowEvents(
     onFlowCompleted,
     onFlowFailed,
     onThresholdWarning,
+    onAlertTriggered,
   } = options;
 
   // 从全局管理器获取初始状态
@@ -79,6 +82,7 @@ export function useFlowEvents(
     onFlowCompleted,
     onFlowFailed,
     onThresholdWarning,
+    onAlertTriggered,
   });
 
   // 更新回调引用
@@ -89,6 +93,7 @@ export function useFlowEvents(
       onFlowCompleted,
       onFlowFailed,
       onThresholdWarning,
+      onAlertTriggered,
     };
   }, [
     onFlowStarted,
@@ -96,6 +101,7 @@ export function useFlowEvents(
     onFlowCompleted,
     onFlowFailed,
     onThresholdWarning,
+    onAlertTriggered,
   ]);
 
   // 处理事件
@@ -130,6 +136,10 @@ export function useFlowEvents(
         callbacksRef.current.onThresholdWarning?.(event.id, event.result);
         break;
 
+      case "AlertTriggered":
+        callbacksRef.current.onAlertTriggered?.(event.alert);
+        break;
+
       case "FlowCompleted":
         setActiveFlows((prev) => {
           const next = new Map(prev);
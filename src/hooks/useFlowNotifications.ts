@@ -16,6 +16,7 @@ import type {
   FlowSummary,
   FlowError,
   ThresholdCheckResult,
+  AlertEvent,
 } from "@/lib/api/flowMonitor";
 
 interface UseFlowNotificationsOptions {
@@ -92,12 +93,23 @@ export function useFlowNotifications(
     [enabled],
   );
 
+  // 处理异常告警事件
+  const handleAlertTriggered = useCallback(
+    (alert: AlertEvent) => {
+      if (!enabled || !alert.desktop) return;
+
+      notificationService.notifyAnomalyAlert(alert.rule_id, alert.message);
+    },
+    [enabled],
+  );
+
   // 使用 Flow 事件 Hook
   useFlowEvents({
     autoConnect: enabled,
     onFlowStarted: handleFlowStarted,
     onFlowFailed: handleFlowFailed,
     onThresholdWarning: handleThresholdWarning,
+    onAlertTriggered: handleAlertTriggered,
   });
 
   // 请求权限
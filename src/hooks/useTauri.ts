@@ -1,5 +1,6 @@
 // 使用共享的 safeInvoke
-import { safeInvoke } from "@/lib/dev-bridge";
+import { safeInvoke, safeListen } from "@/lib/dev-bridge";
+import type { UnlistenFn } from "@tauri-apps/api/event";
 
 export interface ServerStatus {
   running: boolean;
@@ -7,6 +8,8 @@ export interface ServerStatus {
   port: number;
   requests: number;
   uptime_secs: number;
+  /** 代理是否已暂停（全局熔断开关） */
+  proxy_paused: boolean;
 }
 
 // TLS Configuration
@@ -129,12 +132,232 @@ export interface ExperimentalFeatures {
   screenshot_chat: ScreenshotChatConfig;
 }
 
+// Concurrency Limit Configuration
+export interface ConcurrencyConfig {
+  /** 每个 Provider 类型的最大并发数 */
+  per_provider?: Record<string, number>;
+  /** 每个凭证的最大并发数，0 表示不限制 */
+  per_credential: number;
+  /** 并发已满时是否排队等待（false 则立即返回 429） */
+  queue_when_saturated: boolean;
+  /** 排队等待超时（毫秒） */
+  queue_timeout_ms: number;
+  /** 批量请求最长让位时间（毫秒），超过后按到达顺序排队 */
+  batch_max_wait_ms?: number;
+}
+
+// Config Backup Configuration
+export interface ResponseCacheConfig {
+  /** 是否启用响应缓存 */
+  enabled: boolean;
+  /** 缓存有效期（秒） */
+  ttl_secs: number;
+  /** 最大缓存条目数 */
+  max_entries: number;
+  /** 上游连接失败时返回过期缓存（响应头 x-proxycast-cache: stale-offline） */
+  offline_fallback?: boolean;
+}
+
+export interface SpendGuardConfig {
+  /** 默认费用上限（美元），为空时不限制；请求可用 x-proxycast-max-cost 头覆盖 */
+  max_cost_usd?: number | null;
+}
+
+export interface ShadowTestConfig {
+  /** 是否启用 */
+  enabled: boolean;
+  /** 影子凭证 UUID */
+  credential_uuid?: string | null;
+  /** 采样比例（0~1） */
+  sample_rate: number;
+}
+
+export interface DeadLetterConfig {
+  /** 是否记录失败请求（请求体可能包含敏感内容，默认关闭） */
+  enabled: boolean;
+  /** 最多保留的记录数 */
+  max_entries: number;
+  /** 单条记录请求体的最大字节数，超出后截断 */
+  max_body_bytes: number;
+}
+
+export interface StreamResumeConfig {
+  /** 是否为 SSE 事件编号并支持 Last-Event-ID 续传（默认关闭） */
+  enabled: boolean;
+  /** 每个流最多缓冲的事件数 */
+  max_buffered_events: number;
+  /** 流结束后保留缓冲区的秒数 */
+  retention_secs: number;
+}
+
+export interface RequestLimitsConfig {
+  /** 单个请求最多携带的工具数（不设置则不限制） */
+  max_tools?: number;
+  /** 单个工具定义的最大字节数（不设置则不限制） */
+  max_tool_schema_bytes?: number;
+  /** 是否截断过长的工具描述 */
+  truncate_tool_descriptions: boolean;
+  /** 工具描述的软上限（字节） */
+  max_tool_description_bytes: number;
+  /** 按终端用户的限制 */
+  per_user?: UserLimitsConfig;
+}
+
+export interface UserLimitsConfig {
+  /** 每个用户每分钟最多请求数（不设置则不限制） */
+  requests_per_minute?: number;
+  /** 每个用户每天（UTC）最多消耗的 Token 数（不设置则不限制） */
+  daily_token_quota?: number;
+  /** 日志与统计中只保存用户标识的哈希 */
+  hash_user_ids: boolean;
+}
+
+// Local port range for OAuth login callback servers
+export interface OAuthCallbackConfig {
+  port_range_start: number;
+  port_range_end: number;
+}
+
+export interface ToolResultBudget {
+  enabled?: boolean;
+  max_bytes?: number;
+  max_tokens?: number;
+}
+
+export interface ToolResultTruncationConfig {
+  /** 是否启用（可按 Provider 覆盖） */
+  enabled: boolean;
+  /** 单个工具结果的最大字节数 */
+  max_bytes: number;
+  /** 单个工具结果的最大 token 数（约 4 字节 / token，与 max_bytes 取较小值） */
+  max_tokens?: number;
+  /** 按 Provider 类型覆盖 */
+  providers?: Record<string, ToolResultBudget>;
+}
+
+export interface WebSocketConfig {
+  /** 与提供 permessage-deflate 扩展的客户端协商压缩（只影响新连接） */
+  compression: boolean;
+}
+
+export interface TerminalConfig {
+  /** 每个终端会话保留的输出历史（字节），重新连接时回放 */
+  scrollback_bytes: number;
+}
+
+export interface StreamConfig {
+  /**
+   * 内容增量的合并窗口（毫秒），0 表示逐个事件透传。
+   * 窗口越大下游写入越少，但文本最多延迟一个窗口。
+   */
+  coalesce_ms: number;
+}
+
+export interface TelemetryConfig {
+  /** OTLP 接收地址（如 http://localhost:4318），需以 otlp 特性编译 */
+  otlp_endpoint?: string | null;
+  /** 上报的服务名 */
+  service_name: string;
+}
+
+export interface HealthScoringConfig {
+  /** EMA 平滑系数（0~1，越大越看重最近的请求结果） */
+  ema_alpha: number;
+  /** 健康分低于该值时标记为不健康 */
+  disable_below: number;
+  /** 连续不健康超过该天数后自动禁用凭证（为空或 0 时关闭） */
+  auto_disable_after_days?: number | null;
+}
+
+export interface OutboundProxyConfig {
+  /** 代理 URL（http / https / socks5），为空时回退到 proxy_url */
+  url?: string | null;
+  /** 代理认证用户名 */
+  username?: string | null;
+  /** 代理认证密码 */
+  password?: string | null;
+  /** 按 Provider 覆盖的代理 URL，值为 "direct" 表示直连 */
+  overrides?: Record<string, string>;
+  /** 连接超时（秒） */
+  connect_timeout_secs: number;
+  /** 读取超时（秒） */
+  read_timeout_secs: number;
+}
+
+export interface UpstreamHeadersConfig {
+  /** 上游请求的 User-Agent，为空时使用 ProxyCast/<版本> */
+  user_agent?: string | null;
+  /** 所有 Provider 通用的附加请求头 */
+  headers?: Record<string, string>;
+  /** 按 Provider 覆盖的请求头 */
+  providers?: Record<string, Record<string, string>>;
+  /** Kiro 客户端版本号，为空时自动检测 */
+  kiro_version?: string | null;
+  /** Anthropic 原生调用的 anthropic-version，为空时使用 2023-06-01 */
+  anthropic_version?: string | null;
+  /** Anthropic 原生调用默认启用的 beta 功能 */
+  anthropic_betas?: string[];
+  /** 转发给客户端的上游响应头白名单，* 结尾表示前缀匹配 */
+  forward_response_headers?: string[];
+}
+
+export interface BackupConfig {
+  /** 是否启用定时备份 */
+  enabled: boolean;
+  /** 备份间隔（小时） */
+  interval_hours: number;
+  /** 备份目录 */
+  directory: string;
+  /** 保留的备份数量 */
+  keep_last: number;
+}
+
+export interface DatabaseMaintenanceConfig {
+  /** 是否启用定时维护 */
+  enabled: boolean;
+  /** 执行时刻（本地时间的小时，0-23） */
+  hour: number;
+  /** 记录保留天数，0 表示不删除 */
+  retention_days: number;
+  /** 是否执行 VACUUM */
+  vacuum: boolean;
+}
+
+export interface CompactionReport {
+  /** 各表删除的记录数 */
+  pruned: { table: string; rows: number }[];
+  size_before: number;
+  size_after: number;
+  /** 回收的空间（字节） */
+  reclaimed_bytes: number;
+  vacuumed: boolean;
+  /** 跳过 VACUUM 的原因（如数据库繁忙） */
+  vacuum_skipped?: string;
+  duration_ms: number;
+}
+
+export interface RestoreBackupResult {
+  success: boolean;
+  config: Config;
+  warnings: string[];
+}
+
+// Restart policy when the server task exits unexpectedly
+export interface ServerWatchdogConfig {
+  auto_restart: boolean;
+  max_restarts: number;
+  initial_backoff_secs: number;
+  max_backoff_secs: number;
+}
+
 export interface Config {
   server: {
     host: string;
     port: number;
     api_key: string;
     tls: TlsConfig;
+    api_key_grace_minutes?: number;
+    watchdog?: ServerWatchdogConfig;
   };
   providers: {
     kiro: {
@@ -173,6 +396,56 @@ export interface Config {
   language: string;
   /** 实验室功能配置 */
   experimental?: ExperimentalFeatures;
+  /** 并发限制配置 */
+  concurrency?: ConcurrencyConfig;
+  /** 配置自动备份 */
+  backup?: BackupConfig;
+  /** 数据库定时维护 */
+  database_maintenance?: DatabaseMaintenanceConfig;
+  response_cache?: ResponseCacheConfig;
+  /** 出站代理（Provider 上游请求） */
+  outbound_proxy?: OutboundProxyConfig;
+  /** 上游请求头配置 */
+  upstream_headers?: UpstreamHeadersConfig;
+  /** 凭证健康评分 */
+  health_scoring?: HealthScoringConfig;
+  /** 单次请求费用上限 */
+  spend_guard?: SpendGuardConfig;
+  /** 凭证影子测试 */
+  shadow_test?: ShadowTestConfig;
+  /** 链路追踪导出 */
+  telemetry?: TelemetryConfig;
+  /** 失败请求死信记录 */
+  dead_letter?: DeadLetterConfig;
+  /** SSE 断线续传 */
+  stream_resume?: StreamResumeConfig;
+  /** 请求限制 */
+  limits?: RequestLimitsConfig;
+  /** 流式响应转发 */
+  stream?: StreamConfig;
+  oauth_callback?: OAuthCallbackConfig;
+  /** 模型价格表（优先于模型注册表） */
+  price_table?: Record<string, PriceTableEntry>;
+  /** 内置终端 */
+  terminal?: TerminalConfig;
+  /** 工具结果截断 */
+  tool_result_truncation?: ToolResultTruncationConfig;
+  /** WebSocket（/v1/ws） */
+  websocket?: WebSocketConfig;
+}
+
+/** 模型价格（美元 / 百万 token） */
+export interface PriceTableEntry {
+  input_per_million: number;
+  output_per_million: number;
+  cache_write_per_million?: number;
+  cache_read_per_million?: number;
+}
+
+export interface PriceTableStatus {
+  table: Record<string, PriceTableEntry>;
+  /** 模型别名指向但没有价格的模型 */
+  missing_models: string[];
 }
 
 export interface LogEntry {
@@ -193,14 +466,120 @@ export async function getServerStatus(): Promise<ServerStatus> {
   return safeInvoke("get_server_status");
 }
 
+export async function setProxyPaused(paused: boolean): Promise<boolean> {
+  return safeInvoke("set_proxy_paused", { paused });
+}
+
+export async function getProxyPaused(): Promise<boolean> {
+  return safeInvoke("get_proxy_paused");
+}
+
+export interface RotateApiKeyResult {
+  api_key: string;
+  api_key_masked: string;
+  previous_key_expires_at: string | null;
+}
+
+/** 轮换服务器 API 密钥，新密钥仅在返回值中完整出现一次 */
+export async function rotateApiKey(
+  graceMinutes?: number,
+): Promise<RotateApiKeyResult> {
+  return safeInvoke("rotate_api_key", { graceMinutes });
+}
+
+/** 获取配置，其中 `server.api_key` 为掩码 */
 export async function getConfig(): Promise<Config> {
   return safeInvoke("get_config");
 }
 
+/** 获取完整的服务器 API 密钥（用于复制和生成客户端配置） */
+export async function revealApiKey(): Promise<string> {
+  return safeInvoke("reveal_api_key");
+}
+
 export async function saveConfig(config: Config): Promise<void> {
   return safeInvoke("save_config", { config });
 }
 
+/** 配置重载结果 */
+export type ReloadResult =
+  | { status: "success" }
+  | { status: "rolled_back"; error: string }
+  | { status: "failed"; error: string; rollback_error?: string | null };
+
+/** 立即从磁盘重新加载配置（需要服务器正在运行） */
+export async function reloadConfig(): Promise<ReloadResult> {
+  return safeInvoke("reload_config");
+}
+
+/** 配置节来源：配置文件 / 默认值 / 运行时修改 */
+export type ConfigSource = "file" | "default" | "runtime";
+
+/** 运行中实际生效的配置 */
+export interface EffectiveConfig {
+  config: Config;
+  /** 顶层字段 -> 来源 */
+  sources: Record<string, ConfigSource>;
+  /** 比较所用的配置文件路径 */
+  config_path: string;
+  /** 配置文件读取或解析失败的原因 */
+  file_error?: string;
+  /** 服务器是否在运行 */
+  server_running: boolean;
+}
+
+export async function getEffectiveConfig(): Promise<EffectiveConfig> {
+  return safeInvoke("get_effective_config");
+}
+
+/** 配置诊断 */
+export interface ConfigDiagnostic {
+  /** 字段路径（如 server.host），无法定位时为空 */
+  path: string;
+  message: string;
+  severity: "error" | "warning";
+  /** 所在行（从 1 开始） */
+  line?: number;
+  column?: number;
+}
+
+/** 配置校验报告 */
+export interface ConfigValidationReport {
+  /** 没有 error 级别诊断时为 true */
+  valid: boolean;
+  config?: Config;
+  diagnostics: ConfigDiagnostic[];
+}
+
+/** 校验 YAML 配置，返回结构化诊断 */
+export async function validateConfigYaml(
+  yamlContent: string,
+): Promise<ConfigValidationReport> {
+  return safeInvoke("validate_config_yaml", { yamlContent });
+}
+
+/** 获取配置的 JSON Schema（含安全规则约束） */
+export async function getConfigSchema(): Promise<Record<string, unknown>> {
+  return safeInvoke("get_config_schema");
+}
+
+export async function createConfigBackup(): Promise<string> {
+  return safeInvoke("create_config_backup");
+}
+
+export async function listConfigBackups(): Promise<string[]> {
+  return safeInvoke("list_config_backups");
+}
+
+export async function restoreBackup(path: string): Promise<RestoreBackupResult> {
+  return safeInvoke("restore_backup", { path });
+}
+
+/** 立即执行数据库维护（删除过期记录、ANALYZE、VACUUM） */
+export async function compactDatabase(): Promise<CompactionReport> {
+  return safeInvoke("compact_database");
+}
+
 export async function getDefaultProvider(): Promise<string> {
   return safeInvoke("get_default_provider");
 }
@@ -233,6 +612,35 @@ export async function clearLogs(): Promise<void> {
   }
 }
 
+/**
+ * 订阅新日志
+ *
+ * 所有窗口共享同一个级别过滤，再次调用会更新过滤条件。
+ *
+ * @param onEntry - 新日志回调
+ * @param level - 最低日志级别（debug/info/warn/error）
+ * @returns 取消监听函数（不会停止后端推送，需调用 unsubscribeLogs）
+ */
+export async function subscribeLogs(
+  onEntry: (entry: LogEntry) => void,
+  level?: string,
+): Promise<UnlistenFn> {
+  const unlisten = await safeListen<LogEntry>("log-entry", (event) =>
+    onEntry(event.payload),
+  );
+  try {
+    await safeInvoke("subscribe_logs", { level });
+  } catch (e) {
+    unlisten();
+    throw e;
+  }
+  return unlisten;
+}
+
+export async function unsubscribeLogs(): Promise<void> {
+  return safeInvoke("unsubscribe_logs");
+}
+
 export interface TestResult {
   success: boolean;
   status: number;
@@ -249,6 +657,110 @@ export async function testApi(
   return safeInvoke("test_api", { method, path, body, auth });
 }
 
+export type SelfTestStatus = "pass" | "fail" | "skip";
+
+export interface SelfTestCheck {
+  id: string;
+  name: string;
+  status: SelfTestStatus;
+  detail: string;
+  hint?: string;
+  time_ms: number;
+}
+
+export interface SelfTestReport {
+  passed: boolean;
+  model: string;
+  checked_at: string;
+  checks: SelfTestCheck[];
+}
+
+export async function runSelfTest(model?: string): Promise<SelfTestReport> {
+  return safeInvoke("run_self_test", { model });
+}
+
+export interface BenchmarkTarget {
+  /** Provider ID（不设置则按默认路由） */
+  provider?: string;
+  model: string;
+}
+
+export interface BenchmarkResult {
+  provider?: string;
+  model: string;
+  /** 实际执行的请求数（遇到限流时提前停止） */
+  requests: number;
+  success_count: number;
+  success_rate: number;
+  p50_latency_ms?: number;
+  p90_latency_ms?: number;
+  p99_latency_ms?: number;
+  avg_latency_ms?: number;
+  /** 平均输出速度（tokens/秒） */
+  avg_tokens_per_sec?: number;
+  errors: string[];
+}
+
+export interface BenchmarkReport {
+  prompt: string;
+  iterations: number;
+  checked_at: string;
+  results: BenchmarkResult[];
+}
+
+/** 对比多个 Provider / 模型的性能（不计入请求统计） */
+export async function benchmarkProviders(
+  prompt: string,
+  models: BenchmarkTarget[],
+  iterations: number,
+): Promise<BenchmarkReport> {
+  return safeInvoke("benchmark_providers", { prompt, models, iterations });
+}
+
+export interface RoutingPreset {
+  id: string;
+  name: string;
+  description: string;
+  /** 应用后的默认 Provider */
+  default_provider: string;
+  /** 应用后的模型别名（整体替换现有别名） */
+  model_aliases: Record<string, string>;
+}
+
+export interface PresetCanaryResult {
+  /** 预设涉及的模型（别名） */
+  models: string[];
+  resolved_model: string;
+  provider: string;
+  passed: boolean;
+  status?: number | null;
+  detail: string;
+  hint?: string;
+  time_ms: number;
+}
+
+export interface PresetApplyReport {
+  preset_id: string;
+  /** 是否已写入配置 */
+  applied: boolean;
+  /** 是否在金丝雀失败时强制应用 */
+  forced: boolean;
+  checked_at: string;
+  canaries: PresetCanaryResult[];
+}
+
+export async function getRecommendedPresets(): Promise<RoutingPreset[]> {
+  return safeInvoke("get_recommended_presets");
+}
+
+/** 金丝雀请求全部通过（或 force）时才应用预设，始终返回金丝雀结果 */
+export async function applyRecommendedPreset(
+  presetId: string,
+  force = false,
+): Promise<PresetApplyReport> {
+  return safeInvoke("apply_recommended_preset", { presetId, force });
+}
+
 export interface KiroCredentialStatus {
   loaded: boolean;
   has_access_token: boolean;
@@ -471,6 +983,8 @@ export interface EndpointProvidersConfig {
   kiro?: string | null;
   /** 其他客户端使用的 Provider */
   other?: string | null;
+  /** 强制非流式的客户端类型 */
+  force_non_streaming?: string[];
 }
 
 /**
@@ -497,6 +1011,38 @@ export async function setEndpointProvider(
   });
 }
 
+/**
+ * 设置客户端是否强制非流式
+ * @param clientType 客户端类型 (cursor, claude_code, codex, windsurf, kiro, other)
+ * @param enabled 是否强制非流式
+ */
+export async function setEndpointForceNonStreaming(
+  clientType: string,
+  enabled: boolean,
+): Promise<boolean> {
+  return safeInvoke("set_endpoint_force_non_streaming", {
+    endpoint: clientType,
+    enabled,
+  });
+}
+
+/**
+ * 获取价格表及缺少价格的路由模型
+ */
+export async function getPriceTable(): Promise<PriceTableStatus> {
+  return safeInvoke("get_price_table");
+}
+
+/**
+ * 替换价格表，立即用于费用计算并保存到配置
+ * @param table 模型 ID -> 价格
+ */
+export async function updatePriceTable(
+  table: Record<string, PriceTableEntry>,
+): Promise<PriceTableStatus> {
+  return safeInvoke("update_price_table", { table });
+}
+
 // Network Info
 export interface NetworkInfo {
   localhost: string;
@@ -33,6 +33,37 @@ export interface RecoveryConfig {
   recovery_timeout: number;
 }
 
+export interface NotificationThrottleConfig {
+  /** 合并窗口（毫秒），0 表示不合并 */
+  coalesce_window_ms: number;
+  /** 每分钟最多发送的拦截通知数，0 表示不限制 */
+  max_per_minute: number;
+}
+
+export interface InterceptNotification {
+  id: string;
+  notification_type: string;
+  title: string;
+  message: string;
+  url?: string;
+  source_process?: string;
+  timestamp: string;
+  /** 分组通知中合并的 URL（用于展开查看） */
+  grouped_urls: string[];
+}
+
+export type UrlRuleAction =
+  | "intercept"
+  | "ignore"
+  | "open_in_fingerprint_browser";
+
+export interface UrlRule {
+  pattern: string;
+  /** 模式类型，默认 glob */
+  kind?: "glob" | "regex";
+  action: UrlRuleAction;
+}
+
 export interface BrowserInterceptorConfig {
   enabled: boolean;
   target_processes: string[];
@@ -45,6 +76,9 @@ export interface BrowserInterceptorConfig {
   auto_launch_browser: boolean;
   fingerprint_browser: FingerprintBrowserConfig;
   recovery: RecoveryConfig;
+  /** URL 规则（按顺序匹配，首个命中生效；配置后未命中的 URL 将被放行） */
+  rules?: UrlRule[];
+  notification_throttle?: NotificationThrottleConfig;
 }
 
 export interface InterceptorStatistics {
@@ -153,6 +187,10 @@ export const browserInterceptorApi = {
     });
   },
 
+  async getRecentInterceptNotifications(): Promise<InterceptNotification[]> {
+    return await safeInvoke("get_recent_intercept_notifications");
+  },
+
   async showStatusNotification(
     message: string,
     notificationType: string,
@@ -207,6 +245,8 @@ export const showUrlInterceptNotification = (
   url: string,
   sourceProcess: string,
 ) => browserInterceptorApi.showUrlInterceptNotification(url, sourceProcess);
+export const getRecentInterceptNotifications = () =>
+  browserInterceptorApi.getRecentInterceptNotifications();
 export const showBrowserInterceptorStatusNotification = (
   message: string,
   type: string,
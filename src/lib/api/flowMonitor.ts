@@ -5,7 +5,8 @@
  * 支持 Flow 查询、搜索、统计、导出和标注管理。
  */
 
-import { safeInvoke } from "@/lib/dev-bridge";
+import { safeInvoke, safeListen } from "@/lib/dev-bridge";
+import type { UnlistenFn } from "@tauri-apps/api/event";
 
 // ============================================================================
 // Provider 类型
@@ -115,6 +116,38 @@ export interface CleanupFlowsResponse {
   freed_bytes: number;
 }
 
+/**
+ * 持久化 Flow 结果
+ */
+export interface PersistFlowsResponse {
+  /** 成功持久化的 Flow 数量 */
+  persisted_count: number;
+}
+
+/**
+ * 移除内存 Flow 结果
+ */
+export interface PruneMemoryFlowsResponse {
+  /** 从内存中移除的 Flow 数量 */
+  pruned_count: number;
+}
+
+/**
+ * 孤立 Flow 文件清理结果
+ */
+export interface PruneResult {
+  /** 是否为预演（未实际删除） */
+  dry_run: boolean;
+  /** 扫描的文件数 */
+  files_scanned: number;
+  /** 被清理（或将被清理）的文件路径 */
+  pruned_files: string[];
+  /** 因包含会话、书签或内存中的 Flow 而保留的文件数 */
+  files_protected: number;
+  /** 释放的字节数 */
+  bytes_reclaimed: number;
+}
+
 /**
  * 错误类型
  */
@@ -317,6 +350,8 @@ export interface ClientInfo {
   ip?: string;
   user_agent?: string;
   request_id?: string;
+  /** 客户端会话 ID（来自 x-session-id / x-conversation-id / x-correlation-id 请求头） */
+  session_id?: string;
 }
 
 /**
@@ -378,6 +413,30 @@ export interface FlowError {
   retryable: boolean;
 }
 
+/**
+ * 影子测试对比结果（实际响应为左侧，影子响应为右侧）
+ */
+export interface ShadowComparison {
+  credential_id: string;
+  credential_name?: string;
+  response?: LLMResponse;
+  error?: string;
+  latency_ms: number;
+  content_identical: boolean;
+  response_diffs: Array<{
+    path: string;
+    diff_type: "Added" | "Removed" | "Modified" | "Unchanged";
+    left_value: unknown;
+    right_value: unknown;
+  }>;
+  token_diff: {
+    input_diff: number;
+    output_diff: number;
+    total_diff: number;
+  };
+  compared_at: string;
+}
+
 // ============================================================================
 // 核心 Flow 类型
 // ============================================================================
@@ -395,6 +454,71 @@ export interface LLMFlow {
   timestamps: FlowTimestamps;
   state: FlowState;
   annotations: FlowAnnotations;
+  shadow?: ShadowComparison;
+}
+
+// ============================================================================
+// 死信类型
+// ============================================================================
+
+/**
+ * 死信记录（Provider 调用失败的请求，请求体已脱敏）
+ */
+export interface DeadLetter {
+  id: string;
+  created_at: string;
+  provider: string;
+  credential_uuid?: string | null;
+  model: string;
+  path: string;
+  status_code: number;
+  error_type: string;
+  error_message: string;
+  /** 脱敏后的请求体（JSON 字符串） */
+  request_body: string;
+  /** 请求体被截断时不可重放 */
+  truncated: boolean;
+}
+
+/**
+ * 重放配置
+ */
+export interface ReplayConfig {
+  credential_id?: string;
+  interval_ms?: number;
+}
+
+/**
+ * 重放结果
+ */
+export interface ReplayResult {
+  original_flow_id: string;
+  replay_flow_id: string;
+  success: boolean;
+  error?: string;
+  started_at: string;
+  completed_at: string;
+  duration_ms: number;
+}
+
+/**
+ * 模糊重放的请求变异
+ */
+export type FuzzMutation =
+  | { type: "drop_field"; path: string }
+  | { type: "unknown_model" }
+  | { type: "malformed_json" }
+  | { type: "oversized_body"; bytes?: number };
+
+/**
+ * 单个变异的模糊重放结果
+ */
+export interface FuzzResult {
+  mutation: FuzzMutation;
+  status?: number;
+  error?: string;
+  request_bytes: number;
+  duration_ms: number;
 }
 
 // ============================================================================
@@ -581,6 +705,25 @@ export interface ExportResult {
   mime_type: string;
 }
 
+/**
+ * NDJSON 流式导出选项
+ */
+export interface NdjsonExportOptions {
+  include_raw?: boolean;
+  /** 请求/响应体序列化后的最大字节数，超出时截断 */
+  max_body_bytes?: number;
+  include_stream_chunks?: boolean;
+  redact_sensitive?: boolean;
+}
+
+/**
+ * NDJSON 导出结果
+ */
+export interface NdjsonExportSummary {
+  count: number;
+  bytes_written: number;
+}
+
 // ============================================================================
 // 标注更新类型
 // ============================================================================
@@ -619,6 +762,8 @@ export interface FlowSummary {
   has_thinking: boolean;
   content_preview?: string;
   chunk_count?: number;
+  /** 客户端会话 ID */
+  session_id?: string;
 }
 
 /**
@@ -637,10 +782,21 @@ export interface FlowUpdate {
  */
 export type FlowEvent =
   | { type: "FlowStarted"; flow: FlowSummary }
-  | { type: "FlowUpdated"; id: string; update: FlowUpdate }
+  | {
+      type: "FlowUpdated";
+      id: string;
+      update: FlowUpdate;
+      session_id?: string;
+    }
   | { type: "FlowCompleted"; id: string; summary: FlowSummary }
-  | { type: "FlowFailed"; id: string; error: FlowError }
-  | { type: "ThresholdWarning"; id: string; result: ThresholdCheckResult };
+  | { type: "FlowFailed"; id: string; error: FlowError; session_id?: string }
+  | {
+      type: "ThresholdWarning";
+      id: string;
+      result: ThresholdCheckResult;
+      session_id?: string;
+    }
+  | { type: "AlertTriggered"; alert: AlertEvent };
 
 /**
  * 阈值检测结果（用于事件）
@@ -820,6 +976,29 @@ export const flowMonitorApi = {
     };
   },
 
+  /**
+   * 按时间范围把 Flow 流式导出为 NDJSON 文件
+   *
+   * @param range - 时间范围
+   * @param path - 输出文件路径
+   * @param filter - 其他过滤条件（可选）
+   * @param options - 导出选项（可选）
+   * @returns 写入的 Flow 数量和字节数
+   */
+  async exportFlowsNdjson(
+    range: TimeRange,
+    path: string,
+    filter?: FlowFilter,
+    options?: NdjsonExportOptions,
+  ): Promise<NdjsonExportSummary> {
+    return safeInvoke("export_flows_ndjson", {
+      range,
+      filter: filter ?? null,
+      path,
+      options: options ?? null,
+    });
+  },
+
   /**
    * 更新 Flow 标注
    *
@@ -857,6 +1036,39 @@ export const flowMonitorApi = {
     return safeInvoke("cleanup_flows", { request });
   },
 
+  /**
+   * 将内存中的 Flow 持久化到文件存储
+   *
+   * @param flowIds - 要持久化的 Flow ID 列表
+   * @returns 持久化结果
+   */
+  async persistFlowsToStore(flowIds: string[]): Promise<PersistFlowsResponse> {
+    return safeInvoke("persist_flows_to_store", { flowIds });
+  },
+
+  /**
+   * 从内存中移除已持久化的 Flow
+   *
+   * @returns 移除结果
+   */
+  async pruneMemoryFlows(): Promise<PruneMemoryFlowsResponse> {
+    return safeInvoke("prune_memory_flows");
+  },
+
+  /**
+   * 清理磁盘上没有索引引用的孤立 Flow 文件
+   *
+   * @param dryRun - 只统计不删除（默认 true）
+   * @param minAgeHours - 只清理修改时间超过该小时数的文件（默认按保留天数）
+   * @returns 清理结果
+   */
+  async pruneFlowFiles(
+    dryRun?: boolean,
+    minAgeHours?: number,
+  ): Promise<PruneResult> {
+    return safeInvoke("prune_flow_files", { dryRun, minAgeHours });
+  },
+
   /**
    * 获取最近的 Flow 列表
    *
@@ -1241,7 +1453,31 @@ export interface EnhancedStats {
 /**
  * 报告格式
  */
-export type ReportFormat = "json" | "markdown" | "csv";
+export type ReportFormat = "json" | "markdown" | "csv" | "html";
+
+/**
+ * 延迟百分位的时间桶大小
+ */
+export type LatencyBucket = "minute" | "hour" | "day";
+
+/**
+ * 单个时间桶的延迟百分位（毫秒，桶内无样本时为 null）
+ */
+export interface LatencyPercentilePoint {
+  timestamp: string;
+  count: number;
+  p50: number | null;
+  p90: number | null;
+  p99: number | null;
+}
+
+/**
+ * 延迟百分位时间序列
+ */
+export interface LatencyPercentileSeries {
+  bucket: LatencyBucket;
+  points: LatencyPercentilePoint[];
+}
 
 /**
  * 增强统计 API
@@ -1349,6 +1585,33 @@ export const enhancedStatsApi = {
     });
   },
 
+  /**
+   * 获取按时间桶统计的延迟百分位（p50/p90/p99）
+   *
+   * @param filter - 过滤条件（可按 providers/models 限定）
+   * @param timeRange - 时间范围
+   * @param bucket - 时间桶大小
+   * @returns 延迟百分位时间序列
+   */
+  async getLatencyPercentiles(
+    filter: FlowFilter = {},
+    timeRange?: StatsTimeRange,
+    bucket: LatencyBucket = "hour",
+  ): Promise<LatencyPercentileSeries> {
+    const now = new Date();
+    const defaultTimeRange: StatsTimeRange = {
+      start: new Date(now.getTime() - 24 * 60 * 60 * 1000).toISOString(),
+      end: now.toISOString(),
+    };
+    return safeInvoke("get_latency_percentiles", {
+      request: {
+        filter,
+        time_range: timeRange || defaultTimeRange,
+        bucket,
+      },
+    });
+  },
+
   /**
    * 导出统计报告
    *
@@ -1375,6 +1638,67 @@ export const enhancedStatsApi = {
       },
     });
   },
+
+  /**
+   * 查询死信记录（按时间倒序）
+   *
+   * @param provider - 按 Provider 过滤
+   * @param limit - 返回条数
+   * @returns 死信记录列表
+   */
+  async getDeadLetters(
+    provider?: string,
+    limit?: number,
+  ): Promise<DeadLetter[]> {
+    return safeInvoke("get_dead_letters", { provider, limit });
+  },
+
+  /**
+   * 删除单条死信记录
+   *
+   * @param id - 死信 ID
+   * @returns 是否删除成功
+   */
+  async deleteDeadLetter(id: string): Promise<boolean> {
+    return safeInvoke("delete_dead_letter", { id });
+  },
+
+  /**
+   * 清空死信记录
+   *
+   * @returns 删除的条数
+   */
+  async clearDeadLetters(): Promise<number> {
+    return safeInvoke("clear_dead_letters");
+  },
+
+  /**
+   * 重放死信记录
+   *
+   * @param id - 死信 ID
+   * @param config - 重放配置
+   * @returns 重放结果
+   */
+  async replayDeadLetter(
+    id: string,
+    config?: ReplayConfig,
+  ): Promise<ReplayResult> {
+    return safeInvoke("replay_dead_letter", { id, config });
+  },
+
+  /**
+   * 模糊重放：对 Flow 的请求施加变异后发往本地服务
+   *
+   * @param flowId - 作为基准的 Flow ID
+   * @param mutations - 依次施加的变异
+   * @returns 各变异的状态码和错误信息
+   */
+  async fuzzReplayFlow(
+    flowId: string,
+    mutations: FuzzMutation[],
+  ): Promise<FuzzResult[]> {
+    return safeInvoke("fuzz_replay_flow", { flowId, mutations });
+  },
 };
 
 export default flowMonitorApi;
@@ -1397,6 +1721,81 @@ export interface ThresholdConfig {
   input_token_threshold?: number;
   /** 输出 Token 阈值（可选） */
   output_token_threshold?: number;
+  /** 异常告警规则 */
+  alert_rules: AlertRule[];
+  /** 同一规则两次告警之间的冷却时间（秒） */
+  alert_cooldown_seconds: number;
+}
+
+/**
+ * 告警指标
+ */
+export type AlertMetric = "request_rate" | "error_rate" | "p99_latency";
+
+/**
+ * 告警比较运算符
+ */
+export type AlertComparator = "gt" | "gte" | "lt" | "lte";
+
+/**
+ * 异常告警规则
+ */
+export interface AlertRule {
+  /** 规则 ID */
+  id: string;
+  /** 是否启用 */
+  enabled: boolean;
+  /** 监控指标 */
+  metric: AlertMetric;
+  /** 比较运算符 */
+  comparator: AlertComparator;
+  /** 阈值（请求速率为每秒请求数，错误率为 0-1，延迟为毫秒） */
+  threshold: number;
+  /** 统计窗口（秒） */
+  window_seconds: number;
+  /** 是否发送桌面通知 */
+  desktop_notification: boolean;
+}
+
+/**
+ * 异常告警事件
+ */
+export interface AlertEvent {
+  rule_id: string;
+  metric: AlertMetric;
+  comparator: AlertComparator;
+  threshold: number;
+  /** 实际指标值 */
+  value: number;
+  window_seconds: number;
+  message: string;
+  /** 是否需要桌面通知 */
+  desktop: boolean;
+  timestamp: string;
+}
+
+/**
+ * 捕获排除规则（已设置的条件全部满足时排除）
+ */
+export interface CaptureExclusionRule {
+  /** 请求路径（支持 * 通配符） */
+  path?: string;
+  /** HTTP 方法 */
+  method?: string;
+  /** 请求头名称 */
+  header?: string;
+  /** 请求头值（支持 * 通配符） */
+  header_value?: string;
+}
+
+/**
+ * 捕获过滤器
+ */
+export interface CaptureFilter {
+  /** 排除规则（任一规则匹配即排除） */
+  exclusions: CaptureExclusionRule[];
+  /** 被排除的请求是否仍计入请求速率 */
+  count_excluded_in_rate: boolean;
 }
 
 /**
@@ -1450,4 +1849,111 @@ export const realtimeMonitorApi = {
   async setRateWindow(windowSeconds: number): Promise<void> {
     return safeInvoke("set_rate_window", { windowSeconds });
   },
+
+  /**
+   * 获取捕获过滤器
+   *
+   * @returns 捕获排除规则
+   */
+  async getCaptureFilter(): Promise<CaptureFilter> {
+    return safeInvoke("get_flow_capture_filter");
+  },
+
+  /**
+   * 更新捕获过滤器
+   *
+   * @param filter - 新的捕获过滤器
+   */
+  async updateCaptureFilter(filter: CaptureFilter): Promise<void> {
+    return safeInvoke("update_flow_capture_filter", { filter });
+  },
+
+  /**
+   * 跟随单个客户端会话，只接收该会话的 Flow 事件
+   *
+   * 同一时间只跟随一个会话，再次调用会切换到新的会话。
+   *
+   * @param sessionId - 客户端会话 ID
+   * @param onEvent - 事件回调
+   * @returns 取消监听函数（不会停止后端跟随，需调用 unfollowSession）
+   */
+  async followSession(
+    sessionId: string,
+    onEvent: (event: FlowEvent) => void,
+  ): Promise<UnlistenFn> {
+    const unlisten = await safeListen<FlowEvent>(
+      "flow-follow-event",
+      (event) => onEvent(event.payload),
+    );
+    try {
+      await safeInvoke("subscribe_flow_events", { sessionId });
+    } catch (e) {
+      unlisten();
+      throw e;
+    }
+    return unlisten;
+  },
+
+  /**
+   * 停止跟随会话
+   */
+  async unfollowSession(): Promise<void> {
+    return safeInvoke("unfollow_flow_session");
+  },
+};
+
+// ============================================================================
+// 过滤变量
+// ============================================================================
+
+/**
+ * 过滤变量（在过滤表达式中以 `$name` 引用）
+ */
+export interface FilterVariable {
+  /** 变量名（不含 `$`） */
+  name: string;
+  /** 变量表达式 */
+  expression: string;
+  /** 描述 */
+  description?: string;
+  /** 更新时间 */
+  updated_at: string;
+}
+
+/**
+ * 过滤变量 API
+ */
+export const filterVariableApi = {
+  /**
+   * 列出所有过滤变量
+   */
+  async listVariables(): Promise<FilterVariable[]> {
+    return safeInvoke("list_filter_variables");
+  },
+
+  /**
+   * 保存过滤变量（同名变量会被覆盖）
+   *
+   * @param name - 变量名（可带 `$` 前缀）
+   * @param expression - 变量表达式
+   * @param description - 描述
+   */
+  async saveVariable(
+    name: string,
+    expression: string,
+    description?: string,
+  ): Promise<FilterVariable> {
+    return safeInvoke("save_filter_variable", {
+      request: { name, expression, description },
+    });
+  },
+
+  /**
+   * 删除过滤变量（仍被引用时失败）
+   *
+   * @param name - 变量名
+   */
+  async deleteVariable(name: string): Promise<void> {
+    return safeInvoke("delete_filter_variable", { name });
+  },
 };
@@ -44,6 +44,28 @@ export interface MachineIdHistory {
   backup_path?: string;
 }
 
+export interface MachineIdLocation {
+  location: string;
+  value?: string;
+  writable: boolean;
+  error?: string;
+}
+
+export interface MachineIdAudit {
+  platform: string;
+  locations: MachineIdLocation[];
+  consistent: boolean;
+  distinct_values: string[];
+}
+
+export interface MachineIdRepairResult {
+  success: boolean;
+  message: string;
+  requires_admin: boolean;
+  backup_path?: string;
+  updated_locations: string[];
+}
+
 export interface SystemInfo {
   os: string;
   arch: string;
@@ -96,6 +118,29 @@ export const machineIdApi = {
     return safeInvoke("check_admin_privileges");
   },
 
+  /**
+   * 审计各系统位置的机器码是否一致
+   */
+  async auditMachineId(): Promise<MachineIdAudit> {
+    return safeInvoke("audit_machine_id");
+  },
+
+  /**
+   * 将规范机器码写入所有系统位置（写入前自动备份原值）
+   */
+  async repairMachineId(canonicalId: string): Promise<MachineIdRepairResult> {
+    return safeInvoke("repair_machine_id", { canonicalId });
+  },
+
+  /**
+   * 从修复备份恢复各系统位置的原值
+   */
+  async undoMachineIdRepair(
+    backupPath: string,
+  ): Promise<MachineIdRepairResult> {
+    return safeInvoke("undo_machine_id_repair", { backupPath });
+  },
+
   /**
    * 获取操作系统类型
    */
@@ -144,6 +144,18 @@ export interface CredentialDisplay {
   api_key?: string;
   // 凭证级代理 URL（可覆盖全局代理设置）
   proxy_url?: string;
+  // 凭证层级（如 paid、free）
+  tier?: string;
+  // 凭证级上游请求头（覆盖 upstream_headers 配置）
+  upstream_headers?: Record<string, string>;
+  // 健康分（0~1，按请求结果的指数移动平均计算）
+  health_score: number;
+  // 从模板导入、等待填写机密的凭证（不参与选择）
+  needs_secret: boolean;
+  // 是否暂停中（与禁用、不健康分开显示）
+  is_parked: boolean;
+  // 暂停到期时间（仅暂停中时有值）
+  parked_until?: string;
 }
 
 // Pool statistics
@@ -154,6 +166,10 @@ export interface PoolStats {
   disabled: number;
   total_usage: number;
   total_errors: number;
+  // 平均健康分（不含禁用凭证）
+  avg_health_score?: number;
+  // 暂停中的凭证数
+  parked_count?: number;
 }
 
 // Provider pool overview
@@ -192,6 +208,32 @@ export interface TokenCacheStatus {
   last_refresh_error?: string;
 }
 
+// Token cache diagnostics per credential (with in-process hit/miss counts)
+// Availability of a local OAuth callback port
+export interface OAuthCallbackPortStatus {
+  port: number;
+  available: boolean;
+  error?: string;
+}
+
+// Bulk credential update; unset fields are left unchanged.
+// rename_pattern supports {n} (index by creation time) and {name} (old name).
+export interface BulkCredentialUpdate {
+  uuids?: string[];
+  rename_pattern?: string;
+  start_index?: number;
+  is_disabled?: boolean;
+  tier?: string;
+}
+
+export interface CredentialTokenCacheStatus extends TokenCacheStatus {
+  uuid: string;
+  name?: string;
+  provider_type: string;
+  hits: number;
+  misses: number;
+}
+
 // Request types
 export interface AddCredentialRequest {
   provider_type: string;
@@ -219,6 +261,44 @@ export interface UpdateCredentialRequest {
   new_proxy_url?: string;
 }
 
+// 凭证池模板（机密替换为占位符，用于团队共享）
+export interface PoolTemplateExportResult {
+  content: string;
+  suggested_filename: string;
+  credential_count: number;
+}
+
+export interface PoolTemplateImportResult {
+  // 创建的占位凭证（填写机密后才会启用）
+  credentials: CredentialDisplay[];
+  routing_applied: boolean;
+}
+
+// 重复凭证组：保留最早的凭证（已合并统计），删除其余
+export interface DuplicateCredentialGroup {
+  provider_type: PoolProviderType;
+  kept: ProviderCredential;
+  merged: { uuid: string; name?: string }[];
+}
+
+export interface PoolDedupeReport {
+  dry_run: boolean;
+  groups: DuplicateCredentialGroup[];
+  removed_count: number;
+}
+
+// 凭证对应的上游账号（api: 身份接口，credential_file: 凭证文件记录，api_key: 掩码 Key）
+export interface CredentialAccountInfo {
+  uuid: string;
+  provider_type: PoolProviderType;
+  account_id: string;
+  email?: string;
+  display_name?: string;
+  organization?: string;
+  source: "api" | "credential_file" | "api_key";
+  fetched_at: string;
+}
+
 export const providerPoolApi = {
   // Get overview of all provider pools
   async getOverview(): Promise<ProviderPoolOverview[]> {
@@ -266,6 +346,94 @@ export const providerPoolApi = {
     return safeInvoke("toggle_provider_pool_credential", { uuid, isDisabled });
   },
 
+  // Set credential tier (null or empty string clears it)
+  async setCredentialTier(
+    uuid: string,
+    tier: string | null,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("set_provider_pool_credential_tier", { uuid, tier });
+  },
+
+  // Park a credential until the given time (skipped by the selector, auto-unparks when it passes)
+  async parkCredential(
+    uuid: string,
+    until: Date,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("park_credential", {
+      uuid,
+      untilTimestamp: Math.floor(until.getTime() / 1000),
+    });
+  },
+
+  // Unpark a credential immediately
+  async unparkCredential(uuid: string): Promise<ProviderCredential> {
+    return safeInvoke("unpark_credential", { uuid });
+  },
+
+  // Bulk rename / enable / disable / set tier for one provider type; returns changed uuids
+  async bulkUpdateCredentials(
+    providerType: PoolProviderType,
+    updates: BulkCredentialUpdate,
+  ): Promise<string[]> {
+    return safeInvoke("bulk_update_credentials", { providerType, updates });
+  },
+
+  // Set credential upstream header overrides (empty object clears them)
+  async setCredentialUpstreamHeaders(
+    uuid: string,
+    headers: Record<string, string>,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("set_provider_pool_credential_upstream_headers", {
+      uuid,
+      headers,
+    });
+  },
+
+  // Export the pool structure and routing as a template without secrets
+  async exportPoolTemplate(): Promise<PoolTemplateExportResult> {
+    return safeInvoke("export_pool_template");
+  },
+
+  // Import a pool template as credential slots awaiting secrets
+  async importPoolTemplate(
+    content: string,
+    applyRouting: boolean,
+  ): Promise<PoolTemplateImportResult> {
+    return safeInvoke("import_pool_template", { content, applyRouting });
+  },
+
+  // Fill the secret of an imported slot (API key or OAuth file path)
+  async fillCredentialSecret(
+    uuid: string,
+    secret: string,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("fill_provider_pool_credential_secret", {
+      uuid,
+      secret,
+    });
+  },
+
+  // Merge duplicate credentials (dryRun only reports what would be merged)
+  async dedupeCredentials(dryRun: boolean): Promise<PoolDedupeReport> {
+    return safeInvoke("dedupe_pool_credentials", { dryRun });
+  },
+
+  // Look up the upstream account a credential belongs to (cached for 5 minutes)
+  async getCredentialAccountInfo(uuid: string): Promise<CredentialAccountInfo> {
+    return safeInvoke("get_credential_account_info", { uuid });
+  },
+
+  // Set or clear the cached Antigravity project ID (null clears it)
+  async setCredentialProjectId(
+    uuid: string,
+    projectId: string | null,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("set_provider_pool_credential_project_id", {
+      uuid,
+      projectId,
+    });
+  },
+
   // Reset credential counters
   async resetCredential(uuid: string): Promise<void> {
     return safeInvoke("reset_provider_pool_credential", { uuid });
@@ -548,16 +716,30 @@ export const providerPoolApi = {
     return safeInvoke("cancel_kiro_social_auth_login");
   },
 
-  // 启动 Kiro Social Auth 回调服务器
-  async startKiroSocialAuthCallbackServer(): Promise<boolean> {
+  // 启动 Kiro Social Auth 回调服务器，返回实际使用的端口
+  async startKiroSocialAuthCallbackServer(): Promise<number> {
     return safeInvoke("start_kiro_social_auth_callback_server");
   },
 
+  // 检查 OAuth 回调端口是否可用
+  async checkOAuthCallbackPort(port: number): Promise<OAuthCallbackPortStatus> {
+    return safeInvoke("check_oauth_callback_port", { port });
+  },
+
   // OAuth token management
   async refreshCredentialToken(uuid: string): Promise<string> {
     return safeInvoke("refresh_pool_credential_token", { uuid });
   },
 
+  async getTokenCacheStatus(): Promise<CredentialTokenCacheStatus[]> {
+    return safeInvoke("get_token_cache_status");
+  },
+
+  // 清除 Token 缓存（不传 uuid 时清除全部），返回清除的凭证数
+  async invalidateTokenCache(uuid?: string): Promise<number> {
+    return safeInvoke("invalidate_token_cache", { uuid });
+  },
+
   async getCredentialOAuthStatus(uuid: string): Promise<OAuthStatus> {
     return safeInvoke("get_pool_credential_oauth_status", { uuid });
   },
@@ -699,6 +881,37 @@ export async function getLocalKiroCredentialUuid(): Promise<string | null> {
   return safeInvoke("get_local_kiro_credential_uuid");
 }
 
+// 本机发现的 Kiro 凭证来源
+export interface KiroCredentialSource {
+  /** 来源类型：sso_cache（~/.aws/sso/cache）或 app_state（Kiro 应用状态数据库） */
+  kind: "sso_cache" | "app_state";
+  /** 文件路径 */
+  path: string;
+  /** state.vscdb 中的键（仅 app_state） */
+  key?: string;
+  /** 是否由系统钥匙串加密（导入时需要访问钥匙串） */
+  encrypted: boolean;
+  /** 认证方式 */
+  auth_method?: string;
+  /** 过期时间 */
+  expires_at?: string;
+}
+
+// 发现本机已安装的 Kiro 客户端中的凭证来源
+export async function discoverKiroInstallations(): Promise<
+  KiroCredentialSource[]
+> {
+  return safeInvoke("discover_kiro_installations");
+}
+
+// 从本机 Kiro 安装导入凭证副本（不刷新 Token，不影响 Kiro IDE 登录）
+export async function importKiroFromInstallation(
+  source: KiroCredentialSource,
+  name?: string,
+): Promise<ProviderCredential> {
+  return safeInvoke("import_kiro_from_installation", { source, name });
+}
+
 // ============ Kiro 凭证池管理 HTTP API ============
 
 /** 可用凭证信息 */
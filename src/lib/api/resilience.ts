@@ -5,7 +5,11 @@ export interface RetryConfig {
   max_retries: number;
   base_delay_ms: number;
   max_delay_ms: number;
-  retryable_codes: number[];
+  retryable_status_codes: number[];
+  /** 未收到响应的连接错误是否重试（默认 true） */
+  retry_on_connection_error?: boolean;
+  /** 流式请求输出开始前的 5xx 是否重试（默认 false） */
+  retry_streaming_server_errors?: boolean;
 }
 
 // Failover configuration
@@ -14,12 +18,48 @@ export interface FailoverConfig {
   switch_on_quota: boolean;
 }
 
-// Switch log entry
+// Switch log
+export type SwitchTrigger =
+  | "rate_limit"
+  | "server_error"
+  | "health"
+  | "manual"
+  | "credentials_unavailable";
+
 export interface SwitchLogEntry {
+  id: number;
+  /** RFC 3339 时间 */
+  timestamp: string;
   from_provider: string;
   to_provider: string;
-  failure_type: string;
-  timestamp: string;
+  from_credential: string | null;
+  to_credential: string | null;
+  trigger: SwitchTrigger;
+  request_id: string | null;
+  model: string | null;
+  detail: string | null;
+}
+
+export interface SwitchLogQuery {
+  trigger?: SwitchTrigger;
+  /** 匹配原或新 Provider */
+  provider?: string;
+  /** 匹配原或新凭证 UUID */
+  credential_uuid?: string;
+  request_id?: string;
+  /** RFC 3339 时间（含） */
+  since?: string;
+  /** RFC 3339 时间（不含） */
+  until?: string;
+  offset?: number;
+  /** 默认 50，最大 500 */
+  limit?: number;
+}
+
+export interface SwitchLogPage {
+  /** 按时间倒序 */
+  entries: SwitchLogEntry[];
+  total: number;
 }
 
 export const resilienceApi = {
@@ -42,11 +82,11 @@ export const resilienceApi = {
   },
 
   // Switch log
-  async getSwitchLog(): Promise<SwitchLogEntry[]> {
-    return safeInvoke("get_switch_log");
+  async getSwitchLog(query?: SwitchLogQuery): Promise<SwitchLogPage> {
+    return safeInvoke("get_switch_log", { query });
   },
 
-  async clearSwitchLog(): Promise<void> {
+  async clearSwitchLog(): Promise<number> {
     return safeInvoke("clear_switch_log");
   },
 };
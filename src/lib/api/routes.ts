@@ -10,6 +10,8 @@ export interface RouteInfo {
   selector: string;
   provider_type: string;
   credential_count: number;
+  /** 当前健康的凭证数量 */
+  healthy_credential_count?: number;
   endpoints: RouteEndpoint[];
   tags: string[];
   enabled: boolean;
@@ -19,6 +21,15 @@ export interface RouteListResponse {
   base_url: string;
   default_provider: string;
   routes: RouteInfo[];
+  /** 过滤后（分页前）的路由总数，不含默认路由 */
+  total?: number;
+}
+
+export interface RouteQuery {
+  provider_type?: string;
+  tag?: string;
+  limit?: number;
+  offset?: number;
 }
 
 export interface CurlExample {
@@ -26,12 +37,96 @@ export interface CurlExample {
   command: string;
 }
 
+export interface ModelRoutingExplanation {
+  /** 请求中的原始模型名 */
+  requested: string;
+  /** 用于匹配的规范化模型名（与原始模型名相同时为 null） */
+  normalized: string | null;
+  /** 命中的别名规则 */
+  matched_alias: string | null;
+  /** 解析后的实际模型名 */
+  resolved: string;
+  /** 是否启用模型名规范化 */
+  normalization_enabled: boolean;
+  /** 默认 Provider */
+  default_provider: string;
+}
+
+/** 单个模型的路由模拟结果 */
+export interface RoutingSimulation {
+  requested: string;
+  normalized: string | null;
+  /** 命中的别名规则 */
+  matched_alias: string | null;
+  /** 解析后的实际模型名 */
+  resolved: string;
+  /** 路由到的 Provider */
+  provider: string;
+  /** 命中的层级规则 */
+  matched_tier_rule: { index: number; pattern: string; tier: string } | null;
+  /** 可用凭证数量（自定义 Provider 不检查，为 null） */
+  healthy_credentials: number | null;
+  has_healthy_credential: boolean;
+}
+
+/** 模型到凭证层级的偏好规则（按顺序匹配，首条命中生效） */
+export interface TierRule {
+  /** 模型通配符（如 `gemini-*-pro`） */
+  pattern: string;
+  /** 偏好的凭证层级 */
+  tier: string;
+}
+
+export type RoutingRuleIssue =
+  | { kind: "duplicate"; first_index: number }
+  | { kind: "shadowed"; by_index: number; by_pattern: string }
+  | { kind: "no_healthy_credentials"; tier: string };
+
+export type RoutingRuleWarning = RoutingRuleIssue & {
+  /** 规则在 tier_rules 中的位置 */
+  index: number;
+  pattern: string;
+  message: string;
+};
+
+export interface RoutingRulesUpdate {
+  rules: TierRule[];
+  /** 规则检查警告（不阻止保存） */
+  warnings: RoutingRuleWarning[];
+}
+
 export const routesApi = {
-  async getAvailableRoutes(): Promise<RouteListResponse> {
-    return safeInvoke("get_available_routes");
+  async getAvailableRoutes(query?: RouteQuery): Promise<RouteListResponse> {
+    return safeInvoke("get_available_routes", { query });
   },
 
   async getCurlExamples(selector: string): Promise<CurlExample[]> {
     return safeInvoke("get_route_curl_examples", { selector });
   },
+
+  async explainModelRouting(model: string): Promise<ModelRoutingExplanation> {
+    return safeInvoke("explain_model_routing", { model });
+  },
+
+  async simulateRouting(models: string[]): Promise<RoutingSimulation[]> {
+    return safeInvoke("simulate_routing", { models });
+  },
+
+  async lintRoutingRules(): Promise<RoutingRuleWarning[]> {
+    return safeInvoke("lint_routing_rules");
+  },
+
+  async addRoutingRule(
+    rule: TierRule,
+    index?: number,
+  ): Promise<RoutingRulesUpdate> {
+    return safeInvoke("add_routing_rule", { rule, index });
+  },
+
+  async updateRoutingRule(
+    index: number,
+    rule: TierRule,
+  ): Promise<RoutingRulesUpdate> {
+    return safeInvoke("update_routing_rule", { index, rule });
+  },
 };
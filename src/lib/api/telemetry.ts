@@ -9,6 +9,10 @@ export type RequestStatus =
   | "retrying"
   | "cancelled";
 
+export type RequestKind = "sync" | "batch";
+
+export type RequestPriority = "interactive" | "batch";
+
 export interface RequestLog {
   id: string;
   timestamp: string;
@@ -24,6 +28,19 @@ export interface RequestLog {
   is_streaming: boolean;
   credential_id?: string;
   retry_count: number;
+  kind?: RequestKind;
+  /** 是否因超出单次请求费用上限而中止 */
+  aborted_for_cost?: boolean;
+  /** 请求体大小（字节） */
+  request_bytes?: number;
+  /** 响应体大小（字节） */
+  response_bytes?: number;
+  /** 终端用户标识（隐私模式下为哈希） */
+  user_id?: string;
+  /** 请求优先级 */
+  priority?: RequestPriority;
+  /** 等待并发槽位的时间（毫秒） */
+  queue_wait_ms?: number;
 }
 
 export interface StatsSummary {
@@ -38,6 +55,22 @@ export interface StatsSummary {
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
+  total_request_bytes: number;
+  total_response_bytes: number;
+  avg_request_bytes: number;
+  avg_response_bytes: number;
+  avg_queue_wait_ms: number;
+  max_queue_wait_ms: number;
+  /** 按模型的请求/响应体大小（只在总体摘要中返回） */
+  bytes_by_model?: ModelBytes[];
+}
+
+export interface ModelBytes {
+  model: string;
+  total_request_bytes: number;
+  total_response_bytes: number;
+  avg_request_bytes: number;
+  avg_response_bytes: number;
 }
 
 export interface ProviderStats {
@@ -53,6 +86,12 @@ export interface ProviderStats {
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
+  total_request_bytes: number;
+  total_response_bytes: number;
+  avg_request_bytes: number;
+  avg_response_bytes: number;
+  avg_queue_wait_ms: number;
+  max_queue_wait_ms: number;
 }
 
 export interface ModelStats {
@@ -68,12 +107,45 @@ export interface ModelStats {
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
+  total_request_bytes: number;
+  total_response_bytes: number;
+  avg_request_bytes: number;
+  avg_response_bytes: number;
+  avg_queue_wait_ms: number;
+  max_queue_wait_ms: number;
+}
+
+export interface UserStats {
+  user_id: string;
+  total_requests: number;
+  successful_requests: number;
+  failed_requests: number;
+  timeout_requests: number;
+  success_rate: number;
+  avg_latency_ms: number;
+  min_latency_ms?: number;
+  max_latency_ms?: number;
+  total_input_tokens: number;
+  total_output_tokens: number;
+  total_tokens: number;
+  total_request_bytes: number;
+  total_response_bytes: number;
+  avg_request_bytes: number;
+  avg_response_bytes: number;
+  avg_queue_wait_ms: number;
+  max_queue_wait_ms: number;
 }
 
 export interface TokenStatsSummary {
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
+  /** 写入提示缓存的输入 Token 数 */
+  total_cache_creation_input_tokens: number;
+  /** 命中提示缓存的输入 Token 数 */
+  total_cache_read_input_tokens: number;
+  /** 按价格表估算的费用（美元），价格未知时为空 */
+  estimated_cost_usd?: number;
   record_count: number;
   actual_count: number;
   estimated_count: number;
@@ -86,6 +158,12 @@ export interface ProviderTokenStats {
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
+  /** 写入提示缓存的输入 Token 数 */
+  total_cache_creation_input_tokens: number;
+  /** 命中提示缓存的输入 Token 数 */
+  total_cache_read_input_tokens: number;
+  /** 按价格表估算的费用（美元），价格未知时为空 */
+  estimated_cost_usd?: number;
   record_count: number;
   actual_count: number;
   estimated_count: number;
@@ -98,6 +176,12 @@ export interface ModelTokenStats {
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
+  /** 写入提示缓存的输入 Token 数 */
+  total_cache_creation_input_tokens: number;
+  /** 命中提示缓存的输入 Token 数 */
+  total_cache_read_input_tokens: number;
+  /** 按价格表估算的费用（美元），价格未知时为空 */
+  estimated_cost_usd?: number;
   record_count: number;
   actual_count: number;
   estimated_count: number;
@@ -111,6 +195,12 @@ export interface PeriodTokenStats {
   total_input_tokens: number;
   total_output_tokens: number;
   total_tokens: number;
+  /** 写入提示缓存的输入 Token 数 */
+  total_cache_creation_input_tokens: number;
+  /** 命中提示缓存的输入 Token 数 */
+  total_cache_read_input_tokens: number;
+  /** 按价格表估算的费用（美元），价格未知时为空 */
+  estimated_cost_usd?: number;
   record_count: number;
   actual_count: number;
   estimated_count: number;
@@ -118,6 +208,17 @@ export interface PeriodTokenStats {
   avg_output_tokens: number;
 }
 
+/** 会话费用汇总（会话中的 Flow 按请求 ID 关联 Token 记录） */
+export interface SessionCostSummary {
+  session_id: string;
+  flow_count: number;
+  /** 没有对应 Token 记录的 Flow 数 */
+  unmatched_flow_count: number;
+  total: TokenStatsSummary;
+  /** 按估算费用降序 */
+  by_model: ModelTokenStats[];
+}
+
 export interface TimeRangeParam {
   start?: string;
   end?: string;
@@ -165,6 +266,12 @@ export async function getStatsByModel(
   return safeInvoke("get_stats_by_model", { time_range: timeRange });
 }
 
+export async function getStatsByUser(
+  timeRange?: TimeRangeParam,
+): Promise<Record<string, UserStats>> {
+  return safeInvoke("get_stats_by_user", { time_range: timeRange });
+}
+
 // ========== Token 统计 API ==========
 
 export async function getTokenSummary(
@@ -190,3 +297,111 @@ export async function getTokenStatsByDay(
 ): Promise<PeriodTokenStats[]> {
   return safeInvoke("get_token_stats_by_day", { days });
 }
+
+export async function getSessionCostSummary(
+  sessionId: string,
+): Promise<SessionCostSummary> {
+  return safeInvoke("get_session_cost_summary", { sessionId });
+}
+
+// ========== 响应缓存 API ==========
+
+export interface ResponseCacheStats {
+  enabled: boolean;
+  entries: number;
+  max_entries: number;
+  ttl_secs: number;
+  hits: number;
+  misses: number;
+  /** 命中率（0 - 1） */
+  hit_rate: number;
+  /** 离线降级返回过期缓存的次数 */
+  stale_hits: number;
+}
+
+export async function getResponseCacheStats(): Promise<ResponseCacheStats> {
+  return safeInvoke("get_response_cache_stats");
+}
+
+export async function clearResponseCache(): Promise<number> {
+  return safeInvoke("clear_response_cache");
+}
+
+// ========== 默认 Provider 分流 API ==========
+
+export interface DefaultProviderSplitStat {
+  provider: string;
+  weight: number;
+  /** 按权重应得的比例（0 - 1） */
+  expected_ratio: number;
+  count: number;
+  /** 实际比例（0 - 1） */
+  actual_ratio: number;
+}
+
+export async function getDefaultProviderSplitStats(): Promise<
+  DefaultProviderSplitStat[]
+> {
+  return safeInvoke("get_default_provider_split_stats");
+}
+
+export async function resetDefaultProviderSplitStats(): Promise<void> {
+  return safeInvoke("reset_default_provider_split_stats");
+}
+
+// ========== 请求追踪 API ==========
+
+export type TraceStepKind =
+  | { kind: "alias_resolved"; from: string; to: string }
+  | {
+      kind: "injection_applied";
+      applied_rules: string[];
+      injected_params: string[];
+    }
+  | { kind: "provider_selected"; provider: string; reason: string }
+  | { kind: "cache_hit" }
+  | {
+      kind: "credential_selected";
+      provider: string;
+      credential_id?: string;
+      credential_name?: string;
+      /** 凭证来源（pool / api_key_provider） */
+      source: string;
+    }
+  | { kind: "anthropic_headers"; version: string; beta?: string }
+  | { kind: "retry"; attempt: number; status_code?: number; error: string }
+  | { kind: "failover"; from: string; to: string; reason: string }
+  | { kind: "params_dropped"; provider: string; params: string[] }
+  | {
+      kind: "tool_results_truncated";
+      provider: string;
+      count: number;
+      removed_bytes: number;
+    }
+  | { kind: "upstream_response"; status_code: number }
+  | { kind: "failed"; error: string };
+
+export type TraceStep = TraceStepKind & {
+  /** 距请求开始的耗时（毫秒） */
+  elapsed_ms: number;
+  timestamp: string;
+};
+
+export interface RequestTrace {
+  request_id: string;
+  model: string;
+  started_at: string;
+  steps: TraceStep[];
+}
+
+export async function getRequestTrace(
+  requestId: string,
+): Promise<RequestTrace | null> {
+  return safeInvoke("get_request_trace", { requestId });
+}
+
+export async function listRequestTraces(
+  limit?: number,
+): Promise<RequestTrace[]> {
+  return safeInvoke("list_request_traces", { limit });
+}
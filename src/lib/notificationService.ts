@@ -309,6 +309,22 @@ class NotificationService {
       tag: `threshold-${flowId}`,
     });
   }
+
+  /**
+   * 发送异常告警通知
+   */
+  notifyAnomalyAlert(ruleId: string, message: string): void {
+    if (!this.config.notifyOnThresholdWarning) {
+      return;
+    }
+
+    this.notify({
+      title: "异常告警",
+      body: message,
+      type: "warning",
+      tag: `alert-${ruleId}`,
+    });
+  }
 }
 
 // 导出单例
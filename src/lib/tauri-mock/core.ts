@@ -86,6 +86,27 @@ const defaultMocks: Record<string, any> = {
     console.log("[Mock] Config saved:", config);
     return { success: true };
   },
+  reload_config: () => ({ status: "success" }),
+  reveal_api_key: () => "",
+  get_effective_config: () => ({
+    config: defaultMocks.get_config(),
+    sources: {},
+    config_path: "",
+    server_running: false,
+  }),
+  validate_config_yaml: () => ({ valid: true, diagnostics: [] }),
+  get_config_schema: () => ({ type: "object", properties: {} }),
+  create_config_backup: () => "",
+  list_config_backups: () => [],
+  restore_backup: () => ({ success: true, config: {}, warnings: [] }),
+  compact_database: () => ({
+    pruned: [],
+    size_before: 0,
+    size_after: 0,
+    reclaimed_bytes: 0,
+    vacuumed: true,
+    duration_ms: 0,
+  }),
 
   // Provider 相关
   get_providers: () => [],
@@ -105,6 +126,14 @@ const defaultMocks: Record<string, any> = {
     port: 8787,
     requests: 0,
     uptime_secs: 0,
+    proxy_paused: false,
+  }),
+  set_proxy_paused: (args: any) => args?.paused ?? false,
+  get_proxy_paused: () => false,
+  rotate_api_key: () => ({
+    api_key: "pc_mock_rotated_key",
+    api_key_masked: "pc_moc****_key",
+    previous_key_expires_at: null,
   }),
   check_server_status: () => ({
     running: false,
@@ -155,6 +184,12 @@ const defaultMocks: Record<string, any> = {
   terminal_write: () => ({}),
   terminal_resize: () => ({}),
   terminal_close: () => ({}),
+  terminal_attach_session: () => null,
+  terminal_get_session_scrollback: () => ({
+    session_id: "mock-terminal-uuid",
+    data: "",
+    truncated: false,
+  }),
   read_terminal_output: () => [],
   list_terminal_sessions: () => [],
 
@@ -196,6 +231,31 @@ const defaultMocks: Record<string, any> = {
   update_provider_pool_credential: () => ({ success: true }),
   delete_provider_pool_credential: () => ({ success: true }),
   toggle_provider_pool_credential: () => ({ success: true }),
+  set_provider_pool_credential_tier: () => ({ success: true }),
+  park_credential: () => ({ success: true }),
+  unpark_credential: () => ({ success: true }),
+  bulk_update_credentials: () => [],
+  set_provider_pool_credential_upstream_headers: () => ({ success: true }),
+  set_provider_pool_credential_project_id: () => ({ success: true }),
+  export_pool_template: () => ({
+    content: "{}",
+    suggested_filename: "proxycast_pool_template.json",
+    credential_count: 0,
+  }),
+  import_pool_template: () => ({ credentials: [], routing_applied: false }),
+  fill_provider_pool_credential_secret: () => ({ success: true }),
+  dedupe_pool_credentials: (args: any) => ({
+    dry_run: args?.dryRun ?? true,
+    groups: [],
+    removed_count: 0,
+  }),
+  get_credential_account_info: (args: any) => ({
+    uuid: args?.uuid ?? "",
+    provider_type: "openai",
+    account_id: "sk-moc****mock",
+    source: "api_key",
+    fetched_at: new Date().toISOString(),
+  }),
   reset_provider_pool_credential: () => ({ success: true }),
   reset_provider_pool_health: () => ({ success: true }),
   check_provider_pool_credential_health: () => ({ healthy: false }),
@@ -223,6 +283,8 @@ const defaultMocks: Record<string, any> = {
   migrate_legacy_api_key_credentials: () => ({ success: true }),
   delete_legacy_api_key_credential: () => ({ success: true }),
   get_local_kiro_credential_uuid: () => null,
+  discover_kiro_installations: () => [],
+  import_kiro_from_installation: () => ({ success: true }),
 
   // OAuth 凭证相关
   add_kiro_oauth_credential: () => ({ success: true }),
@@ -244,8 +306,14 @@ const defaultMocks: Record<string, any> = {
   start_kiro_social_auth_login: () => ({ success: true }),
   exchange_kiro_social_auth_token: () => ({ success: true }),
   cancel_kiro_social_auth_login: () => ({ success: true }),
-  start_kiro_social_auth_callback_server: () => ({ success: true }),
+  start_kiro_social_auth_callback_server: () => 19823,
+  check_oauth_callback_port: (args: any) => ({
+    port: args?.port ?? 0,
+    available: true,
+  }),
   refresh_pool_credential_token: () => ({ success: true }),
+  get_token_cache_status: () => [],
+  invalidate_token_cache: () => 0,
   get_pool_credential_oauth_status: () => ({ status: "unknown" }),
   migrate_private_config_to_pool: () => ({ success: true }),
   get_credential_health: () => ({ healthy: false }),
@@ -340,27 +408,66 @@ const defaultMocks: Record<string, any> = {
 
   // Flow Monitor 相关
   subscribe_flow_events: () => ({ success: true }),
+  unfollow_flow_session: () => ({ success: true }),
   query_flows: () => ({ flows: [], total: 0 }),
   query_flows_with_expression: () => ({ flows: [], total: 0 }),
   get_flow_detail: () => ({ flow: null }),
   search_flows: () => ({ flows: [] }),
   get_flow_stats: () => ({ stats: {} }),
+  export_flows_ndjson: () => ({ count: 0, bytes_written: 0 }),
   update_flow_annotations: () => ({ success: true }),
   cleanup_flows: () => ({ deleted_count: 0 }),
+  persist_flows_to_store: () => ({ persisted_count: 0 }),
+  prune_memory_flows: () => ({ pruned_count: 0 }),
+  prune_flow_files: () => ({
+    dry_run: true,
+    files_scanned: 0,
+    pruned_files: [],
+    files_protected: 0,
+    bytes_reclaimed: 0,
+  }),
   get_recent_flows: () => [],
   toggle_flow_starred: () => ({ success: true }),
   get_all_flow_tags: () => [],
   delete_flow: () => ({ success: true }),
   delete_flows: () => ({ success: true }),
   get_flow_monitor_debug_info: () => ({ info: {} }),
+  get_dead_letters: () => [],
+  delete_dead_letter: () => true,
+  clear_dead_letters: () => 0,
+  replay_dead_letter: (args: any) => ({
+    original_flow_id: args?.id ?? "",
+    replay_flow_id: "",
+    success: false,
+    error: "Mock 环境不支持重放",
+    started_at: new Date().toISOString(),
+    completed_at: new Date().toISOString(),
+    duration_ms: 0,
+  }),
+  fuzz_replay_flow: (args: any) =>
+    (args?.mutations ?? []).map((mutation: any) => ({
+      mutation,
+      error: "Mock 环境不支持模糊重放",
+      request_bytes: 0,
+      duration_ms: 0,
+    })),
   create_test_flows: () => ({ created_count: 0 }),
   get_enhanced_stats: () => ({ stats: {} }),
   get_request_trend: () => ({ trend: [] }),
   get_token_distribution: () => ({ distribution: {} }),
   get_latency_histogram: () => ({ histogram: [] }),
+  get_latency_percentiles: (args: any) => ({
+    bucket: args?.request?.bucket ?? "hour",
+    points: [],
+  }),
   export_stats_report: () => ({ report: "" }),
   get_threshold_config: () => ({ config: {} }),
   update_threshold_config: () => ({ success: true }),
+  get_flow_capture_filter: () => ({
+    exclusions: [],
+    count_excluded_in_rate: false,
+  }),
+  update_flow_capture_filter: () => undefined,
   get_request_rate: () => ({ rate: 0 }),
   set_rate_window: () => ({ success: true }),
 
@@ -375,6 +482,7 @@ const defaultMocks: Record<string, any> = {
   unarchive_session: () => ({ success: true }),
   archive_session: () => ({ success: true }),
   delete_session: () => ({ success: true }),
+  merge_sessions: () => 0,
 
   // Bookmark 相关
   remove_bookmark: () => ({ success: true }),
@@ -386,6 +494,14 @@ const defaultMocks: Record<string, any> = {
 
   // Quick Filter 相关
   delete_quick_filter: () => ({ success: true }),
+  list_filter_variables: () => [],
+  save_filter_variable: (args: any) => ({
+    name: String(args?.request?.name ?? "").replace(/^\$/, ""),
+    expression: args?.request?.expression ?? "",
+    description: args?.request?.description,
+    updated_at: new Date().toISOString(),
+  }),
+  delete_filter_variable: () => undefined,
 
   // Telemetry 相关
   get_request_logs: () => ({ logs: [] }),
@@ -394,14 +510,76 @@ const defaultMocks: Record<string, any> = {
   get_stats_summary: () => ({ summary: {} }),
   get_stats_by_provider: () => ({ stats: [] }),
   get_stats_by_model: () => ({ stats: [] }),
+  get_stats_by_user: () => ({}),
   get_token_summary: () => ({ summary: {} }),
   get_token_stats_by_provider: () => ({ stats: [] }),
   get_token_stats_by_model: () => ({ stats: [] }),
   get_token_stats_by_day: () => ({ stats: [] }),
+  get_session_cost_summary: (args: any) => ({
+    session_id: args?.sessionId ?? "",
+    flow_count: 0,
+    unmatched_flow_count: 0,
+    total: {
+      total_input_tokens: 0,
+      total_output_tokens: 0,
+      total_tokens: 0,
+      total_cache_creation_input_tokens: 0,
+      total_cache_read_input_tokens: 0,
+      record_count: 0,
+      actual_count: 0,
+      estimated_count: 0,
+      avg_input_tokens: 0,
+      avg_output_tokens: 0,
+    },
+    by_model: [],
+  }),
+  get_response_cache_stats: () => ({
+    enabled: false,
+    entries: 0,
+    max_entries: 256,
+    ttl_secs: 300,
+    hits: 0,
+    misses: 0,
+    hit_rate: 0,
+    stale_hits: 0,
+  }),
+  clear_response_cache: () => 0,
+  get_default_provider_split_stats: () => [],
+  reset_default_provider_split_stats: () => undefined,
+  get_request_trace: () => null,
+  list_request_traces: () => [],
 
   // Routes 相关
   get_available_routes: () => ({ routes: [] }),
   get_route_curl_examples: () => ({ examples: [] }),
+  explain_model_routing: (args: any) => ({
+    requested: args?.model ?? "",
+    normalized: null,
+    matched_alias: null,
+    resolved: args?.model ?? "",
+    normalization_enabled: true,
+    default_provider: "kiro",
+  }),
+  simulate_routing: (args: any) =>
+    (args?.models ?? []).map((model: string) => ({
+      requested: model,
+      normalized: null,
+      matched_alias: null,
+      resolved: model,
+      provider: "kiro",
+      matched_tier_rule: null,
+      healthy_credentials: 0,
+      has_healthy_credential: false,
+    })),
+  lint_routing_rules: () => [],
+  add_routing_rule: (args: any) => ({
+    rules: args?.rule ? [args.rule] : [],
+    warnings: [],
+  }),
+  update_routing_rule: (args: any) => ({
+    rules: args?.rule ? [args.rule] : [],
+    warnings: [],
+  }),
 
   // Prompts 相关
   get_prompts: () => [],
@@ -435,6 +613,7 @@ const defaultMocks: Record<string, any> = {
   // Notification 相关
   show_notification: () => ({}),
   show_url_intercept_notification: () => ({}),
+  get_recent_intercept_notifications: () => [],
   show_status_notification: () => ({}),
 
   // Window 相关
@@ -457,8 +636,8 @@ const defaultMocks: Record<string, any> = {
   update_retry_config: () => ({ success: true }),
   get_failover_config: () => ({ config: {} }),
   update_failover_config: () => ({ success: true }),
-  get_switch_log: () => ({ logs: [] }),
-  clear_switch_log: () => ({ success: true }),
+  get_switch_log: () => ({ entries: [], total: 0 }),
+  clear_switch_log: () => 0,
 
   // Machine ID 相关
   get_current_machine_id: () => ({ machine_id: "" }),
@@ -466,6 +645,14 @@ const defaultMocks: Record<string, any> = {
   generate_random_machine_id: () => ({ machine_id: "" }),
   validate_machine_id: () => ({ valid: true }),
   check_admin_privileges: () => ({ is_admin: false }),
+  audit_machine_id: () => ({
+    platform: "linux",
+    locations: [],
+    consistent: true,
+    distinct_values: [],
+  }),
+  repair_machine_id: () => ({ success: true, updated_locations: [] }),
+  undo_machine_id_repair: () => ({ success: true, updated_locations: [] }),
   get_os_type: () => ({ os_type: "linux" }),
   backup_machine_id_to_file: () => ({ success: true }),
   restore_machine_id_from_file: () => ({ success: true }),
@@ -531,9 +718,31 @@ const defaultMocks: Record<string, any> = {
   // Log 相关
   get_logs: () => [],
   clear_logs: () => ({}),
+  subscribe_logs: () => ({}),
+  unsubscribe_logs: () => ({}),
 
   // Test 相关
   test_api: () => ({ success: true, status: 200, body: "", time_ms: 0 }),
+  run_self_test: () => ({
+    passed: true,
+    model: "claude-sonnet-4-5",
+    checked_at: new Date().toISOString(),
+    checks: [],
+  }),
+  benchmark_providers: () => ({
+    prompt: "",
+    iterations: 0,
+    checked_at: new Date().toISOString(),
+    results: [],
+  }),
+  get_recommended_presets: () => [],
+  apply_recommended_preset: (args: any) => ({
+    preset_id: args?.presetId ?? "",
+    applied: false,
+    forced: false,
+    checked_at: new Date().toISOString(),
+    canaries: [],
+  }),
 
   // Kiro Credentials 相关
   get_kiro_credentials: () => ({ loaded: false }),
@@ -599,6 +808,14 @@ const defaultMocks: Record<string, any> = {
   // Endpoint Providers 相关
   get_endpoint_providers: () => ({}),
   set_endpoint_provider: () => ({ provider: "" }),
+  set_endpoint_force_non_streaming: () => false,
+
+  // 价格表
+  get_price_table: () => ({ table: {}, missing_models: [] }),
+  update_price_table: (args: any) => ({
+    table: args?.table || {},
+    missing_models: [],
+  }),
 
   // Experimental Features 相关
   get_experimental_config: () => ({
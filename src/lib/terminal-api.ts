@@ -13,6 +13,7 @@
  * - 发送输入到终端
  * - 调整终端大小
  * - 监听终端输出和状态事件
+ * - 重新连接已有会话时回放输出历史
  *
  * ## 使用示例
  * ```typescript
@@ -77,12 +78,23 @@ export interface TerminalStatusEvent {
   error?: string;
 }
 
+/** 会话输出历史（回滚缓冲区） */
+export interface TerminalScrollback {
+  /** 会话 ID */
+  session_id: string;
+  /** 输出数据（Base64 编码） */
+  data: string;
+  /** 是否有更早的输出被丢弃 */
+  truncated: boolean;
+}
+
 // ============================================================================
 // 事件名称
 // ============================================================================
 
 export const TERMINAL_OUTPUT_EVENT = "terminal:output";
 export const TERMINAL_STATUS_EVENT = "terminal:status";
+export const TERMINAL_SCROLLBACK_EVENT = "terminal:scrollback";
 
 // ============================================================================
 // API 函数
@@ -194,6 +206,42 @@ export async function getTerminalSession(
   });
 }
 
+/**
+ * 重新连接到已有终端会话
+ *
+ * 后端通过 `terminal:scrollback` 事件推送最近的输出历史，调用前应先用
+ * onSessionScrollback 注册监听。
+ *
+ * @param sessionId - 会话 ID
+ * @param maxBytes - 最多回放的字节数（可选，默认回放整个回滚缓冲区）
+ * @returns 会话元数据
+ */
+export async function attachTerminalSession(
+  sessionId: string,
+  maxBytes?: number,
+): Promise<SessionMetadata> {
+  return safeInvoke<SessionMetadata>("terminal_attach_session", {
+    sessionId,
+    maxBytes,
+  });
+}
+
+/**
+ * 获取终端会话最近的输出历史
+ *
+ * @param sessionId - 会话 ID
+ * @param maxBytes - 最多返回的字节数（可选，默认返回整个回滚缓冲区）
+ */
+export async function getTerminalScrollback(
+  sessionId: string,
+  maxBytes?: number,
+): Promise<TerminalScrollback> {
+  return safeInvoke<TerminalScrollback>("terminal_get_session_scrollback", {
+    sessionId,
+    maxBytes,
+  });
+}
+
 // ============================================================================
 // 事件监听
 // ============================================================================
@@ -250,6 +298,29 @@ export async function onSessionOutput(
   });
 }
 
+/**
+ * 监听特定会话的输出历史回放事件
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收解码后的输出历史和是否有更早的输出被丢弃
+ * @returns 取消监听函数
+ */
+export async function onSessionScrollback(
+  sessionId: string,
+  callback: (data: Uint8Array, truncated: boolean) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalScrollback>(TERMINAL_SCROLLBACK_EVENT, (event) => {
+    if (event.payload.session_id === sessionId) {
+      const binaryString = atob(event.payload.data);
+      const bytes = new Uint8Array(binaryString.length);
+      for (let i = 0; i < binaryString.length; i++) {
+        bytes[i] = binaryString.charCodeAt(i);
+      }
+      callback(bytes, event.payload.truncated);
+    }
+  });
+}
+
 /**
  * 监听特定会话的状态事件
  *